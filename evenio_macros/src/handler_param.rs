@@ -120,6 +120,9 @@ pub(crate) fn derive_handler_param(input: TokenStream) -> Result<TokenStream> {
 
             type This<'__a> = #this;
 
+            const REFRESHES_ARCHETYPES: bool =
+                <#tuple_ty as ::evenio::handler::HandlerParam>::REFRESHES_ARCHETYPES;
+
             fn init(
                 world: &mut ::evenio::world::World,
                 config: &mut ::evenio::handler::HandlerConfig,
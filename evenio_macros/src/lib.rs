@@ -7,6 +7,7 @@ mod component;
 mod event;
 mod handler_param;
 mod query;
+mod sender;
 mod util;
 
 /// Helper macro which repeatedly invokes a given macro with an increasing list
@@ -86,3 +87,12 @@ pub fn derive_handler_param(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|e| e.into_compile_error())
         .into()
 }
+
+/// Attribute macro declaring a `Sender` with a named method per event. See the
+/// documentation of `evenio::event::sender` for more information.
+#[proc_macro_attribute]
+pub fn sender(attr: TokenStream, item: TokenStream) -> TokenStream {
+    sender::sender(attr.into(), item.into())
+        .unwrap_or_else(|e| e.into_compile_error())
+        .into()
+}
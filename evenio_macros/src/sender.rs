@@ -0,0 +1,106 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse2, parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Lifetime, Result};
+
+use crate::handler_param::derive_handler_param;
+use crate::util::make_tuple;
+
+pub(crate) fn sender(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(Error::new_spanned(attr, "`sender` takes no arguments"));
+    }
+
+    let mut input = parse2::<DeriveInput>(item)?;
+
+    let Data::Struct(struct_) = &mut input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`sender` requires a struct with named fields",
+        ));
+    };
+
+    let Fields::Named(fields) = &mut struct_.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`sender` requires a struct with named fields",
+        ));
+    };
+
+    let lifetime = match input.generics.lifetimes().next() {
+        Some(param) => param.lifetime.clone(),
+        None => {
+            let lifetime = Lifetime::new("'a", Span::call_site());
+
+            input
+                .generics
+                .params
+                .insert(0, GenericParam::Lifetime(parse_quote!(#lifetime)));
+
+            lifetime
+        }
+    };
+
+    let mut methods = vec![];
+
+    for field in &mut fields.named {
+        let mut targeted = false;
+
+        field.attrs.retain(|attr| {
+            let is_targeted = attr.path().is_ident("targeted");
+            targeted |= is_targeted;
+            !is_targeted
+        });
+
+        let attrs = &field.attrs;
+        let vis = &field.vis;
+        let ident = &field.ident;
+        let ty = &field.ty;
+
+        methods.push(if targeted {
+            quote! {
+                #(#attrs)*
+                #[track_caller]
+                #vis fn #ident(&self, target: ::evenio::entity::EntityId, event: #ty) {
+                    self.0.send_to(target, event)
+                }
+            }
+        } else {
+            quote! {
+                #(#attrs)*
+                #[track_caller]
+                #vis fn #ident(&self, event: #ty) {
+                    self.0.send(event)
+                }
+            }
+        });
+    }
+
+    let tuple_ty = make_tuple(fields.named.iter().map(|f| &f.ty));
+
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let name = &input.ident;
+    let generics = &input.generics;
+    let where_clause = &generics.where_clause;
+
+    let item = quote! {
+        #(#attrs)*
+        #vis struct #name #generics (
+            ::evenio::event::Sender<#lifetime, #tuple_ty>
+        ) #where_clause;
+    };
+
+    let handler_param = derive_handler_param(item.clone())?;
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #item
+
+        #handler_param
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#methods)*
+        }
+    })
+}
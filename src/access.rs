@@ -3,6 +3,7 @@
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
 use core::cmp::Ordering;
+use core::fmt;
 
 use ahash::RandomState;
 
@@ -103,8 +104,11 @@ pub struct ComponentAccess {
 /// [`ComponentIdx`].
 type Case = Vec<(ComponentIdx, CaseAccess)>;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-enum CaseAccess {
+/// How a single component is involved in one case of a [`ComponentAccess`].
+///
+/// See [`ComponentAccess::cases`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CaseAccess {
     /// Archetype must have the component, but the component is not read or
     /// written.
     With,
@@ -261,6 +265,105 @@ impl ComponentAccess {
         }
     }
 
+    /// Returns an iterator over the cases of this access expression.
+    ///
+    /// An archetype is matched if it satisfies the requirements of any case
+    /// (the cases are combined with a logical OR). Each case is a list of components and
+    /// how they are involved in that case, sorted in ascending order by
+    /// [`ComponentIdx`]. Components not present in a case are unconstrained.
+    ///
+    /// An expression without any cases matches no archetypes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::access::{Access, CaseAccess, ComponentAccess};
+    /// use evenio::component::ComponentIdx;
+    ///
+    /// let a = ComponentIdx(0);
+    /// let b = ComponentIdx(1);
+    ///
+    /// // Like the query `(&mut A, Not<&B>)`.
+    /// let ca = ComponentAccess::var(a, Access::ReadWrite)
+    ///     .and(&ComponentAccess::var(b, Access::None).not());
+    ///
+    /// let cases: Vec<_> = ca.cases().collect();
+    /// assert_eq!(cases, [[(a, CaseAccess::ReadWrite), (b, CaseAccess::Not)]]);
+    /// ```
+    pub fn cases(
+        &self,
+    ) -> impl Iterator<Item = &[(ComponentIdx, CaseAccess)]> + Clone + fmt::Debug + '_ {
+        self.cases.iter().map(Vec::as_slice)
+    }
+
+    /// Returns the strongest access to the component at `idx` in any case.
+    ///
+    /// Components which are only used as filters (e.g. by [`With`] or
+    /// [`Not`]) have an access of [`Access::None`]. Conflicting access is
+    /// reported as [`Access::ReadWrite`].
+    ///
+    /// [`With`]: crate::query::With
+    /// [`Not`]: crate::query::Not
+    pub fn access(&self, idx: ComponentIdx) -> Access {
+        self.cases
+            .iter()
+            .filter_map(|case| {
+                case.binary_search_by_key(&idx, |&(idx, _)| idx)
+                    .ok()
+                    .map(|i| case_access_to_access(case[i].1))
+            })
+            .max()
+            .unwrap_or(Access::None)
+    }
+
+    /// Returns every component which is read or written in any case along
+    /// with the strongest access to it, sorted in ascending order by
+    /// [`ComponentIdx`].
+    ///
+    /// Components which are only used as filters are not included. See
+    /// [`cases`](Self::cases) for the full expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::access::{Access, ComponentAccess};
+    /// use evenio::component::ComponentIdx;
+    ///
+    /// let a = ComponentIdx(0);
+    /// let b = ComponentIdx(1);
+    /// let c = ComponentIdx(2);
+    ///
+    /// // Like the query `(Or<&A, &mut B>, With<&C>)`.
+    /// let ca = ComponentAccess::var(a, Access::Read)
+    ///     .or(&ComponentAccess::var(b, Access::ReadWrite))
+    ///     .and(&ComponentAccess::var(c, Access::None));
+    ///
+    /// assert_eq!(
+    ///     ca.accessed_components(),
+    ///     [(a, Access::Read), (b, Access::ReadWrite)]
+    /// );
+    /// ```
+    pub fn accessed_components(&self) -> Vec<(ComponentIdx, Access)> {
+        let mut res: Vec<(ComponentIdx, Access)> = vec![];
+
+        for case in &self.cases {
+            for &(idx, access) in case {
+                let access = case_access_to_access(access);
+
+                if access == Access::None {
+                    continue;
+                }
+
+                match res.binary_search_by_key(&idx, |&(idx, _)| idx) {
+                    Ok(i) => res[i].1 = res[i].1.max(access),
+                    Err(i) => res.insert(i, (idx, access)),
+                }
+            }
+        }
+
+        res
+    }
+
     /// Returns the set of all conflicting components.
     pub(crate) fn collect_conflicts(&self) -> IndexSet<ComponentIdx> {
         let mut res = IndexSet::with_hasher(RandomState::new());
@@ -295,6 +398,14 @@ impl ComponentAccess {
     }
 }
 
+fn case_access_to_access(access: CaseAccess) -> Access {
+    match access {
+        CaseAccess::With | CaseAccess::Not => Access::None,
+        CaseAccess::Read => Access::Read,
+        CaseAccess::ReadWrite | CaseAccess::Conflict => Access::ReadWrite,
+    }
+}
+
 impl Default for ComponentAccess {
     /// Equivalent to [`Self::new_false`].
     fn default() -> Self {
@@ -351,4 +462,35 @@ mod tests {
         let xor = left.and(&right.not()).or(&right.and(&left.not()));
         check(xor.and(&b(ReadWrite)), [B]);
     }
+
+    #[test]
+    fn access_report() {
+        use Access::*;
+
+        // (Xor<(&A, &B), (&B, &C)>, &mut B)
+        let left = a(Read).and(&b(Read));
+        let right = b(Read).and(&c(Read));
+        let xor = left.and(&right.not()).or(&right.and(&left.not()));
+        let ca = xor.and(&b(ReadWrite));
+
+        assert_eq!(ca.access(A), Read);
+        assert_eq!(ca.access(B), ReadWrite);
+        assert_eq!(ca.access(C), Read);
+        assert_eq!(ca.access(ComponentIdx(3)), None);
+        assert_eq!(
+            ca.accessed_components(),
+            [(A, Read), (B, ReadWrite), (C, Read)]
+        );
+
+        let filter = a(None).and(&b(None).not());
+        assert_eq!(filter.access(A), None);
+        assert_eq!(filter.accessed_components(), []);
+        assert_eq!(
+            filter.cases().collect::<Vec<_>>(),
+            [[(A, CaseAccess::With), (B, CaseAccess::Not)]]
+        );
+
+        assert_eq!(Ca::new_false().cases().count(), 0);
+        assert_eq!(Ca::new_true().cases().collect::<Vec<_>>(), [&[]]);
+    }
 }
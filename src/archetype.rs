@@ -45,10 +45,16 @@ use crate::world::UnsafeWorldCell;
 /// world.add_handler(|_: Receiver<E>, archetypes: &Archetypes| {});
 /// ```
 #[derive(Debug)]
+#[allow(clippy::struct_field_names)]
 pub struct Archetypes {
     /// Always contains the empty archetype at index 0.
     archetypes: Slab<Archetype>,
     by_components: HashMap<AliasedBox<[ComponentIdx]>, ArchetypeIdx>,
+    /// Incremented every time an archetype is added or removed.
+    generation: u64,
+    /// Incremented every time the refresh listeners of an archetype are
+    /// notified. See [`Archetype::changed_at`].
+    changes: u64,
 }
 
 impl Archetypes {
@@ -61,6 +67,8 @@ impl Archetypes {
         Self {
             archetypes: Slab::from_iter([(0, Archetype::empty())]),
             by_components: map,
+            generation: 0,
+            changes: 0,
         }
     }
 
@@ -73,12 +81,6 @@ impl Archetypes {
         unsafe { self.archetypes.get(0).unwrap_unchecked() }
     }
 
-    /// Returns a mutable reference to the empty archetype.
-    pub(crate) fn empty_mut(&mut self) -> &mut Archetype {
-        // SAFETY: The empty archetype is always at index 0.
-        unsafe { self.archetypes.get_mut(0).unwrap_unchecked() }
-    }
-
     /// Gets a reference to the archetype identified by the given
     /// [`ArchetypeIdx`]. Returns `None` if the index is invalid.
     pub fn get(&self, idx: ArchetypeIdx) -> Option<&Archetype> {
@@ -98,7 +100,8 @@ impl Archetypes {
     /// Spawns a new entity into the empty archetype with the given ID and
     /// returns its location.
     pub(crate) fn spawn(&mut self, id: EntityId) -> EntityLocation {
-        let empty = self.empty_mut();
+        // SAFETY: The empty archetype is always at index 0.
+        let empty = unsafe { self.archetypes.get_mut(0).unwrap_unchecked() };
 
         // Reserve space for the spawned entity.
         let reallocated = unsafe { empty.reserve_one() };
@@ -112,6 +115,9 @@ impl Archetypes {
         // If the archetype was empty or has been reallocated, notify the
         // listening handlers of this change.
         if empty.entity_count() == 1 || reallocated {
+            self.changes += 1;
+            empty.changed_at = self.changes;
+
             for mut ptr in empty.refresh_listeners.iter().copied() {
                 unsafe { ptr.as_info_mut().handler_mut().refresh_archetype(empty) };
             }
@@ -134,24 +140,56 @@ impl Archetypes {
         self.archetypes.len()
     }
 
+    /// Returns a counter which changes every time an archetype is added or
+    /// removed.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns a counter which changes every time an archetype gains its first
+    /// entity, loses its last entity, or has its columns reallocated.
+    pub(crate) fn changes(&self) -> u64 {
+        self.changes
+    }
+
     /// Registers an event handler for all archetypes.
     pub(crate) fn register_handler(&mut self, info: &mut HandlerInfo) {
+        if !info.refreshes_archetypes() {
+            return;
+        }
+
         // TODO: use a `Component -> Vec<Archetype>` index to make this faster?
         for (_, arch) in &mut self.archetypes {
             arch.register_handler(info);
         }
     }
 
+    /// Reorders the targeted handler lists of all archetypes by the handlers'
+    /// tiebreaks.
+    pub(crate) fn sort_handler_lists(&mut self) {
+        for (_, arch) in &mut self.archetypes {
+            for list in arch.event_listeners.values_mut() {
+                list.sort();
+            }
+        }
+    }
+
     /// Removes an event handler from all archetypes.
     pub(crate) fn remove_handler(&mut self, info: &HandlerInfo) {
+        if !info.refreshes_archetypes() {
+            return;
+        }
+
         // TODO: use a `Component -> Vec<Archetype>` index to make this faster?
         for (_, arch) in &mut self.archetypes {
             // TODO: remove_handler method for Archetype for this?
             arch.refresh_listeners.remove(&info.ptr());
 
-            if let EventId::Targeted(id) = info.received_event() {
-                if let Some(list) = arch.event_listeners.get_mut(id.index()) {
-                    list.remove(info.ptr());
+            for &event in info.received_events() {
+                if let EventId::Targeted(id) = event {
+                    if let Some(list) = arch.event_listeners.get_mut(id.index()) {
+                        list.remove(info.ptr());
+                    }
                 }
             }
         }
@@ -174,6 +212,7 @@ impl Archetypes {
         for arch_idx in info.member_of.drain(..) {
             // Remove the archetype.
             let mut arch = self.archetypes.remove(arch_idx.0 as usize);
+            self.generation += 1;
 
             // Notify all handlers listening for updates affecting the archetype
             // that it was removed.
@@ -320,7 +359,10 @@ impl Archetypes {
                             .insert(component_idx, src_arch_idx);
 
                         // Register all event handlers for the new archetype.
-                        for info in handlers.iter_mut() {
+                        for info in handlers
+                            .iter_mut()
+                            .filter(|info| info.refreshes_archetypes())
+                        {
                             new_arch.register_handler(info);
                         }
 
@@ -329,13 +371,29 @@ impl Archetypes {
                         vacant_by_components.insert(arch_id);
                         vacant_insert_components.insert(arch_id);
                         self.archetypes.insert(new_arch);
+                        self.generation += 1;
 
                         arch_id
                     }
                     Entry::Occupied(entry) => {
                         // The archetype already exists, insert it into the
                         // map and return it.
-                        *vacant_insert_components.insert(*entry.get())
+                        let dst_arch_idx = *vacant_insert_components.insert(*entry.get());
+
+                        // Insert a backlink to the source archetype, so that
+                        // the edge is removed from both ends when either
+                        // archetype is removed.
+                        // SAFETY: Archetype indices in `by_components` are
+                        // valid.
+                        unsafe {
+                            self.archetypes
+                                .get_mut(dst_arch_idx.0 as usize)
+                                .unwrap_unchecked()
+                        }
+                        .remove_components
+                        .insert(component_idx, src_arch_idx);
+
+                        dst_arch_idx
                     }
                 }
             }
@@ -439,7 +497,10 @@ impl Archetypes {
                             .insert(component_idx, src_arch_idx);
 
                         // Register all event handlers for the new archetype.
-                        for info in handlers.iter_mut() {
+                        for info in handlers
+                            .iter_mut()
+                            .filter(|info| info.refreshes_archetypes())
+                        {
                             new_arch.register_handler(info);
                         }
 
@@ -448,13 +509,29 @@ impl Archetypes {
                         vacant_by_components.insert(arch_id);
                         vacant_remove_components.insert(arch_id);
                         self.archetypes.insert(new_arch);
+                        self.generation += 1;
 
                         arch_id
                     }
                     Entry::Occupied(entry) => {
                         // The archetype already exists, insert it into the
                         // map and return it.
-                        *vacant_remove_components.insert(*entry.get())
+                        let dst_arch_idx = *vacant_remove_components.insert(*entry.get());
+
+                        // Insert a backlink to the source archetype, so that
+                        // the edge is removed from both ends when either
+                        // archetype is removed.
+                        // SAFETY: Archetype indices in `by_components` are
+                        // valid.
+                        unsafe {
+                            self.archetypes
+                                .get_mut(dst_arch_idx.0 as usize)
+                                .unwrap_unchecked()
+                        }
+                        .insert_components
+                        .insert(component_idx, src_arch_idx);
+
+                        dst_arch_idx
                     }
                 }
             }
@@ -465,18 +542,106 @@ impl Archetypes {
         }
     }
 
+    /// Inserts several components on an entity, moving the entity to its new
+    /// archetype at most once. Components the entity already has are
+    /// replaced. Returns the entity's new location.
+    ///
+    /// # Safety
+    ///
+    /// - `src` must be a valid entity location.
+    /// - The component indices must be valid and distinct.
+    /// - The component pointers must point to components of the corresponding
+    ///   types. Ownership of the components is transferred.
+    pub(crate) unsafe fn insert_components(
+        &mut self,
+        src: EntityLocation,
+        new_components: Vec<(ComponentIdx, *const u8)>,
+        components: &mut Components,
+        handlers: &mut Handlers,
+        entities: &mut Entities,
+    ) -> EntityLocation {
+        let mut dst = src.archetype;
+
+        for &(component_idx, _) in &new_components {
+            dst = self.traverse_insert(dst, component_idx, components, handlers);
+        }
+
+        self.assign_and_move_entity(src, dst, new_components, entities)
+    }
+
+    /// Moves an entity to the archetype `dst`, inserting `new_components`.
+    /// Components the entity already has are replaced. Returns the entity's
+    /// new location.
+    ///
+    /// # Safety
+    ///
+    /// - `src` must be a valid entity location.
+    /// - `dst` must have a column for every component in `new_components`.
+    /// - The component indices must be valid and distinct.
+    /// - The component pointers must point to components of the corresponding
+    ///   types. Ownership of the components is transferred.
+    pub(crate) unsafe fn assign_and_move_entity(
+        &mut self,
+        src: EntityLocation,
+        dst: ArchetypeIdx,
+        mut new_components: Vec<(ComponentIdx, *const u8)>,
+        entities: &mut Entities,
+    ) -> EntityLocation {
+        if dst != src.archetype {
+            let src_arch = self
+                .archetypes
+                .get_mut(src.archetype.0 as usize)
+                .unwrap_unchecked();
+
+            // Replace the components the entity already has before moving it.
+            // `move_entity` expects only the missing components, sorted by
+            // index.
+            new_components.retain(|&(component_idx, component_ptr)| {
+                match src_arch.column_of_mut(component_idx) {
+                    Some(col) => {
+                        col.assign(src.row.0 as usize, component_ptr);
+                        false
+                    }
+                    None => true,
+                }
+            });
+
+            new_components.sort_unstable_by_key(|&(component_idx, _)| component_idx);
+        }
+
+        let row = self.move_entity(src, dst, new_components, entities);
+
+        EntityLocation {
+            archetype: dst,
+            row,
+        }
+    }
+
     /// Move an entity from one archetype to another. Returns the entity's row
     /// in the new archetype.
+    ///
+    /// Components in `new_components` must be sorted by component index.
     // TODO: Document safety requirements
-    // NOTE: As of now, this function is never called with more than one new
-    // component. Still, its implementation seems to already support bulk
-    // insertion / removal of components.
     pub(crate) unsafe fn move_entity(
         &mut self,
         src: EntityLocation,
         dst: ArchetypeIdx,
         new_components: impl IntoIterator<Item = (ComponentIdx, *const u8)>,
         entities: &mut Entities,
+    ) -> ArchetypeRow {
+        self.move_entity_taking(src, dst, new_components, None, entities)
+    }
+
+    /// Like [`move_entity`](Self::move_entity), but if `taken` is `Some`, the
+    /// removed component at the given index is moved to the given pointer
+    /// instead of being dropped.
+    pub(crate) unsafe fn move_entity_taking(
+        &mut self,
+        src: EntityLocation,
+        dst: ArchetypeIdx,
+        new_components: impl IntoIterator<Item = (ComponentIdx, *const u8)>,
+        taken: Option<(ComponentIdx, NonNull<u8>)>,
+        entities: &mut Entities,
     ) -> ArchetypeRow {
         let mut new_components = new_components.into_iter();
 
@@ -624,7 +789,12 @@ impl Archetypes {
 
                             // Remove the old component from the source column.
                             let src_col = &mut *src_arch.columns.as_ptr().add(src_idx);
-                            src_col.swap_remove(src_arch.entity_ids.len(), src.row.0 as usize);
+                            src_col.swap_remove_or_take(
+                                src_arch.entity_ids.len(),
+                                src.row.0 as usize,
+                                src_comp_idx,
+                                taken,
+                            );
 
                             // Increment the source column index only, as we
                             // haven't handled the destination column at the
@@ -700,7 +870,13 @@ impl Archetypes {
 
                     // Remove the old component from the source column.
                     let src_col = &mut *src_arch.columns.as_ptr().add(src_idx);
-                    src_col.swap_remove(src_arch.entity_ids.len(), src.row.0 as usize);
+                    let src_comp_idx = *src_arch.component_indices.as_ref().get_unchecked(src_idx);
+                    src_col.swap_remove_or_take(
+                        src_arch.entity_ids.len(),
+                        src.row.0 as usize,
+                        src_comp_idx,
+                        taken,
+                    );
 
                     // Increment the source column index only, as the
                     // destination column index is already out of bounds.
@@ -765,6 +941,9 @@ impl Archetypes {
         // handlers listening for updates affecting the archetype of this
         // change.
         if src_arch.entity_ids.is_empty() {
+            self.changes += 1;
+            src_arch.changed_at = self.changes;
+
             for mut ptr in src_arch.refresh_listeners.iter().copied() {
                 unsafe { ptr.as_info_mut().handler_mut().remove_archetype(src_arch) };
             }
@@ -773,6 +952,9 @@ impl Archetypes {
         // If the destination archetype was empty before or has been
         // reallocated, notify the listening handlers of this change.
         if dst_arch_reallocated || dst_arch.entity_count() == 1 {
+            self.changes += 1;
+            dst_arch.changed_at = self.changes;
+
             for mut ptr in dst_arch.refresh_listeners.iter().copied() {
                 unsafe { ptr.as_info_mut().handler_mut().refresh_archetype(dst_arch) };
             }
@@ -828,6 +1010,9 @@ impl Archetypes {
         // all handlers listening for updates affecting the archetype of this
         // change.
         if arch.entity_count() == 0 {
+            self.changes += 1;
+            arch.changed_at = self.changes;
+
             for mut ptr in arch.refresh_listeners.iter().copied() {
                 unsafe { ptr.as_info_mut().handler_mut().remove_archetype(arch) };
             }
@@ -853,6 +1038,8 @@ unsafe impl HandlerParam for &'_ Archetypes {
 
     type This<'a> = &'a Archetypes;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -938,6 +1125,9 @@ pub struct Archetype {
     refresh_listeners: BTreeSet<HandlerInfoPtr>,
     /// Targeted event listeners for this archetype.
     event_listeners: SparseMap<TargetedEventIdx, HandlerList>,
+    /// The value of [`Archetypes::changes`] when the refresh listeners of
+    /// this archetype were last notified.
+    changed_at: u64,
 }
 
 impl Archetype {
@@ -952,6 +1142,7 @@ impl Archetype {
             remove_components: BTreeMap::new(),
             refresh_listeners: BTreeSet::new(),
             event_listeners: SparseMap::new(),
+            changed_at: 0,
         }
     }
 
@@ -1000,6 +1191,7 @@ impl Archetype {
             remove_components: BTreeMap::new(),
             refresh_listeners: BTreeSet::new(),
             event_listeners: SparseMap::new(),
+            changed_at: 0,
         }
     }
 
@@ -1021,21 +1213,24 @@ impl Archetype {
         }
 
         // If the handler is targeted, and its component access matches this
-        // archetype, insert it into our handler list for the event index.
-        if let (Some(expr), EventId::Targeted(event_id)) = (
-            info.targeted_event_component_access(),
-            info.received_event(),
-        ) {
+        // archetype, insert it into our handler list for each event index.
+        if let Some(expr) = info.targeted_event_component_access() {
             if expr.matches_archetype(|idx| self.column_of(idx).is_some()) {
-                // Insert the handler into the existing list if present, or
-                // make a new list and insert the handler into that.
-                if let Some(list) = self.event_listeners.get_mut(event_id.index()) {
-                    list.insert(info.ptr(), info.priority());
-                } else {
-                    let mut list = HandlerList::new();
-                    list.insert(info.ptr(), info.priority());
-
-                    self.event_listeners.insert(event_id.index(), list);
+                for &event in info.received_events() {
+                    let EventId::Targeted(event_id) = event else {
+                        continue;
+                    };
+
+                    // Insert the handler into the existing list if present, or
+                    // make a new list and insert the handler into that.
+                    if let Some(list) = self.event_listeners.get_mut(event_id.index()) {
+                        list.insert(info.ptr());
+                    } else {
+                        let mut list = HandlerList::new();
+                        list.insert(info.ptr());
+
+                        self.event_listeners.insert(event_id.index(), list);
+                    }
                 }
             }
         }
@@ -1052,6 +1247,13 @@ impl Archetype {
         self.index
     }
 
+    /// Returns the value of [`Archetypes::changes`] when this archetype last
+    /// gained its first entity, lost its last entity, or had its columns
+    /// reallocated.
+    pub(crate) fn changed_at(&self) -> u64 {
+        self.changed_at
+    }
+
     /// Returns the total number of entities in this archetype.
     pub fn entity_count(&self) -> u32 {
         debug_assert!(u32::try_from(self.entity_ids.len()).is_ok());
@@ -1246,6 +1448,7 @@ impl fmt::Debug for Archetype {
             .field("remove_components", &self.remove_components)
             .field("refresh_listeners", &self.refresh_listeners)
             .field("event_listeners", &self.event_listeners)
+            .field("changed_at", &self.changed_at)
             .finish()
     }
 }
@@ -1355,6 +1558,33 @@ impl Column {
         }
     }
 
+    /// Like [`swap_remove`](Self::swap_remove), but if `taken` is the column's
+    /// component index paired with a pointer, the removed element is moved to
+    /// that pointer instead of being dropped.
+    ///
+    /// # Safety
+    ///
+    /// - `idx` must be in bounds
+    /// - `len` must be correct
+    /// - `comp_idx` must be the component index of this column
+    /// - The pointer in `taken` must be valid for writes of the component
+    unsafe fn swap_remove_or_take(
+        &mut self,
+        len: usize,
+        idx: usize,
+        comp_idx: ComponentIdx,
+        taken: Option<(ComponentIdx, NonNull<u8>)>,
+    ) {
+        match taken {
+            Some((taken_idx, out)) if taken_idx == comp_idx => {
+                let elem = self.data.as_ptr().add(self.component_layout.size() * idx);
+                ptr::copy_nonoverlapping(elem, out.as_ptr(), self.component_layout.size());
+                self.swap_remove_no_drop(len, idx);
+            }
+            _ => self.swap_remove(len, idx),
+        }
+    }
+
     /// Moves the element at `src_idx` from `self` to `other`.
     ///
     /// # Safety
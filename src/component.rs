@@ -4,7 +4,9 @@ use alloc::borrow::Cow;
 use alloc::collections::BTreeSet;
 use core::alloc::Layout;
 use core::any::TypeId;
+use core::marker::PhantomData;
 use core::ops::Index;
+use core::ptr::NonNull;
 
 use ahash::RandomState;
 pub use evenio_macros::Component;
@@ -200,6 +202,8 @@ unsafe impl HandlerParam for &'_ Components {
 
     type This<'a> = &'a Components;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -430,8 +434,128 @@ pub struct AddComponent(pub ComponentId);
 #[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct RemoveComponent(pub ComponentId);
 
+/// A type-erased, read-only pointer to a component on an entity.
+///
+/// This is useful for code which must access components that it cannot name
+/// at compile time, such as scripting layers. See [`World::get_ptr`] and
+/// [`World::iter_ptrs`].
+#[derive(Copy, Clone, Debug)]
+pub struct Ptr<'a> {
+    ptr: NonNull<u8>,
+    info: &'a ComponentInfo,
+}
+
+impl<'a> Ptr<'a> {
+    /// Creates a new `Ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid instance of the component described by
+    /// `info`, which is not mutated for the duration of `'a`.
+    pub(crate) unsafe fn new(ptr: NonNull<u8>, info: &'a ComponentInfo) -> Self {
+        Self { ptr, info }
+    }
+
+    /// Returns the raw pointer to the component data.
+    pub fn as_ptr(self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// Returns the [`ComponentInfo`] of the pointed-to component.
+    pub fn info(self) -> &'a ComponentInfo {
+        self.info
+    }
+
+    /// Returns the [`Layout`] of the pointed-to component.
+    pub fn layout(self) -> Layout {
+        self.info.layout()
+    }
+
+    /// Returns the [`DropFn`] of the pointed-to component.
+    pub fn drop_fn(self) -> DropFn {
+        self.info.drop()
+    }
+
+    /// Converts this pointer into a reference to `T`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the type of the pointed-to component.
+    pub unsafe fn deref<T>(self) -> &'a T {
+        &*self.ptr.as_ptr().cast_const().cast::<T>()
+    }
+}
+
+/// A type-erased, mutable pointer to a component on an entity.
+///
+/// This is the mutable version of [`Ptr`]. See [`World::get_ptr_mut`] and
+/// [`World::iter_ptrs_mut`].
+#[derive(Debug)]
+pub struct PtrMut<'a> {
+    ptr: NonNull<u8>,
+    info: &'a ComponentInfo,
+    _marker: PhantomData<&'a mut u8>,
+}
+
+impl<'a> PtrMut<'a> {
+    /// Creates a new `PtrMut`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid instance of the component described by
+    /// `info`, which is not aliased for the duration of `'a`.
+    pub(crate) unsafe fn new(ptr: NonNull<u8>, info: &'a ComponentInfo) -> Self {
+        Self {
+            ptr,
+            info,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the raw pointer to the component data.
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// Returns the [`ComponentInfo`] of the pointed-to component.
+    pub fn info(&self) -> &'a ComponentInfo {
+        self.info
+    }
+
+    /// Returns the [`Layout`] of the pointed-to component.
+    pub fn layout(&self) -> Layout {
+        self.info.layout()
+    }
+
+    /// Returns the [`DropFn`] of the pointed-to component.
+    pub fn drop_fn(&self) -> DropFn {
+        self.info.drop()
+    }
+
+    /// Reborrows this pointer as a read-only [`Ptr`].
+    pub fn as_ref(&self) -> Ptr<'_> {
+        Ptr {
+            ptr: self.ptr,
+            info: self.info,
+        }
+    }
+
+    /// Converts this pointer into a mutable reference to `T`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the type of the pointed-to component.
+    pub unsafe fn deref_mut<T>(self) -> &'a mut T {
+        &mut *self.ptr.as_ptr().cast::<T>()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use core::alloc::Layout;
+
+    use crate::mutability::Mutability;
+
     use crate::prelude::*;
 
     #[derive(GlobalEvent)]
@@ -523,4 +647,53 @@ mod tests {
 
         assert_eq!(world.components()[c1].member_of.len(), 1);
     }
+
+    #[test]
+    fn component_ptrs() {
+        #[derive(Component, PartialEq, Debug)]
+        struct A(u32);
+
+        #[derive(Component, PartialEq, Debug)]
+        #[component(immutable)]
+        struct B(u32);
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, A(1));
+        world.insert(e1, B(10));
+        let e2 = world.spawn();
+        world.insert(e2, A(2));
+        let e3 = world.spawn();
+
+        let a = world.add_component::<A>().index();
+        let b = world.add_component::<B>().index();
+
+        let ptr = world.get_ptr(e1, a).unwrap();
+        assert_eq!(ptr.layout(), Layout::new::<A>());
+        assert_eq!(ptr.info().mutability(), Mutability::Mutable);
+        assert_eq!(unsafe { ptr.deref::<A>() }, &A(1));
+        assert!(world.get_ptr(e3, a).is_none());
+
+        // Immutable components can't be accessed mutably.
+        assert!(world.get_ptr(e1, b).is_some());
+        assert!(world.get_ptr_mut(e1, b).is_none());
+
+        let ptr = world.get_ptr_mut(e2, a).unwrap();
+        unsafe { ptr.deref_mut::<A>() }.0 += 1;
+        assert_eq!(world.get::<A>(e2), Some(&A(3)));
+
+        for (_, ptr) in world.iter_ptrs_mut(a) {
+            unsafe { ptr.deref_mut::<A>() }.0 *= 10;
+        }
+
+        let mut values = world
+            .iter_ptrs(a)
+            .map(|(e, ptr)| (e, unsafe { ptr.deref::<A>() }.0))
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+
+        assert_eq!(values, [(e1, 10), (e2, 30)]);
+        assert_eq!(world.iter_ptrs(b).count(), 1);
+    }
 }
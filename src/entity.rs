@@ -117,6 +117,8 @@ unsafe impl HandlerParam for &'_ Entities {
 
     type This<'a> = &'a Entities;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
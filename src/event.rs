@@ -1,27 +1,128 @@
 //! Types for sending and receiving [`Event`]s.
 
+mod boxed;
+mod bridge;
+mod bundle;
+mod category;
+mod dedup;
+mod either;
 mod global;
+mod info;
+mod intercept;
+mod lifecycle;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod multi_target;
+#[cfg(feature = "persist")]
+mod persist;
+#[cfg(feature = "std")]
+mod queue;
+mod rate_limit;
+mod record;
+mod request;
 mod targeted;
+mod untyped;
 
 use alloc::borrow::Cow;
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::any::TypeId;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 use core::ptr::{self, NonNull};
-use core::{any, fmt, slice, str};
-
+use core::{any, fmt, mem, slice, str};
+
+pub use boxed::*;
+pub use bridge::*;
+pub use bundle::*;
+pub use category::*;
+pub(crate) use dedup::Deduplicators;
+pub use either::*;
 use evenio_macros::all_tuples;
+/// Declares a [`Sender`] with a named method for sending each of its events.
+///
+/// The attribute is applied to a struct with named fields, where each field
+/// declares a method sending the event of the field's type. Fields of targeted
+/// events must be marked with `#[targeted]`, which makes the method take the
+/// target as its first argument. Methods have the visibility and attributes of
+/// their field, including doc comments.
+///
+/// The struct is replaced by a tuple struct wrapping a
+/// `Sender<'a, (E0, E1, ...)>` of the events in field order, which implements
+/// [`HandlerParam`]. A lifetime parameter `'a` is added if the struct doesn't
+/// have a lifetime parameter already. The wrapped sender is still accessible
+/// as field `0`.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::sender;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Attack;
+///
+/// #[derive(TargetedEvent)]
+/// struct Damage(u32);
+///
+/// #[derive(GlobalEvent)]
+/// struct Victory;
+///
+/// #[sender]
+/// struct CombatSender {
+///     /// Deals damage to an entity.
+///     #[targeted]
+///     damage: Damage,
+///     /// Ends the fight.
+///     victory: Victory,
+/// }
+///
+/// let mut world = World::new();
+///
+/// let enemy = world.spawn();
+///
+/// world.add_handler(move |_: Receiver<Attack>, s: CombatSender| {
+///     s.damage(enemy, Damage(10));
+///     s.victory(Victory);
+/// });
+///
+/// world.add_handler(|r: Receiver<Damage, ()>| println!("took {} damage", r.event.0));
+///
+/// world.send(Attack); // Prints "took 10 damage".
+/// ```
+///
+/// [`HandlerParam`]: crate::handler::HandlerParam
+pub use evenio_macros::sender;
 pub use global::*;
+pub use info::*;
+pub use intercept::*;
+pub use lifecycle::*;
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+pub use metrics::*;
+pub use multi_target::*;
+#[cfg(feature = "persist")]
+#[cfg_attr(docsrs, doc(cfg(feature = "persist")))]
+pub use persist::*;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use queue::*;
+pub(crate) use rate_limit::RateLimiters;
+pub use rate_limit::{Overflow, RateLimit};
+pub use record::*;
+pub use request::*;
 pub use targeted::*;
+pub use untyped::*;
 
 use crate::access::Access;
 use crate::archetype::Archetype;
 use crate::component::ComponentIdx;
 use crate::drop::{drop_fn_of, DropFn};
 use crate::entity::{EntityId, EntityLocation};
-use crate::fetch::FetcherState;
-use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::fetch::{Fetcher, FetcherState};
+use crate::handler::{HandlerConfig, HandlerId, HandlerInfo, HandlerParam, InitError};
 use crate::mutability::{Immutable, Mutability, MutabilityMarker, Mutable};
 use crate::prelude::Component;
 use crate::query::Query;
@@ -100,10 +201,57 @@ pub enum EventKind {
         /// The [`ComponentIdx`] of the component to remove.
         component_idx: ComponentIdx,
     },
+    /// The [`Take`] event.
+    Take {
+        /// The [`ComponentIdx`] of the component to take.
+        component_idx: ComponentIdx,
+        /// The [`TargetedEventIdx`] of the [`TakenComponent`] event the
+        /// component is sent with.
+        taken_idx: TargetedEventIdx,
+    },
     /// The [`Spawn`] event.
     Spawn,
     /// The [`Despawn`] event.
     Despawn,
+    /// The [`InsertBundle`] event. The event must be an `InsertBundle<B>`
+    /// whose [`Bundle`] was added to the world during [`Event::init`].
+    InsertBundle,
+    /// The [`SpawnWith`] event. The event must be a `SpawnWith<B>` whose
+    /// [`Bundle`] was added to the world during [`Event::init`].
+    SpawnWith,
+}
+
+impl EventKind {
+    /// Returns `true` if this is the kind of an event which inserts or removes
+    /// a single component: [`Insert`], [`Remove`] or [`Take`]. These are the
+    /// events received by a [`LifecycleReceiver`].
+    pub const fn is_lifecycle(self) -> bool {
+        matches!(
+            self,
+            Self::Insert { .. } | Self::Remove { .. } | Self::Take { .. }
+        )
+    }
+}
+
+/// The priority of a deferred event.
+///
+/// When all non-deferred events have been handled, deferred events with a
+/// higher priority are broadcast before deferred events with a lower priority,
+/// regardless of the order they were sent in. Deferred events with equal
+/// priorities are broadcast in the order they were sent.
+///
+/// See [`World::send_prioritized`] and [`Sender::send_prioritized`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Priority(pub i32);
+
+impl Priority {
+    /// A priority above [`Priority::NORMAL`], e.g. for input events.
+    pub const HIGH: Self = Self(100);
+    /// The priority of events sent with [`World::send_deferred`] and
+    /// [`Sender::send_deferred`].
+    pub const NORMAL: Self = Self(0);
+    /// A priority below [`Priority::NORMAL`], e.g. for cosmetic events.
+    pub const LOW: Self = Self(-100);
 }
 
 /// Data needed to create a new event.
@@ -125,8 +273,56 @@ pub struct EventDescriptor {
     pub drop: DropFn,
     /// The [mutability](Event::Mutability) of this event.
     pub mutability: Mutability,
+    /// The [`CloneFn`] of the event, if it can be cloned.
+    pub clone: CloneFn,
+    /// The [`SerializeFn`] of the event, if it can be serialized.
+    pub serialize: SerializeFn,
+    /// The [`DeserializeFn`] of the event, if it can be deserialized.
+    pub deserialize: DeserializeFn,
+}
+
+/// Clone function for an event. The event may not necessarily have a type in
+/// Rust's type system.
+///
+/// The function pointer takes a pointer to an initialized event and a pointer
+/// to uninitialized memory with the event's layout, and writes a copy of the
+/// event to the latter. In order to be safe to call, both pointers must be
+/// correctly aligned for the event and must not overlap.
+///
+/// If the function pointer is `None`, then the event can't be cloned. This is
+/// the case for all events added with a type, since whether a type implements
+/// [`Clone`] can't be determined generically.
+pub type CloneFn = Option<unsafe fn(NonNull<u8>, NonNull<u8>)>;
+
+/// Returns a [`CloneFn`] for some [`Clone`] Rust type `T`.
+pub const fn clone_fn_of<T: Clone>() -> CloneFn {
+    Some(|src, dst| unsafe {
+        dst.cast::<T>()
+            .as_ptr()
+            .write(src.cast::<T>().as_ref().clone())
+    })
 }
 
+/// Serialize function for an event. The event may not necessarily have a type
+/// in Rust's type system.
+///
+/// The function pointer takes a pointer to an initialized event and appends the
+/// encoded event to the buffer. In order to be safe to call, the pointer must
+/// be correctly aligned for the event.
+///
+/// Events added at runtime are recorded in this encoding. See
+/// [`World::record_untyped`].
+pub type SerializeFn = Option<unsafe fn(NonNull<u8>, &mut Vec<u8>)>;
+
+/// Deserialize function for an event, the inverse of its [`SerializeFn`].
+///
+/// The function pointer takes the bytes written by the [`SerializeFn`] and a
+/// pointer to uninitialized memory with the event's layout, and writes the
+/// decoded event to the latter. It returns `false` without writing anything if
+/// the bytes are invalid. In order to be safe to call, the pointer must be
+/// correctly aligned for the event.
+pub type DeserializeFn = Option<unsafe fn(&[u8], NonNull<u8>) -> bool>;
+
 impl EventDescriptor {
     /// Constructs and initializes an `EventDescriptor` for the given type in a
     /// world.
@@ -138,6 +334,9 @@ impl EventDescriptor {
             layout: Layout::new::<E>(),
             drop: drop_fn_of::<E>(),
             mutability: Mutability::of::<E::Mutability>(),
+            clone: None,
+            serialize: None,
+            deserialize: None,
         }
     }
 }
@@ -198,6 +397,13 @@ pub(crate) struct EventQueueItem {
     /// Type-erased pointer to this event. When null, ownership of the event
     /// has been transferred and no destructor needs to run.
     pub(crate) event: NonNull<u8>,
+    /// The number of events this event was sent in response to. See
+    /// [`World::set_max_event_depth`].
+    pub(crate) depth: u32,
+    /// The number of events left in the transaction this event was sent in
+    /// with [`Sender::send_all`], including this one. Zero if the event is
+    /// not part of a transaction.
+    pub(crate) transaction: u32,
 }
 
 /// Metadata for an event in the event queue.
@@ -218,25 +424,36 @@ pub(crate) enum EventMeta {
 #[derive(Clone, Copy, Debug)]
 pub struct EventPtr<'a> {
     event: NonNull<u8>,
-    // `false` when borrowed, `true` when taken.
-    ownership_flag: NonNull<bool>,
+    ownership_flag: NonNull<Ownership>,
+    meta: EventMeta,
     _marker: PhantomData<&'a mut u8>,
 }
 
 impl<'a> EventPtr<'a> {
     /// Constructs a new event pointer.
-    pub(crate) fn new(event: NonNull<u8>, ownership_flag: NonNull<bool>) -> Self {
+    pub(crate) fn new(
+        event: NonNull<u8>,
+        ownership_flag: NonNull<Ownership>,
+        meta: EventMeta,
+    ) -> Self {
         Self {
             event,
             ownership_flag,
+            meta,
             _marker: PhantomData,
         }
     }
 
+    /// Returns the metadata of the event, which identifies the type of the
+    /// event for handlers receiving more than one event.
+    pub(crate) fn meta(self) -> EventMeta {
+        self.meta
+    }
+
     /// Returns the underlying pointer to the type-erased event.
     #[track_caller]
     pub fn as_ptr(self) -> NonNull<u8> {
-        let is_owned = unsafe { *self.ownership_flag.as_ptr() };
+        let is_owned = unsafe { *self.ownership_flag.as_ptr() } != Ownership::Borrowed;
         debug_assert!(
             !is_owned,
             "`as_ptr` must not be called after the event has been marked as owned"
@@ -256,10 +473,31 @@ impl<'a> EventPtr<'a> {
     ///
     /// [`as_ptr`]: Self::as_ptr
     pub unsafe fn set_owned(self) {
-        *self.ownership_flag.as_ptr() = true;
+        *self.ownership_flag.as_ptr() = Ownership::Taken;
+    }
+
+    /// Like [`set_owned`](Self::set_owned), but also has the world send a
+    /// [`Taken`] event.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`set_owned`](Self::set_owned).
+    pub(crate) unsafe fn set_owned_and_notify(self) {
+        *self.ownership_flag.as_ptr() = Ownership::TakenAndNotify;
     }
 }
 
+/// Whether an event being broadcast is still owned by the world.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Ownership {
+    /// The event is borrowed by the handlers.
+    Borrowed,
+    /// A handler took the event.
+    Taken,
+    /// A handler took the event and requested a [`Taken`] event.
+    TakenAndNotify,
+}
+
 /// Mutable reference to an instance of event `E`.
 ///
 /// To get at `E`, use the [`Deref`] and [`DerefMut`] implementations or
@@ -306,6 +544,152 @@ impl<'a, E: Event> EventMut<'a, E> {
         unsafe { this.ptr.set_owned() };
         res
     }
+
+    /// Takes ownership of the event like [`take`](Self::take), but instead
+    /// of silently skipping the handlers expected to run after the current
+    /// handler, a [`Taken`] event is sent to notify them.
+    ///
+    /// This is useful when the payload is expensive to clone, such as a
+    /// pooled network buffer, but other handlers need to know it was claimed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::event::Taken;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Packet(Vec<u8>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let mut pool = vec![];
+    ///
+    /// world.add_handler(move |r: ReceiverMut<Packet>| {
+    ///     pool.push(EventMut::take_and_notify(r.event).0);
+    /// });
+    ///
+    /// world.add_handler(|_: Receiver<Packet>| unreachable!());
+    ///
+    /// world.add_handler(|r: Receiver<Taken>| println!("{:?} was taken", r.event.event));
+    ///
+    /// world.send(Packet(vec![1, 2, 3]));
+    /// ```
+    pub fn take_and_notify(this: Self) -> E {
+        let res = unsafe { this.ptr.as_ptr().as_ptr().cast::<E>().read() };
+        unsafe { this.ptr.set_owned_and_notify() };
+        res
+    }
+
+    /// Consumes the event, stopping it from propagating to any handlers
+    /// expected to run after the current handler. The event is dropped.
+    ///
+    /// This is equivalent to dropping the result of [`take`](Self::take). As
+    /// with `take`, consuming an [`Insert`], [`Remove`], [`Spawn`], or
+    /// [`Despawn`] event prevents the change to the world from happening.
+    ///
+    /// Only handlers which receive the event mutably with [`ReceiverMut`] may
+    /// consume it, so whether an event can be stopped is determined by the
+    /// handlers listening for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Click {
+    ///     x: f32,
+    ///     y: f32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // The topmost widget runs first and eats clicks inside its bounds.
+    /// world.add_handler(
+    ///     (|r: ReceiverMut<Click>| {
+    ///         if r.event.x < 100.0 && r.event.y < 50.0 {
+    ///             println!("button clicked");
+    ///             EventMut::consume(r.event);
+    ///         }
+    ///     })
+    ///     .high(),
+    /// );
+    ///
+    /// world.add_handler(|r: Receiver<Click>| {
+    ///     println!("background clicked at ({}, {})", r.event.x, r.event.y);
+    /// });
+    ///
+    /// world.send(Click { x: 10.0, y: 10.0 });
+    /// world.send(Click { x: 200.0, y: 10.0 });
+    /// ```
+    ///
+    /// Output:
+    ///
+    /// ```txt
+    /// button clicked
+    /// background clicked at (200, 10)
+    /// ```
+    pub fn consume(this: Self) {
+        drop(Self::take(this));
+    }
+
+    /// Replaces the event with `event` and returns the previous event. The
+    /// handlers expected to run after the current handler receive the new
+    /// event.
+    ///
+    /// Handlers for an event run in order of their [priority], and handlers
+    /// with the same priority run in the order they were added. A handler
+    /// therefore observes every replacement made by the handlers before it
+    /// and none of the replacements made after it. For [`Insert`] and
+    /// [`Remove`] events, the event left after all handlers have run is the
+    /// one applied to the world.
+    ///
+    /// Modifying the event through [`DerefMut`] has the same effect. `replace`
+    /// is useful when a handler substitutes an entirely different value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent, Debug, PartialEq)]
+    /// enum Damage {
+    ///     Physical(u32),
+    ///     Blocked,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // Armor runs first and blocks small hits.
+    /// world.add_handler(
+    ///     (|mut r: ReceiverMut<Damage>| {
+    ///         if matches!(*r.event, Damage::Physical(n) if n < 10) {
+    ///             let old = EventMut::replace(&mut r.event, Damage::Blocked);
+    ///             println!("armor blocked {old:?}");
+    ///         }
+    ///     })
+    ///     .high(),
+    /// );
+    ///
+    /// world.add_handler(|r: Receiver<Damage>| println!("took {:?}", r.event));
+    ///
+    /// world.send(Damage::Physical(5));
+    /// world.send(Damage::Physical(50));
+    /// ```
+    ///
+    /// Output:
+    ///
+    /// ```txt
+    /// armor blocked Physical(5)
+    /// took Blocked
+    /// took Physical(50)
+    /// ```
+    ///
+    /// [priority]: crate::handler::HandlerPriority
+    pub fn replace(this: &mut Self, event: E::This<'a>) -> E::This<'a> {
+        mem::replace(&mut **this, event)
+    }
 }
 
 unsafe impl<'a, E> Send for EventMut<'a, E>
@@ -379,6 +763,8 @@ unsafe impl<E: GlobalEvent> HandlerParam for Receiver<'_, E> {
 
     type This<'a> = Receiver<'a, E>;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         let event_id = world.add_global_event::<E>();
 
@@ -499,6 +885,8 @@ where
 
     type This<'a> = ReceiverMut<'a, E>;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         let event_id = world.add_global_event::<E>();
 
@@ -652,7 +1040,7 @@ impl<'a, ES: EventSet> Sender<'a, ES> {
     pub fn send<E: GlobalEvent + 'a>(&self, event: E) {
         // The event type and event set are all compile time known, so the compiler
         // should be able to optimize this away.
-        let event_idx = ES::find_index::<E>(self.state).unwrap_or_else(|| {
+        let event_idx = ES::find_index_in::<E>(self.state, self.world).unwrap_or_else(|| {
             panic!(
                 "global event `{}` is not in the `EventSet` of this `Sender`",
                 any::type_name::<E>()
@@ -673,7 +1061,7 @@ impl<'a, ES: EventSet> Sender<'a, ES> {
     pub fn send_to<E: TargetedEvent + 'a>(&self, target: EntityId, event: E) {
         // The event type and event set are all compile time known, so the compiler
         // should be able to optimize this away.
-        let event_idx = ES::find_index::<E>(self.state).unwrap_or_else(|| {
+        let event_idx = ES::find_index_in::<E>(self.state, self.world).unwrap_or_else(|| {
             panic!(
                 "targeted event `{}` is not in the `EventSet` of this `Sender`",
                 any::type_name::<E>()
@@ -690,584 +1078,2496 @@ impl<'a, ES: EventSet> Sender<'a, ES> {
         };
     }
 
-    /// Queue the creation of a new entity.
-    ///
-    /// This returns the [`EntityId`] of the to-be-spawned entity and queues the
-    /// [`Spawn`] event. Note that the returned `EntityId` may not be valid
-    /// until after the `Spawn` event has finished broadcasting.
+    /// Add a copy of a [`TargetedEvent`] to the queue of events to send for
+    /// each entity in `targets`.
     ///
-    /// The new entity will be spawned without any components attached, and the
-    /// returned `EntityId` will not have been used by any previous entities.
+    /// The event is cloned for every target except the last, which receives
+    /// the original. This is equivalent to calling [`send_to`](Self::send_to)
+    /// once per target, but only looks up the event in the [`EventSet`] once.
     ///
     /// # Panics
     ///
-    /// Panics if `Spawn` is not in the [`EventSet`] of this sender.
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(TargetedEvent, Clone)]
+    /// struct Damage(u32);
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Explosion;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |_: Receiver<Explosion>, f: Fetcher<(EntityId, With<&Health>)>, s: Sender<Damage>| {
+    ///         s.send_to_many(f.iter().map(|(id, _)| id), Damage(10));
+    ///     },
+    /// );
+    ///
+    /// world.add_handler(|r: Receiver<Damage, &mut Health>| {
+    ///     r.query.0 = r.query.0.saturating_sub(r.event.0);
+    /// });
+    ///
+    /// let e1 = world.spawn();
+    /// world.insert(e1, Health(15));
+    /// let e2 = world.spawn();
+    /// world.insert(e2, Health(5));
+    ///
+    /// world.send(Explosion);
+    ///
+    /// assert_eq!(world.get::<Health>(e1).unwrap().0, 5);
+    /// assert_eq!(world.get::<Health>(e2).unwrap().0, 0);
+    /// ```
     #[track_caller]
-    pub fn spawn(&self) -> EntityId {
-        let id = unsafe { self.world.queue_spawn() };
-        self.send(Spawn(id));
-        id
+    pub fn send_to_many<E, I>(&self, targets: I, event: E)
+    where
+        E: TargetedEvent + Clone + 'a,
+        I: IntoIterator<Item = EntityId>,
+    {
+        let event_idx = ES::find_index_in::<E>(self.state, self.world).unwrap_or_else(|| {
+            panic!(
+                "targeted event `{}` is not in the `EventSet` of this `Sender`",
+                any::type_name::<E>()
+            )
+        });
+
+        let queue = |target, event: E| {
+            let ptr = self.alloc_layout(Layout::new::<E>());
+
+            unsafe { ptr::write::<E>(ptr.as_ptr().cast(), event) };
+
+            unsafe {
+                self.world
+                    .queue_targeted(target, ptr, TargetedEventIdx(event_idx))
+            };
+        };
+
+        let mut targets = targets.into_iter();
+
+        let Some(mut target) = targets.next() else {
+            return;
+        };
+
+        for next in targets {
+            queue(target, event.clone());
+            target = next;
+        }
+
+        queue(target, event);
     }
 
-    /// Queue an [`Insert`] event.
+    /// Add a copy of a [`TargetedEvent`] to the queue of events for every
+    /// entity matched by `fetcher`.
     ///
-    /// This is equivalent to:
+    /// This is like [`send_to_many`](Self::send_to_many) with the IDs of the
+    /// matched entities, but the targets are read directly from the matched
+    /// archetypes, so the query is never executed. The event is cloned for all
+    /// targets but the last, which receives `event` itself. A filter-only
+    /// query such as `With<&C>` doesn't conflict with any other parameter of
+    /// the handler.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// # #[derive(Component)] struct C;
-    /// # fn _f(sender: &mut Sender<Insert<C>>, target: EntityId, component: C) {
-    /// sender.send_to(target, Insert(component));
-    /// # }
-    /// ```
+    /// use evenio::prelude::*;
     ///
-    /// # Panics
+    /// #[derive(GlobalEvent)]
+    /// struct Explosion(u32);
     ///
-    /// Panics if `Insert<C>` is not in the [`EventSet`] of this sender.
+    /// #[derive(TargetedEvent, Clone)]
+    /// struct Damage(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |r: Receiver<Explosion>, f: Fetcher<With<&Health>>, s: Sender<Damage>| {
+    ///         s.send_to_all(&f, Damage(r.event.0));
+    ///     },
+    /// );
+    ///
+    /// world.add_handler(|r: Receiver<Damage, &mut Health>| {
+    ///     r.query.0 = r.query.0.saturating_sub(r.event.0);
+    /// });
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Health(15));
+    ///
+    /// world.send(Explosion(10));
+    ///
+    /// assert_eq!(world.get::<Health>(e).unwrap().0, 5);
+    /// ```
     #[track_caller]
-    pub fn insert<C: Component>(&self, target: EntityId, component: C) {
-        self.send_to(target, Insert(component))
+    pub fn send_to_all<Q, E>(&self, fetcher: &Fetcher<Q>, event: E)
+    where
+        Q: Query,
+        E: TargetedEvent + Clone + 'a,
+    {
+        self.send_to_many(fetcher.entity_ids(), event);
     }
 
-    /// Queue a [`Remove`] event.
+    /// Add a tuple of [`GlobalEvent`]s to the queue of events to send as a
+    /// transaction.
     ///
-    /// This is equivalent to:
+    /// The events are broadcast back-to-back in the order of the tuple, with
+    /// no other events in between. See [`transaction`](Self::transaction) for
+    /// more information.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if one of the events is not in the [`EventSet`] of this
+    ///   sender.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// # #[derive(Component)] struct C;
-    /// # fn _f(sender: &mut Sender<Remove<C>>, target: EntityId) {
-    /// sender.send_to(target, Remove::<C>);
-    /// # }
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Start;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Param(f32);
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Commit;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Log(&'static str);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Tick>, s: Sender<(Start, Param, Commit)>| {
+    ///     s.send_all((Start, Param(0.5), Commit));
+    /// });
+    ///
+    /// world.add_handler(|_: Receiver<Start>, s: Sender<Log>| s.send(Log("started")));
+    /// world.add_handler(|_: Receiver<Param>| println!("param"));
+    /// world.add_handler(|_: Receiver<Commit>| println!("commit"));
+    /// world.add_handler(|r: Receiver<Log>| println!("{}", r.event.0));
+    ///
+    /// // Prints "param", "commit" and then "started".
+    /// world.send(Tick);
     /// ```
+    #[track_caller]
+    pub fn send_all<T: GlobalEventTuple<'a>>(&self, events: T) {
+        self.transaction(|s| events.send_each(s));
+    }
+
+    /// Runs `f` and makes the events it adds to the queue a transaction.
     ///
-    /// # Panics
+    /// The events of a transaction are broadcast back-to-back in the order
+    /// they were sent, with no other events in between. Events sent by the
+    /// handlers of the transaction's events are broadcast after the whole
+    /// transaction, in the order they were sent. Unlike
+    /// [`send_all`](Self::send_all), this can mix global and targeted events.
     ///
-    /// Panics if `Remove<C>` is not in the [`EventSet`] of this sender.
+    /// Deferred events added by `f` are not part of the transaction.
     #[track_caller]
-    pub fn remove<C: Component>(&self, target: EntityId) {
-        self.send_to(target, Remove::<C>)
+    pub fn transaction<F: FnOnce(&Self)>(&self, f: F) {
+        // SAFETY: Senders have access to the event queue.
+        let start = unsafe { self.world.queue_len() };
+
+        f(self);
+
+        unsafe { self.world.mark_transaction(start) };
     }
 
-    /// Queue a [`Despawn`] event.
+    /// Add a [`GlobalEvent`] to the queue of deferred events.
     ///
-    /// This is equivalent to:
+    /// Unlike [`send`](Self::send), the event is not broadcast as soon as the
+    /// handlers of the current event have run. Instead, it is broadcast once
+    /// all other queued events have finished broadcasting, including events
+    /// they send in turn. This avoids deeply nested chains of events. Deferred
+    /// events are broadcast in the order they were sent.
+    ///
+    /// See also [`World::send_deferred`].
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// # fn _f(sender: &mut Sender<Despawn>, target: EntityId) {
-    /// sender.send_to(target, Despawn);
-    /// # }
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct A;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct B(&'static str);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<A>, s: Sender<B>| {
+    ///     s.send_deferred(B("deferred"));
+    ///     s.send(B("immediate"));
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<B>| println!("{}", r.event.0));
+    ///
+    /// world.send(A);
     /// ```
     ///
-    /// # Panics
+    /// Output:
     ///
-    /// Panics if `Despawn` is not in the [`EventSet`] of this sender.
+    /// ```txt
+    /// immediate
+    /// deferred
+    /// ```
     #[track_caller]
-    pub fn despawn(&self, target: EntityId) {
-        self.send_to(target, Despawn)
+    pub fn send_deferred<E: GlobalEvent + 'a>(&self, event: E) {
+        self.send_prioritized(Priority::NORMAL, event);
     }
 
-    /// Allocate an object into the bump allocator and return an exclusive
-    /// reference to it.
-    #[inline]
-    pub fn alloc<T>(&self, value: T) -> &'a mut T {
-        let ptr = self.alloc_layout(Layout::new::<T>()).cast::<T>().as_ptr();
-
-        unsafe { ptr::write(ptr, value) };
-
-        unsafe { &mut *ptr }
+    /// Add a [`TargetedEvent`] to the queue of deferred events.
+    ///
+    /// See [`send_deferred`](Self::send_deferred) for more information.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn send_to_deferred<E: TargetedEvent + 'a>(&self, target: EntityId, event: E) {
+        self.send_to_prioritized(Priority::NORMAL, target, event);
     }
 
-    /// Allocate a slice into the bump allocator and return an exclusive
-    /// reference to it.
+    /// Add a [`GlobalEvent`] to the queue of deferred events with the given
+    /// [`Priority`].
     ///
-    /// The elements of the slice are initialized using the supplied closure.
-    /// The closure argument is the position in the slice.
+    /// Once all non-deferred events have been handled, deferred events are
+    /// broadcast from highest to lowest priority. Deferred events sent while
+    /// handling a deferred event take part in this ordering too, so a
+    /// high-priority event can preempt low-priority events deferred earlier.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::event::Priority;
+    /// use evenio::prelude::*;
+    ///
+    /// # #[derive(GlobalEvent)]
+    /// # struct A;
+    /// #
+    /// #[derive(GlobalEvent)]
+    /// struct B(&'static str);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<A>, s: Sender<B>| {
+    ///     s.send_prioritized(Priority::LOW, B("particles"));
+    ///     s.send_prioritized(Priority::HIGH, B("input"));
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<B>| println!("{}", r.event.0));
+    ///
+    /// world.send(A);
+    /// ```
+    ///
+    /// Output:
+    ///
+    /// ```txt
+    /// input
+    /// particles
+    /// ```
+    #[track_caller]
+    pub fn send_prioritized<E: GlobalEvent + 'a>(&self, priority: Priority, event: E) {
+        let event_idx = ES::find_index_in::<E>(self.state, self.world).unwrap_or_else(|| {
+            panic!(
+                "global event `{}` is not in the `EventSet` of this `Sender`",
+                any::type_name::<E>()
+            )
+        });
+
+        let ptr = self.alloc_layout(Layout::new::<E>());
+
+        unsafe { ptr::write::<E>(ptr.as_ptr().cast(), event) };
+
+        let meta = EventMeta::Global {
+            idx: GlobalEventIdx(event_idx),
+        };
+
+        unsafe { self.world.queue_deferred(priority, meta, ptr) };
+    }
+
+    /// Add a [`TargetedEvent`] to the queue of deferred events with the given
+    /// [`Priority`].
+    ///
+    /// See [`send_prioritized`](Self::send_prioritized) for more information.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn send_to_prioritized<E: TargetedEvent + 'a>(
+        &self,
+        priority: Priority,
+        target: EntityId,
+        event: E,
+    ) {
+        let event_idx = ES::find_index_in::<E>(self.state, self.world).unwrap_or_else(|| {
+            panic!(
+                "targeted event `{}` is not in the `EventSet` of this `Sender`",
+                any::type_name::<E>()
+            )
+        });
+
+        let ptr = self.alloc_layout(Layout::new::<E>());
+
+        unsafe { ptr::write::<E>(ptr.as_ptr().cast(), event) };
+
+        let meta = EventMeta::Targeted {
+            idx: TargetedEventIdx(event_idx),
+            target,
+        };
+
+        unsafe { self.world.queue_deferred(priority, meta, ptr) };
+    }
+
+    /// Add a barrier to the queue of deferred events.
+    ///
+    /// Events deferred after the barrier are broadcast only once all events
+    /// deferred before it have finished broadcasting, including the events
+    /// they send and defer in turn and the structural changes they make.
+    /// Their [`Priority`] only orders events on the same side of a barrier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Damage(u32);
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct EvaluateDeaths;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Tick>, s: Sender<(Damage, EvaluateDeaths)>| {
+    ///     s.send_deferred(Damage(10));
+    ///     s.defer_barrier();
+    ///     s.send_deferred(EvaluateDeaths);
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<Damage>, s: Sender<Damage>| {
+    ///     println!("damage {}", r.event.0);
+    ///
+    ///     if r.event.0 > 1 {
+    ///         // Splash damage is still applied before deaths are evaluated.
+    ///         s.send_deferred(Damage(r.event.0 / 2));
+    ///     }
+    /// });
+    ///
+    /// world.add_handler(|_: Receiver<EvaluateDeaths>| println!("evaluate deaths"));
+    ///
+    /// world.send(Tick);
+    /// ```
+    ///
+    /// Output:
+    ///
+    /// ```txt
+    /// damage 10
+    /// damage 5
+    /// damage 2
+    /// damage 1
+    /// evaluate deaths
+    /// ```
+    pub fn defer_barrier(&self) {
+        unsafe { self.world.queue_barrier() }
+    }
+
+    /// Schedule a [`GlobalEvent`] to be broadcast once the world's tick has
+    /// advanced by `delay`.
+    ///
+    /// Unlike the other events sent by a `Sender`, scheduled events are
+    /// stored in the world until they are due, so they must be `'static`. See
+    /// [`World::send_delayed`] for more information.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Cast;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct CooldownOver;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Cast>, s: Sender<CooldownOver>| {
+    ///     s.send_delayed(60, CooldownOver);
+    /// });
+    ///
+    /// world.add_handler(|_: Receiver<CooldownOver>| println!("ready"));
+    ///
+    /// world.send(Cast);
+    /// world.advance(60); // Prints "ready".
+    /// ```
+    #[track_caller]
+    pub fn send_delayed<E: GlobalEvent + 'static>(&self, delay: u64, event: E) {
+        assert!(
+            ES::find_index_in::<E>(self.state, self.world).is_some(),
+            "global event `{}` is not in the `EventSet` of this `Sender`",
+            any::type_name::<E>()
+        );
+
+        unsafe {
+            self.world
+                .schedule(delay, Box::new(move |world| world.send(event)))
+        };
+    }
+
+    /// Schedule a [`TargetedEvent`] to be broadcast once the world's tick has
+    /// advanced by `delay`.
+    ///
+    /// See [`send_delayed`](Self::send_delayed) for more information.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `E` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn send_to_delayed<E: TargetedEvent + 'static>(
+        &self,
+        delay: u64,
+        target: EntityId,
+        event: E,
+    ) {
+        assert!(
+            ES::find_index_in::<E>(self.state, self.world).is_some(),
+            "targeted event `{}` is not in the `EventSet` of this `Sender`",
+            any::type_name::<E>()
+        );
+
+        unsafe {
+            self.world
+                .schedule(delay, Box::new(move |world| world.send_to(target, event)))
+        };
+    }
+
+    /// Queue the creation of a new entity.
+    ///
+    /// This returns the [`EntityId`] of the to-be-spawned entity and queues the
+    /// [`Spawn`] event. Note that the returned `EntityId` may not be valid
+    /// until after the `Spawn` event has finished broadcasting.
+    ///
+    /// The new entity will be spawned without any components attached, and the
+    /// returned `EntityId` will not have been used by any previous entities.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Spawn` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn spawn(&self) -> EntityId {
+        let id = unsafe { self.world.queue_spawn() };
+        self.send(Spawn(id));
+        id
+    }
+
+    /// Queue the creation of a new entity with all components in `bundle`.
+    ///
+    /// This returns the [`EntityId`] of the to-be-spawned entity and queues the
+    /// [`SpawnWith`] event. Note that the returned `EntityId` may not be valid
+    /// until after the `SpawnWith` event has finished broadcasting. Handlers
+    /// never observe the entity without the components of `bundle`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `SpawnWith<B>` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
+    ///
+    /// The returned `EntityId` can be stored in other entities right away.
+    ///
+    /// ```
+    /// use evenio::event::SpawnWith;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct SpawnSquad;
+    ///
+    /// #[derive(Component)]
+    /// struct Leader;
+    ///
+    /// #[derive(Component)]
+    /// struct Follows(EntityId);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |_: Receiver<SpawnSquad>, s: Sender<(SpawnWith<(Leader,)>, SpawnWith<(Follows,)>)>| {
+    ///         let leader = s.spawn_with((Leader,));
+    ///
+    ///         for _ in 0..3 {
+    ///             s.spawn_with((Follows(leader),));
+    ///         }
+    ///     },
+    /// );
+    ///
+    /// world.send(SpawnSquad);
+    /// ```
+    #[track_caller]
+    pub fn spawn_with<B: Bundle>(&self, bundle: B) -> EntityId {
+        let id = unsafe { self.world.queue_spawn_with() };
+        self.send(SpawnWith(id, bundle));
+        id
+    }
+
+    /// Queue an [`Insert`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # #[derive(Component)] struct C;
+    /// # fn _f(sender: &mut Sender<Insert<C>>, target: EntityId, component: C) {
+    /// sender.send_to(target, Insert(component));
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Insert<C>` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn insert<C: Component>(&self, target: EntityId, component: C) {
+        self.send_to(target, Insert(component))
+    }
+
+    /// Queue a [`Remove`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # #[derive(Component)] struct C;
+    /// # fn _f(sender: &mut Sender<Remove<C>>, target: EntityId) {
+    /// sender.send_to(target, Remove::<C>);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Remove<C>` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn remove<C: Component>(&self, target: EntityId) {
+        self.send_to(target, Remove::<C>)
+    }
+
+    /// Queue a [`Take`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # use evenio::event::Take;
+    /// # #[derive(Component)] struct C;
+    /// # fn _f(sender: &mut Sender<Take<C>>, target: EntityId) {
+    /// sender.send_to(target, Take::<C>);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Take<C>` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn take<C: Component>(&self, target: EntityId) {
+        self.send_to(target, Take::<C>)
+    }
+
+    /// Queue a [`Despawn`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # fn _f(sender: &mut Sender<Despawn>, target: EntityId) {
+    /// sender.send_to(target, Despawn);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Despawn` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn despawn(&self, target: EntityId) {
+        self.send_to(target, Despawn)
+    }
+
+    /// Queue a [`Despawn`] event with a [`DespawnReason`].
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # use evenio::event::DespawnReason;
+    /// # fn _f(sender: &mut Sender<Despawn>, target: EntityId, reason: DespawnReason) {
+    /// sender.send_to(target, Despawn::because(reason));
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Despawn` is not in the [`EventSet`] of this sender.
+    #[track_caller]
+    pub fn despawn_because(&self, target: EntityId, reason: DespawnReason) {
+        self.send_to(target, Despawn::because(reason))
+    }
+
+    /// Allocate an object into the bump allocator and return an exclusive
+    /// reference to it.
     #[inline]
-    pub fn alloc_slice<T, F>(&self, len: usize, mut f: F) -> &'a mut [T]
-    where
-        F: FnMut(usize) -> T,
-    {
-        let layout = Layout::array::<T>(len).expect("invalid slice length");
-        let dst = self.alloc_layout(layout).cast::<T>();
+    pub fn alloc<T>(&self, value: T) -> &'a mut T {
+        let ptr = self.alloc_layout(Layout::new::<T>()).cast::<T>().as_ptr();
+
+        unsafe { ptr::write(ptr, value) };
+
+        unsafe { &mut *ptr }
+    }
+
+    /// Allocate a slice into the bump allocator and return an exclusive
+    /// reference to it.
+    ///
+    /// The elements of the slice are initialized using the supplied closure.
+    /// The closure argument is the position in the slice.
+    #[inline]
+    pub fn alloc_slice<T, F>(&self, len: usize, mut f: F) -> &'a mut [T]
+    where
+        F: FnMut(usize) -> T,
+    {
+        let layout = Layout::array::<T>(len).expect("invalid slice length");
+        let dst = self.alloc_layout(layout).cast::<T>();
+
+        unsafe {
+            for i in 0..len {
+                ptr::write(dst.as_ptr().add(i), f(i));
+            }
+
+            let result = slice::from_raw_parts_mut(dst.as_ptr(), len);
+            debug_assert_eq!(Layout::for_value(result), layout);
+            result
+        }
+    }
+
+    /// Copies the given string into the bump allocator and returns an exclusive
+    /// reference to it.
+    #[inline]
+    pub fn alloc_str(&self, str: &str) -> &'a mut str {
+        unsafe {
+            let ptr = self
+                .alloc_layout(Layout::from_size_align_unchecked(str.len(), 1))
+                .as_ptr();
+
+            ptr::copy_nonoverlapping(str.as_ptr(), ptr, str.len());
+            let slice = slice::from_raw_parts_mut(ptr, str.len());
+            str::from_utf8_unchecked_mut(slice)
+        }
+    }
+
+    /// Allocate space for an object in the bump allocator with the given
+    /// [`Layout`].
+    ///
+    /// The returned pointer points to uninitialized memory.
+    #[inline]
+    pub fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        unsafe { self.world.alloc_layout(layout) }
+    }
+}
+
+unsafe impl<T: EventSet> HandlerParam for Sender<'_, T> {
+    type State = T::Indices;
+
+    type This<'a> = Sender<'a, T>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        config.set_event_queue_access(Access::ReadWrite);
+
+        let state = T::new_indices(world);
+
+        T::for_each_index(&state, |is_targeted, idx| {
+            if is_targeted {
+                config.insert_sent_targeted_event(TargetedEventIdx(idx));
+            } else {
+                config.insert_sent_global_event(GlobalEventIdx(idx));
+            }
+        });
+
+        Ok(state)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        Sender { state, world }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl<T: EventSet> fmt::Debug for Sender<'_, T>
+where
+    T::Indices: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("state", &self.state)
+            .field("world", &self.world)
+            .finish()
+    }
+}
+
+/// A set of [`Event`] types.
+///
+/// This trait is implemented for all events and tuples of events, so `E1`,
+/// `()`, `(E1,)`, `(E1, E2)` etc. are all event sets.
+///
+/// # Safety
+///
+/// This trait is marked `unsafe` because unsafe code relies on implementations
+/// being correct. It is not recommended to implement this trait yourself.
+pub unsafe trait EventSet {
+    /// The set of event indices.
+    type Indices: 'static;
+
+    /// Create a new set of events.
+    fn new_indices(world: &mut World) -> Self::Indices;
+
+    /// Find the event `F` in the set of events. Returns the event index.
+    fn find_index<F: Event>(indices: &Self::Indices) -> Option<u32>;
+
+    /// Like [`find_index`](Self::find_index), but may also look up the event
+    /// in the world for sets whose events are not known ahead of time, such as
+    /// [`AnyEvents`].
+    #[inline]
+    fn find_index_in<F: Event>(indices: &Self::Indices, world: UnsafeWorldCell) -> Option<u32> {
+        let _ = world;
+        Self::find_index::<F>(indices)
+    }
+
+    /// Run a function on every element of the set, passing in the event index
+    /// and a boolean indicating if the event is targeted or not.
+    fn for_each_index<F: FnMut(bool, u32)>(indices: &Self::Indices, f: F);
+}
+
+unsafe impl<E: Event> EventSet for E {
+    type Indices = u32;
+
+    fn new_indices(world: &mut World) -> Self::Indices {
+        let desc = EventDescriptor::new::<E>(world);
+
+        if TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>() {
+            unsafe { world.add_targeted_event_with_descriptor(desc) }
+                .index()
+                .0
+        } else {
+            unsafe { world.add_global_event_with_descriptor(desc) }
+                .index()
+                .0
+        }
+    }
+
+    #[inline]
+    fn find_index<F: Event>(index: &Self::Indices) -> Option<u32> {
+        (TypeId::of::<E::This<'static>>() == TypeId::of::<F::This<'static>>()).then_some(*index)
+    }
+
+    fn for_each_index<F: FnMut(bool, u32)>(index: &Self::Indices, mut f: F) {
+        f(
+            TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>(),
+            *index,
+        )
+    }
+}
+
+macro_rules! impl_event_set_tuple {
+    ($(($E:ident, $e:ident)),*) => {
+        #[allow(unused_variables, unused_mut, clippy::unused_unit)]
+        unsafe impl<$($E: EventSet),*> EventSet for ($($E,)*) {
+            type Indices = ($($E::Indices,)*);
+
+            fn new_indices(world: &mut World) -> Self::Indices {
+                (
+                    $(
+                        $E::new_indices(world),
+                    )*
+                )
+            }
+
+            #[inline]
+            fn find_index<F: Event>(($($e,)*): &Self::Indices) -> Option<u32> {
+                $(
+                    if let Some(id) = $E::find_index::<F>($e) {
+                        return Some(id);
+                    }
+                )*
+
+                None
+            }
+
+            #[inline]
+            fn find_index_in<F: Event>(
+                ($($e,)*): &Self::Indices,
+                world: UnsafeWorldCell,
+            ) -> Option<u32> {
+                $(
+                    if let Some(id) = $E::find_index_in::<F>($e, world) {
+                        return Some(id);
+                    }
+                )*
+
+                None
+            }
+
+            fn for_each_index<F: FnMut(bool, u32)>(($($e,)*): &Self::Indices, mut f: F) {
+                $(
+                    $E::for_each_index($e, &mut f);
+                )*
+            }
+        }
+    };
+}
+
+all_tuples!(impl_event_set_tuple, 0, 64, E, e);
+
+/// A tuple of [`GlobalEvent`]s which can be sent as a transaction with
+/// [`Sender::send_all`].
+pub trait GlobalEventTuple<'a> {
+    /// Adds every event of the tuple to the queue of `sender`, in order.
+    fn send_each<ES: EventSet>(self, sender: &Sender<'a, ES>);
+}
+
+macro_rules! impl_global_event_tuple {
+    ($(($E:ident, $e:ident)),*) => {
+        impl<'a, $($E: GlobalEvent + 'a),*> GlobalEventTuple<'a> for ($($E,)*) {
+            #[track_caller]
+            fn send_each<ES: EventSet>(self, sender: &Sender<'a, ES>) {
+                let ($($e,)*) = self;
+
+                $(
+                    sender.send($e);
+                )*
+            }
+        }
+    };
+}
+
+all_tuples!(impl_global_event_tuple, 1, 16, E, e);
+
+/// An [`EventSet`] containing every event that has been added to the world.
+///
+/// A [`Sender`] of `AnyEvents` can send any event, even if its type is not
+/// known when the handler is added. Whether the event exists is checked when
+/// it is sent, so sending panics if the event has not been added to the world
+/// beforehand, e.g. with [`World::add_global_event`]. Events can be combined
+/// with `AnyEvents` in a tuple, in which case the other events are added to
+/// the world along with the handler.
+///
+/// Since the events sent by the handler are not known, removing an event
+/// from the world does not remove handlers with a `Sender<AnyEvents>`.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::AnyEvents;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Tick;
+///
+/// #[derive(GlobalEvent)]
+/// struct PluginEvent(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_global_event::<PluginEvent>();
+///
+/// world.add_handler(|_: Receiver<Tick>, s: Sender<AnyEvents>| {
+///     s.send(PluginEvent(123));
+/// });
+///
+/// world.add_handler(|r: Receiver<PluginEvent>| println!("{}", r.event.0));
+///
+/// world.send(Tick); // Prints "123".
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum AnyEvents {}
+
+unsafe impl EventSet for AnyEvents {
+    type Indices = ();
+
+    fn new_indices(_world: &mut World) -> Self::Indices {}
+
+    fn find_index<F: Event>(_indices: &Self::Indices) -> Option<u32> {
+        None
+    }
+
+    fn find_index_in<F: Event>(_indices: &Self::Indices, world: UnsafeWorldCell) -> Option<u32> {
+        let type_id = TypeId::of::<F::This<'static>>();
+
+        if TypeId::of::<F::EventIdx>() == TypeId::of::<TargetedEventIdx>() {
+            let info = world.targeted_events().get_by_type_id(type_id)?;
+            Some(info.id().index().0)
+        } else {
+            let info = world.global_events().get_by_type_id(type_id)?;
+            Some(info.id().index().0)
+        }
+    }
+
+    fn for_each_index<F: FnMut(bool, u32)>(_indices: &Self::Indices, _f: F) {}
+}
+
+/// A [`TargetedEvent`] which adds component `C` on an entity when sent. If the
+/// entity already has the component, then the component is replaced.
+///
+/// Any handler which listens for `Insert<C>` will run before the component is
+/// inserted. `Insert<C>` has no effect if the target entity does not exist or
+/// the event is consumed before it finishes broadcasting.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(transparent)]
+pub struct Insert<C>(pub C);
+
+unsafe impl<C: Component> Event for Insert<C> {
+    type This<'a> = Insert<C>;
+
+    type EventIdx = TargetedEventIdx;
+
+    type Mutability = Mutable;
+
+    fn init(world: &mut World) -> EventKind {
+        EventKind::Insert {
+            component_idx: world.add_component::<C>().index(),
+        }
+    }
+}
+
+impl<C> Deref for Insert<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<C> DerefMut for Insert<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// A [`TargetedEvent`] which removes component `C` from an entity when sent.
+/// The component is dropped and cannot be recovered.
+///
+/// Any handler which listens for `Remove<C>` will run before the component is
+/// removed. `Remove<C>` has no effect if the target entity does not exist or
+/// the event is consumed before it finishes broadcasting.
+///
+/// This type behaves like a unit struct. Use `Remove::<C>` to instantiate the
+/// type.
+///
+/// # Examples
+///
+/// ```
+/// # use evenio::prelude::*;
+/// #
+/// # #[derive(Component)]
+/// # struct C;
+/// #
+/// # fn _f(world: &mut World, target: EntityId) {
+/// world.send_to(target, Remove::<C>);
+/// # }
+/// ```
+#[derive(Default)]
+pub enum Remove<C: ?Sized> {
+    // Don't use these variants directly. They are implementation details.
+    #[doc(hidden)]
+    __Ignore(crate::ignore::Ignore<C>),
+    #[doc(hidden)]
+    #[default]
+    __Value,
+}
+
+mod remove_value {
+    #[doc(hidden)]
+    pub use super::Remove::__Value as Remove;
+}
+
+pub use remove_value::*;
+
+impl<C: ?Sized> Copy for Remove<C> {}
+
+impl<C: ?Sized> Clone for Remove<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: ?Sized> fmt::Debug for Remove<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Remove").finish()
+    }
+}
+
+unsafe impl<C: Component> Event for Remove<C> {
+    type This<'a> = Remove<C>;
+
+    type EventIdx = TargetedEventIdx;
+
+    type Mutability = Mutable;
+
+    fn init(world: &mut World) -> EventKind {
+        EventKind::Remove {
+            component_idx: world.add_component::<C>().index(),
+        }
+    }
+}
+
+/// A [`TargetedEvent`] which removes component `C` from an entity when sent,
+/// and sends the removed component to the entity in a [`TakenComponent<C>`]
+/// event.
+///
+/// Unlike [`Remove<C>`], the component is not dropped, so its value can be
+/// moved elsewhere without cloning it first. Handlers of `Take<C>` run before
+/// the component is removed. `Take<C>` has no effect if the target entity does
+/// not exist, the entity does not have the component, or the event is
+/// consumed before it finishes broadcasting.
+///
+/// This type behaves like a unit struct. Use `Take::<C>` to instantiate the
+/// type.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{Take, TakenComponent};
+/// use evenio::prelude::*;
+///
+/// #[derive(Component, Debug)]
+/// struct Sword(u32);
+///
+/// #[derive(Component, Default, Debug)]
+/// struct Inventory(Vec<Sword>);
+///
+/// let mut world = World::new();
+///
+/// let player = world.spawn();
+/// world.insert(player, Inventory::default());
+///
+/// // Move the taken sword into the player's inventory.
+/// world.add_handler(
+///     move |r: ReceiverMut<TakenComponent<Sword>, ()>, mut inventories: Fetcher<&mut Inventory>| {
+///         let sword = EventMut::take(r.event).0;
+///         inventories.get_mut(player).unwrap().0.push(sword);
+///     },
+/// );
+///
+/// let chest = world.spawn();
+/// world.insert(chest, Sword(42));
+///
+/// world.send_to(chest, Take::<Sword>);
+///
+/// assert!(world.get::<Sword>(chest).is_none());
+/// assert_eq!(world.get::<Inventory>(player).unwrap().0[0].0, 42);
+/// ```
+#[derive(Default)]
+pub enum Take<C: ?Sized> {
+    // Don't use these variants directly. They are implementation details.
+    #[doc(hidden)]
+    __Ignore(crate::ignore::Ignore<C>),
+    #[doc(hidden)]
+    #[default]
+    __Value,
+}
+
+mod take_value {
+    #[doc(hidden)]
+    pub use super::Take::__Value as Take;
+}
+
+pub use take_value::*;
+
+impl<C: ?Sized> Copy for Take<C> {}
+
+impl<C: ?Sized> Clone for Take<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: ?Sized> fmt::Debug for Take<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Take").finish()
+    }
+}
+
+unsafe impl<C: Component> Event for Take<C> {
+    type This<'a> = Take<C>;
+
+    type EventIdx = TargetedEventIdx;
+
+    type Mutability = Mutable;
+
+    fn init(world: &mut World) -> EventKind {
+        EventKind::Take {
+            component_idx: world.add_component::<C>().index(),
+            taken_idx: world.add_targeted_event::<TakenComponent<C>>().index(),
+        }
+    }
+}
+
+/// A [`TargetedEvent`] sent to an entity after [`Take<C>`] removed its
+/// component `C`. Contains the removed component.
+///
+/// The component is dropped after the event is broadcast unless a handler
+/// takes ownership of the event with [`EventMut::take`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(transparent)]
+pub struct TakenComponent<C>(pub C);
+
+unsafe impl<C: Component> Event for TakenComponent<C> {
+    type This<'a> = TakenComponent<C>;
+
+    type EventIdx = TargetedEventIdx;
+
+    type Mutability = Mutable;
+}
+
+impl<C> Deref for TakenComponent<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<C> DerefMut for TakenComponent<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// A [`GlobalEvent`] which signals the creation of an entity. Contains the
+/// [`EntityId`] of the new entity, which may or may not exist by the time this
+/// event is observed.
+///
+/// Note that the event by itself cannot be used to spawn new entities. Use
+/// [`World::spawn`] or [`Sender::spawn`] instead.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(transparent)]
+pub struct Spawn(pub EntityId);
+
+unsafe impl Event for Spawn {
+    type This<'a> = Self;
+
+    type EventIdx = GlobalEventIdx;
+
+    type Mutability = Immutable;
+
+    fn init(_world: &mut World) -> EventKind {
+        EventKind::Spawn
+    }
+}
+
+/// A [`TargetedEvent`] which removes an entity from the [`World`] when sent.
+/// All components of the target entity are dropped.
+///
+/// Any handler which listens for `Despawn` will run before the entity is
+/// removed. `Despawn` has no effect if the target entity does not exist or the
+/// event is consumed before it finishes broadcasting. See
+/// [`World::add_despawn_veto`] for letting entities veto their despawning.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// let mut world = World::new();
+///
+/// let id = world.spawn();
+///
+/// assert!(world.entities().contains(id));
+///
+/// world.add_handler(|r: Receiver<Despawn, EntityId>| {
+///     println!("{:?} is about to despawn!", r.query);
+/// });
+///
+/// world.send_to(id, Despawn);
+///
+/// assert!(!world.entities().contains(id));
+/// ```
+///
+/// The sender may give a [`DespawnReason`], which handlers can use to tell
+/// apart different causes of despawning.
+///
+/// ```
+/// use evenio::event::DespawnReason;
+/// use evenio::prelude::*;
+///
+/// const KILLED: DespawnReason = DespawnReason("killed");
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Despawn, EntityId>| {
+///     if r.event.reason == Some(KILLED) {
+///         println!("{:?} was killed", r.query);
+///     }
+/// });
+///
+/// let id = world.spawn();
+/// world.send_to(id, Despawn::because(KILLED));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Despawn {
+    /// Why the entity is despawned, if the sender gave a reason.
+    pub reason: Option<DespawnReason>,
+}
+
+/// A [`Despawn`] event without a reason.
+#[allow(non_upper_case_globals)]
+pub const Despawn: Despawn = Despawn { reason: None };
+
+impl Despawn {
+    /// Returns a [`Despawn`] event with the given reason.
+    pub const fn because(reason: DespawnReason) -> Self {
+        Self {
+            reason: Some(reason),
+        }
+    }
+}
+
+/// The reason given for a [`Despawn`].
+///
+/// Applications define their own reasons as constants. Reasons compare equal
+/// if their names are equal.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DespawnReason(pub &'static str);
+
+impl DespawnReason {
+    /// The reason for the despawns caused by [`World::remove_component`].
+    pub const COMPONENT_REMOVED: Self = Self("component removed");
+}
+
+unsafe impl Event for Despawn {
+    type This<'a> = Despawn;
+
+    type EventIdx = TargetedEventIdx;
+
+    type Mutability = Mutable;
+
+    fn init(_world: &mut World) -> EventKind {
+        EventKind::Despawn
+    }
+}
+
+/// A [`GlobalEvent`] sent in place of an event which exceeded the maximum
+/// event depth of the world. The exceeding event is dropped without being
+/// broadcast, which ends the chain of events that led to it.
+///
+/// See [`World::set_max_event_depth`] for more information.
+#[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DepthLimitExceeded {
+    /// The ID of the dropped event.
+    pub event: EventId,
+    /// The target of the dropped event if it is a targeted event.
+    pub target: Option<EntityId>,
+    /// The depth of the dropped event.
+    pub depth: u32,
+}
+
+/// A [`GlobalEvent`] sent in place of a targeted event whose target entity
+/// doesn't exist, if the event's [`DeadTargetPolicy`] is
+/// [`DeadTargetPolicy::DeadLetter`]. The targeted event is dropped without
+/// being broadcast.
+///
+/// See [`World::set_dead_target_policy`] for more information.
+#[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DeadLetter {
+    /// The ID of the dropped event.
+    pub event: TargetedEventId,
+    /// The target of the dropped event.
+    pub target: EntityId,
+}
+
+/// A [`GlobalEvent`] broadcast once all events sent by a call to
+/// [`World::send`] or a similar method have been broadcast, including
+/// structural changes such as [`Insert`] and [`Despawn`] and the events sent
+/// by their handlers.
+///
+/// Events sent by the handlers of `BroadcastEnded` are broadcast before the
+/// call returns, but do not cause another `BroadcastEnded` to be sent. This
+/// makes it a reliable point for cleanup and synchronization.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::BroadcastEnded;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Chain(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Chain>, s: Sender<Chain>| {
+///     println!("chain {}", r.event.0);
+///     if r.event.0 > 0 {
+///         s.send(Chain(r.event.0 - 1));
+///     }
+/// });
+///
+/// world.add_handler(|_: Receiver<BroadcastEnded>| println!("done"));
+///
+/// world.send(Chain(2));
+/// ```
+///
+/// Output:
+///
+/// ```txt
+/// chain 2
+/// chain 1
+/// chain 0
+/// done
+/// ```
+///
+/// [`World::send`]: crate::world::World::send
+#[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct BroadcastEnded;
+
+/// A [`GlobalEvent`] sent when a handler takes an event with
+/// [`EventMut::take_and_notify`]. The handlers which would have received the
+/// event after the taking handler did not run.
+#[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Taken {
+    /// The ID of the taken event.
+    pub event: EventId,
+    /// The target of the taken event if it is a targeted event.
+    pub target: Option<EntityId>,
+    /// The handler which took the event.
+    pub handler: HandlerId,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use rand::prelude::*;
+
+    use crate::event::{Either, Priority, ReceiverEither};
+    use crate::prelude::*;
+
+    #[test]
+    fn change_entity_during_broadcast() {
+        let mut world = World::new();
+
+        #[derive(TargetedEvent)]
+        struct E;
+
+        #[derive(Component)]
+        struct C(String);
+
+        world.add_handler(|r: Receiver<E, EntityId>, s: Sender<Remove<C>>| {
+            s.remove::<C>(r.query);
+        });
+
+        world.add_handler(|r: Receiver<E, &mut C>| {
+            r.query.0.push_str("123");
+        });
+
+        let e = world.spawn();
+        world.insert(e, C("abc".into()));
+
+        world.send_to(e, E);
+    }
+
+    #[test]
+    fn event_order() {
+        #[derive(GlobalEvent)]
+        struct A;
+        #[derive(GlobalEvent, Debug)]
+        struct B(i32);
+        #[derive(GlobalEvent, Debug)]
+        struct C(i32);
+
+        #[derive(Component)]
+        struct Result(Vec<i32>);
+
+        fn get_a_send_b(_: Receiver<A>, sender: Sender<B>) {
+            sender.send(B(0));
+            sender.send(B(3));
+        }
+
+        fn get_b_send_c(r: Receiver<B>, sender: Sender<C>, mut res: Single<&mut Result>) {
+            res.0.push(r.event.0);
+            sender.send(C(r.event.0 + 1));
+            sender.send(C(r.event.0 + 2));
+        }
+
+        fn get_c(r: Receiver<C>, mut res: Single<&mut Result>) {
+            res.0.push(r.event.0);
+        }
+
+        let mut world = World::new();
+
+        let res = world.spawn();
+        world.insert(res, Result(vec![]));
+
+        world.add_handler(get_a_send_b);
+        world.add_handler(get_b_send_c);
+        world.add_handler(get_c);
+
+        world.send(A);
+
+        assert_eq!(
+            world.get::<Result>(res).unwrap().0.as_slice(),
+            &[0, 1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn deferred_event_order() {
+        #[derive(GlobalEvent)]
+        struct A;
+        #[derive(GlobalEvent)]
+        struct B(i32);
+        #[derive(TargetedEvent)]
+        struct C(i32);
+
+        #[derive(Component)]
+        struct Result(Vec<i32>);
+
+        fn get_a_send_b(_: Receiver<A>, sender: Sender<B>) {
+            sender.send_deferred(B(0));
+            sender.send(B(10));
+            sender.send_deferred(B(20));
+        }
+
+        fn get_b_send_c(r: Receiver<B>, sender: Sender<C>, res: Single<(EntityId, &mut Result)>) {
+            let (id, res) = Single::into_inner(res);
+            res.0.push(r.event.0);
+            sender.send_to_deferred(id, C(r.event.0 + 1));
+            sender.send_to(id, C(r.event.0 + 2));
+        }
+
+        fn get_c(r: Receiver<C, &mut Result>) {
+            r.query.0.push(r.event.0);
+        }
+
+        let mut world = World::new();
+
+        let res = world.spawn();
+        world.insert(res, Result(vec![]));
+
+        world.add_handler(get_a_send_b);
+        world.add_handler(get_b_send_c);
+        world.add_handler(get_c);
+
+        world.send(A);
+
+        assert_eq!(
+            world.get::<Result>(res).unwrap().0.as_slice(),
+            &[10, 12, 0, 2, 20, 22, 11, 1, 21]
+        );
+
+        world.get_mut::<Result>(res).unwrap().0.clear();
+        world.send_deferred(B(30));
+
+        assert_eq!(
+            world.get::<Result>(res).unwrap().0.as_slice(),
+            &[30, 32, 31]
+        );
+    }
+
+    #[test]
+    fn deferred_event_priority() {
+        #[derive(GlobalEvent)]
+        struct A;
+        #[derive(GlobalEvent)]
+        struct B(i32);
+
+        #[derive(Component)]
+        struct Result(Vec<i32>);
+
+        let mut world = World::new();
+
+        let res = world.spawn();
+        world.insert(res, Result(vec![]));
+
+        world.add_handler(|_: Receiver<A>, sender: Sender<B>| {
+            sender.send_prioritized(Priority::LOW, B(0));
+            sender.send_deferred(B(10));
+            sender.send_prioritized(Priority::HIGH, B(20));
+            sender.send(B(30));
+        });
+
+        world.add_handler(
+            |r: Receiver<B>, sender: Sender<B>, res: Single<&mut Result>| {
+                Single::into_inner(res).0.push(r.event.0);
+
+                // Preempts the lower priority events deferred earlier.
+                if r.event.0 == 20 {
+                    sender.send_prioritized(Priority::HIGH, B(21));
+                }
+            },
+        );
+
+        world.send(A);
+
+        assert_eq!(
+            world.get::<Result>(res).unwrap().0.as_slice(),
+            &[30, 20, 21, 10, 0]
+        );
+    }
+
+    #[test]
+    fn max_event_depth() {
+        use alloc::rc::Rc;
+        use core::cell::RefCell;
+
+        use crate::event::{DepthLimitExceeded, EventId};
+
+        #[derive(GlobalEvent)]
+        struct A(u32, Rc<()>);
+
+        #[derive(GlobalEvent)]
+        struct B;
+
+        let mut world = World::new();
+
+        world.set_max_event_depth(Some(10));
+
+        world.add_handler(|r: Receiver<A>, s: Sender<A>| {
+            s.send(A(r.event.0 + 1, r.event.1.clone()))
+        });
+
+        // Deferred events start over at a depth of zero.
+        world.add_handler(|_: Receiver<B>, s: Sender<A>| {
+            s.send_prioritized(Priority::NORMAL, A(0, Rc::new(())))
+        });
+
+        let exceeded = Rc::new(RefCell::new(vec![]));
+        let exceeded_2 = exceeded.clone();
+
+        world.add_handler(move |r: Receiver<DepthLimitExceeded>| {
+            exceeded_2.borrow_mut().push(*r.event);
+        });
+
+        let a = world.add_global_event::<A>();
+        let rc = Rc::new(());
+
+        world.send(A(0, rc.clone()));
+        world.send(B);
+
+        // The dropped event was dropped.
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let expected = DepthLimitExceeded {
+            event: EventId::Global(a),
+            target: None,
+            depth: 11,
+        };
+
+        assert_eq!(exceeded.borrow().as_slice(), &[expected, expected]);
+    }
+
+    #[test]
+    fn send_to_many() {
+        use alloc::rc::Rc;
+
+        #[derive(GlobalEvent)]
+        struct A(Vec<EntityId>, Rc<()>);
+
+        #[derive(TargetedEvent, Clone)]
+        struct B(#[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct Count(usize);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, s: Sender<B>| {
+            s.send_to_many(r.event.0.iter().copied(), B(r.event.1.clone()));
+        });
+
+        world.add_handler(|r: Receiver<B, &mut Count>| {
+            r.query.0 += 1;
+        });
+
+        let entities: Vec<_> = (0..5)
+            .map(|_| {
+                let e = world.spawn();
+                world.insert(e, Count(0));
+                e
+            })
+            .collect();
+
+        let rc = Rc::new(());
+
+        world.send(A(vec![], rc.clone()));
+        world.send(A(
+            vec![entities[0], entities[1], entities[1], entities[4]],
+            rc.clone(),
+        ));
+
+        // All copies of the event have been dropped.
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let counts: Vec<_> = entities
+            .iter()
+            .map(|&e| world.get::<Count>(e).unwrap().0)
+            .collect();
+
+        assert_eq!(counts, [1, 2, 0, 0, 1]);
+    }
+
+    #[test]
+    fn send_to_all() {
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(TargetedEvent, Clone)]
+        struct B;
+
+        #[derive(Component)]
+        struct Count(usize);
+
+        #[derive(Component)]
+        struct Marker;
+
+        let mut world = World::new();
+
+        world.add_handler(|_: Receiver<A>, f: Fetcher<With<&Count>>, s: Sender<B>| {
+            s.send_to_all(&f, B);
+        });
+
+        world.add_handler(|r: Receiver<B, &mut Count>| {
+            r.query.0 += 1;
+        });
+
+        // Spread the entities across archetypes.
+        let entities: Vec<_> = (0..4)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Count(0));
+                if i % 2 == 0 {
+                    world.insert(e, Marker);
+                }
+                e
+            })
+            .collect();
+
+        let other = world.spawn();
+
+        world.send(A);
+        world.send(A);
+
+        for e in entities {
+            assert_eq!(world.get::<Count>(e).unwrap().0, 2);
+        }
+
+        assert!(world.get::<Count>(other).is_none());
+    }
+
+    #[test]
+    fn consume_event() {
+        use alloc::rc::Rc;
+
+        #[derive(GlobalEvent)]
+        struct A(u32, #[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C(0));
+
+        world.add_handler(
+            (|r: ReceiverMut<A>| {
+                if r.event.0 % 2 == 0 {
+                    EventMut::consume(r.event);
+                }
+            })
+            .high(),
+        );
+
+        world.add_handler(|r: Receiver<A>, mut c: Single<&mut C>| c.0 += r.event.0);
+
+        // Consuming an `Insert` keeps the component from being replaced.
+        world.add_handler(|r: ReceiverMut<Insert<C>, ()>| {
+            if r.event.0 .0 == 0 {
+                EventMut::consume(r.event);
+            }
+        });
 
-        unsafe {
-            for i in 0..len {
-                ptr::write(dst.as_ptr().add(i), f(i));
+        let rc = Rc::new(());
+
+        for i in 0..5 {
+            world.send(A(i, rc.clone()));
+        }
+
+        assert_eq!(Rc::strong_count(&rc), 1);
+        assert_eq!(world.get::<C>(e).unwrap().0, 4);
+
+        world.insert(e, C(0));
+        assert_eq!(world.get::<C>(e).unwrap().0, 4);
+    }
+
+    #[test]
+    fn despawn_many() {
+        let mut world = World::new();
+
+        #[derive(GlobalEvent)]
+        struct E;
+
+        #[derive(Component)]
+        struct C(#[allow(unused)] i32);
+
+        let mut entities = vec![];
+
+        let n = 50;
+
+        for i in 0..n {
+            let e = world.spawn();
+            world.insert(e, C(i));
+            entities.push(e);
+        }
+
+        entities.shuffle(&mut rand::thread_rng());
+
+        world.add_handler(move |_: Receiver<E>, s: Sender<Despawn>| {
+            for &e in &entities {
+                s.despawn(e);
             }
+        });
 
-            let result = slice::from_raw_parts_mut(dst.as_ptr(), len);
-            debug_assert_eq!(Layout::for_value(result), layout);
-            result
+        world.send(E);
+
+        assert_eq!(world.entities().len(), 0);
+    }
+
+    #[test]
+    fn send_borrowed() {
+        let mut buf = [1, 2, 3];
+
+        #[derive(GlobalEvent, Debug)]
+        struct A<'a>(&'a mut [i32]);
+
+        impl Drop for A<'_> {
+            fn drop(&mut self) {
+                for item in self.0.iter_mut() {
+                    *item *= 2;
+                    println!("{item}");
+                }
+            }
         }
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>| println!("{r:?}"));
+
+        world.send(A(&mut buf));
     }
 
-    /// Copies the given string into the bump allocator and returns an exclusive
-    /// reference to it.
-    #[inline]
-    pub fn alloc_str(&self, str: &str) -> &'a mut str {
-        unsafe {
-            let ptr = self
-                .alloc_layout(Layout::from_size_align_unchecked(str.len(), 1))
-                .as_ptr();
+    #[test]
+    #[should_panic]
+    fn global_event_not_in_event_set() {
+        let mut world = World::new();
 
-            ptr::copy_nonoverlapping(str.as_ptr(), ptr, str.len());
-            let slice = slice::from_raw_parts_mut(ptr, str.len());
-            str::from_utf8_unchecked_mut(slice)
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(GlobalEvent)]
+        struct B;
+
+        world.add_handler(|_: Receiver<A>, s: Sender<B>| {
+            s.send(A);
+        });
+
+        world.send(A);
+    }
+
+    #[test]
+    #[should_panic]
+    fn targeted_event_not_in_event_set() {
+        let mut world = World::new();
+
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(TargetedEvent)]
+        struct B;
+
+        world.add_handler(|_: Receiver<A>, s: Sender<A>| {
+            s.send_to(EntityId::NULL, B);
+        });
+
+        world.send(A);
+    }
+
+    #[test]
+    fn send_event_from_sender_with_lifetime() {
+        let mut world = World::new();
+
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(GlobalEvent)]
+        struct B<'a> {
+            slice: &'a [i32],
+            string: &'a str,
+            array: &'a [u64; 4],
+        }
+
+        fn get_a_send_b(_: Receiver<A>, s: Sender<B>) {
+            s.send(B {
+                slice: s.alloc_slice(5, |i| i as i32 + 1),
+                string: s.alloc_str("pineapple"),
+                array: s.alloc([10, 20, 30, 40]),
+            });
+        }
+
+        fn get_b(r: Receiver<B>) {
+            assert_eq!(r.event.slice, &[1, 2, 3, 4, 5]);
+            assert_eq!(r.event.string, "pineapple");
+            assert_eq!(r.event.array, &[10, 20, 30, 40]);
         }
+
+        world.add_handler(get_a_send_b);
+        world.add_handler(get_b);
+        world.send(A);
     }
 
-    /// Allocate space for an object in the bump allocator with the given
-    /// [`Layout`].
-    ///
-    /// The returned pointer points to uninitialized memory.
-    #[inline]
-    pub fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
-        unsafe { self.world.alloc_layout(layout) }
+    #[test]
+    fn more_than_one_sender() {
+        let mut world = World::new();
+
+        #[derive(GlobalEvent)]
+        struct A(#[allow(dead_code)] u32);
+
+        #[derive(GlobalEvent)]
+        struct B(#[allow(dead_code)] u32);
+
+        fn send_b_x2(_: Receiver<A>, s1: Sender<B>, s2: Sender<B>) {
+            s1.send(B(123));
+            s2.send(B(456));
+        }
+
+        world.add_handler(send_b_x2);
+
+        world.send(A(123));
     }
-}
 
-unsafe impl<T: EventSet> HandlerParam for Sender<'_, T> {
-    type State = T::Indices;
+    #[allow(unused, clippy::type_complexity)]
+    mod derive_event {
+        use core::marker::PhantomData;
 
-    type This<'a> = Sender<'a, T>;
+        use super::*;
 
-    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
-        config.set_event_queue_access(Access::ReadWrite);
+        #[derive(GlobalEvent)]
+        struct StructWithLifetime_<'a> {
+            _ignore: &'a (),
+        }
 
-        let state = T::new_indices(world);
+        #[derive(TargetedEvent)]
+        struct StructWithLifetime<'a> {
+            _ignore: &'a (),
+        }
 
-        T::for_each_index(&state, |is_targeted, idx| {
-            if is_targeted {
-                config.insert_sent_targeted_event(TargetedEventIdx(idx));
-            } else {
-                config.insert_sent_global_event(GlobalEventIdx(idx));
-            }
+        #[derive(GlobalEvent)]
+        struct StructWithGenericType_<T>(T);
+
+        #[derive(TargetedEvent)]
+        struct StructWithGenericType<T>(T);
+
+        #[derive(GlobalEvent)]
+        struct StructWithBoth_<'a, T>(PhantomData<(fn() -> T, &'a ())>);
+
+        #[derive(TargetedEvent)]
+        struct StructWithBoth<'a, T>(PhantomData<(fn() -> T, &'a ())>);
+    }
+
+    #[test]
+    fn replace_event() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            (|mut r: ReceiverMut<A>| {
+                let doubled = A(r.event.0 * 2);
+                let old = EventMut::replace(&mut r.event, doubled);
+                assert_eq!(old.0, 1);
+            })
+            .high(),
+        );
+        world.add_handler(|r: Receiver<A>| assert_eq!(r.event.0, 2));
+
+        world.send(A(1));
+
+        // The replaced component is inserted.
+        world.add_handler(|mut r: ReceiverMut<Insert<C>, ()>| {
+            EventMut::replace(&mut r.event, Insert(C(100)));
         });
 
-        Ok(state)
+        let e = world.spawn();
+        world.insert(e, C(1));
+
+        assert_eq!(world.get::<C>(e), Some(&C(100)));
     }
 
-    unsafe fn get<'a>(
-        state: &'a mut Self::State,
-        _info: &'a HandlerInfo,
-        _event_ptr: EventPtr<'a>,
-        _target_location: EntityLocation,
-        world: UnsafeWorldCell<'a>,
-    ) -> Self::This<'a> {
-        Sender { state, world }
+    #[test]
+    fn receiver_either() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(GlobalEvent)]
+        struct B(&'static str);
+
+        #[derive(GlobalEvent)]
+        struct C;
+
+        #[derive(TargetedEvent)]
+        struct D;
+
+        #[derive(TargetedEvent)]
+        struct F;
+
+        #[derive(Component)]
+        struct Health(u32);
+
+        let mut world = World::new();
+
+        let log = Rc::new(RefCell::new(vec![]));
+        let l = log.clone();
+
+        let handler = world.add_handler(move |r: ReceiverEither<A, Either<B, C>>| {
+            l.borrow_mut().push(match r.event {
+                Either::Left(a) => format!("A({})", a.0),
+                Either::Right(Either::Left(b)) => format!("B({})", b.0),
+                Either::Right(Either::Right(C)) => "C".into(),
+            })
+        });
+
+        assert_eq!(
+            world
+                .handlers()
+                .get(handler)
+                .unwrap()
+                .received_events()
+                .len(),
+            3
+        );
+
+        world.send(C);
+        world.send(A(1));
+        world.send(B("b"));
+
+        assert_eq!(*log.borrow(), ["C", "A(1)", "B(b)"]);
+
+        let l = log.clone();
+        world.add_handler(move |r: ReceiverEither<D, F, &Health>| {
+            l.borrow_mut().push(match r.event {
+                Either::Left(D) => format!("D {}", r.query.0),
+                Either::Right(F) => format!("F {}", r.query.0),
+            })
+        });
+
+        log.borrow_mut().clear();
+
+        let e1 = world.spawn();
+        world.insert(e1, Health(5));
+        let e2 = world.spawn();
+
+        world.send_to(e1, F);
+        world.send_to(e2, D);
+        world.send_to(e1, D);
+
+        assert_eq!(*log.borrow(), ["F 5", "D 5"]);
+
+        // Removing any of the events removes the handler.
+        let b = world.add_global_event::<B>();
+        world.remove_global_event(b);
+
+        assert!(!world.handlers().contains(handler));
     }
 
-    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+    #[test]
+    fn sender_any_events() {
+        use crate::event::AnyEvents;
+
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(GlobalEvent)]
+        struct B(u32);
 
-    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
-}
+        #[derive(TargetedEvent)]
+        struct C(u32);
 
-impl<T: EventSet> fmt::Debug for Sender<'_, T>
-where
-    T::Indices: fmt::Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Sender")
-            .field("state", &self.state)
-            .field("world", &self.world)
-            .finish()
-    }
-}
+        #[derive(GlobalEvent)]
+        struct Unregistered;
 
-/// A set of [`Event`] types.
-///
-/// This trait is implemented for all events and tuples of events, so `E1`,
-/// `()`, `(E1,)`, `(E1, E2)` etc. are all event sets.
-///
-/// # Safety
-///
-/// This trait is marked `unsafe` because unsafe code relies on implementations
-/// being correct. It is not recommended to implement this trait yourself.
-pub unsafe trait EventSet {
-    /// The set of event indices.
-    type Indices: 'static;
+        #[derive(Component)]
+        struct Sum(u32);
 
-    /// Create a new set of events.
-    fn new_indices(world: &mut World) -> Self::Indices;
+        let mut world = World::new();
 
-    /// Find the event `F` in the set of events. Returns the event index.
-    fn find_index<F: Event>(indices: &Self::Indices) -> Option<u32>;
+        world.add_global_event::<B>();
+        world.add_targeted_event::<C>();
 
-    /// Run a function on every element of the set, passing in the event index
-    /// and a boolean indicating if the event is targeted or not.
-    fn for_each_index<F: FnMut(bool, u32)>(indices: &Self::Indices, f: F);
-}
+        let e = world.spawn();
+        world.insert(e, Sum(0));
 
-unsafe impl<E: Event> EventSet for E {
-    type Indices = u32;
+        world.add_handler(move |_: Receiver<A>, s: Sender<AnyEvents>| {
+            s.send(B(1));
+            s.send_to(e, C(10));
+        });
 
-    fn new_indices(world: &mut World) -> Self::Indices {
-        let desc = EventDescriptor::new::<E>(world);
+        world.add_handler(move |r: Receiver<B>, mut f: Fetcher<&mut Sum>| {
+            f.get_mut(e).unwrap().0 += r.event.0;
+        });
 
-        if TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>() {
-            unsafe { world.add_targeted_event_with_descriptor(desc) }
-                .index()
-                .0
-        } else {
-            unsafe { world.add_global_event_with_descriptor(desc) }
-                .index()
-                .0
-        }
-    }
+        world.add_handler(|r: Receiver<C, &mut Sum>| r.query.0 += r.event.0);
 
-    #[inline]
-    fn find_index<F: Event>(index: &Self::Indices) -> Option<u32> {
-        (TypeId::of::<E::This<'static>>() == TypeId::of::<F::This<'static>>()).then_some(*index)
-    }
+        world.send(A);
 
-    fn for_each_index<F: FnMut(bool, u32)>(index: &Self::Indices, mut f: F) {
-        f(
-            TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>(),
-            *index,
-        )
-    }
-}
+        assert_eq!(world.get::<Sum>(e).unwrap().0, 11);
 
-macro_rules! impl_event_set_tuple {
-    ($(($E:ident, $e:ident)),*) => {
-        #[allow(unused_variables, unused_mut, clippy::unused_unit)]
-        unsafe impl<$($E: EventSet),*> EventSet for ($($E,)*) {
-            type Indices = ($($E::Indices,)*);
+        world.add_handler(|_: Receiver<B>, s: Sender<AnyEvents>| s.send(Unregistered));
 
-            fn new_indices(world: &mut World) -> Self::Indices {
-                (
-                    $(
-                        $E::new_indices(world),
-                    )*
-                )
-            }
+        let res = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| world.send(B(0))));
+        assert!(res.is_err());
+    }
 
-            #[inline]
-            fn find_index<F: Event>(($($e,)*): &Self::Indices) -> Option<u32> {
-                $(
-                    if let Some(id) = $E::find_index::<F>($e) {
-                        return Some(id);
-                    }
-                )*
+    #[test]
+    fn receiver_targets() {
+        use crate::event::{MultiTargetEvent, ReceiverTargets};
+        use crate::fetch::GetManyMutError;
 
-                None
-            }
+        #[derive(GlobalEvent)]
+        struct Transfer {
+            from: EntityId,
+            to: EntityId,
+            amount: u32,
+        }
 
-            fn for_each_index<F: FnMut(bool, u32)>(($($e,)*): &Self::Indices, mut f: F) {
-                $(
-                    $E::for_each_index($e, &mut f);
-                )*
+        impl MultiTargetEvent<2> for Transfer {
+            fn targets(event: &Self) -> [EntityId; 2] {
+                [event.from, event.to]
             }
         }
-    };
-}
 
-all_tuples!(impl_event_set_tuple, 0, 64, E, e);
+        #[derive(Component)]
+        struct Gold(u32);
 
-/// A [`TargetedEvent`] which adds component `C` on an entity when sent. If the
-/// entity already has the component, then the component is replaced.
-///
-/// Any handler which listens for `Insert<C>` will run before the component is
-/// inserted. `Insert<C>` has no effect if the target entity does not exist or
-/// the event is consumed before it finishes broadcasting.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[repr(transparent)]
-pub struct Insert<C>(pub C);
+        #[derive(GlobalEvent)]
+        struct Failed(GetManyMutError);
 
-unsafe impl<C: Component> Event for Insert<C> {
-    type This<'a> = Insert<C>;
+        let mut world = World::new();
 
-    type EventIdx = TargetedEventIdx;
+        world.add_handler(
+            |r: ReceiverTargets<Transfer, &mut Gold, 2>, s: Sender<Failed>| match r.query {
+                Ok([from, to]) => {
+                    from.0 -= r.event.amount;
+                    to.0 += r.event.amount;
+                }
+                Err(e) => s.send(Failed(e)),
+            },
+        );
 
-    type Mutability = Mutable;
+        let errors = Rc::new(RefCell::new(vec![]));
+        let e = errors.clone();
+        world.add_handler(move |r: Receiver<Failed>| e.borrow_mut().push(r.event.0));
 
-    fn init(world: &mut World) -> EventKind {
-        EventKind::Insert {
-            component_idx: world.add_component::<C>().index(),
-        }
-    }
-}
+        let a = world.spawn();
+        world.insert(a, Gold(10));
+        let b = world.spawn();
+        world.insert(b, Gold(0));
+        let c = world.spawn();
 
-impl<C> Deref for Insert<C> {
-    type Target = C;
+        world.send(Transfer {
+            from: a,
+            to: b,
+            amount: 3,
+        });
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
+        assert_eq!(world.get::<Gold>(a).unwrap().0, 7);
+        assert_eq!(world.get::<Gold>(b).unwrap().0, 3);
 
-impl<C> DerefMut for Insert<C> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        world.send(Transfer {
+            from: a,
+            to: a,
+            amount: 1,
+        });
+        world.send(Transfer {
+            from: a,
+            to: c,
+            amount: 1,
+        });
+
+        assert_eq!(
+            *errors.borrow(),
+            [
+                GetManyMutError::AliasedMutability,
+                GetManyMutError::QueryDoesNotMatch
+            ]
+        );
+        assert_eq!(world.get::<Gold>(a).unwrap().0, 7);
     }
-}
 
-/// A [`TargetedEvent`] which removes component `C` from an entity when sent.
-/// The component is dropped and cannot be recovered.
-///
-/// Any handler which listens for `Remove<C>` will run before the component is
-/// removed. `Remove<C>` has no effect if the target entity does not exist or
-/// the event is consumed before it finishes broadcasting.
-///
-/// This type behaves like a unit struct. Use `Remove::<C>` to instantiate the
-/// type.
-///
-/// # Examples
-///
-/// ```
-/// # use evenio::prelude::*;
-/// #
-/// # #[derive(Component)]
-/// # struct C;
-/// #
-/// # fn _f(world: &mut World, target: EntityId) {
-/// world.send_to(target, Remove::<C>);
-/// # }
-/// ```
-#[derive(Default)]
-pub enum Remove<C: ?Sized> {
-    // Don't use these variants directly. They are implementation details.
-    #[doc(hidden)]
-    __Ignore(crate::ignore::Ignore<C>),
-    #[doc(hidden)]
-    #[default]
-    __Value,
-}
+    #[test]
+    fn defer_barrier() {
+        #[derive(GlobalEvent)]
+        struct Start(bool);
 
-mod remove_value {
-    #[doc(hidden)]
-    pub use super::Remove::__Value as Remove;
-}
+        #[derive(GlobalEvent)]
+        struct E(&'static str);
 
-pub use remove_value::*;
+        let mut world = World::new();
 
-impl<C: ?Sized> Copy for Remove<C> {}
+        world.add_handler(|r: Receiver<Start>, s: Sender<E>| {
+            s.send_deferred(E("a"));
+            if r.event.0 {
+                s.defer_barrier();
+            }
+            s.send_prioritized(Priority::HIGH, E("b"));
+        });
 
-impl<C: ?Sized> Clone for Remove<C> {
-    fn clone(&self) -> Self {
-        *self
-    }
-}
+        let log = Rc::new(RefCell::new(vec![]));
+        let l = log.clone();
 
-impl<C: ?Sized> fmt::Debug for Remove<C> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Remove").finish()
-    }
-}
+        world.add_handler(move |r: Receiver<E>, s: Sender<E>| {
+            l.borrow_mut().push(r.event.0);
 
-unsafe impl<C: Component> Event for Remove<C> {
-    type This<'a> = Remove<C>;
+            match r.event.0 {
+                "a" => s.send_deferred(E("a2")),
+                "b" => {
+                    s.send_deferred(E("b2"));
+                    s.defer_barrier();
+                    s.send_prioritized(Priority::HIGH, E("b3"));
+                }
+                _ => {}
+            }
+        });
 
-    type EventIdx = TargetedEventIdx;
+        world.send(Start(false));
+        assert_eq!(*log.borrow(), ["b", "a", "b2", "a2", "b3"]);
 
-    type Mutability = Mutable;
+        log.borrow_mut().clear();
 
-    fn init(world: &mut World) -> EventKind {
-        EventKind::Remove {
-            component_idx: world.add_component::<C>().index(),
-        }
+        world.send(Start(true));
+        assert_eq!(*log.borrow(), ["a", "a2", "b", "b2", "b3"]);
     }
-}
-
-/// A [`GlobalEvent`] which signals the creation of an entity. Contains the
-/// [`EntityId`] of the new entity, which may or may not exist by the time this
-/// event is observed.
-///
-/// Note that the event by itself cannot be used to spawn new entities. Use
-/// [`World::spawn`] or [`Sender::spawn`] instead.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[repr(transparent)]
-pub struct Spawn(pub EntityId);
 
-unsafe impl Event for Spawn {
-    type This<'a> = Self;
+    #[test]
+    fn event_info() {
+        use core::any::TypeId;
 
-    type EventIdx = GlobalEventIdx;
+        use crate::event::{EventId, EventInfo, EventKind};
 
-    type Mutability = Immutable;
+        #[derive(GlobalEvent)]
+        struct A;
 
-    fn init(_world: &mut World) -> EventKind {
-        EventKind::Spawn
-    }
-}
+        #[derive(TargetedEvent)]
+        struct B;
 
-/// A [`TargetedEvent`] which removes an entity from the [`World`] when sent.
-/// All components of the target entity are dropped.
-///
-/// Any handler which listens for `Despawn` will run before the entity is
-/// removed. `Despawn` has no effect if the target entity does not exist or the
-/// event is consumed before it finishes broadcasting.
-///
-/// # Examples
-///
-/// ```
-/// use evenio::prelude::*;
-///
-/// let mut world = World::new();
-///
-/// let id = world.spawn();
-///
-/// assert!(world.entities().contains(id));
-///
-/// world.add_handler(|r: Receiver<Despawn, EntityId>| {
-///     println!("{:?} is about to despawn!", r.query);
-/// });
-///
-/// world.send_to(id, Despawn);
-///
-/// assert!(!world.entities().contains(id));
-/// ```
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-pub struct Despawn;
+        #[derive(Component)]
+        struct C;
 
-unsafe impl Event for Despawn {
-    type This<'a> = Despawn;
+        let mut world = World::new();
 
-    type EventIdx = TargetedEventIdx;
+        let log = Rc::new(RefCell::new(vec![]));
 
-    type Mutability = Mutable;
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<A>, info: EventInfo| {
+            assert!(info.name().ends_with('A'));
+            l.borrow_mut().push((info.id(), info.kind(), info.target()));
+        });
 
-    fn init(_world: &mut World) -> EventKind {
-        EventKind::Despawn
-    }
-}
+        let l = log.clone();
+        world.add_handler(
+            move |_: ReceiverEither<B, Insert<C>, ()>, info: EventInfo| {
+                l.borrow_mut().push((info.id(), info.kind(), info.target()));
+            },
+        );
 
-#[cfg(test)]
-mod tests {
-    use rand::prelude::*;
+        let e = world.spawn();
+        world.send(A);
+        world.send_to(e, B);
+        world.insert(e, C);
 
-    use crate::prelude::*;
+        let a = world.global_events().get_by_type_id(TypeId::of::<A>());
+        let b = world.targeted_events().get_by_type_id(TypeId::of::<B>());
+        let c = world
+            .targeted_events()
+            .get_by_type_id(TypeId::of::<Insert<C>>());
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                (EventId::Global(a.unwrap().id()), EventKind::Normal, None),
+                (
+                    EventId::Targeted(b.unwrap().id()),
+                    EventKind::Normal,
+                    Some(e)
+                ),
+                (
+                    EventId::Targeted(c.unwrap().id()),
+                    c.unwrap().kind(),
+                    Some(e)
+                ),
+            ]
+        );
+        assert!(matches!(c.unwrap().kind(), EventKind::Insert { .. }));
+    }
 
     #[test]
-    fn change_entity_during_broadcast() {
-        let mut world = World::new();
+    fn untyped_receiver() {
+        use crate::event::{EventId, UntypedReceiver};
+
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(GlobalEvent)]
+        struct B;
 
         #[derive(TargetedEvent)]
-        struct E;
+        struct C(u32);
 
-        #[derive(Component)]
-        struct C(String);
+        let mut world = World::new();
 
-        world.add_handler(|r: Receiver<E, EntityId>, s: Sender<Remove<C>>| {
-            s.remove::<C>(r.query);
-        });
+        let a = world.add_global_event::<A>();
+        let b = world.add_global_event::<B>();
+        let c = world.add_targeted_event::<C>();
+
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let l = log.clone();
+        world.add_handler(
+            (move |r: UntypedReceiver| {
+                let value = if r.info.id() == EventId::Global(a) {
+                    // SAFETY: The event is `A`.
+                    unsafe { r.event.cast::<A>().as_ref().0 }
+                } else {
+                    0
+                };
+                l.borrow_mut().push((r.info.id(), value));
+            })
+            .receive_events([a, b]),
+        );
 
-        world.add_handler(|r: Receiver<E, &mut C>| {
-            r.query.0.push_str("123");
-        });
+        let l = log.clone();
+        world.add_handler(
+            (move |r: UntypedReceiver| {
+                // SAFETY: The event is `C`.
+                let value = unsafe { r.event.cast::<C>().as_ref().0 };
+                l.borrow_mut().push((r.info.id(), value));
+            })
+            .receive_events([c]),
+        );
 
         let e = world.spawn();
-        world.insert(e, C("abc".into()));
 
-        world.send_to(e, E);
+        world.send(B);
+        world.send(A(1));
+        world.send_to(e, C(2));
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                (EventId::Global(b), 0),
+                (EventId::Global(a), 1),
+                (EventId::Targeted(c), 2),
+            ]
+        );
+
+        let both: [EventId; 2] = [a.into(), c.into()];
+        assert!(world
+            .try_add_handler((|_: UntypedReceiver| {}).receive_events(both))
+            .is_err());
+        assert!(world
+            .try_add_handler((|_: Receiver<A>| {}).receive_events([b]))
+            .is_err());
     }
 
     #[test]
-    fn event_order() {
-        #[derive(GlobalEvent)]
-        struct A;
-        #[derive(GlobalEvent, Debug)]
-        struct B(i32);
-        #[derive(GlobalEvent, Debug)]
-        struct C(i32);
+    fn take_and_notify() {
+        use crate::event::{EventId, Taken};
 
-        #[derive(Component)]
-        struct Result(Vec<i32>);
+        #[derive(TargetedEvent)]
+        struct E(String);
 
-        fn get_a_send_b(_: Receiver<A>, sender: Sender<B>) {
-            sender.send(B(0));
-            sender.send(B(3));
-        }
+        let mut world = World::new();
 
-        fn get_b_send_c(r: Receiver<B>, sender: Sender<C>, mut res: Single<&mut Result>) {
-            res.0.push(r.event.0);
-            sender.send(C(r.event.0 + 1));
-            sender.send(C(r.event.0 + 2));
-        }
+        let log = Rc::new(RefCell::new(vec![]));
 
-        fn get_c(r: Receiver<C>, mut res: Single<&mut Result>) {
-            res.0.push(r.event.0);
-        }
+        let l = log.clone();
+        let taker = world.add_handler(move |r: ReceiverMut<E, ()>| {
+            if r.event.0 == "notify" {
+                l.borrow_mut().push(EventMut::take_and_notify(r.event).0);
+            } else if r.event.0 == "skip" {
+                l.borrow_mut().push(EventMut::take(r.event).0);
+            }
+        });
 
-        let mut world = World::new();
+        let l = log.clone();
+        world.add_handler(move |r: Receiver<E, ()>| l.borrow_mut().push(r.event.0.clone()));
 
-        let res = world.spawn();
-        world.insert(res, Result(vec![]));
+        let taken = Rc::new(RefCell::new(vec![]));
+        let t = taken.clone();
+        world.add_handler(move |r: Receiver<Taken>| t.borrow_mut().push(*r.event));
 
-        world.add_handler(get_a_send_b);
-        world.add_handler(get_b_send_c);
-        world.add_handler(get_c);
+        let e = world.spawn();
+        world.send_to(e, E("notify".into()));
+        world.send_to(e, E("skip".into()));
+        world.send_to(e, E("other".into()));
 
-        world.send(A);
+        assert_eq!(*log.borrow(), ["notify", "skip", "other"]);
 
+        let id = world.add_targeted_event::<E>();
         assert_eq!(
-            world.get::<Result>(res).unwrap().0.as_slice(),
-            &[0, 1, 2, 3, 4, 5]
+            *taken.borrow(),
+            [Taken {
+                event: EventId::Targeted(id),
+                target: Some(e),
+                handler: taker,
+            }]
         );
     }
 
     #[test]
-    fn despawn_many() {
-        let mut world = World::new();
+    fn broadcast_ended() {
+        use crate::event::BroadcastEnded;
 
         #[derive(GlobalEvent)]
-        struct E;
+        struct A;
 
         #[derive(Component)]
-        struct C(#[allow(unused)] i32);
+        struct C;
 
-        let mut entities = vec![];
+        let mut world = World::new();
 
-        let n = 50;
+        let log = Rc::new(RefCell::new(vec![]));
 
-        for i in 0..n {
-            let e = world.spawn();
-            world.insert(e, C(i));
-            entities.push(e);
-        }
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<A>, s: Sender<(Spawn, Insert<C>)>| {
+            l.borrow_mut().push("A");
+            let e = s.spawn();
+            s.insert(e, C);
+        });
 
-        entities.shuffle(&mut rand::thread_rng());
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<Insert<C>, ()>| l.borrow_mut().push("insert"));
 
-        world.add_handler(move |_: Receiver<E>, s: Sender<Despawn>| {
-            for &e in &entities {
-                s.despawn(e);
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<BroadcastEnded>, s: Sender<A>| {
+            l.borrow_mut().push("ended");
+            // Doesn't cause another `BroadcastEnded`.
+            if l.borrow().len() < 4 {
+                s.send(A);
             }
         });
 
-        world.send(E);
+        // Adding the handler broadcasts `AddHandler`.
+        assert_eq!(*log.borrow(), ["ended", "A", "insert"]);
 
-        assert_eq!(world.entities().len(), 0);
+        log.borrow_mut().clear();
+
+        world.send(A);
+        assert_eq!(*log.borrow(), ["A", "insert", "ended", "A", "insert"]);
     }
 
     #[test]
-    fn send_borrowed() {
-        let mut buf = [1, 2, 3];
+    fn take_component() {
+        use crate::event::{Take, TakenComponent};
 
-        #[derive(GlobalEvent, Debug)]
-        struct A<'a>(&'a mut [i32]);
+        #[derive(Component, PartialEq, Debug)]
+        struct C(Rc<()>);
 
-        impl Drop for A<'_> {
-            fn drop(&mut self) {
-                for item in self.0.iter_mut() {
-                    *item *= 2;
-                    println!("{item}");
-                }
-            }
-        }
+        #[derive(Component)]
+        struct D;
 
         let mut world = World::new();
 
-        world.add_handler(|r: Receiver<A>| println!("{r:?}"));
+        let taken = Rc::new(RefCell::new(vec![]));
 
-        world.send(A(&mut buf));
+        let t = taken.clone();
+        world.add_handler(move |r: ReceiverMut<TakenComponent<C>, EntityId>| {
+            t.borrow_mut().push((r.query, EventMut::take(r.event).0));
+        });
+
+        let rc = Rc::new(());
+
+        let e1 = world.spawn();
+        world.insert(e1, C(rc.clone()));
+        world.insert(e1, D);
+
+        let e2 = world.spawn();
+        world.insert(e2, C(rc.clone()));
+
+        world.send_to(e1, Take::<C>);
+        // Has no effect, and doesn't send `TakenComponent`.
+        world.send_to(e1, Take::<C>);
+
+        assert!(world.get::<C>(e1).is_none());
+        assert!(world.get::<D>(e1).is_some());
+        assert!(world.get::<C>(e2).is_some());
+        assert_eq!(Rc::strong_count(&rc), 3);
+        assert_eq!(*taken.borrow(), [(e1, C(rc.clone()))]);
+
+        // Dropped if no handler takes it.
+        world.remove_handler(world.handlers().iter().last().unwrap().id());
+        world.send_to(e2, Take::<C>);
+
+        assert!(world.get::<C>(e2).is_none());
+        assert_eq!(Rc::strong_count(&rc), 2);
     }
 
     #[test]
-    #[should_panic]
-    fn global_event_not_in_event_set() {
-        let mut world = World::new();
+    fn send_all() {
+        #[derive(GlobalEvent)]
+        struct Tick;
 
         #[derive(GlobalEvent)]
         struct A;
@@ -1275,110 +3575,254 @@ mod tests {
         #[derive(GlobalEvent)]
         struct B;
 
-        world.add_handler(|_: Receiver<A>, s: Sender<B>| {
-            s.send(A);
+        #[derive(TargetedEvent)]
+        struct C;
+
+        #[derive(GlobalEvent)]
+        struct Log(&'static str);
+
+        let mut world = World::new();
+
+        let log = Rc::new(RefCell::new(vec![]));
+
+        world.add_handler(|_: Receiver<Tick>, s: Sender<(A, B, C, Spawn, Log)>| {
+            s.send(Log("before"));
+            let e = s.spawn();
+            s.transaction(|s| {
+                s.send_all((A, B));
+                s.send_to(e, C);
+            });
+            s.send(Log("after"));
         });
 
-        world.send(A);
+        world.add_handler(|_: Receiver<A>, s: Sender<Log>| {
+            s.send(Log("from A 1"));
+            s.send(Log("from A 2"));
+        });
+        world.add_handler(|_: Receiver<B>, s: Sender<Log>| s.send(Log("from B")));
+        world.add_handler(|_: Receiver<C, ()>, s: Sender<Log>| s.send(Log("from C")));
+
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<A>| l.borrow_mut().push("A"));
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<B>| l.borrow_mut().push("B"));
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<C, ()>| l.borrow_mut().push("C"));
+        let l = log.clone();
+        world.add_handler(move |r: Receiver<Log>| l.borrow_mut().push(r.event.0));
+
+        world.send(Tick);
+
+        assert_eq!(
+            *log.borrow(),
+            ["before", "A", "B", "C", "from A 1", "from A 2", "from B", "from C", "after"]
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn targeted_event_not_in_event_set() {
-        let mut world = World::new();
+    fn named_sender() {
+        use crate::event::sender;
 
         #[derive(GlobalEvent)]
-        struct A;
+        struct Tick;
+
+        #[derive(GlobalEvent)]
+        struct Log(&'static str);
 
         #[derive(TargetedEvent)]
-        struct B;
+        struct Hit(#[allow(dead_code)] &'static str);
+
+        // Uses the existing lifetime instead of adding one.
+        #[sender]
+        struct Named<'b> {
+            log: Log,
+            #[targeted]
+            hit: Hit,
+        }
 
-        world.add_handler(|_: Receiver<A>, s: Sender<A>| {
-            s.send_to(EntityId::NULL, B);
+        let mut world = World::new();
+
+        let e = world.spawn();
+
+        world.add_handler(move |_: Receiver<Tick>, s: Named| {
+            s.log(Log("log"));
+            s.hit(e, Hit("hit"));
+            s.0.send(Log("inner"));
         });
 
-        world.send(A);
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let l = log.clone();
+        world.add_handler(move |r: Receiver<Log>| l.borrow_mut().push(r.event.0));
+        let l = log.clone();
+        world.add_handler(move |r: Receiver<Hit, EntityId>| {
+            assert_eq!(r.query, e);
+            l.borrow_mut().push("hit");
+        });
+
+        world.send(Tick);
+
+        assert_eq!(*log.borrow(), ["log", "hit", "inner"]);
     }
 
     #[test]
-    fn send_event_from_sender_with_lifetime() {
-        let mut world = World::new();
+    fn bridge() {
+        use crate::event::Bridge;
 
-        #[derive(GlobalEvent)]
-        struct A;
+        #[derive(GlobalEvent, Clone)]
+        struct A(u32);
 
         #[derive(GlobalEvent)]
-        struct B<'a> {
-            slice: &'a [i32],
-            string: &'a str,
-            array: &'a [u64; 4],
-        }
+        struct B(EntityId);
 
-        fn get_a_send_b(_: Receiver<A>, s: Sender<B>) {
-            s.send(B {
-                slice: s.alloc_slice(5, |i| i as i32 + 1),
-                string: s.alloc_str("pineapple"),
-                array: s.alloc([10, 20, 30, 40]),
-            });
-        }
+        #[derive(TargetedEvent, Clone)]
+        struct C(u32);
 
-        fn get_b(r: Receiver<B>) {
-            assert_eq!(r.event.slice, &[1, 2, 3, 4, 5]);
-            assert_eq!(r.event.string, "pineapple");
-            assert_eq!(r.event.array, &[10, 20, 30, 40]);
-        }
+        let mut src = World::new();
+        let mut dst = World::new();
 
-        world.add_handler(get_a_send_b);
-        world.add_handler(get_b);
-        world.send(A);
+        let src_e1 = src.spawn();
+        let src_e2 = src.spawn();
+        let dst_e = dst.spawn();
+
+        let bridge = Bridge::new();
+        bridge.set_entity_map(move |e| (e == src_e1).then_some(dst_e));
+
+        let a = bridge.forward::<A>(&mut src);
+        bridge.forward_map(&mut src, move |b: &B| (b.0 == src_e1).then_some(A(100)));
+        bridge.forward_targeted::<C>(&mut src);
+
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let l = log.clone();
+        dst.add_handler(move |r: Receiver<A>| l.borrow_mut().push(r.event.0));
+        let l = log.clone();
+        dst.add_handler(move |r: Receiver<C, EntityId>| {
+            assert_eq!(r.query, dst_e);
+            l.borrow_mut().push(r.event.0);
+        });
+
+        src.send(A(1));
+        src.send(B(src_e1));
+        src.send(B(src_e2));
+        src.send_to(src_e1, C(2));
+        src.send_to(src_e2, C(3));
+
+        assert!(log.borrow().is_empty());
+        assert_eq!(bridge.len(), 4);
+
+        dst.drain_bridge(&bridge);
+
+        assert_eq!(*log.borrow(), [1, 100, 2]);
+        assert!(bridge.is_empty());
+
+        src.remove_handler(a);
+        src.send(A(4));
+        assert!(bridge.is_empty());
     }
 
     #[test]
-    fn more_than_one_sender() {
-        let mut world = World::new();
+    fn event_clone_fn() {
+        use core::alloc::Layout;
+        use core::mem::MaybeUninit;
+        use core::ptr::NonNull;
 
-        #[derive(GlobalEvent)]
-        struct A(#[allow(dead_code)] u32);
+        use crate::event::{clone_fn_of, EventDescriptor, EventKind, UntypedReceiver};
+        use crate::mutability::Mutability;
 
         #[derive(GlobalEvent)]
-        struct B(#[allow(dead_code)] u32);
+        struct Typed;
 
-        fn send_b_x2(_: Receiver<A>, s1: Sender<B>, s2: Sender<B>) {
-            s1.send(B(123));
-            s2.send(B(456));
-        }
+        let mut world = World::new();
 
-        world.add_handler(send_b_x2);
+        let id = unsafe {
+            world.add_global_event_with_descriptor(EventDescriptor {
+                name: "Dynamic".into(),
+                type_id: None,
+                kind: EventKind::Normal,
+                layout: Layout::new::<[u8; 4]>(),
+                drop: None,
+                mutability: Mutability::Immutable,
+                clone: clone_fn_of::<[u8; 4]>(),
+                serialize: None,
+                deserialize: None,
+            })
+        };
 
-        world.send(A(123));
+        let typed = world.add_global_event::<Typed>();
+
+        assert!(world.global_events().get(id).unwrap().clone_fn().is_some());
+        assert!(world
+            .global_events()
+            .get(typed)
+            .unwrap()
+            .clone_fn()
+            .is_none());
+
+        let copies = Rc::new(RefCell::new(vec![]));
+
+        let c = copies.clone();
+        world.add_handler(
+            (move |r: UntypedReceiver| {
+                let clone = r.info.clone_fn().unwrap();
+                let mut copy = MaybeUninit::<[u8; 4]>::uninit();
+
+                unsafe { clone(r.event, NonNull::from(&mut copy).cast()) };
+
+                c.borrow_mut().push(unsafe { copy.assume_init() });
+            })
+            .receive_events([id]),
+        );
+
+        world.send_bytes(id, &[1, 2, 3, 4]);
+
+        assert_eq!(*copies.borrow(), [[1, 2, 3, 4]]);
     }
 
-    #[allow(unused, clippy::type_complexity)]
-    mod derive_event {
-        use core::marker::PhantomData;
+    #[test]
+    fn lifecycle_receiver() {
+        use crate::event::{LifecycleKind, LifecycleReceiver};
 
-        use super::*;
+        #[derive(Component)]
+        struct A(u32);
 
-        #[derive(GlobalEvent)]
-        struct StructWithLifetime_<'a> {
-            _ignore: &'a (),
-        }
+        #[derive(Component)]
+        struct B;
 
-        #[derive(TargetedEvent)]
-        struct StructWithLifetime<'a> {
-            _ignore: &'a (),
-        }
+        let mut world = World::new();
 
-        #[derive(GlobalEvent)]
-        struct StructWithGenericType_<T>(T);
+        let log = Rc::new(RefCell::new(vec![]));
+        let l = log.clone();
 
-        #[derive(TargetedEvent)]
-        struct StructWithGenericType<T>(T);
+        let id = world.add_handler(move |r: LifecycleReceiver| {
+            let value = (r.component.name().ends_with("A") && r.value.is_some())
+                .then(|| unsafe { r.value.unwrap().cast::<A>().as_ref().0 });
 
-        #[derive(GlobalEvent)]
-        struct StructWithBoth_<'a, T>(PhantomData<(fn() -> T, &'a ())>);
+            l.borrow_mut().push((r.kind, r.entity, value));
+        });
 
-        #[derive(TargetedEvent)]
-        struct StructWithBoth<'a, T>(PhantomData<(fn() -> T, &'a ())>);
+        let e = world.spawn();
+        world.insert(e, A(1));
+        world.insert(e, B);
+        world.remove::<A>(e);
+        world.remove::<A>(e);
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                (LifecycleKind::Insert, e, Some(1)),
+                (LifecycleKind::Insert, e, None),
+                (LifecycleKind::Remove, e, Some(1)),
+                (LifecycleKind::Remove, e, None),
+            ]
+        );
+
+        let insert_b = world.add_targeted_event::<Insert<B>>();
+        world.remove_targeted_event(insert_b);
+
+        assert!(world.handlers().contains(id));
+        assert!(!world.handlers()[id]
+            .received_events()
+            .contains(&insert_b.into()));
     }
 }
@@ -0,0 +1,122 @@
+use alloc::boxed::Box;
+use core::any::Any;
+use core::fmt;
+
+use super::{EventId, GlobalEvent, GlobalEventId, TargetedEvent, TargetedEventId};
+use crate::entity::EntityId;
+
+/// An owned, type-erased event together with its [`EventId`] and target,
+/// which can be sent with [`World::send_boxed`].
+///
+/// This is intended for events constructed without a compile-time type, such
+/// as events received over the network or created by a scripting backend.
+/// Whether the payload matches the event is checked when the event is sent.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::BoxedEvent;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Chat(String);
+///
+/// let mut world = World::new();
+///
+/// let id = world.add_global_event::<Chat>();
+///
+/// world.add_handler(|r: Receiver<Chat>| println!("{}", r.event.0));
+///
+/// // E.g. produced by a deserializer which maps message tags to event types.
+/// let event = BoxedEvent::new(id, Chat("hello".into()));
+///
+/// world.send_boxed(event); // Prints "hello".
+/// ```
+///
+/// [`World::send_boxed`]: crate::world::World::send_boxed
+pub struct BoxedEvent {
+    id: EventId,
+    target: Option<EntityId>,
+    payload: BoxedPayload,
+}
+
+pub(crate) enum BoxedPayload {
+    Typed(Box<dyn Any>),
+    Bytes(Box<[u8]>),
+}
+
+impl BoxedEvent {
+    /// Boxes a global event of type `E` identified by `id`.
+    pub fn new<E: GlobalEvent + 'static>(id: GlobalEventId, event: E) -> Self {
+        Self {
+            id: EventId::Global(id),
+            target: None,
+            payload: BoxedPayload::Typed(Box::new(event)),
+        }
+    }
+
+    /// Boxes a targeted event of type `E` identified by `id`, sent to
+    /// `target`.
+    pub fn new_targeted<E: TargetedEvent + 'static>(
+        id: TargetedEventId,
+        target: EntityId,
+        event: E,
+    ) -> Self {
+        Self {
+            id: EventId::Targeted(id),
+            target: Some(target),
+            payload: BoxedPayload::Typed(Box::new(event)),
+        }
+    }
+
+    /// Boxes a global event made of plain bytes. See [`World::send_bytes`]
+    /// for the requirements on the event.
+    ///
+    /// [`World::send_bytes`]: crate::world::World::send_bytes
+    pub fn from_bytes<B: Into<Box<[u8]>>>(id: GlobalEventId, bytes: B) -> Self {
+        Self {
+            id: EventId::Global(id),
+            target: None,
+            payload: BoxedPayload::Bytes(bytes.into()),
+        }
+    }
+
+    /// Boxes a targeted event made of plain bytes, sent to `target`. See
+    /// [`World::send_bytes`] for the requirements on the event.
+    ///
+    /// [`World::send_bytes`]: crate::world::World::send_bytes
+    pub fn from_bytes_targeted<B: Into<Box<[u8]>>>(
+        id: TargetedEventId,
+        target: EntityId,
+        bytes: B,
+    ) -> Self {
+        Self {
+            id: EventId::Targeted(id),
+            target: Some(target),
+            payload: BoxedPayload::Bytes(bytes.into()),
+        }
+    }
+
+    /// Returns the [`EventId`] of the event.
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// Returns the target of the event if it is a targeted event.
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+
+    pub(crate) fn into_parts(self) -> (EventId, Option<EntityId>, BoxedPayload) {
+        (self.id, self.target, self.payload)
+    }
+}
+
+impl fmt::Debug for BoxedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedEvent")
+            .field("id", &self.id)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
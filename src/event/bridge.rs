@@ -0,0 +1,174 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::{fmt, mem};
+
+use super::{GlobalEvent, Receiver, TargetedEvent};
+use crate::entity::EntityId;
+use crate::handler::HandlerId;
+use crate::world::World;
+
+/// A forwarded event, which is sent by calling the function with the
+/// destination world and the bridge's entity map.
+type BridgedEvent = Box<dyn FnOnce(&mut World, &mut dyn FnMut(EntityId) -> Option<EntityId>)>;
+
+/// A one-way connection between two [`World`]s which forwards events broadcast
+/// in one world to the other.
+///
+/// Event types are selected with [`Bridge::forward`] and its variants, which
+/// add a handler to the source world that copies the events into the bridge.
+/// The events are sent to the destination world by calling
+/// [`World::drain_bridge`]. Since entities of one world have no meaning in the
+/// other, the targets of forwarded targeted events are translated with the
+/// hook set by [`Bridge::set_entity_map`].
+///
+/// Cloning a `Bridge` produces another handle to the same bridge. For events
+/// travelling in both directions, use two bridges.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::Bridge;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent, Clone)]
+/// struct ScoreChanged(u32);
+///
+/// let mut sim = World::new();
+/// let mut ui = World::new();
+///
+/// let bridge = Bridge::new();
+/// bridge.forward::<ScoreChanged>(&mut sim);
+///
+/// ui.add_handler(|r: Receiver<ScoreChanged>| println!("score: {}", r.event.0));
+///
+/// sim.send(ScoreChanged(42));
+///
+/// ui.drain_bridge(&bridge); // Prints "score: 42".
+/// ```
+#[derive(Clone, Default)]
+pub struct Bridge {
+    inner: Rc<RefCell<BridgeInner>>,
+}
+
+#[derive(Default)]
+struct BridgeInner {
+    events: Vec<BridgedEvent>,
+    map: Option<Box<dyn FnMut(EntityId) -> Option<EntityId>>>,
+}
+
+impl Bridge {
+    /// Creates a new bridge which forwards no events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the hook translating the targets of forwarded targeted events
+    /// from entities of the source world to entities of the destination
+    /// world, replacing the previous hook. Events whose target is mapped to
+    /// `None` are dropped.
+    ///
+    /// Without a hook, targets are passed through unchanged. The hook is
+    /// called when the events are drained.
+    pub fn set_entity_map<F>(&self, map: F)
+    where
+        F: FnMut(EntityId) -> Option<EntityId> + 'static,
+    {
+        self.inner.borrow_mut().map = Some(Box::new(map));
+    }
+
+    /// Forwards the global event `E` broadcast in `from` by adding a handler
+    /// which clones the events into the bridge. Returns the ID of the
+    /// handler, which stops forwarding when removed.
+    pub fn forward<E>(&self, from: &mut World) -> HandlerId
+    where
+        E: for<'a> GlobalEvent<This<'a> = E> + Clone + 'static,
+    {
+        self.forward_map(from, |event: &E| Some(event.clone()))
+    }
+
+    /// Forwards the global event `E` broadcast in `from` as the event
+    /// returned by `f`, or not at all if `f` returns `None`. This is useful
+    /// for events which contain entity IDs or which are represented
+    /// differently in the destination world.
+    ///
+    /// Returns the ID of the added handler. See [`Bridge::forward`].
+    pub fn forward_map<E, R, F>(&self, from: &mut World, mut f: F) -> HandlerId
+    where
+        E: for<'a> GlobalEvent<This<'a> = E> + 'static,
+        R: GlobalEvent + 'static,
+        F: FnMut(&E) -> Option<R> + 'static,
+    {
+        let bridge = self.clone();
+
+        from.add_handler(move |r: Receiver<E>| {
+            if let Some(event) = f(r.event) {
+                bridge.push(Box::new(move |world, _| world.send(event)));
+            }
+        })
+    }
+
+    /// Forwards the targeted event `E` broadcast in `from` by adding a handler
+    /// which clones the events into the bridge. The target is translated with
+    /// the hook set by [`Bridge::set_entity_map`].
+    ///
+    /// Returns the ID of the added handler. See [`Bridge::forward`].
+    pub fn forward_targeted<E>(&self, from: &mut World) -> HandlerId
+    where
+        E: for<'a> TargetedEvent<This<'a> = E> + Clone + 'static,
+    {
+        let bridge = self.clone();
+
+        from.add_handler(move |r: Receiver<E, EntityId>| {
+            let target = r.query;
+            let event = r.event.clone();
+
+            bridge.push(Box::new(move |world, map| {
+                if let Some(target) = map(target) {
+                    world.send_to(target, event);
+                }
+            }));
+        })
+    }
+
+    /// Returns the number of events waiting to be drained.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().events.len()
+    }
+
+    /// Returns `true` if no events are waiting to be drained.
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().events.is_empty()
+    }
+
+    fn push(&self, event: BridgedEvent) {
+        self.inner.borrow_mut().events.push(event);
+    }
+
+    /// Sends all forwarded events to `world` in the order they were
+    /// forwarded.
+    pub(crate) fn drain(&self, world: &mut World) {
+        let events = mem::take(&mut self.inner.borrow_mut().events);
+
+        // The bridge is only borrowed while the map runs, since handlers in
+        // `world` may forward events to this bridge.
+        let mut map = |id| match &mut self.inner.borrow_mut().map {
+            Some(map) => map(id),
+            None => Some(id),
+        };
+
+        for f in events {
+            f(world, &mut map);
+        }
+    }
+}
+
+impl fmt::Debug for Bridge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bridge")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
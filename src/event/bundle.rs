@@ -0,0 +1,238 @@
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use evenio_macros::all_tuples;
+
+use super::{Event, EventKind, GlobalEventIdx, TargetedEventIdx};
+use crate::component::{Component, ComponentIdx};
+use crate::entity::EntityId;
+use crate::mutability::{Immutable, Mutable};
+use crate::world::World;
+
+/// A set of components which can be inserted on an entity at once with
+/// [`InsertBundle`].
+///
+/// This is implemented for tuples of [`Component`]s.
+///
+/// # Safety
+///
+/// - [`component_indices`] and [`component_ptrs`] must list the components in
+///   the same order.
+/// - [`component_indices`] must return the indices of the types of the
+///   pointers produced by [`component_ptrs`], and must not contain
+///   duplicates.
+/// - Dropping the bundle must do nothing but drop each of the components.
+///
+/// [`component_indices`]: Self::component_indices
+/// [`component_ptrs`]: Self::component_ptrs
+pub unsafe trait Bundle: 'static {
+    /// Adds the components of the bundle to the world and returns their
+    /// indices.
+    fn component_indices(world: &mut World) -> Vec<ComponentIdx>;
+
+    /// Pushes a pointer to each component of the bundle onto `out`.
+    ///
+    /// # Safety
+    ///
+    /// `bundle` must point to a valid instance of `Self`.
+    unsafe fn component_ptrs(bundle: NonNull<u8>, out: &mut Vec<*const u8>);
+}
+
+macro_rules! impl_bundle_tuple {
+    ($(($C:ident, $c:ident)),*) => {
+        #[allow(unused_variables, unused_mut, clippy::unused_unit)]
+        unsafe impl<$($C: Component),*> Bundle for ($($C,)*) {
+            fn component_indices(world: &mut World) -> Vec<ComponentIdx> {
+                vec![$(world.add_component::<$C>().index()),*]
+            }
+
+            unsafe fn component_ptrs(bundle: NonNull<u8>, out: &mut Vec<*const u8>) {
+                let ($($c,)*) = bundle.cast::<Self>().as_ref();
+
+                $(
+                    out.push(($c as *const $C).cast());
+                )*
+            }
+        }
+    }
+}
+
+all_tuples!(impl_bundle_tuple, 1, 15, C, c);
+
+/// A [`TargetedEvent`] which adds all components in the [`Bundle`] `B` to an
+/// entity when sent. Components the entity already has are replaced.
+///
+/// Unlike sending an [`Insert`] event for every component, the entity is
+/// moved to its new archetype only once, so handlers never observe the entity
+/// with only some of the components inserted.
+///
+/// Any handler which listens for `InsertBundle<B>` will run before the
+/// components are inserted. Handlers listening for [`Insert`] events of the
+/// individual components do not run. `InsertBundle<B>` has no effect if the
+/// target entity does not exist or the event is consumed before it finishes
+/// broadcasting.
+///
+/// # Panics
+///
+/// Adding the event to a world panics if `B` contains the same component
+/// more than once.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::InsertBundle;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Position(f32, f32);
+///
+/// #[derive(Component)]
+/// struct Velocity(f32, f32);
+///
+/// let mut world = World::new();
+/// let e = world.spawn();
+///
+/// world.send_to(e, InsertBundle((Position(0.0, 0.0), Velocity(1.0, 0.0))));
+///
+/// assert!(world.get::<Position>(e).is_some());
+/// assert!(world.get::<Velocity>(e).is_some());
+/// ```
+///
+/// [`TargetedEvent`]: super::TargetedEvent
+/// [`Insert`]: super::Insert
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(transparent)]
+pub struct InsertBundle<B>(pub B);
+
+unsafe impl<B: Bundle> Event for InsertBundle<B> {
+    type This<'a> = InsertBundle<B>;
+
+    type EventIdx = TargetedEventIdx;
+
+    type Mutability = Mutable;
+
+    fn init(world: &mut World) -> EventKind {
+        // `InsertBundle<B>` is `repr(transparent)`.
+        world.add_bundle::<Self, B>(B::component_ptrs);
+        EventKind::InsertBundle
+    }
+}
+
+impl<B> Deref for InsertBundle<B> {
+    type Target = B;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<B> DerefMut for InsertBundle<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// A [`GlobalEvent`] which creates an entity with all components in the
+/// [`Bundle`] `B`. Contains the [`EntityId`] of the new entity and the bundle.
+///
+/// The entity is spawned and the components are inserted in one step after the
+/// event has finished broadcasting, so handlers never observe the entity
+/// without its components. Any handler which listens for `SpawnWith<B>` will
+/// run before the entity is spawned. Handlers listening for [`Spawn`] or
+/// [`Insert`] events do not run.
+///
+/// Note that the event by itself cannot be used to spawn new entities. Use
+/// [`World::spawn_with`] or [`Sender::spawn_with`] instead. Events whose
+/// entity wasn't reserved by these methods, such as an existing entity, are
+/// ignored. If the event is consumed or dropped before it spawns the entity,
+/// the reserved [`EntityId`] is released at the end of the flush.
+///
+/// # Panics
+///
+/// Adding the event to a world panics if `B` contains the same component
+/// more than once.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Position(f32, f32);
+///
+/// #[derive(Component)]
+/// struct Velocity(f32, f32);
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn_with((Position(0.0, 0.0), Velocity(1.0, 0.0)));
+///
+/// assert!(world.get::<Position>(e).is_some());
+/// assert!(world.get::<Velocity>(e).is_some());
+/// ```
+///
+/// [`GlobalEvent`]: super::GlobalEvent
+/// [`Spawn`]: super::Spawn
+/// [`Insert`]: super::Insert
+/// [`Sender::spawn_with`]: super::Sender::spawn_with
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(C)] // The entity ID must be at offset zero.
+pub struct SpawnWith<B>(pub EntityId, pub B);
+
+unsafe impl<B: Bundle> Event for SpawnWith<B> {
+    type This<'a> = SpawnWith<B>;
+
+    type EventIdx = GlobalEventIdx;
+
+    type Mutability = Immutable;
+
+    fn init(world: &mut World) -> EventKind {
+        unsafe fn component_ptrs<B: Bundle>(event: NonNull<u8>, out: &mut Vec<*const u8>) {
+            let bundle = &event.cast::<SpawnWith<B>>().as_ref().1;
+            B::component_ptrs(NonNull::from(bundle).cast(), out);
+        }
+
+        world.add_bundle::<Self, B>(component_ptrs::<B>);
+        EventKind::SpawnWith
+    }
+}
+
+/// Information about a [`Bundle`] type used by an event added to a world.
+#[derive(Debug)]
+pub(crate) struct BundleInfo {
+    /// The components of the bundle, in the order of [`Bundle::component_ptrs`].
+    pub(crate) component_indices: Vec<ComponentIdx>,
+    /// Pushes a pointer to each component of the bundle contained in the
+    /// event onto the vector.
+    pub(crate) component_ptrs: ComponentPtrsFn,
+}
+
+/// Produces pointers to the components of a bundle inside of a type-erased
+/// event.
+pub(crate) type ComponentPtrsFn = unsafe fn(NonNull<u8>, &mut Vec<*const u8>);
+
+impl BundleInfo {
+    /// Creates the info for `B`. Panics if `B` contains duplicate components.
+    #[track_caller]
+    pub(crate) fn new<B: Bundle>(world: &mut World, component_ptrs: ComponentPtrsFn) -> Self {
+        let component_indices = B::component_indices(world);
+
+        let mut sorted = component_indices.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        assert_eq!(
+            sorted.len(),
+            component_indices.len(),
+            "bundle `{}` contains duplicate components",
+            core::any::type_name::<B>()
+        );
+
+        Self {
+            component_indices,
+            component_ptrs,
+        }
+    }
+}
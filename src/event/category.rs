@@ -0,0 +1,208 @@
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::cell::RefCell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use super::{Event, EventId, EventPtr};
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::entity::{EntityId, EntityLocation};
+use crate::fetch::FetcherState;
+use crate::handler::{Handler, HandlerConfig, HandlerInfo, InitError};
+use crate::map::TypeIdMap;
+use crate::query::Query;
+use crate::world::{UnsafeWorldCell, World};
+
+/// A type-erased view of an event received by a category handler.
+///
+/// See [`World::add_category_handler`] for more information.
+pub struct CategoryEvent<'a> {
+    id: EventId,
+    name: &'a str,
+    type_id: Option<TypeId>,
+    target: Option<EntityId>,
+    ptr: NonNull<u8>,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> CategoryEvent<'a> {
+    /// Returns the [`EventId`] of the event.
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// Returns the name of the event.
+    ///
+    /// This name is intended for debugging purposes and should not be relied
+    /// upon for correctness.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the target of the event if it is a targeted event.
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+
+    /// Returns a reference to the event if it is of type `E`. Lifetimes in
+    /// `E` are shortened to `'a`, as with [`Receiver`].
+    ///
+    /// [`Receiver`]: crate::event::Receiver
+    pub fn downcast_ref<E: Event>(&self) -> Option<&'a E::This<'a>> {
+        let is_e = self.type_id == Some(TypeId::of::<E::This<'static>>());
+        // SAFETY: The type IDs match and we have read access to the event.
+        is_e.then(|| unsafe { self.ptr.cast::<E::This<'a>>().as_ref() })
+    }
+
+    /// Returns a pointer to the event.
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+}
+
+impl fmt::Debug for CategoryEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CategoryEvent")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A function subscribed to a category, shared between the handlers added for
+/// each event in the category.
+pub(crate) type CategoryFn = Rc<RefCell<dyn FnMut(CategoryEvent)>>;
+
+/// The events and subscribers of every category in a world.
+#[derive(Default)]
+pub(crate) struct Categories {
+    map: TypeIdMap<CategoryMembers>,
+}
+
+#[derive(Default)]
+pub(crate) struct CategoryMembers {
+    pub(crate) events: Vec<EventId>,
+    pub(crate) subscribers: Vec<CategoryFn>,
+}
+
+impl Categories {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn get_or_insert(&mut self, category: TypeId) -> &mut CategoryMembers {
+        self.map.entry(category).or_default()
+    }
+}
+
+impl fmt::Debug for Categories {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Categories")
+            .field("len", &self.map.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The handler added for each combination of an event and a subscriber of a
+/// category it belongs to.
+pub(crate) struct CategoryHandler {
+    category: &'static str,
+    event: EventId,
+    func: CategoryFn,
+    event_name: Cow<'static, str>,
+    type_id: Option<TypeId>,
+    target_state: Option<FetcherState<EntityId>>,
+}
+
+impl CategoryHandler {
+    pub(crate) fn new(category: &'static str, event: EventId, func: CategoryFn) -> Self {
+        Self {
+            category,
+            event,
+            func,
+            event_name: Cow::Borrowed(""),
+            type_id: None,
+            target_state: None,
+        }
+    }
+}
+
+impl Handler for CategoryHandler {
+    fn type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(self.category)
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        match self.event {
+            EventId::Global(id) => {
+                let info = world.global_events().get(id).unwrap();
+                self.event_name = Cow::Owned(info.name().into());
+                self.type_id = info.type_id();
+            }
+            EventId::Targeted(id) => {
+                let info = world.targeted_events().get(id).unwrap();
+                self.event_name = Cow::Owned(info.name().into());
+                self.type_id = info.type_id();
+
+                let (ca, state) = <EntityId as Query>::init(world, config)?;
+
+                config.set_targeted_event_component_access(ca.clone());
+                config.push_component_access(ca);
+
+                self.target_state = Some(FetcherState::new(state));
+            }
+        }
+
+        config.set_received_event(self.event);
+        config.set_received_event_access(Access::Read);
+
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        _info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        _world: UnsafeWorldCell,
+    ) {
+        let target = self
+            .target_state
+            .as_mut()
+            // SAFETY: Caller guarantees the target entity matches the query.
+            .map(|state| state.get_by_location_mut(target_location));
+
+        let event = CategoryEvent {
+            id: self.event,
+            name: &self.event_name,
+            type_id: self.type_id,
+            target,
+            ptr: event_ptr.as_ptr(),
+            _marker: PhantomData,
+        };
+
+        (self.func.borrow_mut())(event);
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        if let Some(state) = &mut self.target_state {
+            state.refresh_archetype(arch);
+        }
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        if let Some(state) = &mut self.target_state {
+            state.remove_archetype(arch);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use alloc::boxed::Box;
+use core::any::TypeId;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use ahash::RandomState;
+
+use crate::entity::EntityId;
+use crate::map::{HashSet, TypeIdMap};
+
+/// The event types which are deduplicated within a flush of the event queue.
+///
+/// See [`World::dedup_by_key`].
+///
+/// [`World::dedup_by_key`]: crate::world::World::dedup_by_key
+#[derive(Default)]
+pub(crate) struct Deduplicators {
+    map: TypeIdMap<Box<dyn Dedup>>,
+}
+
+impl Deduplicators {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Deduplicates events of type `E` by the key returned from `key`,
+    /// replacing any previous key function for `E`.
+    pub(crate) fn insert<E, K, F>(&mut self, key: F)
+    where
+        E: 'static,
+        K: Eq + Hash + 'static,
+        F: FnMut(&E) -> K + 'static,
+    {
+        self.map.insert(
+            TypeId::of::<E>(),
+            Box::new(DedupByKey {
+                seen: HashSet::with_hasher(RandomState::new()),
+                key,
+                _marker: PhantomData,
+            }),
+        );
+    }
+
+    /// Returns `true` if an event with the same key and target was already
+    /// seen since the last call to [`Deduplicators::clear`].
+    ///
+    /// # Safety
+    ///
+    /// `event` must point to a valid instance of the event with the given type
+    /// ID.
+    pub(crate) unsafe fn is_duplicate(
+        &mut self,
+        type_id: Option<TypeId>,
+        event: NonNull<u8>,
+        target: Option<EntityId>,
+    ) -> bool {
+        match type_id.and_then(|id| self.map.get_mut(&id)) {
+            Some(dedup) => dedup.is_duplicate(event, target),
+            None => false,
+        }
+    }
+
+    /// Forgets all events seen so far.
+    pub(crate) fn clear(&mut self) {
+        self.map.values_mut().for_each(|dedup| dedup.clear());
+    }
+}
+
+impl fmt::Debug for Deduplicators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deduplicators")
+            .field("len", &self.map.len())
+            .finish_non_exhaustive()
+    }
+}
+
+trait Dedup {
+    /// # Safety
+    ///
+    /// `event` must point to a valid instance of the deduplicated event type.
+    unsafe fn is_duplicate(&mut self, event: NonNull<u8>, target: Option<EntityId>) -> bool;
+
+    fn clear(&mut self);
+}
+
+struct DedupByKey<E, K, F> {
+    seen: HashSet<(Option<EntityId>, K)>,
+    key: F,
+    _marker: PhantomData<fn(&E)>,
+}
+
+impl<E, K, F> Dedup for DedupByKey<E, K, F>
+where
+    K: Eq + Hash,
+    F: FnMut(&E) -> K,
+{
+    unsafe fn is_duplicate(&mut self, event: NonNull<u8>, target: Option<EntityId>) -> bool {
+        let key = (self.key)(event.cast::<E>().as_ref());
+        !self.seen.insert((target, key))
+    }
+
+    fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
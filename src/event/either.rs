@@ -0,0 +1,326 @@
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::any::TypeId;
+use core::fmt;
+use core::ptr::NonNull;
+
+use super::{
+    Event, EventId, EventMeta, EventPtr, EventSet, GlobalEventIdx, NullReceiverQuery,
+    ReceiverQuery, TargetedEventIdx,
+};
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::entity::EntityLocation;
+use crate::fetch::FetcherState;
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::query::Query;
+use crate::world::{UnsafeWorldCell, World};
+
+/// One of two values. Used both to name the events received by
+/// [`ReceiverEither`] and to hold the event that was received.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Either<L, R> {
+    /// The left value.
+    Left(L),
+    /// The right value.
+    Right(R),
+}
+
+impl<L, R> Either<L, R> {
+    /// Convert `Either<L, R>` to `Either<M, S>` using the supplied closures.
+    pub fn map<F, G, M, S>(self, f: F, g: G) -> Either<M, S>
+    where
+        F: FnOnce(L) -> M,
+        G: FnOnce(R) -> S,
+    {
+        match self {
+            Either::Left(l) => Either::Left(f(l)),
+            Either::Right(r) => Either::Right(g(r)),
+        }
+    }
+
+    /// Converts `&Either<L, R>` to `Either<&L, &R>`.
+    pub const fn as_ref(&self) -> Either<&L, &R> {
+        match self {
+            Either::Left(l) => Either::Left(l),
+            Either::Right(r) => Either::Right(r),
+        }
+    }
+}
+
+/// A set of [`Event`] types which can be received together by
+/// [`ReceiverEither`].
+///
+/// This trait is implemented for all events and for [`Either`] of event
+/// unions, so `E1`, `Either<E1, E2>`, `Either<E1, Either<E2, E3>>` etc. are
+/// all event unions. The events of a union are either all global or all
+/// targeted.
+///
+/// # Safety
+///
+/// This trait is marked `unsafe` because unsafe code relies on implementations
+/// being correct. It is not recommended to implement this trait yourself.
+pub unsafe trait EventUnion: 'static {
+    /// [`GlobalEventIdx`] if the events are global, [`TargetedEventIdx`] if
+    /// they are targeted.
+    type EventIdx: 'static;
+
+    /// The value produced when one of the events is received.
+    type Item<'a>;
+
+    /// The number of events in the union.
+    const LEN: usize;
+
+    /// Adds the events of the union to the world and pushes their indices onto
+    /// `out`, from left to right.
+    fn init(world: &mut World, out: &mut Vec<u32>);
+
+    /// Produces the item for the event at position `pos` of the union.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be less than [`Self::LEN`].
+    /// - `event` must point to a valid instance of the event at position `pos`
+    ///   for the lifetime `'a`.
+    unsafe fn get<'a>(pos: usize, event: NonNull<u8>) -> Self::Item<'a>;
+}
+
+unsafe impl<E: Event + 'static> EventUnion for E {
+    type EventIdx = E::EventIdx;
+
+    type Item<'a> = &'a E::This<'a>;
+
+    const LEN: usize = 1;
+
+    fn init(world: &mut World, out: &mut Vec<u32>) {
+        out.push(<E as EventSet>::new_indices(world));
+    }
+
+    unsafe fn get<'a>(_pos: usize, event: NonNull<u8>) -> Self::Item<'a> {
+        event.cast::<E::This<'a>>().as_ref()
+    }
+}
+
+unsafe impl<L, R> EventUnion for Either<L, R>
+where
+    L: EventUnion,
+    R: EventUnion<EventIdx = L::EventIdx>,
+{
+    type EventIdx = L::EventIdx;
+
+    type Item<'a> = Either<L::Item<'a>, R::Item<'a>>;
+
+    const LEN: usize = L::LEN + R::LEN;
+
+    fn init(world: &mut World, out: &mut Vec<u32>) {
+        L::init(world, out);
+        R::init(world, out);
+    }
+
+    unsafe fn get<'a>(pos: usize, event: NonNull<u8>) -> Self::Item<'a> {
+        if pos < L::LEN {
+            Either::Left(L::get(pos, event))
+        } else {
+            Either::Right(R::get(pos - L::LEN, event))
+        }
+    }
+}
+
+/// A [`HandlerParam`] which listens for events of type `L` or `R`.
+///
+/// The handler is added to the handler list of every event in the union, so
+/// it runs when any of them is broadcast. `L` and `R` may themselves be
+/// [`Either`]s to receive more than two events. All events must be global, or
+/// all events must be targeted, in which case the query `Q` applies to the
+/// target of every event. If an event appears more than once in the union,
+/// the leftmost occurrence is used.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{Either, ReceiverEither};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Click;
+///
+/// #[derive(GlobalEvent)]
+/// struct Purchase(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: ReceiverEither<Click, Purchase>| match r.event {
+///     Either::Left(Click) => println!("click"),
+///     Either::Right(p) => println!("purchase: {}", p.0),
+/// });
+///
+/// world.send(Click); // Prints "click".
+/// world.send(Purchase(10)); // Prints "purchase: 10".
+/// ```
+pub struct ReceiverEither<'a, L, R, Q = NullReceiverQuery>
+where
+    L: EventUnion,
+    R: EventUnion,
+    Q: ReceiverQuery + 'static,
+{
+    /// A reference to the received event.
+    pub event: Either<L::Item<'a>, R::Item<'a>>,
+    /// The result of the query. This field is meaningless if the events are
+    /// not targeted.
+    pub query: Q::Item<'a>,
+}
+
+/// Adds the events of `U` to the world, configures the handler to receive
+/// them, and returns their indices.
+fn init_union<U: EventUnion>(world: &mut World, config: &mut HandlerConfig) -> Box<[u32]> {
+    let mut indices = vec![];
+    U::init(world, &mut indices);
+
+    // All events of the union have the same kind.
+    let is_targeted = TypeId::of::<U::EventIdx>() == TypeId::of::<TargetedEventIdx>();
+
+    config.set_received_events(indices.iter().map(|&idx| {
+        if is_targeted {
+            let info = world.targeted_events().get_by_index(TargetedEventIdx(idx));
+            EventId::Targeted(info.unwrap().id())
+        } else {
+            let info = world.global_events().get_by_index(GlobalEventIdx(idx));
+            EventId::Global(info.unwrap().id())
+        }
+    }));
+    config.set_received_event_access(Access::Read);
+
+    indices.into()
+}
+
+/// Returns the position of the received event in the union.
+///
+/// # Safety
+///
+/// The handler must have been configured with [`init_union`].
+unsafe fn position_in_union(indices: &[u32], event_ptr: EventPtr) -> usize {
+    let idx = match event_ptr.meta() {
+        EventMeta::Global { idx } => idx.0,
+        EventMeta::Targeted { idx, .. } => idx.0,
+    };
+
+    // SAFETY: The handler only receives the events in the union.
+    indices.iter().position(|&i| i == idx).unwrap_unchecked()
+}
+
+unsafe impl<L, R> HandlerParam for ReceiverEither<'_, L, R>
+where
+    L: EventUnion<EventIdx = GlobalEventIdx>,
+    R: EventUnion<EventIdx = GlobalEventIdx>,
+{
+    type State = Box<[u32]>;
+
+    type This<'a> = ReceiverEither<'a, L, R>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        Ok(init_union::<Either<L, R>>(world, config))
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        _world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        let pos = position_in_union(state, event_ptr);
+
+        ReceiverEither {
+            // SAFETY:
+            // - We have permission to access the event immutably.
+            // - The event is the one at position `pos` of the union.
+            event: Either::<L, R>::get(pos, event_ptr.as_ptr()),
+            query: (),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl<'a, L, R> fmt::Debug for ReceiverEither<'a, L, R>
+where
+    L: EventUnion<EventIdx = GlobalEventIdx>,
+    R: EventUnion<EventIdx = GlobalEventIdx>,
+    L::Item<'a>: fmt::Debug,
+    R::Item<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceiverEither")
+            .field("event", &self.event)
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe impl<L, R, Q> HandlerParam for ReceiverEither<'_, L, R, Q>
+where
+    L: EventUnion<EventIdx = TargetedEventIdx>,
+    R: EventUnion<EventIdx = TargetedEventIdx>,
+    Q: Query + 'static,
+{
+    type State = (Box<[u32]>, FetcherState<Q>);
+
+    type This<'a> = ReceiverEither<'a, L, R, Q>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        let indices = init_union::<Either<L, R>>(world, config);
+
+        let (ca, state) = Q::init(world, config)?;
+
+        config.set_targeted_event_component_access(ca.clone());
+        config.push_component_access(ca);
+
+        Ok((indices, FetcherState::new(state)))
+    }
+
+    unsafe fn get<'a>(
+        (indices, state): &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        target_location: EntityLocation,
+        _world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        let pos = position_in_union(indices, event_ptr);
+
+        let event = Either::<L, R>::get(pos, event_ptr.as_ptr());
+
+        // SAFETY: Caller guarantees the target entity matches the query.
+        let query = state.get_by_location_mut(target_location);
+
+        ReceiverEither { event, query }
+    }
+
+    fn refresh_archetype((_, state): &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype((_, state): &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+impl<'a, L, R, Q> fmt::Debug for ReceiverEither<'a, L, R, Q>
+where
+    L: EventUnion<EventIdx = TargetedEventIdx>,
+    R: EventUnion<EventIdx = TargetedEventIdx>,
+    L::Item<'a>: fmt::Debug,
+    R::Item<'a>: fmt::Debug,
+    Q: Query,
+    Q::This<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceiverEither")
+            .field("event", &self.event)
+            .field("query", &self.query)
+            .finish()
+    }
+}
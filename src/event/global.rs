@@ -5,7 +5,11 @@ use core::ops::Index;
 
 pub use evenio_macros::GlobalEvent;
 
-use super::{Event, EventDescriptor, EventKind, EventPtr, Mutability};
+#[cfg(feature = "metrics")]
+use super::EventMetrics;
+use super::{
+    CloneFn, DeserializeFn, Event, EventDescriptor, EventKind, EventPtr, Mutability, SerializeFn,
+};
 use crate::archetype::Archetype;
 use crate::drop::DropFn;
 use crate::entity::EntityLocation;
@@ -93,6 +97,11 @@ impl GlobalEvents {
             layout: desc.layout,
             drop: desc.drop,
             mutability: desc.mutability,
+            clone: desc.clone,
+            serialize: desc.serialize,
+            deserialize: desc.deserialize,
+            #[cfg(feature = "metrics")]
+            metrics: EventMetrics::default(),
         };
 
         let insert = || {
@@ -143,6 +152,11 @@ impl GlobalEvents {
         Some(self.infos.get_by_index(idx.0)?.1)
     }
 
+    #[cfg(feature = "metrics")]
+    pub(crate) fn get_by_index_mut(&mut self, idx: GlobalEventIdx) -> Option<&mut GlobalEventInfo> {
+        Some(self.infos.get_by_index_mut(idx.0)?.1)
+    }
+
     /// Gets the [`GlobalEventInfo`] for an event using its [`TypeId`]. Returns
     /// `None` if the `TypeId` does not map to an event.
     pub fn get_by_type_id(&self, type_id: TypeId) -> Option<&GlobalEventInfo> {
@@ -172,6 +186,11 @@ impl GlobalEvents {
     pub fn iter(&self) -> impl Iterator<Item = &GlobalEventInfo> {
         self.infos.iter().map(|(_, v)| v)
     }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut GlobalEventInfo> {
+        self.infos.iter_mut().map(|(_, v)| v)
+    }
 }
 
 impl Index<GlobalEventId> for GlobalEvents {
@@ -215,6 +234,8 @@ unsafe impl HandlerParam for &'_ GlobalEvents {
 
     type This<'a> = &'a GlobalEvents;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -244,6 +265,11 @@ pub struct GlobalEventInfo {
     layout: Layout,
     drop: DropFn,
     mutability: Mutability,
+    clone: CloneFn,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn,
+    #[cfg(feature = "metrics")]
+    metrics: EventMetrics,
 }
 
 impl GlobalEventInfo {
@@ -280,12 +306,39 @@ impl GlobalEventInfo {
         self.drop
     }
 
+    /// Gets the [`CloneFn`] of the event.
+    pub fn clone_fn(&self) -> CloneFn {
+        self.clone
+    }
+
+    /// Gets the [`SerializeFn`] of the event.
+    pub fn serialize_fn(&self) -> SerializeFn {
+        self.serialize
+    }
+
+    /// Gets the [`DeserializeFn`] of the event.
+    pub fn deserialize_fn(&self) -> DeserializeFn {
+        self.deserialize
+    }
+
     /// Gets the [mutability] of the event
     ///
     /// [mutability]: Event::Mutability
     pub fn mutability(&self) -> Mutability {
         self.mutability
     }
+
+    /// Gets the [`EventMetrics`] of the event.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn metrics(&self) -> EventMetrics {
+        self.metrics
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_mut(&mut self) -> &mut EventMetrics {
+        &mut self.metrics
+    }
 }
 
 /// Lightweight identifier for a global event type.
@@ -0,0 +1,150 @@
+use core::any::TypeId;
+use core::fmt;
+
+use super::{CloneFn, EventId, EventKind, EventMeta, EventPtr};
+use crate::archetype::Archetype;
+use crate::entity::{EntityId, EntityLocation};
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::world::{UnsafeWorldCell, World};
+
+/// A [`HandlerParam`] describing the event received by the handler.
+///
+/// This is useful in handlers which receive more than one event type, such as
+/// those added with [`World::add_handler_for_events`], or those with a
+/// [`ReceiverEither`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::EventInfo;
+/// use evenio::prelude::*;
+///
+/// #[derive(TargetedEvent)]
+/// struct Hit;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<Hit, ()>, info: EventInfo| {
+///     println!("received {} targeting {:?}", info.name(), info.target());
+/// });
+///
+/// let e = world.spawn();
+/// world.send_to(e, Hit);
+/// ```
+///
+/// [`ReceiverEither`]: super::ReceiverEither
+#[derive(Clone, Copy)]
+pub struct EventInfo<'a> {
+    id: EventId,
+    name: &'a str,
+    kind: EventKind,
+    type_id: Option<TypeId>,
+    clone: CloneFn,
+    target: Option<EntityId>,
+}
+
+impl<'a> EventInfo<'a> {
+    /// # Safety
+    ///
+    /// `meta` must describe an event which exists in the world.
+    pub(crate) unsafe fn new(meta: EventMeta, world: UnsafeWorldCell<'a>) -> Self {
+        match meta {
+            EventMeta::Global { idx } => {
+                let info = world.global_events().get_by_index(idx).unwrap_unchecked();
+
+                EventInfo {
+                    id: EventId::Global(info.id()),
+                    name: info.name(),
+                    kind: info.kind(),
+                    type_id: info.type_id(),
+                    clone: info.clone_fn(),
+                    target: None,
+                }
+            }
+            EventMeta::Targeted { idx, target } => {
+                let info = world.targeted_events().get_by_index(idx).unwrap_unchecked();
+
+                EventInfo {
+                    id: EventId::Targeted(info.id()),
+                    name: info.name(),
+                    kind: info.kind(),
+                    type_id: info.type_id(),
+                    clone: info.clone_fn(),
+                    target: Some(target),
+                }
+            }
+        }
+    }
+
+    /// Returns the [`EventId`] of the event.
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// Returns the name of the event.
+    ///
+    /// This name is intended for debugging purposes and should not be relied
+    /// upon for correctness.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the [`EventKind`] of the event.
+    pub fn kind(&self) -> EventKind {
+        self.kind
+    }
+
+    /// Returns the [`TypeId`] of the event, if any.
+    pub fn type_id(&self) -> Option<TypeId> {
+        self.type_id
+    }
+
+    /// Returns the [`CloneFn`] of the event, which is `None` unless the event
+    /// was added with a descriptor providing one.
+    pub fn clone_fn(&self) -> CloneFn {
+        self.clone
+    }
+
+    /// Returns the target of the event if it is a targeted event.
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+}
+
+unsafe impl HandlerParam for EventInfo<'_> {
+    type State = ();
+
+    type This<'a> = EventInfo<'a>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        // SAFETY: The received event exists.
+        EventInfo::new(event_ptr.meta(), world)
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl fmt::Debug for EventInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventInfo")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("kind", &self.kind)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,240 @@
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use super::{Event, EventId, EventMeta, EventQueueItem, GlobalEvents, TargetedEvents};
+use crate::entity::EntityId;
+
+/// A hook which observes every event broadcast in a [`World`].
+///
+/// Interceptors are added with [`World::add_interceptor`] and run in the order
+/// they were added. Unlike handlers, they see every event without naming its
+/// type, which makes them suited for logging, validation, and recording.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{InterceptedEvent, Interceptor, Verdict};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Chat(String);
+///
+/// struct ChatFilter;
+///
+/// impl Interceptor for ChatFilter {
+///     fn before(&mut self, event: &mut InterceptedEvent) -> Verdict {
+///         match event.downcast_ref::<Chat>() {
+///             Some(chat) if chat.0.contains("cheat") => Verdict::Veto,
+///             _ => Verdict::Continue,
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_interceptor(ChatFilter);
+/// world.add_handler(|r: Receiver<Chat>| println!("{}", r.event.0));
+///
+/// world.send(Chat("hello".into())); // Prints "hello".
+/// world.send(Chat("I cheat".into())); // Prints nothing.
+/// ```
+///
+/// [`World`]: crate::world::World
+/// [`World::add_interceptor`]: crate::world::World::add_interceptor
+pub trait Interceptor: 'static {
+    /// Called before the handlers of an event run.
+    ///
+    /// Returning [`Verdict::Veto`] drops the event. Its handlers, its built-in
+    /// effect such as inserting a component, and the interceptors after this
+    /// one are skipped.
+    #[allow(unused_variables)]
+    fn before(&mut self, event: &mut InterceptedEvent) -> Verdict {
+        Verdict::Continue
+    }
+
+    /// Called after the handlers of an event ran, but before its built-in
+    /// effect, such as inserting a component, is applied.
+    ///
+    /// If a handler consumed the event, its contents are no longer available.
+    #[allow(unused_variables)]
+    fn after(&mut self, event: &InterceptedEvent) {}
+}
+
+/// Decides if an event continues broadcasting. Returned from
+/// [`Interceptor::before`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum Verdict {
+    /// Broadcast the event as usual.
+    #[default]
+    Continue,
+    /// Drop the event without broadcasting it.
+    Veto,
+}
+
+/// A type-erased view of an event passed to an [`Interceptor`].
+pub struct InterceptedEvent<'a> {
+    id: EventId,
+    name: &'a str,
+    type_id: Option<TypeId>,
+    target: Option<EntityId>,
+    depth: u32,
+    ptr: Option<NonNull<u8>>,
+    _marker: PhantomData<&'a mut ()>,
+}
+
+impl<'a> InterceptedEvent<'a> {
+    pub(crate) fn new(
+        item: &EventQueueItem,
+        global_events: &'a GlobalEvents,
+        targeted_events: &'a TargetedEvents,
+    ) -> Self {
+        let (id, name, type_id, target) = match item.meta {
+            EventMeta::Global { idx } => {
+                // SAFETY: Queued events have valid indices.
+                let info = unsafe { global_events.get_by_index(idx).unwrap_unchecked() };
+                (
+                    EventId::Global(info.id()),
+                    info.name(),
+                    info.type_id(),
+                    None,
+                )
+            }
+            EventMeta::Targeted { idx, target } => {
+                // SAFETY: Queued events have valid indices.
+                let info = unsafe { targeted_events.get_by_index(idx).unwrap_unchecked() };
+                let id = EventId::Targeted(info.id());
+                (id, info.name(), info.type_id(), Some(target))
+            }
+        };
+
+        Self {
+            id,
+            name,
+            type_id,
+            target,
+            depth: item.depth,
+            ptr: Some(item.event),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Marks the event as consumed, making its contents unavailable.
+    pub(crate) fn consume(&mut self) {
+        self.ptr = None;
+    }
+
+    /// Returns the [`EventId`] of the event.
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// Returns the name of the event.
+    ///
+    /// This name is intended for debugging purposes and should not be relied
+    /// upon for correctness.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the target of the event if it is a targeted event.
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+
+    /// Returns the depth of the event. See [`World::set_max_event_depth`].
+    ///
+    /// [`World::set_max_event_depth`]: crate::world::World::set_max_event_depth
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns `true` if a handler consumed the event.
+    pub fn is_consumed(&self) -> bool {
+        self.ptr.is_none()
+    }
+
+    /// Returns a reference to the event if it is of type `E` and was not
+    /// consumed. Lifetimes in `E` are shortened to `'a`, as with
+    /// [`Receiver`].
+    ///
+    /// [`Receiver`]: crate::event::Receiver
+    pub fn downcast_ref<E: Event>(&self) -> Option<&E::This<'a>> {
+        let ptr = self
+            .ptr
+            .filter(|_| self.type_id == Some(TypeId::of::<E::This<'static>>()))?;
+        // SAFETY: The type IDs match and the event is alive.
+        Some(unsafe { ptr.cast::<E::This<'a>>().as_ref() })
+    }
+
+    /// Returns a mutable reference to the event if it is of type `E` and was
+    /// not consumed. Lifetimes in `E` are shortened to `'a`, as with
+    /// [`ReceiverMut`].
+    ///
+    /// [`ReceiverMut`]: crate::event::ReceiverMut
+    pub fn downcast_mut<E: Event>(&mut self) -> Option<&mut E::This<'a>> {
+        let ptr = self
+            .ptr
+            .filter(|_| self.type_id == Some(TypeId::of::<E::This<'static>>()))?;
+        // SAFETY: The type IDs match, the event is alive, and the world gives
+        // up its access to the event while interceptors run.
+        Some(unsafe { ptr.cast::<E::This<'a>>().as_mut() })
+    }
+
+    /// Returns a pointer to the event, or `None` if it was consumed.
+    pub fn as_ptr(&self) -> Option<NonNull<u8>> {
+        self.ptr
+    }
+}
+
+impl fmt::Debug for InterceptedEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterceptedEvent")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("target", &self.target)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The interceptors of a world, in the order they were added.
+#[derive(Default)]
+pub(crate) struct Interceptors {
+    pub(crate) list: Vec<Box<dyn Interceptor>>,
+}
+
+impl Interceptors {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs [`Interceptor::before`] until an interceptor vetoes the event.
+    pub(crate) fn before(&mut self, event: &mut InterceptedEvent) -> Verdict {
+        for interceptor in &mut self.list {
+            if interceptor.before(event) == Verdict::Veto {
+                return Verdict::Veto;
+            }
+        }
+
+        Verdict::Continue
+    }
+
+    pub(crate) fn after(&mut self, event: &InterceptedEvent) {
+        for interceptor in &mut self.list {
+            interceptor.after(event);
+        }
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interceptors")
+            .field("len", &self.list.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,184 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr::NonNull;
+
+use super::{EventKind, EventMeta, EventPtr};
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::component::ComponentInfo;
+use crate::entity::{EntityId, EntityLocation};
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::query::Query;
+use crate::world::{UnsafeWorldCell, World};
+
+/// A [`HandlerParam`] which receives the [`Insert`] and [`Remove`] events of
+/// every component, including components added to the world after the
+/// handler. [`Take`] events are received as removals.
+///
+/// This is intended for systems which must observe all component changes,
+/// such as replication and undo, without adding a handler for every component
+/// type. Like the handlers of the typed events, the handler runs before the
+/// component is inserted or removed. Components inserted or removed in other
+/// ways, such as by [`InsertBundle`] or [`Despawn`], are not reported.
+///
+/// The handler may not have any other parameter which sets the received
+/// event. It cannot be added with [`World::add_parallel_handler`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{LifecycleKind, LifecycleReceiver};
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: LifecycleReceiver| {
+///     let verb = match r.kind {
+///         LifecycleKind::Insert => "inserted",
+///         LifecycleKind::Remove => "removed",
+///     };
+///
+///     println!("{verb} {} on {:?}", r.component.name(), r.entity);
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100)); // Prints "inserted ...Health on ...".
+/// world.remove::<Health>(e); // Prints "removed ...Health on ...".
+/// ```
+///
+/// [`Insert`]: crate::event::Insert
+/// [`Remove`]: crate::event::Remove
+/// [`Take`]: crate::event::Take
+/// [`InsertBundle`]: crate::event::InsertBundle
+/// [`Despawn`]: crate::event::Despawn
+/// [`World::add_parallel_handler`]: crate::world::World::add_parallel_handler
+#[derive(Clone, Copy)]
+pub struct LifecycleReceiver<'a> {
+    /// Whether the component is being inserted or removed.
+    pub kind: LifecycleKind,
+    /// The component being inserted or removed.
+    pub component: &'a ComponentInfo,
+    /// The entity the component is inserted on or removed from.
+    pub entity: EntityId,
+    /// A pointer to the component value, valid for reads for the lifetime
+    /// `'a`. For insertions, this is the inserted value. For removals, this
+    /// is the value being removed, or `None` if the entity doesn't have the
+    /// component.
+    pub value: Option<NonNull<u8>>,
+}
+
+/// Whether a component is inserted or removed. See [`LifecycleReceiver`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum LifecycleKind {
+    /// The component is inserted, possibly replacing an existing value.
+    Insert,
+    /// The component is removed.
+    Remove,
+}
+
+unsafe impl HandlerParam for LifecycleReceiver<'_> {
+    type State = ();
+
+    type This<'a> = LifecycleReceiver<'a>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        let events: Vec<_> = world
+            .targeted_events()
+            .iter()
+            .filter(|info| info.kind().is_lifecycle())
+            .map(|info| info.id())
+            .collect();
+
+        config.set_received_events(events);
+        config.set_received_event_access(Access::Read);
+        config.receives_lifecycle_events = true;
+
+        // The removed components are read outside of the handler's component
+        // access.
+        config.set_thread_local();
+
+        // Receive the events regardless of the target's components.
+        let (ca, ()) = <() as Query>::init(world, config)?;
+        config.set_targeted_event_component_access(ca.clone());
+        config.push_component_access(ca);
+
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        let EventMeta::Targeted { idx, target } = event_ptr.meta() else {
+            unreachable!("lifecycle events are targeted")
+        };
+
+        // SAFETY: The received event exists.
+        let kind = unsafe { world.targeted_events().get_by_index(idx).unwrap_unchecked() }.kind();
+
+        let (kind, component_idx) = match kind {
+            EventKind::Insert { component_idx } => (LifecycleKind::Insert, component_idx),
+            EventKind::Remove { component_idx } | EventKind::Take { component_idx, .. } => {
+                (LifecycleKind::Remove, component_idx)
+            }
+            _ => unreachable!("only lifecycle events are received"),
+        };
+
+        // SAFETY: Components of existing events exist.
+        let component = unsafe {
+            world
+                .components()
+                .get_by_index(component_idx)
+                .unwrap_unchecked()
+        };
+
+        let value = match kind {
+            // `Insert<C>` is `repr(transparent)`.
+            LifecycleKind::Insert => Some(event_ptr.as_ptr()),
+            LifecycleKind::Remove => {
+                // SAFETY: The target exists.
+                let arch = unsafe {
+                    world
+                        .archetypes()
+                        .get(target_location.archetype)
+                        .unwrap_unchecked()
+                };
+
+                arch.column_of(component_idx).map(|col| {
+                    let offset = component.layout().size() * target_location.row.0 as usize;
+                    // SAFETY: The row of the target is in bounds.
+                    unsafe { NonNull::new_unchecked(col.data().as_ptr().add(offset)) }
+                })
+            }
+        };
+
+        LifecycleReceiver {
+            kind,
+            component,
+            entity: target,
+            value,
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl fmt::Debug for LifecycleReceiver<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LifecycleReceiver")
+            .field("kind", &self.kind)
+            .field("component", &self.component.name())
+            .field("entity", &self.entity)
+            .field("value", &self.value)
+            .finish()
+    }
+}
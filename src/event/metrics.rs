@@ -0,0 +1,39 @@
+use core::time::Duration;
+
+/// Statistics about the broadcasts of an event.
+///
+/// Obtained from [`GlobalEventInfo::metrics`], [`TargetedEventInfo::metrics`]
+/// or [`World::event_metrics`]. Only broadcasts which ran the event's handlers
+/// are counted. Targeted events sent to entities which don't exist are not.
+///
+/// [`GlobalEventInfo::metrics`]: super::GlobalEventInfo::metrics
+/// [`TargetedEventInfo::metrics`]: super::TargetedEventInfo::metrics
+/// [`World::event_metrics`]: crate::world::World::event_metrics
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct EventMetrics {
+    count: u64,
+    total_time: Duration,
+}
+
+impl EventMetrics {
+    /// Returns the number of times the event was broadcast.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the total time spent running the handlers of the event.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Returns the average time spent running the handlers of the event per
+    /// broadcast, or `None` if the event was never broadcast.
+    pub fn mean_time(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total_time.div_f64(self.count as f64))
+    }
+
+    pub(crate) fn record(&mut self, time: Duration) {
+        self.count += 1;
+        self.total_time += time;
+    }
+}
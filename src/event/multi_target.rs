@@ -0,0 +1,152 @@
+use core::fmt;
+
+use super::{EventPtr, GlobalEvent};
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::entity::{EntityId, EntityLocation};
+use crate::fetch::{FetcherState, GetManyMutError};
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::query::Query;
+use crate::world::{UnsafeWorldCell, World};
+
+/// A [`GlobalEvent`] which involves a fixed number `N` of target entities,
+/// such as a collision between two entities.
+///
+/// The components of the targets can be accessed with [`ReceiverTargets`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::MultiTargetEvent;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Collision {
+///     a: EntityId,
+///     b: EntityId,
+/// }
+///
+/// impl MultiTargetEvent<2> for Collision {
+///     fn targets(event: &Self) -> [EntityId; 2] {
+///         [event.a, event.b]
+///     }
+/// }
+/// ```
+pub trait MultiTargetEvent<const N: usize>: GlobalEvent {
+    /// Returns the targets of the event.
+    fn targets(event: &Self::This<'_>) -> [EntityId; N];
+}
+
+/// A [`HandlerParam`] which listens for the [`MultiTargetEvent`] `E` and
+/// executes the query `Q` for each of its `N` targets.
+///
+/// Unlike a [`Receiver`] of a [`TargetedEvent`], the handler always runs.
+/// The query results are in the order of [`MultiTargetEvent::targets`], or
+/// a [`GetManyMutError`] if a target doesn't exist, doesn't match the query,
+/// or appears more than once.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{MultiTargetEvent, ReceiverTargets};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Collision(EntityId, EntityId);
+///
+/// impl MultiTargetEvent<2> for Collision {
+///     fn targets(event: &Self) -> [EntityId; 2] {
+///         [event.0, event.1]
+///     }
+/// }
+///
+/// #[derive(Component)]
+/// struct Velocity(f32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: ReceiverTargets<Collision, &mut Velocity, 2>| {
+///     if let Ok([a, b]) = r.query {
+///         std::mem::swap(&mut a.0, &mut b.0);
+///     }
+/// });
+///
+/// let e1 = world.spawn();
+/// world.insert(e1, Velocity(1.0));
+/// let e2 = world.spawn();
+/// world.insert(e2, Velocity(-1.0));
+///
+/// world.send(Collision(e1, e2));
+///
+/// assert_eq!(world.get::<Velocity>(e1).unwrap().0, -1.0);
+/// assert_eq!(world.get::<Velocity>(e2).unwrap().0, 1.0);
+/// ```
+///
+/// [`Receiver`]: super::Receiver
+/// [`TargetedEvent`]: super::TargetedEvent
+pub struct ReceiverTargets<'a, E: MultiTargetEvent<N>, Q: Query, const N: usize> {
+    /// A reference to the received event.
+    pub event: &'a E::This<'a>,
+    /// The results of the query for each target.
+    pub query: Result<[Q::This<'a>; N], GetManyMutError>,
+}
+
+unsafe impl<E, Q, const N: usize> HandlerParam for ReceiverTargets<'_, E, Q, N>
+where
+    E: MultiTargetEvent<N>,
+    Q: Query + 'static,
+{
+    type State = FetcherState<Q>;
+
+    type This<'a> = ReceiverTargets<'a, E, Q, N>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        let event_id = world.add_global_event::<E>();
+
+        config.set_received_event(event_id);
+        config.set_received_event_access(Access::Read);
+
+        FetcherState::init(world, config)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        // SAFETY:
+        // - We have permission to access the event immutably.
+        // - Handler was configured to listen for `E`.
+        let event = event_ptr.as_ptr().cast::<E::This<'a>>().as_ref();
+
+        // SAFETY: Handler has access to the components of the query.
+        let query = state.get_many_mut(world.entities(), E::targets(event));
+
+        ReceiverTargets { event, query }
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+impl<'a, E, Q, const N: usize> fmt::Debug for ReceiverTargets<'a, E, Q, N>
+where
+    E: MultiTargetEvent<N>,
+    E::This<'a>: fmt::Debug,
+    Q: Query,
+    Q::This<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceiverTargets")
+            .field("event", &self.event)
+            .field("query", &self.query)
+            .finish()
+    }
+}
@@ -0,0 +1,563 @@
+use alloc::rc::Rc;
+use core::any::{Any, TypeId};
+use core::fmt;
+use std::io::{self, Read, Write};
+
+use super::{Event, EventId, EventLog, RecordedEvent};
+use crate::entity::EntityId;
+use crate::map::{HashMap, TypeIdMap};
+
+/// Encodes and decodes events of type `E` for a persisted [`EventLog`].
+///
+/// This is implemented for pairs of closures `(encode, decode)` with the
+/// signatures of [`encode`](Self::encode) and [`decode`](Self::decode).
+pub trait EventCodec<E>: 'static {
+    /// Appends the encoded event to `out`.
+    fn encode(&self, event: &E, out: &mut Vec<u8>);
+
+    /// Decodes an event from the bytes produced by [`encode`](Self::encode).
+    fn decode(&self, bytes: &[u8]) -> io::Result<E>;
+}
+
+impl<E, Enc, Dec> EventCodec<E> for (Enc, Dec)
+where
+    Enc: Fn(&E, &mut Vec<u8>) + 'static,
+    Dec: Fn(&[u8]) -> io::Result<E> + 'static,
+{
+    fn encode(&self, event: &E, out: &mut Vec<u8>) {
+        (self.0)(event, out)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> io::Result<E> {
+        (self.1)(bytes)
+    }
+}
+
+type EncodeFn = Rc<dyn Fn(&dyn Any, &mut Vec<u8>)>;
+
+type DecodeFn = Rc<dyn Fn(u64, u64, Option<EntityId>, &[u8]) -> io::Result<RecordedEvent>>;
+
+#[derive(Clone)]
+struct CodecEntry {
+    name: Rc<str>,
+    encode: EncodeFn,
+}
+
+/// The [`EventCodec`]s used to write and read a persisted [`EventLog`],
+/// registered under a name for every event type.
+///
+/// Events added at runtime are recorded already encoded with the
+/// [`SerializeFn`] of their [`EventDescriptor`], so they only need to be
+/// registered under a name with [`register_untyped`](Self::register_untyped).
+///
+/// Names are stored in the log to identify the type of each event, so they
+/// must stay the same between the program which writes a log and the one
+/// which reads it.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{EventCodecs, EventLog, EventLogWriter};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent, Clone)]
+/// struct Score(u32);
+///
+/// let mut codecs = EventCodecs::new();
+/// codecs.register::<Score, _>(
+///     "score",
+///     (
+///         |e: &Score, out: &mut Vec<u8>| out.extend(e.0.to_le_bytes()),
+///         |b: &[u8]| Ok(Score(u32::from_le_bytes(b.try_into().unwrap()))),
+///     ),
+/// );
+///
+/// let mut world = World::new();
+/// world.record::<Score>();
+/// world.start_recording();
+///
+/// let mut writer = EventLogWriter::new(vec![], codecs.clone()).unwrap();
+///
+/// world.send(Score(3));
+/// writer.append(world.recorded_events().unwrap()).unwrap();
+/// world.send(Score(4));
+/// // Only the new event is written.
+/// writer.append(world.recorded_events().unwrap()).unwrap();
+///
+/// let bytes = writer.into_inner();
+/// let log = EventLog::read(&bytes[..], &codecs).unwrap();
+///
+/// assert_eq!(log.len(), 2);
+/// assert_eq!(log.iter().nth(1).unwrap().downcast_ref::<Score>().unwrap().0, 4);
+/// ```
+///
+/// [`SerializeFn`]: super::SerializeFn
+/// [`EventDescriptor`]: super::EventDescriptor
+#[derive(Clone, Default)]
+pub struct EventCodecs {
+    by_type: TypeIdMap<CodecEntry>,
+    untyped: HashMap<EventId, Rc<str>>,
+    by_name: HashMap<Rc<str>, DecodeFn>,
+}
+
+impl EventCodecs {
+    /// Creates an empty set of codecs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the codec for events of type `E` under `name`, replacing the
+    /// previous codec of `E` or `name`.
+    pub fn register<E, C>(&mut self, name: &str, codec: C)
+    where
+        E: for<'a> Event<This<'a> = E> + Clone + 'static,
+        C: EventCodec<E>,
+    {
+        let codec = Rc::new(codec);
+        let name: Rc<str> = name.into();
+
+        let c = codec.clone();
+        let encode: EncodeFn = Rc::new(move |event: &dyn Any, out: &mut Vec<u8>| {
+            c.encode(event.downcast_ref::<E>().unwrap(), out)
+        });
+
+        let decode: DecodeFn = Rc::new(move |ordinal, tick, target, bytes: &[u8]| {
+            let event = codec.decode(bytes)?;
+            Ok(RecordedEvent::new(ordinal, tick, target, event))
+        });
+
+        self.by_type.insert(
+            TypeId::of::<E>(),
+            CodecEntry {
+                name: name.clone(),
+                encode,
+            },
+        );
+        self.by_name.insert(name, decode);
+    }
+
+    /// Registers the event added at runtime with the given ID under `name`,
+    /// replacing the previous codec of `id` or `name`.
+    ///
+    /// The event is encoded and decoded with the [`SerializeFn`] and
+    /// [`DeserializeFn`] of its [`EventDescriptor`], and should be recorded
+    /// with [`World::record_untyped`]. Since the log stores the ID of the
+    /// event, the world a log is replayed into must add its events in the same
+    /// order as the recorded world.
+    ///
+    /// [`SerializeFn`]: super::SerializeFn
+    /// [`DeserializeFn`]: super::DeserializeFn
+    /// [`EventDescriptor`]: super::EventDescriptor
+    /// [`World::record_untyped`]: crate::world::World::record_untyped
+    pub fn register_untyped(&mut self, name: &str, id: EventId) {
+        let name: Rc<str> = name.into();
+
+        let n = name.clone();
+        let decode: DecodeFn = Rc::new(move |ordinal, tick, target, bytes: &[u8]| {
+            if matches!(id, EventId::Targeted(_)) != target.is_some() {
+                return Err(invalid(&format!("invalid target for event `{n}`")));
+            }
+
+            Ok(RecordedEvent::new_serialized(
+                ordinal,
+                tick,
+                target,
+                String::from(&*n).into(),
+                id,
+                bytes.to_vec(),
+            ))
+        });
+
+        self.untyped.insert(id, name.clone());
+        self.by_name.insert(name, decode);
+    }
+}
+
+impl fmt::Debug for EventCodecs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventCodecs")
+            .field("names", &self.by_name.keys())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Identifies the format of a persisted event log.
+const MAGIC: &[u8; 8] = b"EVLOG\0\0\x01";
+
+/// An append-only writer of an [`EventLog`] to a byte stream such as a file.
+/// Requires the `persist` feature.
+///
+/// Every call to [`append`](Self::append) writes the events of the log which
+/// were not written yet, so the log returned by [`World::recorded_events`]
+/// can be appended periodically while the world is recording. Logs are read
+/// back with [`EventLog::read`].
+///
+/// See [`EventCodecs`] for an example.
+///
+/// [`World::recorded_events`]: crate::world::World::recorded_events
+pub struct EventLogWriter<W> {
+    writer: W,
+    codecs: EventCodecs,
+    /// The ordinal of the next event to write.
+    next_ordinal: u64,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> EventLogWriter<W> {
+    /// Creates a new writer and writes the header of the log.
+    pub fn new(mut writer: W, codecs: EventCodecs) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+
+        Ok(Self {
+            writer,
+            codecs,
+            next_ordinal: 0,
+            buf: vec![],
+        })
+    }
+
+    /// Writes the events of `log` which were not written before, then
+    /// flushes the underlying writer.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] without
+    /// writing anything if no codec is registered for one of the events.
+    pub fn append(&mut self, log: &EventLog) -> io::Result<()> {
+        self.buf.clear();
+
+        let mut next_ordinal = self.next_ordinal;
+
+        for event in log.iter().filter(|e| e.ordinal() >= self.next_ordinal) {
+            let name = match event.serialized() {
+                Some((id, _)) => self.codecs.untyped.get(&id),
+                None => self
+                    .codecs
+                    .by_type
+                    .get(&event.payload().type_id())
+                    .map(|entry| &entry.name),
+            };
+
+            let Some(name) = name else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("no codec registered for event `{}`", event.name()),
+                ));
+            };
+
+            let buf = &mut self.buf;
+
+            buf.extend(event.ordinal().to_le_bytes());
+            buf.extend(event.tick().to_le_bytes());
+
+            match event.target() {
+                Some(target) => {
+                    buf.push(1);
+                    buf.extend(target.index().0.to_le_bytes());
+                    buf.extend(target.generation().to_le_bytes());
+                }
+                None => buf.push(0),
+            }
+
+            let name_len = u16::try_from(name.len()).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "event name is too long")
+            })?;
+
+            buf.extend(name_len.to_le_bytes());
+            buf.extend(name.as_bytes());
+
+            // Reserve the length of the payload and fill it in after encoding.
+            let len_pos = buf.len();
+            buf.extend([0; 4]);
+
+            match event.serialized() {
+                // Events added at runtime were encoded when they were recorded.
+                Some((_, bytes)) => buf.extend_from_slice(bytes),
+                None => {
+                    let entry = &self.codecs.by_type[&event.payload().type_id()];
+                    (entry.encode)(event.payload(), buf);
+                }
+            }
+
+            let payload_len = u32::try_from(buf.len() - len_pos - 4).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "encoded event is too long")
+            })?;
+
+            buf[len_pos..len_pos + 4].copy_from_slice(&payload_len.to_le_bytes());
+
+            next_ordinal = event.ordinal() + 1;
+        }
+
+        self.writer.write_all(&self.buf)?;
+        self.writer.flush()?;
+
+        self.next_ordinal = next_ordinal;
+
+        Ok(())
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consumes the `EventLogWriter`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for EventLogWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventLogWriter")
+            .field("writer", &self.writer)
+            .field("codecs", &self.codecs)
+            .field("next_ordinal", &self.next_ordinal)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads a log written by an [`EventLogWriter`]. See [`EventLog::read`].
+pub(super) fn read_log<R: Read>(mut reader: R, codecs: &EventCodecs) -> io::Result<EventLog> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+
+    if &magic != MAGIC {
+        return Err(invalid("not an event log"));
+    }
+
+    let mut log = EventLog::new();
+
+    loop {
+        match read_event(&mut reader, codecs) {
+            Ok(Some(event)) => log.push(event),
+            Ok(None) => break,
+            // A truncated final event.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(log)
+}
+
+/// Reads the next event of a log, or `None` at the end of the log.
+fn read_event<R: Read>(reader: &mut R, codecs: &EventCodecs) -> io::Result<Option<RecordedEvent>> {
+    let mut ordinal = [0; 8];
+
+    // Check for the end of the log before reading the rest of the event.
+    match reader.read(&mut ordinal[..1])? {
+        0 => return Ok(None),
+        _ => reader.read_exact(&mut ordinal[1..])?,
+    }
+
+    let ordinal = u64::from_le_bytes(ordinal);
+    let tick = u64::from_le_bytes(read_array(reader)?);
+
+    let target = match read_array::<_, 1>(reader)? {
+        [0] => None,
+        [1] => {
+            let index = u32::from_le_bytes(read_array(reader)?);
+            let generation = u32::from_le_bytes(read_array(reader)?);
+            Some(EntityId::new(index, generation).ok_or_else(|| invalid("invalid entity ID"))?)
+        }
+        _ => return Err(invalid("invalid target")),
+    };
+
+    let name_len = u16::from_le_bytes(read_array(reader)?);
+    let mut name = vec![0; name_len as usize];
+    reader.read_exact(&mut name)?;
+    let name = String::from_utf8(name).map_err(|_| invalid("invalid event name"))?;
+
+    let payload_len = u32::from_le_bytes(read_array(reader)?);
+
+    // The length may be corrupted, so the buffer only grows as the payload is
+    // actually read instead of being allocated up front.
+    let mut payload = vec![];
+    reader
+        .by_ref()
+        .take(u64::from(payload_len))
+        .read_to_end(&mut payload)?;
+
+    if payload.len() != payload_len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let Some(decode) = codecs.by_name.get(name.as_str()) else {
+        return Err(invalid(&format!("no codec registered for event `{name}`")));
+    };
+
+    decode(ordinal, tick, target, &payload).map(Some)
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(TargetedEvent, Clone)]
+    struct Damage(u32);
+
+    fn codecs() -> EventCodecs {
+        let mut codecs = EventCodecs::new();
+        codecs.register::<Damage, _>(
+            "damage",
+            (
+                |e: &Damage, out: &mut Vec<u8>| out.extend(e.0.to_le_bytes()),
+                |b: &[u8]| Ok(Damage(u32::from_le_bytes(b.try_into().unwrap()))),
+            ),
+        );
+        codecs
+    }
+
+    #[derive(Component)]
+    struct Health(u32);
+
+    fn setup() -> (World, EntityId) {
+        let mut world = World::new();
+        let e = world.spawn();
+        world.insert(e, Health(100));
+        world.add_handler(|r: Receiver<Damage, &mut Health>| r.query.0 -= r.event.0);
+        world.record::<Damage>();
+        (world, e)
+    }
+
+    #[test]
+    fn write_and_replay() {
+        let (mut world, e) = setup();
+        world.start_recording();
+
+        let mut writer = EventLogWriter::new(vec![], codecs()).unwrap();
+
+        world.send_to(e, Damage(10));
+        world.send_to(e, Damage(20));
+        writer.append(world.recorded_events().unwrap()).unwrap();
+        world.send_to(e, Damage(30));
+        writer.append(world.recorded_events().unwrap()).unwrap();
+
+        let mut bytes = writer.into_inner();
+
+        // Simulate a crash while writing another event.
+        let len = bytes.len();
+        bytes.extend_from_within(len - 10..);
+
+        let log = EventLog::read(&bytes[..], &codecs()).unwrap();
+        assert_eq!(log.len(), 3);
+
+        let (mut replayed, e2) = setup();
+        log.replay(&mut replayed);
+
+        assert_eq!(replayed.get::<Health>(e2).unwrap().0, 40);
+        assert_eq!(world.get::<Health>(e).unwrap().0, 40);
+
+        assert!(EventLog::read(&bytes[..], &EventCodecs::new()).is_err());
+    }
+
+    #[test]
+    fn corrupted_payload_len() {
+        let (mut world, e) = setup();
+        world.start_recording();
+        world.send_to(e, Damage(10));
+
+        let mut writer = EventLogWriter::new(vec![], codecs()).unwrap();
+        writer.append(world.recorded_events().unwrap()).unwrap();
+        let mut bytes = writer.into_inner();
+
+        // Overwrite the payload length with the largest possible length. The
+        // event is treated as truncated.
+        let len = bytes.len();
+        bytes[len - 8..len - 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let log = EventLog::read(&bytes[..], &codecs()).unwrap();
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn write_and_replay_untyped() {
+        use core::alloc::Layout;
+        use core::cell::RefCell;
+        use core::ptr::NonNull;
+
+        use crate::event::{EventDescriptor, EventKind, GlobalEventId, UntypedReceiver};
+        use crate::mutability::Mutability;
+
+        fn setup() -> (World, GlobalEventId, Rc<RefCell<Vec<[u8; 4]>>>) {
+            let mut world = World::new();
+
+            let id = unsafe {
+                world.add_global_event_with_descriptor(EventDescriptor {
+                    name: "Dynamic".into(),
+                    type_id: None,
+                    kind: EventKind::Normal,
+                    layout: Layout::new::<[u8; 4]>(),
+                    drop: None,
+                    mutability: Mutability::Immutable,
+                    clone: None,
+                    serialize: Some(|event: NonNull<u8>, out: &mut Vec<u8>| {
+                        out.extend(event.cast::<[u8; 4]>().read())
+                    }),
+                    deserialize: Some(
+                        |bytes: &[u8], event: NonNull<u8>| match <[u8; 4]>::try_from(bytes) {
+                            Ok(bytes) => {
+                                event.cast::<[u8; 4]>().write(bytes);
+                                true
+                            }
+                            Err(_) => false,
+                        },
+                    ),
+                })
+            };
+
+            let received = Rc::new(RefCell::new(vec![]));
+
+            let r = received.clone();
+            world.add_handler(
+                (move |e: UntypedReceiver| {
+                    r.borrow_mut()
+                        .push(unsafe { e.event.cast::<[u8; 4]>().read() })
+                })
+                .receive_events([id]),
+            );
+
+            world.record_untyped(id.into());
+
+            (world, id, received)
+        }
+
+        let (mut world, id, _) = setup();
+        world.start_recording();
+
+        world.send_bytes(id, &[1, 2, 3, 4]);
+
+        let log = world.stop_recording().unwrap();
+        assert_eq!(
+            log.iter().next().unwrap().serialized(),
+            Some((id.into(), &[1, 2, 3, 4][..]))
+        );
+
+        let mut codecs = codecs();
+        codecs.register_untyped("dynamic", id.into());
+
+        let mut writer = EventLogWriter::new(vec![], codecs.clone()).unwrap();
+        writer.append(&log).unwrap();
+        let bytes = writer.into_inner();
+
+        let log = EventLog::read(&bytes[..], &codecs).unwrap();
+        assert_eq!(log.len(), 1);
+
+        let (mut replayed, _, received) = setup();
+        log.replay(&mut replayed);
+
+        assert_eq!(*received.borrow(), [[1, 2, 3, 4]]);
+
+        assert!(EventLog::read(&bytes[..], &self::codecs()).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::{fmt, mem};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use super::{GlobalEvent, TargetedEvent};
+use crate::entity::EntityId;
+use crate::world::World;
+
+/// A queued event, which is sent by calling the function.
+type QueuedEvent = Box<dyn FnOnce(&mut World) + Send>;
+
+/// A thread-safe queue of events waiting to be sent to a [`World`].
+///
+/// Unlike the world itself, `EventQueue` is `Send` and `Sync`, so events can
+/// be pushed from other threads such as a network receiver or an audio
+/// callback. The events are sent on the world's thread by calling
+/// [`World::drain_queue`]. Cloning an `EventQueue` produces another handle to
+/// the same queue.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::EventQueue;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Packet(Vec<u8>);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Packet>| println!("received {:?}", r.event.0));
+///
+/// let queue = EventQueue::new();
+///
+/// let handle = queue.clone();
+/// std::thread::spawn(move || handle.push(Packet(vec![1, 2, 3])))
+///     .join()
+///     .unwrap();
+///
+/// world.drain_queue(&queue); // Prints "received [1, 2, 3]".
+/// ```
+#[derive(Clone, Default)]
+pub struct EventQueue {
+    events: Arc<Mutex<Vec<QueuedEvent>>>,
+}
+
+impl EventQueue {
+    /// Creates a new, empty event queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a global event to the end of the queue.
+    pub fn push<E: GlobalEvent + Send + 'static>(&self, event: E) {
+        self.push_fn(Box::new(move |world| world.send(event)));
+    }
+
+    /// Adds a targeted event to the end of the queue.
+    pub fn push_to<E: TargetedEvent + Send + 'static>(&self, target: EntityId, event: E) {
+        self.push_fn(Box::new(move |world| world.send_to(target, event)));
+    }
+
+    /// Returns the number of events in the queue.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if the queue contains no events.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn push_fn(&self, f: QueuedEvent) {
+        self.lock().push(f);
+    }
+
+    /// Removes all events from the queue and returns them in the order they
+    /// were pushed.
+    pub(crate) fn take(&self) -> Vec<QueuedEvent> {
+        mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<QueuedEvent>> {
+        // The queue is never left in an inconsistent state, so poisoning can
+        // be ignored.
+        self.events.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for EventQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventQueue")
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,327 @@
+use alloc::alloc::{alloc, dealloc, handle_alloc_error};
+use alloc::collections::BTreeMap;
+use core::alloc::Layout;
+use core::fmt;
+use core::ptr::{self, NonNull};
+use core::time::Duration;
+
+use bumpalo::Bump;
+
+use super::EventId;
+use crate::drop::DropFn;
+use crate::entity::EntityId;
+
+/// Limits how often an event is broadcast. See [`World::rate_limit`].
+///
+/// [`World::rate_limit`]: crate::world::World::rate_limit
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RateLimit {
+    window: Window,
+    overflow: Overflow,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Window {
+    PerFlush(u32),
+    MinInterval(Duration),
+}
+
+impl RateLimit {
+    /// Broadcasts at most `max` events of the type within a flush of the
+    /// event queue.
+    pub const fn per_flush(max: u32) -> Self {
+        Self {
+            window: Window::PerFlush(max),
+            overflow: Overflow::Drop,
+        }
+    }
+
+    /// Broadcasts an event of the type only if at least `interval` has passed
+    /// since the last one was broadcast, as measured by the [`Time`] of the
+    /// world.
+    ///
+    /// [`Time`]: crate::time::Time
+    pub const fn min_interval(interval: Duration) -> Self {
+        Self {
+            window: Window::MinInterval(interval),
+            overflow: Overflow::Drop,
+        }
+    }
+
+    /// Sets what happens to events over the limit. Defaults to
+    /// [`Overflow::Drop`].
+    pub const fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// What happens to events over a [`RateLimit`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Overflow {
+    /// The events are dropped without being broadcast.
+    #[default]
+    Drop,
+    /// Only the latest event over the limit is kept, and the others are
+    /// dropped. The kept event is broadcast at the end of the flush once the
+    /// limit allows it: for [`RateLimit::per_flush`] at the end of the same
+    /// flush, and for [`RateLimit::min_interval`] at the end of the first
+    /// flush after the interval has passed. It is dropped instead if another
+    /// event of the type is broadcast first.
+    ///
+    /// Events which may borrow data are never kept past the end of the flush,
+    /// since the data could be gone by the next one. See
+    /// [`World::rate_limit_event`].
+    ///
+    /// [`World::rate_limit_event`]: crate::world::World::rate_limit_event
+    Coalesce,
+}
+
+/// The events which are rate limited.
+///
+/// See [`World::rate_limit`].
+///
+/// [`World::rate_limit`]: crate::world::World::rate_limit
+#[derive(Default)]
+pub(crate) struct RateLimiters {
+    map: BTreeMap<EventId, Limiter>,
+    /// Whether the next event checked was released by
+    /// [`RateLimiters::release`] and should bypass the limits.
+    bypass: bool,
+}
+
+impl RateLimiters {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Limits the event with the given ID, layout and drop function,
+    /// replacing any previous limit for it. Events held back by the previous
+    /// limit are dropped. If `keep_held` is `false`, held events are dropped
+    /// at the end of the flush instead of being kept for the next one.
+    pub(crate) fn insert(
+        &mut self,
+        event: EventId,
+        layout: Layout,
+        drop: DropFn,
+        keep_held: bool,
+        limit: RateLimit,
+    ) {
+        self.map.insert(
+            event,
+            Limiter {
+                limit,
+                count: 0,
+                last: None,
+                layout,
+                drop,
+                keep_held,
+                held: None,
+            },
+        );
+    }
+
+    /// Removes the limit of the event, dropping any event held back by it.
+    pub(crate) fn remove(&mut self, event: EventId) {
+        self.map.remove(&event);
+    }
+
+    /// Returns `true` if the event may be broadcast. Otherwise, the event
+    /// has been dropped or moved out of `event`.
+    ///
+    /// # Safety
+    ///
+    /// `event` must point to a valid instance of the event with the given ID
+    /// which the caller owns.
+    pub(crate) unsafe fn admit(
+        &mut self,
+        id: EventId,
+        event: NonNull<u8>,
+        target: Option<EntityId>,
+        now: Duration,
+    ) -> bool {
+        if core::mem::take(&mut self.bypass) {
+            return true;
+        }
+
+        match self.map.get_mut(&id) {
+            Some(limiter) => limiter.admit(event, target, now),
+            None => true,
+        }
+    }
+
+    /// Moves a held event which the limits now allow into `bump` and returns
+    /// it, together with its ID and target. The returned event bypasses the
+    /// limits when it is next passed to [`RateLimiters::admit`].
+    pub(crate) fn release(
+        &mut self,
+        now: Duration,
+        bump: &Bump,
+    ) -> Option<(EventId, NonNull<u8>, Option<EntityId>)> {
+        let (id, (event, target)) = self
+            .map
+            .iter_mut()
+            .find_map(|(id, limiter)| Some((*id, limiter.release(now, bump)?)))?;
+
+        self.bypass = true;
+
+        Some((id, event, target))
+    }
+
+    /// Resets the per-flush counts, and drops the held events which may not
+    /// outlive the flush.
+    pub(crate) fn end_flush(&mut self) {
+        self.bypass = false;
+        self.map
+            .values_mut()
+            .for_each(|limiter| limiter.end_flush());
+    }
+}
+
+impl fmt::Debug for RateLimiters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiters")
+            .field("len", &self.map.len())
+            .finish_non_exhaustive()
+    }
+}
+
+struct Limiter {
+    limit: RateLimit,
+    /// Number of events broadcast in this flush.
+    count: u32,
+    /// Time the last event was broadcast at.
+    last: Option<Duration>,
+    layout: Layout,
+    drop: DropFn,
+    /// Whether the held event may be kept for the next flush.
+    keep_held: bool,
+    held: Option<Held>,
+}
+
+/// An event moved into its own allocation.
+struct Held {
+    event: NonNull<u8>,
+    target: Option<EntityId>,
+}
+
+impl Limiter {
+    fn allows(&self, now: Duration) -> bool {
+        match self.limit.window {
+            Window::PerFlush(max) => self.count < max,
+            Window::MinInterval(interval) => self
+                .last
+                .map_or(true, |last| now.saturating_sub(last) >= interval),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `event` must point to a valid, owned instance of the limited event.
+    unsafe fn admit(
+        &mut self,
+        event: NonNull<u8>,
+        target: Option<EntityId>,
+        now: Duration,
+    ) -> bool {
+        if self.allows(now) {
+            self.count += 1;
+            self.last = Some(now);
+            // The held event is superseded by this one.
+            self.discard_held();
+            return true;
+        }
+
+        match self.limit.overflow {
+            Overflow::Drop => {
+                if let Some(drop) = self.drop {
+                    drop(event);
+                }
+            }
+            Overflow::Coalesce => {
+                self.discard_held();
+
+                let dst = if self.layout.size() == 0 {
+                    NonNull::new_unchecked(self.layout.align() as *mut u8)
+                } else {
+                    let Some(dst) = NonNull::new(alloc(self.layout)) else {
+                        handle_alloc_error(self.layout)
+                    };
+                    dst
+                };
+
+                ptr::copy_nonoverlapping(event.as_ptr(), dst.as_ptr(), self.layout.size());
+
+                self.held = Some(Held { event: dst, target });
+            }
+        }
+
+        false
+    }
+
+    fn release(&mut self, now: Duration, bump: &Bump) -> Option<(NonNull<u8>, Option<EntityId>)> {
+        let released = match self.limit.window {
+            // Held events are broadcast at the end of the flush regardless
+            // of the count.
+            Window::PerFlush(_) => self.held.take(),
+            Window::MinInterval(_) if self.allows(now) => self.held.take(),
+            Window::MinInterval(_) => None,
+        };
+
+        let Held { event, target } = released?;
+
+        self.count += 1;
+        self.last = Some(now);
+
+        let dst = bump.alloc_layout(self.layout);
+
+        // SAFETY: The held event is moved into the bump allocation, and its
+        // own allocation is freed without dropping it.
+        unsafe {
+            ptr::copy_nonoverlapping(event.as_ptr(), dst.as_ptr(), self.layout.size());
+            self.dealloc(event);
+        }
+
+        Some((dst, target))
+    }
+
+    fn discard_held(&mut self) {
+        if let Some(held) = self.held.take() {
+            // SAFETY: The held event is valid and owned by the limiter.
+            unsafe {
+                if let Some(drop) = self.drop {
+                    drop(held.event);
+                }
+                self.dealloc(held.event);
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `event` must have been allocated by [`Limiter::admit`] and must not be
+    /// used afterwards.
+    unsafe fn dealloc(&self, event: NonNull<u8>) {
+        if self.layout.size() != 0 {
+            dealloc(event.as_ptr(), self.layout);
+        }
+    }
+
+    fn end_flush(&mut self) {
+        self.count = 0;
+
+        if !self.keep_held {
+            self.discard_held();
+        }
+    }
+}
+
+impl Drop for Limiter {
+    fn drop(&mut self) {
+        self.discard_held();
+    }
+}
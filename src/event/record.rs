@@ -0,0 +1,488 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::any::{self, Any, TypeId};
+use core::fmt;
+use core::ptr::NonNull;
+
+use super::{Event, EventId, Spawn};
+use crate::entity::EntityId;
+use crate::map::TypeIdMap;
+use crate::world::World;
+
+/// A log of the events sent into a [`World`] from outside of handlers,
+/// captured while recording.
+///
+/// Only events whose types were registered with [`World::record`], and events
+/// added at runtime which were registered with [`World::record_untyped`], are
+/// captured. Events sent by handlers are not part of the log, since replaying
+/// the log causes the handlers to send them again. Scheduled events are
+/// captured when [`World::advance`] broadcasts them, and are replayed in that
+/// position.
+///
+/// See [`World::start_recording`] for an example.
+#[derive(Default)]
+pub struct EventLog {
+    events: Vec<RecordedEvent>,
+}
+
+impl EventLog {
+    /// Creates a new, empty event log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of recorded events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if no events were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    #[cfg_attr(not(feature = "persist"), allow(dead_code))]
+    pub(crate) fn push(&mut self, event: RecordedEvent) {
+        self.events.push(event);
+    }
+
+    /// Returns an iterator over the recorded events in the order they were
+    /// sent.
+    pub fn iter(&self) -> core::slice::Iter<'_, RecordedEvent> {
+        self.events.iter()
+    }
+
+    /// Reads a log written by an [`EventLogWriter`]. Requires the `persist`
+    /// feature.
+    ///
+    /// A truncated final event, as left behind when the writing program
+    /// crashed, is ignored. Returns an error if the log is otherwise
+    /// malformed or contains an event without a codec in `codecs`.
+    ///
+    /// The log can then be replayed with [`EventLog::replay`]. See
+    /// [`EventCodecs`] for an example.
+    ///
+    /// [`EventLogWriter`]: super::EventLogWriter
+    /// [`EventCodecs`]: super::EventCodecs
+    #[cfg(feature = "persist")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "persist")))]
+    pub fn read<R: std::io::Read>(reader: R, codecs: &super::EventCodecs) -> std::io::Result<Self> {
+        super::persist::read_log(reader, codecs)
+    }
+
+    /// Sends a copy of every recorded event to `world`, in the order they
+    /// were originally sent.
+    ///
+    /// For the replay to reproduce the original run, `world` should be set up
+    /// the same way the recorded world was when recording started, i.e. with
+    /// the same handlers and entities. [`Spawn`] events are replayed by
+    /// spawning a new entity. The world's tick is not advanced, so events
+    /// scheduled by handlers during the replay stay scheduled in `world`.
+    pub fn replay(&self, world: &mut World) {
+        for event in &self.events {
+            (event.replay)(world, &*event.payload, event.target);
+        }
+    }
+
+    /// Replays the log into worlds whose handlers of the same priority run in
+    /// shuffled orders, and returns the first point where the state of a
+    /// world diverges from a replay in the default order.
+    ///
+    /// Every replay starts with a world created by `setup`. For every seed in
+    /// `seeds`, the world's handlers are shuffled with
+    /// [`World::shuffle_handler_order`] before replaying. After each event,
+    /// the state of the world is summarized with `checksum`, which should
+    /// cover the components and resources that the handlers modify. Returns
+    /// `None` if all checksums match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent, Clone)]
+    /// struct Tick;
+    ///
+    /// #[derive(Component)]
+    /// struct Value(u64);
+    ///
+    /// fn setup() -> World {
+    ///     let mut world = World::new();
+    ///
+    ///     let e = world.spawn();
+    ///     world.insert(e, Value(1));
+    ///
+    ///     // These two handlers produce different results depending on which runs first.
+    ///     world.add_handler(|_: Receiver<Tick>, mut f: Fetcher<&mut Value>| {
+    ///         f.iter_mut().for_each(|v| v.0 += 1);
+    ///     });
+    ///     world.add_handler(|_: Receiver<Tick>, mut f: Fetcher<&mut Value>| {
+    ///         f.iter_mut().for_each(|v| v.0 *= 2);
+    ///     });
+    ///
+    ///     world
+    /// }
+    ///
+    /// let mut world = setup();
+    /// world.record::<Tick>();
+    /// world.start_recording();
+    /// world.send(Tick);
+    /// let log = world.stop_recording().unwrap();
+    ///
+    /// let checksum = |world: &mut World| world.iter::<&Value>().map(|v| v.0).sum();
+    ///
+    /// let divergence = log.find_order_dependence(setup, checksum, 0..8);
+    ///
+    /// assert_eq!(divergence.unwrap().event, 0);
+    /// ```
+    pub fn find_order_dependence<S, C, I>(
+        &self,
+        mut setup: S,
+        mut checksum: C,
+        seeds: I,
+    ) -> Option<OrderDependence>
+    where
+        S: FnMut() -> World,
+        C: FnMut(&mut World) -> u64,
+        I: IntoIterator<Item = u64>,
+    {
+        let mut world = setup();
+
+        let expected: Vec<_> = self
+            .events
+            .iter()
+            .map(|event| {
+                (event.replay)(&mut world, &*event.payload, event.target);
+                checksum(&mut world)
+            })
+            .collect();
+
+        for seed in seeds {
+            let mut world = setup();
+            world.shuffle_handler_order(Some(seed));
+
+            for (i, event) in self.events.iter().enumerate() {
+                (event.replay)(&mut world, &*event.payload, event.target);
+
+                let actual = checksum(&mut world);
+
+                if actual != expected[i] {
+                    return Some(OrderDependence {
+                        seed,
+                        event: i,
+                        expected: expected[i],
+                        actual,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// A divergence found by [`EventLog::find_order_dependence`].
+///
+/// The divergence can be reproduced by calling
+/// [`World::shuffle_handler_order`] with `seed` before replaying the log.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct OrderDependence {
+    /// The seed of the shuffled handler order.
+    pub seed: u64,
+    /// The index in the log of the event after which the checksums differed.
+    pub event: usize,
+    /// The checksum of the world with the default handler order.
+    pub expected: u64,
+    /// The checksum of the world with the shuffled handler order.
+    pub actual: u64,
+}
+
+impl<'a> IntoIterator for &'a EventLog {
+    type Item = &'a RecordedEvent;
+
+    type IntoIter = core::slice::Iter<'a, RecordedEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.events).finish()
+    }
+}
+
+/// An event captured in an [`EventLog`].
+pub struct RecordedEvent {
+    ordinal: u64,
+    tick: u64,
+    name: Cow<'static, str>,
+    target: Option<EntityId>,
+    payload: Box<dyn Any>,
+    replay: ReplayFn,
+}
+
+impl RecordedEvent {
+    /// Creates a recorded event from an owned event, such as one loaded from
+    /// disk.
+    #[cfg_attr(not(feature = "persist"), allow(dead_code))]
+    pub(crate) fn new<E: for<'a> Event<This<'a> = E> + Clone + 'static>(
+        ordinal: u64,
+        tick: u64,
+        target: Option<EntityId>,
+        event: E,
+    ) -> Self {
+        Self {
+            ordinal,
+            tick,
+            name: any::type_name::<E>().into(),
+            target,
+            payload: Box::new(event),
+            replay: replay::<E>,
+        }
+    }
+
+    /// Creates a recorded event from an event added at runtime, encoded with
+    /// the [`SerializeFn`] of its descriptor.
+    ///
+    /// [`SerializeFn`]: super::SerializeFn
+    #[cfg_attr(not(feature = "persist"), allow(dead_code))]
+    pub(crate) fn new_serialized(
+        ordinal: u64,
+        tick: u64,
+        target: Option<EntityId>,
+        name: Cow<'static, str>,
+        id: EventId,
+        bytes: Vec<u8>,
+    ) -> Self {
+        Self {
+            ordinal,
+            tick,
+            name,
+            target,
+            payload: Box::new(SerializedEvent { id, bytes }),
+            replay: replay_serialized,
+        }
+    }
+
+    /// Returns the position of this event among all events recorded in the
+    /// world, starting at zero.
+    pub fn ordinal(&self) -> u64 {
+        self.ordinal
+    }
+
+    /// Returns the [tick](World::tick) of the world when the event was sent.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the type name of the event, or the name in the descriptor of
+    /// an event added at runtime.
+    ///
+    /// This name is intended for debugging purposes and should not be relied
+    /// upon for correctness.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the target of the event if it is a targeted event.
+    pub fn target(&self) -> Option<EntityId> {
+        self.target
+    }
+
+    /// Returns a reference to the recorded event if it is of type `E`.
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&E> {
+        self.payload.downcast_ref()
+    }
+
+    /// Returns the [`EventId`] and the serialized bytes of the recorded event
+    /// if it was added at runtime. See [`World::record_untyped`].
+    pub fn serialized(&self) -> Option<(EventId, &[u8])> {
+        self.payload
+            .downcast_ref::<SerializedEvent>()
+            .map(|e| (e.id, &e.bytes[..]))
+    }
+
+    /// Returns the recorded event as [`Any`].
+    #[cfg_attr(not(feature = "persist"), allow(dead_code))]
+    pub(crate) fn payload(&self) -> &dyn Any {
+        &*self.payload
+    }
+}
+
+impl fmt::Debug for RecordedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordedEvent")
+            .field("ordinal", &self.ordinal)
+            .field("tick", &self.tick)
+            .field("name", &self.name)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Clones the type-erased event behind the pointer.
+type CloneFn = unsafe fn(NonNull<u8>) -> Box<dyn Any>;
+
+/// Sends a copy of the recorded event to the world.
+type ReplayFn = fn(&mut World, &dyn Any, Option<EntityId>);
+
+fn replay<E: for<'a> Event<This<'a> = E> + Clone + 'static>(
+    world: &mut World,
+    payload: &dyn Any,
+    target: Option<EntityId>,
+) {
+    let event = payload.downcast_ref::<E>().unwrap().clone();
+
+    if TypeId::of::<E>() == TypeId::of::<Spawn>() {
+        // Spawning requires reserving the entity first.
+        world.spawn();
+    } else {
+        world.send_any(target, event);
+    }
+}
+
+/// An event added at runtime, recorded in the encoding of its descriptor.
+struct SerializedEvent {
+    id: EventId,
+    bytes: Vec<u8>,
+}
+
+fn replay_serialized(world: &mut World, payload: &dyn Any, target: Option<EntityId>) {
+    let event = payload.downcast_ref::<SerializedEvent>().unwrap();
+    world.send_serialized(event.id, target, &event.bytes);
+}
+
+/// Functions for recording and replaying an event type.
+#[derive(Clone, Copy)]
+struct Recordable {
+    name: &'static str,
+    clone: CloneFn,
+    replay: ReplayFn,
+}
+
+/// Functions for recording an event added at runtime.
+struct UntypedRecordable {
+    name: Cow<'static, str>,
+    serialize: unsafe fn(NonNull<u8>, &mut Vec<u8>),
+}
+
+/// Records the events sent into a world.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    recordable: TypeIdMap<Recordable>,
+    untyped: BTreeMap<EventId, UntypedRecordable>,
+    log: Option<EventLog>,
+    next_ordinal: u64,
+}
+
+impl Recorder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `E` as an event type to record.
+    pub(crate) fn register<E: for<'a> Event<This<'a> = E> + Clone + 'static>(&mut self) {
+        unsafe fn clone<E: Clone + 'static>(ptr: NonNull<u8>) -> Box<dyn Any> {
+            Box::new(ptr.cast::<E>().as_ref().clone())
+        }
+
+        self.recordable.insert(
+            TypeId::of::<E>(),
+            Recordable {
+                name: any::type_name::<E>(),
+                clone: clone::<E>,
+                replay: replay::<E>,
+            },
+        );
+    }
+
+    /// Registers the event added at runtime with the given ID as an event to
+    /// record.
+    pub(crate) fn register_untyped(
+        &mut self,
+        id: EventId,
+        name: Cow<'static, str>,
+        serialize: unsafe fn(NonNull<u8>, &mut Vec<u8>),
+    ) {
+        self.untyped
+            .insert(id, UntypedRecordable { name, serialize });
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.log.is_some()
+    }
+
+    pub(crate) fn start(&mut self) {
+        self.log.get_or_insert_with(EventLog::new);
+    }
+
+    pub(crate) fn stop(&mut self) -> Option<EventLog> {
+        self.log.take()
+    }
+
+    pub(crate) fn log(&self) -> Option<&EventLog> {
+        self.log.as_ref()
+    }
+
+    /// Records an event if recording is enabled and the event is registered.
+    ///
+    /// # Safety
+    ///
+    /// `event` must point to a valid instance of the event with the given ID
+    /// and type ID.
+    pub(crate) unsafe fn record(
+        &mut self,
+        id: EventId,
+        type_id: Option<TypeId>,
+        event: NonNull<u8>,
+        target: Option<EntityId>,
+        tick: u64,
+    ) {
+        let Some(log) = &mut self.log else {
+            return;
+        };
+
+        let event = if let Some(recordable) = type_id.and_then(|id| self.recordable.get(&id)) {
+            RecordedEvent {
+                ordinal: self.next_ordinal,
+                tick,
+                name: recordable.name.into(),
+                target,
+                payload: (recordable.clone)(event),
+                replay: recordable.replay,
+            }
+        } else if let Some(recordable) = self.untyped.get(&id) {
+            let mut bytes = Vec::new();
+            (recordable.serialize)(event, &mut bytes);
+
+            RecordedEvent::new_serialized(
+                self.next_ordinal,
+                tick,
+                target,
+                recordable.name.clone(),
+                id,
+                bytes,
+            )
+        } else {
+            return;
+        };
+
+        log.events.push(event);
+
+        self.next_ordinal += 1;
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("log", &self.log)
+            .field("next_ordinal", &self.next_ordinal)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,120 @@
+use core::cell::Cell;
+use core::fmt;
+
+use super::{Event, GlobalEventIdx};
+use crate::mutability::Immutable;
+
+/// A global event which asks for a single response of type `R` to the query
+/// `Q`.
+///
+/// Requests are sent with [`World::send_request`], which returns the value
+/// passed to [`Request::respond`] by the handler which answered the request.
+/// Exactly one handler must respond to each request.
+///
+/// See [`World::send_request`] for an example.
+///
+/// [`World::send_request`]: crate::world::World::send_request
+pub struct Request<'a, Q, R> {
+    query: Q,
+    slot: &'a ResponseSlot<R>,
+}
+
+impl<'a, Q, R> Request<'a, Q, R> {
+    pub(crate) fn new(query: Q, slot: &'a ResponseSlot<R>) -> Self {
+        Self { query, slot }
+    }
+
+    /// Returns the query of this request.
+    pub fn query(&self) -> &Q {
+        &self.query
+    }
+
+    /// Answers the request with `response`.
+    ///
+    /// If the request was already answered, `response` is discarded and the
+    /// request fails with [`RequestError::MultipleResponses`].
+    pub fn respond(&self, response: R) {
+        let count = self.slot.count.get();
+
+        if count == 0 {
+            self.slot.response.set(Some(response));
+        }
+
+        self.slot.count.set(count + 1);
+    }
+
+    /// Returns `true` if a handler has already responded to this request.
+    pub fn is_answered(&self) -> bool {
+        self.slot.count.get() > 0
+    }
+}
+
+unsafe impl<Q: 'static, R: 'static> Event for Request<'_, Q, R> {
+    type This<'a> = Request<'a, Q, R>;
+
+    type EventIdx = GlobalEventIdx;
+
+    type Mutability = Immutable;
+}
+
+impl<Q: fmt::Debug, R> fmt::Debug for Request<'_, Q, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("query", &self.query)
+            .field("responses", &self.slot.count.get())
+            .finish()
+    }
+}
+
+/// The response to a [`Request`] and the number of times it was answered.
+pub(crate) struct ResponseSlot<R> {
+    response: Cell<Option<R>>,
+    count: Cell<u32>,
+}
+
+impl<R> ResponseSlot<R> {
+    pub(crate) fn new() -> Self {
+        Self {
+            response: Cell::new(None),
+            count: Cell::new(0),
+        }
+    }
+
+    /// Returns the response if exactly one handler responded.
+    pub(crate) fn into_result(self) -> Result<R, RequestError> {
+        match (self.response.into_inner(), self.count.into_inner()) {
+            (Some(response), 1) => Ok(response),
+            (_, 0) => Err(RequestError::NoResponse),
+            (_, count) => Err(RequestError::MultipleResponses(count)),
+        }
+    }
+}
+
+/// Error returned by [`World::send_request`] when the request did not
+/// receive exactly one response.
+///
+/// [`World::send_request`]: crate::world::World::send_request
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum RequestError {
+    /// No handler responded to the request.
+    NoResponse,
+    /// More than one handler responded to the request. Contains the number of
+    /// responses.
+    MultipleResponses(u32),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::NoResponse => write!(f, "no handler responded to the request"),
+            RequestError::MultipleResponses(count) => write!(
+                f,
+                "more than one handler responded to the request ({count} responses)"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for RequestError {}
@@ -6,7 +6,11 @@ use core::ops::Index;
 pub use evenio_macros::TargetedEvent;
 
 use super::global::GlobalEvent;
-use super::{Event, EventDescriptor, EventKind, EventPtr, Mutability};
+#[cfg(feature = "metrics")]
+use super::EventMetrics;
+use super::{
+    CloneFn, DeserializeFn, Event, EventDescriptor, EventKind, EventPtr, Mutability, SerializeFn,
+};
 use crate::archetype::Archetype;
 use crate::drop::DropFn;
 use crate::entity::EntityLocation;
@@ -93,6 +97,12 @@ impl TargetedEvents {
             layout: desc.layout,
             drop: desc.drop,
             mutability: desc.mutability,
+            clone: desc.clone,
+            serialize: desc.serialize,
+            deserialize: desc.deserialize,
+            dead_target_policy: DeadTargetPolicy::default(),
+            #[cfg(feature = "metrics")]
+            metrics: EventMetrics::default(),
         };
 
         let insert = || {
@@ -142,6 +152,18 @@ impl TargetedEvents {
         Some(self.infos.get_by_index(idx.0)?.1)
     }
 
+    pub(crate) fn get_mut(&mut self, id: TargetedEventId) -> Option<&mut TargetedEventInfo> {
+        self.infos.get_mut(id.0)
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn get_by_index_mut(
+        &mut self,
+        idx: TargetedEventIdx,
+    ) -> Option<&mut TargetedEventInfo> {
+        Some(self.infos.get_by_index_mut(idx.0)?.1)
+    }
+
     /// Gets the [`TargetedEventInfo`] for an event using its [`TypeId`].
     /// Returns `None` if the `TypeId` does not map to an event.
     pub fn get_by_type_id(&self, type_id: TypeId) -> Option<&TargetedEventInfo> {
@@ -171,6 +193,11 @@ impl TargetedEvents {
     pub fn iter(&self) -> impl Iterator<Item = &TargetedEventInfo> {
         self.infos.iter().map(|(_, v)| v)
     }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut TargetedEventInfo> {
+        self.infos.iter_mut().map(|(_, v)| v)
+    }
 }
 
 impl Index<TargetedEventId> for TargetedEvents {
@@ -214,6 +241,8 @@ unsafe impl HandlerParam for &'_ TargetedEvents {
 
     type This<'a> = &'a TargetedEvents;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -243,6 +272,12 @@ pub struct TargetedEventInfo {
     layout: Layout,
     drop: DropFn,
     mutability: Mutability,
+    clone: CloneFn,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn,
+    dead_target_policy: DeadTargetPolicy,
+    #[cfg(feature = "metrics")]
+    metrics: EventMetrics,
 }
 
 impl TargetedEventInfo {
@@ -279,12 +314,70 @@ impl TargetedEventInfo {
         self.drop
     }
 
+    /// Gets the [`CloneFn`] of the event.
+    pub fn clone_fn(&self) -> CloneFn {
+        self.clone
+    }
+
+    /// Gets the [`SerializeFn`] of the event.
+    pub fn serialize_fn(&self) -> SerializeFn {
+        self.serialize
+    }
+
+    /// Gets the [`DeserializeFn`] of the event.
+    pub fn deserialize_fn(&self) -> DeserializeFn {
+        self.deserialize
+    }
+
     /// Gets the [mutability] of the event
     ///
     /// [mutability]: Event::Mutability
     pub fn mutability(&self) -> Mutability {
         self.mutability
     }
+
+    /// Gets the [`DeadTargetPolicy`] of the event.
+    ///
+    /// See [`World::set_dead_target_policy`].
+    pub fn dead_target_policy(&self) -> DeadTargetPolicy {
+        self.dead_target_policy
+    }
+
+    pub(crate) fn set_dead_target_policy(&mut self, policy: DeadTargetPolicy) {
+        self.dead_target_policy = policy;
+    }
+
+    /// Gets the [`EventMetrics`] of the event.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn metrics(&self) -> EventMetrics {
+        self.metrics
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_mut(&mut self) -> &mut EventMetrics {
+        &mut self.metrics
+    }
+}
+
+/// What happens to a targeted event whose target entity doesn't exist when the
+/// event is broadcast, e.g. because the entity was despawned earlier in the
+/// same flush.
+///
+/// See [`World::set_dead_target_policy`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum DeadTargetPolicy {
+    /// The event is dropped without being broadcast.
+    #[default]
+    Drop,
+    /// The event is dropped without being broadcast, and a [`DeadLetter`]
+    /// event is sent in its place.
+    ///
+    /// [`DeadLetter`]: super::DeadLetter
+    DeadLetter,
+    /// The event is dropped, and the world panics. The events remaining in
+    /// the queue are dropped as well.
+    Panic,
 }
 
 /// Lightweight identifier for a targeted event type.
@@ -0,0 +1,94 @@
+use core::fmt;
+use core::ptr::NonNull;
+
+use super::{EventInfo, EventPtr};
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::entity::EntityLocation;
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::world::{UnsafeWorldCell, World};
+
+/// A [`HandlerParam`] which receives a type-erased pointer to any of the
+/// events chosen at runtime with [`IntoHandler::receive_events`].
+///
+/// This is intended for dynamic environments such as scripting backends, where
+/// the set of events to listen for is not known at compile time. The handler
+/// only has immutable access to the event.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::{EventId, UntypedReceiver};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct A(u32);
+///
+/// #[derive(GlobalEvent)]
+/// struct B;
+///
+/// let mut world = World::new();
+///
+/// let events: Vec<EventId> = vec![
+///     world.add_global_event::<A>().into(),
+///     world.add_global_event::<B>().into(),
+/// ];
+///
+/// world.add_handler(
+///     (|r: UntypedReceiver| println!("received {}", r.info.name())).receive_events(events),
+/// );
+///
+/// world.send(A(1));
+/// world.send(B);
+/// ```
+///
+/// [`IntoHandler::receive_events`]: crate::handler::IntoHandler::receive_events
+#[derive(Clone, Copy)]
+pub struct UntypedReceiver<'a> {
+    /// A pointer to the received event. Valid for reads of the event's type
+    /// for the lifetime `'a`.
+    pub event: NonNull<u8>,
+    /// Information about the received event.
+    pub info: EventInfo<'a>,
+}
+
+unsafe impl HandlerParam for UntypedReceiver<'_> {
+    type State = ();
+
+    type This<'a> = UntypedReceiver<'a>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        config.set_received_event_access(Access::Read);
+
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        UntypedReceiver {
+            event: event_ptr.as_ptr(),
+            // SAFETY: The received event exists.
+            info: EventInfo::new(event_ptr.meta(), world),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl fmt::Debug for UntypedReceiver<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UntypedReceiver")
+            .field("event", &self.event)
+            .field("info", &self.info)
+            .finish()
+    }
+}
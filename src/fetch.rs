@@ -1,25 +1,43 @@
 //! Accessing components on entities.
 
+use alloc::rc::Rc;
+use alloc::vec;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, vec::Vec};
+use core::any::{Any, TypeId};
+use core::cell::UnsafeCell;
 use core::iter::FusedIterator;
+use core::marker::PhantomData;
 use core::mem::{self, MaybeUninit};
 use core::ops::{Deref, DerefMut};
 use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::NonNull;
 use core::{any, fmt};
 
+use crate::access::{Access, ComponentAccess};
 use crate::archetype::{Archetype, ArchetypeIdx, ArchetypeRow, Archetypes};
 use crate::assume_unchecked;
+use crate::component::{
+    Component, ComponentId, ComponentIdx, ComponentInfo, Components, Ptr, PtrMut,
+};
 use crate::entity::{Entities, EntityId, EntityLocation};
 use crate::event::EventPtr;
 use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
-use crate::query::{Query, ReadOnlyQuery};
+use crate::mutability::Mutable;
+use crate::query::{FilterItem, Query, ReadOnlyQuery, With};
 use crate::sparse_map::SparseMap;
-use crate::world::{UnsafeWorldCell, World};
+use crate::task::Completion;
+use crate::world::{column_ptr, UnsafeWorldCell, World};
 
 /// Internal state for a [`Fetcher`].
 #[doc(hidden)]
 pub struct FetcherState<Q: Query> {
     /// Stores the query's per-archetype state.
+    ///
+    /// Only archetypes which are matched by the query _and_ contain at least
+    /// one entity are present. Archetypes are added when they become nonempty
+    /// and removed when they become empty again, so iteration never visits
+    /// empty archetypes no matter how many exist in the world.
     map: SparseMap<ArchetypeIdx, Q::ArchState>,
     /// Stores the query's overall state.
     state: Q::State,
@@ -203,6 +221,196 @@ impl<Q: Query> FetcherState<Q> {
         }
     }
 
+    /// Calls `f` with the query item of every matched entity and the query item
+    /// of the distinct entity returned by `key`.
+    ///
+    /// # Safety
+    ///
+    /// - Must have permission to access the components that the query accesses.
+    /// - The entity locations in `entities` must be valid.
+    unsafe fn for_each_self_join<K, F>(
+        &mut self,
+        entities: &Entities,
+        archetypes: &Archetypes,
+        mut key: K,
+        mut f: F,
+    ) where
+        K: FnMut(&Q::This<'_>) -> Option<EntityId>,
+        F: FnMut(Q::This<'_>, Q::This<'_>),
+    {
+        for (&idx, state) in self.map.keys().iter().zip(self.map.values()) {
+            let arch = archetypes.get(idx).unwrap_unchecked();
+
+            for (row, &id) in arch.entity_ids().iter().enumerate() {
+                let item = Q::get(state, ArchetypeRow(row as u32));
+
+                let Some(other_id) = key(&item) else {
+                    continue;
+                };
+
+                // Items of distinct entities never alias.
+                if other_id == id {
+                    continue;
+                }
+
+                if let Ok(other_item) = self.get_unchecked(entities, other_id) {
+                    f(item, other_item);
+                }
+            }
+        }
+    }
+
+    /// Returns an iterator over the results of this query which visits
+    /// archetypes in an order derived from their components rather than the
+    /// order they were matched in.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission to access the components that the query accesses.
+    pub(crate) unsafe fn iter_sorted_unchecked<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+        components: &Components,
+    ) -> SortedIter<'a, Q> {
+        let indices = self.map.keys();
+        let states = self.map.values();
+
+        assume_unchecked(indices.len() == states.len());
+
+        // Sort positions in the map by the sorted list of component names in each
+        // archetype. Component names do not depend on the order components
+        // were added to the world.
+        let mut keyed: Vec<(Vec<&str>, u32)> = indices
+            .iter()
+            .enumerate()
+            .map(|(pos, &idx)| {
+                let arch = archetypes.get(idx).unwrap_unchecked();
+
+                let mut names: Vec<&str> = arch
+                    .component_indices()
+                    .iter()
+                    .map(|&c| components.get_by_index(c).unwrap_unchecked().name())
+                    .collect();
+
+                names.sort_unstable();
+
+                (names, pos as u32)
+            })
+            .collect();
+
+        keyed.sort_unstable();
+
+        SortedIter {
+            states,
+            indices,
+            order: keyed.into_iter().map(|(_, pos)| pos).collect(),
+            next: 0,
+            current: 0,
+            row: ArchetypeRow(0),
+            len: 0,
+            archetypes,
+        }
+    }
+
+    /// Returns the total number of entities matched by the query.
+    fn matched_len(&self, archetypes: &Archetypes) -> usize {
+        self.map
+            .keys()
+            .iter()
+            .map(|&idx| unsafe { archetypes.get(idx).unwrap_unchecked() }.entity_count() as usize)
+            .sum()
+    }
+
+    /// Collects the results of the query for every matched entity into a
+    /// `Vec`, in iteration order.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission to access the components that the query accesses.
+    unsafe fn collect_vec(&self, archetypes: &Archetypes) -> Vec<Q::This<'_>> {
+        let mut res = Vec::with_capacity(self.matched_len(archetypes));
+
+        for (state, &idx) in self.map.values().iter().zip(self.map.keys()) {
+            let entity_count = archetypes.get(idx).unwrap_unchecked().entity_count();
+
+            res.extend((0..entity_count).map(|row| Q::get(state, ArchetypeRow(row))));
+        }
+
+        res
+    }
+
+    /// Calls `f` with the results of the query for every matched entity, in
+    /// iteration order.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission to access the components that the query accesses.
+    #[inline]
+    unsafe fn for_each<'a, F>(&'a self, archetypes: &Archetypes, mut f: F)
+    where
+        F: FnMut(Q::This<'a>),
+    {
+        for (state, &idx) in self.map.values().iter().zip(self.map.keys()) {
+            let entity_count = archetypes.get(idx).unwrap_unchecked().entity_count();
+
+            for row in 0..entity_count {
+                f(Q::get(state, ArchetypeRow(row)));
+            }
+        }
+    }
+
+    /// Returns the IDs of all entities matched by the query, in iteration
+    /// order.
+    fn matched_entity_ids(&self, archetypes: &Archetypes) -> Vec<EntityId> {
+        self.map
+            .keys()
+            .iter()
+            .flat_map(|&idx| unsafe { archetypes.get(idx).unwrap_unchecked() }.entity_ids())
+            .copied()
+            .collect()
+    }
+
+    /// Executes the query for the entities at the given positions, where a
+    /// position counts entities across all matched archetypes in iteration
+    /// order. `positions` must be sorted in ascending order and contain no
+    /// duplicates.
+    ///
+    /// # Safety
+    ///
+    /// - Every position must be less than [`Self::matched_len`].
+    /// - Must have permission to access the components that the query accesses.
+    #[cfg(feature = "rand")]
+    unsafe fn get_by_positions(
+        &self,
+        archetypes: &Archetypes,
+        positions: impl IntoIterator<Item = usize>,
+    ) -> Vec<Q::This<'_>> {
+        let mut res = Vec::new();
+        let mut states = self.map.keys().iter().zip(self.map.values());
+        // Number of entities in the archetypes we've skipped over.
+        let mut offset = 0;
+        let mut current = None;
+
+        for pos in positions {
+            loop {
+                if let Some((state, len)) = current {
+                    if pos < offset + len {
+                        res.push(Q::get(state, ArchetypeRow((pos - offset) as u32)));
+                        break;
+                    }
+
+                    offset += len;
+                }
+
+                let (&idx, state) = states.next().unwrap_unchecked();
+                let len = archetypes.get(idx).unwrap_unchecked().entity_count() as usize;
+                current = Some((state, len));
+            }
+        }
+
+        res
+    }
+
     /// Returns a parallel iterator over the results of this query for each
     /// entity in an archetype that the query matches.
     ///
@@ -218,6 +426,8 @@ impl<Q: Query> FetcherState<Q> {
             arch_states: self.map.values(),
             arch_indices: self.map.keys(),
             archetypes,
+            min_batch_size: 1,
+            batches_per_thread: None,
         }
     }
 
@@ -236,6 +446,8 @@ impl<Q: Query> FetcherState<Q> {
             arch_states: self.map.values(),
             arch_indices: self.map.keys(),
             archetypes,
+            min_batch_size: 1,
+            batches_per_thread: None,
         }
     }
 
@@ -329,6 +541,141 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
         unsafe { self.state.get_unchecked(self.world.entities(), entity) }
     }
 
+    /// Returns the query item for the entity at the given location without
+    /// checking borrowing rules.
+    ///
+    /// Returns `None` if the archetype is not matched by the query or if `row`
+    /// is out of bounds for the archetype. Together with
+    /// [`matched_archetypes`](Self::matched_archetypes), this can be used to
+    /// implement custom iteration strategies.
+    ///
+    /// # Safety
+    ///
+    /// You must ensure that all locations that co-occur are disjoint if they
+    /// contain any mutable references.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::archetype::{ArchetypeRow, Archetypes};
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Value(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<&mut Value>, archetypes: &Archetypes| {
+    ///     // Visit the rows of each archetype in reverse.
+    ///     for &idx in f.matched_archetypes() {
+    ///         for row in (0..archetypes[idx].entity_count()).rev() {
+    ///             let value = unsafe { f.get_by_location(idx, ArchetypeRow(row)) }.unwrap();
+    ///             value.0 += 1;
+    ///         }
+    ///     }
+    /// });
+    /// # world.send(E);
+    /// ```
+    #[inline]
+    pub unsafe fn get_by_location(
+        &self,
+        archetype: ArchetypeIdx,
+        row: ArchetypeRow,
+    ) -> Option<Q::This<'_>> {
+        let state = self.state.map.get(archetype)?;
+
+        let arch = self.world.archetypes().get(archetype)?;
+
+        if row.0 >= arch.entity_count() {
+            return None;
+        }
+
+        Some(Q::get(state, row))
+    }
+
+    /// Returns the indices of all nonempty archetypes matched by the query,
+    /// in iteration order.
+    ///
+    /// See [`get_by_location`](Self::get_by_location).
+    pub fn matched_archetypes(&self) -> &[ArchetypeIdx] {
+        self.state.map.keys()
+    }
+
+    /// Returns the IDs of all entities matched by the query, in iteration
+    /// order, without fetching their components.
+    pub(crate) fn entity_ids(&self) -> impl Iterator<Item = EntityId> + '_ {
+        let archetypes = self.world.archetypes();
+
+        self.matched_archetypes()
+            .iter()
+            .flat_map(move |&idx| unsafe { archetypes.get(idx).unwrap_unchecked() }.entity_ids())
+            .copied()
+    }
+
+    /// Returns a view of this fetcher which only visits entities in the given
+    /// archetypes.
+    ///
+    /// Archetypes which are not matched by the query, are empty, or appear
+    /// more than once are ignored. The remaining archetypes are visited in the
+    /// same order as [`iter`](Self::iter). This is useful for algorithms
+    /// which select a subset of archetypes once, e.g. from
+    /// [`matched_archetypes`](Self::matched_archetypes), and then iterate
+    /// over that subset repeatedly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::archetype::Archetypes;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Velocity(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, mut f: Fetcher<&mut Velocity>, archetypes: &Archetypes| {
+    ///     // Only visit large archetypes.
+    ///     let large: Vec<_> = f
+    ///         .matched_archetypes()
+    ///         .iter()
+    ///         .copied()
+    ///         .filter(|&idx| archetypes[idx].entity_count() > 100)
+    ///         .collect();
+    ///
+    ///     let mut view = f.restrict(large);
+    ///
+    ///     for _ in 0..10 {
+    ///         for vel in &mut view {
+    ///             vel.0 *= 0.9;
+    ///         }
+    ///     }
+    /// });
+    /// # world.send(E);
+    /// ```
+    pub fn restrict<I>(&mut self, archetypes: I) -> RestrictedFetcher<'_, Q>
+    where
+        I: IntoIterator<Item = ArchetypeIdx>,
+    {
+        let mut positions: Vec<u32> = archetypes
+            .into_iter()
+            .filter_map(|idx| self.state.map.position(idx))
+            .map(|pos| pos as u32)
+            .collect();
+
+        // Sorting and deduplicating ensures no entity is visited twice, which
+        // would alias mutable query items.
+        positions.sort_unstable();
+        positions.dedup();
+
+        RestrictedFetcher {
+            state: self.state,
+            world: self.world,
+            positions,
+        }
+    }
+
     /// Returns the query items for the given array of entities.
     ///
     /// An error of type [`GetManyMutError`] is returned in the following
@@ -350,6 +697,164 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
         unsafe { self.state.get_many_mut(self.world.entities(), entities) }
     }
 
+    /// Returns the query item for `entity` along with an [`ExcludingFetcher`]
+    /// that can access every _other_ entity matching the query.
+    ///
+    /// This makes it possible to hold on to one entity's item while looking
+    /// up other entities mutably, which is useful for interactions between
+    /// pairs of entities like springs or constraints. Requests for `entity`
+    /// through the returned fetcher fail with
+    /// [`GetManyMutError::AliasedMutability`].
+    ///
+    /// If `entity` doesn't exist or doesn't match the query, then a
+    /// [`GetError`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Spring {
+    ///     other: EntityId,
+    ///     force: f32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, mut f: Fetcher<(EntityId, &mut Spring)>| {
+    ///     let ids: Vec<_> = f.iter_mut().map(|(id, _)| id).collect();
+    ///
+    ///     for id in ids {
+    ///         let ((_, spring), mut rest) = f.split_mut(id).unwrap();
+    ///
+    ///         if let Ok((_, other)) = rest.get_mut(spring.other) {
+    ///             other.force -= 1.0;
+    ///             spring.force += 1.0;
+    ///         }
+    ///     }
+    /// });
+    /// # world.send(E);
+    /// ```
+    pub fn split_mut(
+        &mut self,
+        entity: EntityId,
+    ) -> Result<(Q::This<'_>, ExcludingFetcher<'_, Q>), GetError> {
+        let item = unsafe { self.state.get_unchecked(self.world.entities(), entity) }?;
+
+        let rest = ExcludingFetcher {
+            state: self.state,
+            world: self.world,
+            excluded: entity,
+        };
+
+        Ok((item, rest))
+    }
+
+    /// Joins every entity matching this fetcher's query with the entity
+    /// identified by `key` in `other`.
+    ///
+    /// For each item of this fetcher, `key` is called to obtain the
+    /// [`EntityId`] of a related entity (such as a parent or target), and `f`
+    /// is called with the item and the related entity's item from `other`.
+    /// Items for which `key` returns `None` or whose related entity does not
+    /// match `other`'s query are skipped.
+    ///
+    /// Both items only live for the duration of the call to `f`, so `other` may
+    /// yield mutable references even if several items refer to the same
+    /// related entity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Target(EntityId);
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |_: Receiver<E>, mut attackers: Fetcher<&Target>, mut victims: Fetcher<&mut Health>| {
+    ///         attackers.for_each_join(
+    ///             &mut victims,
+    ///             |target| Some(target.0),
+    ///             |_, health| health.0 -= 1,
+    ///         );
+    ///     },
+    /// );
+    /// # world.send(E);
+    /// ```
+    pub fn for_each_join<Q2, K, F>(&mut self, other: &mut Fetcher<Q2>, mut key: K, mut f: F)
+    where
+        Q2: Query,
+        K: FnMut(&Q::This<'_>) -> Option<EntityId>,
+        F: FnMut(Q::This<'_>, Q2::This<'_>),
+    {
+        for item in self.iter_mut() {
+            let Some(id) = key(&item) else {
+                continue;
+            };
+
+            if let Ok(other_item) = other.get_mut(id) {
+                f(item, other_item);
+            }
+        }
+    }
+
+    /// Joins every entity matching this fetcher's query with the entity
+    /// identified by `key` in the same fetcher.
+    ///
+    /// This is like [`for_each_join`](Self::for_each_join), except that both
+    /// items come from this fetcher. This allows relationships such as
+    /// parent→child to be followed when both sides need mutable access to
+    /// the same components. Items whose `key` refers to the entity itself are
+    /// skipped, so the two items passed to `f` never alias.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Parent(EntityId);
+    ///
+    /// #[derive(Component)]
+    /// struct Mass(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |_: Receiver<E>, mut f: Fetcher<(&mut Mass, Option<&Parent>)>| {
+    ///         // Move half of each child's mass to its parent.
+    ///         f.for_each_self_join(
+    ///             |(_, parent)| parent.map(|p| p.0),
+    ///             |(child_mass, _), (parent_mass, _)| {
+    ///                 parent_mass.0 += child_mass.0 / 2.0;
+    ///                 child_mass.0 /= 2.0;
+    ///             },
+    ///         );
+    ///     },
+    /// );
+    /// # world.send(E);
+    /// ```
+    pub fn for_each_self_join<K, F>(&mut self, key: K, f: F)
+    where
+        K: FnMut(&Q::This<'_>) -> Option<EntityId>,
+        F: FnMut(Q::This<'_>, Q::This<'_>),
+    {
+        unsafe {
+            self.state
+                .for_each_self_join(self.world.entities(), self.world.archetypes(), key, f)
+        }
+    }
+
     /// Returns an iterator over all entities matching the read-only query.
     pub fn iter(&self) -> Iter<Q>
     where
@@ -362,40 +867,361 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
     pub fn iter_mut(&mut self) -> Iter<Q> {
         unsafe { self.state.iter_mut(self.world.archetypes()) }
     }
-}
-
-impl<'a, Q: Query> IntoIterator for Fetcher<'a, Q> {
-    type Item = Q::This<'a>;
 
-    type IntoIter = Iter<'a, Q>;
+    /// Calls `f` with the read-only query item of every matching entity, in
+    /// the same order as [`iter`](Self::iter).
+    ///
+    /// This is equivalent to `self.iter().for_each(f)`, but loops over the
+    /// matched archetypes and their rows directly instead of driving the
+    /// [`Iter`] state machine. This tends to produce tighter code for hot
+    /// loops over many entities.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<&Health>| {
+    ///     let mut total = 0;
+    ///     f.for_each(|h| total += h.0);
+    ///     println!("total health: {total}");
+    /// });
+    /// # world.send(E);
+    /// ```
+    #[inline]
+    pub fn for_each<F>(&self, f: F)
+    where
+        Q: ReadOnlyQuery,
+        F: FnMut(Q::This<'_>),
+    {
+        unsafe { self.state.for_each(self.world.archetypes(), f) }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        unsafe { self.state.iter_mut(self.world.archetypes()) }
+    /// Calls `f` with the query item of every matching entity, in the same
+    /// order as [`iter_mut`](Self::iter_mut).
+    ///
+    /// See [`for_each`](Self::for_each) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, mut f: Fetcher<&mut Health>| {
+    ///     f.for_each_mut(|h| h.0 += 1);
+    /// });
+    /// # world.send(E);
+    /// ```
+    #[inline]
+    pub fn for_each_mut<F>(&mut self, f: F)
+    where
+        F: FnMut(Q::This<'_>),
+    {
+        unsafe { self.state.for_each(self.world.archetypes(), f) }
     }
-}
 
-impl<'a, Q: ReadOnlyQuery> IntoIterator for &'a Fetcher<'_, Q> {
-    type Item = Q::This<'a>;
+    /// Collects the read-only query items of all matching entities into a
+    /// `Vec`, in the same order as [`iter`](Self::iter).
+    ///
+    /// The `Vec` is allocated up front with the exact number of matched
+    /// entities, so it never needs to grow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32, f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<(EntityId, &Position)>| {
+    ///     let snapshot = f.collect_vec();
+    ///     println!("saved {} positions", snapshot.len());
+    /// });
+    /// # world.send(E);
+    /// ```
+    pub fn collect_vec(&self) -> Vec<Q::This<'_>>
+    where
+        Q: ReadOnlyQuery,
+    {
+        unsafe { self.state.collect_vec(self.world.archetypes()) }
+    }
 
-    type IntoIter = Iter<'a, Q>;
+    /// Collects the query items of all matching entities into a `Vec`, in the
+    /// same order as [`iter_mut`](Self::iter_mut).
+    ///
+    /// See [`collect_vec`](Self::collect_vec) for details.
+    pub fn collect_vec_mut(&mut self) -> Vec<Q::This<'_>> {
+        unsafe { self.state.collect_vec(self.world.archetypes()) }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+    /// Returns the read-only query item for an entity chosen uniformly at
+    /// random among all entities matching the query, or `None` if the query
+    /// matches no entities.
+    ///
+    /// This does not iterate over the matched entities. It only needs to visit
+    /// each matched archetype once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// # #[derive(Component)] struct Monster;
+    /// use evenio::prelude::*;
+    /// use rand::thread_rng;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<(EntityId, With<&Monster>)>| {
+    ///     if let Some((id, _)) = f.pick(&mut thread_rng()) {
+    ///         println!("picked monster {id:?}");
+    ///     }
+    /// });
+    /// # world.send(E);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
+    pub fn pick<R>(&self, rng: &mut R) -> Option<Q::This<'_>>
+    where
+        Q: ReadOnlyQuery,
+        R: rand_core::RngCore + ?Sized,
+    {
+        unsafe { self.pick_unchecked(rng) }
     }
-}
 
-impl<'a, Q: Query> IntoIterator for &'a mut Fetcher<'_, Q> {
-    type Item = Q::This<'a>;
+    /// Returns the query item for an entity chosen uniformly at random among
+    /// all entities matching the query, or `None` if the query matches no
+    /// entities.
+    ///
+    /// See [`pick`](Self::pick) for details.
+    #[cfg(feature = "rand")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
+    pub fn pick_mut<R>(&mut self, rng: &mut R) -> Option<Q::This<'_>>
+    where
+        R: rand_core::RngCore + ?Sized,
+    {
+        unsafe { self.pick_unchecked(rng) }
+    }
 
-    type IntoIter = Iter<'a, Q>;
+    /// Returns the read-only query items for `amount` distinct entities chosen
+    /// uniformly at random among all entities matching the query.
+    ///
+    /// If fewer than `amount` entities match the query, then all matching
+    /// entities are returned. The items are returned in iteration order.
+    #[cfg(feature = "rand")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
+    pub fn sample<R>(&self, amount: usize, rng: &mut R) -> Vec<Q::This<'_>>
+    where
+        Q: ReadOnlyQuery,
+        R: rand_core::RngCore + ?Sized,
+    {
+        unsafe { self.sample_unchecked(amount, rng) }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter_mut()
+    /// Returns the query items for `amount` distinct entities chosen uniformly
+    /// at random among all entities matching the query.
+    ///
+    /// See [`sample`](Self::sample) for details.
+    #[cfg(feature = "rand")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
+    pub fn sample_mut<R>(&mut self, amount: usize, rng: &mut R) -> Vec<Q::This<'_>>
+    where
+        R: rand_core::RngCore + ?Sized,
+    {
+        unsafe { self.sample_unchecked(amount, rng) }
     }
-}
 
-impl<'a, Q: Query> fmt::Debug for Fetcher<'a, Q> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    #[cfg(feature = "rand")]
+    unsafe fn pick_unchecked<R>(&self, rng: &mut R) -> Option<Q::This<'_>>
+    where
+        R: rand_core::RngCore + ?Sized,
+    {
+        let archetypes = self.world.archetypes();
+        let len = self.state.matched_len(archetypes);
+
+        if len == 0 {
+            return None;
+        }
+
+        let pos = random_index(rng, len);
+
+        self.state.get_by_positions(archetypes, [pos]).pop()
+    }
+
+    #[cfg(feature = "rand")]
+    unsafe fn sample_unchecked<R>(&self, amount: usize, rng: &mut R) -> Vec<Q::This<'_>>
+    where
+        R: rand_core::RngCore + ?Sized,
+    {
+        use alloc::collections::BTreeSet;
+
+        let archetypes = self.world.archetypes();
+        let len = self.state.matched_len(archetypes);
+        let amount = amount.min(len);
+
+        // Robert Floyd's algorithm for choosing `amount` distinct positions.
+        let mut positions = BTreeSet::new();
+
+        for j in len - amount..len {
+            let t = random_index(rng, j + 1);
+
+            if !positions.insert(t) {
+                positions.insert(j);
+            }
+        }
+
+        self.state.get_by_positions(archetypes, positions)
+    }
+
+    /// Returns an iterator over all entities matching the read-only query,
+    /// visiting archetypes in a stable order.
+    ///
+    /// Unlike [`iter`](Self::iter), the order archetypes are visited in only
+    /// depends on the names of the components in each archetype. Two worlds
+    /// which registered their components in different orders will visit
+    /// entities in the same order, provided the entities within each archetype
+    /// were added in the same order.
+    ///
+    /// This allocates and sorts the list of matched archetypes, so it is
+    /// slower to create than [`iter`](Self::iter).
+    pub fn iter_sorted(&self) -> SortedIter<'_, Q>
+    where
+        Q: ReadOnlyQuery,
+    {
+        unsafe {
+            self.state
+                .iter_sorted_unchecked(self.world.archetypes(), self.world.components())
+        }
+    }
+
+    /// Returns an iterator over all entities matching the query, visiting
+    /// archetypes in a stable order.
+    ///
+    /// See [`iter_sorted`](Self::iter_sorted) for details.
+    pub fn iter_sorted_mut(&mut self) -> SortedIter<'_, Q> {
+        unsafe {
+            self.state
+                .iter_sorted_unchecked(self.world.archetypes(), self.world.components())
+        }
+    }
+
+    /// Returns an iterator over groups of read-only query items which share
+    /// the same key, in ascending order of the keys.
+    ///
+    /// `key` is called once for every matched entity. Each group is yielded
+    /// as the key and an iterator over the items in the group. Items within a
+    /// group are visited in the same order as [`iter`](Self::iter).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Material(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Mesh;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<(&Material, &Mesh)>| {
+    ///     for (material, meshes) in f.group_by(|(material, _)| material.0) {
+    ///         // Bind the material once, then draw all meshes using it.
+    ///         println!("material {material}: {} meshes", meshes.len());
+    ///     }
+    /// });
+    /// # world.send(E);
+    /// ```
+    pub fn group_by<K, F>(&self, key: F) -> GroupBy<'_, Q, K>
+    where
+        Q: ReadOnlyQuery,
+        K: Ord,
+        F: FnMut(&Q::This<'_>) -> K,
+    {
+        GroupBy::new(self.iter(), key)
+    }
+
+    /// Returns an iterator over groups of query items which share the same
+    /// key, in ascending order of the keys.
+    ///
+    /// See [`group_by`](Self::group_by) for details.
+    pub fn group_by_mut<K, F>(&mut self, key: F) -> GroupBy<'_, Q, K>
+    where
+        K: Ord,
+        F: FnMut(&Q::This<'_>) -> K,
+    {
+        GroupBy::new(self.iter_mut(), key)
+    }
+}
+
+/// Returns a uniformly distributed random number in `0..len`.
+#[cfg(feature = "rand")]
+fn random_index<R: rand_core::RngCore + ?Sized>(rng: &mut R, len: usize) -> usize {
+    debug_assert!(len > 0);
+
+    let len = len as u64;
+    // Reject values in the final incomplete multiple of `len` to avoid modulo
+    // bias.
+    let zone = u64::MAX - u64::MAX % len;
+
+    loop {
+        let n = rng.next_u64();
+
+        if n < zone {
+            return (n % len) as usize;
+        }
+    }
+}
+
+impl<'a, Q: Query> IntoIterator for Fetcher<'a, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        unsafe { self.state.iter_mut(self.world.archetypes()) }
+    }
+}
+
+impl<'a, Q: ReadOnlyQuery> IntoIterator for &'a Fetcher<'_, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, Q: Query> IntoIterator for &'a mut Fetcher<'_, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, Q: Query> fmt::Debug for Fetcher<'a, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Fetcher")
             .field("state", &self.state)
             .field("world", &self.world)
@@ -403,6 +1229,140 @@ impl<'a, Q: Query> fmt::Debug for Fetcher<'a, Q> {
     }
 }
 
+/// A view of a [`Fetcher`] which can access every entity except one.
+///
+/// See [`Fetcher::split_mut`].
+pub struct ExcludingFetcher<'a, Q: Query> {
+    state: &'a FetcherState<Q>,
+    world: UnsafeWorldCell<'a>,
+    excluded: EntityId,
+}
+
+impl<Q: Query> ExcludingFetcher<'_, Q> {
+    /// Returns the entity which this fetcher cannot access.
+    pub fn excluded(&self) -> EntityId {
+        self.excluded
+    }
+
+    /// Returns the read-only query item for the given entity.
+    ///
+    /// If the entity is the excluded entity, doesn't exist, or doesn't match
+    /// the query, then a [`GetManyMutError`] is returned.
+    pub fn get(&self, entity: EntityId) -> Result<Q::This<'_>, GetManyMutError>
+    where
+        Q: ReadOnlyQuery,
+    {
+        if entity == self.excluded {
+            return Err(GetManyMutError::AliasedMutability);
+        }
+
+        Ok(unsafe { self.state.get_unchecked(self.world.entities(), entity) }?)
+    }
+
+    /// Returns the query item for the given entity.
+    ///
+    /// If the entity is the excluded entity, doesn't exist, or doesn't match
+    /// the query, then a [`GetManyMutError`] is returned.
+    pub fn get_mut(&mut self, entity: EntityId) -> Result<Q::This<'_>, GetManyMutError> {
+        if entity == self.excluded {
+            return Err(GetManyMutError::AliasedMutability);
+        }
+
+        // SAFETY: The item for the excluded entity is the only one handed out
+        // by the parent fetcher, and we just checked that `entity` isn't it.
+        Ok(unsafe { self.state.get_unchecked(self.world.entities(), entity) }?)
+    }
+}
+
+impl<Q: Query> fmt::Debug for ExcludingFetcher<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExcludingFetcher")
+            .field("state", &self.state)
+            .field("world", &self.world)
+            .field("excluded", &self.excluded)
+            .finish()
+    }
+}
+
+/// A view of a [`Fetcher`] which only visits entities in a subset of the
+/// matched archetypes.
+///
+/// See [`Fetcher::restrict`].
+pub struct RestrictedFetcher<'a, Q: Query> {
+    state: &'a FetcherState<Q>,
+    world: UnsafeWorldCell<'a>,
+    /// Sorted positions of the visited archetypes in the fetcher state.
+    positions: Vec<u32>,
+}
+
+impl<Q: Query> RestrictedFetcher<'_, Q> {
+    /// Returns the indices of the archetypes visited by this view, in
+    /// iteration order.
+    pub fn archetypes(&self) -> impl Iterator<Item = ArchetypeIdx> + '_ {
+        let indices = self.state.map.keys();
+
+        self.positions
+            .iter()
+            .map(|&pos| unsafe { *indices.get_unchecked(pos as usize) })
+    }
+
+    /// Returns an iterator over the read-only query items of all entities in
+    /// the view.
+    pub fn iter(&self) -> RestrictedIter<'_, Q>
+    where
+        Q: ReadOnlyQuery,
+    {
+        self.iter_unchecked()
+    }
+
+    /// Returns an iterator over the query items of all entities in the view.
+    pub fn iter_mut(&mut self) -> RestrictedIter<'_, Q> {
+        self.iter_unchecked()
+    }
+
+    fn iter_unchecked(&self) -> RestrictedIter<'_, Q> {
+        RestrictedIter {
+            states: self.state.map.values(),
+            indices: self.state.map.keys(),
+            positions: self.positions.iter(),
+            current: 0,
+            row: ArchetypeRow(0),
+            len: 0,
+            archetypes: self.world.archetypes(),
+        }
+    }
+}
+
+impl<'a, Q: ReadOnlyQuery> IntoIterator for &'a RestrictedFetcher<'_, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = RestrictedIter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, Q: Query> IntoIterator for &'a mut RestrictedFetcher<'_, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = RestrictedIter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<Q: Query> fmt::Debug for RestrictedFetcher<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestrictedFetcher")
+            .field("state", &self.state)
+            .field("world", &self.world)
+            .field("positions", &self.positions)
+            .finish()
+    }
+}
+
 /// An error returned when a random-access entity lookup fails.
 ///
 /// See [`Fetcher::get`] and [`Fetcher::get_mut`].
@@ -492,11 +1452,20 @@ where
     }
 }
 
-/// A [`HandlerParam`] which fetches a single entity from the world.
+/// A [`HandlerParam`] like [`Fetcher`] whose state is shared with every other
+/// `CachedFetcher` of the same query in the world.
 ///
-/// If there isn't exactly one entity that matches the [`Query`], a runtime
-/// panic occurs. This is useful for representing global variables or singleton
-/// entities.
+/// A regular [`Fetcher`] keeps its own list of matched archetypes and
+/// per-archetype state, so every handler using the same query stores a copy of
+/// it. `CachedFetcher`s with identical query types store it once, which saves
+/// memory in worlds with many archetypes and many handlers. Every handler is
+/// still told about the archetypes it matches.
+///
+/// `CachedFetcher` dereferences to [`Fetcher`], so the same methods are
+/// available. A handler may not contain more than one `CachedFetcher` with the
+/// same query type. Since the state is shared through an [`Rc`], which isn't
+/// [`Send`], handlers with a `CachedFetcher` can't be added with
+/// `World::add_parallel_handler`.
 ///
 /// # Examples
 ///
@@ -504,140 +1473,204 @@ where
 /// # #[derive(GlobalEvent)] struct E;
 /// use evenio::prelude::*;
 ///
-/// #[derive(Component, Debug)]
-/// struct MyComponent(i32);
+/// #[derive(Component)]
+/// struct Health(i32);
 ///
 /// let mut world = World::new();
 ///
-/// world.add_handler(|_: Receiver<E>, data: Single<&MyComponent>| {
-///     println!("The data is: {data:?}");
+/// // Both handlers share the same fetcher state.
+/// world.add_handler(|_: Receiver<E>, f: CachedFetcher<&Health>| {
+///     println!("{} entities with health", f.iter().len());
 /// });
-///
-/// let e = world.spawn();
-/// world.insert(e, MyComponent(123));
-///
-/// world.send(E);
+/// world.add_handler(|_: Receiver<E>, mut f: CachedFetcher<&Health>| {
+///     for _health in &mut f {}
+/// });
+/// # world.send(E);
 /// ```
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
-pub struct Single<Q>(Q);
-
-impl<Q> Single<Q> {
-    /// Create a new instance of `Single`.
-    pub const fn new(q: Q) -> Self {
-        Self(q)
-    }
+pub struct CachedFetcher<'a, Q: Query>(Fetcher<'a, Q>);
 
-    /// Consumes the `Single` and returns the inner value.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use evenio::fetch::Single;
-    ///
-    /// let single = Single::new("bananas");
-    /// assert_eq!(Single::into_inner(single), "bananas");
-    /// ```
-    pub fn into_inner(this: Self) -> Q {
-        this.0
-    }
+/// Internal state for a [`CachedFetcher`].
+#[doc(hidden)]
+pub struct CachedFetcherState<Q: Query>(Rc<UnsafeCell<SharedFetcherState<Q>>>);
+
+/// Fetcher state shared between all [`CachedFetcher`]s and
+/// [`PreparedQuery`]s with the same query.
+struct SharedFetcherState<Q: Query> {
+    fetcher: FetcherState<Q>,
+    /// All archetypes matched by the query, including empty ones. Only
+    /// maintained by [`PreparedQuery`], since handlers are told about the
+    /// archetypes they match.
+    matched: Vec<ArchetypeIdx>,
+    /// The archetype generation `matched` was computed for, or `None` if it
+    /// hasn't been computed yet.
+    generation: Option<u64>,
+    /// The value of [`Archetypes::changes`] when a [`PreparedQuery`] last
+    /// brought `fetcher` up to date.
+    synced: u64,
 }
 
-unsafe impl<Q: Query + 'static> HandlerParam for Single<Q> {
-    type State = FetcherState<Q>;
+impl<Q: Query + 'static> SharedFetcherState<Q> {
+    /// Returns the shared state for the query `Q` in `world`, creating it from
+    /// `state` if it doesn't exist yet.
+    fn get_or_insert(world: &mut World, state: Q::State) -> Rc<UnsafeCell<Self>> {
+        let type_id = TypeId::of::<Q>();
 
-    type This<'a> = Single<Q::This<'a>>;
+        if let Some(shared) = world
+            .fetcher_cache_mut()
+            .get(&type_id)
+            .and_then(|weak| weak.upgrade())
+        {
+            return shared
+                .downcast::<UnsafeCell<Self>>()
+                .expect("fetcher cache entry should have the correct type");
+        }
 
-    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
-        FetcherState::init(world, config)
-    }
+        let shared = Rc::new(UnsafeCell::new(Self {
+            fetcher: FetcherState::new(state),
+            matched: vec![],
+            generation: None,
+            synced: 0,
+        }));
 
-    #[track_caller]
-    unsafe fn get<'a>(
-        state: &'a mut Self::State,
-        info: &'a HandlerInfo,
-        event_ptr: EventPtr<'a>,
-        target_location: EntityLocation,
-        world: UnsafeWorldCell<'a>,
-    ) -> Self::This<'a> {
-        match TrySingle::get(state, info, event_ptr, target_location, world) {
-            Ok(item) => Single(item),
-            Err(e) => {
-                panic!(
-                    "failed to fetch exactly one entity matching the query `{}`: {e}",
-                    any::type_name::<Q>()
-                )
-            }
-        }
-    }
+        let erased: Rc<dyn Any> = shared.clone();
+        let cache = world.fetcher_cache_mut();
 
-    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
-        state.refresh_archetype(arch)
-    }
+        // Drop the entries of states which are no longer used by any handler.
+        cache.retain(|_, weak| weak.strong_count() > 0);
+        cache.insert(type_id, Rc::downgrade(&erased));
 
-    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
-        state.remove_archetype(arch)
+        shared
     }
-}
 
-impl<'a, T> Deref for Single<&'a T> {
-    type Target = T;
+    /// Brings the per-archetype state up to date with `archetypes`. Only the
+    /// archetypes which changed since the last call are visited, unless
+    /// archetypes were added or removed in the meantime.
+    fn sync(&mut self, archetypes: &Archetypes) {
+        if self.generation != Some(archetypes.generation()) {
+            self.matched.clear();
+            self.fetcher.map.clear();
 
-    fn deref(&self) -> &Self::Target {
-        self.0
+            for arch in archetypes.iter() {
+                if Q::new_arch_state(arch, &mut self.fetcher.state).is_some() {
+                    self.matched.push(arch.index());
+
+                    if arch.entity_count() > 0 {
+                        self.fetcher.refresh_archetype(arch);
+                    }
+                }
+            }
+
+            self.generation = Some(archetypes.generation());
+        } else if self.synced != archetypes.changes() {
+            for &idx in &self.matched {
+                let arch = unsafe { archetypes.get(idx).unwrap_unchecked() };
+
+                if arch.changed_at() > self.synced {
+                    if arch.entity_count() > 0 {
+                        self.fetcher.refresh_archetype(arch);
+                    } else {
+                        self.fetcher.remove_archetype(arch);
+                    }
+                }
+            }
+        }
+
+        self.synced = archetypes.changes();
     }
 }
 
-impl<'a, T> Deref for Single<&'a mut T> {
-    type Target = T;
+impl<'a, Q: Query> Deref for CachedFetcher<'a, Q> {
+    type Target = Fetcher<'a, Q>;
 
     fn deref(&self) -> &Self::Target {
-        self.0
+        &self.0
     }
 }
 
-impl<'a, T> DerefMut for Single<&'a mut T> {
+impl<Q: Query> DerefMut for CachedFetcher<'_, Q> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0
+        &mut self.0
     }
 }
 
-impl<'a, T> AsRef<T> for Single<&'a T> {
-    fn as_ref(&self) -> &T {
-        self.0
-    }
-}
+impl<'a, Q: Query> IntoIterator for CachedFetcher<'a, Q> {
+    type Item = Q::This<'a>;
 
-impl<'a, T> AsRef<T> for Single<&'a mut T> {
-    fn as_ref(&self) -> &T {
-        self.0
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
     }
 }
 
-impl<'a, T> AsMut<T> for Single<&'a mut T> {
-    fn as_mut(&mut self) -> &mut T {
-        self.0
+impl<'a, Q: ReadOnlyQuery> IntoIterator for &'a CachedFetcher<'_, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
     }
 }
 
-impl<T> From<T> for Single<T> {
-    fn from(value: T) -> Self {
-        Self(value)
+impl<'a, Q: Query> IntoIterator for &'a mut CachedFetcher<'_, Q> {
+    type Item = Q::This<'a>;
+
+    type IntoIter = Iter<'a, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
     }
 }
 
-/// Like [`Single`], but yields a `Result` rather than panicking on error.
-///
-/// This is useful if you need to explicitly handle the situation where the
-/// query does not match exactly one entity.
-pub type TrySingle<Q> = Result<Q, SingleError>;
+impl<Q: Query> fmt::Debug for CachedFetcher<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CachedFetcher").field(&self.0).finish()
+    }
+}
 
-unsafe impl<Q: Query + 'static> HandlerParam for TrySingle<Q> {
-    type State = FetcherState<Q>;
+impl<Q: Query> fmt::Debug for CachedFetcherState<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // SAFETY: The shared state is only mutated while no handler is running.
+        let shared = unsafe { &*self.0.get() };
+
+        f.debug_struct("CachedFetcherState")
+            .field("fetcher", &shared.fetcher)
+            .field("matched", &shared.matched)
+            .field("generation", &shared.generation)
+            .field("synced", &shared.synced)
+            .finish()
+    }
+}
 
-    type This<'a> = Result<Q::This<'a>, SingleError>;
+unsafe impl<Q> HandlerParam for CachedFetcher<'_, Q>
+where
+    Q: Query + 'static,
+{
+    type State = CachedFetcherState<Q>;
+
+    type This<'a> = CachedFetcher<'a, Q>;
 
     fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
-        FetcherState::init(world, config)
+        let type_id = TypeId::of::<Q>();
+
+        if config.cached_fetchers.contains(&type_id) {
+            return Err(InitError::new(format!(
+                "handler contains more than one `CachedFetcher` with the query `{}`",
+                any::type_name::<Q>()
+            )));
+        }
+
+        // Initialize the query so that its component access is registered with
+        // the handler.
+        let (ca, state) = Q::init(world, config)?;
+
+        config.push_component_access(ca);
+        config.cached_fetchers.push(type_id);
+
+        Ok(CachedFetcherState(SharedFetcherState::get_or_insert(
+            world, state,
+        )))
     }
 
     unsafe fn get<'a>(
@@ -647,550 +1680,3344 @@ unsafe impl<Q: Query + 'static> HandlerParam for TrySingle<Q> {
         _target_location: EntityLocation,
         world: UnsafeWorldCell<'a>,
     ) -> Self::This<'a> {
-        let mut it = state.iter_mut(world.archetypes());
-
-        let Some(item) = it.next() else {
-            return Err(SingleError::QueryDoesNotMatch);
-        };
-
-        if it.next().is_some() {
-            return Err(SingleError::MoreThanOneMatch);
-        }
-
-        Ok(item)
+        // SAFETY: Handlers with a `CachedFetcher` run one at a time on the thread
+        // owning the world, and a handler contains at most one `CachedFetcher`
+        // for this state, so the state is not aliased.
+        let shared = &mut *state.0.get();
+
+        CachedFetcher(Fetcher {
+            state: &mut shared.fetcher,
+            world,
+        })
     }
 
     fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
-        state.refresh_archetype(arch)
-    }
+        // SAFETY: Archetypes are refreshed while no handler is running.
+        let shared = unsafe { &mut *state.0.get() };
 
-    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
-        state.remove_archetype(arch)
+        // Every sharing handler is notified. The archetype is refreshed each
+        // time, since its columns may have been reallocated.
+        shared.fetcher.refresh_archetype(arch);
     }
-}
-
-/// Error raised when fetching exactly one entity matching a query fails.
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-pub enum SingleError {
-    /// Query does not match any entities
-    QueryDoesNotMatch,
-    /// More than one entity matched the query.
-    MoreThanOneMatch,
-}
 
-impl fmt::Display for SingleError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let msg = match self {
-            SingleError::QueryDoesNotMatch => "query does not match any entities",
-            SingleError::MoreThanOneMatch => "more than one entity matched the query",
-        };
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        // SAFETY: Archetypes are removed while no handler is running.
+        let shared = unsafe { &mut *state.0.get() };
 
-        write!(f, "{msg}")
+        shared.fetcher.remove_archetype(arch);
     }
 }
 
-#[cfg(feature = "std")]
-#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
-impl std::error::Error for SingleError {}
-
-/// Iterator over entities matching the query `Q`.
+/// A [`HandlerParam`] like [`Fetcher`] which only yields query items
+/// satisfying the [`FilterItem`] predicate of `F`.
 ///
-/// Entities are visited in a deterministic but otherwise unspecified order.
-#[must_use = "iterators are lazy and do nothing unless consumed"]
-pub struct Iter<'a, Q: Query> {
-    /// Pointer into the array of archetype states. This pointer moves forward
-    /// until it reaches `state_last`.
-    state: NonNull<Q::ArchState>,
-    /// Pointer to the last arch state, or dangling if there are no arch states.
-    /// This is _not_ a one-past-the-end pointer.
-    state_last: NonNull<Q::ArchState>,
-    /// Pointer into the array of archetype indices. This pointer moves forward
-    /// in lockstep with `state`.
-    index: NonNull<ArchetypeIdx>,
-    /// Current row of the current archetype.
-    row: ArchetypeRow,
-    /// Number of entities in the current archetype.
-    len: u32,
-    archetypes: &'a Archetypes,
+/// Items which fail the predicate are skipped during iteration, including
+/// parallel iteration, and are treated as not matching the query by
+/// [`get`](Self::get) and [`get_mut`](Self::get_mut). This keeps the predicate
+/// next to the query instead of repeating it at every use.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::fetch::FilteredFetcher;
+/// use evenio::prelude::*;
+/// use evenio::query::FilterItem;
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// struct IsAlive;
+///
+/// impl FilterItem<&mut Health> for IsAlive {
+///     fn filter(health: &&mut Health) -> bool {
+///         health.0 > 0
+///     }
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, f: FilteredFetcher<&mut Health, IsAlive>| {
+///     for health in f {
+///         // Only living entities take damage.
+///         health.0 -= 1;
+///     }
+/// });
+/// # world.send(E);
+/// ```
+pub struct FilteredFetcher<'a, Q: Query, F> {
+    fetcher: Fetcher<'a, Q>,
+    _marker: PhantomData<fn() -> F>,
 }
 
-impl<'a, Q: Query> Iterator for Iter<'a, Q> {
-    type Item = Q::This<'a>;
-
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        // If we reached the end of the current archetype, move on to the next
-        // or return `None`.
-        if self.row.0 == self.len {
-            if self.state == self.state_last {
-                // There are no more archetypes to iterate through.
-                return None;
-            }
-
-            // Move on to the next archetype.
-            self.state = unsafe { NonNull::new_unchecked(self.state.as_ptr().add(1)) };
-            self.index = unsafe { NonNull::new_unchecked(self.index.as_ptr().add(1)) };
+impl<'a, Q, F> FilteredFetcher<'a, Q, F>
+where
+    Q: Query,
+    F: for<'x> FilterItem<Q::This<'x>>,
+{
+    /// Returns the read-only query item for the given entity.
+    ///
+    /// If the entity doesn't exist, doesn't match the query, or fails the
+    /// predicate, then a [`GetError`] is returned.
+    pub fn get(&self, entity: EntityId) -> Result<Q::This<'_>, GetError>
+    where
+        Q: ReadOnlyQuery,
+    {
+        let item = self.fetcher.get(entity)?;
 
-            let idx = unsafe { *self.index.as_ptr() };
-            let arch = unsafe { self.archetypes.get(idx).unwrap_unchecked() };
+        if F::filter(&item) {
+            Ok(item)
+        } else {
+            Err(GetError::QueryDoesNotMatch)
+        }
+    }
 
-            self.row = ArchetypeRow(0);
-            self.len = arch.entity_count();
+    /// Returns the query item for the given entity.
+    ///
+    /// If the entity doesn't exist, doesn't match the query, or fails the
+    /// predicate, then a [`GetError`] is returned.
+    pub fn get_mut(&mut self, entity: EntityId) -> Result<Q::This<'_>, GetError> {
+        let item = self.fetcher.get_mut(entity)?;
 
-            // SAFETY: Fetcher state only contains nonempty archetypes.
-            unsafe { assume_unchecked(self.len > 0) };
+        if F::filter(&item) {
+            Ok(item)
+        } else {
+            Err(GetError::QueryDoesNotMatch)
         }
+    }
 
-        // Execute the query for the current entity.
-        let state = unsafe { &*self.state.as_ptr().cast_const() };
-        let item = unsafe { Q::get(state, self.row) };
-
-        // Move on to the next row (entity) in the archetype.
-        self.row.0 += 1;
+    /// Returns an iterator over all read-only query items which satisfy the
+    /// predicate.
+    pub fn iter(&self) -> FilteredIter<'_, Q, F>
+    where
+        Q: ReadOnlyQuery,
+    {
+        FilteredIter::new(self.fetcher.iter())
+    }
 
-        Some(item)
+    /// Returns an iterator over all query items which satisfy the predicate.
+    pub fn iter_mut(&mut self) -> FilteredIter<'_, Q, F> {
+        FilteredIter::new(self.fetcher.iter_mut())
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.len();
-        (len, Some(len))
+    /// Consumes the `FilteredFetcher` and returns the underlying [`Fetcher`],
+    /// which ignores the predicate.
+    pub fn into_inner(self) -> Fetcher<'a, Q> {
+        self.fetcher
     }
 }
 
-impl<Q: Query> ExactSizeIterator for Iter<'_, Q> {
-    fn len(&self) -> usize {
-        // The number of rows in the current archetype which we did not iterate
-        // over yet.
-        let mut remaining = self.len - self.row.0;
-
-        let mut index = self.index.as_ptr();
-
-        // The index of the last archetype we will iterate through.
-        let index_last = unsafe {
-            // TODO: use `.sub_ptr` when stabilized.
-            index.add(self.state_last.as_ptr().offset_from(self.state.as_ptr()) as usize)
-        };
-
-        // Add the number of rows of each remaining archetype to `remaining`.
-        while index != index_last {
-            index = unsafe { index.add(1) };
+impl<'a, Q, F> IntoIterator for FilteredFetcher<'a, Q, F>
+where
+    Q: Query,
+    F: for<'x> FilterItem<Q::This<'x>>,
+{
+    type Item = Q::This<'a>;
 
-            remaining += unsafe { self.archetypes.get(*index).unwrap_unchecked() }.entity_count();
-        }
+    type IntoIter = FilteredIter<'a, Q, F>;
 
-        // Return the total.
-        remaining as usize
+    fn into_iter(self) -> Self::IntoIter {
+        FilteredIter::new(self.fetcher.into_iter())
     }
 }
 
-impl<Q: Query> FusedIterator for Iter<'_, Q> {}
+impl<'a, Q, F> IntoIterator for &'a FilteredFetcher<'_, Q, F>
+where
+    Q: ReadOnlyQuery,
+    F: for<'x> FilterItem<Q::This<'x>>,
+{
+    type Item = Q::This<'a>;
 
-// SAFETY: Iter is only cloneable when the query is read-only.
-impl<'a, Q: ReadOnlyQuery> Clone for Iter<'a, Q> {
-    fn clone(&self) -> Self {
-        Self {
-            state: self.state,
-            state_last: self.state_last,
-            index: self.index,
-            row: self.row,
-            len: self.len,
-            archetypes: self.archetypes,
-        }
-    }
-}
+    type IntoIter = FilteredIter<'a, Q, F>;
 
-impl<Q: Query> fmt::Debug for Iter<'_, Q> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Iter")
-            .field("state", &self.state)
-            .field("state_last", &self.state_last)
-            .field("index", &self.index)
-            .field("row", &self.row)
-            .field("len", &self.len)
-            .field("archetypes", &self.archetypes)
-            .finish()
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
-unsafe impl<'a, Q> Send for Iter<'_, Q>
+impl<'a, Q, F> IntoIterator for &'a mut FilteredFetcher<'_, Q, F>
 where
     Q: Query,
-    Q::This<'a>: Send,
+    F: for<'x> FilterItem<Q::This<'x>>,
 {
-}
+    type Item = Q::This<'a>;
 
-unsafe impl<'a, Q> Sync for Iter<'a, Q>
-where
-    Q: Query,
-    Q::This<'a>: Sync,
-{
+    type IntoIter = FilteredIter<'a, Q, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
-impl<'a, Q> UnwindSafe for Iter<'a, Q>
-where
-    Q: Query,
-    Q::This<'a>: UnwindSafe,
-{
+impl<Q: Query, F> fmt::Debug for FilteredFetcher<'_, Q, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredFetcher")
+            .field("fetcher", &self.fetcher)
+            .finish()
+    }
 }
 
-impl<'a, Q> RefUnwindSafe for Iter<'a, Q>
+unsafe impl<Q, F> HandlerParam for FilteredFetcher<'_, Q, F>
 where
-    Q: Query,
-    Q::This<'a>: RefUnwindSafe,
+    Q: Query + 'static,
+    F: for<'x> FilterItem<Q::This<'x>> + 'static,
 {
-}
-
-#[cfg(feature = "rayon")]
-#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
-pub use rayon_impl::*;
+    type State = FetcherState<Q>;
 
-#[cfg(feature = "rayon")]
-mod rayon_impl {
-    use rayon::iter::plumbing::UnindexedConsumer;
-    use rayon::prelude::*;
+    type This<'a> = FilteredFetcher<'a, Q, F>;
 
-    use super::*;
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        FetcherState::init(world, config)
+    }
 
-    /// A [`ParallelIterator`] over entities matching the query `Q`.
-    ///
-    /// This is the parallel version of [`Iter`].
-    #[must_use = "iterators are lazy and do nothing unless consumed"]
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        FilteredFetcher {
+            fetcher: Fetcher { state, world },
+            _marker: PhantomData,
+        }
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+/// A [`HandlerParam`] which resolves the query `Q` on the entity linked to by
+/// the component `L`.
+///
+/// `L` is a component containing the [`EntityId`] of another entity, such as
+/// a parent, owner, or target. `Via` follows the link from an entity with `L`
+/// and fetches `Q` on the linked entity, so handlers don't need to combine two
+/// [`Fetcher`]s by hand. Links are followed one hop at a time, and
+/// [`follow`](Self::follow) walks chains of links.
+///
+/// `Via` accesses `L` immutably and the components of `Q` on any entity, so
+/// it conflicts with other parameters accessing the same components mutably.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::fetch::Via;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Target(EntityId);
+///
+/// impl AsRef<EntityId> for Target {
+///     fn as_ref(&self) -> &EntityId {
+///         &self.0
+///     }
+/// }
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, mut via: Via<Target, &mut Health>| {
+///     // Every turret damages its target.
+///     via.for_each_mut(|_turret, health| health.0 -= 1);
+/// });
+/// # world.send(E);
+/// ```
+pub struct Via<'a, L: Component, Q: Query> {
+    links: Fetcher<'a, (EntityId, &'static L)>,
+    targets: Fetcher<'a, Q>,
+}
+
+impl<'a, L, Q> Via<'a, L, Q>
+where
+    L: Component + AsRef<EntityId>,
+    Q: Query,
+{
+    /// Returns the entity linked to by `entity`.
+    ///
+    /// If `entity` doesn't exist or doesn't have the component `L`, then a
+    /// [`GetError`] is returned.
+    pub fn target(&self, entity: EntityId) -> Result<EntityId, GetError> {
+        self.links.get(entity).map(|(_, link)| *link.as_ref())
+    }
+
+    /// Returns the read-only query item of the entity linked to by `entity`.
+    ///
+    /// If `entity` or the linked entity don't exist, `entity` doesn't have the
+    /// component `L`, or the linked entity doesn't match the query, then a
+    /// [`GetError`] is returned.
+    pub fn get(&self, entity: EntityId) -> Result<Q::This<'_>, GetError>
+    where
+        Q: ReadOnlyQuery,
+    {
+        self.targets.get(self.target(entity)?)
+    }
+
+    /// Returns the query item of the entity linked to by `entity`.
+    ///
+    /// See [`get`](Self::get) for details.
+    pub fn get_mut(&mut self, entity: EntityId) -> Result<Q::This<'_>, GetError> {
+        let target = self.target(entity)?;
+        self.targets.get_mut(target)
+    }
+
+    /// Returns an iterator over every entity with the component `L` along
+    /// with the read-only query item of the linked entity. Entities whose
+    /// linked entity doesn't match the query are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, Q::This<'_>)> + '_
+    where
+        Q: ReadOnlyQuery,
+    {
+        self.links.iter().filter_map(|(id, link)| {
+            let item = self.targets.get(*link.as_ref()).ok()?;
+            Some((id, item))
+        })
+    }
+
+    /// Calls `f` with every entity with the component `L` and the query item
+    /// of the linked entity. Entities whose linked entity doesn't match the
+    /// query are skipped.
+    ///
+    /// The item only lives for the duration of the call to `f`, so several
+    /// entities may link to the same entity even if the query accesses data
+    /// mutably.
+    pub fn for_each_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(EntityId, Q::This<'_>),
+    {
+        for (id, link) in &self.links {
+            if let Ok(item) = self.targets.get_mut(*link.as_ref()) {
+                f(id, item);
+            }
+        }
+    }
+
+    /// Returns an iterator over the chain of entities linked to from
+    /// `entity`. The chain ends at the first entity without the component
+    /// `L` or that doesn't exist. `entity` itself is not included.
+    ///
+    /// The iterator never ends if the links form a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[derive(GlobalEvent)] struct E;
+    /// use evenio::fetch::Via;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Parent(EntityId);
+    ///
+    /// impl AsRef<EntityId> for Parent {
+    ///     fn as_ref(&self) -> &EntityId {
+    ///         &self.0
+    ///     }
+    /// }
+    ///
+    /// #[derive(Component)]
+    /// struct Name(&'static str);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, via: Via<Parent, &Name>| {
+    ///     for (child, _) in via.iter() {
+    ///         let path: Vec<_> = via
+    ///             .follow(child)
+    ///             .filter_map(|ancestor| via.targets().get(ancestor).ok())
+    ///             .map(|name| name.0)
+    ///             .collect();
+    ///
+    ///         println!("ancestors of {child:?}: {path:?}");
+    ///     }
+    /// });
+    /// # world.send(E);
+    /// ```
+    pub fn follow(&self, entity: EntityId) -> impl Iterator<Item = EntityId> + '_ {
+        let mut current = entity;
+
+        core::iter::from_fn(move || {
+            current = self.target(current).ok()?;
+            Some(current)
+        })
+    }
+
+    /// Returns the fetcher used to resolve the query on linked entities.
+    pub fn targets(&self) -> &Fetcher<'a, Q> {
+        &self.targets
+    }
+
+    /// Returns the fetcher used to resolve the query on linked entities.
+    pub fn targets_mut(&mut self) -> &mut Fetcher<'a, Q> {
+        &mut self.targets
+    }
+}
+
+impl<L: Component, Q: Query> fmt::Debug for Via<'_, L, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Via")
+            .field("links", &self.links)
+            .field("targets", &self.targets)
+            .finish()
+    }
+}
+
+unsafe impl<L, Q> HandlerParam for Via<'_, L, Q>
+where
+    L: Component,
+    Q: Query + 'static,
+{
+    type State = (FetcherState<(EntityId, &'static L)>, FetcherState<Q>);
+
+    type This<'a> = Via<'a, L, Q>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        Ok((
+            FetcherState::init(world, config)?,
+            FetcherState::init(world, config)?,
+        ))
+    }
+
+    unsafe fn get<'a>(
+        (links, targets): &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        Via {
+            links: Fetcher {
+                state: links,
+                world,
+            },
+            targets: Fetcher {
+                state: targets,
+                world,
+            },
+        }
+    }
+
+    fn refresh_archetype((links, targets): &mut Self::State, arch: &Archetype) {
+        links.refresh_archetype(arch);
+        targets.refresh_archetype(arch);
+    }
+
+    fn remove_archetype((links, targets): &mut Self::State, arch: &Archetype) {
+        links.remove_archetype(arch);
+        targets.remove_archetype(arch);
+    }
+}
+
+/// A [`HandlerParam`] which fetches a single entity from the world.
+///
+/// If there isn't exactly one entity that matches the [`Query`], a runtime
+/// panic occurs. This is useful for representing global variables or singleton
+/// entities.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component, Debug)]
+/// struct MyComponent(i32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, data: Single<&MyComponent>| {
+///     println!("The data is: {data:?}");
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, MyComponent(123));
+///
+/// world.send(E);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct Single<Q>(Q);
+
+impl<Q> Single<Q> {
+    /// Create a new instance of `Single`.
+    pub const fn new(q: Q) -> Self {
+        Self(q)
+    }
+
+    /// Consumes the `Single` and returns the inner value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::fetch::Single;
+    ///
+    /// let single = Single::new("bananas");
+    /// assert_eq!(Single::into_inner(single), "bananas");
+    /// ```
+    pub fn into_inner(this: Self) -> Q {
+        this.0
+    }
+}
+
+unsafe impl<Q: Query + 'static> HandlerParam for Single<Q> {
+    type State = FetcherState<Q>;
+
+    type This<'a> = Single<Q::This<'a>>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        FetcherState::init(world, config)
+    }
+
+    #[track_caller]
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        match TrySingle::get(state, info, event_ptr, target_location, world) {
+            Ok(item) => Single(item),
+            Err(e) => {
+                panic!(
+                    "failed to fetch exactly one entity matching the query `{}`: {e}",
+                    any::type_name::<Q>()
+                )
+            }
+        }
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+impl<'a, T> Deref for Single<&'a T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T> Deref for Single<&'a mut T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T> DerefMut for Single<&'a mut T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T> AsRef<T> for Single<&'a T> {
+    fn as_ref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<'a, T> AsRef<T> for Single<&'a mut T> {
+    fn as_ref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<'a, T> AsMut<T> for Single<&'a mut T> {
+    fn as_mut(&mut self) -> &mut T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Single<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+/// Like [`Single`], but spawns an entity with the component's [`Default`]
+/// value if no entity has the component.
+///
+/// This is useful for global settings or resources which should always exist,
+/// without needing a separate handler to create them. `SingleOrDefault` can
+/// be used with `&C` and `&mut C`.
+///
+/// The entity is spawned when the handler is added. If it is later despawned
+/// or loses the component, the handler receives `None` and the default is
+/// spawned again at the end of the flush. Like [`Single`], a runtime panic
+/// occurs if more than one entity has the component.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component, Default, Debug)]
+/// struct Settings {
+///     volume: u32,
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, mut settings: SingleOrDefault<&mut Settings>| {
+///     if let Some(settings) = settings.get_mut() {
+///         settings.volume += 1;
+///     }
+/// });
+///
+/// world.send(E);
+/// world.send(E);
+///
+/// let volume = world.iter::<&Settings>().next().unwrap().volume;
+/// assert_eq!(volume, 2);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct SingleOrDefault<Q>(Option<Q>);
+
+impl<Q> SingleOrDefault<Q> {
+    /// Consumes the `SingleOrDefault` and returns the inner value, or `None`
+    /// if no entity had the component.
+    pub fn into_inner(this: Self) -> Option<Q> {
+        this.0
+    }
+}
+
+impl<T> SingleOrDefault<&T> {
+    /// Returns the component, or `None` if no entity had it.
+    pub fn get(&self) -> Option<&T> {
+        self.0
+    }
+}
+
+impl<T> SingleOrDefault<&mut T> {
+    /// Returns the component, or `None` if no entity had it.
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_deref()
+    }
+
+    /// Returns the component mutably, or `None` if no entity had it.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.0.as_deref_mut()
+    }
+}
+
+/// Spawns an entity with the default value of `C` if no entity has `C`.
+fn spawn_default_if_missing<C: Component + Default>(world: &mut World) {
+    if world.iter::<With<&C>>().len() == 0 {
+        let e = world.spawn();
+        world.insert(e, C::default());
+    }
+}
+
+/// Fetches the single entity matching `Q` for a [`SingleOrDefault`]. If there
+/// is none, the default of `C` is spawned at the end of the flush.
+///
+/// # Safety
+///
+/// Same as [`HandlerParam::get`].
+#[track_caller]
+unsafe fn get_or_respawn<'a, Q, C>(
+    state: &'a mut FetcherState<Q>,
+    info: &'a HandlerInfo,
+    event_ptr: EventPtr<'a>,
+    target_location: EntityLocation,
+    world: UnsafeWorldCell<'a>,
+) -> Option<Q::This<'a>>
+where
+    Q: Query + 'static,
+    C: Component + Default,
+{
+    match <TrySingle<Q> as HandlerParam>::get(state, info, event_ptr, target_location, world) {
+        Ok(item) => Some(item),
+        Err(SingleError::QueryDoesNotMatch) => {
+            world.spawn_task(async { Box::new(spawn_default_if_missing::<C>) as Completion });
+            None
+        }
+        Err(e) => panic!(
+            "failed to fetch exactly one entity matching the query `{}`: {e}",
+            any::type_name::<Q>()
+        ),
+    }
+}
+
+unsafe impl<C: Component + Default> HandlerParam for SingleOrDefault<&'_ C> {
+    type State = FetcherState<&'static C>;
+
+    type This<'a> = SingleOrDefault<&'a C>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        spawn_default_if_missing::<C>(world);
+
+        // Respawning the default spawns a task, which isn't thread safe.
+        config.set_thread_local();
+
+        FetcherState::init(world, config)
+    }
+
+    #[track_caller]
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        SingleOrDefault(get_or_respawn::<_, C>(
+            state,
+            info,
+            event_ptr,
+            target_location,
+            world,
+        ))
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+unsafe impl<C> HandlerParam for SingleOrDefault<&'_ mut C>
+where
+    C: Component<Mutability = Mutable> + Default,
+{
+    type State = FetcherState<&'static mut C>;
+
+    type This<'a> = SingleOrDefault<&'a mut C>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        spawn_default_if_missing::<C>(world);
+
+        // Respawning the default spawns a task, which isn't thread safe.
+        config.set_thread_local();
+
+        FetcherState::init(world, config)
+    }
+
+    #[track_caller]
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        SingleOrDefault(get_or_respawn::<_, C>(
+            state,
+            info,
+            event_ptr,
+            target_location,
+            world,
+        ))
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+/// Like [`Single`], but yields a `Result` rather than panicking on error.
+///
+/// This is useful if you need to explicitly handle the situation where the
+/// query does not match exactly one entity.
+pub type TrySingle<Q> = Result<Q, SingleError>;
+
+unsafe impl<Q: Query + 'static> HandlerParam for TrySingle<Q> {
+    type State = FetcherState<Q>;
+
+    type This<'a> = Result<Q::This<'a>, SingleError>;
+
+    fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        FetcherState::init(world, config)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        let archetypes = world.archetypes();
+
+        // Only nonempty archetypes are in the map, so we can count the matches
+        // without iterating.
+        match state.map.keys() {
+            [] => return Err(SingleError::QueryDoesNotMatch),
+            [idx] if archetypes.get(*idx).unwrap_unchecked().entity_count() == 1 => {}
+            _ => {
+                return Err(SingleError::MoreThanOneMatch(
+                    state.matched_entity_ids(archetypes),
+                ))
+            }
+        }
+
+        Ok(state.iter_mut(archetypes).next().unwrap_unchecked())
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+/// Error raised when fetching exactly one entity matching a query fails.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum SingleError {
+    /// Query does not match any entities
+    QueryDoesNotMatch,
+    /// More than one entity matched the query. Contains the IDs of every
+    /// matching entity.
+    MoreThanOneMatch(Vec<EntityId>),
+}
+
+impl fmt::Display for SingleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SingleError::QueryDoesNotMatch => write!(f, "query does not match any entities"),
+            SingleError::MoreThanOneMatch(ids) => write!(
+                f,
+                "more than one entity matched the query ({} matches: {ids:?})",
+                ids.len()
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for SingleError {}
+
+/// Iterator over entities matching the query `Q`.
+///
+/// Entities are visited in a deterministic but otherwise unspecified order.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Iter<'a, Q: Query> {
+    /// Pointer into the array of archetype states. This pointer moves forward
+    /// until it reaches `state_last`.
+    state: NonNull<Q::ArchState>,
+    /// Pointer to the last arch state, or dangling if there are no arch states.
+    /// This is _not_ a one-past-the-end pointer.
+    state_last: NonNull<Q::ArchState>,
+    /// Pointer into the array of archetype indices. This pointer moves forward
+    /// in lockstep with `state`.
+    index: NonNull<ArchetypeIdx>,
+    /// Current row of the current archetype.
+    row: ArchetypeRow,
+    /// Number of entities in the current archetype.
+    len: u32,
+    archetypes: &'a Archetypes,
+}
+
+impl<'a, Q: Query> Iterator for Iter<'a, Q> {
+    type Item = Q::This<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        // If we reached the end of the current archetype, move on to the next
+        // or return `None`.
+        if self.row.0 == self.len {
+            if self.state == self.state_last {
+                // There are no more archetypes to iterate through.
+                return None;
+            }
+
+            // Move on to the next archetype.
+            self.state = unsafe { NonNull::new_unchecked(self.state.as_ptr().add(1)) };
+            self.index = unsafe { NonNull::new_unchecked(self.index.as_ptr().add(1)) };
+
+            let idx = unsafe { *self.index.as_ptr() };
+            let arch = unsafe { self.archetypes.get(idx).unwrap_unchecked() };
+
+            self.row = ArchetypeRow(0);
+            self.len = arch.entity_count();
+
+            // SAFETY: Fetcher state only contains nonempty archetypes.
+            unsafe { assume_unchecked(self.len > 0) };
+        }
+
+        // Execute the query for the current entity.
+        let state = unsafe { &*self.state.as_ptr().cast_const() };
+        let item = unsafe { Q::get(state, self.row) };
+
+        // Move on to the next row (entity) in the archetype.
+        self.row.0 += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for Iter<'_, Q> {
+    fn len(&self) -> usize {
+        // The number of rows in the current archetype which we did not iterate
+        // over yet.
+        let mut remaining = self.len - self.row.0;
+
+        let mut index = self.index.as_ptr();
+
+        // The index of the last archetype we will iterate through.
+        let index_last = unsafe {
+            // TODO: use `.sub_ptr` when stabilized.
+            index.add(self.state_last.as_ptr().offset_from(self.state.as_ptr()) as usize)
+        };
+
+        // Add the number of rows of each remaining archetype to `remaining`.
+        while index != index_last {
+            index = unsafe { index.add(1) };
+
+            remaining += unsafe { self.archetypes.get(*index).unwrap_unchecked() }.entity_count();
+        }
+
+        // Return the total.
+        remaining as usize
+    }
+}
+
+impl<Q: Query> FusedIterator for Iter<'_, Q> {}
+
+// SAFETY: Iter is only cloneable when the query is read-only.
+impl<'a, Q: ReadOnlyQuery> Clone for Iter<'a, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state,
+            state_last: self.state_last,
+            index: self.index,
+            row: self.row,
+            len: self.len,
+            archetypes: self.archetypes,
+        }
+    }
+}
+
+impl<Q: Query> fmt::Debug for Iter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter")
+            .field("state", &self.state)
+            .field("state_last", &self.state_last)
+            .field("index", &self.index)
+            .field("row", &self.row)
+            .field("len", &self.len)
+            .field("archetypes", &self.archetypes)
+            .finish()
+    }
+}
+
+unsafe impl<'a, Q> Send for Iter<'_, Q>
+where
+    Q: Query,
+    Q::This<'a>: Send,
+{
+}
+
+unsafe impl<'a, Q> Sync for Iter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: Sync,
+{
+}
+
+impl<'a, Q> UnwindSafe for Iter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: UnwindSafe,
+{
+}
+
+impl<'a, Q> RefUnwindSafe for Iter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: RefUnwindSafe,
+{
+}
+
+/// Iterator over entities matching the query `Q` which owns its fetcher state.
+///
+/// See [`World::iter`] and [`World::iter_mut`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct QueryIter<'a, Q: Query> {
+    state: FetcherState<Q>,
+    /// Position of the next archetype to visit in the fetcher state.
+    next: usize,
+    /// Position of the current archetype in the fetcher state.
+    current: usize,
+    /// Current row of the current archetype.
+    row: ArchetypeRow,
+    /// Number of entities in the current archetype.
+    len: u32,
+    archetypes: &'a Archetypes,
+}
+
+impl<'a, Q: Query> QueryIter<'a, Q> {
+    /// Creates an iterator over the archetypes in `state`.
+    pub(crate) fn new(state: FetcherState<Q>, archetypes: &'a Archetypes) -> Self {
+        Self {
+            state,
+            next: 0,
+            current: 0,
+            row: ArchetypeRow(0),
+            len: 0,
+            archetypes,
+        }
+    }
+}
+
+impl<'a, Q: Query> Iterator for QueryIter<'a, Q> {
+    type Item = Q::This<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row.0 == self.len {
+            // Move on to the next archetype or return `None`.
+            let &idx = self.state.map.keys().get(self.next)?;
+            let arch = unsafe { self.archetypes.get(idx).unwrap_unchecked() };
+
+            self.current = self.next;
+            self.next += 1;
+            self.row = ArchetypeRow(0);
+            self.len = arch.entity_count();
+
+            // SAFETY: Fetcher state only contains nonempty archetypes.
+            unsafe { assume_unchecked(self.len > 0) };
+        }
+
+        let state = unsafe { self.state.map.values().get_unchecked(self.current) };
+        // SAFETY: The arch state points into the world's columns, which are
+        // borrowed for `'a`.
+        let item = unsafe { Q::get(state, self.row) };
+
+        self.row.0 += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for QueryIter<'_, Q> {
+    fn len(&self) -> usize {
+        let remaining = self.len - self.row.0;
+
+        self.state.map.keys()[self.next..]
+            .iter()
+            .map(|&idx| unsafe { self.archetypes.get(idx).unwrap_unchecked() }.entity_count())
+            .sum::<u32>() as usize
+            + remaining as usize
+    }
+}
+
+impl<Q: Query> FusedIterator for QueryIter<'_, Q> {}
+
+impl<Q: Query> fmt::Debug for QueryIter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryIter")
+            .field("state", &self.state)
+            .field("next", &self.next)
+            .field("current", &self.current)
+            .field("row", &self.row)
+            .field("len", &self.len)
+            .field("archetypes", &self.archetypes)
+            .finish()
+    }
+}
+
+unsafe impl<'a, Q> Send for QueryIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: Send,
+    Q::State: Send,
+{
+}
+
+unsafe impl<'a, Q> Sync for QueryIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: Sync,
+    Q::State: Sync,
+{
+}
+
+/// A query prepared for repeated execution outside of handlers.
+///
+/// [`World::iter`] matches the query against every archetype in the world each
+/// time it is called. A `PreparedQuery` remembers which archetypes match, so
+/// executing it again only needs to visit those. The set of matched archetypes
+/// is recomputed only after archetypes have been added to or removed from the
+/// world, and otherwise only the archetypes whose entities changed since the
+/// last execution are updated.
+///
+/// The prepared state is shared with every [`CachedFetcher`] and
+/// `PreparedQuery` of the same query in the world. Handlers use the prepared
+/// state through a [`CachedFetcher`], which keeps it up to date as the
+/// handler is told about archetypes.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::fetch::PreparedQuery;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// let mut world = World::new();
+/// let mut query = PreparedQuery::<&mut Health>::new(&mut world);
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(10));
+///
+/// for health in query.iter_mut(&mut world) {
+///     health.0 -= 1;
+/// }
+///
+/// assert_eq!(world.get::<Health>(e).unwrap().0, 9);
+/// ```
+pub struct PreparedQuery<Q: Query> {
+    shared: Rc<UnsafeCell<SharedFetcherState<Q>>>,
+    /// The components referenced by the query.
+    components: Vec<ComponentId>,
+    /// The world this query was created for.
+    world_id: u64,
+}
+
+impl<Q: Query + 'static> PreparedQuery<Q> {
+    /// Prepares the query `Q` for execution in `world`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query fails to initialize or has conflicting component
+    /// access, like `(&mut A, &A)`.
+    #[track_caller]
+    pub fn new(world: &mut World) -> Self {
+        let mut config = HandlerConfig::new();
+        let state = world.init_query::<Q>(&mut config);
+
+        let components = config
+            .referenced_components
+            .iter()
+            .filter_map(|idx| world.components().get_by_index(idx))
+            .map(|info| info.id())
+            .collect();
+
+        let shared = SharedFetcherState::get_or_insert(world, state);
+
+        // SAFETY: No handler is running, since we have `&mut World`.
+        unsafe { (*shared.get()).sync(world.archetypes()) };
+
+        Self {
+            shared,
+            components,
+            world_id: world.id(),
+        }
+    }
+
+    /// Returns an iterator over all entities in `world` matching the read-only
+    /// query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `world` is not the world this query was created for, or if a
+    /// component referenced by the query has been removed from the world.
+    #[track_caller]
+    pub fn iter<'a>(&'a mut self, world: &'a World) -> Iter<'a, Q>
+    where
+        Q: ReadOnlyQuery,
+    {
+        self.refresh(world);
+
+        unsafe { (*self.shared.get()).fetcher.iter(world.archetypes()) }
+    }
+
+    /// Returns an iterator over all entities in `world` matching the query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `world` is not the world this query was created for, or if a
+    /// component referenced by the query has been removed from the world.
+    #[track_caller]
+    pub fn iter_mut<'a>(&'a mut self, world: &'a mut World) -> Iter<'a, Q> {
+        self.refresh(world);
+
+        unsafe { (*self.shared.get()).fetcher.iter_mut(world.archetypes()) }
+    }
+
+    /// Returns the archetypes matched by the query as of its last execution,
+    /// including empty ones.
+    pub fn matched_archetypes(&self) -> Vec<ArchetypeIdx> {
+        // SAFETY: `matched` is only mutated through `&mut World`, which can't
+        // be held during this call by a handler.
+        unsafe { (*self.shared.get()).matched.clone() }
+    }
+
+    /// Brings the shared state up to date with `world`.
+    #[track_caller]
+    fn refresh(&mut self, world: &World) {
+        assert_eq!(
+            self.world_id,
+            world.id(),
+            "`PreparedQuery` was executed in a different world than the one it was created for"
+        );
+
+        world.publish_address();
+
+        for &id in &self.components {
+            assert!(
+                world.components().get(id).is_some(),
+                "component referenced by `PreparedQuery<{}>` was removed from the world",
+                any::type_name::<Q>()
+            );
+        }
+
+        let archetypes = world.archetypes();
+
+        // SAFETY: Handlers don't run during this call.
+        let shared = unsafe { &*self.shared.get() };
+
+        if shared.generation == Some(archetypes.generation())
+            && shared.synced == archetypes.changes()
+        {
+            // Iterators of other `PreparedQuery`s may still borrow the state.
+            return;
+        }
+
+        // SAFETY: The world changed since the state was last synced, so no
+        // iterator borrowing the world, and therefore the state, is alive.
+        unsafe { (*self.shared.get()).sync(archetypes) };
+    }
+}
+
+impl<Q: Query> fmt::Debug for PreparedQuery<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // SAFETY: The shared state is only mutated while no handler is running.
+        let shared = unsafe { &*self.shared.get() };
+
+        f.debug_struct("PreparedQuery")
+            .field("fetcher", &shared.fetcher)
+            .field("matched", &shared.matched)
+            .field("components", &self.components)
+            .field("world_id", &self.world_id)
+            .finish()
+    }
+}
+
+/// Iterator over entities matching the query `Q` which visits archetypes in a
+/// stable order.
+///
+/// Archetypes are visited in the lexicographic order of their sorted component
+/// names. Entities within an archetype are visited in the order they appear in
+/// the archetype.
+///
+/// See [`Fetcher::iter_sorted`] and [`Fetcher::iter_sorted_mut`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct SortedIter<'a, Q: Query> {
+    states: &'a [Q::ArchState],
+    indices: &'a [ArchetypeIdx],
+    /// Positions into `states` and `indices` in the order they are visited.
+    order: Vec<u32>,
+    /// Index into `order` of the next archetype to visit.
+    next: usize,
+    /// Position of the current archetype in `states` and `indices`.
+    current: u32,
+    /// Current row of the current archetype.
+    row: ArchetypeRow,
+    /// Number of entities in the current archetype.
+    len: u32,
+    archetypes: &'a Archetypes,
+}
+
+impl<'a, Q: Query> Iterator for SortedIter<'a, Q> {
+    type Item = Q::This<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row.0 == self.len {
+            // Move on to the next archetype or return `None`.
+            let &pos = self.order.get(self.next)?;
+            self.next += 1;
+
+            let idx = unsafe { *self.indices.get_unchecked(pos as usize) };
+            let arch = unsafe { self.archetypes.get(idx).unwrap_unchecked() };
+
+            self.current = pos;
+            self.row = ArchetypeRow(0);
+            self.len = arch.entity_count();
+
+            // SAFETY: Fetcher state only contains nonempty archetypes.
+            unsafe { assume_unchecked(self.len > 0) };
+        }
+
+        let state = unsafe { self.states.get_unchecked(self.current as usize) };
+        let item = unsafe { Q::get(state, self.row) };
+
+        self.row.0 += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for SortedIter<'_, Q> {
+    fn len(&self) -> usize {
+        let remaining = self.len - self.row.0;
+
+        self.order[self.next..]
+            .iter()
+            .map(|&pos| {
+                let idx = unsafe { *self.indices.get_unchecked(pos as usize) };
+                unsafe { self.archetypes.get(idx).unwrap_unchecked() }.entity_count()
+            })
+            .sum::<u32>() as usize
+            + remaining as usize
+    }
+}
+
+impl<Q: Query> FusedIterator for SortedIter<'_, Q> {}
+
+// SAFETY: SortedIter is only cloneable when the query is read-only.
+impl<'a, Q: ReadOnlyQuery> Clone for SortedIter<'a, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            states: self.states,
+            indices: self.indices,
+            order: self.order.clone(),
+            next: self.next,
+            current: self.current,
+            row: self.row,
+            len: self.len,
+            archetypes: self.archetypes,
+        }
+    }
+}
+
+impl<Q: Query> fmt::Debug for SortedIter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SortedIter")
+            .field("states", &self.states)
+            .field("indices", &self.indices)
+            .field("order", &self.order)
+            .field("next", &self.next)
+            .field("current", &self.current)
+            .field("row", &self.row)
+            .field("len", &self.len)
+            .field("archetypes", &self.archetypes)
+            .finish()
+    }
+}
+
+unsafe impl<'a, Q> Send for SortedIter<'_, Q>
+where
+    Q: Query,
+    Q::This<'a>: Send,
+{
+}
+
+unsafe impl<'a, Q> Sync for SortedIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: Sync,
+{
+}
+
+impl<'a, Q> UnwindSafe for SortedIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: UnwindSafe,
+{
+}
+
+impl<'a, Q> RefUnwindSafe for SortedIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: RefUnwindSafe,
+{
+}
+
+/// Iterator over the entities of a [`RestrictedFetcher`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct RestrictedIter<'a, Q: Query> {
+    states: &'a [Q::ArchState],
+    indices: &'a [ArchetypeIdx],
+    /// Positions into `states` and `indices` of the archetypes left to visit.
+    positions: core::slice::Iter<'a, u32>,
+    /// Position of the current archetype in `states` and `indices`.
+    current: u32,
+    /// Current row of the current archetype.
+    row: ArchetypeRow,
+    /// Number of entities in the current archetype.
+    len: u32,
+    archetypes: &'a Archetypes,
+}
+
+impl<'a, Q: Query> Iterator for RestrictedIter<'a, Q> {
+    type Item = Q::This<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row.0 == self.len {
+            // Move on to the next archetype or return `None`.
+            let &pos = self.positions.next()?;
+
+            let idx = unsafe { *self.indices.get_unchecked(pos as usize) };
+            let arch = unsafe { self.archetypes.get(idx).unwrap_unchecked() };
+
+            self.current = pos;
+            self.row = ArchetypeRow(0);
+            self.len = arch.entity_count();
+
+            // SAFETY: Fetcher state only contains nonempty archetypes.
+            unsafe { assume_unchecked(self.len > 0) };
+        }
+
+        let state = unsafe { self.states.get_unchecked(self.current as usize) };
+        let item = unsafe { Q::get(state, self.row) };
+
+        self.row.0 += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for RestrictedIter<'_, Q> {
+    fn len(&self) -> usize {
+        let remaining = self.len - self.row.0;
+
+        self.positions
+            .as_slice()
+            .iter()
+            .map(|&pos| {
+                let idx = unsafe { *self.indices.get_unchecked(pos as usize) };
+                unsafe { self.archetypes.get(idx).unwrap_unchecked() }.entity_count()
+            })
+            .sum::<u32>() as usize
+            + remaining as usize
+    }
+}
+
+impl<Q: Query> FusedIterator for RestrictedIter<'_, Q> {}
+
+// SAFETY: RestrictedIter is only cloneable when the query is read-only.
+impl<Q: ReadOnlyQuery> Clone for RestrictedIter<'_, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            states: self.states,
+            indices: self.indices,
+            positions: self.positions.clone(),
+            current: self.current,
+            row: self.row,
+            len: self.len,
+            archetypes: self.archetypes,
+        }
+    }
+}
+
+impl<Q: Query> fmt::Debug for RestrictedIter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestrictedIter")
+            .field("states", &self.states)
+            .field("indices", &self.indices)
+            .field("positions", &self.positions)
+            .field("current", &self.current)
+            .field("row", &self.row)
+            .field("len", &self.len)
+            .field("archetypes", &self.archetypes)
+            .finish()
+    }
+}
+
+unsafe impl<'a, Q> Send for RestrictedIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: Send,
+{
+}
+
+unsafe impl<'a, Q> Sync for RestrictedIter<'a, Q>
+where
+    Q: Query,
+    Q::This<'a>: Sync,
+{
+}
+
+/// Iterator over groups of query items which share the same key.
+///
+/// See [`Fetcher::group_by`] and [`Fetcher::group_by_mut`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct GroupBy<'a, Q: Query, K> {
+    /// Items sorted by key.
+    items: vec::IntoIter<(K, Q::This<'a>)>,
+}
+
+impl<'a, Q: Query, K: Ord> GroupBy<'a, Q, K> {
+    fn new<F>(iter: Iter<'a, Q>, mut key: F) -> Self
+    where
+        F: FnMut(&Q::This<'a>) -> K,
+    {
+        let mut items: Vec<_> = iter.map(|item| (key(&item), item)).collect();
+
+        // Stable sort to preserve the iteration order within groups.
+        items.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Self {
+            items: items.into_iter(),
+        }
+    }
+}
+
+impl<'a, Q: Query, K: Ord> Iterator for GroupBy<'a, Q, K> {
+    type Item = (K, vec::IntoIter<Q::This<'a>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, first) = self.items.next()?;
+
+        let mut group = vec![first];
+
+        while let Some((next_key, _)) = self.items.as_slice().first() {
+            if *next_key != key {
+                break;
+            }
+
+            // SAFETY: We just checked that there is another item.
+            let (_, item) = unsafe { self.items.next().unwrap_unchecked() };
+            group.push(item);
+        }
+
+        Some((key, group.into_iter()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.items.len();
+        (usize::from(len > 0), Some(len))
+    }
+}
+
+impl<Q: Query, K: Ord> FusedIterator for GroupBy<'_, Q, K> {}
+
+impl<Q: Query, K: fmt::Debug> fmt::Debug for GroupBy<'_, Q, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = self.items.as_slice().iter().map(|(k, _)| k);
+        f.debug_struct("GroupBy")
+            .field("keys", &keys.collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Iterator over entities matching the query `Q` whose items satisfy the
+/// predicate of `F`.
+///
+/// See [`FilteredFetcher`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct FilteredIter<'a, Q: Query, F> {
+    iter: Iter<'a, Q>,
+    _marker: PhantomData<fn() -> F>,
+}
+
+impl<'a, Q: Query, F> FilteredIter<'a, Q, F> {
+    fn new(iter: Iter<'a, Q>) -> Self {
+        Self {
+            iter,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q, F> Iterator for FilteredIter<'a, Q, F>
+where
+    Q: Query,
+    F: for<'x> FilterItem<Q::This<'x>>,
+{
+    type Item = Q::This<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().find(|item| F::filter(item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<Q, F> FusedIterator for FilteredIter<'_, Q, F>
+where
+    Q: Query,
+    F: for<'x> FilterItem<Q::This<'x>>,
+{
+}
+
+impl<Q: ReadOnlyQuery, F> Clone for FilteredIter<'_, Q, F> {
+    fn clone(&self) -> Self {
+        Self::new(self.iter.clone())
+    }
+}
+
+impl<Q: Query, F> fmt::Debug for FilteredIter<'_, Q, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredIter")
+            .field("iter", &self.iter)
+            .finish()
+    }
+}
+
+/// A [`HandlerParam`] for accessing components chosen at runtime through
+/// type-erased [`Ptr`]s and [`PtrMut`]s.
+///
+/// The components must be declared with [`IntoHandler::access_components`],
+/// which registers their access with the handler. Accessing a component which
+/// wasn't declared, or mutably accessing one declared as [`Access::Read`],
+/// panics.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::access::Access;
+/// use evenio::fetch::PtrFetcher;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100));
+///
+/// let health = world.add_component::<Health>();
+///
+/// world.add_handler(
+///     (move |_: Receiver<E>, mut f: PtrFetcher| {
+///         for (_, ptr) in f.iter_mut(health.index()) {
+///             unsafe { ptr.deref_mut::<Health>().0 -= 10 };
+///         }
+///     })
+///     .access_components([(health, Access::ReadWrite)]),
+/// );
+///
+/// world.send(E);
+///
+/// assert_eq!(world.get::<Health>(e).unwrap().0, 90);
+/// ```
+///
+/// [`IntoHandler::access_components`]: crate::handler::IntoHandler::access_components
+pub struct PtrFetcher<'a> {
+    components: &'a [(ComponentIdx, Access)],
+    world: UnsafeWorldCell<'a>,
+}
+
+impl<'a> PtrFetcher<'a> {
+    /// Returns a type-erased pointer to the component with index `component`
+    /// on `entity`. Returns `None` if `entity` doesn't exist or doesn't have
+    /// the component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component wasn't declared with at least
+    /// [`Access::Read`].
+    pub fn get(&self, entity: EntityId, component: ComponentIdx) -> Option<Ptr<'_>> {
+        self.check_access(component, Access::Read);
+        // SAFETY: The component is declared with read access.
+        unsafe {
+            self.get_raw(entity, component)
+                .map(|(ptr, info)| Ptr::new(ptr, info))
+        }
+    }
+
+    /// Returns a type-erased mutable pointer to the component with index
+    /// `component` on `entity`. Returns `None` if `entity` doesn't exist or
+    /// doesn't have the component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component wasn't declared with [`Access::ReadWrite`].
+    pub fn get_mut(&mut self, entity: EntityId, component: ComponentIdx) -> Option<PtrMut<'_>> {
+        self.check_access(component, Access::ReadWrite);
+        // SAFETY: The component is declared with write access, and `&mut self`
+        // prevents aliasing within this handler.
+        unsafe {
+            self.get_raw(entity, component)
+                .map(|(ptr, info)| PtrMut::new(ptr, info))
+        }
+    }
+
+    /// Returns an iterator over every entity with the component at index
+    /// `component`, along with a type-erased pointer to the component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component wasn't declared with at least
+    /// [`Access::Read`].
+    pub fn iter(&self, component: ComponentIdx) -> impl Iterator<Item = (EntityId, Ptr<'_>)> + '_ {
+        self.check_access(component, Access::Read);
+        // SAFETY: The component is declared with read access.
+        unsafe {
+            self.iter_raw(component)
+                .map(|(id, ptr, info)| (id, Ptr::new(ptr, info)))
+        }
+    }
+
+    /// Returns an iterator over every entity with the component at index
+    /// `component`, along with a type-erased mutable pointer to the
+    /// component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component wasn't declared with [`Access::ReadWrite`].
+    pub fn iter_mut(
+        &mut self,
+        component: ComponentIdx,
+    ) -> impl Iterator<Item = (EntityId, PtrMut<'_>)> + '_ {
+        self.check_access(component, Access::ReadWrite);
+        // SAFETY: The component is declared with write access, every entity
+        // is visited once, and `&mut self` prevents aliasing within this
+        // handler.
+        unsafe {
+            self.iter_raw(component)
+                .map(|(id, ptr, info)| (id, PtrMut::new(ptr, info)))
+        }
+    }
+
+    fn check_access(&self, component: ComponentIdx, access: Access) {
+        let declared = self
+            .components
+            .iter()
+            .find(|&&(idx, _)| idx == component)
+            .map_or(Access::None, |&(_, access)| access);
+
+        assert!(
+            declared == Access::ReadWrite || declared == access,
+            "component {component:?} was not declared with {access:?} access in \
+             `access_components`"
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Must have permission to access the component.
+    unsafe fn get_raw(
+        &self,
+        entity: EntityId,
+        component: ComponentIdx,
+    ) -> Option<(NonNull<u8>, &'a ComponentInfo)> {
+        let loc = self.world.entities().get(entity)?;
+        let info = self.world.components().get_by_index(component)?;
+
+        let arch = self.world.archetypes().get(loc.archetype)?;
+        let col = arch.column_of(component)?;
+
+        Some((column_ptr(col.data(), info, loc.row), info))
+    }
+
+    /// # Safety
+    ///
+    /// Must have permission to access the component.
+    unsafe fn iter_raw(
+        &self,
+        component: ComponentIdx,
+    ) -> impl Iterator<Item = (EntityId, NonNull<u8>, &'a ComponentInfo)> + 'a {
+        let world = self.world;
+        let info = world.components().get_by_index(component);
+
+        info.into_iter().flat_map(move |info| {
+            info.member_of.iter().flat_map(move |&idx| {
+                let arch = unsafe { world.archetypes().get(idx).unwrap_unchecked() };
+                let col = unsafe { arch.column_of(component).unwrap_unchecked() };
+                let data = col.data();
+
+                arch.entity_ids().iter().enumerate().map(move |(row, &id)| {
+                    (id, column_ptr(data, info, ArchetypeRow(row as u32)), info)
+                })
+            })
+        })
+    }
+}
+
+impl fmt::Debug for PtrFetcher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PtrFetcher")
+            .field("components", &self.components)
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe impl HandlerParam for PtrFetcher<'_> {
+    type State = Box<[(ComponentIdx, Access)]>;
+
+    type This<'a> = PtrFetcher<'a>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        for &(idx, access) in &config.accessed_components.clone() {
+            config.push_component_access(ComponentAccess::var(idx, access));
+            config.insert_referenced_components(idx);
+        }
+
+        Ok(config.accessed_components.clone().into_boxed_slice())
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        PtrFetcher {
+            components: state,
+            world,
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub use rayon_impl::*;
+
+#[cfg(feature = "rayon")]
+mod rayon_impl {
+    use rayon::iter::plumbing::UnindexedConsumer;
+    use rayon::prelude::*;
+
+    use super::*;
+
+    /// A [`ParallelIterator`] over entities matching the query `Q`.
+    ///
+    /// This is the parallel version of [`Iter`].
+    ///
+    /// By default, work is split using rayon's adaptive heuristic. When the
+    /// per-entity work is very cheap or very expensive, the splitting can be
+    /// tuned with [`min_batch_size`] and [`batches_per_thread`].
+    ///
+    /// [`min_batch_size`]: Self::min_batch_size
+    /// [`batches_per_thread`]: Self::batches_per_thread
+    #[must_use = "iterators are lazy and do nothing unless consumed"]
     pub struct ParIter<'a, Q: Query> {
         pub(super) arch_states: &'a [Q::ArchState],
         pub(super) arch_indices: &'a [ArchetypeIdx],
         pub(super) archetypes: &'a Archetypes,
+        pub(super) min_batch_size: usize,
+        pub(super) batches_per_thread: Option<usize>,
+    }
+
+    impl<Q: Query> ParIter<'_, Q> {
+        /// Sets the minimum number of entities processed together as a single
+        /// job. Larger batches reduce scheduling overhead when the work per
+        /// entity is small.
+        ///
+        /// Batches never span multiple archetypes, so archetypes with fewer
+        /// entities than `size` are processed as a single batch.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use evenio::prelude::*;
+        /// use evenio::rayon::prelude::*;
+        ///
+        /// #[derive(Component)]
+        /// struct Transform(f32);
+        ///
+        /// fn propagate(mut f: Fetcher<&mut Transform>) {
+        ///     f.par_iter_mut()
+        ///         .min_batch_size(1024)
+        ///         .for_each(|t| t.0 *= 2.0);
+        /// }
+        /// ```
+        pub fn min_batch_size(mut self, size: usize) -> Self {
+            self.min_batch_size = size.max(1);
+            self
+        }
+
+        /// Hints that each archetype should be split into no more than
+        /// roughly `count` batches for every thread in the current rayon
+        /// thread pool. Passing `None` restores the default behavior.
+        ///
+        /// This is combined with [`min_batch_size`], with the larger of the two
+        /// resulting batch sizes taking effect.
+        ///
+        /// [`min_batch_size`]: Self::min_batch_size
+        pub fn batches_per_thread(mut self, count: Option<usize>) -> Self {
+            self.batches_per_thread = count.map(|c| c.max(1));
+            self
+        }
+
+        /// Returns the minimum batch size to use for an archetype with the
+        /// given number of entities.
+        fn batch_size_for(&self, entity_count: usize) -> usize {
+            let from_batches = match self.batches_per_thread {
+                Some(count) => {
+                    let batches = rayon::current_num_threads().saturating_mul(count);
+                    entity_count.div_ceil(batches)
+                }
+                None => 1,
+            };
+
+            self.min_batch_size.max(from_batches)
+        }
+    }
+
+    impl<Q: ReadOnlyQuery> Clone for ParIter<'_, Q> {
+        fn clone(&self) -> Self {
+            Self {
+                arch_states: self.arch_states,
+                arch_indices: self.arch_indices,
+                archetypes: self.archetypes,
+                min_batch_size: self.min_batch_size,
+                batches_per_thread: self.batches_per_thread,
+            }
+        }
+    }
+
+    impl<Q: Query> fmt::Debug for ParIter<'_, Q> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("ParIter")
+                .field("arch_states", &self.arch_states)
+                .field("arch_indices", &self.arch_indices)
+                .field("archetypes", &self.archetypes)
+                .field("min_batch_size", &self.min_batch_size)
+                .field("batches_per_thread", &self.batches_per_thread)
+                .finish()
+        }
+    }
+
+    impl<'a, Q> ParallelIterator for ParIter<'a, Q>
+    where
+        Q: Query,
+        Q::This<'a>: Send,
+    {
+        type Item = Q::This<'a>;
+
+        fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where
+            C: UnindexedConsumer<Self::Item>,
+        {
+            unsafe { assume_unchecked(self.arch_states.len() == self.arch_indices.len()) };
+
+            self.arch_states
+                .par_iter()
+                .zip_eq(self.arch_indices)
+                .flat_map(|(state, &index)| {
+                    let entity_count =
+                        unsafe { self.archetypes.get(index).unwrap_unchecked() }.entity_count();
+
+                    (0..entity_count)
+                        .into_par_iter()
+                        .with_min_len(self.batch_size_for(entity_count as usize))
+                        .map(|row| {
+                            let item: Q::This<'a> = unsafe { Q::get(state, ArchetypeRow(row)) };
+                            item
+                        })
+                })
+                .drive_unindexed(consumer)
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    impl<'a, Q> IntoParallelIterator for Fetcher<'a, Q>
+    where
+        Q: Query,
+        Q::This<'a>: Send,
+    {
+        type Iter = ParIter<'a, Q>;
+
+        type Item = Q::This<'a>;
+
+        fn into_par_iter(self) -> Self::Iter {
+            unsafe { self.state.par_iter_mut(self.world.archetypes()) }
+        }
+    }
+
+    /// A [`ParallelIterator`] over the items of a [`FilteredFetcher`].
+    pub type FilteredParIter<'a, Q> =
+        rayon::iter::Filter<ParIter<'a, Q>, fn(&<Q as Query>::This<'a>) -> bool>;
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    impl<'a, Q, F> IntoParallelIterator for FilteredFetcher<'a, Q, F>
+    where
+        Q: Query,
+        Q::This<'a>: Send,
+        F: for<'x> FilterItem<Q::This<'x>>,
+    {
+        type Iter = FilteredParIter<'a, Q>;
+
+        type Item = Q::This<'a>;
+
+        fn into_par_iter(self) -> Self::Iter {
+            self.fetcher.into_par_iter().filter(F::filter)
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    impl<'a, Q, F> IntoParallelIterator for &'a FilteredFetcher<'_, Q, F>
+    where
+        Q: ReadOnlyQuery,
+        Q::This<'a>: Send,
+        F: for<'x> FilterItem<Q::This<'x>>,
+    {
+        type Iter = FilteredParIter<'a, Q>;
+
+        type Item = Q::This<'a>;
+
+        fn into_par_iter(self) -> Self::Iter {
+            (&self.fetcher).into_par_iter().filter(F::filter)
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    impl<'a, Q, F> IntoParallelIterator for &'a mut FilteredFetcher<'_, Q, F>
+    where
+        Q: Query,
+        Q::This<'a>: Send,
+        F: for<'x> FilterItem<Q::This<'x>>,
+    {
+        type Iter = FilteredParIter<'a, Q>;
+
+        type Item = Q::This<'a>;
+
+        fn into_par_iter(self) -> Self::Iter {
+            (&mut self.fetcher).into_par_iter().filter(F::filter)
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    impl<'a, Q> IntoParallelIterator for &'a Fetcher<'_, Q>
+    where
+        Q: ReadOnlyQuery,
+        Q::This<'a>: Send,
+    {
+        type Iter = ParIter<'a, Q>;
+
+        type Item = Q::This<'a>;
+
+        fn into_par_iter(self) -> Self::Iter {
+            unsafe { self.state.par_iter(self.world.archetypes()) }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    impl<'a, Q: Query> IntoParallelIterator for &'a mut Fetcher<'_, Q>
+    where
+        Q: Query,
+        Q::This<'a>: Send,
+    {
+        type Iter = ParIter<'a, Q>;
+
+        type Item = Q::This<'a>;
+
+        fn into_par_iter(self) -> Self::Iter {
+            unsafe { self.state.par_iter_mut(self.world.archetypes()) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+    use alloc::rc::Rc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(GlobalEvent)]
+    struct E1;
+
+    #[derive(GlobalEvent)]
+    struct E2;
+
+    #[derive(GlobalEvent)]
+    struct E3;
+
+    #[derive(Component, PartialEq, Eq, Debug)]
+    struct C1(u32);
+
+    #[derive(Component, PartialEq, Eq, Debug)]
+    struct C2(u32);
+
+    #[derive(Component, PartialEq, Eq, Debug)]
+    struct C3(u32);
+
+    #[test]
+    fn random_access() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+        world.spawn();
+
+        world.insert(e, C1(123));
+        world.insert(e2, C1(456));
+        world.insert(e3, C2(789));
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            assert_eq!(f.get(e), Ok(&C1(123)));
+        });
+
+        world.add_handler(move |_: Receiver<E2>, f: Fetcher<&C2>| {
+            assert_eq!(f.get(e3), Ok(&C2(789)))
+        });
+
+        world.send(E1);
+
+        world.add_handler(|_: Receiver<E2>, f: Fetcher<&C1>| {
+            assert_eq!(f.get(EntityId::NULL), Err(GetError::NoSuchEntity));
+        });
+
+        world.send(E2);
+
+        world.add_handler(move |_: Receiver<E3>, f: Fetcher<&C2>| {
+            assert_eq!(f.get(e), Err(GetError::QueryDoesNotMatch))
+        });
+
+        world.send(E3);
+    }
+
+    #[test]
+    fn fetch_many_mut() {
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(123));
+        let e2 = world.spawn();
+        world.insert(e2, C1(456));
+        let e3 = world.spawn();
+        world.insert(e3, C1(789));
+
+        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<&mut C1>| {
+            assert_eq!(
+                f.get_many_mut([e1, e2, e3]),
+                Ok([&mut C1(123), &mut C1(456), &mut C1(789)])
+            );
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn fetch_many_mut_drop() {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Query, PartialEq, Debug)]
+        struct CustomQuery;
+
+        impl Drop for CustomQuery {
+            fn drop(&mut self) {
+                COUNT.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+
+        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<CustomQuery>| {
+            assert_eq!(
+                f.get_many_mut([e1, e2, EntityId::NULL, e3]),
+                Err(GetManyMutError::NoSuchEntity)
+            );
+        });
+
+        world.send(E1);
+
+        assert_eq!(COUNT.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn iter() {
+        let mut world = World::new();
+
+        let mut set = BTreeSet::new();
+
+        for i in 0..20_u32 {
+            let e = world.spawn();
+
+            world.insert(e, C1(i.pow(2)));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i.pow(2)));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i.pow(2)));
+            }
+
+            set.insert(i.pow(2));
+        }
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            for c in f {
+                assert!(set.remove(&c.0));
+            }
+
+            assert!(set.is_empty());
+        });
+
+        world.send(E1);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        let mut world = World::new();
+
+        const N: u32 = 20;
+
+        for i in 0..N {
+            let e = world.spawn();
+
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+        }
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            let sum = f.par_iter().map(|c| c.0).sum::<u32>();
+            assert_eq!(sum, N * (N - 1) / 2);
+        });
+
+        world.send(E1);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter_batch_size() {
+        use rayon::prelude::*;
+
+        let mut world = World::new();
+
+        const N: u32 = 1000;
+
+        for i in 0..N {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+        }
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            // All entities are in one archetype, so a batch size covering the
+            // whole archetype must produce a single batch.
+            let batches = f
+                .par_iter()
+                .min_batch_size(N as usize)
+                .fold(|| 0, |n, _| n + 1)
+                .collect::<Vec<u32>>();
+
+            assert_eq!(batches, [N]);
+
+            let batches = f
+                .par_iter()
+                .batches_per_thread(Some(2))
+                .fold(|| 0, |n, _| n + 1)
+                .collect::<Vec<u32>>();
+
+            assert!(batches.len() <= rayon::current_num_threads() * 2);
+            assert_eq!(batches.iter().sum::<u32>(), N);
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn iter_empty() {
+        let mut world = World::new();
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            for c in f {
+                println!("{c:?}");
+            }
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn iter_previously_nonempty() {
+        let mut world = World::new();
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<EntityId>| {
+            for id in f {
+                println!("{id:?}");
+            }
+        });
+
+        let e = world.spawn();
+        world.send(E1);
+        world.despawn(e);
+        world.send(E1);
+    }
+
+    #[test]
+    fn iter_len() {
+        let mut world = World::new();
+
+        let count = 20;
+
+        for i in 1..=count {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+        }
+
+        world.add_handler(
+            move |_: Receiver<E1>, f1: Fetcher<&C1>, f2: Fetcher<&C2>, f3: Fetcher<&C3>| {
+                assert_eq!(f1.iter().len(), count as usize);
+                assert_eq!(f2.iter().len(), count as usize / 2);
+                assert_eq!(f3.iter().len(), count as usize / 3);
+            },
+        );
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn iter_sorted() {
+        fn run(c2_first: bool) -> Vec<u32> {
+            let mut world = World::new();
+
+            if c2_first {
+                world.add_component::<C2>();
+            }
+
+            let res = Rc::new(RefCell::new(vec![]));
+            let res2 = res.clone();
+
+            world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+                let it = f.iter_sorted();
+                assert_eq!(it.len(), 3);
+                res2.borrow_mut().extend(it.map(|c| c.0));
+            });
+
+            let mut spawn = |with_c2: bool, n: u32| {
+                let e = world.spawn();
+                world.insert(e, C1(n));
+                if with_c2 {
+                    world.insert(e, C2(n));
+                }
+            };
+
+            // Populate the archetypes in different orders.
+            if c2_first {
+                spawn(true, 3);
+                spawn(false, 1);
+                spawn(false, 2);
+            } else {
+                spawn(false, 1);
+                spawn(false, 2);
+                spawn(true, 3);
+            }
+
+            world.send(E1);
+
+            let v = res.borrow().clone();
+            v
+        }
+
+        assert_eq!(run(false), [1, 2, 3]);
+        assert_eq!(run(true), [1, 2, 3]);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn pick_and_sample() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        #[derive(Component)]
+        struct C4;
+
+        let mut world = World::new();
+
+        const N: u32 = 20;
+
+        for i in 0..N {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+        }
+
+        world.add_handler(
+            |_: Receiver<E1>, mut f: Fetcher<&mut C1>, empty: Fetcher<&C4>| {
+                let mut rng = StdRng::seed_from_u64(1234);
+
+                assert!(empty.pick(&mut rng).is_none());
+                assert!(empty.sample(5, &mut rng).is_empty());
+
+                for _ in 0..100 {
+                    assert!(f.pick_mut(&mut rng).unwrap().0 < N);
+                }
+
+                let sample = f.sample_mut(5, &mut rng);
+                assert_eq!(sample.len(), 5);
+                let set = sample.iter().map(|c| c.0).collect::<BTreeSet<_>>();
+                assert_eq!(set.len(), 5);
+
+                let all = f.sample_mut(N as usize + 10, &mut rng);
+                let set = all.iter().map(|c| c.0).collect::<BTreeSet<_>>();
+                assert_eq!(set, (0..N).collect());
+            },
+        );
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn join() {
+        #[derive(Component)]
+        struct Target(EntityId);
+
+        let mut world = World::new();
+
+        let victim = world.spawn();
+        world.insert(victim, C1(10));
+
+        let bystander = world.spawn();
+        world.insert(bystander, C1(10));
+
+        for _ in 0..3 {
+            let attacker = world.spawn();
+            world.insert(attacker, Target(victim));
+        }
+
+        let dangling = world.spawn();
+        world.insert(dangling, Target(EntityId::NULL));
+
+        world.add_handler(
+            |_: Receiver<E1>, mut attackers: Fetcher<&Target>, mut victims: Fetcher<&mut C1>| {
+                attackers.for_each_join(&mut victims, |t| Some(t.0), |_, c| c.0 -= 1);
+            },
+        );
+
+        world.send(E1);
+
+        assert_eq!(world.get::<C1>(victim), Some(&C1(7)));
+        assert_eq!(world.get::<C1>(bystander), Some(&C1(10)));
+    }
+
+    #[test]
+    fn self_join() {
+        #[derive(Component)]
+        struct Parent(EntityId);
+
+        let mut world = World::new();
+
+        let root = world.spawn();
+        world.insert(root, C1(0));
+
+        let child = world.spawn();
+        world.insert(child, C1(1));
+        world.insert(child, Parent(root));
+
+        let grandchild = world.spawn();
+        world.insert(grandchild, C1(2));
+        world.insert(grandchild, Parent(child));
+
+        // Refers to itself and should be skipped.
+        let cyclic = world.spawn();
+        world.insert(cyclic, C1(100));
+        world.insert(cyclic, Parent(cyclic));
+
+        world.add_handler(
+            |_: Receiver<E1>, mut f: Fetcher<(&mut C1, Option<&Parent>)>| {
+                f.for_each_self_join(
+                    |(_, p)| p.map(|p| p.0),
+                    |(c, _), (parent_c, _)| {
+                        parent_c.0 += c.0;
+                    },
+                );
+            },
+        );
+
+        world.send(E1);
+
+        assert_eq!(world.get::<C1>(root), Some(&C1(1)));
+        assert_eq!(world.get::<C1>(child), Some(&C1(3)));
+        assert_eq!(world.get::<C1>(grandchild), Some(&C1(2)));
+        assert_eq!(world.get::<C1>(cyclic), Some(&C1(100)));
     }
 
-    impl<Q: ReadOnlyQuery> Clone for ParIter<'_, Q> {
-        fn clone(&self) -> Self {
-            Self {
-                arch_states: self.arch_states,
-                arch_indices: self.arch_indices,
-                archetypes: self.archetypes,
+    #[test]
+    fn cached_fetcher() {
+        let mut world = World::new();
+
+        fn add_sum_handler(world: &mut World) -> (HandlerId, Rc<RefCell<u32>>) {
+            let sum = Rc::new(RefCell::new(0));
+            let sum_clone = sum.clone();
+
+            let id = world.add_handler(
+                (move |_: Receiver<E1>, f: CachedFetcher<&C1>| {
+                    *sum_clone.borrow_mut() = f.iter().map(|c| c.0).sum::<u32>();
+                })
+                .no_type_id(),
+            );
+
+            (id, sum)
+        }
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+
+        let (h1, sum_1) = add_sum_handler(&mut world);
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+        world.insert(e2, C2(2));
+
+        let (_, sum_2) = add_sum_handler(&mut world);
+
+        world.add_handler(|_: Receiver<E2>, mut f: CachedFetcher<&mut C1>| {
+            for c in &mut f {
+                c.0 += 10;
+            }
+        });
+
+        let e3 = world.spawn();
+        world.insert(e3, C1(3));
+        world.insert(e3, C3(3));
+
+        // Both handlers of `&C1` share one state.
+        let weak = &world.fetcher_cache_mut()[&TypeId::of::<&C1>()];
+        assert_eq!(weak.strong_count(), 2);
+
+        world.send(E2);
+        world.send(E1);
+
+        assert_eq!(*sum_1.borrow(), 11 + 12 + 13);
+        assert_eq!(*sum_2.borrow(), 11 + 12 + 13);
+
+        // Empty an archetype and fill it again.
+        world.remove::<C3>(e3);
+        world.despawn(e1);
+        world.remove_handler(h1);
+
+        let (_, sum_3) = add_sum_handler(&mut world);
+
+        let e4 = world.spawn();
+        world.insert(e4, C1(4));
+        world.insert(e4, C3(4));
+
+        world.send(E1);
+
+        assert_eq!(*sum_2.borrow(), 12 + 13 + 4);
+        assert_eq!(*sum_3.borrow(), 12 + 13 + 4);
+    }
+
+    #[test]
+    fn cached_fetcher_prune() {
+        let mut world = World::new();
+
+        let h = world.add_handler(|_: Receiver<E1>, _: CachedFetcher<&C1>| {});
+        world.remove_handler(h);
+
+        world.add_handler(|_: Receiver<E1>, _: CachedFetcher<&C2>| {});
+
+        // The entry of the dropped state was pruned.
+        assert_eq!(world.fetcher_cache_mut().len(), 1);
+        assert!(world.fetcher_cache_mut().contains_key(&TypeId::of::<&C2>()));
+    }
+
+    #[test]
+    fn cached_fetcher_duplicate() {
+        let mut world = World::new();
+
+        let res = world
+            .try_add_handler(|_: Receiver<E1>, _: CachedFetcher<&C1>, _: CachedFetcher<&C1>| {});
+        assert!(res.is_err());
+
+        let res = world
+            .try_add_handler(|_: Receiver<E1>, _: CachedFetcher<&C1>, _: CachedFetcher<&C2>| {});
+        assert!(res.is_ok(), "{res:?}");
+    }
+
+    #[test]
+    fn get_by_location() {
+        let mut world = World::new();
+
+        let mut expected = BTreeSet::new();
+
+        for i in 0..10_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+            expected.insert(i);
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
             }
         }
+
+        let e = world.spawn();
+        world.insert(e, C2(100));
+
+        world.add_handler(
+            move |_: Receiver<E1>, f: Fetcher<&C1>, archetypes: &Archetypes| {
+                assert_eq!(f.matched_archetypes().len(), 2);
+
+                let mut found = BTreeSet::new();
+
+                for &idx in f.matched_archetypes() {
+                    let len = archetypes[idx].entity_count();
+
+                    for row in 0..len {
+                        let c = unsafe { f.get_by_location(idx, ArchetypeRow(row)) }.unwrap();
+                        found.insert(c.0);
+                    }
+
+                    assert!(unsafe { f.get_by_location(idx, ArchetypeRow(len)) }.is_none());
+                }
+
+                assert_eq!(found, expected);
+
+                // Archetype with only `C2` is not matched.
+                let loc = archetypes
+                    .iter()
+                    .find(|a| a.component_indices().len() == 1 && a.entity_count() == 1)
+                    .unwrap()
+                    .index();
+                assert!(unsafe { f.get_by_location(loc, ArchetypeRow(0)) }.is_none());
+            },
+        );
+
+        world.send(E1);
     }
 
-    impl<Q: Query> fmt::Debug for ParIter<'_, Q> {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            f.debug_struct("ParIter")
-                .field("arch_states", &self.arch_states)
-                .field("arch_indices", &self.arch_indices)
-                .field("archetypes", &self.archetypes)
-                .finish()
+    #[test]
+    fn world_iter() {
+        let mut world = World::new();
+
+        assert_eq!(world.iter::<&C1>().len(), 0);
+
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+        }
+
+        // Emptied archetypes are skipped.
+        let e = world.spawn();
+        world.insert(e, C3(0));
+        world.despawn(e);
+
+        assert_eq!(world.iter::<&C1>().len(), 10);
+        assert_eq!(world.iter::<&C1>().map(|c| c.0).sum::<u32>(), 45);
+        assert_eq!(world.iter::<(&C1, &C2)>().count(), 5);
+        assert_eq!(world.iter::<&C3>().next(), None);
+
+        for (c1, c2) in world.iter_mut::<(&mut C1, &C2)>() {
+            c1.0 += c2.0;
         }
+
+        assert_eq!(world.iter::<&C1>().map(|c| c.0).sum::<u32>(), 45 + 20);
     }
 
-    impl<'a, Q> ParallelIterator for ParIter<'a, Q>
-    where
-        Q: Query,
-        Q::This<'a>: Send,
-    {
-        type Item = Q::This<'a>;
+    #[test]
+    #[should_panic]
+    fn world_iter_mut_conflict() {
+        let mut world = World::new();
 
-        fn drive_unindexed<C>(self, consumer: C) -> C::Result
-        where
-            C: UnindexedConsumer<Self::Item>,
-        {
-            unsafe { assume_unchecked(self.arch_states.len() == self.arch_indices.len()) };
+        let _ = world.iter_mut::<(&mut C1, &C1)>();
+    }
 
-            self.arch_states
-                .par_iter()
-                .zip_eq(self.arch_indices)
-                .flat_map(|(state, &index)| {
-                    let entity_count =
-                        unsafe { self.archetypes.get(index).unwrap_unchecked() }.entity_count();
+    #[test]
+    fn via() {
+        #[derive(Component)]
+        struct Link(EntityId);
 
-                    (0..entity_count).into_par_iter().map(|row| {
-                        let item: Q::This<'a> = unsafe { Q::get(state, ArchetypeRow(row)) };
-                        item
-                    })
-                })
-                .drive_unindexed(consumer)
+        impl AsRef<EntityId> for Link {
+            fn as_ref(&self) -> &EntityId {
+                &self.0
+            }
         }
+
+        let mut world = World::new();
+
+        let target = world.spawn();
+        world.insert(target, C1(0));
+
+        let a = world.spawn();
+        world.insert(a, Link(target));
+        let b = world.spawn();
+        world.insert(b, Link(target));
+        world.insert(b, C2(0));
+
+        // Links to an entity which doesn't match the query.
+        let c = world.spawn();
+        world.insert(c, Link(a));
+
+        let unlinked = world.spawn();
+
+        world.add_handler(move |_: Receiver<E1>, mut via: Via<Link, &mut C1>| {
+            assert_eq!(via.target(a), Ok(target));
+            assert_eq!(via.target(unlinked), Err(GetError::QueryDoesNotMatch));
+            assert_eq!(via.get_mut(c), Err(GetError::QueryDoesNotMatch));
+
+            let mut count = 0;
+            via.for_each_mut(|_, c1| {
+                c1.0 += 1;
+                count += 1;
+            });
+            assert_eq!(count, 2);
+
+            via.get_mut(a).unwrap().0 += 10;
+        });
+
+        world.send(E1);
+        assert_eq!(world.get::<C1>(target), Some(&C1(12)));
+
+        world.add_handler(move |_: Receiver<E2>, via: Via<Link, EntityId>| {
+            let mut linked = via.iter().collect::<Vec<_>>();
+            linked.sort_unstable();
+
+            let mut expected = vec![(a, target), (b, target), (c, a)];
+            expected.sort_unstable();
+
+            assert_eq!(linked, expected);
+            assert_eq!(via.follow(c).collect::<Vec<_>>(), [a, target]);
+        });
+
+        world.send(E2);
+
+        let res = world.try_add_handler(|_: Receiver<E3>, _: Via<Link, &mut Link>| {});
+        assert!(res.is_err());
     }
 
-    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
-    impl<'a, Q> IntoParallelIterator for Fetcher<'a, Q>
-    where
-        Q: Query,
-        Q::This<'a>: Send,
-    {
-        type Iter = ParIter<'a, Q>;
+    #[test]
+    fn ptr_fetcher() {
+        let mut world = World::new();
 
-        type Item = Q::This<'a>;
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+        world.insert(e1, C2(10));
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
 
-        fn into_par_iter(self) -> Self::Iter {
-            unsafe { self.state.par_iter_mut(self.world.archetypes()) }
+        let c1 = world.add_component::<C1>();
+        let c2 = world.add_component::<C2>();
+
+        world.add_handler(
+            (move |_: Receiver<E1>, mut f: PtrFetcher| {
+                let add = unsafe { f.get(e1, c2.index()).unwrap().deref::<C2>().0 };
+                assert!(f.get(e2, c2.index()).is_none());
+
+                for (_, ptr) in f.iter_mut(c1.index()) {
+                    unsafe { ptr.deref_mut::<C1>().0 += add };
+                }
+            })
+            .access_components([(c1, Access::ReadWrite), (c2, Access::Read)]),
+        );
+
+        world.send(E1);
+
+        assert_eq!(world.get::<C1>(e1), Some(&C1(11)));
+        assert_eq!(world.get::<C1>(e2), Some(&C1(12)));
+
+        // The declared access conflicts with other params.
+        let res = world.try_add_handler(
+            (|_: Receiver<E2>, _: PtrFetcher, _: Fetcher<&C1>| {})
+                .access_components([(c1, Access::ReadWrite)]),
+        );
+        assert!(res.is_err());
+
+        world.add_handler(
+            (move |_: Receiver<E2>, mut f: PtrFetcher| {
+                f.get_mut(e1, c2.index());
+            })
+            .access_components([(c2, Access::Read)]),
+        );
+
+        let res = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| world.send(E2)));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn restrict() {
+        let mut world = World::new();
+
+        for i in 0..12 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            match i % 3 {
+                0 => {}
+                1 => world.insert(e, C2(i)),
+                _ => world.insert(e, C3(i)),
+            }
         }
+
+        world.add_handler(
+            |_: Receiver<E1>, mut f: Fetcher<&mut C1>, archetypes: &Archetypes| {
+                let two_components: Vec<_> = f
+                    .matched_archetypes()
+                    .iter()
+                    .copied()
+                    .filter(|&idx| archetypes[idx].component_indices().len() == 2)
+                    .collect();
+
+                assert_eq!(two_components.len(), 2);
+
+                // Duplicates and unmatched archetypes are ignored.
+                let mut view = f.restrict([
+                    two_components[0],
+                    two_components[1],
+                    two_components[0],
+                    ArchetypeIdx::EMPTY,
+                ]);
+
+                assert_eq!(view.archetypes().count(), 2);
+                assert_eq!(view.iter_mut().len(), 8);
+
+                for _ in 0..2 {
+                    for c in &mut view {
+                        c.0 += 100;
+                    }
+                }
+            },
+        );
+
+        world.send(E1);
+
+        world.add_handler(|_: Receiver<E2>, f: Fetcher<&C1>| {
+            let mut values = f.iter().map(|c| c.0).collect::<Vec<_>>();
+            values.sort_unstable();
+
+            assert_eq!(values, [0, 3, 6, 9, 201, 202, 204, 205, 207, 208, 210, 211]);
+        });
+
+        world.send(E2);
     }
 
-    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
-    impl<'a, Q> IntoParallelIterator for &'a Fetcher<'_, Q>
-    where
-        Q: ReadOnlyQuery,
-        Q::This<'a>: Send,
-    {
-        type Iter = ParIter<'a, Q>;
+    #[test]
+    fn collect_vec() {
+        let mut world = World::new();
 
-        type Item = Q::This<'a>;
+        for i in 0..20 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
 
-        fn into_par_iter(self) -> Self::Iter {
-            unsafe { self.state.par_iter(self.world.archetypes()) }
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
         }
+
+        world.add_handler(|_: Receiver<E1>, f: Fetcher<&C1>| {
+            let items = f.collect_vec();
+
+            assert_eq!(items.capacity(), 20);
+            assert!(items.iter().copied().eq(f.iter()));
+        });
+
+        world.add_handler(|_: Receiver<E2>, mut f: Fetcher<&mut C1>| {
+            for c in f.collect_vec_mut() {
+                c.0 += 1;
+            }
+        });
+
+        world.send(E1);
+        world.send(E2);
+
+        world.add_handler(|_: Receiver<E3>, f: Fetcher<&C1>| {
+            let mut values = f.collect_vec().into_iter().map(|c| c.0).collect::<Vec<_>>();
+            values.sort_unstable();
+
+            assert_eq!(values, (1..=20).collect::<Vec<_>>());
+        });
+
+        world.send(E3);
     }
 
-    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
-    impl<'a, Q: Query> IntoParallelIterator for &'a mut Fetcher<'_, Q>
-    where
-        Q: Query,
-        Q::This<'a>: Send,
-    {
-        type Iter = ParIter<'a, Q>;
+    #[test]
+    fn prepared_query() {
+        let mut world = World::new();
 
-        type Item = Q::This<'a>;
+        let mut query = PreparedQuery::<(&mut C1, Not<&C2>)>::new(&mut world);
+        assert_eq!(query.iter_mut(&mut world).count(), 0);
 
-        fn into_par_iter(self) -> Self::Iter {
-            unsafe { self.state.par_iter_mut(self.world.archetypes()) }
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+        }
+
+        for (c1, _) in query.iter_mut(&mut world) {
+            c1.0 += 100;
         }
+
+        let matched = query.matched_archetypes();
+        assert_eq!(matched.len(), 1);
+
+        // No new archetypes, so the matched archetypes stay the same.
+        let e = world.spawn();
+        world.insert(e, C1(10));
+        assert_eq!(query.iter_mut(&mut world).count(), 6);
+        assert_eq!(query.matched_archetypes(), matched);
+
+        world.insert(e, C3(10));
+        assert_eq!(query.iter_mut(&mut world).count(), 6);
+        assert_eq!(query.matched_archetypes().len(), 2);
+
+        let mut query = PreparedQuery::<&C1>::new(&mut world);
+        let mut values = query.iter(&world).map(|c| c.0).collect::<Vec<_>>();
+        values.sort_unstable();
+
+        assert_eq!(values, [0, 2, 4, 6, 8, 10, 101, 103, 105, 107, 109]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use alloc::collections::BTreeSet;
-    use core::sync::atomic::{AtomicUsize, Ordering};
+    #[test]
+    fn prepared_query_shared() {
+        let mut world = World::new();
+
+        let mut query = PreparedQuery::<&C1>::new(&mut world);
 
-    use super::*;
-    use crate::prelude::*;
+        world.add_handler(|_: Receiver<E1>, f: CachedFetcher<&C1>| {
+            assert_eq!(f.iter().map(|c| c.0).sum::<u32>(), 190);
+        });
 
-    #[derive(GlobalEvent)]
-    struct E1;
+        // Reallocates the archetype's columns several times.
+        for i in 0..20 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+        }
 
-    #[derive(GlobalEvent)]
-    struct E2;
+        world.send(E1);
 
-    #[derive(GlobalEvent)]
-    struct E3;
+        assert_eq!(query.iter(&world).map(|c| c.0).sum::<u32>(), 190);
 
-    #[derive(Component, PartialEq, Eq, Debug)]
-    struct C1(u32);
+        let e = world.spawn();
+        world.insert(e, C1(10));
+        assert_eq!(query.iter(&world).count(), 21);
 
-    #[derive(Component, PartialEq, Eq, Debug)]
-    struct C2(u32);
+        world.despawn(e);
+        world.send(E1);
+        assert_eq!(query.iter(&world).count(), 20);
+    }
 
-    #[derive(Component, PartialEq, Eq, Debug)]
-    struct C3(u32);
+    #[test]
+    #[should_panic]
+    fn prepared_query_different_world() {
+        let mut world = World::new();
+        let mut query = PreparedQuery::<&C1>::new(&mut world);
+
+        let other = World::new();
+        query.iter(&other).for_each(drop);
+    }
 
     #[test]
-    fn random_access() {
+    #[should_panic]
+    fn prepared_query_removed_component() {
         let mut world = World::new();
+        let mut query = PreparedQuery::<&C1>::new(&mut world);
 
         let e = world.spawn();
-        let e2 = world.spawn();
-        let e3 = world.spawn();
-        world.spawn();
+        world.insert(e, C1(0));
 
-        world.insert(e, C1(123));
-        world.insert(e2, C1(456));
-        world.insert(e3, C2(789));
+        let id = world.add_component::<C1>();
+        world.remove_component(id);
 
-        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
-            assert_eq!(f.get(e), Ok(&C1(123)));
-        });
+        query.iter(&world).for_each(drop);
+    }
 
-        world.add_handler(move |_: Receiver<E2>, f: Fetcher<&C2>| {
-            assert_eq!(f.get(e3), Ok(&C2(789)))
+    #[test]
+    fn skips_empty_archetypes() {
+        let mut world = World::new();
+
+        // Added before the archetypes exist, so the state is maintained through
+        // `refresh_archetype` and `remove_archetype`.
+        world.add_handler(|_: Receiver<E1>, f: Fetcher<&C1>| {
+            assert_eq!(f.state.map.keys().len(), 1);
+            assert_eq!(f.iter().count(), 1);
         });
 
+        let mut keep = None;
+
+        // Leave behind lots of empty archetypes containing `C1`.
+        for i in 0..50_u32 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+
+            world.remove::<C2>(e);
+
+            if i == 0 {
+                keep = Some(e);
+            } else {
+                world.despawn(e);
+            }
+        }
+
+        world.remove::<C3>(keep.unwrap());
+
+        assert!(world.archetypes().len() > 4);
+
         world.send(E1);
 
+        // Added after the archetypes exist, so the state is built from scratch.
         world.add_handler(|_: Receiver<E2>, f: Fetcher<&C1>| {
-            assert_eq!(f.get(EntityId::NULL), Err(GetError::NoSuchEntity));
+            assert_eq!(f.state.map.keys().len(), 1);
         });
 
         world.send(E2);
-
-        world.add_handler(move |_: Receiver<E3>, f: Fetcher<&C2>| {
-            assert_eq!(f.get(e), Err(GetError::QueryDoesNotMatch))
-        });
-
-        world.send(E3);
     }
 
     #[test]
-    fn fetch_many_mut() {
+    fn for_each() {
         let mut world = World::new();
 
-        let e1 = world.spawn();
-        world.insert(e1, C1(123));
-        let e2 = world.spawn();
-        world.insert(e2, C1(456));
-        let e3 = world.spawn();
-        world.insert(e3, C1(789));
+        for i in 0..20 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
 
-        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<&mut C1>| {
-            assert_eq!(
-                f.get_many_mut([e1, e2, e3]),
-                Ok([&mut C1(123), &mut C1(456), &mut C1(789)])
-            );
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+        }
+
+        world.add_handler(|_: Receiver<E1>, f: Fetcher<&C1>| {
+            let mut values = vec![];
+            f.for_each(|c| values.push(c.0));
+
+            assert!(values.into_iter().eq(f.iter().map(|c| c.0)));
+        });
+
+        world.add_handler(|_: Receiver<E2>, mut f: Fetcher<&mut C1>| {
+            f.for_each_mut(|c| c.0 += 1);
         });
 
         world.send(E1);
+        world.send(E2);
+
+        world.add_handler(|_: Receiver<E3>, f: Fetcher<&C1>| {
+            let mut values = vec![];
+            f.for_each(|c| values.push(c.0));
+            values.sort_unstable();
+
+            assert_eq!(values, (1..=20).collect::<Vec<_>>());
+        });
+
+        world.send(E3);
     }
 
     #[test]
-    fn fetch_many_mut_drop() {
-        static COUNT: AtomicUsize = AtomicUsize::new(0);
+    fn group_by() {
+        let mut world = World::new();
 
-        #[derive(Query, PartialEq, Debug)]
-        struct CustomQuery;
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, C1(i % 3));
+            world.insert(e, C2(i));
 
-        impl Drop for CustomQuery {
-            fn drop(&mut self) {
-                COUNT.fetch_add(1, Ordering::SeqCst);
+            if i % 2 == 0 {
+                world.insert(e, C3(i));
             }
         }
 
-        let mut world = World::new();
-
-        let e1 = world.spawn();
-        let e2 = world.spawn();
-        let e3 = world.spawn();
+        world.add_handler(|_: Receiver<E1>, f: Fetcher<(&C1, &C2)>| {
+            let groups = f
+                .group_by(|(c1, _)| c1.0)
+                .map(|(k, group)| {
+                    let mut values = group.map(|(_, c2)| c2.0).collect::<Vec<_>>();
+                    values.sort_unstable();
+                    (k, values)
+                })
+                .collect::<Vec<_>>();
 
-        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<CustomQuery>| {
             assert_eq!(
-                f.get_many_mut([e1, e2, EntityId::NULL, e3]),
-                Err(GetManyMutError::NoSuchEntity)
+                groups,
+                [
+                    (0, vec![0, 3, 6, 9]),
+                    (1, vec![1, 4, 7]),
+                    (2, vec![2, 5, 8])
+                ]
             );
         });
 
-        world.send(E1);
+        world.add_handler(|_: Receiver<E2>, mut f: Fetcher<(&C1, &mut C2)>| {
+            for (key, group) in f.group_by_mut(|(c1, _)| c1.0) {
+                for (_, c2) in group {
+                    c2.0 = key;
+                }
+            }
+        });
 
-        assert_eq!(COUNT.load(Ordering::SeqCst), 2);
-    }
+        world.send(E1);
+        world.send(E2);
 
-    #[test]
-    fn iter() {
-        let mut world = World::new();
+        world.add_handler(|_: Receiver<E3>, f: Fetcher<(&C1, &C2)>| {
+            for (c1, c2) in &f {
+                assert_eq!(c1.0, c2.0);
+            }
 
-        let mut set = BTreeSet::new();
+            assert_eq!(f.group_by(|_| 0).count(), 1);
+        });
 
-        for i in 0..20_u32 {
-            let e = world.spawn();
+        world.send(E3);
+    }
 
-            world.insert(e, C1(i.pow(2)));
+    #[test]
+    fn filtered_fetcher() {
+        struct IsEven;
 
-            if i % 2 == 0 {
-                world.insert(e, C2(i.pow(2)));
+        impl FilterItem<&C1> for IsEven {
+            fn filter(item: &&C1) -> bool {
+                item.0 % 2 == 0
             }
+        }
 
-            if i % 3 == 0 {
-                world.insert(e, C3(i.pow(2)));
+        impl FilterItem<(EntityId, &mut C1)> for IsEven {
+            fn filter((_, item): &(EntityId, &mut C1)) -> bool {
+                item.0 % 2 == 0
             }
+        }
 
-            set.insert(i.pow(2));
+        let mut world = World::new();
+
+        let mut entities = vec![];
+
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+            entities.push(e);
         }
 
-        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
-            for c in f {
-                assert!(set.remove(&c.0));
-            }
+        let e_odd = entities[1];
+        let e_even = entities[2];
 
-            assert!(set.is_empty());
+        world.add_handler(move |_: Receiver<E1>, f: FilteredFetcher<&C1, IsEven>| {
+            assert_eq!(f.iter().map(|c| c.0).sum::<u32>(), 20);
+            assert_eq!(f.iter().count(), 5);
+            assert_eq!(f.get(e_odd), Err(GetError::QueryDoesNotMatch));
+            assert_eq!(f.get(e_even), Ok(&C1(2)));
         });
 
+        world.add_handler(
+            move |_: Receiver<E2>, mut f: FilteredFetcher<(EntityId, &mut C1), IsEven>| {
+                assert!(f.get_mut(e_odd).is_err());
+
+                for (_, c) in &mut f {
+                    c.0 += 1;
+                }
+            },
+        );
+
         world.send(E1);
+        world.send(E2);
+
+        let values = entities
+            .iter()
+            .map(|&e| world.get::<C1>(e).unwrap().0)
+            .collect::<Vec<_>>();
+
+        assert_eq!(values, [1, 1, 3, 3, 5, 5, 7, 7, 9, 9]);
     }
 
     #[cfg(feature = "rayon")]
     #[test]
-    fn par_iter() {
+    fn filtered_fetcher_par_iter() {
         use rayon::prelude::*;
 
-        let mut world = World::new();
+        struct IsEven;
 
-        const N: u32 = 20;
+        impl FilterItem<&C1> for IsEven {
+            fn filter(item: &&C1) -> bool {
+                item.0 % 2 == 0
+            }
+        }
 
-        for i in 0..N {
-            let e = world.spawn();
+        let mut world = World::new();
 
+        for i in 0..100 {
+            let e = world.spawn();
             world.insert(e, C1(i));
-
-            if i % 2 == 0 {
-                world.insert(e, C2(i));
-            }
-
-            if i % 3 == 0 {
-                world.insert(e, C3(i));
-            }
         }
 
-        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
-            let sum = f.par_iter().map(|c| c.0).sum::<u32>();
-            assert_eq!(sum, N * (N - 1) / 2);
+        world.add_handler(|_: Receiver<E1>, f: FilteredFetcher<&C1, IsEven>| {
+            assert_eq!(f.par_iter().map(|c| c.0).sum::<u32>(), 2450);
         });
 
         world.send(E1);
     }
 
     #[test]
-    fn iter_empty() {
+    fn split_mut() {
         let mut world = World::new();
 
-        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
-            for c in f {
-                println!("{c:?}");
-            }
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+        let e3 = world.spawn();
+
+        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<&mut C1>| {
+            let (a, mut rest) = f.split_mut(e1).unwrap();
+
+            assert_eq!(rest.excluded(), e1);
+            assert_eq!(rest.get_mut(e1), Err(GetManyMutError::AliasedMutability));
+            assert_eq!(rest.get_mut(e3), Err(GetManyMutError::QueryDoesNotMatch));
+
+            let b = rest.get_mut(e2).unwrap();
+            mem::swap(a, b);
+
+            assert_eq!(f.split_mut(e3).err(), Some(GetError::QueryDoesNotMatch));
         });
 
         world.send(E1);
+
+        assert_eq!(world.get::<C1>(e1), Some(&C1(2)));
+        assert_eq!(world.get::<C1>(e2), Some(&C1(1)));
     }
 
     #[test]
-    fn iter_previously_nonempty() {
+    fn single_or_default_param() {
+        #[derive(Component, Default, PartialEq, Eq, Debug)]
+        struct Settings {
+            n: u32,
+        }
+
         let mut world = World::new();
 
-        world.add_handler(move |_: Receiver<E1>, f: Fetcher<EntityId>| {
-            for id in f {
-                println!("{id:?}");
-            }
+        world.add_handler(|_: Receiver<E1>, mut s: SingleOrDefault<&mut Settings>| {
+            s.get_mut().unwrap().n += 1;
+        });
+
+        // Doesn't spawn a second entity.
+        world.add_handler(|_: Receiver<E1>, s: SingleOrDefault<&Settings>| {
+            assert!(s.get().unwrap().n > 0);
         });
 
-        let e = world.spawn();
         world.send(E1);
-        world.despawn(e);
         world.send(E1);
+
+        assert_eq!(
+            world.iter::<&Settings>().collect::<Vec<_>>(),
+            [&Settings { n: 2 }]
+        );
+
+        // Existing entities are used.
+        #[derive(Component, Default)]
+        struct Count(u32);
+
+        let e = world.spawn();
+        world.insert(e, Count(123));
+
+        world.add_handler(|_: Receiver<E2>, s: SingleOrDefault<&Count>| {
+            assert_eq!(s.get().unwrap().0, 123);
+        });
+
+        world.send(E2);
+        assert_eq!(world.iter::<&Count>().len(), 1);
     }
 
     #[test]
-    fn iter_len() {
-        let mut world = World::new();
+    fn single_or_default_despawned() {
+        #[derive(Component, Default, PartialEq, Eq, Debug)]
+        struct Settings(u32);
 
-        let count = 20;
+        #[derive(GlobalEvent)]
+        struct Check(Option<u32>);
 
-        for i in 1..=count {
-            let e = world.spawn();
-            world.insert(e, C1(i));
+        let mut world = World::new();
 
-            if i % 2 == 0 {
-                world.insert(e, C2(i));
-            }
+        world.add_handler(|r: Receiver<Check>, s: SingleOrDefault<&Settings>| {
+            assert_eq!(s.get().map(|s| s.0), r.event.0);
+        });
 
-            if i % 3 == 0 {
-                world.insert(e, C3(i));
-            }
-        }
+        let e = world
+            .iter::<(EntityId, With<&Settings>)>()
+            .next()
+            .unwrap()
+            .0;
+        world.insert(e, Settings(5));
+        world.send(Check(Some(5)));
 
-        world.add_handler(
-            move |_: Receiver<E1>, f1: Fetcher<&C1>, f2: Fetcher<&C2>, f3: Fetcher<&C3>| {
-                assert_eq!(f1.iter().len(), count as usize);
-                assert_eq!(f2.iter().len(), count as usize / 2);
-                assert_eq!(f3.iter().len(), count as usize / 3);
-            },
-        );
+        // The handler receives `None`, and the default is respawned at the end
+        // of the flush.
+        world.despawn(e);
+        world.send(Check(None));
+        world.send(Check(Some(0)));
 
-        world.send(E1);
+        assert_eq!(
+            world.iter::<&Settings>().collect::<Vec<_>>(),
+            [&Settings(0)]
+        );
     }
 
     #[test]
@@ -1240,21 +5067,19 @@ mod tests {
     fn try_single_param() {
         let mut world = World::new();
 
-        {
-            let e = world.spawn();
-            world.insert(e, C2(123));
+        let e = world.spawn();
+        world.insert(e, C2(123));
 
-            let e = world.spawn();
-            world.insert(e, C3(123));
-            let e = world.spawn();
-            world.insert(e, C3(456));
-        }
+        let e1 = world.spawn();
+        world.insert(e1, C3(123));
+        let e2 = world.spawn();
+        world.insert(e2, C3(456));
 
         world.add_handler(
-            |_: Receiver<E1>, s1: TrySingle<&C1>, s2: TrySingle<&C2>, s3: TrySingle<&C3>| {
+            move |_: Receiver<E1>, s1: TrySingle<&C1>, s2: TrySingle<&C2>, s3: TrySingle<&C3>| {
                 assert_eq!(s1, Err(SingleError::QueryDoesNotMatch));
                 assert_eq!(s2, Ok(&C2(123)));
-                assert_eq!(s3, Err(SingleError::MoreThanOneMatch));
+                assert_eq!(s3, Err(SingleError::MoreThanOneMatch(vec![e1, e2])));
             },
         );
 
@@ -1,28 +1,47 @@
 //! Event handlers
 
+mod description;
+mod graph;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod raw;
+
 use alloc::borrow::Cow;
 use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
 #[cfg(not(feature = "std"))]
-use alloc::{boxed::Box, vec, vec::Vec};
-use core::any::TypeId;
+use alloc::{boxed::Box, format, vec, vec::Vec};
+use core::alloc::Layout;
+use core::any::{Any, TypeId};
+use core::cell::RefCell;
 use core::cmp::Ordering;
 use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut, Index};
-use core::ptr::NonNull;
-use core::{any, fmt};
+use core::ptr::{self, NonNull};
+use core::{any, fmt, mem};
 
+pub use description::*;
 use evenio_macros::all_tuples;
 pub use evenio_macros::HandlerParam;
+pub use graph::*;
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+pub use metrics::*;
+pub use raw::*;
 
 use crate::access::{Access, ComponentAccess};
 use crate::aliased_box::AliasedBox;
 use crate::archetype::Archetype;
 use crate::bit_set::BitSet;
-use crate::component::ComponentIdx;
+use crate::component::{ComponentId, ComponentIdx};
 use crate::entity::EntityLocation;
-use crate::event::{EventId, EventPtr, GlobalEvent, GlobalEventIdx, TargetedEventIdx};
+use crate::event::{
+    Event, EventId, EventPtr, EventSet, GlobalEvent, GlobalEventIdx, TargetedEventIdx,
+};
 use crate::map::TypeIdMap;
+use crate::mutability::Mutability;
+use crate::query::Query;
 use crate::slot_map::{Key, SlotMap};
 use crate::sparse::SparseIndex;
 use crate::world::{UnsafeWorldCell, World};
@@ -53,6 +72,9 @@ pub struct Handlers {
     /// Handlers ordered by the order they were added to the world. This ensures
     /// that iteration over all handlers is done in insertion order.
     by_insert_order: BTreeMap<u64, HandlerInfoPtr>,
+    /// The seed handlers of the same priority are shuffled with. See
+    /// [`World::shuffle_handler_order`].
+    order_seed: Option<u64>,
 }
 
 impl Handlers {
@@ -64,6 +86,7 @@ impl Handlers {
             by_type_id: Default::default(),
             insert_counter: 0,
             by_insert_order: BTreeMap::new(),
+            order_seed: None,
         }
     }
 
@@ -87,18 +110,21 @@ impl Handlers {
             // Fill in the info's `id` and `order` fields.
             inner.id = id;
             inner.order = self.insert_counter;
+            inner.tiebreak = tiebreak(self.order_seed, self.insert_counter);
 
-            // If the handler receives a global event, add the handler to the
-            // event's handler list.
-            if let EventId::Global(id) = info.received_event() {
-                let idx = id.index().0 as usize;
+            // If the handler receives global events, add the handler to the
+            // handler list of each event.
+            for &event in info.received_events() {
+                if let EventId::Global(id) = event {
+                    let idx = id.index().0 as usize;
 
-                if idx >= self.by_global_event.len() {
-                    self.by_global_event
-                        .resize_with(idx + 1, HandlerList::default);
-                }
+                    if idx >= self.by_global_event.len() {
+                        self.by_global_event
+                            .resize_with(idx + 1, HandlerList::default);
+                    }
 
-                self.by_global_event[idx].insert(ptr, info.priority())
+                    self.by_global_event[idx].insert(ptr)
+                }
             }
 
             info
@@ -122,9 +148,11 @@ impl Handlers {
     pub(crate) fn remove(&mut self, id: HandlerId) -> Option<HandlerInfo> {
         let info = self.infos.remove(id.0)?;
 
-        if let EventId::Global(event_id) = info.received_event() {
-            let list = &mut self.by_global_event[event_id.index().0 as usize];
-            list.remove(info.ptr());
+        for &event in info.received_events() {
+            if let EventId::Global(event_id) = event {
+                let list = &mut self.by_global_event[event_id.index().0 as usize];
+                list.remove(info.ptr());
+            }
         }
 
         if let Some(type_id) = info.type_id() {
@@ -138,6 +166,69 @@ impl Handlers {
         Some(info)
     }
 
+    /// Replaces the info of the handler `id` with `info`, which takes over the
+    /// ID and the place in the insertion order. Returns the replaced info.
+    /// Panics if the ID is invalid, or if another handler with the type id of
+    /// `info` exists.
+    pub(crate) fn replace(&mut self, id: HandlerId, info: HandlerInfo) -> HandlerInfo {
+        let old = self.infos.get_mut(id.0).unwrap();
+
+        for &event in old.received_events() {
+            if let EventId::Global(event_id) = event {
+                let list = &mut self.by_global_event[event_id.index().0 as usize];
+                list.remove(old.ptr());
+            }
+        }
+
+        if let Some(type_id) = HandlerInfo::type_id(old) {
+            self.by_type_id.remove(&type_id);
+        }
+
+        let ptr = info.ptr();
+
+        if let Some(type_id) = info.type_id() {
+            assert!(self.by_type_id.insert(type_id, ptr).is_none());
+        }
+
+        let inner = unsafe { &mut *ptr.0.as_ptr() };
+
+        inner.id = id;
+        inner.order = old.order();
+        inner.tiebreak = old.tiebreak();
+
+        for &event in info.received_events() {
+            if let EventId::Global(event_id) = event {
+                let idx = event_id.index().0 as usize;
+
+                if idx >= self.by_global_event.len() {
+                    self.by_global_event
+                        .resize_with(idx + 1, HandlerList::default);
+                }
+
+                self.by_global_event[idx].insert(ptr)
+            }
+        }
+
+        self.by_insert_order.insert(old.order(), ptr);
+
+        mem::replace(old, info)
+    }
+
+    /// Shuffles handlers of the same priority with `seed`, or restores the
+    /// insertion order if `seed` is `None`, and reorders the global handler
+    /// lists accordingly.
+    pub(crate) fn shuffle(&mut self, seed: Option<u64>) {
+        self.order_seed = seed;
+
+        for info in self.infos.iter_mut() {
+            info.1.set_tiebreak(tiebreak(seed, info.1.order()));
+        }
+
+        for list in &mut self.by_global_event {
+            list.sort();
+        }
+    }
+
     /// Adds a handler list for the given global event.
     pub(crate) fn register_event(&mut self, event_idx: GlobalEventIdx) {
         let idx = event_idx.0 as usize;
@@ -194,6 +285,35 @@ impl Handlers {
             .values_mut()
             .map(|ptr| unsafe { ptr.as_info_mut() })
     }
+
+    /// Returns all handler infos ordered by the total time spent running the
+    /// handlers, from longest to shortest. See [`HandlerInfo::metrics`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Tick>| {});
+    /// world.send(Tick);
+    ///
+    /// for info in world.handlers().by_total_time().iter().take(10) {
+    ///     let metrics = info.metrics();
+    ///     println!("{}: {} runs, {:?}", info.name(), metrics.count(), metrics.total_time());
+    /// }
+    /// ```
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn by_total_time(&self) -> Vec<&HandlerInfo> {
+        let mut infos: Vec<_> = self.iter().collect();
+        infos.sort_by_key(|info| core::cmp::Reverse(info.metrics().total_time()));
+        infos
+    }
 }
 
 impl Index<HandlerId> for Handlers {
@@ -237,6 +357,8 @@ unsafe impl HandlerParam for &'_ Handlers {
 
     type This<'a> = &'a Handlers;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -321,15 +443,37 @@ pub(crate) struct HandlerInfoInner<H: ?Sized = dyn Handler> {
     pub(crate) id: HandlerId,
     pub(crate) type_id: Option<TypeId>,
     pub(crate) order: u64,
-    pub(crate) received_event: EventId,
+    /// Orders the handler among the handlers of the same priority. Equal to
+    /// `order` unless the order was shuffled with
+    /// [`World::shuffle_handler_order`].
+    pub(crate) tiebreak: u64,
+    pub(crate) received_events: Box<[EventId]>,
     pub(crate) received_event_access: Access,
     pub(crate) targeted_event_component_access: ComponentAccess,
     pub(crate) sent_untargeted_events: BitSet<GlobalEventIdx>,
     pub(crate) sent_targeted_events: BitSet<TargetedEventIdx>,
+    pub(crate) event_queue_access: Access,
     pub(crate) component_access: ComponentAccess,
     pub(crate) archetype_filter: ComponentAccess,
     pub(crate) referenced_components: BitSet<ComponentIdx>,
     pub(crate) priority: HandlerPriority,
+    pub(crate) phase: HandlerPhase,
+    pub(crate) labels: Box<[Cow<'static, str>]>,
+    pub(crate) runs_before: Box<[Cow<'static, str>]>,
+    pub(crate) runs_after: Box<[Cow<'static, str>]>,
+    /// Whether the handler runs. See [`World::set_group_enabled`].
+    pub(crate) enabled: bool,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: HandlerMetrics,
+    /// Whether the handler may run in parallel with other handlers. See
+    /// [`World::add_parallel_handler`].
+    pub(crate) parallel: bool,
+    /// Whether the handler receives the lifecycle events of all components.
+    pub(crate) receives_lifecycle_events: bool,
+    /// Whether the handler is registered with archetypes.
+    pub(crate) refreshes_archetypes: bool,
+    #[cfg(feature = "std")]
+    pub(crate) watchdog: Watchdog,
     // SAFETY: There is intentionally no public accessor for this field as it would lead to mutable
     // aliasing.
     pub(crate) handler: H,
@@ -367,9 +511,42 @@ impl HandlerInfo {
         unsafe { (*AliasedBox::as_ptr(&self.0)).order }
     }
 
-    /// Gets the [`EventId`] of the event this handler listens for.
+    /// Gets the key ordering this handler among handlers of the same priority.
+    pub(crate) fn tiebreak(&self) -> u64 {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).tiebreak }
+    }
+
+    /// Gets the [`EventId`] of the event this handler listens for. If the
+    /// handler listens for more than one event, this is the first of
+    /// [`Self::received_events`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler doesn't listen for any events. This only happens
+    /// for handlers with a [`LifecycleReceiver`] while the world has no
+    /// lifecycle events.
+    ///
+    /// [`LifecycleReceiver`]: crate::event::LifecycleReceiver
     pub fn received_event(&self) -> EventId {
-        unsafe { (*AliasedBox::as_ptr(&self.0)).received_event }
+        self.received_events()[0]
+    }
+
+    /// Gets the [`EventId`]s of all events this handler listens for. The
+    /// events are either all global or all targeted.
+    pub fn received_events(&self) -> &[EventId] {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).received_events }
+    }
+
+    pub(crate) fn set_received_events(&mut self, events: Box<[EventId]>) {
+        unsafe { (*AliasedBox::as_mut_ptr(&mut self.0)).received_events = events }
+    }
+
+    /// Returns `true` if this handler receives the lifecycle events of all
+    /// components through a [`LifecycleReceiver`].
+    ///
+    /// [`LifecycleReceiver`]: crate::event::LifecycleReceiver
+    pub fn receives_lifecycle_events(&self) -> bool {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).receives_lifecycle_events }
     }
 
     /// Gets the handler's [`Access`] to the event it listens for.
@@ -380,8 +557,11 @@ impl HandlerInfo {
     /// Gets the expression describing the handler's targeted event query, or
     /// `None` if this handler is not targeted.
     pub fn targeted_event_component_access(&self) -> Option<&ComponentAccess> {
-        self.received_event()
-            .is_targeted()
+        // Only handlers receiving lifecycle events, which are targeted, may
+        // receive no events.
+        self.received_events()
+            .first()
+            .map_or(true, |e| e.is_targeted())
             .then(|| unsafe { &(*AliasedBox::as_ptr(&self.0)).targeted_event_component_access })
     }
 
@@ -411,6 +591,15 @@ impl HandlerInfo {
         unsafe { &(*AliasedBox::as_ptr(&self.0)).sent_targeted_events }
     }
 
+    /// Gets the handler's [`Access`] to the event queue. Handlers which send
+    /// events, such as those with a [`Sender`] parameter, have
+    /// [`Access::ReadWrite`].
+    ///
+    /// [`Sender`]: crate::event::Sender
+    pub fn event_queue_access(&self) -> Access {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).event_queue_access }
+    }
+
     /// Gets the expression describing this handler's access
     pub fn component_access(&self) -> &ComponentAccess {
         unsafe { &(*AliasedBox::as_ptr(&self.0)).component_access }
@@ -441,15 +630,160 @@ impl HandlerInfo {
         unsafe { (*AliasedBox::as_ptr(&self.0)).priority }
     }
 
+    /// Gets the [`HandlerPhase`] of this handler.
+    pub fn phase(&self) -> HandlerPhase {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).phase }
+    }
+
+    /// Gets the key of the segment of handler lists this handler belongs to.
+    /// Segments are run in ascending order.
+    pub(crate) fn segment(&self) -> (HandlerPhase, HandlerPriority) {
+        (self.phase(), self.priority())
+    }
+
+    /// Gets the labels of this handler, as added by [`IntoHandler::label`].
+    pub fn labels(&self) -> &[Cow<'static, str>] {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).labels }
+    }
+
+    /// Gets the labels of the handlers this handler runs before, as added by
+    /// [`IntoHandler::before`].
+    pub fn runs_before(&self) -> &[Cow<'static, str>] {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).runs_before }
+    }
+
+    /// Gets the labels of the handlers this handler runs after, as added by
+    /// [`IntoHandler::after`].
+    pub fn runs_after(&self) -> &[Cow<'static, str>] {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).runs_after }
+    }
+
+    /// Returns `false` if this handler is skipped because one of its groups
+    /// is disabled. See [`World::set_group_enabled`].
+    pub fn is_enabled(&self) -> bool {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).enabled }
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        unsafe { (*AliasedBox::as_mut_ptr(&mut self.0)).enabled = enabled }
+    }
+
+    /// Returns the time budget of this handler, as set by
+    /// [`World::set_handler_budget`], or else the smallest budget of its
+    /// groups set by [`World::set_group_budget`].
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn budget(&self) -> Option<HandlerBudget> {
+        let watchdog = self.watchdog();
+        watchdog.budget.or(watchdog.group_budget)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn watchdog(&self) -> &Watchdog {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).watchdog }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn watchdog_mut(&mut self) -> &mut Watchdog {
+        unsafe { &mut (*AliasedBox::as_mut_ptr(&mut self.0)).watchdog }
+    }
+
+    /// Returns `true` if the run time of this handler needs to be measured.
+    #[cfg(feature = "std")]
+    pub(crate) fn is_timed(&self) -> bool {
+        cfg!(feature = "metrics") || self.budget().is_some()
+    }
+
+    /// Records the time a run of this handler took during the given frame.
+    #[cfg(feature = "std")]
+    pub(crate) fn record_run_time(&mut self, time: core::time::Duration, frame: u64) {
+        #[cfg(feature = "metrics")]
+        self.metrics_mut().record(time);
+
+        if let Some(budget) = self.budget() {
+            if time > budget.time {
+                let watchdog = self.watchdog_mut();
+                watchdog.overrun = Some(time);
+
+                if budget.skip_next_tick {
+                    watchdog.skipped_frame = Some(frame + 1);
+                }
+            }
+        }
+    }
+
+    /// Returns `false` if the handler is skipped when archetypes are created,
+    /// refreshed or removed. See [`HandlerConfig::set_skip_archetype_refresh`].
+    pub fn refreshes_archetypes(&self) -> bool {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).refreshes_archetypes }
+    }
+
+    /// Returns `true` if this handler was added with
+    /// [`World::add_parallel_handler`].
+    pub fn is_parallel(&self) -> bool {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).parallel }
+    }
+
+    /// Returns `true` if this handler and `other` may run at the same time,
+    /// because they are both parallel, don't access the same components
+    /// mutably, and are not ordered relative to each other by labels.
+    #[cfg(feature = "rayon")]
+    fn can_run_in_parallel_with(&self, other: &HandlerInfo) -> bool {
+        self.is_parallel()
+            && other.is_parallel()
+            && !self.must_run_before(other)
+            && !other.must_run_before(self)
+            && self
+                .archetype_filter()
+                .and(other.archetype_filter())
+                .collect_conflicts()
+                .is_empty()
+    }
+
+    /// Gets the [`HandlerMetrics`] of this handler.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn metrics(&self) -> HandlerMetrics {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).metrics }
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_mut(&mut self) -> &mut HandlerMetrics {
+        unsafe { &mut (*AliasedBox::as_mut_ptr(&mut self.0)).metrics }
+    }
+
+    /// Returns `true` if this handler has ordering constraints.
+    fn has_constraints(&self) -> bool {
+        !self.runs_before().is_empty() || !self.runs_after().is_empty()
+    }
+
+    /// Returns `true` if this handler must run before `other` because of
+    /// their labels.
+    fn must_run_before(&self, other: &HandlerInfo) -> bool {
+        self.runs_before()
+            .iter()
+            .any(|l| other.labels().contains(l))
+            || other.runs_after().iter().any(|l| self.labels().contains(l))
+    }
+
     /// Returns a pointer to this handler info.
     pub(crate) fn ptr(&self) -> HandlerInfoPtr {
         HandlerInfoPtr(AliasedBox::as_non_null(&self.0))
     }
 
+    /// Returns a reference to the inner handler.
+    pub(crate) fn handler(&self) -> &dyn Handler {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).handler }
+    }
+
     /// Returns a mutable reference to the inner handler.
     pub(crate) fn handler_mut(&mut self) -> &mut dyn Handler {
         unsafe { &mut (*AliasedBox::as_mut_ptr(&mut self.0)).handler }
     }
+
+    pub(crate) fn set_tiebreak(&mut self, tiebreak: u64) {
+        unsafe { (*AliasedBox::as_mut_ptr(&mut self.0)).tiebreak = tiebreak }
+    }
 }
 
 impl fmt::Debug for HandlerInfo {
@@ -459,7 +793,7 @@ impl fmt::Debug for HandlerInfo {
             .field("id", &self.id())
             .field("type_id", &self.type_id())
             .field("order", &self.order())
-            .field("received_event", &self.received_event())
+            .field("received_events", &self.received_events())
             .field("received_event_access", &self.received_event_access())
             .field("targeted_event_component_access", &self.targeted_event_component_access())
             .field("sent_global_events", &self.sent_global_events())
@@ -468,18 +802,30 @@ impl fmt::Debug for HandlerInfo {
             .field("archetype_filter", &self.archetype_filter())
             .field("referenced_components", &self.referenced_components())
             .field("priority", &self.priority())
+            .field("phase", &self.phase())
+            .field("labels", &self.labels())
+            .field("runs_before", &self.runs_before())
+            .field("runs_after", &self.runs_after())
+            .field("enabled", &self.is_enabled())
+            .field("parallel", &self.is_parallel())
             // Don't access the `handler` field.
             .finish_non_exhaustive()
     }
 }
 
-/// A list of handlers that listen to an event, ordered by priority and
-/// insertion order.
+/// A list of handlers that listen to an event, ordered by phase, priority,
+/// label constraints and insertion order.
+///
+/// Handlers of the same phase and priority form a contiguous segment of the
+/// list.
 #[derive(Debug, Default)]
 pub(crate) struct HandlerList {
-    before: u32,
-    after: u32,
     entries: Vec<HandlerInfoPtr>,
+    /// For each handler which starts a group of handlers that run in
+    /// parallel, the index one past the end of the group. Empty if the list
+    /// has no parallel handlers.
+    #[cfg(feature = "rayon")]
+    parallel_ends: Vec<u32>,
 }
 
 unsafe impl Sync for HandlerList {}
@@ -488,30 +834,69 @@ impl HandlerList {
     /// Constructs an empty handler list.
     pub(crate) const fn new() -> HandlerList {
         Self {
-            before: 0,
-            after: 0,
             entries: vec![],
+            #[cfg(feature = "rayon")]
+            parallel_ends: vec![],
         }
     }
 
-    /// Inserts a handler with the given priority into the list.
-    pub(crate) fn insert(&mut self, ptr: HandlerInfoPtr, priority: HandlerPriority) {
+    /// Inserts a handler into the list, after the handlers of the same phase
+    /// and priority with a lower tiebreak.
+    pub(crate) fn insert(&mut self, ptr: HandlerInfoPtr) {
         assert!(self.entries.len() < u32::MAX as usize);
 
-        match priority {
-            HandlerPriority::High => {
-                self.entries.insert(self.before as usize, ptr);
-                self.before += 1;
-                self.after += 1;
-            }
-            HandlerPriority::Medium => {
-                self.entries.insert(self.after as usize, ptr);
-                self.after += 1;
-            }
-            HandlerPriority::Low => {
-                self.entries.push(ptr);
+        let info = unsafe { ptr.as_info() };
+        let segment = info.segment();
+        let tiebreak = info.tiebreak();
+
+        let start = self
+            .entries
+            .partition_point(|p| unsafe { p.as_info() }.segment() < segment);
+        let end = start
+            + self.entries[start..]
+                .partition_point(|p| unsafe { p.as_info() }.segment() == segment);
+
+        let idx = start
+            + self.entries[start..end]
+                .partition_point(|p| unsafe { p.as_info() }.tiebreak() <= tiebreak);
+
+        self.entries.insert(idx, ptr);
+
+        let segment = &mut self.entries[start..=end];
+
+        if segment
+            .iter()
+            .any(|p| unsafe { p.as_info() }.has_constraints())
+        {
+            order_by_constraints(segment);
+        }
+
+        #[cfg(feature = "rayon")]
+        self.group_parallel();
+    }
+
+    /// Reorders the handlers of each phase and priority by their label
+    /// constraints and tiebreak.
+    pub(crate) fn sort(&mut self) {
+        self.entries.sort_by_key(|p| {
+            let info = unsafe { p.as_info() };
+            (info.segment(), info.tiebreak())
+        });
+
+        for entries in self
+            .entries
+            .chunk_by_mut(|a, b| unsafe { a.as_info().segment() == b.as_info().segment() })
+        {
+            if entries
+                .iter()
+                .any(|p| unsafe { p.as_info() }.has_constraints())
+            {
+                order_by_constraints(entries);
             }
         }
+
+        #[cfg(feature = "rayon")]
+        self.group_parallel();
     }
 
     /// Tries to remove the given handler from the list. Returns `true` on
@@ -520,15 +905,8 @@ impl HandlerList {
         if let Some(idx) = self.entries.iter().position(|&p| p == ptr) {
             self.entries.remove(idx);
 
-            let idx = idx as u32;
-
-            if idx < self.after {
-                self.after -= 1;
-
-                if idx < self.before {
-                    self.before -= 1;
-                }
-            }
+            #[cfg(feature = "rayon")]
+            self.group_parallel();
 
             true
         } else {
@@ -540,6 +918,91 @@ impl HandlerList {
     pub(crate) fn slice(&self) -> &[HandlerInfoPtr] {
         &self.entries
     }
+
+    /// Returns the index one past the end of the group of handlers which run
+    /// in parallel, starting with the handler at `idx`. Handlers which don't
+    /// run in parallel form groups of one.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn parallel_end(&self, idx: usize) -> usize {
+        self.parallel_ends
+            .get(idx)
+            .map_or(idx + 1, |&end| end as usize)
+    }
+
+    /// Splits the list into groups of consecutive handlers which can run in
+    /// parallel. Groups don't span handlers of different phases or
+    /// priorities.
+    #[cfg(feature = "rayon")]
+    fn group_parallel(&mut self) {
+        self.parallel_ends.clear();
+
+        let infos: Vec<&HandlerInfo> = self
+            .entries
+            .iter()
+            .map(|p| unsafe { p.as_info() })
+            .collect();
+
+        if !infos.iter().any(|info| info.is_parallel()) {
+            return;
+        }
+
+        self.parallel_ends.resize(infos.len(), 0);
+
+        let mut start = 0;
+
+        while start < infos.len() {
+            let mut end = start + 1;
+
+            while end < infos.len()
+                && infos[end].segment() == infos[start].segment()
+                && infos[start..end]
+                    .iter()
+                    .all(|info| info.can_run_in_parallel_with(infos[end]))
+            {
+                end += 1;
+            }
+
+            self.parallel_ends[start] = end as u32;
+            start = end;
+        }
+    }
+}
+
+/// Reorders handlers sorted by tiebreak so that the constraints added by
+/// [`IntoHandler::before`] and [`IntoHandler::after`] are satisfied, while
+/// otherwise keeping the tiebreak order.
+///
+/// Constraints which form a cycle cannot all be satisfied. When a cycle is
+/// reached, the remaining handler with the lowest tiebreak runs first.
+fn order_by_constraints(entries: &mut [HandlerInfoPtr]) {
+    let infos: Vec<&HandlerInfo> = entries.iter().map(|p| unsafe { p.as_info() }).collect();
+
+    // Number of unplaced handlers which must run before each handler.
+    let mut preds: Vec<usize> = infos
+        .iter()
+        .map(|b| infos.iter().filter(|a| a.must_run_before(b)).count())
+        .collect();
+
+    let mut placed = vec![false; infos.len()];
+    let mut order = Vec::with_capacity(infos.len());
+
+    while order.len() < infos.len() {
+        let next = (0..infos.len())
+            .find(|&i| !placed[i] && preds[i] == 0)
+            .or_else(|| (0..infos.len()).find(|&i| !placed[i]))
+            .unwrap();
+
+        placed[next] = true;
+        order.push(entries[next]);
+
+        for (i, info) in infos.iter().enumerate() {
+            if !placed[i] && infos[next].must_run_before(info) {
+                preds[i] -= 1;
+            }
+        }
+    }
+
+    entries.copy_from_slice(&order);
 }
 
 /// Lightweight identifier for a handler.
@@ -642,6 +1105,237 @@ pub trait IntoHandler<Marker>: Sized {
     fn low(self) -> LowPriority<Self::Handler> {
         LowPriority(self.into_handler())
     }
+
+    /// Returns a wrapper which sets the [`HandlerPhase`] of this handler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::handler::HandlerPhase;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Damage(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     (|r: Receiver<Damage>| println!("took {} damage", r.event.0)).phase(HandlerPhase::Post),
+    /// );
+    /// world.add_handler(
+    ///     (|mut r: ReceiverMut<Damage>| r.event.0 /= 2).phase(HandlerPhase::Pre),
+    /// );
+    ///
+    /// world.send(Damage(10)); // Prints "took 5 damage".
+    /// ```
+    fn phase(self, phase: HandlerPhase) -> InPhase<Self::Handler> {
+        InPhase {
+            handler: self.into_handler(),
+            phase,
+        }
+    }
+
+    /// Returns a wrapper which adds `label` to the labels of this handler.
+    ///
+    /// Labels name groups of handlers, such as `"input"` or `"physics"`, which
+    /// other handlers can be ordered relative to with [`before`] and
+    /// [`after`] without knowing their [`HandlerId`]s. A handler may have
+    /// any number of labels.
+    ///
+    /// [`before`]: Self::before
+    /// [`after`]: Self::after
+    ///
+    /// Labels also form the groups of handlers which are enabled and disabled
+    /// together with [`World::set_group_enabled`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler((|_: Receiver<Tick>| println!("render")).after("physics"));
+    /// world.add_handler((|_: Receiver<Tick>| println!("physics")).label("physics"));
+    /// world.add_handler((|_: Receiver<Tick>| println!("input")).before("physics"));
+    ///
+    /// world.send(Tick); // Prints "input", "physics" and "render".
+    /// ```
+    fn label<L>(self, label: L) -> Label<Self::Handler>
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        Label {
+            handler: self.into_handler(),
+            label: label.into(),
+        }
+    }
+
+    /// Returns a wrapper which makes this handler run before the handlers
+    /// with the given label, for every event they both receive.
+    ///
+    /// Label constraints only order handlers of the same [`HandlerPhase`] and
+    /// [`HandlerPriority`].
+    /// Constraints which cannot all be satisfied because they form a cycle
+    /// fall back to the order the handlers were added in. See
+    /// [`label`](Self::label).
+    fn before<L>(self, label: L) -> Before<Self::Handler>
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        Before {
+            handler: self.into_handler(),
+            label: label.into(),
+        }
+    }
+
+    /// Returns a wrapper which makes this handler run after the handlers
+    /// with the given label, for every event they both receive.
+    ///
+    /// See [`before`](Self::before).
+    fn after<L>(self, label: L) -> After<Self::Handler>
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        After {
+            handler: self.into_handler(),
+            label: label.into(),
+        }
+    }
+
+    /// Returns a wrapper which only runs this handler if the received event
+    /// of type `E` satisfies the predicate `f`.
+    ///
+    /// The predicate is evaluated before any of the handler's parameters are
+    /// constructed, which makes it cheaper than returning early from the
+    /// handler itself. Initialization fails if the handler does not receive
+    /// `E`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Damage(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     (|r: Receiver<Damage>| println!("critical hit: {}", r.event.0))
+    ///         .filter(|d: &Damage| d.0 >= 100),
+    /// );
+    ///
+    /// world.send(Damage(10)); // Handler doesn't run.
+    /// world.send(Damage(200)); // Prints "critical hit: 200".
+    /// ```
+    fn filter<E, F>(self, f: F) -> Filter<Self::Handler, E, F>
+    where
+        E: Event + 'static,
+        F: FnMut(&E) -> bool + 'static,
+    {
+        Filter {
+            handler: self.into_handler(),
+            predicate: f,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a wrapper which gives this handler the name `name`, as returned
+    /// by [`Handler::name`] and [`HandlerInfo::name`].
+    fn named<N>(self, name: N) -> Named<Self::Handler>
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        Named {
+            handler: self.into_handler(),
+            name: name.into(),
+        }
+    }
+
+    /// Returns a wrapper which only runs this handler while the condition `f`
+    /// returns `true`.
+    ///
+    /// The condition is evaluated every time the handler would run, before
+    /// any of the handler's parameters are constructed. Unlike
+    /// [`filter`](Self::filter), it does not look at the received event,
+    /// which makes it suitable for conditions shared between handlers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let paused = Rc::new(Cell::new(false));
+    /// let p = paused.clone();
+    ///
+    /// world.add_handler((|_: Receiver<Tick>| println!("tick")).run_if(move || !p.get()));
+    ///
+    /// world.send(Tick); // Prints "tick".
+    ///
+    /// paused.set(true);
+    /// world.send(Tick); // Handler doesn't run.
+    /// ```
+    fn run_if<F>(self, f: F) -> RunIf<Self::Handler, F>
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        RunIf {
+            handler: self.into_handler(),
+            condition: f,
+        }
+    }
+
+    /// Returns a wrapper which makes this handler receive the given events,
+    /// chosen at runtime. The events must all be global or all be targeted.
+    ///
+    /// The handler should access the events through an [`UntypedReceiver`],
+    /// and must not have any other parameter which sets the received event.
+    /// Targeted events are received regardless of the target's components.
+    /// Initialization fails if any of the events doesn't exist.
+    ///
+    /// [`UntypedReceiver`]: crate::event::UntypedReceiver
+    fn receive_events<I>(self, events: I) -> ReceiveEvents<Self::Handler>
+    where
+        I: IntoIterator,
+        I::Item: Into<EventId>,
+    {
+        ReceiveEvents {
+            handler: self.into_handler(),
+            events: events.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns a wrapper which lets this handler access the given components,
+    /// chosen at runtime, through a [`PtrFetcher`].
+    ///
+    /// The access of each component is registered with the handler, so
+    /// conflicts are detected like for any other component access.
+    /// Initialization fails if any of the components doesn't exist or if an
+    /// [immutable](Mutability::Immutable) component is accessed with
+    /// [`Access::ReadWrite`].
+    ///
+    /// [`PtrFetcher`]: crate::fetch::PtrFetcher
+    fn access_components<I>(self, components: I) -> AccessComponents<Self::Handler>
+    where
+        I: IntoIterator<Item = (ComponentId, Access)>,
+    {
+        AccessComponents {
+            handler: self.into_handler(),
+            components: components.into_iter().collect(),
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -702,6 +1396,14 @@ impl<H: Handler> Handler for NoTypeId<H> {
     fn remove_archetype(&mut self, arch: &Archetype) {
         self.0.remove_archetype(arch)
     }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.0.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.0.state_mut()
+    }
 }
 
 /// The wrapper handler returned by [`IntoHandler::high`].
@@ -740,6 +1442,14 @@ impl<H: Handler> Handler for HighPriority<H> {
     fn remove_archetype(&mut self, arch: &Archetype) {
         self.0.remove_archetype(arch)
     }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.0.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.0.state_mut()
+    }
 }
 
 /// The wrapper handler returned by [`IntoHandler::low`].
@@ -778,10 +1488,878 @@ impl<H: Handler> Handler for LowPriority<H> {
     fn remove_archetype(&mut self, arch: &Archetype) {
         self.0.remove_archetype(arch)
     }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.0.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.0.state_mut()
+    }
 }
 
-/// A callback function that listens for events.
-///
+/// The wrapper handler returned by [`IntoHandler::phase`].
+#[derive(Clone, Copy, Debug)]
+pub struct InPhase<H> {
+    handler: H,
+    phase: HandlerPhase,
+}
+
+impl<H: Handler> Handler for InPhase<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        let res = self.handler.init(world, config);
+        config.set_phase(self.phase);
+        res
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::label`].
+#[derive(Clone, Debug)]
+pub struct Label<H> {
+    handler: H,
+    label: Cow<'static, str>,
+}
+
+impl<H: Handler> Handler for Label<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        let res = self.handler.init(world, config);
+        config.add_label(self.label.clone());
+        res
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::before`].
+#[derive(Clone, Debug)]
+pub struct Before<H> {
+    handler: H,
+    label: Cow<'static, str>,
+}
+
+impl<H: Handler> Handler for Before<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        let res = self.handler.init(world, config);
+        config.add_run_before(self.label.clone());
+        res
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::after`].
+#[derive(Clone, Debug)]
+pub struct After<H> {
+    handler: H,
+    label: Cow<'static, str>,
+}
+
+impl<H: Handler> Handler for After<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        let res = self.handler.init(world, config);
+        config.add_run_after(self.label.clone());
+        res
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler used by [`World::add_handler_once`].
+#[derive(Debug)]
+pub(crate) struct Once<H> {
+    handler: H,
+    ran: bool,
+}
+
+impl<H> Once<H> {
+    pub(crate) fn new(handler: H) -> Self {
+        Self {
+            handler,
+            ran: false,
+        }
+    }
+}
+
+impl<H: Handler> Handler for Once<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        // Every one-shot handler is distinct.
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        self.handler.init(world, config)
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        // The handler is only removed once the flush has finished, so it may
+        // receive more events before then.
+        if self.ran {
+            return;
+        }
+
+        self.ran = true;
+        self.handler.run(info, event_ptr, target_location, world);
+        world.remove_handler_after_flush(info.id());
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler used by [`World::add_parallel_handler`].
+#[cfg(feature = "rayon")]
+#[derive(Debug)]
+pub(crate) struct Parallel<H>(pub(crate) H);
+
+#[cfg(feature = "rayon")]
+impl<H: Handler> Handler for Parallel<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        // Don't return the ID of a handler added with `World::add_handler`.
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.0.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        self.0.init(world, config)?;
+        config.parallel = true;
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.0.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.0.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.0.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.0.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.0.state_mut()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::filter`].
+pub struct Filter<H, E, F> {
+    handler: H,
+    predicate: F,
+    _marker: PhantomData<fn(&E)>,
+}
+
+impl<H, E, F> Handler for Filter<H, E, F>
+where
+    H: Handler,
+    E: Event + 'static,
+    F: FnMut(&E) -> bool + 'static,
+{
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        self.handler.init(world, config)?;
+
+        let idx = <E as EventSet>::new_indices(world);
+
+        let receives_event = match config.received_event {
+            ReceivedEventId::Ok(EventId::Global(id)) => {
+                TypeId::of::<E::EventIdx>() == TypeId::of::<GlobalEventIdx>() && id.index().0 == idx
+            }
+            ReceivedEventId::Ok(EventId::Targeted(id)) => {
+                TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>()
+                    && id.index().0 == idx
+            }
+            ReceivedEventId::None | ReceivedEventId::Many(_) | ReceivedEventId::Invalid => false,
+        };
+
+        if !receives_event {
+            return Err(InitError::new(format!(
+                "handler `{}` filters events of type `{}`, but does not receive them",
+                self.handler.name(),
+                any::type_name::<E>()
+            )));
+        }
+
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        // SAFETY: The handler receives events of type `E`, as checked in `init`.
+        let event = unsafe { event_ptr.as_ptr().cast::<E>().as_ref() };
+
+        if (self.predicate)(event) {
+            self.handler.run(info, event_ptr, target_location, world)
+        }
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+impl<H: fmt::Debug, E, F> fmt::Debug for Filter<H, E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("handler", &self.handler)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::named`].
+#[derive(Clone, Debug)]
+pub struct Named<H> {
+    handler: H,
+    name: Cow<'static, str>,
+}
+
+impl<H: Handler> Handler for Named<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        self.handler.init(world, config)
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::run_if`].
+pub struct RunIf<H, F> {
+    handler: H,
+    condition: F,
+}
+
+impl<H, F> Handler for RunIf<H, F>
+where
+    H: Handler,
+    F: FnMut() -> bool + 'static,
+{
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        self.handler.init(world, config)
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        if (self.condition)() {
+            self.handler.run(info, event_ptr, target_location, world)
+        }
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+impl<H: fmt::Debug, F> fmt::Debug for RunIf<H, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunIf")
+            .field("handler", &self.handler)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::receive_events`].
+#[derive(Clone, Debug)]
+pub struct ReceiveEvents<H> {
+    handler: H,
+    events: Box<[EventId]>,
+}
+
+impl<H: Handler> Handler for ReceiveEvents<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        // The same handler may receive different events.
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        for &event in self.events.iter() {
+            let exists = match event {
+                EventId::Global(id) => world.global_events().contains(id),
+                EventId::Targeted(id) => world.targeted_events().contains(id),
+            };
+
+            if !exists {
+                return Err(InitError::new(format!(
+                    "event {event:?} does not exist in the world"
+                )));
+            }
+        }
+
+        config.set_received_events(self.events.iter().copied());
+
+        if self.events.iter().any(|e| e.is_targeted()) {
+            // Receive the events regardless of the target's components.
+            let (ca, ()) = <() as Query>::init(world, config)?;
+            config.set_targeted_event_component_access(ca.clone());
+            config.push_component_access(ca);
+        }
+
+        self.handler.init(world, config)
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::access_components`].
+#[derive(Clone, Debug)]
+pub struct AccessComponents<H> {
+    handler: H,
+    components: Box<[(ComponentId, Access)]>,
+}
+
+impl<H: Handler> Handler for AccessComponents<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        // The same handler may access different components.
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        for &(id, access) in self.components.iter() {
+            let Some(info) = world.components().get(id) else {
+                return Err(InitError::new(format!(
+                    "component {id:?} does not exist in the world"
+                )));
+            };
+
+            if access == Access::ReadWrite && info.mutability() == Mutability::Immutable {
+                return Err(InitError::new(format!(
+                    "component `{}` is immutable and can't be accessed mutably",
+                    info.name()
+                )));
+            }
+
+            config.accessed_components.push((id.index(), access));
+        }
+
+        self.handler.init(world, config)
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.handler.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.handler.state_mut()
+    }
+}
+
+/// Configures a handler before adding it to a [`World`]. Returned by
+/// [`World::build_handler`].
+///
+/// Every method wraps the handler like the [`IntoHandler`] method of the same
+/// name. The handler only becomes active once [`add`](Self::add) is called,
+/// so it is fully configured before the first event reaches it.
+#[must_use = "the handler is not added until `add` is called"]
+#[derive(Debug)]
+pub struct HandlerBuilder<'a, H> {
+    world: &'a mut World,
+    handler: H,
+}
+
+impl<'a, H: Handler> HandlerBuilder<'a, H> {
+    pub(crate) fn new(world: &'a mut World, handler: H) -> Self {
+        Self { world, handler }
+    }
+
+    fn map<G>(self, f: impl FnOnce(H) -> G) -> HandlerBuilder<'a, G> {
+        HandlerBuilder {
+            world: self.world,
+            handler: f(self.handler),
+        }
+    }
+
+    /// Sets the name of the handler. See [`IntoHandler::named`].
+    pub fn name<N>(self, name: N) -> HandlerBuilder<'a, Named<H>>
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        self.map(|h| h.named(name))
+    }
+
+    /// Gives the handler [`HandlerPriority::High`]. See [`IntoHandler::high`].
+    pub fn high(self) -> HandlerBuilder<'a, HighPriority<H>> {
+        self.map(HighPriority)
+    }
+
+    /// Gives the handler [`HandlerPriority::Low`]. See [`IntoHandler::low`].
+    pub fn low(self) -> HandlerBuilder<'a, LowPriority<H>> {
+        self.map(LowPriority)
+    }
+
+    /// Sets the phase of the handler. See [`IntoHandler::phase`].
+    pub fn phase(self, phase: HandlerPhase) -> HandlerBuilder<'a, InPhase<H>> {
+        self.map(|h| h.phase(phase))
+    }
+
+    /// Adds the handler to the group `label`. See [`IntoHandler::label`].
+    pub fn label<L>(self, label: L) -> HandlerBuilder<'a, Label<H>>
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        self.map(|h| h.label(label))
+    }
+
+    /// Runs the handler before the handlers labeled `label`. See
+    /// [`IntoHandler::before`].
+    pub fn before<L>(self, label: L) -> HandlerBuilder<'a, Before<H>>
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        self.map(|h| h.before(label))
+    }
+
+    /// Runs the handler after the handlers labeled `label`. See
+    /// [`IntoHandler::after`].
+    pub fn after<L>(self, label: L) -> HandlerBuilder<'a, After<H>>
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        self.map(|h| h.after(label))
+    }
+
+    /// Only runs the handler for events satisfying `f`. See
+    /// [`IntoHandler::filter`].
+    pub fn filter<E, F>(self, f: F) -> HandlerBuilder<'a, Filter<H, E, F>>
+    where
+        E: Event + 'static,
+        F: FnMut(&E) -> bool + 'static,
+    {
+        self.map(|h| h.filter(f))
+    }
+
+    /// Only runs the handler while `f` returns `true`. See
+    /// [`IntoHandler::run_if`].
+    pub fn run_if<F>(self, f: F) -> HandlerBuilder<'a, RunIf<H, F>>
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        self.map(|h| h.run_if(f))
+    }
+
+    /// Adds the configured handler to the world. See [`World::add_handler`].
+    #[track_caller]
+    pub fn add(self) -> HandlerId {
+        self.world.add_handler(self.handler)
+    }
+}
+
+/// A type which handles events of type `E`. Implemented once for every
+/// event type, usually with a blanket implementation, so that a single value
+/// can be added for a whole set of events with
+/// [`World::add_handler_for_events`].
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Debug;
+///
+/// use evenio::handler::HandleEvent;
+/// use evenio::prelude::*;
+///
+/// struct Logger;
+///
+/// impl<E: GlobalEvent + Debug> HandleEvent<E> for Logger {
+///     fn handle(&mut self, event: &E) {
+///         println!("{event:?}");
+///     }
+/// }
+/// ```
+pub trait HandleEvent<E>: 'static {
+    /// Called when an event of type `E` is received.
+    fn handle(&mut self, event: &E);
+}
+
+/// Implemented for types which implement [`HandleEvent`] for every event in
+/// the tuple `S`. See [`World::add_handler_for_events`].
+///
+/// Events with lifetime parameters are not supported.
+pub trait HandleEvents<S>: Sized + 'static {
+    /// Adds one handler to the world for every event in `S`, each calling
+    /// into the shared `this`, and pushes their IDs onto `ids`.
+    fn add_handlers(this: Rc<RefCell<Self>>, world: &mut World, ids: &mut Vec<HandlerId>);
+}
+
+macro_rules! impl_handle_events_tuple {
+    ($(($E:ident, $e:ident)),*) => {
+        #[allow(unused_variables, unused_mut, clippy::unused_unit)]
+        impl<F, $($E: for<'a> Event<This<'a> = $E> + 'static),*> HandleEvents<($($E,)*)> for F
+        where
+            F: 'static $(+ HandleEvent<$E>)*,
+        {
+            fn add_handlers(this: Rc<RefCell<Self>>, world: &mut World, ids: &mut Vec<HandlerId>) {
+                $(
+                    ids.push(world.add_handler(SharedHandler::<$E, F>::new(this.clone())));
+                )*
+            }
+        }
+    }
+}
+
+all_tuples!(impl_handle_events_tuple, 0, 15, E, e);
+
+/// The handler added for each event by [`World::add_handler_for_events`].
+struct SharedHandler<E, F> {
+    func: Rc<RefCell<F>>,
+    _marker: PhantomData<fn(E)>,
+}
+
+impl<E, F> SharedHandler<E, F> {
+    fn new(func: Rc<RefCell<F>>) -> Self {
+        Self {
+            func,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E, F> Handler for SharedHandler<E, F>
+where
+    E: for<'a> Event<This<'a> = E> + 'static,
+    F: HandleEvent<E>,
+{
+    fn type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(any::type_name::<F>())
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        let idx = <E as EventSet>::new_indices(world);
+
+        if TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>() {
+            let id = world.targeted_events().get_by_index(TargetedEventIdx(idx));
+            config.set_received_event(id.unwrap().id());
+
+            // Receive the event regardless of the target's components.
+            let (ca, ()) = <() as Query>::init(world, config)?;
+            config.set_targeted_event_component_access(ca.clone());
+            config.push_component_access(ca);
+        } else {
+            let id = world.global_events().get_by_index(GlobalEventIdx(idx));
+            config.set_received_event(id.unwrap().id());
+        }
+
+        config.set_received_event_access(Access::Read);
+
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        _handler_info: &HandlerInfo,
+        event_ptr: EventPtr,
+        _target_location: EntityLocation,
+        _world: UnsafeWorldCell,
+    ) {
+        // SAFETY: The handler receives `E` and has read access to it.
+        let event = event_ptr.as_ptr().cast::<E>().as_ref();
+        self.func.borrow_mut().handle(event);
+    }
+
+    fn refresh_archetype(&mut self, _arch: &Archetype) {}
+
+    fn remove_archetype(&mut self, _arch: &Archetype) {}
+}
+
+/// A callback function that listens for events.
+///
 /// Handlers are added to a world using the [`World::add_handler`] method.
 ///
 /// For more information about handlers, see the [tutorial](crate::tutorial).
@@ -849,18 +2427,57 @@ pub trait Handler: 'static {
     /// available. Attempting to read the component data from a removed
     /// archetype is illegal.
     fn remove_archetype(&mut self, arch: &Archetype);
+
+    /// Returns the explicit state of this handler, or `None` if it has none.
+    /// See [`World::add_handler_with`].
+    ///
+    /// Wrapper handlers should forward this to the wrapped handler.
+    fn state(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    /// Mutable version of [`Handler::state`].
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+}
+
+/// An error returned when handler initialization fails. Contains an error
+/// message and, if the handler accesses components in conflicting ways, the
+/// [`AccessConflict`]s.
+///
+/// The error message is not stable.
+#[derive(Clone, Debug)]
+pub struct InitError {
+    pub(crate) message: Box<str>,
+    pub(crate) conflicts: Vec<AccessConflict>,
 }
 
-/// An error returned when handler initialization fails. Contains an error
-/// message.
-///
-/// The error message is not stable.
-#[derive(Clone, Debug)]
-pub struct InitError(pub Box<str>);
+impl InitError {
+    /// Creates a new error with the given message.
+    pub fn new<M: Into<Box<str>>>(message: M) -> Self {
+        Self {
+            message: message.into(),
+            conflicts: vec![],
+        }
+    }
+
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the components which the handler accesses in conflicting
+    /// ways, such as mutably from two of its parameters. Empty if the error
+    /// has another cause.
+    pub fn conflicts(&self) -> &[AccessConflict] {
+        &self.conflicts
+    }
+}
 
 impl fmt::Display for InitError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", &self.0)
+        write!(f, "{}", &self.message)
     }
 }
 
@@ -868,10 +2485,30 @@ impl fmt::Display for InitError {
 #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 impl std::error::Error for InitError {}
 
+/// A component which a handler accesses in conflicting ways (aliased
+/// mutability). See [`InitError::conflicts`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct AccessConflict {
+    /// The ID of the component.
+    pub component: ComponentId,
+    /// The name of the component.
+    pub component_name: Cow<'static, str>,
+    /// The type names of the two handler params whose access to the
+    /// component conflicts. The type names include the queries of the
+    /// params, such as `Fetcher<(&mut Health, &Armor)>`.
+    ///
+    /// Both are the same if a param conflicts with itself. A name is `None`
+    /// if the access was not registered by a param, which happens for
+    /// handlers implementing [`Handler`] directly.
+    pub params: [Option<&'static str>; 2],
+}
+
 /// The configuration of a handler. Accessible during handler initialization.
 #[derive(Clone, Default, Debug)]
 pub struct HandlerConfig {
     pub(crate) priority: HandlerPriority,
+    pub(crate) phase: HandlerPhase,
     pub(crate) received_event: ReceivedEventId,
     pub(crate) received_event_access: MaybeInvalidAccess,
     pub(crate) targeted_event_component_access: ComponentAccess,
@@ -879,7 +2516,35 @@ pub struct HandlerConfig {
     pub(crate) sent_targeted_events: BitSet<TargetedEventIdx>,
     pub(crate) event_queue_access: MaybeInvalidAccess,
     pub(crate) component_accesses: Vec<ComponentAccess>,
+    /// The type names of the params which pushed the entries of
+    /// `component_accesses`.
+    pub(crate) component_access_params: Vec<Option<&'static str>>,
     pub(crate) referenced_components: BitSet<ComponentIdx>,
+    pub(crate) labels: Vec<Cow<'static, str>>,
+    pub(crate) runs_before: Vec<Cow<'static, str>>,
+    pub(crate) runs_after: Vec<Cow<'static, str>>,
+    /// Type IDs of the queries used by [`CachedFetcher`]s in this handler.
+    ///
+    /// [`CachedFetcher`]: crate::fetch::CachedFetcher
+    pub(crate) cached_fetchers: Vec<TypeId>,
+    /// Whether the handler was added with [`World::add_parallel_handler`].
+    pub(crate) parallel: bool,
+    /// Whether the handler accesses the world in ways which are not thread
+    /// safe. See [`HandlerConfig::set_thread_local`].
+    pub(crate) thread_local: bool,
+    /// Whether the handler receives the lifecycle events of all components,
+    /// including ones added later. See [`LifecycleReceiver`].
+    ///
+    /// [`LifecycleReceiver`]: crate::event::LifecycleReceiver
+    pub(crate) receives_lifecycle_events: bool,
+    /// Whether the handler doesn't need to be told about archetypes. See
+    /// [`HandlerConfig::set_skip_archetype_refresh`].
+    pub(crate) skip_archetype_refresh: bool,
+    /// The components chosen at runtime which the handler accesses through a
+    /// [`PtrFetcher`]. See [`IntoHandler::access_components`].
+    ///
+    /// [`PtrFetcher`]: crate::fetch::PtrFetcher
+    pub(crate) accessed_components: Vec<(ComponentIdx, Access)>,
 }
 
 impl HandlerConfig {
@@ -895,20 +2560,99 @@ impl HandlerConfig {
         self.priority = priority;
     }
 
+    /// Overwrites the [`HandlerPhase`] of this handler.
+    ///
+    /// Handlers default to [`HandlerPhase::Main`].
+    pub fn set_phase(&mut self, phase: HandlerPhase) {
+        self.phase = phase;
+    }
+
+    /// Marks the handler as only able to run on the thread which owns the
+    /// world, because it accesses the world in ways not described by its
+    /// other accesses. Such handlers can't be added with
+    /// [`World::add_parallel_handler`].
+    ///
+    /// [`HandlerParam`]s which modify the world through the
+    /// [`UnsafeWorldCell`], other than the received event, the event queue and
+    /// the accessed components, should call this.
+    pub fn set_thread_local(&mut self) {
+        self.thread_local = true;
+    }
+
+    /// Declares that the handler doesn't fetch components, so
+    /// [`Handler::refresh_archetype`] and [`Handler::remove_archetype`] don't
+    /// need to be called. The handler is then skipped when archetypes are
+    /// created or change. Has no effect if the handler receives targeted
+    /// events.
+    ///
+    /// Function handlers call this when [`HandlerParam::REFRESHES_ARCHETYPES`]
+    /// is `false` for all of their params.
+    pub fn set_skip_archetype_refresh(&mut self) {
+        self.skip_archetype_refresh = true;
+    }
+
+    /// Adds a label to this handler. See [`IntoHandler::label`].
+    pub fn add_label<L: Into<Cow<'static, str>>>(&mut self, label: L) {
+        let label = label.into();
+        if !self.labels.contains(&label) {
+            self.labels.push(label);
+        }
+    }
+
+    /// Makes this handler run before the handlers with the given label. See
+    /// [`IntoHandler::before`].
+    pub fn add_run_before<L: Into<Cow<'static, str>>>(&mut self, label: L) {
+        let label = label.into();
+        if !self.runs_before.contains(&label) {
+            self.runs_before.push(label);
+        }
+    }
+
+    /// Makes this handler run after the handlers with the given label. See
+    /// [`IntoHandler::after`].
+    pub fn add_run_after<L: Into<Cow<'static, str>>>(&mut self, label: L) {
+        let label = label.into();
+        if !self.runs_after.contains(&label) {
+            self.runs_after.push(label);
+        }
+    }
+
     /// Sets the event sent by this handler. Causes an initialization error
     /// if a different event was previously set.
     pub fn set_received_event<E: Into<EventId>>(&mut self, event: E) {
         let event = event.into();
         self.received_event = match self.received_event {
             ReceivedEventId::None => ReceivedEventId::Ok(event),
-            ReceivedEventId::Ok(old_event) => {
-                if old_event == event {
-                    ReceivedEventId::Ok(event)
-                } else {
-                    ReceivedEventId::Invalid
+            ReceivedEventId::Ok(old_event) if old_event == event => ReceivedEventId::Ok(event),
+            ReceivedEventId::Ok(_) | ReceivedEventId::Many(_) | ReceivedEventId::Invalid => {
+                ReceivedEventId::Invalid
+            }
+        };
+    }
+
+    /// Sets the events received by this handler, for handlers which receive
+    /// more than one event. Duplicate events are ignored. Causes an
+    /// initialization error if the received event was previously set, or if
+    /// the events are not either all global or all targeted.
+    pub fn set_received_events<I>(&mut self, events: I)
+    where
+        I: IntoIterator,
+        I::Item: Into<EventId>,
+    {
+        self.received_event = match self.received_event {
+            ReceivedEventId::None => {
+                let mut list: Vec<EventId> = vec![];
+
+                for event in events {
+                    let event = event.into();
+                    if !list.contains(&event) {
+                        list.push(event);
+                    }
                 }
+
+                ReceivedEventId::Many(list)
             }
-            ReceivedEventId::Invalid => ReceivedEventId::Invalid,
+            _ => ReceivedEventId::Invalid,
         };
     }
 
@@ -968,6 +2712,15 @@ impl HandlerConfig {
     /// pushed per handler param that accesses components.
     pub fn push_component_access(&mut self, component_access: ComponentAccess) {
         self.component_accesses.push(component_access);
+        self.component_access_params.push(None);
+    }
+
+    /// Attributes the component accesses pushed since `start` which aren't
+    /// attributed yet to the param named `param`.
+    pub(crate) fn name_component_accesses(&mut self, start: usize, param: &'static str) {
+        for name in &mut self.component_access_params[start..] {
+            name.get_or_insert(param);
+        }
     }
 
     /// Inserts a component into the set of components referenced by this
@@ -978,27 +2731,55 @@ impl HandlerConfig {
     }
 }
 
+/// The phase of an event's broadcast in which a handler runs.
+///
+/// The handlers of an event run phase by phase, in the order the phases are
+/// declared in. Within a phase, handlers are ordered by their
+/// [`HandlerPriority`]. This lets crates which don't know about each other
+/// agree on where a handler runs, for instance by modifying an event in
+/// [`Pre`](Self::Pre), applying it in [`Main`](Self::Main) and reacting to it
+/// in [`Post`](Self::Post).
+///
+/// If a handler takes ownership of the event, the handlers of later phases
+/// don't run either.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum HandlerPhase {
+    /// Runs first, for handlers which inspect or modify the event before it is
+    /// acted upon.
+    Pre,
+    /// The default phase, for handlers which act upon the event.
+    #[default]
+    Main,
+    /// Runs after the event has been acted upon, for handlers which react to
+    /// it.
+    Post,
+    /// Runs last.
+    Cleanup,
+}
+
 /// The priority of a handler relative to other handlers that handle the same
-/// event.
+/// event in the same [`HandlerPhase`].
 ///
-/// If multiple handlers have the same priority, then the order they were added
-/// to the [`World`] is used as a fallback.
+/// If multiple handlers have the same priority, then they are ordered by the
+/// constraints added with [`IntoHandler::before`] and [`IntoHandler::after`],
+/// and then by the order they were added to the [`World`].
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
 pub enum HandlerPriority {
-    /// The handler runs before other handlers.
+    /// The handler runs before other handlers of the same phase.
     High,
     /// The default handler priority.
     #[default]
     Medium,
-    /// The handler runs after other handlers.
+    /// The handler runs after other handlers of the same phase.
     Low,
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub(crate) enum ReceivedEventId {
     #[default]
     None,
     Ok(EventId),
+    Many(Vec<EventId>),
     Invalid,
 }
 
@@ -1058,6 +2839,17 @@ pub unsafe trait HandlerParam {
     /// of `Self` but with the lifetime of `'a`.
     type This<'a>;
 
+    /// Whether [`HandlerParam::refresh_archetype`] and
+    /// [`HandlerParam::remove_archetype`] need to be called for this param.
+    /// Defaults to `true`.
+    ///
+    /// Params which don't fetch components should set this to `false`.
+    /// Handlers whose params all set it to `false` are skipped when archetypes
+    /// are created, refreshed or removed, which saves time in worlds with many
+    /// archetypes. Handlers which receive targeted events are never skipped.
+    /// See [`HandlerConfig::set_skip_archetype_refresh`].
+    const REFRESHES_ARCHETYPES: bool = true;
+
     /// Initializes the handler using the input [`World`] and [`HandlerConfig`].
     ///
     /// If initialization fails, [`InitError`] is returned and the handler is
@@ -1096,6 +2888,8 @@ unsafe impl<T> HandlerParam for PhantomData<T> {
 
     type This<'a> = Self;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -1123,11 +2917,18 @@ macro_rules! impl_handler_param_tuple {
 
             type This<'a> = ($($P::This<'a>,)*);
 
+            const REFRESHES_ARCHETYPES: bool = false $(|| $P::REFRESHES_ARCHETYPES)*;
+
             #[inline]
             fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
                 Ok((
                     $(
-                        $P::init(world, config)?,
+                        {
+                            let start = config.component_accesses.len();
+                            let state = $P::init(world, config)?;
+                            config.name_component_accesses(start, any::type_name::<$P>());
+                            state
+                        },
                     )*
                 ))
             }
@@ -1179,6 +2980,8 @@ all_tuples!(impl_handler_param_tuple, 0, 15, P, s);
 pub struct FunctionHandler<Marker, F: HandlerParamFunction<Marker>> {
     func: F,
     state: Option<<F::Param as HandlerParam>::State>,
+    /// The index of the [`HandlerFailed`] event, if the function is fallible.
+    failed_event: Option<GlobalEventIdx>,
 }
 
 impl<Marker, F> FunctionHandler<Marker, F>
@@ -1187,7 +2990,11 @@ where
 {
     /// Create a new uninitialized function handler.
     pub fn new(func: F) -> Self {
-        Self { func, state: None }
+        Self {
+            func,
+            state: None,
+            failed_event: None,
+        }
     }
 }
 
@@ -1200,6 +3007,7 @@ where
         f.debug_struct("FunctionHandler")
             .field("func", &self.func)
             .field("state", &self.state)
+            .field("failed_event", &self.failed_event)
             .finish()
     }
 }
@@ -1219,6 +3027,19 @@ where
 
     fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
         self.state = Some(<F::Param as HandlerParam>::init(world, config)?);
+
+        if !<F::Param as HandlerParam>::REFRESHES_ARCHETYPES {
+            config.set_skip_archetype_refresh();
+        }
+
+        if F::FALLIBLE {
+            let idx = world.add_global_event::<HandlerFailed>().index();
+            config.insert_sent_global_event(idx);
+            // Failures are sent without declared access to the event queue.
+            config.set_thread_local();
+            self.failed_event = Some(idx);
+        }
+
         Ok(())
     }
 
@@ -1234,7 +3055,22 @@ where
 
         let param =
             <F::Param as HandlerParam>::get(state, handler_info, event_ptr, target_location, world);
+
         self.func.run(param);
+
+        if let Some(error) = self.func.take_error() {
+            // Fallible handlers have the index of the event set in `init`.
+            let idx = unsafe { self.failed_event.unwrap_unchecked() };
+
+            let event = HandlerFailed {
+                handler: handler_info.id(),
+                error,
+            };
+
+            let ptr = unsafe { world.alloc_layout(Layout::new::<HandlerFailed>()) };
+            unsafe { ptr::write::<HandlerFailed>(ptr.as_ptr().cast(), event) };
+            unsafe { world.queue_global(ptr, idx) };
+        }
     }
 
     fn refresh_archetype(&mut self, arch: &Archetype) {
@@ -1250,6 +3086,14 @@ where
 
         F::Param::remove_archetype(state, arch)
     }
+
+    fn state(&self) -> Option<&dyn Any> {
+        self.func.state()
+    }
+
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        self.func.state_mut()
+    }
 }
 
 /// Trait for functions whose parameters are [`HandlerParam`]s.
@@ -1257,8 +3101,28 @@ pub trait HandlerParamFunction<Marker>: 'static {
     /// The handler params used by this function, combined into a single type.
     type Param: HandlerParam;
 
+    /// Whether the function may fail. See [`Fallible`].
+    #[doc(hidden)]
+    const FALLIBLE: bool = false;
+
     /// Call the function.
     fn run(&mut self, param: <Self::Param as HandlerParam>::This<'_>);
+
+    /// Returns the error of the last call, if it failed. See [`Fallible`].
+    #[doc(hidden)]
+    fn take_error(&mut self) -> Option<Box<dyn HandlerError>> {
+        None
+    }
+
+    /// Returns the explicit state of this function. See [`Handler::state`].
+    fn state(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    /// Mutable version of [`HandlerParamFunction::state`].
+    fn state_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
 }
 
 macro_rules! impl_handler_param_function {
@@ -1281,6 +3145,177 @@ macro_rules! impl_handler_param_function {
 
 all_tuples!(impl_handler_param_function, 0, 15, P, p);
 
+/// A function handler which may fail, returning `Result<(), E>`. When the
+/// function returns an error, a [`HandlerFailed`] event containing the error
+/// is sent.
+///
+/// Like [`WithState`], `Fallible` can be combined with the methods of
+/// [`IntoHandler`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::handler::{Fallible, HandlerFailed};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Load(&'static str);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(Fallible::new(|r: Receiver<Load>| {
+///     if r.event.0.is_empty() {
+///         return Err("empty path");
+///     }
+///     Ok(())
+/// }));
+///
+/// world.add_handler(|r: Receiver<HandlerFailed>| {
+///     println!("handler failed: {}", r.event.error);
+/// });
+///
+/// world.send(Load("")); // Prints "handler failed: empty path".
+/// ```
+pub struct Fallible<F> {
+    func: F,
+    error: Option<Box<dyn HandlerError>>,
+}
+
+impl<F> Fallible<F> {
+    /// Creates a fallible function handler from `func`.
+    pub const fn new(func: F) -> Self {
+        Self { func, error: None }
+    }
+}
+
+impl<F> fmt::Debug for Fallible<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fallible")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum FallibleMarker {}
+
+macro_rules! impl_handler_param_function_fallible {
+    ($(($P:ident, $p:ident)),*) => {
+        impl<F, E, $($P: HandlerParam),*> HandlerParamFunction<(FallibleMarker, fn($($P),*) -> Result<(), E>)>
+            for Fallible<F>
+        where
+            F: FnMut($($P),*) -> Result<(), E> + FnMut($($P::This<'_>),*) -> Result<(), E> + 'static,
+            E: HandlerError,
+        {
+            type Param = ($($P,)*);
+
+            const FALLIBLE: bool = true;
+
+            fn run(
+                &mut self,
+                ($($p,)*): <Self::Param as HandlerParam>::This<'_>
+            ) {
+                if let Err(error) = (self.func)($($p),*) {
+                    self.error = Some(Box::new(error));
+                }
+            }
+
+            fn take_error(&mut self) -> Option<Box<dyn HandlerError>> {
+                self.error.take()
+            }
+        }
+    }
+}
+
+all_tuples!(impl_handler_param_function_fallible, 0, 15, P, p);
+
+/// A function which receives a mutable reference to explicit state owned by
+/// the handler, followed by [`HandlerParam`]s.
+///
+/// Unlike state captured by a closure or kept in a [`Local`], the state can be
+/// accessed from outside the handler with [`World::handler_state`] and
+/// [`World::handler_state_mut`], for instance to inspect or serialize it.
+///
+/// Handlers with explicit state are usually added with
+/// [`World::add_handler_with`]. `WithState` can be used directly to combine
+/// explicit state with the methods of [`IntoHandler`]. Note that like other
+/// function handlers, it is identified by its type, so adding a second
+/// `WithState` of the same type with [`World::add_handler`] has no effect.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::handler::WithState;
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct E;
+///
+/// let mut world = World::new();
+///
+/// let id = world.add_handler(
+///     WithState::new(0_u32, |count: &mut u32, _: Receiver<E>| *count += 1).high(),
+/// );
+///
+/// world.send(E);
+///
+/// assert_eq!(world.handler_state::<u32>(id), Some(&1));
+/// ```
+pub struct WithState<S, F> {
+    state: S,
+    func: F,
+}
+
+impl<S, F> WithState<S, F> {
+    /// Creates a function handler from `func` with the initial state `state`.
+    pub const fn new(state: S, func: F) -> Self {
+        Self { state, func }
+    }
+}
+
+impl<S: fmt::Debug, F> fmt::Debug for WithState<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithState")
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum WithStateMarker {}
+
+macro_rules! impl_handler_param_function_with_state {
+    ($(($P:ident, $p:ident)),*) => {
+        impl<S, F, $($P: HandlerParam),*> HandlerParamFunction<(WithStateMarker, fn(&mut S, $($P),*))>
+            for WithState<S, F>
+        where
+            S: 'static,
+            F: FnMut(&mut S, $($P),*) + FnMut(&mut S, $($P::This<'_>),*) + 'static,
+        {
+            type Param = ($($P,)*);
+
+            fn run(
+                &mut self,
+                ($($p,)*): <Self::Param as HandlerParam>::This<'_>
+            ) {
+                (self.func)(&mut self.state, $($p),*)
+            }
+
+            fn state(&self) -> Option<&dyn Any> {
+                Some(&self.state)
+            }
+
+            fn state_mut(&mut self) -> Option<&mut dyn Any> {
+                Some(&mut self.state)
+            }
+        }
+    }
+}
+
+all_tuples!(impl_handler_param_function_with_state, 0, 15, P, p);
+
 /// A [`HandlerParam`] for storing handler-local state.
 ///
 /// Any type that implements [`Default`] can be wrapped in a `Local`.
@@ -1288,7 +3323,6 @@ all_tuples!(impl_handler_param_function, 0, 15, P, p);
 /// # Examples
 ///
 /// ```
-/// use evenio::handler::Local;
 /// use evenio::prelude::*;
 ///
 /// #[derive(GlobalEvent)]
@@ -1325,17 +3359,142 @@ all_tuples!(impl_handler_param_function, 0, 15, P, p);
 /// counter is 1
 /// ```
 #[derive(Debug)]
-pub struct Local<'a, T> {
-    state: &'a mut T,
+pub struct Local<'a, T> {
+    state: &'a mut T,
+}
+
+unsafe impl<T: Default + 'static> HandlerParam for Local<'_, T> {
+    type State = T;
+
+    type This<'a> = Local<'a, T>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        Ok(T::default())
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        _world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        Local { state }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl<T> Deref for Local<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.state
+    }
+}
+
+impl<T> DerefMut for Local<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.state
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Local<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.state.fmt(f)
+    }
+}
+
+/// A [`HandlerParam`] which accumulates data while events are broadcast, and
+/// applies it to the world once the event queue has been flushed.
+///
+/// Like a [`Local`], the data is owned by the handler. The first time the
+/// handler accesses it during a flush of the event queue, the data is queued
+/// to be flushed. After the outermost flush of the event queue finishes, the
+/// data is taken out of the handler, leaving a default value in its place,
+/// and [`DeferredFlush::flush`] is called on it with mutable access to the
+/// world.
+///
+/// This is useful for collecting data from many events and applying it at
+/// once, without sending another event to apply it.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::handler::{Deferred, DeferredFlush};
+/// use evenio::prelude::*;
+///
+/// #[derive(GlobalEvent)]
+/// struct Damage(u32);
+///
+/// #[derive(GlobalEvent)]
+/// struct TotalDamage(u32);
+///
+/// #[derive(Default)]
+/// struct Total(u32);
+///
+/// impl DeferredFlush for Total {
+///     fn flush(&mut self, world: &mut World) {
+///         world.send(TotalDamage(self.0));
+///     }
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Damage>, mut total: Deferred<Total>| {
+///     total.0 += r.event.0;
+/// });
+///
+/// world.add_handler(|r: Receiver<TotalDamage>| println!("total damage: {}", r.event.0));
+///
+/// world.add_handler(|r: Receiver<Damage>, sender: Sender<Damage>| {
+///     if r.event.0 > 1 {
+///         sender.send(Damage(r.event.0 - 1));
+///     }
+/// });
+///
+/// world.send(Damage(3)); // Prints "total damage: 6".
+/// ```
+#[derive(Debug)]
+pub struct Deferred<'a, T> {
+    data: core::cell::RefMut<'a, T>,
+}
+
+/// Data accumulated by a [`Deferred`] handler param.
+pub trait DeferredFlush: Default + 'static {
+    /// Applies the accumulated data to the world. Called after the outermost
+    /// flush of the event queue in which the data was accessed.
+    fn flush(&mut self, world: &mut World);
+}
+
+/// Internal state for a [`Deferred`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DeferredState<T> {
+    data: RefCell<T>,
+    /// Whether the data is queued to be flushed.
+    queued: core::cell::Cell<bool>,
 }
 
-unsafe impl<T: Default + 'static> HandlerParam for Local<'_, T> {
-    type State = T;
+unsafe impl<T: DeferredFlush> HandlerParam for Deferred<'_, T> {
+    type State = Rc<DeferredState<T>>;
 
-    type This<'a> = Local<'a, T>;
+    type This<'a> = Deferred<'a, T>;
 
-    fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
-        Ok(T::default())
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        // The flush is queued outside of the handler's declared access.
+        config.set_thread_local();
+
+        Ok(Rc::new(DeferredState {
+            data: RefCell::new(T::default()),
+            queued: core::cell::Cell::new(false),
+        }))
     }
 
     unsafe fn get<'a>(
@@ -1343,9 +3502,21 @@ unsafe impl<T: Default + 'static> HandlerParam for Local<'_, T> {
         _info: &'a HandlerInfo,
         _event_ptr: EventPtr<'a>,
         _target_location: EntityLocation,
-        _world: UnsafeWorldCell<'a>,
+        world: UnsafeWorldCell<'a>,
     ) -> Self::This<'a> {
-        Local { state }
+        if !state.queued.replace(true) {
+            let state = state.clone();
+
+            world.queue_flush(Box::new(move |world: &mut World| {
+                state.queued.set(false);
+                let mut data = mem::take(&mut *state.data.borrow_mut());
+                data.flush(world);
+            }));
+        }
+
+        Deferred {
+            data: state.data.borrow_mut(),
+        }
     }
 
     fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
@@ -1353,23 +3524,43 @@ unsafe impl<T: Default + 'static> HandlerParam for Local<'_, T> {
     fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
 }
 
-impl<T> Deref for Local<'_, T> {
-    type Target = T;
+/// Applies the data of a [`Deferred`] param to the world.
+pub(crate) type Flush = Box<dyn FnOnce(&mut World)>;
 
-    fn deref(&self) -> &Self::Target {
-        self.state
+/// The flushes of [`Deferred`] params queued during a flush of the event
+/// queue.
+#[derive(Default)]
+pub(crate) struct DeferredFlushes(pub(crate) Vec<Flush>);
+
+impl DeferredFlushes {
+    pub(crate) fn new() -> Self {
+        Self::default()
     }
-}
 
-impl<T> DerefMut for Local<'_, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.state
+    pub(crate) fn take(&mut self) -> Vec<Flush> {
+        mem::take(&mut self.0)
     }
 }
 
-impl<T: fmt::Display> fmt::Display for Local<'_, T> {
+impl fmt::Debug for DeferredFlushes {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.state.fmt(f)
+        f.debug_struct("DeferredFlushes")
+            .field("len", &self.0.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Deref for Deferred<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<T> DerefMut for Deferred<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
     }
 }
 
@@ -1379,6 +3570,8 @@ unsafe impl HandlerParam for &'_ HandlerInfo {
 
     type This<'a> = &'a HandlerInfo;
 
+    const REFRESHES_ARCHETYPES: bool = false;
+
     fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         Ok(())
     }
@@ -1405,6 +3598,8 @@ unsafe impl<P: HandlerParam> HandlerParam for std::sync::Mutex<P> {
 
     type This<'a> = std::sync::Mutex<P::This<'a>>;
 
+    const REFRESHES_ARCHETYPES: bool = P::REFRESHES_ARCHETYPES;
+
     fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         P::init(world, config)
     }
@@ -1435,6 +3630,8 @@ unsafe impl<P: HandlerParam> HandlerParam for std::sync::RwLock<P> {
 
     type This<'a> = std::sync::RwLock<P::This<'a>>;
 
+    const REFRESHES_ARCHETYPES: bool = P::REFRESHES_ARCHETYPES;
+
     fn init(world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
         P::init(world, config)
     }
@@ -1468,11 +3665,139 @@ pub struct AddHandler(pub HandlerId);
 #[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct RemoveHandler(pub HandlerId);
 
+/// An error returned by a fallible handler. Implemented for all types which
+/// implement [`Debug`] and [`Display`].
+///
+/// [`Debug`]: fmt::Debug
+/// [`Display`]: fmt::Display
+pub trait HandlerError: fmt::Debug + fmt::Display + 'static {
+    #[doc(hidden)]
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: fmt::Debug + fmt::Display + 'static> HandlerError for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl dyn HandlerError {
+    /// Returns `true` if the error is of type `T`.
+    pub fn is<T: HandlerError>(&self) -> bool {
+        self.as_any().is::<T>()
+    }
+
+    /// Returns a reference to the error if it is of type `T`.
+    pub fn downcast_ref<T: HandlerError>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+/// An event sent when a handler panics while panics are caught. See
+/// [`World::set_catch_panics`].
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(GlobalEvent, Clone, Debug)]
+pub struct HandlerPanicked {
+    /// The handler which panicked.
+    pub handler: HandlerId,
+    /// The panic message, if the panic payload is a string.
+    pub message: String,
+}
+
+/// A soft limit on the time a handler may take to run. See
+/// [`World::set_handler_budget`].
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct HandlerBudget {
+    /// The time the handler may take.
+    pub time: core::time::Duration,
+    /// Whether the handler is skipped until the world is next advanced after
+    /// it exceeded the budget.
+    pub skip_next_tick: bool,
+}
+
+#[cfg(feature = "std")]
+impl HandlerBudget {
+    /// Creates a budget of `time` which doesn't skip the handler.
+    pub const fn new(time: core::time::Duration) -> Self {
+        Self {
+            time,
+            skip_next_tick: false,
+        }
+    }
+
+    /// Makes the handler skip all of its runs during the frame after the one
+    /// in which it exceeded the budget. Each call to [`World::advance`] or
+    /// [`World::advance_time`] starts a new frame.
+    pub const fn skip_next_tick(mut self) -> Self {
+        self.skip_next_tick = true;
+        self
+    }
+}
+
+/// The state of a handler's time budget.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct Watchdog {
+    /// The budget set for the handler itself.
+    pub(crate) budget: Option<HandlerBudget>,
+    /// The smallest budget of the handler's groups.
+    pub(crate) group_budget: Option<HandlerBudget>,
+    /// The run time of the last run, if it exceeded the budget and wasn't
+    /// reported yet.
+    pub(crate) overrun: Option<core::time::Duration>,
+    /// The frame during which the handler is skipped, if any. See
+    /// [`World::advance_time`].
+    pub(crate) skipped_frame: Option<u64>,
+}
+
+/// An event sent when a handler takes longer to run than its
+/// [`HandlerBudget`]. See [`World::set_handler_budget`].
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(GlobalEvent, Clone, Debug)]
+pub struct HandlerOverBudget {
+    /// The handler which exceeded its budget.
+    pub handler: HandlerId,
+    /// The time the handler took to run.
+    pub duration: core::time::Duration,
+    /// The budget of the handler.
+    pub budget: HandlerBudget,
+}
+
+/// An event sent when a [`Fallible`] handler returns an error.
+#[derive(GlobalEvent, Debug)]
+pub struct HandlerFailed {
+    /// The handler which failed.
+    pub handler: HandlerId,
+    /// The error returned by the handler.
+    pub error: Box<dyn HandlerError>,
+}
+
+/// Returns the tiebreak of the handler with the given insertion order. See
+/// [`World::shuffle_handler_order`].
+fn tiebreak(seed: Option<u64>, order: u64) -> u64 {
+    let Some(seed) = seed else {
+        return order;
+    };
+
+    // SplitMix64 finalizer.
+    let mut z = seed ^ order.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 #[cfg(test)]
 mod tests {
+    use core::cell::Cell;
+
     use evenio::prelude::*;
 
     use super::*;
+    use crate::access::CaseAccess;
     use crate::event::GlobalEvents;
 
     #[test]
@@ -1560,4 +3885,488 @@ mod tests {
 
         world.send(E);
     }
+
+    #[test]
+    fn filter() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(TargetedEvent)]
+        struct B(u32);
+
+        #[derive(Component)]
+        struct Sum(u32);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Sum(0));
+
+        world.add_handler(
+            (|r: Receiver<A>, mut s: Single<&mut Sum>| s.0 += r.event.0).filter(|a: &A| a.0 > 5),
+        );
+        world.add_handler(
+            (|r: Receiver<B, &mut Sum>| r.query.0 += r.event.0).filter(|b: &B| b.0 % 2 == 0),
+        );
+
+        for i in 0..10 {
+            world.send(A(i));
+            world.send_to(e, B(i * 100 + 1));
+        }
+
+        world.send_to(e, B(1000));
+
+        assert_eq!(world.get::<Sum>(e).unwrap().0, 6 + 7 + 8 + 9 + 1000);
+
+        // The handler doesn't receive `B`.
+        assert!(world
+            .try_add_handler((|_: Receiver<A>| {}).filter(|_: &B| true))
+            .is_err());
+    }
+
+    #[test]
+    fn fallible_handler() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct Failures(Vec<(HandlerId, u32)>);
+
+        #[derive(Debug, PartialEq)]
+        struct TooLarge(u32);
+
+        impl fmt::Display for TooLarge {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} is too large", self.0)
+            }
+        }
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Failures(vec![]));
+
+        let id = world.add_handler(Fallible::new(|r: Receiver<A>, _: Sender<A>| {
+            if r.event.0 > 5 {
+                return Err(TooLarge(r.event.0));
+            }
+            Ok(())
+        }));
+
+        world.add_handler(|r: Receiver<HandlerFailed>, mut f: Single<&mut Failures>| {
+            assert_eq!(r.event.error.to_string(), "42 is too large");
+            let err = r.event.error.downcast_ref::<TooLarge>().unwrap();
+            f.0.push((r.event.handler, err.0));
+        });
+
+        world.send(A(1));
+        world.send(A(42));
+
+        assert_eq!(world.get::<Failures>(e).unwrap().0, [(id, 42)]);
+    }
+
+    #[test]
+    fn add_handler_with() {
+        #[derive(GlobalEvent)]
+        struct E(u32);
+
+        let mut world = World::new();
+
+        fn handler(sum: &mut u32, r: Receiver<E>) {
+            *sum += r.event.0;
+        }
+
+        // Each handler has its own state.
+        let a = world.add_handler_with(0_u32, handler);
+        let b = world.add_handler_with(100_u32, handler);
+        assert_ne!(a, b);
+
+        world.send(E(1));
+        world.send(E(2));
+
+        assert_eq!(world.handler_state::<u32>(a), Some(&3));
+        assert_eq!(world.handler_state::<u32>(b), Some(&103));
+        assert_eq!(world.handler_state::<i32>(a), None);
+
+        *world.handler_state_mut::<u32>(a).unwrap() = 10;
+        world.send(E(1));
+        assert_eq!(world.handler_state::<u32>(a), Some(&11));
+
+        // The state is reachable through wrapper handlers.
+        let c = world.add_handler(WithState::new(0_u32, handler).low().label("sum"));
+        world.send(E(5));
+        assert_eq!(world.handler_state::<u32>(c), Some(&5));
+
+        let d = world.add_handler(|_: Receiver<E>| {});
+        assert_eq!(world.handler_state::<u32>(d), None);
+    }
+
+    #[test]
+    fn handler_phases() {
+        use core::cell::RefCell;
+
+        #[derive(GlobalEvent)]
+        struct E;
+
+        #[derive(TargetedEvent)]
+        struct T;
+
+        let mut world = World::new();
+
+        thread_local! {
+            static ORDER: RefCell<Vec<&'static str>> = const { RefCell::new(vec![]) };
+        }
+
+        macro_rules! push {
+            ($name:literal) => {
+                (|_: Receiver<E>| ORDER.with_borrow_mut(|o| o.push($name)))
+            };
+        }
+
+        world.add_handler(push!("cleanup").phase(HandlerPhase::Cleanup));
+        world.add_handler(push!("post high").phase(HandlerPhase::Post).high());
+        world.add_handler(push!("main"));
+        world.add_handler(push!("post").phase(HandlerPhase::Post).label("post"));
+        world.add_handler(push!("pre low").low().phase(HandlerPhase::Pre));
+        world.add_handler(push!("main high").high());
+        world.add_handler(push!("pre").phase(HandlerPhase::Pre));
+        world.add_handler(
+            push!("post before")
+                .phase(HandlerPhase::Post)
+                .before("post"),
+        );
+
+        world.send(E);
+
+        assert_eq!(
+            ORDER.take(),
+            [
+                "pre",
+                "pre low",
+                "main high",
+                "main",
+                "post high",
+                "post before",
+                "post",
+                "cleanup"
+            ]
+        );
+
+        // Targeted handler lists are ordered the same way.
+        let e = world.spawn();
+
+        world.add_handler(
+            (|_: Receiver<T, ()>| ORDER.with_borrow_mut(|o| o.push("post")))
+                .phase(HandlerPhase::Post),
+        );
+        world.add_handler(|_: Receiver<T, ()>| ORDER.with_borrow_mut(|o| o.push("main")));
+
+        world.send_to(e, T);
+
+        assert_eq!(ORDER.take(), ["main", "post"]);
+    }
+
+    #[test]
+    fn describe_handler() {
+        #[derive(TargetedEvent)]
+        struct Hit;
+
+        #[derive(GlobalEvent)]
+        struct Died;
+
+        #[derive(Component)]
+        struct Health;
+
+        #[derive(Component)]
+        struct Armor;
+
+        #[derive(Component)]
+        struct Invulnerable;
+
+        let mut world = World::new();
+
+        let id = world.add_handler(
+            (|_: Receiver<Hit, (&mut Health, Not<&Invulnerable>)>,
+              _: Fetcher<&Armor>,
+              _: Sender<Died>| {})
+            .phase(HandlerPhase::Post),
+        );
+
+        let health = world.add_component::<Health>();
+        let armor = world.add_component::<Armor>();
+        let invulnerable = world.add_component::<Invulnerable>();
+        let hit = world.add_targeted_event::<Hit>();
+        let died = world.add_global_event::<Died>();
+
+        let desc = world.describe_handler(id).unwrap();
+
+        assert_eq!(desc.id, id);
+        assert_eq!(desc.received_events[0].id, EventId::Targeted(hit));
+        assert_eq!(desc.received_event_access, Access::Read);
+        assert_eq!(desc.sent_events[0].id, EventId::Global(died));
+        assert_eq!(desc.event_queue_access, Access::ReadWrite);
+        assert_eq!(desc.phase, HandlerPhase::Post);
+
+        let query: Vec<Vec<_>> = desc
+            .target_query
+            .unwrap()
+            .into_iter()
+            .map(|case| case.into_iter().map(|(c, a)| (c.id, a)).collect())
+            .collect();
+        assert_eq!(
+            query,
+            [[
+                (health, CaseAccess::ReadWrite),
+                (invulnerable, CaseAccess::Not)
+            ]]
+        );
+
+        let access: Vec<_> = desc
+            .component_access
+            .iter()
+            .map(|(c, a)| (c.id, *a))
+            .collect();
+        assert_eq!(access, [(health, Access::ReadWrite), (armor, Access::Read)]);
+        assert!(desc.component_access[1].0.name.ends_with("Armor"));
+
+        world.remove_handler(id);
+        assert!(world.describe_handler(id).is_none());
+    }
+
+    #[test]
+    fn build_handler() {
+        #[derive(GlobalEvent)]
+        struct E;
+
+        let mut world = World::new();
+
+        let log = Rc::new(RefCell::new(vec![]));
+        let enabled = Rc::new(Cell::new(true));
+
+        let l = log.clone();
+        let e = enabled.clone();
+        let id = world
+            .build_handler(move |_: Receiver<E>| l.borrow_mut().push("b"))
+            .name("b")
+            .label("b")
+            .run_if(move || e.get())
+            .add();
+
+        let l = log.clone();
+        world
+            .build_handler(move |_: Receiver<E>| l.borrow_mut().push("a"))
+            .before("b")
+            .add();
+
+        assert_eq!(world.handlers()[id].name(), "b");
+
+        world.send(E);
+        assert_eq!(*log.borrow(), ["a", "b"]);
+
+        enabled.set(false);
+        world.send(E);
+        assert_eq!(*log.borrow(), ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn handler_graph() {
+        #[derive(TargetedEvent)]
+        struct E;
+
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let a = world.add_handler((|_: Receiver<E, &C>| {}).label("a"));
+        let b = world.add_handler((|_: Receiver<E, ()>| {}).label("b").after("a"));
+        let c = world.add_handler(|_: Receiver<E, ()>| {});
+        let d = world.add_handler((|_: Receiver<E, ()>| {}).phase(HandlerPhase::Pre));
+
+        let event = world.add_targeted_event::<E>();
+        let graph = world.handler_graph(event).unwrap();
+
+        assert_eq!(graph.nodes, [d, a, b, c]);
+        assert_eq!(
+            graph.edges,
+            [
+                HandlerEdge {
+                    before: a,
+                    after: b,
+                    reason: OrderingReason::After("a".into())
+                },
+                HandlerEdge {
+                    before: d,
+                    after: a,
+                    reason: OrderingReason::Phase
+                },
+                HandlerEdge {
+                    before: b,
+                    after: c,
+                    reason: OrderingReason::InsertionOrder
+                },
+            ]
+        );
+
+        let dot = graph.to_dot(world.handlers());
+        assert!(dot.contains("1 -> 2 [label=\"after a\"];"));
+        assert!(dot.contains("2 -> 3 [label=\"insertion order\", style=dashed];"));
+
+        world.remove_targeted_event(event);
+        assert!(world.handler_graph(event).is_none());
+    }
+
+    #[test]
+    fn skip_archetype_refresh() {
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(TargetedEvent)]
+        struct B;
+
+        #[derive(Component)]
+        struct C;
+
+        #[derive(HandlerParam)]
+        #[allow(dead_code)]
+        struct EventParams<'a> {
+            sender: Sender<'a, A>,
+            handlers: &'a Handlers,
+        }
+
+        let mut world = World::new();
+
+        let count = Rc::new(Cell::new(0));
+        let c = count.clone();
+
+        let a = world
+            .add_handler(move |_: Receiver<A>, _: Local<u32>, _: EventParams| c.set(c.get() + 1));
+        let b = world.add_handler(|_: Receiver<A>, _: Fetcher<&C>| {});
+        let c = world.add_handler(|_: Receiver<B, ()>| {});
+
+        assert!(!world.handlers()[a].refreshes_archetypes());
+        assert!(world.handlers()[b].refreshes_archetypes());
+        assert!(world.handlers()[c].refreshes_archetypes());
+
+        let e = world.spawn();
+        world.insert(e, C);
+
+        world.send(A);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn raw_handler() {
+        #[derive(TargetedEvent)]
+        struct E;
+
+        #[derive(Component)]
+        struct C(u32);
+
+        unsafe fn run(
+            data: NonNull<u8>,
+            _info: &HandlerInfo,
+            _event_ptr: EventPtr,
+            target_location: EntityLocation,
+            world: UnsafeWorldCell,
+        ) {
+            let arch = world.archetypes().get(target_location.archetype).unwrap();
+            let idx = world
+                .components()
+                .get_by_type_id(TypeId::of::<C>())
+                .unwrap()
+                .id()
+                .index();
+            let col = arch.column_of(idx).unwrap().data().cast::<C>();
+
+            let c = &mut *col.as_ptr().add(target_location.row.0 as usize);
+            c.0 += *data.cast::<u32>().as_ref();
+        }
+
+        let mut world = World::new();
+
+        let event = world.add_targeted_event::<E>();
+        let c = world.add_component::<C>();
+
+        let desc = |drop: crate::drop::DropFn| RawHandlerDescriptor {
+            name: "raw".into(),
+            received_events: vec![event.into()],
+            received_event_access: Access::Read,
+            target_access: vec![(c, Access::ReadWrite)],
+            component_access: vec![],
+            sent_events: vec![],
+            run,
+            data: NonNull::from(&5_u32).cast(),
+            drop,
+        };
+
+        thread_local! {
+            static DROPS: Cell<u32> = const { Cell::new(0) };
+        }
+
+        let id = world.add_handler(unsafe {
+            RawHandler::new(desc(Some(|_| DROPS.with(|d| d.set(d.get() + 1)))))
+        });
+
+        let e1 = world.spawn();
+        world.insert(e1, C(1));
+        let e2 = world.spawn();
+
+        world.send_to(e1, E);
+        world.send_to(e2, E);
+        assert_eq!(world.get::<C>(e1).unwrap().0, 6);
+        assert_eq!(world.handlers()[id].name(), "raw");
+
+        world.remove_handler(id);
+        assert_eq!(DROPS.with(Cell::get), 1);
+
+        let mut conflicting = desc(None);
+        conflicting.component_access.push((c, Access::Read));
+        let err = world
+            .try_add_handler(unsafe { RawHandler::new(conflicting) })
+            .unwrap_err();
+        assert_eq!(err.conflicts()[0].component, c);
+    }
+
+    #[test]
+    fn deferred() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(GlobalEvent)]
+        struct Flushed(Vec<u32>);
+
+        #[derive(Default)]
+        struct Collected(Vec<u32>);
+
+        impl DeferredFlush for Collected {
+            fn flush(&mut self, world: &mut World) {
+                world.send(Flushed(mem::take(&mut self.0)));
+            }
+        }
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, mut c: Deferred<Collected>| c.0.push(r.event.0));
+
+        world.add_handler(|r: Receiver<A>, sender: Sender<A>| {
+            if r.event.0 > 0 {
+                sender.send(A(r.event.0 - 1));
+            }
+        });
+
+        let log = Rc::new(RefCell::new(vec![]));
+        let l = log.clone();
+        world.add_handler(move |r: Receiver<Flushed>, sender: Sender<A>| {
+            l.borrow_mut().push(r.event.0.clone());
+
+            // Data accessed during a flush is flushed again.
+            if r.event.0 == [2, 1, 0] {
+                sender.send(A(0));
+            }
+        });
+
+        world.send(A(2));
+        assert_eq!(*log.borrow(), [vec![2, 1, 0], vec![0]]);
+    }
 }
@@ -0,0 +1,148 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::{HandlerId, HandlerInfo, HandlerPhase, HandlerPriority};
+use crate::access::{Access, CaseAccess};
+use crate::component::{ComponentId, ComponentIdx, Components};
+use crate::event::{EventId, GlobalEvents, TargetedEvents};
+
+/// A description of the configuration of a handler, with the events and
+/// components it uses resolved to their names. This is intended for tools
+/// which display the handlers of a world, such as inspectors and debuggers.
+///
+/// Obtained from [`World::describe_handler`]. Events and components which
+/// were removed from the world since the handler was added are left out.
+///
+/// [`World::describe_handler`]: crate::world::World::describe_handler
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct HandlerDescription<'a> {
+    /// The ID of the handler.
+    pub id: HandlerId,
+    /// The name of the handler. See [`HandlerInfo::name`].
+    pub name: &'a str,
+    /// The events the handler receives.
+    pub received_events: Vec<EventDescription<'a>>,
+    /// The handler's access to the events it receives.
+    pub received_event_access: Access,
+    /// The query which the targets of the received events must match, or
+    /// `None` if the handler receives global events.
+    ///
+    /// The query is in disjunctive normal form: a target matches if it
+    /// matches every component of any of the cases. See
+    /// [`ComponentAccess::cases`].
+    ///
+    /// [`ComponentAccess::cases`]: crate::access::ComponentAccess::cases
+    pub target_query: Option<Vec<Vec<(ComponentDescription<'a>, CaseAccess)>>>,
+    /// The events the handler may send.
+    pub sent_events: Vec<EventDescription<'a>>,
+    /// The handler's access to the event queue. See
+    /// [`HandlerInfo::event_queue_access`].
+    pub event_queue_access: Access,
+    /// The components the handler reads or writes, with the strongest access
+    /// to each. See [`ComponentAccess::accessed_components`].
+    ///
+    /// [`ComponentAccess::accessed_components`]: crate::access::ComponentAccess::accessed_components
+    pub component_access: Vec<(ComponentDescription<'a>, Access)>,
+    /// The priority of the handler.
+    pub priority: HandlerPriority,
+    /// The phase of the handler.
+    pub phase: HandlerPhase,
+}
+
+/// An event in a [`HandlerDescription`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct EventDescription<'a> {
+    /// The ID of the event.
+    pub id: EventId,
+    /// The name of the event.
+    pub name: &'a str,
+}
+
+/// A component in a [`HandlerDescription`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ComponentDescription<'a> {
+    /// The ID of the component.
+    pub id: ComponentId,
+    /// The name of the component.
+    pub name: &'a str,
+}
+
+impl<'a> HandlerDescription<'a> {
+    pub(crate) fn new(
+        info: &'a HandlerInfo,
+        components: &'a Components,
+        global_events: &'a GlobalEvents,
+        targeted_events: &'a TargetedEvents,
+    ) -> Self {
+        let component = |idx: ComponentIdx| {
+            let info = components.get_by_index(idx)?;
+
+            Some(ComponentDescription {
+                id: info.id(),
+                name: info.name(),
+            })
+        };
+
+        let received_events = info
+            .received_events()
+            .iter()
+            .filter_map(|&id| {
+                let name = match id {
+                    EventId::Global(id) => global_events.get(id)?.name(),
+                    EventId::Targeted(id) => targeted_events.get(id)?.name(),
+                };
+
+                Some(EventDescription { id, name })
+            })
+            .collect();
+
+        let target_query = info.targeted_event_component_access().map(|ca| {
+            ca.cases()
+                .map(|case| {
+                    case.iter()
+                        .filter_map(|&(idx, access)| Some((component(idx)?, access)))
+                        .collect()
+                })
+                .collect()
+        });
+
+        let sent_global_events = info.sent_global_events().filter_map(|idx| {
+            let info = global_events.get_by_index(idx)?;
+
+            Some(EventDescription {
+                id: EventId::Global(info.id()),
+                name: info.name(),
+            })
+        });
+
+        let sent_targeted_events = info.sent_targeted_events().filter_map(|idx| {
+            let info = targeted_events.get_by_index(idx)?;
+
+            Some(EventDescription {
+                id: EventId::Targeted(info.id()),
+                name: info.name(),
+            })
+        });
+
+        let component_access = info
+            .component_access()
+            .accessed_components()
+            .into_iter()
+            .filter_map(|(idx, access)| Some((component(idx)?, access)))
+            .collect();
+
+        Self {
+            id: info.id(),
+            name: info.name(),
+            received_events,
+            received_event_access: info.received_event_access(),
+            target_query,
+            sent_events: sent_global_events.chain(sent_targeted_events).collect(),
+            event_queue_access: info.event_queue_access(),
+            component_access,
+            priority: info.priority(),
+            phase: info.phase(),
+        }
+    }
+}
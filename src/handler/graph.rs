@@ -0,0 +1,219 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec, vec::Vec};
+use core::fmt::Write;
+
+use super::{HandlerId, HandlerInfo, HandlerInfoPtr, Handlers};
+use crate::event::EventId;
+
+/// The order in which the handlers of an event run, as a graph whose nodes
+/// are handlers and whose edges are the constraints ordering them. This is
+/// intended for debugging the order of handlers and for visualizing it.
+///
+/// Obtained from [`World::handler_graph`].
+///
+/// [`World::handler_graph`]: crate::world::World::handler_graph
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct HandlerGraph {
+    /// The event whose handlers are in the graph.
+    pub event: EventId,
+    /// The handlers of the event, in the order they run.
+    pub nodes: Vec<HandlerId>,
+    /// The constraints ordering the handlers. Contains every label
+    /// constraint between handlers of the event, and an implicit edge between
+    /// each pair of consecutive handlers which isn't ordered by a label.
+    pub edges: Vec<HandlerEdge>,
+}
+
+/// A constraint in a [`HandlerGraph`] which makes one handler run before
+/// another.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct HandlerEdge {
+    /// The handler which runs first.
+    pub before: HandlerId,
+    /// The handler which runs second.
+    pub after: HandlerId,
+    /// Why `before` runs before `after`.
+    pub reason: OrderingReason,
+}
+
+/// The reason for a [`HandlerEdge`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum OrderingReason {
+    /// The first handler runs in an earlier [`HandlerPhase`].
+    ///
+    /// [`HandlerPhase`]: super::HandlerPhase
+    Phase,
+    /// The first handler has a higher [`HandlerPriority`] in the same phase.
+    ///
+    /// [`HandlerPriority`]: super::HandlerPriority
+    Priority,
+    /// The first handler runs before the handlers with the label, which the
+    /// second handler has. See [`IntoHandler::before`].
+    ///
+    /// [`IntoHandler::before`]: super::IntoHandler::before
+    Before(Cow<'static, str>),
+    /// The second handler runs after the handlers with the label, which the
+    /// first handler has. See [`IntoHandler::after`].
+    ///
+    /// [`IntoHandler::after`]: super::IntoHandler::after
+    After(Cow<'static, str>),
+    /// The handlers are otherwise unordered, and run in the order they were
+    /// added, or in the order set by [`World::shuffle_handler_order`].
+    ///
+    /// [`World::shuffle_handler_order`]: crate::world::World::shuffle_handler_order
+    InsertionOrder,
+}
+
+impl OrderingReason {
+    /// Returns `true` if the order follows from the phases, priorities or
+    /// insertion order of the handlers, rather than from a label constraint.
+    pub fn is_implicit(&self) -> bool {
+        matches!(self, Self::Phase | Self::Priority | Self::InsertionOrder)
+    }
+}
+
+impl HandlerGraph {
+    /// Creates the graph of the handlers in `entries`, which must be in the
+    /// order they run.
+    pub(crate) fn new(event: EventId, entries: &[HandlerInfoPtr]) -> Self {
+        let infos: Vec<&HandlerInfo> = entries.iter().map(|p| unsafe { p.as_info() }).collect();
+
+        let mut edges = vec![];
+
+        for (i, a) in infos.iter().enumerate() {
+            for b in &infos[i + 1..] {
+                // Label constraints only order handlers of the same phase and
+                // priority.
+                if a.segment() == b.segment() {
+                    label_edges(a, b, &mut edges);
+                    label_edges(b, a, &mut edges);
+                }
+            }
+        }
+
+        for pair in infos.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+
+            let ordered = edges.iter().any(|e: &HandlerEdge| {
+                (e.before == a.id() && e.after == b.id())
+                    || (e.before == b.id() && e.after == a.id())
+            });
+
+            if ordered {
+                continue;
+            }
+
+            let reason = if a.phase() != b.phase() {
+                OrderingReason::Phase
+            } else if a.priority() != b.priority() {
+                OrderingReason::Priority
+            } else {
+                OrderingReason::InsertionOrder
+            };
+
+            edges.push(HandlerEdge {
+                before: a.id(),
+                after: b.id(),
+                reason,
+            });
+        }
+
+        Self {
+            event,
+            nodes: infos.iter().map(|info| info.id()).collect(),
+            edges,
+        }
+    }
+
+    /// Returns the edges between the handlers `a` and `b`, in either
+    /// direction. This answers why one of them runs before the other, if
+    /// they are consecutive or ordered by a label.
+    pub fn edges_between(
+        &self,
+        a: HandlerId,
+        b: HandlerId,
+    ) -> impl Iterator<Item = &HandlerEdge> + '_ {
+        self.edges
+            .iter()
+            .filter(move |e| (e.before == a && e.after == b) || (e.before == b && e.after == a))
+    }
+
+    /// Renders the graph in the DOT language of Graphviz, labeling the
+    /// handlers with their names from `handlers`. Implicit edges are dashed.
+    pub fn to_dot(&self, handlers: &Handlers) -> String {
+        let mut dot = String::from("digraph {\n");
+
+        for (i, &id) in self.nodes.iter().enumerate() {
+            let name = handlers.get(id).map_or("", |info| info.name());
+            writeln!(dot, "    {i} [label={}];", quoted(name)).unwrap();
+        }
+
+        for edge in &self.edges {
+            let index = |id| self.nodes.iter().position(|&n| n == id).unwrap();
+
+            let label = match &edge.reason {
+                OrderingReason::Phase => "phase".into(),
+                OrderingReason::Priority => "priority".into(),
+                OrderingReason::Before(label) => format!("before {label}"),
+                OrderingReason::After(label) => format!("after {label}"),
+                OrderingReason::InsertionOrder => "insertion order".into(),
+            };
+
+            let style = if edge.reason.is_implicit() {
+                ", style=dashed"
+            } else {
+                ""
+            };
+
+            writeln!(
+                dot,
+                "    {} -> {} [label={}{style}];",
+                index(edge.before),
+                index(edge.after),
+                quoted(&label)
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Pushes the label constraints making `a` run before `b` onto `edges`.
+fn label_edges(a: &HandlerInfo, b: &HandlerInfo, edges: &mut Vec<HandlerEdge>) {
+    for label in a.runs_before().iter().filter(|l| b.labels().contains(l)) {
+        edges.push(HandlerEdge {
+            before: a.id(),
+            after: b.id(),
+            reason: OrderingReason::Before(label.clone()),
+        });
+    }
+
+    for label in b.runs_after().iter().filter(|l| a.labels().contains(l)) {
+        edges.push(HandlerEdge {
+            before: a.id(),
+            after: b.id(),
+            reason: OrderingReason::After(label.clone()),
+        });
+    }
+}
+
+/// Quotes a string for use as a DOT identifier.
+fn quoted(s: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in s.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,44 @@
+use core::time::Duration;
+
+/// Statistics about the runs of a handler.
+///
+/// Obtained from [`HandlerInfo::metrics`]. Runs which were skipped because
+/// the handler is disabled, or because an earlier handler took ownership of
+/// the event, are not counted.
+///
+/// [`HandlerInfo::metrics`]: super::HandlerInfo::metrics
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct HandlerMetrics {
+    count: u64,
+    total_time: Duration,
+    max_time: Duration,
+}
+
+impl HandlerMetrics {
+    /// Returns the number of times the handler ran.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the total time spent running the handler.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Returns the longest time a single run of the handler took.
+    pub fn max_time(&self) -> Duration {
+        self.max_time
+    }
+
+    /// Returns the average time spent per run of the handler, or `None` if
+    /// the handler never ran.
+    pub fn mean_time(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total_time.div_f64(self.count as f64))
+    }
+
+    pub(crate) fn record(&mut self, time: Duration) {
+        self.count += 1;
+        self.total_time += time;
+        self.max_time = self.max_time.max(time);
+    }
+}
@@ -0,0 +1,239 @@
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec::Vec};
+use core::any::TypeId;
+use core::fmt;
+use core::ptr::NonNull;
+
+use super::{Handler, HandlerConfig, HandlerInfo, InitError};
+use crate::access::{Access, ComponentAccess};
+use crate::archetype::Archetype;
+use crate::component::ComponentId;
+use crate::drop::DropFn;
+use crate::entity::EntityLocation;
+use crate::event::{EventId, EventPtr};
+use crate::world::{UnsafeWorldCell, World};
+
+/// The function run by a [`RawHandler`]. It is passed the user data of the
+/// handler, followed by the arguments of [`Handler::run`].
+pub type RawHandlerFn = unsafe fn(
+    data: NonNull<u8>,
+    info: &HandlerInfo,
+    event_ptr: EventPtr<'_>,
+    target_location: EntityLocation,
+    world: UnsafeWorldCell<'_>,
+);
+
+/// Data needed to create a [`RawHandler`].
+#[derive(Debug)]
+pub struct RawHandlerDescriptor {
+    /// The name of the handler. See [`HandlerInfo::name`].
+    pub name: Cow<'static, str>,
+    /// The events received by the handler. The events must all be global or
+    /// all be targeted.
+    pub received_events: Vec<EventId>,
+    /// The handler's access to the received events. [`Access::ReadWrite`]
+    /// allows the handler to mutate the events and take ownership of them.
+    pub received_event_access: Access,
+    /// The components which the targets of the received events must have,
+    /// and the handler's access to them. Ignored for global events.
+    pub target_access: Vec<(ComponentId, Access)>,
+    /// The components the handler accesses on any entity.
+    pub component_access: Vec<(ComponentId, Access)>,
+    /// The events the handler may send.
+    pub sent_events: Vec<EventId>,
+    /// The function run by the handler.
+    pub run: RawHandlerFn,
+    /// User data passed to `run`, such as a pointer to a script.
+    pub data: NonNull<u8>,
+    /// Drops `data` when the handler is dropped.
+    pub drop: DropFn,
+}
+
+/// A handler which runs a function pointer with user data, with the access
+/// it is configured with declared at runtime by a [`RawHandlerDescriptor`].
+///
+/// This bypasses [`HandlerParam`]s, and is intended for hosting handlers
+/// defined at runtime, such as handlers written in a scripting language. The
+/// function accesses the world through the [`UnsafeWorldCell`] it is passed,
+/// within the declared access. Like other handlers, a `RawHandler` is added
+/// with [`World::add_handler`], and can be configured with the methods of
+/// [`IntoHandler`].
+///
+/// # Examples
+///
+/// ```
+/// use std::ptr::NonNull;
+///
+/// use evenio::access::Access;
+/// use evenio::entity::EntityLocation;
+/// use evenio::event::EventPtr;
+/// use evenio::handler::{HandlerInfo, RawHandler, RawHandlerDescriptor};
+/// use evenio::prelude::*;
+/// use evenio::world::UnsafeWorldCell;
+///
+/// #[derive(GlobalEvent)]
+/// struct Hit(u32);
+///
+/// unsafe fn run(
+///     data: NonNull<u8>,
+///     _info: &HandlerInfo,
+///     event_ptr: EventPtr,
+///     _target_location: EntityLocation,
+///     _world: UnsafeWorldCell,
+/// ) {
+///     let total = data.cast::<u32>().as_ptr();
+///     *total += event_ptr.as_ptr().cast::<Hit>().as_ref().0;
+///     println!("total damage: {}", *total);
+/// }
+///
+/// let mut world = World::new();
+///
+/// let hit = world.add_global_event::<Hit>();
+///
+/// let desc = RawHandlerDescriptor {
+///     name: "script".into(),
+///     received_events: vec![hit.into()],
+///     received_event_access: Access::Read,
+///     target_access: vec![],
+///     component_access: vec![],
+///     sent_events: vec![],
+///     run,
+///     data: NonNull::from(Box::leak(Box::new(0_u32))).cast(),
+///     drop: Some(|data| unsafe { drop(Box::from_raw(data.cast::<u32>().as_ptr())) }),
+/// };
+///
+/// world.add_handler(unsafe { RawHandler::new(desc) });
+///
+/// world.send(Hit(3)); // Prints "total damage: 3".
+/// world.send(Hit(4)); // Prints "total damage: 7".
+/// ```
+///
+/// [`HandlerParam`]: super::HandlerParam
+/// [`IntoHandler`]: super::IntoHandler
+/// [`World::add_handler`]: crate::world::World::add_handler
+pub struct RawHandler {
+    desc: RawHandlerDescriptor,
+}
+
+impl RawHandler {
+    /// Creates a handler from a descriptor.
+    ///
+    /// # Safety
+    ///
+    /// - `run` must be safe to call with `data` and the arguments of
+    ///   [`Handler::run`], and must only access the received event, the event
+    ///   queue and the components as described by the descriptor.
+    /// - `drop` must be safe to call with `data` once `run` won't be called
+    ///   anymore, as described by [`DropFn`]'s documentation.
+    pub unsafe fn new(desc: RawHandlerDescriptor) -> Self {
+        Self { desc }
+    }
+
+    /// Returns the descriptor of this handler.
+    pub fn descriptor(&self) -> &RawHandlerDescriptor {
+        &self.desc
+    }
+}
+
+impl Handler for RawHandler {
+    fn type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.desc.name.clone()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut HandlerConfig) -> Result<(), InitError> {
+        let desc = &self.desc;
+
+        let event_exists = |world: &World, event: EventId| match event {
+            EventId::Global(id) => world.global_events().contains(id),
+            EventId::Targeted(id) => world.targeted_events().contains(id),
+        };
+
+        for &event in desc.received_events.iter().chain(&desc.sent_events) {
+            if !event_exists(world, event) {
+                return Err(InitError::new(format!(
+                    "event {event:?} does not exist in the world"
+                )));
+            }
+        }
+
+        let component_idx = |world: &World, id: ComponentId| match world.components().get(id) {
+            Some(info) => Ok(info.id().index()),
+            None => Err(InitError::new(format!(
+                "component {id:?} does not exist in the world"
+            ))),
+        };
+
+        config.set_received_events(desc.received_events.iter().copied());
+        config.set_received_event_access(desc.received_event_access);
+
+        if desc.received_events.iter().any(|e| e.is_targeted()) {
+            let mut ca = ComponentAccess::new_true();
+
+            for &(id, access) in &desc.target_access {
+                ca = ca.and(&ComponentAccess::var(component_idx(world, id)?, access));
+            }
+
+            config.set_targeted_event_component_access(ca.clone());
+            config.push_component_access(ca);
+        }
+
+        for &(id, access) in &desc.component_access {
+            let idx = component_idx(world, id)?;
+            config.push_component_access(ComponentAccess::var(idx, access));
+        }
+
+        if !desc.sent_events.is_empty() {
+            config.set_event_queue_access(Access::ReadWrite);
+        }
+
+        for &event in &desc.sent_events {
+            match event {
+                EventId::Global(id) => {
+                    config.insert_sent_global_event(id.index());
+                }
+                EventId::Targeted(id) => {
+                    config.insert_sent_targeted_event(id.index());
+                }
+            }
+        }
+
+        config.set_skip_archetype_refresh();
+
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        (self.desc.run)(self.desc.data, info, event_ptr, target_location, world)
+    }
+
+    fn refresh_archetype(&mut self, _arch: &Archetype) {}
+
+    fn remove_archetype(&mut self, _arch: &Archetype) {}
+}
+
+impl Drop for RawHandler {
+    fn drop(&mut self) {
+        if let Some(drop) = self.desc.drop {
+            unsafe { drop(self.desc.data) }
+        }
+    }
+}
+
+impl fmt::Debug for RawHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawHandler")
+            .field("desc", &self.desc)
+            .finish()
+    }
+}
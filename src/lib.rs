@@ -24,12 +24,18 @@ mod ignore;
 mod map;
 pub mod mutability;
 pub mod query;
+pub mod rng;
 mod slot_map;
 mod sparse;
 mod sparse_map;
+pub mod task;
+pub mod time;
 pub mod tutorial;
 pub mod world;
 
+#[cfg(feature = "rand")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
+pub use rand_core;
 #[cfg(feature = "rayon")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
 pub use rayon;
@@ -48,8 +54,10 @@ pub mod prelude {
         Despawn, EventMut, GlobalEvent, GlobalEventId, Insert, Receiver, ReceiverMut, Remove,
         Sender, Spawn, TargetedEvent, TargetedEventId,
     };
-    pub use crate::fetch::{Fetcher, GetError, Single, SingleError, TrySingle};
-    pub use crate::handler::{Handler, HandlerId, HandlerParam, IntoHandler};
+    pub use crate::fetch::{
+        CachedFetcher, Fetcher, GetError, Single, SingleError, SingleOrDefault, TrySingle,
+    };
+    pub use crate::handler::{Handler, HandlerId, HandlerParam, IntoHandler, Local};
     pub use crate::query::{Has, Not, Or, Query, ReadOnlyQuery, With, Xor};
     pub use crate::world::World;
 }
@@ -15,6 +15,12 @@ pub(crate) type Entry<'a, K, V> = std::collections::hash_map::Entry<'a, K, V>;
 #[cfg(not(feature = "std"))]
 pub(crate) type Entry<'a, K, V, S = ahash::RandomState> = hashbrown::hash_map::Entry<'a, K, V, S>;
 
+#[cfg(feature = "std")]
+pub(crate) type HashSet<T, S = ahash::RandomState> = std::collections::HashSet<T, S>;
+
+#[cfg(not(feature = "std"))]
+pub(crate) type HashSet<T, S = ahash::RandomState> = hashbrown::HashSet<T, S>;
+
 pub(crate) type IndexSet<T, S = ahash::RandomState> = indexmap::IndexSet<T, S>;
 
 /// Map type optimized for [`TypeId`] keys.
@@ -6,17 +6,18 @@ use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicPtr, Ordering as AtomicOrdering};
 
 use evenio_macros::all_tuples;
 pub use evenio_macros::Query;
 
 use crate::access::{Access, ComponentAccess};
-use crate::archetype::{Archetype, ArchetypeRow};
+use crate::archetype::{Archetype, ArchetypeIdx, ArchetypeRow};
 use crate::component::{Component, ComponentIdx};
 use crate::entity::EntityId;
 use crate::handler::{HandlerConfig, InitError};
 use crate::mutability::Mutable;
-use crate::world::World;
+use crate::world::{UnsafeWorldCell, World};
 
 /// Types that can be fetched from an entity.
 ///
@@ -26,7 +27,9 @@ use crate::world::World;
 /// # Deriving
 ///
 /// This trait can be safely implemented using the `Query` derive macro. For a
-/// struct to derive `Query`, all fields must also implement `Query`.
+/// struct to derive `Query`, all fields must also implement `Query`. The
+/// struct may have lifetime, type, and const generic parameters, and any
+/// `where` clauses on the struct are carried over to the generated impls.
 ///
 /// ```
 /// # #[derive(GlobalEvent)]
@@ -708,6 +711,562 @@ unsafe impl<Q: Query> Query for Has<Q> {
 
 unsafe impl<Q: Query> ReadOnlyQuery for Has<Q> {}
 
+/// A [`Query`] which matches entities with the relation `R` and returns the
+/// [`EntityId`] of the related entity, along with the query `Q` on the related
+/// entity.
+///
+/// A relation is a component containing the [`EntityId`] of another entity,
+/// such as a parent, owner, or target. `Related<R, Q>` accesses `R`
+/// immutably, and the components of the read-only query `Q` on any entity, so
+/// it conflicts with other queries and parameters accessing the same
+/// components mutably. The query item of the related entity is `None` if the
+/// related entity doesn't exist or doesn't match `Q`.
+///
+/// `Q` is resolved on the related entity every time the item is fetched. To
+/// fetch from related entities many times, or mutably, use [`Via`].
+///
+/// [`Via`]: crate::fetch::Via
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::prelude::*;
+/// use evenio::query::Related;
+///
+/// #[derive(Component)]
+/// struct ChildOf(EntityId);
+///
+/// impl AsRef<EntityId> for ChildOf {
+///     fn as_ref(&self) -> &EntityId {
+///         &self.0
+///     }
+/// }
+///
+/// #[derive(Component)]
+/// struct Name(&'static str);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, f: Fetcher<(&Name, Related<ChildOf, &Name>)>| {
+///     for (child, parent) in f {
+///         if let Some(name) = parent.item() {
+///             println!("{} is a child of {}", child.0, name.0);
+///         }
+///     }
+/// });
+/// # world.send(E);
+/// ```
+pub struct Related<R, Q = ()> {
+    target: EntityId,
+    item: Option<Q>,
+    _marker: PhantomData<fn() -> R>,
+}
+
+impl<R> Related<R> {
+    /// Creates a new instance wrapping `target`.
+    pub const fn new(target: EntityId) -> Self {
+        Self::with_item(target, Some(()))
+    }
+}
+
+impl<R, Q> Related<R, Q> {
+    /// Creates a new instance wrapping `target` and the query item of the
+    /// related entity.
+    pub const fn with_item(target: EntityId, item: Option<Q>) -> Self {
+        Self {
+            target,
+            item,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the [`EntityId`] of the related entity.
+    pub const fn get(&self) -> EntityId {
+        self.target
+    }
+
+    /// Returns the query item of the related entity, or `None` if the related
+    /// entity doesn't exist or doesn't match the query.
+    pub const fn item(&self) -> Option<&Q> {
+        self.item.as_ref()
+    }
+
+    /// Consumes the `Related` and returns the query item of the related
+    /// entity.
+    pub fn into_item(self) -> Option<Q> {
+        self.item
+    }
+}
+
+impl<R, Q: Clone> Clone for Related<R, Q> {
+    fn clone(&self) -> Self {
+        Self::with_item(self.target, self.item.clone())
+    }
+}
+
+impl<R, Q: Copy> Copy for Related<R, Q> {}
+
+impl<R, Q: PartialEq> PartialEq for Related<R, Q> {
+    fn eq(&self, other: &Self) -> bool {
+        self.target == other.target && self.item == other.item
+    }
+}
+
+impl<R, Q: Eq> Eq for Related<R, Q> {}
+
+impl<R, Q: Ord> PartialOrd for Related<R, Q> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<R, Q: Ord> Ord for Related<R, Q> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.target
+            .cmp(&other.target)
+            .then_with(|| self.item.cmp(&other.item))
+    }
+}
+
+impl<R, Q: Hash> Hash for Related<R, Q> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.target.hash(state);
+        self.item.hash(state);
+    }
+}
+
+impl<R, Q: fmt::Debug> fmt::Debug for Related<R, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Related")
+            .field(&self.target)
+            .field(&self.item)
+            .finish()
+    }
+}
+
+/// The [`Query::State`] of [`Related`].
+#[doc(hidden)]
+pub struct RelatedState<S> {
+    relation: ComponentIdx,
+    query: S,
+    world: NonNull<AtomicPtr<World>>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for RelatedState<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RelatedState")
+            .field("relation", &self.relation)
+            .field("query", &self.query)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The [`Query::ArchState`] of [`Related`].
+#[doc(hidden)]
+pub struct RelatedArchState<R, S> {
+    relation: ColumnPtr<R>,
+    query: S,
+    world: NonNull<AtomicPtr<World>>,
+}
+
+impl<R, S: fmt::Debug> fmt::Debug for RelatedArchState<R, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RelatedArchState")
+            .field("relation", &self.relation)
+            .field("query", &self.query)
+            .finish_non_exhaustive()
+    }
+}
+
+// SAFETY: The world pointer is only used to read the world's address, and the
+// query state is `Send` and `Sync`.
+unsafe impl<R, S: Send> Send for RelatedArchState<R, S> {}
+unsafe impl<R, S: Sync> Sync for RelatedArchState<R, S> {}
+
+unsafe impl<R, Q> Query for Related<R, Q>
+where
+    R: Component + AsRef<EntityId>,
+    Q: ReadOnlyQuery,
+    Q::State: Clone + Send + Sync,
+{
+    type This<'a> = Related<R, Q::This<'a>>;
+
+    type ArchState = RelatedArchState<R, Q::State>;
+
+    type State = RelatedState<Q::State>;
+
+    fn init(
+        world: &mut World,
+        config: &mut HandlerConfig,
+    ) -> Result<(ComponentAccess, Self::State), InitError> {
+        let (ca, relation) = <&R>::init(world, config)?;
+        let (query_ca, query) = Q::init(world, config)?;
+
+        // The subquery accesses components on the related entity, which may be
+        // any entity.
+        if !query_ca.accessed_components().is_empty() {
+            config.push_component_access(query_ca);
+        }
+
+        Ok((
+            ca,
+            RelatedState {
+                relation,
+                query,
+                world: world.address(),
+            },
+        ))
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        RelatedState {
+            relation: <&R>::new_state(world),
+            query: Q::new_state(world),
+            world: world.address(),
+        }
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        Some(RelatedArchState {
+            relation: <&R>::new_arch_state(arch, &mut state.relation)?,
+            query: state.query.clone(),
+            world: state.world,
+        })
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::This<'a> {
+        let target = *<&R>::get(&state.relation, row).as_ref();
+
+        // SAFETY: The address is published before queries are executed, and
+        // the world can't move while they are.
+        let world = (*state.world.as_ptr()).load(AtomicOrdering::Relaxed);
+        let world = UnsafeWorldCell::from_address(NonNull::new_unchecked(world));
+
+        let item = world.entities().get(target).and_then(|loc| {
+            let arch = world.archetypes().get(loc.archetype).unwrap_unchecked();
+            let mut query = state.query.clone();
+            let arch_state = Q::new_arch_state(arch, &mut query)?;
+
+            // SAFETY: The access of `Q` was registered in `init`.
+            Some(Q::get(&arch_state, loc.row))
+        });
+
+        Related::with_item(target, item)
+    }
+}
+
+unsafe impl<R, Q> ReadOnlyQuery for Related<R, Q>
+where
+    R: Component + AsRef<EntityId>,
+    Q: ReadOnlyQuery,
+    Q::State: Clone + Send + Sync,
+{
+}
+
+/// A [`Query`] filter which matches entities with the relation `R`, without
+/// accessing it.
+///
+/// See [`Related`] for more information.
+pub type WithRelation<R> = With<Related<R>>;
+
+/// A function from a query item of type `I` to some other value, used by the
+/// [`Map`] query.
+///
+/// This is implemented on a type rather than a closure so that it can be
+/// named in the type of a query.
+pub trait MapItem<I> {
+    /// The type produced by the mapping.
+    type Output;
+
+    /// Transforms the query item.
+    fn map(item: I) -> Self::Output;
+}
+
+/// A [`Query`] which transforms the item of query `Q` using the [`MapItem`]
+/// implementation of `F`.
+///
+/// `Map` dereferences to the transformed value. Since `Map` is itself a query,
+/// it can be used as a field in derived queries to expose domain-specific
+/// views of component data.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::prelude::*;
+/// use evenio::query::{Map, MapItem};
+///
+/// #[derive(Component)]
+/// struct Position(f32, f32);
+///
+/// #[derive(Debug)]
+/// struct Aabb {
+///     min: (f32, f32),
+///     max: (f32, f32),
+/// }
+///
+/// struct ToAabb;
+///
+/// impl MapItem<&Position> for ToAabb {
+///     type Output = Aabb;
+///
+///     fn map(pos: &Position) -> Aabb {
+///         Aabb {
+///             min: (pos.0 - 0.5, pos.1 - 0.5),
+///             max: (pos.0 + 0.5, pos.1 + 0.5),
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, f: Fetcher<Map<&Position, ToAabb>>| {
+///     for aabb in f {
+///         println!("{:?}", *aabb);
+///     }
+/// });
+/// # world.send(E);
+/// ```
+pub struct Map<Q, F: MapItem<Q>> {
+    value: F::Output,
+    _marker: PhantomData<fn() -> (Q, F)>,
+}
+
+impl<Q, F: MapItem<Q>> Map<Q, F> {
+    /// Creates a new instance from a query item by applying `F`.
+    pub fn new(item: Q) -> Self {
+        Self {
+            value: F::map(item),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Extracts the transformed value.
+    pub fn into_inner(self) -> F::Output {
+        self.value
+    }
+}
+
+impl<Q, F> Clone for Map<Q, F>
+where
+    F: MapItem<Q>,
+    F::Output: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Q, F> Copy for Map<Q, F>
+where
+    F: MapItem<Q>,
+    F::Output: Copy,
+{
+}
+
+impl<Q, F> fmt::Debug for Map<Q, F>
+where
+    F: MapItem<Q>,
+    F::Output: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Map").field(&self.value).finish()
+    }
+}
+
+impl<Q, F: MapItem<Q>> Deref for Map<Q, F> {
+    type Target = F::Output;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<Q, F: MapItem<Q>> DerefMut for Map<Q, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+unsafe impl<Q, F> Query for Map<Q, F>
+where
+    Q: Query,
+    F: MapItem<Q> + for<'a> MapItem<Q::This<'a>>,
+{
+    type This<'a> = Map<Q::This<'a>, F>;
+
+    type ArchState = Q::ArchState;
+
+    type State = Q::State;
+
+    fn init(
+        world: &mut World,
+        config: &mut HandlerConfig,
+    ) -> Result<(ComponentAccess, Self::State), InitError> {
+        Q::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        Q::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        Q::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::This<'a> {
+        Map::new(Q::get(state, row))
+    }
+}
+
+unsafe impl<Q, F> ReadOnlyQuery for Map<Q, F>
+where
+    Q: ReadOnlyQuery,
+    F: MapItem<Q> + for<'a> MapItem<Q::This<'a>>,
+{
+}
+
+/// A predicate on query items of type `I`, used by the [`Filter`] query.
+///
+/// This is implemented on a type rather than a closure so that it can be
+/// named in the type of a query.
+pub trait FilterItem<I> {
+    /// Returns `true` if the item should be kept.
+    fn filter(item: &I) -> bool;
+}
+
+/// A [`Query`] which contains the item of query `Q` if the item satisfies the
+/// [`FilterItem`] predicate of `F`, and `None` otherwise.
+///
+/// `Filter` dereferences to an `Option` of the item. Note that queries match
+/// whole archetypes, so entities whose item is rejected are still visited
+/// when iterating. Use [`FilteredFetcher`] to skip them.
+///
+/// [`FilteredFetcher`]: crate::fetch::FilteredFetcher
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::prelude::*;
+/// use evenio::query::{Filter, FilterItem};
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// struct IsDead;
+///
+/// impl FilterItem<&Health> for IsDead {
+///     fn filter(health: &&Health) -> bool {
+///         health.0 <= 0
+///     }
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<E>, f: Fetcher<(EntityId, Filter<&Health, IsDead>)>| {
+///     for (id, _) in f.iter().filter(|(_, dead)| dead.is_some()) {
+///         println!("{id:?} is dead");
+///     }
+/// });
+/// # world.send(E);
+/// ```
+pub struct Filter<Q, F> {
+    item: Option<Q>,
+    _marker: PhantomData<fn() -> F>,
+}
+
+impl<Q, F: FilterItem<Q>> Filter<Q, F> {
+    /// Creates a new instance from a query item by applying the predicate of
+    /// `F`.
+    pub fn new(item: Q) -> Self {
+        Self {
+            item: F::filter(&item).then_some(item),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Q, F> Filter<Q, F> {
+    /// Extracts the item if it satisfied the predicate.
+    pub fn into_inner(self) -> Option<Q> {
+        self.item
+    }
+}
+
+impl<Q: Clone, F> Clone for Filter<Q, F> {
+    fn clone(&self) -> Self {
+        Self {
+            item: self.item.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Q: Copy, F> Copy for Filter<Q, F> {}
+
+impl<Q: fmt::Debug, F> fmt::Debug for Filter<Q, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Filter").field(&self.item).finish()
+    }
+}
+
+impl<Q, F> Deref for Filter<Q, F> {
+    type Target = Option<Q>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.item
+    }
+}
+
+impl<Q, F> DerefMut for Filter<Q, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.item
+    }
+}
+
+unsafe impl<Q, F> Query for Filter<Q, F>
+where
+    Q: Query,
+    F: for<'a> FilterItem<Q::This<'a>>,
+{
+    type This<'a> = Filter<Q::This<'a>, F>;
+
+    type ArchState = Q::ArchState;
+
+    type State = Q::State;
+
+    fn init(
+        world: &mut World,
+        config: &mut HandlerConfig,
+    ) -> Result<(ComponentAccess, Self::State), InitError> {
+        Q::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        Q::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        Q::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::This<'a> {
+        Filter::new(Q::get(state, row))
+    }
+}
+
+unsafe impl<Q, F> ReadOnlyQuery for Filter<Q, F>
+where
+    Q: ReadOnlyQuery,
+    F: for<'a> FilterItem<Q::This<'a>>,
+{
+}
+
 /// Returns the [`EntityId`] of the matched entity.
 unsafe impl Query for EntityId {
     type This<'a> = Self;
@@ -738,6 +1297,146 @@ unsafe impl Query for EntityId {
 
 unsafe impl ReadOnlyQuery for EntityId {}
 
+/// Returns the [`Archetype`] of the matched entity.
+///
+/// This gives access to archetype metadata such as the set of components and
+/// the number of entities, but does not conflict with any component access.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(GlobalEvent)] struct E;
+/// use evenio::archetype::Archetype;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct A;
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, A);
+///
+/// world.add_handler(|_: Receiver<E>, f: Fetcher<(EntityId, &Archetype)>| {
+///     for (id, arch) in f {
+///         println!("{id:?} has {} components", arch.component_indices().len());
+///     }
+/// });
+///
+/// world.send(E);
+/// ```
+unsafe impl Query for &'_ Archetype {
+    type This<'a> = &'a Archetype;
+
+    type ArchState = ArchetypeState;
+
+    type State = NonNull<AtomicPtr<World>>;
+
+    fn init(
+        world: &mut World,
+        _config: &mut HandlerConfig,
+    ) -> Result<(ComponentAccess, Self::State), InitError> {
+        Ok((ComponentAccess::new_true(), world.address()))
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        world.address()
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        Some(ArchetypeState {
+            idx: arch.index(),
+            world: *state,
+        })
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, _row: ArchetypeRow) -> Self::This<'a> {
+        // SAFETY: The address is published before queries are executed, and
+        // archetypes are not added or removed while they are.
+        let world = (*state.world.as_ptr()).load(AtomicOrdering::Relaxed);
+        let world = UnsafeWorldCell::from_address(NonNull::new_unchecked(world));
+
+        world.archetypes().get(state.idx).unwrap_unchecked()
+    }
+}
+
+/// The [`Query::ArchState`] of `&Archetype`.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ArchetypeState {
+    idx: ArchetypeIdx,
+    world: NonNull<AtomicPtr<World>>,
+}
+
+// SAFETY: The world pointer is only used to read the world's address.
+unsafe impl Send for ArchetypeState {}
+unsafe impl Sync for ArchetypeState {}
+
+unsafe impl ReadOnlyQuery for &'_ Archetype {}
+
+/// Returns the [`ArchetypeIdx`] of the archetype containing the matched
+/// entity.
+///
+/// Together with [`ArchetypeRow`], this identifies the location of the entity
+/// in the world until the entity is moved to a different archetype or another
+/// entity is removed from its archetype.
+unsafe impl Query for ArchetypeIdx {
+    type This<'a> = Self;
+
+    type ArchState = ArchetypeIdx;
+
+    type State = ();
+
+    fn init(
+        _world: &mut World,
+        _config: &mut HandlerConfig,
+    ) -> Result<(ComponentAccess, Self::State), InitError> {
+        Ok((ComponentAccess::new_true(), ()))
+    }
+
+    fn new_state(_world: &mut World) -> Self::State {}
+
+    fn new_arch_state(arch: &Archetype, (): &mut Self::State) -> Option<Self::ArchState> {
+        Some(arch.index())
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, _row: ArchetypeRow) -> Self::This<'a> {
+        *state
+    }
+}
+
+unsafe impl ReadOnlyQuery for ArchetypeIdx {}
+
+/// Returns the [`ArchetypeRow`] of the matched entity within its archetype.
+///
+/// See the [`ArchetypeIdx`] query for more information.
+unsafe impl Query for ArchetypeRow {
+    type This<'a> = Self;
+
+    type ArchState = ();
+
+    type State = ();
+
+    fn init(
+        _world: &mut World,
+        _config: &mut HandlerConfig,
+    ) -> Result<(ComponentAccess, Self::State), InitError> {
+        Ok((ComponentAccess::new_true(), ()))
+    }
+
+    fn new_state(_world: &mut World) -> Self::State {}
+
+    fn new_arch_state(_arch: &Archetype, (): &mut Self::State) -> Option<Self::ArchState> {
+        Some(())
+    }
+
+    unsafe fn get<'a>((): &Self::ArchState, row: ArchetypeRow) -> Self::This<'a> {
+        row
+    }
+}
+
+unsafe impl ReadOnlyQuery for ArchetypeRow {}
+
 /// Like `()`, the `PhantomData<T>` query always succeeds.
 unsafe impl<T: ?Sized> Query for PhantomData<T> {
     type This<'a> = Self;
@@ -793,6 +1492,7 @@ unsafe impl<T> Sync for ColumnPtr<T> {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::archetype::Archetypes;
     use crate::prelude::*;
 
     #[derive(GlobalEvent)]
@@ -848,6 +1548,43 @@ mod tests {
     );
     check_access!(t19, true, (&mut A, &A, Not<&A>));
     check_access!(t20, true, (Not<&A>, &mut A, &A));
+    check_access!(t21, true, ((&mut A, With<&B>), (&mut A, Not<&B>)));
+    check_access!(t22, true, ((&mut A, &B), (&mut A, Not<&B>)));
+    check_access!(t23, false, ((&mut A, With<&B>), (&mut A, Has<&B>)));
+    check_access!(
+        t24,
+        true,
+        ((&mut A, Or<With<&B>, With<&C>>), (&mut A, Not<&B>, Not<&C>))
+    );
+    check_access!(t25, false, ((&mut A, With<&B>), (&mut A, Not<&C>)));
+
+    #[test]
+    fn disjoint_handler_params() {
+        #[derive(TargetedEvent)]
+        struct T;
+
+        let mut world = World::new();
+
+        let res = world.try_add_handler(
+            |_: Receiver<E>, _: Fetcher<(&mut A, With<&B>)>, _: Fetcher<(&mut A, Not<&B>)>| {},
+        );
+        assert!(res.is_ok(), "{res:?}");
+
+        let res = world.try_add_handler(
+            |_: Receiver<T, (&mut A, With<&B>)>, _: Fetcher<(&mut A, Not<&B>)>| {},
+        );
+        assert!(res.is_ok(), "{res:?}");
+
+        let res = world.try_add_handler(
+            |_: Receiver<E>, _: Fetcher<(&mut A, With<&B>)>, _: Single<(&mut A, Not<&B>)>| {},
+        );
+        assert!(res.is_ok(), "{res:?}");
+
+        let res = world.try_add_handler(
+            |_: Receiver<E>, _: Fetcher<&mut A>, _: Fetcher<(&mut A, Not<&B>)>| {},
+        );
+        assert!(res.is_err());
+    }
 
     macro_rules! check_matching {
         (
@@ -999,11 +1736,314 @@ mod tests {
         #[derive(Query)]
         struct TupleStructQuery<'a>(&'a A, &'a mut B);
 
+        #[derive(Query)]
+        struct QueryWithConstParam<'a, const N: usize> {
+            foo: &'a A,
+            bar: PhantomData<[u8; N]>,
+        }
+
+        #[derive(Query)]
+        struct QueryWithConstParamDefault<'a, const N: usize = 3> {
+            foo: &'a A,
+            bar: PhantomData<[u8; N]>,
+        }
+
+        #[derive(Query)]
+        struct QueryWithWhereClause<'a, T>
+        where
+            T: Component,
+        {
+            foo: &'a T,
+        }
+
+        #[derive(Query)]
+        struct QueryWithNestedConst<'a, const N: usize> {
+            inner: QueryWithConstParam<'a, N>,
+        }
+
+        #[derive(Query)]
+        #[allow(explicit_outlives_requirements)]
+        struct QueryWithLifetimeBound<'a, T>
+        where
+            T: Component + 'a,
+        {
+            foo: &'a T,
+        }
+
+        #[derive(Query)]
+        struct TupleQueryWithWhereClause<'a, T>(&'a T, EntityId)
+        where
+            T: Component;
+
+        #[derive(Query)]
+        struct UnitQueryWithConstParam<const N: usize>;
+
+        #[derive(Query)]
+        struct QueryWithBounds<'a, T: Component, U = ()>
+        where
+            U: Default,
+        {
+            foo: &'a T,
+            bar: Option<&'a mut A>,
+            baz: PhantomData<U>,
+        }
+
         assert_read_only_query::<UnitQuery>();
         assert_read_only_query::<QueryWithLifetime>();
         assert_read_only_query::<QueryWithTwoLifetimes>();
         assert_read_only_query::<QueryWithTypeParam<()>>();
+        assert_read_only_query::<QueryWithConstParam<5>>();
+        assert_read_only_query::<QueryWithConstParamDefault>();
+        assert_read_only_query::<QueryWithWhereClause<B>>();
+        assert_query::<QueryWithBounds<C>>();
+        assert_read_only_query::<TupleQueryWithWhereClause<A>>();
+        assert_read_only_query::<UnitQueryWithConstParam<1>>();
 
         fn assert_read_only_query<Q: ReadOnlyQuery>() {}
+        fn assert_query<Q: Query>() {}
+    }
+
+    #[test]
+    fn map_and_filter() {
+        #[derive(Component)]
+        struct Value(u32);
+
+        struct Double;
+
+        impl MapItem<&Value> for Double {
+            type Output = u32;
+
+            fn map(item: &Value) -> u32 {
+                item.0 * 2
+            }
+        }
+
+        impl MapItem<&mut Value> for Double {
+            type Output = u32;
+
+            fn map(item: &mut Value) -> u32 {
+                item.0 *= 2;
+                item.0
+            }
+        }
+
+        struct IsEven;
+
+        impl FilterItem<&Value> for IsEven {
+            fn filter(item: &&Value) -> bool {
+                item.0 % 2 == 0
+            }
+        }
+
+        #[derive(Query)]
+        struct View<'a> {
+            doubled: Map<&'a Value, Double>,
+            even: Filter<&'a Value, IsEven>,
+        }
+
+        let mut world = World::new();
+
+        let mut entities = vec![];
+
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, Value(i));
+            entities.push(e);
+        }
+
+        let h = world.add_handler(|_: Receiver<E>, f: Fetcher<View>| {
+            let mut doubled = 0;
+            let mut even = 0;
+
+            for view in f {
+                doubled += *view.doubled;
+                even += view.even.map_or(0, |v| v.0);
+            }
+
+            assert_eq!(doubled, 90);
+            assert_eq!(even, 20);
+        });
+
+        world.send(E);
+        world.remove_handler(h);
+
+        world.add_handler(|_: Receiver<E>, f: Fetcher<Map<&mut Value, Double>>| {
+            assert_eq!(f.into_iter().map(Map::into_inner).sum::<u32>(), 90);
+        });
+
+        world.send(E);
+
+        let total = entities
+            .iter()
+            .map(|&e| world.get::<Value>(e).unwrap().0)
+            .sum::<u32>();
+        assert_eq!(total, 90);
+
+        assert_read_only_query::<View>();
+        assert_read_only_query::<Map<&Value, Double>>();
+
+        fn assert_read_only_query<Q: ReadOnlyQuery>() {}
+    }
+
+    #[test]
+    fn archetype_query() {
+        #[derive(Component)]
+        struct D;
+
+        #[derive(Component)]
+        struct F;
+
+        #[derive(Component)]
+        struct G;
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, A);
+
+        let e2 = world.spawn();
+        world.insert(e2, A);
+        world.insert(e2, B);
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<(EntityId, &Archetype)>| {
+            for (id, arch) in f {
+                assert!(arch.entity_ids().contains(&id));
+
+                if id == e1 {
+                    assert_eq!(arch.component_indices().len(), 1);
+                } else if id == e2 {
+                    assert_eq!(arch.component_indices().len(), 2);
+                }
+            }
+        });
+
+        // Create lots of new archetypes, which may reallocate the archetype
+        // storage.
+        for mask in 0..64_u32 {
+            let e = world.spawn();
+
+            if mask & 1 != 0 {
+                world.insert(e, A);
+            }
+            if mask & 2 != 0 {
+                world.insert(e, B);
+            }
+            if mask & 4 != 0 {
+                world.insert(e, C);
+            }
+            if mask & 8 != 0 {
+                world.insert(e, D);
+            }
+            if mask & 16 != 0 {
+                world.insert(e, F);
+            }
+            if mask & 32 != 0 {
+                world.insert(e, G);
+            }
+
+            world.send(E);
+        }
+    }
+
+    #[test]
+    fn related() {
+        #[derive(Component)]
+        struct ChildOf(EntityId);
+
+        impl AsRef<EntityId> for ChildOf {
+            fn as_ref(&self) -> &EntityId {
+                &self.0
+            }
+        }
+
+        let mut world = World::new();
+
+        let parent = world.spawn();
+        let c1 = world.spawn();
+        let c2 = world.spawn();
+        world.insert(c1, ChildOf(parent));
+        world.insert(c2, ChildOf(parent));
+        world.insert(c2, A);
+
+        let h = world.add_handler(
+            move |_: Receiver<E>,
+                  f: Fetcher<(EntityId, Related<ChildOf>)>,
+                  mut g: Fetcher<(&mut A, WithRelation<ChildOf>)>| {
+                assert_eq!(f.get(parent), Err(GetError::QueryDoesNotMatch));
+                assert_eq!(f.get(c1), Ok((c1, Related::new(parent))));
+                assert_eq!(f.iter().count(), 2);
+
+                assert_eq!(g.iter_mut().count(), 1);
+            },
+        );
+
+        world.send(E);
+        world.remove_handler(h);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Name(&'static str);
+
+        world.insert(parent, Name("parent"));
+        world.insert(c1, Name("c1"));
+
+        let h1 = world.add_handler(
+            move |_: Receiver<E>, f: Fetcher<(&Name, Related<ChildOf, &Name>)>| {
+                let (name, related) = f.get(c1).unwrap();
+                assert_eq!(name, &Name("c1"));
+                assert_eq!(related.get(), parent);
+                assert_eq!(related.item(), Some(&&Name("parent")));
+                assert_eq!(f.iter().count(), 1);
+            },
+        );
+
+        let h2 = world.add_handler(move |_: Receiver<E>, f: Fetcher<Related<ChildOf, &Name>>| {
+            assert_eq!(f.get(c2).unwrap().item(), Some(&&Name("parent")));
+        });
+
+        world.send(E);
+        world.remove_handler(h1);
+        world.remove_handler(h2);
+
+        world.despawn(parent);
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<Related<ChildOf, &Name>>| {
+            assert_eq!(f.get(c1).unwrap().into_item(), None);
+        });
+
+        world.send(E);
+
+        assert!(world
+            .try_add_handler(|_: Receiver<E>, _: Fetcher<(&mut Name, Related<ChildOf, &Name>)>| {})
+            .is_err());
+        assert!(world
+            .try_add_handler(
+                |_: Receiver<E>, _: Fetcher<&mut Name>, _: Fetcher<Related<ChildOf, &Name>>| {}
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn archetype_location_query() {
+        let mut world = World::new();
+
+        for i in 0..6 {
+            let e = world.spawn();
+            world.insert(e, A);
+
+            if i % 2 == 0 {
+                world.insert(e, B);
+            }
+        }
+
+        world.add_handler(
+            |_: Receiver<E>, f: Fetcher<(EntityId, ArchetypeIdx, ArchetypeRow)>, a: &Archetypes| {
+                for (id, idx, row) in f {
+                    let arch = a.get(idx).unwrap();
+                    assert_eq!(arch.entity_ids()[row.0 as usize], id);
+                }
+            },
+        );
+
+        world.send(E);
     }
 }
@@ -0,0 +1,281 @@
+//! Deterministic random numbers for handlers.
+//!
+//! The [`Rng`] handler param draws pseudo-random numbers from a stream owned
+//! by the handler. Every stream is derived from the world's seed, set with
+//! [`World::set_rng_seed`], and from the order in which the handlers using
+//! `Rng` were added. Two worlds with the same seed and the same handlers,
+//! added in the same order, therefore draw the same numbers, which is what
+//! deterministic simulations such as lockstep games need.
+//!
+//! Since every handler has its own stream, the numbers drawn by one handler
+//! don't depend on how often other handlers run.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::prelude::*;
+//! use evenio::rng::Rng;
+//!
+//! #[derive(GlobalEvent)]
+//! struct Roll;
+//!
+//! let mut world = World::new();
+//!
+//! world.set_rng_seed(42);
+//!
+//! world.add_handler(|_: Receiver<Roll>, mut rng: Rng| {
+//!     println!("rolled a {}", rng.below(6) + 1);
+//! });
+//!
+//! world.send(Roll); // Prints the same number for every world seeded with 42.
+//! ```
+//!
+//! [`World::set_rng_seed`]: crate::world::World::set_rng_seed
+
+use crate::archetype::Archetype;
+use crate::entity::EntityLocation;
+use crate::event::EventPtr;
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::world::{UnsafeWorldCell, World};
+
+/// [`HandlerParam`] for drawing deterministic pseudo-random numbers. See the
+/// [module documentation](self).
+///
+/// Every `Rng` param has its own stream, including several `Rng` params of
+/// the same handler. The stream is restarted when the world is reseeded.
+///
+/// The numbers are not suitable for cryptography.
+#[derive(Debug)]
+pub struct Rng<'a> {
+    state: &'a mut u64,
+}
+
+impl Rng<'_> {
+    /// Returns a random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        *self.state = self.state.wrapping_add(GAMMA);
+        mix(*self.state)
+    }
+
+    /// Returns a random `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a random `f64` in the range `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1_u64 << 53) as f64)
+    }
+
+    /// Returns a random `u64` in the range `[0, bound)`, without bias.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    #[track_caller]
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound != 0, "bound must not be zero");
+
+        // Lemire's method: take the high half of a 128-bit product, and
+        // reject the few low halves which would make the result biased.
+        let threshold = bound.wrapping_neg() % bound;
+
+        loop {
+            let product = u128::from(self.next_u64()) * u128::from(bound);
+
+            if product as u64 >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+
+    /// Returns `true` with probability `p`. Always returns `false` if `p` is
+    /// at most 0, and `true` if it is at least 1.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    /// Fills `dest` with random bytes.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
+impl rand_core::RngCore for Rng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        Rng::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Rng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        Rng::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        Rng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
+/// Internal state for an [`Rng`].
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct RngState {
+    /// The index of the stream, in the order the params were initialized.
+    stream: u64,
+    /// The reseed count of the world when `state` was derived.
+    epoch: u64,
+    state: u64,
+}
+
+unsafe impl HandlerParam for Rng<'_> {
+    type State = RngState;
+
+    type This<'a> = Rng<'a>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        let seeds = world.rng_seeds_mut();
+
+        let stream = seeds.next_stream;
+        seeds.next_stream += 1;
+
+        Ok(RngState {
+            stream,
+            epoch: seeds.epoch,
+            state: seeds.stream_start(stream),
+        })
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        let seeds = world.world().rng_seeds();
+
+        if state.epoch != seeds.epoch {
+            state.epoch = seeds.epoch;
+            state.state = seeds.stream_start(state.stream);
+        }
+
+        Rng {
+            state: &mut state.state,
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+/// The seed of a world's [`Rng`] streams.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RngSeeds {
+    pub(crate) seed: u64,
+    /// Incremented every time the world is reseeded.
+    pub(crate) epoch: u64,
+    /// The stream of the next initialized [`Rng`] param.
+    pub(crate) next_stream: u64,
+}
+
+impl RngSeeds {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the seed and restarts all streams.
+    pub(crate) fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.epoch += 1;
+    }
+
+    /// Returns the initial state of the given stream.
+    fn stream_start(&self, stream: u64) -> u64 {
+        mix(self.seed ^ mix(stream.wrapping_add(1).wrapping_mul(GAMMA)))
+    }
+}
+
+/// The increment of `SplitMix64`.
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The `SplitMix64` finalizer.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(GlobalEvent)]
+    struct Draw;
+
+    /// Returns the numbers drawn by the two `Rng`s of each of two handlers in
+    /// a world with `seed`.
+    fn draws(seed: u64) -> Vec<[u64; 2]> {
+        let mut world = World::new();
+        world.set_rng_seed(seed);
+
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<Draw>, mut a: Rng, mut b: Rng| {
+            l.borrow_mut().push([a.below(1000), b.below(1000)])
+        });
+
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<Draw>, mut rng: Rng| {
+            l.borrow_mut().push([rng.next_u64(), rng.next_u64()])
+        });
+
+        world.send(Draw);
+        world.send(Draw);
+
+        let draws = log.borrow().clone();
+        draws
+    }
+
+    #[test]
+    fn rng_streams() {
+        let a = draws(1);
+        assert_eq!(a, draws(1));
+        assert_ne!(a, draws(2));
+
+        // Every param has its own stream.
+        assert_ne!(a[0][0], a[0][1]);
+        assert_ne!(a[0], a[2]);
+
+        let mut world = World::new();
+
+        let log = Rc::new(RefCell::new(vec![]));
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<Draw>, mut rng: Rng| {
+            l.borrow_mut().push(rng.next_u64())
+        });
+
+        world.send(Draw);
+        world.set_rng_seed(world.rng_seed());
+        world.send(Draw);
+
+        // Reseeding restarts the stream.
+        assert_eq!(log.borrow()[0], log.borrow()[1]);
+    }
+}
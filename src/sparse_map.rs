@@ -35,6 +35,15 @@ impl<K: SparseIndex, V> SparseMap<K, V> {
         }
     }
 
+    /// Returns the position of `key` in [`keys`](Self::keys) and
+    /// [`values`](Self::values).
+    #[inline]
+    pub(crate) fn position(&self, key: K) -> Option<usize> {
+        let idx = self.sparse.get(key.index())?.index();
+
+        (idx < K::MAX.index()).then_some(idx)
+    }
+
     #[inline]
     pub(crate) fn contains_key(&self, key: K) -> bool {
         self.get(key).is_some()
@@ -111,6 +120,16 @@ impl<K: SparseIndex, V> SparseMap<K, V> {
         }
     }
 
+    pub(crate) fn clear(&mut self) {
+        for key in &self.indices {
+            // SAFETY: Every key in `indices` has an entry in `sparse`.
+            *unsafe { self.sparse.get_unchecked_mut(key.index()) } = K::MAX;
+        }
+
+        self.dense.clear();
+        self.indices.clear();
+    }
+
     pub(crate) fn keys(&self) -> &[K] {
         &self.indices
     }
@@ -0,0 +1,304 @@
+//! Async tasks whose results are sent as events.
+//!
+//! Handlers spawn futures with the [`Spawner`] handler parameter, and outside
+//! of handlers with [`World::spawn_task`]. The futures are owned and polled
+//! by the world. When a future completes, its output is sent to the world as
+//! an event.
+//!
+//! Tasks are polled at the end of every flush of the event queue, and by
+//! [`World::poll_tasks`]. Only tasks which were woken by their [`Waker`]
+//! since they were last polled are polled again, so futures driven by an
+//! external runtime, such as the receiving end of a channel, must wake the
+//! task and then have [`World::poll_tasks`] called.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::prelude::*;
+//! use evenio::task::Spawner;
+//!
+//! #[derive(GlobalEvent)]
+//! struct LoadAsset(&'static str);
+//!
+//! #[derive(GlobalEvent)]
+//! struct AssetLoaded(&'static str, usize);
+//!
+//! let mut world = World::new();
+//!
+//! world.add_handler(|r: Receiver<LoadAsset>, spawner: Spawner| {
+//!     let path = r.event.0;
+//!     spawner.spawn(async move { AssetLoaded(path, path.len()) });
+//! });
+//!
+//! world.add_handler(|r: Receiver<AssetLoaded>| {
+//!     println!("loaded {} ({} bytes)", r.event.0, r.event.1)
+//! });
+//!
+//! world.send(LoadAsset("player.png")); // Prints "loaded player.png (10 bytes)".
+//! ```
+//!
+//! [`World::spawn_task`]: crate::world::World::spawn_task
+//! [`World::poll_tasks`]: crate::world::World::poll_tasks
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use crate::archetype::Archetype;
+use crate::entity::{EntityId, EntityLocation};
+use crate::event::{EventPtr, GlobalEvent, TargetedEvent};
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::world::{UnsafeWorldCell, World};
+
+/// Sends the output of a completed task to the world.
+pub(crate) type Completion = Box<dyn FnOnce(&mut World)>;
+
+/// [`HandlerParam`] for spawning tasks whose output is sent as an event when
+/// they complete. See the [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct Spawner<'a> {
+    world: UnsafeWorldCell<'a>,
+}
+
+impl Spawner<'_> {
+    /// Spawns a task which sends the output of `future` as a global event when
+    /// it completes.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future + 'static,
+        F::Output: GlobalEvent + 'static,
+    {
+        unsafe { self.world.spawn_task(completion(future)) }
+    }
+
+    /// Spawns a task which sends the output of `future` as a targeted event to
+    /// `target` when it completes.
+    pub fn spawn_to<F>(&self, target: EntityId, future: F)
+    where
+        F: Future + 'static,
+        F::Output: TargetedEvent + 'static,
+    {
+        unsafe { self.world.spawn_task(completion_to(target, future)) }
+    }
+}
+
+unsafe impl HandlerParam for Spawner<'_> {
+    type State = ();
+
+    type This<'a> = Spawner<'a>;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        config.set_thread_local();
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        Spawner { world }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+/// Wraps `future` into a task future which sends its output as a global
+/// event.
+pub(crate) async fn completion<F>(future: F) -> Completion
+where
+    F: Future + 'static,
+    F::Output: GlobalEvent + 'static,
+{
+    let event = future.await;
+    Box::new(move |world: &mut World| world.send(event))
+}
+
+/// Wraps `future` into a task future which sends its output as a targeted
+/// event to `target`.
+pub(crate) async fn completion_to<F>(target: EntityId, future: F) -> Completion
+where
+    F: Future + 'static,
+    F::Output: TargetedEvent + 'static,
+{
+    let event = future.await;
+    Box::new(move |world: &mut World| world.send_to(target, event))
+}
+
+/// The tasks owned by a world.
+#[derive(Default)]
+pub(crate) struct Tasks {
+    tasks: Vec<Task>,
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = Completion>>>,
+    woken: Arc<Woken>,
+}
+
+/// Set when a task is woken.
+struct Woken(AtomicBool);
+
+impl Wake for Woken {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::Release)
+    }
+}
+
+impl Tasks {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Adds a task, which is polled the next time tasks are polled.
+    pub(crate) fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = Completion> + 'static,
+    {
+        self.tasks.push(Task {
+            future: Box::pin(future),
+            woken: Arc::new(Woken(AtomicBool::new(true))),
+        });
+    }
+
+    /// Polls the woken tasks once, removes the completed ones and returns
+    /// their completions in the order the tasks were spawned.
+    pub(crate) fn poll(&mut self) -> Vec<Completion> {
+        let mut completions = vec![];
+
+        self.tasks.retain_mut(|task| {
+            if !task.woken.0.swap(false, Ordering::Acquire) {
+                return true;
+            }
+
+            let waker = Waker::from(task.woken.clone());
+            let mut cx = Context::from_waker(&waker);
+
+            match task.future.as_mut().poll(&mut cx) {
+                Poll::Ready(completion) => {
+                    completions.push(completion);
+                    false
+                }
+                Poll::Pending => true,
+            }
+        });
+
+        completions
+    }
+}
+
+impl fmt::Debug for Tasks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tasks")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::prelude::*;
+
+    /// A future which completes once a value is set through the shared cell.
+    struct Slot(Rc<RefCell<(Option<u32>, Option<Waker>)>>);
+
+    impl Future for Slot {
+        type Output = Loaded;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Loaded> {
+            let mut slot = self.0.borrow_mut();
+
+            match slot.0.take() {
+                Some(value) => Poll::Ready(Loaded(value)),
+                None => {
+                    slot.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[derive(GlobalEvent)]
+    struct Load;
+
+    #[derive(GlobalEvent)]
+    struct Loaded(u32);
+
+    #[derive(TargetedEvent)]
+    struct Ping(u32);
+
+    #[derive(Component)]
+    struct Log(Vec<u32>);
+
+    #[test]
+    fn tasks() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        let slot = Rc::new(RefCell::new((None, None)));
+        let s = slot.clone();
+
+        world.add_handler(move |_: Receiver<Load>, spawner: Spawner| {
+            spawner.spawn(async { Loaded(1) });
+            spawner.spawn(Slot(s.clone()));
+        });
+
+        world.add_handler(|r: Receiver<Loaded>, mut log: Single<&mut Log>| log.0.push(r.event.0));
+
+        world.add_handler(|r: Receiver<Ping, &mut Log>| r.query.0.push(r.event.0));
+
+        // Ready futures complete in the same flush.
+        world.send(Load);
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1]);
+        assert_eq!(world.task_count(), 1);
+
+        // Tasks which weren't woken aren't polled.
+        world.poll_tasks();
+        assert_eq!(world.task_count(), 1);
+
+        let waker = {
+            let mut slot = slot.borrow_mut();
+            slot.0 = Some(2);
+            slot.1.take().unwrap()
+        };
+        waker.wake();
+
+        world.poll_tasks();
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 2]);
+        assert_eq!(world.task_count(), 0);
+
+        world.spawn_task_to(e, async { Ping(3) });
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 2, 3]);
+    }
+}
@@ -0,0 +1,463 @@
+//! Fixed-timestep updates driven by [`World::advance_time`].
+//!
+//! Games usually run their simulation at a fixed rate, independently of the
+//! rate at which frames are rendered. Calling [`World::advance_time`] once per
+//! frame with the frame's duration sends one [`Update`] event per call, and
+//! as many [`FixedUpdate`] events as whole timesteps have elapsed.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use evenio::prelude::*;
+//! use evenio::time::{FixedUpdate, Update};
+//!
+//! let mut world = World::new();
+//!
+//! world.set_fixed_timestep(Duration::from_millis(20));
+//!
+//! world.add_handler(|_: Receiver<FixedUpdate>| println!("physics"));
+//! world.add_handler(|r: Receiver<Update>| println!("render after {:?}", r.event.delta));
+//!
+//! // Prints "physics" twice, then "render after 45ms".
+//! world.advance_time(Duration::from_millis(45));
+//! ```
+//!
+//! The [`Time`] of the world can be read in handlers with `&Time`. [`Timer`]
+//! and [`Stopwatch`] components are advanced by the same calls, and a
+//! [`TimerFinished`] event is sent to a timer's entity when it finishes.
+//!
+//! [`World::advance_time`]: crate::world::World::advance_time
+
+use core::time::Duration;
+
+use crate::archetype::Archetype;
+use crate::component::Component;
+use crate::entity::EntityLocation;
+use crate::event::{EventPtr, GlobalEvent, TargetedEvent};
+use crate::handler::{HandlerConfig, HandlerInfo, HandlerParam, InitError};
+use crate::world::{UnsafeWorldCell, World};
+
+/// A [`GlobalEvent`] sent once per call to [`World::advance_time`], after the
+/// [`FixedUpdate`] events of the call.
+///
+/// [`World::advance_time`]: crate::world::World::advance_time
+#[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Update {
+    /// The time passed to [`World::advance_time`].
+    ///
+    /// [`World::advance_time`]: crate::world::World::advance_time
+    pub delta: Duration,
+}
+
+/// A [`GlobalEvent`] sent by [`World::advance_time`] once for every fixed
+/// timestep which elapsed.
+///
+/// [`World::advance_time`]: crate::world::World::advance_time
+#[derive(GlobalEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct FixedUpdate {
+    /// The fixed timestep of the world. See [`World::set_fixed_timestep`].
+    ///
+    /// [`World::set_fixed_timestep`]: crate::world::World::set_fixed_timestep
+    pub delta: Duration,
+}
+
+/// The time of a [`World`], advanced by [`World::advance_time`].
+///
+/// Obtained from [`World::time`] or with the `&Time` [`HandlerParam`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use evenio::prelude::*;
+/// use evenio::time::{Time, Update};
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<Update>, time: &Time| {
+///     println!("{:?} since start", time.elapsed());
+/// });
+///
+/// world.advance_time(Duration::from_millis(16));
+/// world.advance_time(Duration::from_millis(17));
+///
+/// assert_eq!(world.time().elapsed(), Duration::from_millis(33));
+/// ```
+///
+/// [`World::advance_time`]: crate::world::World::advance_time
+/// [`World::time`]: crate::world::World::time
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    fixed_elapsed: Duration,
+}
+
+impl Time {
+    /// Returns the time passed to the most recent call to
+    /// [`World::advance_time`].
+    ///
+    /// [`World::advance_time`]: crate::world::World::advance_time
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the total time passed to [`World::advance_time`].
+    ///
+    /// [`World::advance_time`]: crate::world::World::advance_time
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the total time of all fixed timesteps run so far, including
+    /// the one currently running.
+    pub fn fixed_elapsed(&self) -> Duration {
+        self.fixed_elapsed
+    }
+}
+
+unsafe impl HandlerParam for &'_ Time {
+    type State = ();
+
+    type This<'a> = &'a Time;
+
+    const REFRESHES_ARCHETYPES: bool = false;
+
+    fn init(_world: &mut World, _config: &mut HandlerConfig) -> Result<Self::State, InitError> {
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::This<'a> {
+        world.world().time()
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+/// Whether a [`Timer`] runs once or restarts when it finishes.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum TimerMode {
+    /// The timer finishes once and stays finished until it is reset.
+    #[default]
+    Once,
+    /// The timer restarts every time it finishes.
+    Repeating,
+}
+
+/// A [`Component`] which counts down a duration as the world's time advances.
+///
+/// When the timer finishes, a [`TimerFinished`] event is sent to its entity.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use evenio::prelude::*;
+/// use evenio::time::{Timer, TimerFinished};
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<TimerFinished, EntityId>| {
+///     println!("{:?}'s cooldown is over", r.query);
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Timer::once(Duration::from_secs(2)));
+///
+/// world.advance_time(Duration::from_secs(1));
+/// world.advance_time(Duration::from_secs(1)); // Prints the message.
+/// ```
+#[derive(Component, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    paused: bool,
+    /// The number of times the timer finished during the last tick.
+    times_finished: u32,
+}
+
+impl Timer {
+    /// Creates a new timer which finishes after `duration`.
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            mode,
+            paused: false,
+            times_finished: 0,
+        }
+    }
+
+    /// Creates a new timer which finishes once after `duration`.
+    pub fn once(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    /// Creates a new timer which finishes every `duration`.
+    pub fn repeating(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Repeating)
+    }
+
+    /// Returns the duration of the timer.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the [`TimerMode`] of the timer.
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    /// Returns the time elapsed since the timer was started or last
+    /// restarted.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the time left until the timer finishes.
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    /// Returns `true` if the timer finished. A repeating timer is only
+    /// finished if it finished during the last time advance.
+    pub fn is_finished(&self) -> bool {
+        match self.mode {
+            TimerMode::Once => self.elapsed >= self.duration,
+            TimerMode::Repeating => self.times_finished > 0,
+        }
+    }
+
+    /// Returns `true` if the timer is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stops the timer from advancing.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Lets the timer advance after a call to [`pause`](Self::pause).
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    /// Restarts the timer from zero.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.times_finished = 0;
+    }
+
+    /// Advances the timer by `delta` and returns the number of times it
+    /// finished.
+    pub(crate) fn tick(&mut self, delta: Duration) -> u32 {
+        self.times_finished = 0;
+
+        if self.paused || (self.mode == TimerMode::Once && self.elapsed >= self.duration) {
+            return 0;
+        }
+
+        self.elapsed = self.elapsed.saturating_add(delta);
+
+        if self.elapsed < self.duration {
+            return 0;
+        }
+
+        self.times_finished = match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                1
+            }
+            TimerMode::Repeating if self.duration.is_zero() => {
+                self.elapsed = Duration::ZERO;
+                1
+            }
+            TimerMode::Repeating => {
+                let nanos = self.duration.as_nanos();
+                let times = self.elapsed.as_nanos() / nanos;
+                // The remainder is less than the duration, which fits in a
+                // `u64`.
+                self.elapsed = Duration::from_nanos((self.elapsed.as_nanos() % nanos) as u64);
+                u32::try_from(times).unwrap_or(u32::MAX)
+            }
+        };
+
+        self.times_finished
+    }
+}
+
+/// A [`TargetedEvent`] sent to the entity of a [`Timer`] when it finishes.
+#[derive(TargetedEvent, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TimerFinished {
+    /// The number of times the timer finished during the time advance. This
+    /// is greater than one if a repeating timer finished more than once.
+    pub times: u32,
+}
+
+/// A [`Component`] which measures the time elapsed as the world's time
+/// advances.
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    paused: bool,
+}
+
+impl Stopwatch {
+    /// Creates a new stopwatch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the time measured by the stopwatch.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns `true` if the stopwatch is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stops the stopwatch from measuring time.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Lets the stopwatch measure time after a call to
+    /// [`pause`](Self::pause).
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    /// Sets the measured time to zero.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub(crate) fn tick(&mut self, delta: Duration) {
+        if !self.paused {
+            self.elapsed = self.elapsed.saturating_add(delta);
+        }
+    }
+}
+
+/// Accumulates the time passed to [`World::advance_time`] and converts it to
+/// fixed timesteps.
+///
+/// [`World::advance_time`]: crate::world::World::advance_time
+#[derive(Clone, Debug)]
+pub(crate) struct Clock {
+    pub(crate) timestep: Duration,
+    pub(crate) max_steps: u32,
+    /// Time which has not been consumed by a fixed timestep yet.
+    pub(crate) accumulator: Duration,
+    pub(crate) time: Time,
+}
+
+impl Clock {
+    /// 60 updates per second.
+    pub(crate) const DEFAULT_TIMESTEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    pub(crate) const DEFAULT_MAX_STEPS: u32 = 8;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            timestep: Self::DEFAULT_TIMESTEP,
+            max_steps: Self::DEFAULT_MAX_STEPS,
+            accumulator: Duration::ZERO,
+            time: Time::default(),
+        }
+    }
+
+    /// Adds `delta` to the accumulator and returns the number of fixed
+    /// timesteps to run.
+    ///
+    /// At most `max_steps` timesteps are run. The whole timesteps beyond that
+    /// are discarded, so a slow update doesn't cause ever more timesteps to
+    /// run in the following updates.
+    pub(crate) fn accumulate(&mut self, delta: Duration) -> u32 {
+        self.time.delta = delta;
+        self.time.elapsed = self.time.elapsed.saturating_add(delta);
+
+        self.accumulator = self.accumulator.saturating_add(delta);
+
+        let steps = self.accumulator.as_nanos() / self.timestep.as_nanos();
+
+        if steps > u128::from(self.max_steps) {
+            let rem = self.accumulator.as_nanos() % self.timestep.as_nanos();
+            // The remainder is less than the timestep, which fits in a `u64`.
+            self.accumulator = Duration::from_nanos(rem as u64);
+            return self.max_steps;
+        }
+
+        let steps = steps as u32;
+        self.accumulator -= self.timestep * steps;
+        steps
+    }
+
+    /// Records that a fixed timestep is about to run.
+    pub(crate) fn step(&mut self) {
+        self.time.fixed_elapsed = self.time.fixed_elapsed.saturating_add(self.timestep);
+    }
+
+    /// The fraction of a timestep left in the accumulator.
+    pub(crate) fn overstep(&self) -> f64 {
+        self.accumulator.as_secs_f64() / self.timestep.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Clock, Timer};
+
+    #[test]
+    fn accumulate() {
+        let mut clock = Clock::new();
+        clock.timestep = Duration::from_millis(10);
+        clock.max_steps = 3;
+
+        assert_eq!(clock.accumulate(Duration::from_millis(5)), 0);
+        assert_eq!(clock.accumulate(Duration::from_millis(5)), 1);
+        assert_eq!(clock.accumulator, Duration::ZERO);
+
+        assert_eq!(clock.accumulate(Duration::from_millis(25)), 2);
+        assert_eq!(clock.overstep(), 0.5);
+
+        // Spiral of death: the backlog is discarded.
+        assert_eq!(clock.accumulate(Duration::from_secs(10)), 3);
+        assert_eq!(clock.accumulator, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn timer_tick() {
+        let mut once = Timer::once(Duration::from_millis(10));
+        assert_eq!(once.tick(Duration::from_millis(4)), 0);
+        assert_eq!(once.tick(Duration::from_millis(30)), 1);
+        assert!(once.is_finished());
+        assert_eq!(once.tick(Duration::from_millis(30)), 0);
+        assert!(once.is_finished());
+
+        let mut repeating = Timer::repeating(Duration::from_millis(10));
+        assert_eq!(repeating.tick(Duration::from_millis(25)), 2);
+        assert_eq!(repeating.elapsed(), Duration::from_millis(5));
+        assert!(repeating.is_finished());
+        assert_eq!(repeating.tick(Duration::from_millis(1)), 0);
+        assert!(!repeating.is_finished());
+    }
+}
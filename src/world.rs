@@ -1,35 +1,66 @@
 //! Defines the [`World`] and related APIs.
 
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::rc::{Rc, Weak};
 #[cfg(not(feature = "std"))]
-use alloc::{format, string::String, vec, vec::Vec};
+use alloc::{format, vec, vec::Vec};
 use core::alloc::Layout;
 use core::any::{self, TypeId};
-use core::fmt::Write;
+use core::cell::RefCell;
+use core::cmp::Reverse;
+use core::fmt::{self, Write};
+use core::future::Future;
+use core::hash::Hash;
 use core::marker::PhantomData;
 use core::mem;
-use core::ptr::NonNull;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use core::time::Duration;
 
 use bumpalo::Bump;
 
-use crate::access::ComponentAccess;
-use crate::archetype::Archetypes;
+use crate::access::{Access, ComponentAccess};
+use crate::archetype::{Archetype, ArchetypeIdx, ArchetypeRow, Archetypes};
 use crate::component::{
-    AddComponent, Component, ComponentDescriptor, ComponentId, ComponentInfo, Components,
-    RemoveComponent,
+    AddComponent, Component, ComponentDescriptor, ComponentId, ComponentIdx, ComponentInfo,
+    Components, Ptr, PtrMut, RemoveComponent,
 };
 use crate::drop::{drop_fn_of, DropFn};
 use crate::entity::{Entities, EntityId, EntityLocation, ReservedEntities};
+#[cfg(feature = "metrics")]
+use crate::event::EventMetrics;
+#[cfg(feature = "std")]
+use crate::event::EventQueue;
 use crate::event::{
-    AddGlobalEvent, AddTargetedEvent, Despawn, EventDescriptor, EventKind, EventMeta, EventPtr,
-    EventQueueItem, GlobalEvent, GlobalEventId, GlobalEventIdx, GlobalEventInfo, GlobalEvents,
-    Insert, Remove, RemoveGlobalEvent, RemoveTargetedEvent, Spawn, TargetedEvent, TargetedEventId,
-    TargetedEventIdx, TargetedEventInfo, TargetedEvents,
+    AddGlobalEvent, AddTargetedEvent, BoxedEvent, BoxedPayload, Bridge, BroadcastEnded, Bundle,
+    BundleInfo, Categories, CategoryEvent, CategoryFn, CategoryHandler, ComponentPtrsFn,
+    DeadLetter, DeadTargetPolicy, Deduplicators, DepthLimitExceeded, Despawn, DespawnReason, Event,
+    EventDescriptor, EventId, EventKind, EventLog, EventMeta, EventMut, EventPtr, EventQueueItem,
+    EventSet, GlobalEvent, GlobalEventId, GlobalEventIdx, GlobalEventInfo, GlobalEvents, Insert,
+    InsertBundle, InterceptedEvent, Interceptor, Interceptors, Ownership, Priority, RateLimit,
+    RateLimiters, Receiver, ReceiverMut, Recorder, Remove, RemoveGlobalEvent, RemoveTargetedEvent,
+    Request, RequestError, ResponseSlot, Sender, Spawn, SpawnWith, Taken, TargetedEvent,
+    TargetedEventId, TargetedEventIdx, TargetedEventInfo, TargetedEvents, Verdict,
 };
+use crate::fetch::{FetcherState, QueryIter};
 use crate::handler::{
-    AddHandler, Handler, HandlerConfig, HandlerId, HandlerInfo, HandlerInfoInner, HandlerList,
-    Handlers, IntoHandler, MaybeInvalidAccess, ReceivedEventId, RemoveHandler,
+    AccessConflict, AddHandler, DeferredFlushes, Flush, HandleEvents, Handler, HandlerBuilder,
+    HandlerConfig, HandlerDescription, HandlerGraph, HandlerId, HandlerInfo, HandlerInfoInner,
+    HandlerList, HandlerParamFunction, Handlers, InitError, IntoHandler, MaybeInvalidAccess, Once,
+    ReceivedEventId, RemoveHandler, WithState,
 };
+#[cfg(feature = "std")]
+use crate::handler::{HandlerBudget, HandlerOverBudget, HandlerPanicked, Watchdog};
+#[cfg(feature = "rayon")]
+use crate::handler::{HandlerInfoPtr, Parallel};
+use crate::map::TypeIdMap;
 use crate::mutability::{Mutability, Mutable};
+use crate::query::{Query, ReadOnlyQuery};
+use crate::rng::RngSeeds;
+use crate::task::{completion, completion_to, Completion, Tasks};
+use crate::time::{Clock, FixedUpdate, Stopwatch, Time, Timer, TimerFinished, Update};
 
 /// A container for all data in the ECS. This includes entities, components,
 /// handlers, and events.
@@ -43,7 +74,81 @@ pub struct World {
     global_events: GlobalEvents,
     targeted_events: TargetedEvents,
     event_queue: Vec<EventQueueItem>,
+    /// Events which are sent once `event_queue` is empty.
+    deferred_queue: DeferredQueue,
+    /// The depth of the event currently being broadcast.
+    event_depth: u32,
+    max_event_depth: Option<u32>,
+    /// Whether panics of handlers are caught. See
+    /// [`World::set_catch_panics`].
+    catch_panics: bool,
+    /// Whether handlers of lifecycle events are skipped. See
+    /// [`World::without_lifecycle_events`].
+    lifecycle_events_suppressed: bool,
+    /// Handler labels whose handlers don't run. See
+    /// [`World::set_group_enabled`].
+    disabled_groups: Vec<Cow<'static, str>>,
+    /// Time budgets of handler labels. See [`World::set_group_budget`].
+    #[cfg(feature = "std")]
+    group_budgets: Vec<(Cow<'static, str>, HandlerBudget)>,
+    /// Incremented by every call to [`World::advance`] and
+    /// [`World::advance_time`]. Handlers which exceeded their budget are
+    /// skipped during the next frame.
+    #[cfg(feature = "std")]
+    frame: u64,
+    /// The number of events pushed by the handlers of the transaction being
+    /// broadcast. See [`Sender::send_all`].
+    transaction_deferred: usize,
+    schedule: Schedule,
+    tasks: Tasks,
+    /// Flushes of [`Deferred`] handler params, run once the event queue has
+    /// been flushed.
+    ///
+    /// [`Deferred`]: crate::handler::Deferred
+    deferred_flushes: DeferredFlushes,
+    clock: Clock,
+    rng_seeds: RngSeeds,
+    recorder: Recorder,
+    categories: Categories,
+    interceptors: Interceptors,
+    dedup: Deduplicators,
+    rate_limits: RateLimiters,
+    /// Handlers which are removed when the entity they're keyed by is
+    /// despawned. See [`World::add_owned_handler`].
+    handler_owners: BTreeMap<EntityId, Vec<HandlerId>>,
+    /// Handlers which are removed at the end of the flush, because their
+    /// owner was despawned or because they only run once.
+    expired_handlers: Vec<HandlerId>,
+    /// Entities reserved by `spawn_with` whose [`SpawnWith`] event hasn't
+    /// spawned them yet. Those left at the end of the flush are released.
+    pending_spawns: BTreeSet<EntityId>,
+    /// Information about the [`Bundle`]s of the [`InsertBundle`] and
+    /// [`SpawnWith`] events in this world, keyed by the type ID of the event.
+    bundles: TypeIdMap<BundleInfo>,
+    /// Arena for the events sent during a broadcast and the data allocated by
+    /// [`Sender`]s. Reset at the end of every flush, so the memory is reused
+    /// by the next broadcast instead of going through the global allocator.
+    ///
+    /// [`Sender`]: crate::event::Sender
     bump: Bump,
+    /// Fetcher states shared between [`CachedFetcher`]s, keyed by the type ID
+    /// of the query. Entries whose state was dropped are pruned whenever a new
+    /// state is inserted.
+    ///
+    /// [`CachedFetcher`]: crate::fetch::CachedFetcher
+    fetcher_cache: TypeIdMap<Weak<dyn any::Any>>,
+    /// Unique identifier of this world, used to check that a
+    /// [`PreparedQuery`] is executed in the world it was created for.
+    ///
+    /// [`PreparedQuery`]: crate::fetch::PreparedQuery
+    id: u64,
+    /// The current address of this world. Queries which look up other
+    /// entities, like [`Related`], keep a pointer to this allocation, since
+    /// the world itself may move. Updated whenever an [`UnsafeWorldCell`] is
+    /// created and before queries are executed outside of handlers.
+    ///
+    /// [`Related`]: crate::query::Related
+    address: Box<AtomicPtr<World>>,
     /// So the world doesn't accidentally implement `Send` or `Sync`.
     _marker: PhantomData<*const ()>,
 }
@@ -68,7 +173,35 @@ impl World {
             global_events: GlobalEvents::new(),
             targeted_events: TargetedEvents::new(),
             event_queue: vec![],
+            deferred_queue: DeferredQueue::new(),
+            event_depth: 0,
+            max_event_depth: None,
+            catch_panics: false,
+            lifecycle_events_suppressed: false,
+            disabled_groups: vec![],
+            #[cfg(feature = "std")]
+            group_budgets: vec![],
+            #[cfg(feature = "std")]
+            frame: 0,
+            transaction_deferred: 0,
+            schedule: Schedule::new(),
+            tasks: Tasks::new(),
+            deferred_flushes: DeferredFlushes::new(),
+            clock: Clock::new(),
+            rng_seeds: RngSeeds::new(),
+            recorder: Recorder::new(),
+            categories: Categories::new(),
+            interceptors: Interceptors::new(),
+            dedup: Deduplicators::new(),
+            rate_limits: RateLimiters::new(),
+            handler_owners: BTreeMap::new(),
+            expired_handlers: vec![],
+            pending_spawns: BTreeSet::new(),
+            bundles: TypeIdMap::default(),
             bump: Bump::new(),
+            fetcher_cache: TypeIdMap::default(),
+            id: NEXT_WORLD_ID.fetch_add(1, Ordering::Relaxed),
+            address: Box::new(AtomicPtr::new(ptr::null_mut())),
             _marker: PhantomData,
         }
     }
@@ -104,12 +237,36 @@ impl World {
     /// ```txt
     /// got event: 123
     /// ```
+    ///
+    /// Events may borrow data, since the broadcast is over by the time `send`
+    /// returns. Large data can therefore be passed to handlers without copying
+    /// it into the event.
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct FrameReady<'a> {
+    ///     pixels: &'a [u8],
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|r: Receiver<FrameReady>| {
+    ///     println!("frame of {} bytes", r.event.pixels.len());
+    /// });
+    ///
+    /// let buffer = vec![0; 1920 * 1080 * 4];
+    /// world.send(FrameReady { pixels: &buffer });
+    /// ```
     pub fn send<E: GlobalEvent>(&mut self, event: E) {
         let idx = self.add_global_event::<E>().index();
 
         self.event_queue.push(EventQueueItem {
             meta: EventMeta::Global { idx },
             event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: 0,
+            transaction: 0,
         });
 
         self.flush_event_queue();
@@ -149,1293 +306,7499 @@ impl World {
         self.event_queue.push(EventQueueItem {
             meta: EventMeta::Targeted { target, idx },
             event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: 0,
+            transaction: 0,
         });
 
         self.flush_event_queue();
     }
 
-    /// Creates a new entity, returns its [`EntityId`], and sends the [`Spawn`]
-    /// event to signal its creation.
+    /// Broadcast a global event identified by its [`GlobalEventId`] to all
+    /// handlers in this world.
     ///
-    /// The new entity is spawned without any components attached. The returned
-    /// `EntityId` will not have been used by any previous entities in this
-    /// world.
+    /// This is the untyped counterpart of [`World::send`], intended for events
+    /// added at runtime with [`World::add_global_event_with_descriptor`]. The
+    /// event is moved out of `event` by copying its bytes, so the caller must
+    /// not use or drop the original afterwards.
     ///
-    /// # Examples
+    /// # Panics
     ///
-    /// ```
-    /// use evenio::prelude::*;
+    /// Panics if `id` does not identify an event in this world.
     ///
-    /// let mut world = World::new();
-    /// let id = world.spawn();
+    /// # Safety
     ///
-    /// assert!(world.entities().contains(id));
-    /// ```
-    pub fn spawn(&mut self) -> EntityId {
-        let id = self.reserved_entities.reserve(&self.entities);
+    /// - `event` must point to a valid instance of the event identified by
+    ///   `id`, as described by the event's [`layout`] and [`TypeId`]. If the
+    ///   event has an [`EventKind`] other than [`EventKind::Normal`], the
+    ///   event must also be valid for that kind.
+    /// - `event` must be valid for reads of the event's size.
+    ///
+    /// [`layout`]: GlobalEventInfo::layout
+    #[track_caller]
+    pub unsafe fn send_untyped(&mut self, id: GlobalEventId, event: *mut u8) {
+        let Some(info) = self.global_events.get(id) else {
+            panic!("global event {id:?} does not exist")
+        };
 
-        self.send(Spawn(id));
+        let meta = EventMeta::Global { idx: id.index() };
+        let layout = info.layout();
 
-        id
+        self.send_raw(meta, layout, event);
     }
 
-    /// Sends the [`Insert`] event.
-    ///
-    /// This is equivalent to:
+    /// Send a targeted event identified by its [`TargetedEventId`] to the
+    /// handlers in this world.
     ///
-    /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// # #[derive(Component)]
-    /// # struct C;
-    /// #
-    /// # let component = C;
-    /// #
-    /// world.send_to(entity, Insert(component));
-    /// ```
-    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
-        self.send_to(entity, Insert(component))
-    }
-
-    /// Sends the [`Remove`] event.
+    /// This is the untyped counterpart of [`World::send_to`]. See
+    /// [`World::send_untyped`] for more information.
     ///
-    /// This is equivalent to:
+    /// # Panics
     ///
-    /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// # #[derive(Component)]
-    /// # struct C;
-    /// #
-    /// world.send_to(entity, Remove::<C>);
-    /// ```
-    pub fn remove<C: Component>(&mut self, entity: EntityId) {
-        self.send_to(entity, Remove::<C>)
-    }
-
-    /// Sends the [`Despawn`] event.
+    /// Panics if `id` does not identify an event in this world.
     ///
-    /// This is equivalent to:
+    /// # Safety
     ///
-    /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// world.send_to(entity, Despawn);
-    /// ```
-    pub fn despawn(&mut self, entity: EntityId) {
-        self.send_to(entity, Despawn)
+    /// Same as [`World::send_untyped`].
+    #[track_caller]
+    pub unsafe fn send_to_untyped(
+        &mut self,
+        id: TargetedEventId,
+        target: EntityId,
+        event: *mut u8,
+    ) {
+        let Some(info) = self.targeted_events.get(id) else {
+            panic!("targeted event {id:?} does not exist")
+        };
+
+        let meta = EventMeta::Targeted {
+            idx: id.index(),
+            target,
+        };
+        let layout = info.layout();
+
+        self.send_raw(meta, layout, event);
     }
 
-    /// Gets an immutable reference to component `C` on `entity`. Returns `None`
-    /// if `entity` doesn't exist or doesn't have the requested component.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use evenio::prelude::*;
-    ///
-    /// #[derive(Component, PartialEq, Debug)]
-    /// struct MyComponent(i32);
+    /// Sends an owned, type-erased [`BoxedEvent`] to the handlers in this
+    /// world.
     ///
-    /// let mut world = World::new();
+    /// This is the counterpart of [`World::send`] and [`World::send_to`] for
+    /// events whose type is only known at runtime. See [`BoxedEvent`] for an
+    /// example.
     ///
-    /// let e = world.spawn();
-    /// world.insert(e, MyComponent(123));
+    /// # Panics
     ///
-    /// assert_eq!(world.get::<MyComponent>(e), Some(&MyComponent(123)));
-    /// ```
-    pub fn get<C: Component>(&self, entity: EntityId) -> Option<&C> {
-        let loc = self.entities.get(entity)?;
+    /// Panics if the event's ID does not identify an event in this world, if
+    /// a typed payload does not have the type of the event, or if a payload
+    /// of bytes does not meet the requirements of [`World::send_bytes`].
+    #[track_caller]
+    pub fn send_boxed(&mut self, event: BoxedEvent) {
+        let (id, target, payload) = event.into_parts();
 
-        let component_idx = self
-            .components()
-            .get_by_type_id(TypeId::of::<C>())?
-            .id()
-            .index();
+        let (meta, type_id, kind, drop, layout) = match (id, target) {
+            (EventId::Global(id), _) => {
+                let Some(info) = self.global_events.get(id) else {
+                    panic!("global event {id:?} does not exist")
+                };
 
-        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_unchecked() };
+                let meta = EventMeta::Global { idx: id.index() };
+                (
+                    meta,
+                    info.type_id(),
+                    info.kind(),
+                    info.drop(),
+                    info.layout(),
+                )
+            }
+            (EventId::Targeted(id), target) => {
+                let Some(info) = self.targeted_events.get(id) else {
+                    panic!("targeted event {id:?} does not exist")
+                };
 
-        let col = arch.column_of(component_idx)?;
+                let meta = EventMeta::Targeted {
+                    idx: id.index(),
+                    // Targeted boxed events always have a target.
+                    target: target.unwrap(),
+                };
+                (
+                    meta,
+                    info.type_id(),
+                    info.kind(),
+                    info.drop(),
+                    info.layout(),
+                )
+            }
+        };
 
-        Some(unsafe {
-            &*col
-                .data()
-                .as_ptr()
-                .cast_const()
-                .cast::<C>()
-                .add(loc.row.0 as usize)
-        })
+        match payload {
+            BoxedPayload::Typed(event) => {
+                assert_eq!(
+                    Some((*event).type_id()),
+                    type_id,
+                    "boxed event does not have the type of event {id:?}"
+                );
+
+                // The payload has the event's type, and therefore its layout.
+                let event = Box::into_raw(event).cast::<u8>();
+
+                // SAFETY: The event is moved into the queue, so the box is
+                // deallocated without dropping the event.
+                unsafe {
+                    self.send_raw(meta, layout, event);
+
+                    if layout.size() != 0 {
+                        alloc::alloc::dealloc(event, layout);
+                    }
+                }
+            }
+            BoxedPayload::Bytes(bytes) => {
+                assert_plain_event(type_id, kind, drop, layout, &bytes);
+
+                // SAFETY: The event is plain bytes of the correct size.
+                unsafe { self.send_raw(meta, layout, bytes.as_ptr().cast_mut()) };
+            }
+        }
     }
 
-    /// Gets a mutable reference to component `C` on `entity`. Returns `None` if
-    /// `entity` doesn't exist or doesn't have the requested component.
+    /// Broadcast a global event made of plain bytes to all handlers in this
+    /// world.
+    ///
+    /// This is a safe alternative to [`World::send_untyped`] for events with
+    /// no Rust type, such as events defined by plugins or scripts. The event
+    /// must have been added with [`World::add_global_event_with_descriptor`]
+    /// without a [`TypeId`] or drop function and with [`EventKind::Normal`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not identify an event in this world, if the event
+    /// does not meet the above requirements, or if the length of `bytes`
+    /// differs from the size of the event.
     ///
     /// # Examples
     ///
     /// ```
-    /// use evenio::prelude::*;
+    /// use std::alloc::Layout;
     ///
-    /// #[derive(Component, PartialEq, Debug)]
-    /// struct MyComponent(i32);
+    /// use evenio::event::{EventDescriptor, EventKind};
+    /// use evenio::mutability::Mutability;
+    /// use evenio::prelude::*;
     ///
     /// let mut world = World::new();
     ///
-    /// let e = world.spawn();
-    /// world.insert(e, MyComponent(123));
+    /// let id = unsafe {
+    ///     world.add_global_event_with_descriptor(EventDescriptor {
+    ///         name: "ScriptEvent".into(),
+    ///         type_id: None,
+    ///         kind: EventKind::Normal,
+    ///         layout: Layout::new::<[u8; 4]>(),
+    ///         drop: None,
+    ///         mutability: Mutability::Immutable,
+    ///         clone: None,
+    ///         serialize: None,
+    ///         deserialize: None,
+    ///     })
+    /// };
     ///
-    /// assert_eq!(world.get_mut::<MyComponent>(e), Some(&mut MyComponent(123)));
+    /// world.send_bytes(id, &[1, 2, 3, 4]);
     /// ```
-    pub fn get_mut<C: Component<Mutability = Mutable>>(
-        &mut self,
-        entity: EntityId,
-    ) -> Option<&mut C> {
-        let loc = self.entities.get(entity)?;
-
-        let component_idx = self
-            .components()
-            .get_by_type_id(TypeId::of::<C>())?
-            .id()
-            .index();
+    #[track_caller]
+    pub fn send_bytes(&mut self, id: GlobalEventId, bytes: &[u8]) {
+        let Some(info) = self.global_events.get(id) else {
+            panic!("global event {id:?} does not exist")
+        };
 
-        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_unchecked() };
+        assert_plain_event(
+            info.type_id(),
+            info.kind(),
+            info.drop(),
+            info.layout(),
+            bytes,
+        );
 
-        let col = arch.column_of(component_idx)?;
+        let meta = EventMeta::Global { idx: id.index() };
+        let layout = info.layout();
 
-        Some(unsafe { &mut *col.data().as_ptr().cast::<C>().add(loc.row.0 as usize) })
+        // SAFETY: The event is plain bytes of the correct size.
+        unsafe { self.send_raw(meta, layout, bytes.as_ptr().cast_mut()) };
     }
 
-    /// Adds a new handler to the world, returns its [`HandlerId`], and sends
-    /// the [`AddHandler`] event to signal its creation.
+    /// Send a targeted event made of plain bytes to the handlers in this
+    /// world.
     ///
-    /// If the handler already exists (as determined by [`Handler::type_id`])
-    /// then the `HandlerId` of the existing handler is returned and no
-    /// event is sent.
+    /// See [`World::send_bytes`] for more information.
     ///
-    /// Returns an error if the configuration of the handler is invalid.
-    pub(crate) fn try_add_handler<H: IntoHandler<M>, M>(
-        &mut self,
-        handler: H,
-    ) -> Result<HandlerId, String> {
-        let mut handler = handler.into_handler();
-        let mut config = HandlerConfig::default();
-
-        let type_id = handler.type_id();
+    /// # Panics
+    ///
+    /// Same as [`World::send_bytes`].
+    #[track_caller]
+    pub fn send_to_bytes(&mut self, id: TargetedEventId, target: EntityId, bytes: &[u8]) {
+        let Some(info) = self.targeted_events.get(id) else {
+            panic!("targeted event {id:?} does not exist")
+        };
 
-        if let Some(type_id) = type_id {
-            if let Some(info) = self.handlers.get_by_type_id(type_id) {
-                return Ok(info.id());
-            }
-        }
+        assert_plain_event(
+            info.type_id(),
+            info.kind(),
+            info.drop(),
+            info.layout(),
+            bytes,
+        );
+
+        let meta = EventMeta::Targeted {
+            idx: id.index(),
+            target,
+        };
+        let layout = info.layout();
 
-        let handler_name = handler.name();
+        // SAFETY: The event is plain bytes of the correct size.
+        unsafe { self.send_raw(meta, layout, bytes.as_ptr().cast_mut()) };
+    }
 
-        if let Err(e) = handler.init(self, &mut config) {
-            return Err(format!("initialization of {handler_name} failed: {e}"));
-        }
+    /// Sends an event added at runtime which was encoded with the
+    /// [`SerializeFn`] of its descriptor, decoding it with the
+    /// [`DeserializeFn`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not identify an event in this world, if the event
+    /// doesn't have a deserialize function, if `bytes` are not a valid
+    /// encoding, or if a targeted event has no target.
+    ///
+    /// [`SerializeFn`]: crate::event::SerializeFn
+    /// [`DeserializeFn`]: crate::event::DeserializeFn
+    #[track_caller]
+    pub(crate) fn send_serialized(&mut self, id: EventId, target: Option<EntityId>, bytes: &[u8]) {
+        let (layout, deserialize) = match id {
+            EventId::Global(id) => {
+                let Some(info) = self.global_events.get(id) else {
+                    panic!("global event {id:?} does not exist")
+                };
 
-        let received_event = match config.received_event {
-            ReceivedEventId::None => {
-                return Err(format!(
-                    "handler {handler_name} did not specify an event to receive"
-                ));
+                (info.layout(), info.deserialize_fn())
             }
-            ReceivedEventId::Ok(event) => event,
-            ReceivedEventId::Invalid => {
-                return Err(format!(
-                    "handler {handler_name} attempted to listen for more than one event type"
-                ))
+            EventId::Targeted(id) => {
+                let Some(info) = self.targeted_events.get(id) else {
+                    panic!("targeted event {id:?} does not exist")
+                };
+
+                (info.layout(), info.deserialize_fn())
             }
         };
 
-        let received_event_access = match config.received_event_access {
-            MaybeInvalidAccess::Ok(access) => access,
-            MaybeInvalidAccess::Invalid => {
-                return Err(format!(
-                    "handler {handler_name} has conflicting access to the received event"
-                ))
-            }
+        let Some(deserialize) = deserialize else {
+            panic!("event {id:?} can't be deserialized")
         };
 
-        let component_access_conjunction = config
-            .component_accesses
-            .iter()
-            .fold(ComponentAccess::new_true(), |acc, a| acc.and(a));
+        let event = if layout.size() == 0 {
+            layout.align() as *mut u8
+        } else {
+            // SAFETY: The layout has a nonzero size.
+            let event = unsafe { alloc::alloc::alloc(layout) };
 
-        let conflicts = component_access_conjunction.collect_conflicts();
+            if event.is_null() {
+                alloc::alloc::handle_alloc_error(layout);
+            }
 
-        if !conflicts.is_empty() {
-            let mut errmsg = format!(
-                "handler {handler_name} contains conflicting component access (aliased \
-                 mutability)\nconflicting components are...\n"
-            );
+            event
+        };
 
-            for idx in conflicts {
-                errmsg += "- ";
-                match self.components.get_by_index(idx) {
-                    Some(info) => errmsg += info.name(),
-                    None => {
-                        write!(&mut errmsg, "{idx:?}").unwrap();
+        // SAFETY: The pointer is non-null and aligned for the event.
+        let valid = unsafe { deserialize(bytes, NonNull::new_unchecked(event)) };
+
+        // SAFETY: The event is initialized if it was valid, and is moved into
+        // the queue, so the allocation is freed without dropping the event.
+        unsafe {
+            if valid {
+                match id {
+                    EventId::Global(id) => self.send_untyped(id, event),
+                    EventId::Targeted(id) => {
+                        let target = target.expect("targeted event has no target");
+                        self.send_to_untyped(id, target, event)
                     }
-                };
+                }
             }
 
-            return Err(errmsg);
+            if layout.size() != 0 {
+                alloc::alloc::dealloc(event, layout);
+            }
         }
 
-        let component_access_disjunction = config
-            .component_accesses
-            .iter()
-            .fold(ComponentAccess::new_false(), |acc, a| acc.or(a));
+        assert!(valid, "invalid serialized event {id:?}");
+    }
 
-        let info = HandlerInfo::new(HandlerInfoInner {
-            name: handler_name,
-            id: HandlerId::NULL, // Filled in later.
-            type_id,
-            order: 0, // Filled in later.
-            received_event,
-            received_event_access,
-            targeted_event_component_access: config.targeted_event_component_access,
-            sent_untargeted_events: config.sent_global_events,
-            sent_targeted_events: config.sent_targeted_events,
-            component_access: component_access_conjunction,
-            archetype_filter: component_access_disjunction,
-            referenced_components: config.referenced_components,
-            priority: config.priority,
-            handler,
+    /// Copies the event into the bump allocator and broadcasts it.
+    unsafe fn send_raw(&mut self, meta: EventMeta, layout: Layout, event: *mut u8) {
+        let ptr = self.bump.alloc_layout(layout);
+        ptr::copy_nonoverlapping(event, ptr.as_ptr(), layout.size());
+
+        self.event_queue.push(EventQueueItem {
+            meta,
+            event: ptr,
+            depth: 0,
+            transaction: 0,
         });
 
-        let id = self.handlers.add(info);
-        let info = self.handlers.get_mut(id).unwrap();
-
-        self.archetypes.register_handler(info);
-
-        self.send(AddHandler(id));
-
-        Ok(id)
+        self.flush_event_queue();
     }
 
-    /// Adds a new handler to the world, returns its [`HandlerId`], and sends
-    /// the [`AddHandler`] event to signal its creation.
+    /// Sends a copy of a targeted event to every entity matching the query
+    /// `Q`.
     ///
-    /// If the handler already exists (as determined by [`Handler::type_id`])
-    /// then the `HandlerId` of the existing handler is returned and no
-    /// event is sent.
+    /// The matching entities are found from the archetypes matched by `Q`, so
+    /// `Q` is usually a filter like `With<&C>` or `(With<&A>, Not<&B>)`. The
+    /// event is cloned for every target except the last, which receives the
+    /// original. Targets are visited in an unspecified order. Entities which
+    /// start or stop matching `Q` while the events are broadcast are not taken
+    /// into account.
     ///
-    /// # Panics
+    /// To do the same from inside a handler, pass the entities of a
+    /// [`Fetcher`] to [`Sender::send_to_many`].
     ///
-    /// Panics if the configuration of the handler is invalid. This can occur
-    /// when, for instance, the handler does not specify an event to receive.
+    /// [`Fetcher`]: crate::fetch::Fetcher
+    /// [`Sender::send_to_many`]: crate::event::Sender::send_to_many
     ///
-    /// ```should_panic
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// world.add_handler(|| {}); // Panics
-    /// ```
+    /// # Panics
+    ///
+    /// Panics if the query fails to initialize or has conflicting component
+    /// access.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
-    /// # #[derive(GlobalEvent)]
-    /// # struct MyEvent;
     ///
-    /// fn my_handler(_: Receiver<MyEvent>) {};
-    ///
-    /// let mut world = World::new();
-    /// let id = world.add_handler(my_handler);
+    /// #[derive(TargetedEvent, Clone)]
+    /// struct Heal(u32);
     ///
-    /// assert!(world.handlers().contains(id));
-    /// ```
+    /// #[derive(Component)]
+    /// struct Health(u32);
     ///
-    /// [`Handler::type_id`]: crate::handler::Handler::type_id
-    #[track_caller]
-    pub fn add_handler<H: IntoHandler<M>, M>(&mut self, handler: H) -> HandlerId {
-        match self.try_add_handler(handler) {
-            Ok(id) => id,
-            Err(e) => panic!("{e}"),
-        }
-    }
-
-    /// Removes a handler from the world, returns its [`HandlerInfo`], and sends
-    /// the [`RemoveHandler`] event. If the `handler` ID is invalid, then `None`
-    /// is returned and no event is sent.
+    /// #[derive(Component)]
+    /// struct Ally;
     ///
-    /// # Example
+    /// let mut world = World::new();
     ///
-    /// ```
-    /// use evenio::prelude::*;
+    /// world.add_handler(|r: Receiver<Heal, &mut Health>| r.query.0 += r.event.0);
     ///
-    /// let mut world = World::new();
+    /// let ally = world.spawn();
+    /// world.insert(ally, Health(10));
+    /// world.insert(ally, Ally);
     ///
-    /// # #[derive(GlobalEvent)]
-    /// # struct MyEvent;
-    /// let handler_id = world.add_handler(|_: Receiver<MyEvent>| {});
+    /// let enemy = world.spawn();
+    /// world.insert(enemy, Health(10));
     ///
-    /// let info = world.remove_handler(handler_id).unwrap();
+    /// world.send_to_all::<With<&Ally>, _>(Heal(5));
     ///
-    /// assert_eq!(info.id(), handler_id);
-    /// assert!(!world.handlers().contains(handler_id));
+    /// assert_eq!(world.get::<Health>(ally).unwrap().0, 15);
+    /// assert_eq!(world.get::<Health>(enemy).unwrap().0, 10);
     /// ```
-    pub fn remove_handler(&mut self, handler: HandlerId) -> Option<HandlerInfo> {
-        if !self.handlers.contains(handler) {
-            return None;
+    #[track_caller]
+    pub fn send_to_all<Q: Query, E: TargetedEvent + Clone>(&mut self, event: E) {
+        let mut state = self.init_query::<Q>(&mut HandlerConfig::new());
+        let idx = self.add_targeted_event::<E>().index();
+
+        let mut targets = vec![];
+
+        for arch in self.archetypes.iter() {
+            if arch.entity_count() > 0 && Q::new_arch_state(arch, &mut state).is_some() {
+                targets.extend_from_slice(arch.entity_ids());
+            }
         }
 
-        self.send(RemoveHandler(handler));
+        let Some((&last, rest)) = targets.split_last() else {
+            return;
+        };
 
-        let info = self.handlers.remove(handler).unwrap();
+        let events_before = self.event_queue.len();
 
-        self.archetypes.remove_handler(&info);
+        for &target in rest {
+            self.event_queue.push(EventQueueItem {
+                meta: EventMeta::Targeted { target, idx },
+                event: NonNull::from(self.bump.alloc(event.clone())).cast(),
+                depth: 0,
+                transaction: 0,
+            });
+        }
 
-        Some(info)
+        self.event_queue.push(EventQueueItem {
+            meta: EventMeta::Targeted { target: last, idx },
+            event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: 0,
+            transaction: 0,
+        });
+
+        // Reverse pushed events so they're handled in FIFO order.
+        self.event_queue[events_before..].reverse();
+
+        self.flush_event_queue();
     }
 
-    /// Adds the component `C` to the world, returns its [`ComponentId`], and
-    /// sends the [`AddComponent`] event to signal its creation.
+    /// Broadcast an event which may be either global or targeted. `target` must
+    /// be `Some` if the event is targeted.
+    pub(crate) fn send_any<E: Event + 'static>(&mut self, target: Option<EntityId>, event: E) {
+        let idx = <E as EventSet>::new_indices(self);
+
+        let meta = if TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>() {
+            EventMeta::Targeted {
+                idx: TargetedEventIdx(idx),
+                target: target.expect("targeted event should have a target"),
+            }
+        } else {
+            EventMeta::Global {
+                idx: GlobalEventIdx(idx),
+            }
+        };
+
+        self.event_queue.push(EventQueueItem {
+            meta,
+            event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: 0,
+            transaction: 0,
+        });
+
+        self.flush_event_queue();
+    }
+
+    /// Broadcast a [`Request`] for `query` and return the response of the
+    /// handler which answered it.
     ///
-    /// If the component already exists, then the [`ComponentId`] of the
-    /// existing component is returned and no event is sent.
+    /// Handlers receive the request as a `Receiver<Request<Q, R>>` and answer
+    /// it with [`Request::respond`]. Exactly one handler must respond,
+    /// otherwise an error is returned.
+    ///
+    /// Requests can only be sent from outside of handlers, since events sent
+    /// by handlers are not broadcast until the handler returns.
     ///
     /// # Examples
     ///
     /// ```
+    /// use evenio::event::{Request, RequestError};
     /// use evenio::prelude::*;
     ///
-    /// #[derive(Component)]
-    /// struct MyComponent;
+    /// struct Double(u32);
     ///
     /// let mut world = World::new();
-    /// let id = world.add_component::<MyComponent>();
     ///
-    /// assert_eq!(id, world.add_component::<MyComponent>());
+    /// assert_eq!(
+    ///     world.send_request::<Double, u32>(Double(2)),
+    ///     Err(RequestError::NoResponse)
+    /// );
+    ///
+    /// world.add_handler(|r: Receiver<Request<Double, u32>>| {
+    ///     r.event.respond(r.event.query().0 * 2);
+    /// });
+    ///
+    /// assert_eq!(world.send_request::<Double, u32>(Double(2)), Ok(4));
     /// ```
-    pub fn add_component<C: Component>(&mut self) -> ComponentId {
-        let desc = ComponentDescriptor {
-            name: any::type_name::<C>().into(),
-            type_id: Some(TypeId::of::<C>()),
-            layout: Layout::new::<C>(),
-            drop: drop_fn_of::<C>(),
-            mutability: Mutability::of::<C::Mutability>(),
-        };
+    pub fn send_request<Q: 'static, R: 'static>(&mut self, query: Q) -> Result<R, RequestError> {
+        let slot = ResponseSlot::new();
 
-        unsafe { self.add_component_with_descriptor(desc) }
+        self.send(Request::new(query, &slot));
+
+        slot.into_result()
     }
 
-    /// Adds a component described by a given [`ComponentDescriptor`].
+    /// Broadcast a global event after all other queued events have finished
+    /// broadcasting.
     ///
-    /// Like [`add_component`], an [`AddComponent`] event is sent if the
-    /// component is newly added. If the [`TypeId`] of the component matches an
-    /// existing component, then the existing component's [`ComponentId`] is
-    /// returned and no event is sent.
+    /// Events sent with [`World::send`] and [`Sender::send`] are handled
+    /// depth-first: an event sent from a handler is broadcast as soon as the
+    /// handlers of the current event have run. Deferred events instead wait
+    /// until every other event has been handled, including events sent by
+    /// handlers of other events. Deferred events are broadcast in the order
+    /// they were sent, unless they were given a different [`Priority`] with
+    /// [`World::send_prioritized`].
     ///
-    /// # Safety
+    /// Outside of handlers, no other events are queued, so the event is
+    /// broadcast before this function returns.
     ///
-    /// - If the component is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the component as
-    ///   described by [`DropFn`]'s documentation.
+    /// See also [`Sender::send_deferred`].
     ///
-    /// [`add_component`]: World::add_component
-    pub unsafe fn add_component_with_descriptor(
+    /// [`Sender::send`]: crate::event::Sender::send
+    /// [`Sender::send_deferred`]: crate::event::Sender::send_deferred
+    pub fn send_deferred<E: GlobalEvent>(&mut self, event: E) {
+        self.send_prioritized(Priority::NORMAL, event);
+    }
+
+    /// Broadcast a targeted event after all other queued events have finished
+    /// broadcasting.
+    ///
+    /// See [`World::send_deferred`] for more information.
+    pub fn send_to_deferred<E: TargetedEvent>(&mut self, target: EntityId, event: E) {
+        self.send_to_prioritized(Priority::NORMAL, target, event);
+    }
+
+    /// Broadcast a global event after all other queued events have finished
+    /// broadcasting, ordered among the deferred events by its [`Priority`].
+    ///
+    /// Deferred events with a higher priority are broadcast first. Outside of
+    /// handlers, no other events are queued, so the event is broadcast before
+    /// this function returns.
+    ///
+    /// See also [`Sender::send_prioritized`].
+    ///
+    /// [`Sender::send_prioritized`]: crate::event::Sender::send_prioritized
+    pub fn send_prioritized<E: GlobalEvent>(&mut self, priority: Priority, event: E) {
+        let idx = self.add_global_event::<E>().index();
+
+        self.deferred_queue.push(
+            priority,
+            EventQueueItem {
+                meta: EventMeta::Global { idx },
+                event: NonNull::from(self.bump.alloc(event)).cast(),
+                depth: 0,
+                transaction: 0,
+            },
+        );
+
+        self.flush_event_queue();
+    }
+
+    /// Broadcast a targeted event after all other queued events have finished
+    /// broadcasting, ordered among the deferred events by its [`Priority`].
+    ///
+    /// See [`World::send_prioritized`] for more information.
+    pub fn send_to_prioritized<E: TargetedEvent>(
         &mut self,
-        desc: ComponentDescriptor,
-    ) -> ComponentId {
-        let (id, is_new) = self.components.add(desc);
+        priority: Priority,
+        target: EntityId,
+        event: E,
+    ) {
+        let idx = self.add_targeted_event::<E>().index();
 
-        if is_new {
-            self.send(AddComponent(id));
-        }
+        self.deferred_queue.push(
+            priority,
+            EventQueueItem {
+                meta: EventMeta::Targeted { target, idx },
+                event: NonNull::from(self.bump.alloc(event)).cast(),
+                depth: 0,
+                transaction: 0,
+            },
+        );
 
-        id
+        self.flush_event_queue();
     }
 
-    /// Removes a component from the world and returns its [`ComponentInfo`]. If
-    /// the `component` ID is invalid, then `None` is returned and the function
-    /// has no effect.
+    /// Adds an [`Interceptor`] to the world, which observes every event
+    /// broadcast from now on. Interceptors run in the order they were added.
     ///
-    /// Removing a component has the following effects in the order listed:
-    /// 1. The [`RemoveComponent`] event is sent.
-    /// 2. All entities with the component are despawned.
-    /// 3. All handlers that reference the component are removed.
-    /// 4. The corresponding [`Insert`] events for the component are removed.
-    /// 5. The corresponding [`Remove`] events for the component are removed.
+    /// See [`Interceptor`] for an example.
+    pub fn add_interceptor<I: Interceptor>(&mut self, interceptor: I) {
+        self.interceptors.list.push(Box::new(interceptor));
+    }
+
+    /// Returns the [`EventMetrics`] of an event, or `None` if the event
+    /// doesn't exist.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// # let mut world = World::new();
-    /// # #[derive(Component)] struct C;
-    /// # #[derive(GlobalEvent)] struct E;
-    /// #
-    /// let component = world.add_component::<C>();
-    /// let handler = world.add_handler(|_: Receiver<E>, _: Fetcher<&C>| {});
+    /// use evenio::prelude::*;
     ///
-    /// assert!(world.components().contains(component));
-    /// assert!(world.handlers().contains(handler));
+    /// #[derive(GlobalEvent)]
+    /// struct E;
     ///
-    /// world.remove_component(component);
+    /// let mut world = World::new();
     ///
-    /// assert!(!world.components().contains(component));
-    /// // Handler was also removed because it references `C` in its `Fetcher`.
-    /// assert!(!world.handlers().contains(handler));
+    /// world.add_handler(|_: Receiver<E>| {});
+    ///
+    /// world.send(E);
+    /// world.send(E);
+    ///
+    /// let id = world.add_global_event::<E>();
+    /// let metrics = world.event_metrics(id.into()).unwrap();
+    ///
+    /// assert_eq!(metrics.count(), 2);
+    /// println!("average time per broadcast: {:?}", metrics.mean_time().unwrap());
     /// ```
-    pub fn remove_component(&mut self, component: ComponentId) -> Option<ComponentInfo> {
-        if !self.components.contains(component) {
-            return None;
-        }
-
-        // Send event first.
-        self.send(RemoveComponent(component));
-
-        let despawn_idx = self.add_targeted_event::<Despawn>().index();
-
-        // Attempt to despawn all entities that still have this component.
-        for arch in self.archetypes.iter() {
-            if arch.column_of(component.index()).is_some() {
-                for &entity_id in arch.entity_ids() {
-                    self.event_queue.push(EventQueueItem {
-                        meta: EventMeta::Targeted {
-                            idx: despawn_idx,
-                            target: entity_id,
-                        },
-                        event: NonNull::<Despawn>::dangling().cast(),
-                    });
-                }
-            }
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn event_metrics(&self, event: EventId) -> Option<EventMetrics> {
+        match event {
+            EventId::Global(id) => self.global_events.get(id).map(|info| info.metrics()),
+            EventId::Targeted(id) => self.targeted_events.get(id).map(|info| info.metrics()),
         }
+    }
 
-        self.flush_event_queue();
-
-        // Remove all handlers that reference this component.
-        let mut handlers_to_remove = vec![];
-
-        for handler in self.handlers.iter() {
-            if handler.references_component(component.index()) {
-                handlers_to_remove.push(handler.id());
-            }
+    /// Resets the [`EventMetrics`] of all events.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn reset_event_metrics(&mut self) {
+        for info in self.global_events.iter_mut() {
+            *info.metrics_mut() = EventMetrics::default();
         }
 
-        for handler_id in handlers_to_remove {
-            self.remove_handler(handler_id);
+        for info in self.targeted_events.iter_mut() {
+            *info.metrics_mut() = EventMetrics::default();
         }
+    }
 
-        let info = &self.components[component];
-
-        // Remove all the `Insert` and `Remove` events for this component.
-        let events_to_remove = info
-            .insert_events()
-            .iter()
-            .copied()
-            .chain(info.remove_events().iter().copied())
-            .collect::<Vec<_>>();
-
-        for event in events_to_remove {
-            self.remove_targeted_event(event);
+    /// Resets the [`HandlerMetrics`] of all handlers.
+    ///
+    /// [`HandlerMetrics`]: crate::handler::HandlerMetrics
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn reset_handler_metrics(&mut self) {
+        for info in self.handlers.iter_mut() {
+            *info.metrics_mut() = Default::default();
         }
-
-        let mut info = self
-            .components
-            .remove(component)
-            .expect("component should still exist");
-
-        // Remove all archetypes with this component. If there are still entities with
-        // the component by this point, then they will be silently removed.
-        self.archetypes
-            .remove_component(&mut info, &mut self.components, |id| {
-                self.entities.remove(id);
-            });
-
-        Some(info)
     }
 
-    /// Adds the global event `E` to the world, returns its [`GlobalEventId`],
-    /// and sends the [`AddGlobalEvent`] event to signal its creation.
+    /// Deduplicates events of type `E` within a flush of the event queue.
     ///
-    /// If the event already exists, then the [`GlobalEventId`] of the existing
-    /// event is returned and no event is sent.
+    /// This is equivalent to calling [`World::dedup_by_key`] with a key
+    /// function that clones the event.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
+    /// #[derive(GlobalEvent, Clone, PartialEq, Eq, Hash)]
+    /// struct RecomputeNavmesh;
+    ///
     /// #[derive(GlobalEvent)]
-    /// struct MyEvent;
+    /// struct Tick;
     ///
     /// let mut world = World::new();
-    /// let id = world.add_global_event::<MyEvent>();
     ///
-    /// assert_eq!(id, world.add_global_event::<MyEvent>());
+    /// world.dedup::<RecomputeNavmesh>();
+    ///
+    /// world.add_handler(|_: Receiver<Tick>, s: Sender<RecomputeNavmesh>| {
+    ///     for _ in 0..10 {
+    ///         s.send(RecomputeNavmesh);
+    ///     }
+    /// });
+    ///
+    /// world.add_handler(|_: Receiver<RecomputeNavmesh>| println!("recomputing"));
+    ///
+    /// world.send(Tick); // Prints "recomputing" once.
     /// ```
-    pub fn add_global_event<E: GlobalEvent>(&mut self) -> GlobalEventId {
-        let desc = EventDescriptor::new::<E>(self);
-        unsafe { self.add_global_event_with_descriptor(desc) }
+    pub fn dedup<E>(&mut self)
+    where
+        E: for<'a> Event<This<'a> = E> + Clone + Eq + Hash + 'static,
+    {
+        self.dedup_by_key(E::clone);
     }
 
-    /// Adds the targeted event `E` to the world, returns its
-    /// [`TargetedEventId`], and sends the [`AddTargetedEvent`] event to
-    /// signal its creation.
+    /// Deduplicates events of type `E` within a flush of the event queue,
+    /// comparing events by the key returned from `key`.
     ///
-    /// If the event already exists, then the [`TargetedEventId`] of the
-    /// existing event is returned and no event is sent.
+    /// A flush starts when an event is sent with a method of the world, such
+    /// as [`World::send`], and ends once all events sent by handlers, including
+    /// deferred events, have finished broadcasting. Within a flush, an event of
+    /// type `E` is dropped without being broadcast if an event with the same
+    /// key and target was broadcast before it. Calling this again for `E`
+    /// replaces the key function.
+    ///
+    /// Events which borrow data can't be deduplicated, since the key function
+    /// could hold on to the borrowed data past the end of the flush.
+    pub fn dedup_by_key<E, K, F>(&mut self, key: F)
+    where
+        E: for<'a> Event<This<'a> = E> + 'static,
+        K: Eq + Hash + 'static,
+        F: FnMut(&E) -> K + 'static,
+    {
+        self.dedup.insert(key);
+    }
+
+    /// Limits how often events of type `E` are broadcast, adding the event to
+    /// the world if it doesn't exist yet. Calling this again for `E` replaces
+    /// the limit, dropping any event held back by the previous one.
+    ///
+    /// The limit is checked when an event is about to be broadcast. Events of
+    /// type `E` over the limit are handled according to the limit's
+    /// [`Overflow`](crate::event::Overflow) behavior.
+    ///
+    /// Events which borrow data can't be limited with this method, since they
+    /// could be held back past the lifetime of the data. Use
+    /// [`World::rate_limit_event`] for those, and for events without a Rust
+    /// type.
     ///
     /// # Examples
     ///
     /// ```
+    /// use evenio::event::{Overflow, RateLimit};
     /// use evenio::prelude::*;
     ///
-    /// #[derive(TargetedEvent)]
-    /// struct MyEvent;
+    /// #[derive(GlobalEvent)]
+    /// struct Progress(u32);
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Work;
     ///
     /// let mut world = World::new();
-    /// let id = world.add_targeted_event::<MyEvent>();
     ///
-    /// assert_eq!(id, world.add_targeted_event::<MyEvent>());
+    /// world.rate_limit::<Progress>(RateLimit::per_flush(1).overflow(Overflow::Coalesce));
+    ///
+    /// world.add_handler(|_: Receiver<Work>, s: Sender<Progress>| {
+    ///     for i in 1..=100 {
+    ///         s.send(Progress(i));
+    ///     }
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<Progress>| println!("{}%", r.event.0));
+    ///
+    /// world.send(Work); // Prints "1%" and "100%".
     /// ```
-    pub fn add_targeted_event<E: TargetedEvent>(&mut self) -> TargetedEventId {
-        let desc = EventDescriptor::new::<E>(self);
-        unsafe { self.add_targeted_event_with_descriptor(desc) }
+    pub fn rate_limit<E>(&mut self, limit: RateLimit)
+    where
+        E: for<'a> Event<This<'a> = E> + 'static,
+    {
+        let id = self.event_id_of::<E>();
+        self.insert_rate_limit(id, true, limit);
     }
 
-    /// Adds a global event described by a given [`EventDescriptor`].
+    /// Limits how often the event with the given ID is broadcast. Calling this
+    /// again for the event replaces the limit, dropping any event held back by
+    /// the previous one. The limit is removed along with the event.
     ///
-    /// Like [`add_global_event`], an [`AddGlobalEvent`] event is sent if the
-    /// event is newly added. If the [`TypeId`] of the event matches an
-    /// existing global event, then the existing event's [`GlobalEventId`] is
-    /// returned and no event is sent.
+    /// This works like [`World::rate_limit`], except that an event with a
+    /// [`TypeId`] is assumed to borrow data. Such events are never held back
+    /// past the end of a flush by [`Overflow::Coalesce`], and are dropped
+    /// instead.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the event as
-    ///   described by [`DropFn`]'s documentation.
-    /// - The event's kind must be correct for the descriptor. See [`EventKind`]
-    ///   for more information.
+    /// Panics if `event` does not identify an event in this world.
     ///
-    /// [`add_global_event`]: World::add_global_event
-    pub unsafe fn add_global_event_with_descriptor(
-        &mut self,
-        desc: EventDescriptor,
-    ) -> GlobalEventId {
-        let (id, is_new) = self.global_events.add(desc);
+    /// [`Overflow::Coalesce`]: crate::event::Overflow::Coalesce
+    #[track_caller]
+    pub fn rate_limit_event(&mut self, event: EventId, limit: RateLimit) {
+        let type_id = match event {
+            EventId::Global(id) => self.global_events.get(id).map(|info| info.type_id()),
+            EventId::Targeted(id) => self.targeted_events.get(id).map(|info| info.type_id()),
+        };
 
-        if is_new {
-            self.handlers.register_event(id.index());
-            self.send(AddGlobalEvent(id));
-        }
+        let Some(type_id) = type_id else {
+            panic!("event {event:?} does not exist")
+        };
 
-        id
+        self.insert_rate_limit(event, type_id.is_none(), limit);
     }
 
-    /// Adds a targeted event described by a given [`EventDescriptor`].
+    /// Limits an existing event. See [`RateLimiters::insert`].
+    fn insert_rate_limit(&mut self, event: EventId, keep_held: bool, limit: RateLimit) {
+        let (layout, drop) = match event {
+            EventId::Global(id) => {
+                let info = self.global_events.get(id).unwrap();
+                (info.layout(), info.drop())
+            }
+            EventId::Targeted(id) => {
+                let info = self.targeted_events.get(id).unwrap();
+                (info.layout(), info.drop())
+            }
+        };
+
+        self.rate_limits
+            .insert(event, layout, drop, keep_held, limit);
+    }
+
+    /// Sets the maximum depth of events sent by handlers. `None` removes the
+    /// limit, which is the default.
     ///
-    /// Like [`add_targeted_event`], an [`AddTargetedEvent`] event is sent if
-    /// the event is newly added. If the [`TypeId`] of the event matches an
-    /// existing targeted event, then the existing event's [`GlobalEventId`] is
-    /// returned and no event is sent.
+    /// Events sent from outside of handlers and deferred events have a depth
+    /// of zero. An event sent by a handler while handling an event of depth
+    /// `n` has a depth of `n + 1`. When an event deeper than the limit is
+    /// about to be broadcast, it is dropped instead and a
+    /// [`DepthLimitExceeded`] event is sent in its place. This stops feedback
+    /// loops between handlers from running forever.
     ///
-    /// # Safety
+    /// # Examples
     ///
-    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the event as
-    ///   described by [`DropFn`]'s documentation.
-    /// - The event's kind must be correct for the descriptor. See [`EventKind`]
-    ///   for more information.
+    /// ```
+    /// use evenio::event::DepthLimitExceeded;
+    /// use evenio::prelude::*;
     ///
-    /// [`add_targeted_event`]: World::add_targeted_event
-    pub unsafe fn add_targeted_event_with_descriptor(
-        &mut self,
-        desc: EventDescriptor,
-    ) -> TargetedEventId {
-        let kind = desc.kind;
-
-        let (id, is_new) = self.targeted_events.add(desc);
-
-        if is_new {
-            match kind {
-                EventKind::Normal => {}
-                EventKind::Insert { component_idx } => {
-                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                        info.insert_events.insert(id);
-                    }
-                }
-                EventKind::Remove { component_idx } => {
-                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                        info.remove_events.insert(id);
-                    }
-                }
-                EventKind::Spawn => {}
-                EventKind::Despawn => {}
-            }
-
-            self.send(AddTargetedEvent(id))
-        }
+    /// #[derive(GlobalEvent)]
+    /// struct Ping;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Pong;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_max_event_depth(Some(16));
+    ///
+    /// world.add_handler(|_: Receiver<Ping>, s: Sender<Pong>| s.send(Pong));
+    /// world.add_handler(|_: Receiver<Pong>, s: Sender<Ping>| s.send(Ping));
+    ///
+    /// world.add_handler(|r: Receiver<DepthLimitExceeded>| {
+    ///     println!("dropped event at depth {}", r.event.depth);
+    /// });
+    ///
+    /// world.send(Ping); // Prints "dropped event at depth 17".
+    /// ```
+    pub fn set_max_event_depth(&mut self, depth: Option<u32>) {
+        self.max_event_depth = depth;
+    }
 
-        id
+    /// Returns the maximum depth of events sent by handlers. See
+    /// [`World::set_max_event_depth`].
+    pub fn max_event_depth(&self) -> Option<u32> {
+        self.max_event_depth
     }
 
-    /// Removes a global event from the world and returns its
-    /// [`GlobalEventInfo`]. If the event ID is invalid, then `None` is
-    /// returned and the function has no effect.
+    /// Sets whether panics of handlers are caught. Disabled by default.
     ///
-    /// Removing an event has the following additional effects in the order
-    /// listed:
-    /// 1. The [`RemoveTargetedEvent`] event is sent.
-    /// 2. All handlers that send or receive the event are removed.
+    /// When enabled, a handler which panics is stopped, and the broadcast
+    /// continues with the next handler as if the panicking handler had
+    /// returned. A [`HandlerPanicked`] event with the panic message is sent
+    /// afterwards. The panic hook still runs, so the panic is printed as
+    /// usual. Changes the handler made before panicking, including the events
+    /// it sent, are kept.
+    ///
+    /// Panics of the handlers of [`HandlerPanicked`] itself are not caught,
+    /// so that a panicking handler can't cause an endless stream of events.
     ///
     /// # Examples
     ///
     /// ```
+    /// use evenio::handler::HandlerPanicked;
     /// use evenio::prelude::*;
     ///
     /// #[derive(GlobalEvent)]
-    /// struct MyEvent;
+    /// struct Tick;
     ///
     /// let mut world = World::new();
     ///
-    /// let id = world.add_global_event::<MyEvent>();
-    /// world.remove_global_event(id);
+    /// world.set_catch_panics(true);
     ///
-    /// assert!(!world.global_events().contains(id));
+    /// world.add_handler(|_: Receiver<Tick>| panic!("oops"));
+    /// world.add_handler(|_: Receiver<Tick>| println!("still running"));
+    ///
+    /// world.add_handler(|r: Receiver<HandlerPanicked>| {
+    ///     println!("handler panicked: {}", r.event.message);
+    /// });
+    ///
+    /// world.send(Tick); // Prints "still running" and "handler panicked: oops".
     /// ```
-    pub fn remove_global_event(&mut self, event: GlobalEventId) -> Option<GlobalEventInfo> {
-        assert!(self.event_queue.is_empty());
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn set_catch_panics(&mut self, catch: bool) {
+        self.catch_panics = catch;
+    }
 
-        if !self.global_events.contains(event) {
-            return None;
+    /// Returns whether panics of handlers are caught. See
+    /// [`World::set_catch_panics`].
+    pub fn catches_panics(&self) -> bool {
+        self.catch_panics
+    }
+
+    /// Sets the [`DeadTargetPolicy`] of the targeted event `E`, which decides
+    /// what happens to an `E` whose target entity doesn't exist when the event
+    /// is broadcast. The default policy is [`DeadTargetPolicy::Drop`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::event::{DeadLetter, DeadTargetPolicy};
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(TargetedEvent)]
+    /// struct Damage(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_dead_target_policy::<Damage>(DeadTargetPolicy::DeadLetter);
+    ///
+    /// world.add_handler(|r: Receiver<DeadLetter>| {
+    ///     println!("{:?} was already dead", r.event.target);
+    /// });
+    ///
+    /// let e = world.spawn();
+    /// world.despawn(e);
+    ///
+    /// world.send_to(e, Damage(10)); // Prints the message.
+    /// ```
+    pub fn set_dead_target_policy<E: TargetedEvent>(&mut self, policy: DeadTargetPolicy) {
+        let id = self.add_targeted_event::<E>();
+
+        if policy == DeadTargetPolicy::DeadLetter {
+            self.add_global_event::<DeadLetter>();
         }
 
-        // Send event before removing anything.
-        self.send(RemoveGlobalEvent(event));
+        // SAFETY: The event was just added.
+        unsafe { self.targeted_events.get_mut(id).unwrap_unchecked() }
+            .set_dead_target_policy(policy);
+    }
 
-        // Remove all handlers that send or receive this event.
-        let mut to_remove = vec![];
+    /// Runs `f` without broadcasting lifecycle events to their handlers.
+    ///
+    /// While `f` runs, [`Spawn`], [`Insert`], [`Remove`], [`Take`],
+    /// [`InsertBundle`] and [`SpawnWith`] events still take effect, but their
+    /// handlers and [interceptors] do not run. This includes events sent by
+    /// handlers of other events. It is intended for bulk operations such as
+    /// loading a saved world, where notifying handlers of every change is
+    /// wasted work. [`Despawn`] and [`TakenComponent`] events are broadcast as
+    /// usual. The previous setting is restored when `f` returns, even if it
+    /// panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Insert<Health>, ()>| println!("inserted"));
+    ///
+    /// world.without_lifecycle_events(|world| {
+    ///     for _ in 0..1000 {
+    ///         let e = world.spawn();
+    ///         world.insert(e, Health(100)); // Prints nothing.
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// [interceptors]: crate::event::Interceptor
+    /// [`Take`]: crate::event::Take
+    /// [`TakenComponent`]: crate::event::TakenComponent
+    pub fn without_lifecycle_events<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut World) -> R,
+    {
+        struct Restore<'a> {
+            world: &'a mut World,
+            prev: bool,
+        }
 
-        for handler in self.handlers.iter() {
-            if handler.received_event() == event.into()
-                || handler.sent_global_events_bitset().contains(event.index())
-            {
-                to_remove.push(handler.id());
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.world.lifecycle_events_suppressed = self.prev;
             }
         }
 
-        for id in to_remove {
-            self.remove_handler(id);
+        let prev = mem::replace(&mut self.lifecycle_events_suppressed, true);
+        let restore = Restore { world: self, prev };
+
+        f(restore.world)
+    }
+
+    /// Sends all events in the [`EventQueue`] in the order they were pushed,
+    /// leaving the queue empty.
+    ///
+    /// Events pushed to the queue while it is drained, such as by other
+    /// threads or by handlers, are sent during the next call.
+    ///
+    /// See [`EventQueue`] for an example.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn drain_queue(&mut self, queue: &EventQueue) {
+        for f in queue.take() {
+            f(self);
         }
+    }
 
-        Some(self.global_events.remove(event).unwrap())
+    /// Sends all events forwarded through the [`Bridge`] in the order they
+    /// were forwarded, leaving the bridge empty.
+    ///
+    /// Events forwarded while the bridge is drained are sent during the next
+    /// call.
+    ///
+    /// See [`Bridge`] for an example.
+    pub fn drain_bridge(&mut self, bridge: &Bridge) {
+        bridge.drain(self);
     }
 
-    /// Removes a targeted event from the world and returns its
-    /// [`TargetedEventInfo`]. If the event ID is invalid, then `None` is
-    /// returned and the function has no effect.
+    /// Schedules a global event to be broadcast once the world's tick has
+    /// advanced by `delay`.
     ///
-    /// Removing an event has the following additional effects in the order
-    /// listed:
-    /// 1. The [`RemoveTargetedEvent`] event is sent.
-    /// 2. All handlers that send or receive the event are removed.
+    /// Scheduled events are stored in the world until they are due and are
+    /// broadcast by [`World::advance`]. Events which are due on the same tick
+    /// are broadcast in the order they were scheduled. A `delay` of zero
+    /// broadcasts the event during the next call to `advance`.
+    ///
+    /// See also [`Sender::send_delayed`].
+    ///
+    /// [`Sender::send_delayed`]: crate::event::Sender::send_delayed
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
-    /// #[derive(TargetedEvent)]
-    /// struct MyEvent;
+    /// #[derive(GlobalEvent)]
+    /// struct Respawn;
     ///
     /// let mut world = World::new();
     ///
-    /// let id = world.add_targeted_event::<MyEvent>();
-    /// world.remove_targeted_event(id);
+    /// world.add_handler(|_: Receiver<Respawn>| println!("respawned"));
     ///
-    /// assert!(!world.targeted_events().contains(id));
+    /// world.send_delayed(3, Respawn);
+    ///
+    /// world.advance(2); // Nothing happens yet.
+    /// world.advance(1); // Prints "respawned".
     /// ```
-    pub fn remove_targeted_event(&mut self, event: TargetedEventId) -> Option<TargetedEventInfo> {
-        assert!(self.event_queue.is_empty());
+    pub fn send_delayed<E: GlobalEvent + 'static>(&mut self, delay: u64, event: E) {
+        self.add_global_event::<E>();
+        self.schedule
+            .insert(delay, Box::new(move |world| world.send(event)));
+    }
 
-        if !self.targeted_events.contains(event) {
-            return None;
-        }
+    /// Schedules a targeted event to be broadcast once the world's tick has
+    /// advanced by `delay`.
+    ///
+    /// See [`World::send_delayed`] for more information.
+    pub fn send_to_delayed<E: TargetedEvent + 'static>(
+        &mut self,
+        delay: u64,
+        target: EntityId,
+        event: E,
+    ) {
+        self.add_targeted_event::<E>();
+        self.schedule
+            .insert(delay, Box::new(move |world| world.send_to(target, event)));
+    }
 
-        // Send event before doing anything else.
-        self.send(RemoveTargetedEvent(event));
+    /// Advances the world's tick by `ticks` and broadcasts all scheduled events
+    /// which are now due, in the order they are due.
+    ///
+    /// Events scheduled by the handlers of these events are broadcast during
+    /// this call as well if they are due by the new tick.
+    ///
+    /// See [`World::send_delayed`] for an example.
+    pub fn advance(&mut self, ticks: u64) {
+        self.schedule.tick += ticks;
 
-        // Remove all handlers that send or receive this event.
-        let mut to_remove = vec![];
+        #[cfg(feature = "std")]
+        {
+            self.frame += 1;
+        }
 
-        for handler in self.handlers.iter() {
-            if handler.received_event() == event.into()
-                || handler
-                    .sent_targeted_events_bitset()
-                    .contains(event.index())
-            {
-                to_remove.push(handler.id());
-            }
+        while let Some(f) = self.schedule.pop_due() {
+            f(self);
         }
+    }
 
-        for id in to_remove {
-            self.remove_handler(id);
+    /// Returns the world's current tick, i.e. the sum of all arguments passed
+    /// to [`World::advance`].
+    pub fn tick(&self) -> u64 {
+        self.schedule.tick
+    }
+
+    /// Advances the world's time by `delta`, sending a [`FixedUpdate`] event
+    /// for every fixed timestep which elapsed, followed by an [`Update`]
+    /// event. Returns the number of [`FixedUpdate`] events sent.
+    ///
+    /// Before the [`Update`] event, all [`Timer`] and [`Stopwatch`]
+    /// components are advanced by `delta`, and [`TimerFinished`] is sent to
+    /// the timers which finished.
+    ///
+    /// Time which is left over is carried to the next call. If more than
+    /// [`max_fixed_steps`] timesteps elapsed, only that many are run and the
+    /// others are discarded, so that a slow frame doesn't cause ever slower
+    /// frames.
+    ///
+    /// This is unrelated to the tick of [`World::advance`].
+    ///
+    /// See the [`time`] module for an example.
+    ///
+    /// [`max_fixed_steps`]: World::set_max_fixed_steps
+    /// [`time`]: crate::time
+    pub fn advance_time(&mut self, delta: Duration) -> u32 {
+        #[cfg(feature = "std")]
+        {
+            self.frame += 1;
         }
 
-        let info = self.targeted_events.remove(event).unwrap();
+        let steps = self.clock.accumulate(delta);
 
-        match info.kind() {
-            EventKind::Normal => {}
-            EventKind::Insert { component_idx } => {
-                if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                    info.insert_events.remove(&event);
-                }
-            }
-            EventKind::Remove { component_idx } => {
-                if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                    info.remove_events.remove(&event);
-                }
-            }
-            EventKind::Spawn => {}
-            EventKind::Despawn => {}
+        for _ in 0..steps {
+            self.clock.step();
+            self.send(FixedUpdate {
+                delta: self.clock.timestep,
+            });
         }
 
-        Some(info)
+        self.tick_timers(delta);
+
+        self.send(Update { delta });
+
+        steps
     }
 
-    /// Returns the [`Entities`] for this world.
-    pub fn entities(&self) -> &Entities {
+    /// Advances all [`Timer`]s and [`Stopwatch`]es by `delta`, and sends
+    /// [`TimerFinished`] to the timers which finished.
+    fn tick_timers(&mut self, delta: Duration) {
+        // Don't add the components if they were never used.
+        if self
+            .components
+            .get_by_type_id(TypeId::of::<Timer>())
+            .is_some()
+        {
+            let finished: Vec<_> = self
+                .iter_mut::<(EntityId, &mut Timer)>()
+                .filter_map(|(id, timer)| match timer.tick(delta) {
+                    0 => None,
+                    times => Some((id, times)),
+                })
+                .collect();
+
+            for (id, times) in finished {
+                self.send_to(id, TimerFinished { times });
+            }
+        }
+
+        if self
+            .components
+            .get_by_type_id(TypeId::of::<Stopwatch>())
+            .is_some()
+        {
+            for stopwatch in self.iter_mut::<&mut Stopwatch>() {
+                stopwatch.tick(delta);
+            }
+        }
+    }
+
+    /// Returns the [`Time`] of the world. See [`World::advance_time`].
+    pub fn time(&self) -> &Time {
+        &self.clock.time
+    }
+
+    /// Spawns a task which sends the output of `future` as a global event when
+    /// it completes. The future is first polled before this returns. See the
+    /// [`task`] module.
+    ///
+    /// [`task`]: crate::task
+    pub fn spawn_task<F>(&mut self, future: F)
+    where
+        F: Future + 'static,
+        F::Output: GlobalEvent + 'static,
+    {
+        self.tasks.spawn(completion(future));
+        self.poll_tasks();
+    }
+
+    /// Spawns a task which sends the output of `future` as a targeted event to
+    /// `target` when it completes. The future is first polled before this
+    /// returns. See the [`task`] module.
+    ///
+    /// [`task`]: crate::task
+    pub fn spawn_task_to<F>(&mut self, target: EntityId, future: F)
+    where
+        F: Future + 'static,
+        F::Output: TargetedEvent + 'static,
+    {
+        self.tasks.spawn(completion_to(target, future));
+        self.poll_tasks();
+    }
+
+    /// Polls the tasks which were woken since they were last polled, and sends
+    /// the outputs of the completed tasks as events. See the [`task`] module.
+    ///
+    /// [`task`]: crate::task
+    pub fn poll_tasks(&mut self) {
+        for completion in self.tasks.poll() {
+            completion(self);
+        }
+    }
+
+    /// Returns the number of tasks which have not completed yet. See the
+    /// [`task`] module.
+    ///
+    /// [`task`]: crate::task
+    pub fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns the duration of a fixed timestep. See [`World::advance_time`].
+    ///
+    /// Defaults to 1/60th of a second.
+    pub fn fixed_timestep(&self) -> Duration {
+        self.clock.timestep
+    }
+
+    /// Sets the duration of a fixed timestep. See [`World::advance_time`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timestep` is zero.
+    #[track_caller]
+    pub fn set_fixed_timestep(&mut self, timestep: Duration) {
+        assert!(!timestep.is_zero(), "fixed timestep must not be zero");
+        self.clock.timestep = timestep;
+    }
+
+    /// Sets the maximum number of fixed timesteps run by a single call to
+    /// [`World::advance_time`]. Defaults to 8.
+    pub fn set_max_fixed_steps(&mut self, max: u32) {
+        self.clock.max_steps = max;
+    }
+
+    /// Returns the time left over from the last call to
+    /// [`World::advance_time`] as a fraction of the fixed timestep, in the
+    /// range `[0, 1)`.
+    ///
+    /// This is useful to interpolate between the states of the last two
+    /// fixed timesteps when rendering.
+    pub fn fixed_overstep(&self) -> f64 {
+        self.clock.overstep()
+    }
+
+    /// Returns the seed of the [`Rng`] handler param. Defaults to 0.
+    ///
+    /// [`Rng`]: crate::rng::Rng
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seeds.seed
+    }
+
+    /// Sets the seed of the [`Rng`] handler param, and restarts the streams of
+    /// all `Rng`s from the new seed. See the [`rng`] module.
+    ///
+    /// [`Rng`]: crate::rng::Rng
+    /// [`rng`]: crate::rng
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng_seeds.reseed(seed);
+    }
+
+    pub(crate) fn rng_seeds(&self) -> &RngSeeds {
+        &self.rng_seeds
+    }
+
+    pub(crate) fn rng_seeds_mut(&mut self) -> &mut RngSeeds {
+        &mut self.rng_seeds
+    }
+
+    /// Registers `E` as an event type to capture while recording. Events which
+    /// borrow data can't be recorded, since the log outlives the data.
+    ///
+    /// See [`World::start_recording`] for more information.
+    pub fn record<E>(&mut self)
+    where
+        E: for<'a> Event<This<'a> = E> + Clone + 'static,
+    {
+        self.recorder.register::<E>();
+    }
+
+    /// Registers an event added at runtime as an event to capture while
+    /// recording.
+    ///
+    /// The event is captured in the encoding of the [`SerializeFn`] of its
+    /// [`EventDescriptor`], and decoded with its [`DeserializeFn`] when
+    /// replayed. Persisted logs store the encoded event as is.
+    ///
+    /// See [`World::start_recording`] for more information.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not identify an event in this world, or if the
+    /// event doesn't have both a serialize and a deserialize function.
+    ///
+    /// [`SerializeFn`]: crate::event::SerializeFn
+    /// [`DeserializeFn`]: crate::event::DeserializeFn
+    #[track_caller]
+    pub fn record_untyped(&mut self, id: EventId) {
+        let (name, serialize, deserialize) = match id {
+            EventId::Global(id) => {
+                let Some(info) = self.global_events.get(id) else {
+                    panic!("global event {id:?} does not exist")
+                };
+
+                (info.name(), info.serialize_fn(), info.deserialize_fn())
+            }
+            EventId::Targeted(id) => {
+                let Some(info) = self.targeted_events.get(id) else {
+                    panic!("targeted event {id:?} does not exist")
+                };
+
+                (info.name(), info.serialize_fn(), info.deserialize_fn())
+            }
+        };
+
+        let (Some(serialize), Some(_)) = (serialize, deserialize) else {
+            panic!("event {id:?} can't be recorded without serialize and deserialize functions")
+        };
+
+        let name = Cow::Owned(name.into());
+        self.recorder.register_untyped(id, name, serialize);
+    }
+
+    /// Starts capturing the events sent into the world in an [`EventLog`].
+    /// Does nothing if the world is already recording.
+    ///
+    /// Every event sent to the world from outside of handlers which was
+    /// registered with [`World::record`] or [`World::record_untyped`] is added to the log along with its
+    /// target and the world's [tick](World::tick). The log can be replayed
+    /// into another world with [`EventLog::replay`] to reproduce the run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent, Clone)]
+    /// struct Score(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Total(u32);
+    ///
+    /// fn setup() -> (World, EntityId) {
+    ///     let mut world = World::new();
+    ///     let e = world.spawn();
+    ///     world.insert(e, Total(0));
+    ///     world.add_handler(|r: Receiver<Score>, mut t: Single<&mut Total>| t.0 += r.event.0);
+    ///     world.record::<Score>();
+    ///     (world, e)
+    /// }
+    ///
+    /// let (mut world, e) = setup();
+    ///
+    /// world.start_recording();
+    /// world.send(Score(3));
+    /// world.send(Score(4));
+    /// let log = world.stop_recording().unwrap();
+    ///
+    /// assert_eq!(log.len(), 2);
+    ///
+    /// let (mut replayed, e2) = setup();
+    /// log.replay(&mut replayed);
+    ///
+    /// assert_eq!(replayed.get::<Total>(e2).unwrap().0, 7);
+    /// assert_eq!(world.get::<Total>(e).unwrap().0, 7);
+    /// ```
+    pub fn start_recording(&mut self) {
+        self.recorder.start();
+    }
+
+    /// Stops recording and returns the log, or `None` if the world was not
+    /// recording.
+    pub fn stop_recording(&mut self) -> Option<EventLog> {
+        self.recorder.stop()
+    }
+
+    /// Returns the events recorded so far, or `None` if the world is not
+    /// recording.
+    pub fn recorded_events(&self) -> Option<&EventLog> {
+        self.recorder.log()
+    }
+
+    /// Creates a new entity, returns its [`EntityId`], and sends the [`Spawn`]
+    /// event to signal its creation.
+    ///
+    /// The new entity is spawned without any components attached. The returned
+    /// `EntityId` will not have been used by any previous entities in this
+    /// world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.spawn();
+    ///
+    /// assert!(world.entities().contains(id));
+    /// ```
+    pub fn spawn(&mut self) -> EntityId {
+        let id = self.reserved_entities.reserve(&self.entities);
+
+        self.send(Spawn(id));
+
+        id
+    }
+
+    /// Creates a new entity with all components in `bundle` and returns its
+    /// [`EntityId`].
+    ///
+    /// This sends the [`SpawnWith`] event. Unlike calling [`World::spawn`]
+    /// followed by [`World::insert`] for every component, no handler observes
+    /// the entity before all of its components are inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    /// let id = world.spawn_with((Health(100),));
+    ///
+    /// assert_eq!(world.get::<Health>(id).unwrap().0, 100);
+    /// ```
+    pub fn spawn_with<B: Bundle>(&mut self, bundle: B) -> EntityId {
+        // Adding the event flushes the queue, which would release the entity
+        // if it was already reserved.
+        self.add_global_event::<SpawnWith<B>>();
+
+        let id = self.reserved_entities.reserve(&self.entities);
+        self.pending_spawns.insert(id);
+
+        self.send(SpawnWith(id, bundle));
+
+        id
+    }
+
+    /// Sends the [`Insert`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct C;
+    /// #
+    /// # let component = C;
+    /// #
+    /// world.send_to(entity, Insert(component));
+    /// ```
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.send_to(entity, Insert(component))
+    }
+
+    /// Sends the [`InsertBundle`] event, which inserts all components of
+    /// `bundle` on the entity at once.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # use evenio::event::InsertBundle;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct A;
+    /// #
+    /// # #[derive(Component)]
+    /// # struct B;
+    /// #
+    /// # let bundle = (A, B);
+    /// #
+    /// world.send_to(entity, InsertBundle(bundle));
+    /// ```
+    pub fn insert_bundle<B: Bundle>(&mut self, entity: EntityId, bundle: B) {
+        self.send_to(entity, InsertBundle(bundle))
+    }
+
+    /// Sends the [`Remove`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct C;
+    /// #
+    /// world.send_to(entity, Remove::<C>);
+    /// ```
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.send_to(entity, Remove::<C>)
+    }
+
+    /// Sends the [`Despawn`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// world.send_to(entity, Despawn);
+    /// ```
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.send_to(entity, Despawn)
+    }
+
+    /// Sends the [`Despawn`] event with a [`DespawnReason`].
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # use evenio::event::DespawnReason;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// # let reason = DespawnReason("unloaded");
+    /// #
+    /// world.send_to(entity, Despawn::because(reason));
+    /// ```
+    pub fn despawn_because(&mut self, entity: EntityId, reason: DespawnReason) {
+        self.send_to(entity, Despawn::because(reason))
+    }
+
+    /// Makes despawning an entity with component `C` also despawn the
+    /// entities it links to. `links` returns the linked entities of a `C`.
+    ///
+    /// The cascade is transitive: linked entities with a `C` of their own
+    /// despawn their links in turn. Links to entities which don't exist are
+    /// ignored, so cycles are harmless. Linked entities are despawned after
+    /// the entity linking to them, with the same [`DespawnReason`].
+    ///
+    /// This adds a handler which listens for [`Despawn`]. Remove the returned
+    /// handler to stop the cascade.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Children(Vec<EntityId>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_despawn_cascade(|c: &Children| c.0.clone());
+    ///
+    /// let child = world.spawn();
+    /// let parent = world.spawn();
+    /// world.insert(parent, Children(vec![child]));
+    ///
+    /// world.despawn(parent);
+    ///
+    /// assert!(!world.entities().contains(child));
+    /// ```
+    pub fn add_despawn_cascade<C, F, I>(&mut self, mut links: F) -> HandlerId
+    where
+        C: Component,
+        F: FnMut(&C) -> I + 'static,
+        I: IntoIterator<Item = EntityId>,
+    {
+        self.add_handler(move |r: Receiver<Despawn, &C>, s: Sender<Despawn>| {
+            for entity in links(r.query) {
+                s.send_to(entity, *r.event);
+            }
+        })
+    }
+
+    /// Lets entities with component `C` veto their own despawning. When an
+    /// entity with a `C` is about to despawn, `veto` is called with its `C` and
+    /// the [`Despawn`] event. If it returns `true`, the event is consumed, and
+    /// the entity stays alive.
+    ///
+    /// This adds a [high priority] handler which listens for [`Despawn`], so
+    /// the veto happens before ordinary handlers of `Despawn` observe the
+    /// event, regardless of who sent it. Remove the returned handler to stop
+    /// vetoing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct LastStand {
+    ///     charges: u32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_despawn_veto(|buff: &mut LastStand, _: &Despawn| {
+    ///     let veto = buff.charges > 0;
+    ///     buff.charges = buff.charges.saturating_sub(1);
+    ///     veto
+    /// });
+    ///
+    /// let hero = world.spawn();
+    /// world.insert(hero, LastStand { charges: 1 });
+    ///
+    /// world.despawn(hero);
+    /// assert!(world.entities().contains(hero));
+    ///
+    /// world.despawn(hero);
+    /// assert!(!world.entities().contains(hero));
+    /// ```
+    ///
+    /// [high priority]: crate::handler::IntoHandler::high
+    pub fn add_despawn_veto<C, F>(&mut self, mut veto: F) -> HandlerId
+    where
+        C: Component<Mutability = Mutable>,
+        F: FnMut(&mut C, &Despawn) -> bool + 'static,
+    {
+        self.add_handler(
+            (move |r: ReceiverMut<Despawn, &mut C>| {
+                if veto(r.query, &r.event) {
+                    EventMut::consume(r.event);
+                }
+            })
+            .high(),
+        )
+    }
+
+    /// Gets an immutable reference to component `C` on `entity`. Returns `None`
+    /// if `entity` doesn't exist or doesn't have the requested component.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct MyComponent(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, MyComponent(123));
+    ///
+    /// assert_eq!(world.get::<MyComponent>(e), Some(&MyComponent(123)));
+    /// ```
+    pub fn get<C: Component>(&self, entity: EntityId) -> Option<&C> {
+        let loc = self.entities.get(entity)?;
+
+        let component_idx = self
+            .components()
+            .get_by_type_id(TypeId::of::<C>())?
+            .id()
+            .index();
+
+        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_unchecked() };
+
+        let col = arch.column_of(component_idx)?;
+
+        Some(unsafe {
+            &*col
+                .data()
+                .as_ptr()
+                .cast_const()
+                .cast::<C>()
+                .add(loc.row.0 as usize)
+        })
+    }
+
+    /// Gets a mutable reference to component `C` on `entity`. Returns `None` if
+    /// `entity` doesn't exist or doesn't have the requested component.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct MyComponent(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, MyComponent(123));
+    ///
+    /// assert_eq!(world.get_mut::<MyComponent>(e), Some(&mut MyComponent(123)));
+    /// ```
+    pub fn get_mut<C: Component<Mutability = Mutable>>(
+        &mut self,
+        entity: EntityId,
+    ) -> Option<&mut C> {
+        let loc = self.entities.get(entity)?;
+
+        let component_idx = self
+            .components()
+            .get_by_type_id(TypeId::of::<C>())?
+            .id()
+            .index();
+
+        let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_unchecked() };
+
+        let col = arch.column_of(component_idx)?;
+
+        Some(unsafe { &mut *col.data().as_ptr().cast::<C>().add(loc.row.0 as usize) })
+    }
+
+    /// Gets a type-erased pointer to the component with index `component` on
+    /// `entity`. Returns `None` if `entity` doesn't exist or doesn't have the
+    /// component.
+    ///
+    /// This is useful for accessing components which can't be named at compile
+    /// time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct MyComponent(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, MyComponent(123));
+    ///
+    /// let idx = world.add_component::<MyComponent>().index();
+    /// let ptr = world.get_ptr(e, idx).unwrap();
+    ///
+    /// assert_eq!(ptr.info().name(), std::any::type_name::<MyComponent>());
+    /// assert_eq!(unsafe { ptr.deref::<MyComponent>() }, &MyComponent(123));
+    /// ```
+    pub fn get_ptr(&self, entity: EntityId, component: ComponentIdx) -> Option<Ptr<'_>> {
+        let loc = self.entities.get(entity)?;
+        let info = self.components.get_by_index(component)?;
+
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_unchecked() };
+        let col = arch.column_of(component)?;
+
+        Some(unsafe { Ptr::new(column_ptr(col.data(), info, loc.row), info) })
+    }
+
+    /// Gets a type-erased mutable pointer to the component with index
+    /// `component` on `entity`. Returns `None` if `entity` doesn't exist,
+    /// doesn't have the component, or if the component is
+    /// [immutable](Mutability::Immutable).
+    pub fn get_ptr_mut(&mut self, entity: EntityId, component: ComponentIdx) -> Option<PtrMut<'_>> {
+        let loc = self.entities.get(entity)?;
+        let info = self.components.get_by_index(component)?;
+
+        if info.mutability() == Mutability::Immutable {
+            return None;
+        }
+
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_unchecked() };
+        let col = arch.column_of(component)?;
+
+        Some(unsafe { PtrMut::new(column_ptr(col.data(), info, loc.row), info) })
+    }
+
+    /// Returns an iterator over every entity with the component at index
+    /// `component`, along with a type-erased pointer to the component.
+    ///
+    /// Entities are visited in an unspecified order.
+    pub fn iter_ptrs(
+        &self,
+        component: ComponentIdx,
+    ) -> impl Iterator<Item = (EntityId, Ptr<'_>)> + '_ {
+        self.component_columns(component)
+            .flat_map(|(arch, data, info)| {
+                arch.entity_ids().iter().enumerate().map(move |(row, &id)| {
+                    let ptr = column_ptr(data, info, ArchetypeRow(row as u32));
+                    (id, unsafe { Ptr::new(ptr, info) })
+                })
+            })
+    }
+
+    /// Returns an iterator over every entity with the component at index
+    /// `component`, along with a type-erased mutable pointer to the component.
+    /// The iterator is empty if the component is
+    /// [immutable](Mutability::Immutable).
+    ///
+    /// Entities are visited in an unspecified order.
+    pub fn iter_ptrs_mut(
+        &mut self,
+        component: ComponentIdx,
+    ) -> impl Iterator<Item = (EntityId, PtrMut<'_>)> + '_ {
+        let is_mutable = self
+            .components
+            .get_by_index(component)
+            .is_some_and(|info| info.mutability() == Mutability::Mutable);
+
+        self.component_columns(component)
+            .filter(move |_| is_mutable)
+            .flat_map(|(arch, data, info)| {
+                arch.entity_ids().iter().enumerate().map(move |(row, &id)| {
+                    let ptr = column_ptr(data, info, ArchetypeRow(row as u32));
+                    (id, unsafe { PtrMut::new(ptr, info) })
+                })
+            })
+    }
+
+    /// Returns the archetypes containing the given component along with a
+    /// pointer to the component's column in each.
+    fn component_columns(
+        &self,
+        component: ComponentIdx,
+    ) -> impl Iterator<Item = (&Archetype, NonNull<u8>, &ComponentInfo)> + '_ {
+        let info = self.components.get_by_index(component);
+
+        info.into_iter().flat_map(move |info| {
+            info.member_of.iter().map(move |&idx| {
+                let arch = unsafe { self.archetypes.get(idx).unwrap_unchecked() };
+                let col = unsafe { arch.column_of(component).unwrap_unchecked() };
+                (arch, col.data(), info)
+            })
+        })
+    }
+
+    /// Returns an iterator over all entities matching the read-only query
+    /// `Q`, without adding a handler or sending an event.
+    ///
+    /// This takes `&mut self` because initializing the query may add
+    /// components to the world. The archetypes matched by the query are
+    /// found anew on every call, so prefer [`Fetcher`] inside of handlers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query fails to initialize.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// for i in 0..3 {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Health(i));
+    /// }
+    ///
+    /// let total: i32 = world.iter::<&Health>().map(|h| h.0).sum();
+    /// assert_eq!(total, 3);
+    /// ```
+    ///
+    /// [`Fetcher`]: crate::fetch::Fetcher
+    #[track_caller]
+    pub fn iter<Q: ReadOnlyQuery>(&mut self) -> QueryIter<'_, Q> {
+        self.query_iter::<Q>()
+    }
+
+    /// Returns an iterator over all entities matching the query `Q`, without
+    /// adding a handler or sending an event.
+    ///
+    /// See [`World::iter`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query fails to initialize or has conflicting component
+    /// access, like `(&mut A, &A)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Health(10));
+    ///
+    /// for health in world.iter_mut::<&mut Health>() {
+    ///     health.0 -= 1;
+    /// }
+    ///
+    /// assert_eq!(world.get::<Health>(e).unwrap().0, 9);
+    /// ```
+    #[track_caller]
+    pub fn iter_mut<Q: Query>(&mut self) -> QueryIter<'_, Q> {
+        self.query_iter::<Q>()
+    }
+
+    #[track_caller]
+    fn query_iter<Q: Query>(&mut self) -> QueryIter<'_, Q> {
+        let state = self.init_query::<Q>(&mut HandlerConfig::default());
+        self.publish_address();
+
+        let mut fetcher = FetcherState::new(state);
+
+        for arch in self.archetypes.iter() {
+            if arch.entity_count() > 0 {
+                fetcher.refresh_archetype(arch);
+            }
+        }
+
+        QueryIter::new(fetcher, &self.archetypes)
+    }
+
+    /// Initializes the query `Q` for use outside of a handler.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query fails to initialize or has conflicting component
+    /// access.
+    #[track_caller]
+    pub(crate) fn init_query<Q: Query>(&mut self, config: &mut HandlerConfig) -> Q::State {
+        let (ca, state) = match Q::init(self, config) {
+            Ok(res) => res,
+            Err(e) => panic!(
+                "failed to initialize query `{}`: {e}",
+                any::type_name::<Q>()
+            ),
+        };
+
+        // Queries like `Related` register the access of their subqueries
+        // separately.
+        let conflicts = config
+            .component_accesses
+            .iter()
+            .fold(ca, |acc, a| acc.and(a))
+            .collect_conflicts();
+
+        if !conflicts.is_empty() {
+            let names: Vec<_> = conflicts
+                .iter()
+                .filter_map(|&idx| self.components.get_by_index(idx))
+                .map(ComponentInfo::name)
+                .collect();
+
+            panic!(
+                "query `{}` contains conflicting component access (aliased mutability) for \
+                 components {names:?}",
+                any::type_name::<Q>()
+            );
+        }
+
+        state
+    }
+
+    /// Returns a [`HandlerBuilder`] for configuring `handler` before adding it
+    /// to the world with [`HandlerBuilder::add`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world
+    ///     .build_handler(|_: Receiver<Tick>| println!("physics"))
+    ///     .name("physics")
+    ///     .label("simulation")
+    ///     .high()
+    ///     .add();
+    ///
+    /// assert_eq!(world.handlers()[id].name(), "physics");
+    /// ```
+    pub fn build_handler<H: IntoHandler<M>, M>(
+        &mut self,
+        handler: H,
+    ) -> HandlerBuilder<'_, H::Handler> {
+        HandlerBuilder::new(self, handler.into_handler())
+    }
+
+    /// Adds a new handler to the world, returns its [`HandlerId`], and sends
+    /// the [`AddHandler`] event to signal its creation.
+    ///
+    /// If the handler already exists (as determined by [`Handler::type_id`])
+    /// then the `HandlerId` of the existing handler is returned and no
+    /// event is sent.
+    ///
+    /// This is the fallible version of [`World::add_handler`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InitError`] if the configuration of the handler is
+    /// invalid. If the handler accesses components in conflicting ways, the
+    /// components and the params involved are available from
+    /// [`InitError::conflicts`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct E;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let err = world
+    ///     .try_add_handler(|_: Receiver<E>, _: Fetcher<&mut Health>, _: Fetcher<&Health>| {})
+    ///     .unwrap_err();
+    ///
+    /// let conflict = &err.conflicts()[0];
+    ///
+    /// assert!(conflict.component_name.ends_with("Health"));
+    /// // The names of the params, such as `evenio::fetch::Fetcher<'_, &mut Health>`.
+    /// assert!(conflict.params[0].unwrap().contains("&mut"));
+    /// assert!(!conflict.params[1].unwrap().contains("&mut"));
+    /// ```
+    pub fn try_add_handler<H: IntoHandler<M>, M>(
+        &mut self,
+        handler: H,
+    ) -> Result<HandlerId, InitError> {
+        let handler = handler.into_handler();
+
+        if let Some(type_id) = handler.type_id() {
+            if let Some(info) = self.handlers.get_by_type_id(type_id) {
+                return Ok(info.id());
+            }
+        }
+
+        let info = self.init_handler(handler, HandlerConfig::default())?;
+
+        let id = self.handlers.add(info);
+        let info = self.handlers.get_mut(id).unwrap();
+
+        self.archetypes.register_handler(info);
+
+        self.send(AddHandler(id));
+
+        Ok(id)
+    }
+
+    /// Initializes `handler` starting from `config`, and validates the
+    /// resulting configuration.
+    fn init_handler<H: Handler>(
+        &mut self,
+        mut handler: H,
+        mut config: HandlerConfig,
+    ) -> Result<HandlerInfo, InitError> {
+        let type_id = handler.type_id();
+        let handler_name = handler.name();
+
+        if let Err(e) = handler.init(self, &mut config) {
+            return Err(InitError {
+                message: format!("initialization of {handler_name} failed: {e}").into(),
+                ..e
+            });
+        }
+
+        let received_events: Box<[EventId]> = match config.received_event {
+            ReceivedEventId::Ok(event) => Box::new([event]),
+            // Handlers receiving lifecycle events start receiving them once
+            // they exist.
+            ReceivedEventId::Many(events)
+                if !events.is_empty() || config.receives_lifecycle_events =>
+            {
+                events.into()
+            }
+            ReceivedEventId::None | ReceivedEventId::Many(_) => {
+                return Err(InitError::new(format!(
+                    "handler {handler_name} did not specify an event to receive"
+                )));
+            }
+            ReceivedEventId::Invalid => {
+                return Err(InitError::new(format!(
+                    "handler {handler_name} attempted to listen for more than one event type"
+                )))
+            }
+        };
+
+        if received_events
+            .iter()
+            .any(|e| e.is_targeted() != received_events[0].is_targeted())
+        {
+            return Err(InitError::new(format!(
+                "handler {handler_name} listens for both global and targeted events"
+            )));
+        }
+
+        let received_event_access = match config.received_event_access {
+            MaybeInvalidAccess::Ok(access) => access,
+            MaybeInvalidAccess::Invalid => {
+                return Err(InitError::new(format!(
+                    "handler {handler_name} has conflicting access to the received event"
+                )))
+            }
+        };
+
+        let component_access_conjunction = config
+            .component_accesses
+            .iter()
+            .fold(ComponentAccess::new_true(), |acc, a| acc.and(a));
+
+        let conflicts = component_access_conjunction.collect_conflicts();
+
+        if !conflicts.is_empty() {
+            let mut errmsg = format!(
+                "handler {handler_name} contains conflicting component access (aliased \
+                 mutability)\nconflicting components are...\n"
+            );
+
+            let mut access_conflicts = vec![];
+
+            for idx in conflicts {
+                let Some(info) = self.components.get_by_index(idx) else {
+                    writeln!(&mut errmsg, "- {idx:?}").unwrap();
+                    continue;
+                };
+
+                let params = conflicting_params(
+                    &config.component_accesses,
+                    &config.component_access_params,
+                    idx,
+                );
+
+                write!(&mut errmsg, "- {}", info.name()).unwrap();
+
+                if let [Some(a), Some(b)] = params {
+                    write!(&mut errmsg, " (in `{a}` and `{b}`)").unwrap();
+                }
+
+                errmsg.push('\n');
+
+                access_conflicts.push(AccessConflict {
+                    component: info.id(),
+                    component_name: Cow::Owned(info.name().into()),
+                    params,
+                });
+            }
+
+            return Err(InitError {
+                message: errmsg.into(),
+                conflicts: access_conflicts,
+            });
+        }
+
+        if config.parallel {
+            if !matches!(
+                config.event_queue_access,
+                MaybeInvalidAccess::Ok(Access::None)
+            ) {
+                return Err(InitError::new(format!(
+                    "parallel handler {handler_name} accesses the event queue"
+                )));
+            }
+
+            if received_event_access == Access::ReadWrite {
+                return Err(InitError::new(format!(
+                    "parallel handler {handler_name} has mutable access to the received event"
+                )));
+            }
+
+            if config.thread_local {
+                return Err(InitError::new(format!(
+                    "parallel handler {handler_name} can only run on the thread owning the world"
+                )));
+            }
+        }
+
+        let component_access_disjunction = config
+            .component_accesses
+            .iter()
+            .fold(ComponentAccess::new_false(), |acc, a| acc.or(a));
+
+        let mut labels: Vec<Cow<'static, str>> = vec![];
+
+        for label in config.labels {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+
+        let enabled = !labels
+            .iter()
+            .any(|label| self.disabled_groups.contains(label));
+
+        // Handlers of targeted events are found through the archetypes of
+        // the targets.
+        let refreshes_archetypes = !config.skip_archetype_refresh
+            || config.receives_lifecycle_events
+            || received_events.iter().any(|e| e.is_targeted());
+
+        #[cfg(feature = "std")]
+        let group_budget = group_budget(&self.group_budgets, &labels);
+
+        let info = HandlerInfo::new(HandlerInfoInner {
+            name: handler_name,
+            id: HandlerId::NULL, // Filled in later.
+            type_id,
+            order: 0,    // Filled in later.
+            tiebreak: 0, // Filled in later.
+            received_events,
+            received_event_access,
+            targeted_event_component_access: config.targeted_event_component_access,
+            sent_untargeted_events: config.sent_global_events,
+            sent_targeted_events: config.sent_targeted_events,
+            event_queue_access: match config.event_queue_access {
+                MaybeInvalidAccess::Ok(access) => access,
+                // Conflicting access is reported as the strongest access.
+                MaybeInvalidAccess::Invalid => Access::ReadWrite,
+            },
+            component_access: component_access_conjunction,
+            archetype_filter: component_access_disjunction,
+            referenced_components: config.referenced_components,
+            priority: config.priority,
+            phase: config.phase,
+            labels: labels.into(),
+            runs_before: config.runs_before.into(),
+            runs_after: config.runs_after.into(),
+            enabled,
+            #[cfg(feature = "metrics")]
+            metrics: Default::default(),
+            parallel: config.parallel,
+            receives_lifecycle_events: config.receives_lifecycle_events,
+            refreshes_archetypes,
+            #[cfg(feature = "std")]
+            watchdog: Watchdog {
+                group_budget,
+                ..Default::default()
+            },
+            handler,
+        });
+
+        Ok(info)
+    }
+
+    /// Adds a new handler to the world, returns its [`HandlerId`], and sends
+    /// the [`AddHandler`] event to signal its creation.
+    ///
+    /// If the handler already exists (as determined by [`Handler::type_id`])
+    /// then the `HandlerId` of the existing handler is returned and no
+    /// event is sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration of the handler is invalid. This can occur
+    /// when, for instance, the handler does not specify an event to receive.
+    ///
+    /// ```should_panic
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// world.add_handler(|| {}); // Panics
+    /// ```
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// # #[derive(GlobalEvent)]
+    /// # struct MyEvent;
+    ///
+    /// fn my_handler(_: Receiver<MyEvent>) {};
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_handler(my_handler);
+    ///
+    /// assert!(world.handlers().contains(id));
+    /// ```
+    ///
+    /// [`Handler::type_id`]: crate::handler::Handler::type_id
+    #[track_caller]
+    pub fn add_handler<H: IntoHandler<M>, M>(&mut self, handler: H) -> HandlerId {
+        match self.try_add_handler(handler) {
+            Ok(id) => id,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Adds a handler to the world which may run at the same time as other
+    /// parallel handlers, and returns its [`HandlerId`].
+    ///
+    /// When an event is broadcast, consecutive parallel handlers of the same
+    /// priority which don't access the same components mutably and aren't
+    /// ordered relative to each other by [labels] are run on the rayon thread
+    /// pool. The broadcast continues once all of them have finished. Other
+    /// handlers run sequentially in the usual order.
+    ///
+    /// Parallel handlers can't send events, mutate or take the received event,
+    /// or spawn tasks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler fails to initialize or doesn't meet the
+    /// requirements above. See [`World::add_handler`].
+    ///
+    /// # Safety
+    ///
+    /// The event and the components accessed by the handler must be [`Sync`],
+    /// and the components it accesses mutably must also be [`Send`]. Custom
+    /// [`HandlerParam`]s of the handler must not access the world other than
+    /// through the access they declare in [`HandlerParam::init`], unless they
+    /// call [`HandlerConfig::set_thread_local`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // Both handlers run at the same time, because they access different
+    /// // components.
+    /// unsafe {
+    ///     world.add_parallel_handler(|_: Receiver<Tick>, f: Fetcher<&mut Position>| {
+    ///         for pos in f {
+    ///             pos.0 += 1.0;
+    ///         }
+    ///     });
+    ///
+    ///     world.add_parallel_handler(|_: Receiver<Tick>, f: Fetcher<&mut Health>| {
+    ///         for health in f {
+    ///             health.0 += 1;
+    ///         }
+    ///     });
+    /// }
+    ///
+    /// world.send(Tick);
+    /// ```
+    ///
+    /// [labels]: crate::handler::IntoHandler::label
+    /// [`HandlerParam`]: crate::handler::HandlerParam
+    /// [`HandlerParam::init`]: crate::handler::HandlerParam::init
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    #[track_caller]
+    pub unsafe fn add_parallel_handler<H, M>(&mut self, handler: H) -> HandlerId
+    where
+        H: IntoHandler<M>,
+        H::Handler: Send,
+    {
+        self.add_handler(Parallel(handler.into_handler()))
+    }
+
+    /// Adds a handler with explicit state to the world, and returns its
+    /// [`HandlerId`].
+    ///
+    /// `handler` is a function whose first parameter is a mutable reference
+    /// to the state, followed by [`HandlerParam`]s. The state is owned by the
+    /// handler, and can be accessed with [`World::handler_state`] and
+    /// [`World::handler_state_mut`].
+    ///
+    /// Unlike [`World::add_handler`], adding the same function more than once
+    /// adds separate handlers, each with its own state. To combine explicit
+    /// state with the methods of [`IntoHandler`], use [`WithState`] instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler fails to initialize. See [`World::add_handler`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Score(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_handler_with(vec![], |history: &mut Vec<u32>, r: Receiver<Score>| {
+    ///     history.push(r.event.0);
+    /// });
+    ///
+    /// world.send(Score(10));
+    /// world.send(Score(20));
+    ///
+    /// assert_eq!(world.handler_state::<Vec<u32>>(id).unwrap(), &[10, 20]);
+    /// ```
+    ///
+    /// [`HandlerParam`]: crate::handler::HandlerParam
+    #[track_caller]
+    pub fn add_handler_with<S, F, M>(&mut self, state: S, handler: F) -> HandlerId
+    where
+        WithState<S, F>: HandlerParamFunction<M>,
+        M: 'static,
+    {
+        self.add_handler(WithState::new(state, handler).no_type_id())
+    }
+
+    /// Returns a reference to the explicit state of the given handler, or
+    /// `None` if the handler doesn't exist or its state isn't of type `S`.
+    ///
+    /// See [`World::add_handler_with`].
+    pub fn handler_state<S: 'static>(&self, handler: HandlerId) -> Option<&S> {
+        self.handlers
+            .get(handler)?
+            .handler()
+            .state()?
+            .downcast_ref()
+    }
+
+    /// Returns a mutable reference to the explicit state of the given handler,
+    /// or `None` if the handler doesn't exist or its state isn't of type `S`.
+    ///
+    /// See [`World::add_handler_with`].
+    pub fn handler_state_mut<S: 'static>(&mut self, handler: HandlerId) -> Option<&mut S> {
+        self.handlers
+            .get_mut(handler)?
+            .handler_mut()
+            .state_mut()?
+            .downcast_mut()
+    }
+
+    /// Returns a [`HandlerDescription`] of the given handler, with the events
+    /// and components it uses resolved to their names. Returns `None` if the
+    /// handler doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::access::Access;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Moved;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_handler(
+    ///     |_: Receiver<Tick>, f: Fetcher<&mut Position>, s: Sender<Moved>| {
+    ///         for pos in f {
+    ///             pos.0 += 1.0;
+    ///             s.send(Moved);
+    ///         }
+    ///     },
+    /// );
+    ///
+    /// let desc = world.describe_handler(id).unwrap();
+    ///
+    /// assert!(desc.received_events[0].name.ends_with("Tick"));
+    /// assert!(desc.sent_events[0].name.ends_with("Moved"));
+    /// assert_eq!(desc.event_queue_access, Access::ReadWrite);
+    ///
+    /// let (component, access) = desc.component_access[0];
+    /// assert!(component.name.ends_with("Position"));
+    /// assert_eq!(access, Access::ReadWrite);
+    /// ```
+    pub fn describe_handler(&self, handler: HandlerId) -> Option<HandlerDescription<'_>> {
+        Some(HandlerDescription::new(
+            self.handlers.get(handler)?,
+            &self.components,
+            &self.global_events,
+            &self.targeted_events,
+        ))
+    }
+
+    /// Returns the order in which the handlers of `event` run, as a
+    /// [`HandlerGraph`] of the handlers and the constraints ordering them.
+    /// Returns `None` if the event doesn't exist.
+    ///
+    /// The handlers of a targeted event which run depend on the components
+    /// of the target, but always run in the order of the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::handler::OrderingReason;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.add_handler((|_: Receiver<Tick>| {}).label("a"));
+    /// let b = world.add_handler((|_: Receiver<Tick>| {}).before("a"));
+    /// let c = world.add_handler((|_: Receiver<Tick>| {}).high());
+    ///
+    /// let tick = world.add_global_event::<Tick>();
+    /// let graph = world.handler_graph(tick).unwrap();
+    ///
+    /// assert_eq!(graph.nodes, [c, b, a]);
+    ///
+    /// // Why does `b` run before `a`?
+    /// let edge = graph.edges_between(a, b).next().unwrap();
+    /// assert_eq!(edge.reason, OrderingReason::Before("a".into()));
+    ///
+    /// // Why does `c` run before `b`?
+    /// let edge = graph.edges_between(b, c).next().unwrap();
+    /// assert_eq!(edge.reason, OrderingReason::Priority);
+    ///
+    /// println!("{}", graph.to_dot(world.handlers()));
+    /// ```
+    pub fn handler_graph<E: Into<EventId>>(&self, event: E) -> Option<HandlerGraph> {
+        let event = event.into();
+
+        match event {
+            EventId::Global(id) => {
+                if !self.global_events.contains(id) {
+                    return None;
+                }
+
+                let list = self
+                    .handlers
+                    .get_global_list(id.index())
+                    .unwrap_or(&EMPTY_HANDLER_LIST);
+
+                Some(HandlerGraph::new(event, list.slice()))
+            }
+            EventId::Targeted(id) => {
+                if !self.targeted_events.contains(id) {
+                    return None;
+                }
+
+                let mut list = HandlerList::new();
+
+                for info in self.handlers.iter() {
+                    if info.received_events().contains(&event) {
+                        list.insert(info.ptr());
+                    }
+                }
+
+                list.sort();
+
+                Some(HandlerGraph::new(event, list.slice()))
+            }
+        }
+    }
+
+    /// Adds the event `E` to the category identified by the type `C`.
+    ///
+    /// Every handler added to the category with
+    /// [`World::add_category_handler`], including those added before this
+    /// call, receives `E`. Adding an event to a category more than once has no
+    /// effect.
+    ///
+    /// See [`World::add_category_handler`] for an example.
+    pub fn add_to_category<C: 'static, E: Event>(&mut self) {
+        let id = self.event_id_of::<E>();
+
+        let members = self.categories.get_or_insert(TypeId::of::<C>());
+
+        if members.events.contains(&id) {
+            return;
+        }
+
+        members.events.push(id);
+
+        for func in members.subscribers.clone() {
+            self.add_handler(CategoryHandler::new(any::type_name::<C>(), id, func));
+        }
+    }
+
+    /// Adds a handler which receives every event in the category identified
+    /// by the type `C`.
+    ///
+    /// Events are added to categories with [`World::add_to_category`]. The
+    /// handler receives a type-erased [`CategoryEvent`], which can be
+    /// downcast to the concrete event type. Internally, one handler is added
+    /// for every event in the category.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// struct Analytics;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Click;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Purchase(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_to_category::<Analytics, Click>();
+    /// world.add_to_category::<Analytics, Purchase>();
+    ///
+    /// world.add_category_handler::<Analytics, _>(|e| {
+    ///     println!("{}", e.name());
+    ///
+    ///     if let Some(p) = e.downcast_ref::<Purchase>() {
+    ///         println!("price: {}", p.0);
+    ///     }
+    /// });
+    ///
+    /// world.send(Click);
+    /// world.send(Purchase(10));
+    /// ```
+    pub fn add_category_handler<C: 'static, F: FnMut(CategoryEvent) + 'static>(&mut self, f: F) {
+        let func: CategoryFn = Rc::new(RefCell::new(f));
+
+        let members = self.categories.get_or_insert(TypeId::of::<C>());
+
+        members.subscribers.push(func.clone());
+
+        for id in members.events.clone() {
+            let exists = match id {
+                EventId::Global(id) => self.global_events.contains(id),
+                EventId::Targeted(id) => self.targeted_events.contains(id),
+            };
+
+            if exists {
+                self.add_handler(CategoryHandler::new(
+                    any::type_name::<C>(),
+                    id,
+                    func.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Adds one handler for every event in the tuple `S`, all of which call
+    /// into the same `handler`, and returns their [`HandlerId`]s in the order
+    /// of `S`.
+    ///
+    /// This is useful for cross-cutting concerns such as logging, where the
+    /// same code handles many event types. `handler` implements
+    /// [`HandleEvent`] for each event, typically with a blanket
+    /// implementation bounded by a trait the events share. Targeted events
+    /// are received regardless of the components of their target.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fmt::Debug;
+    ///
+    /// use evenio::handler::HandleEvent;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent, Debug)]
+    /// struct A;
+    ///
+    /// #[derive(GlobalEvent, Debug)]
+    /// struct B(u32);
+    ///
+    /// struct Logger;
+    ///
+    /// impl<E: GlobalEvent + Debug> HandleEvent<E> for Logger {
+    ///     fn handle(&mut self, event: &E) {
+    ///         println!("{event:?}");
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler_for_events::<(A, B), _>(Logger);
+    ///
+    /// world.send(A); // Prints "A".
+    /// world.send(B(1)); // Prints "B(1)".
+    /// ```
+    ///
+    /// [`HandleEvent`]: crate::handler::HandleEvent
+    pub fn add_handler_for_events<S, F: HandleEvents<S>>(&mut self, handler: F) -> Vec<HandlerId> {
+        let mut ids = vec![];
+        F::add_handlers(Rc::new(RefCell::new(handler)), self, &mut ids);
+        ids
+    }
+
+    /// Adds a handler to the world which is owned by the entity `owner`, and
+    /// returns its [`HandlerId`].
+    ///
+    /// The handler stops running as soon as `owner` is despawned, and is
+    /// removed with [`World::remove_handler`] after the flush of the event
+    /// queue which despawned it has finished. If `owner` doesn't exist, the
+    /// handler is removed right away.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler fails to initialize. See [`World::add_handler`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let npc = world.spawn();
+    ///
+    /// let handler = world.add_owned_handler(npc, |_: Receiver<Tick>| println!("thinking"));
+    ///
+    /// world.send(Tick); // Prints "thinking".
+    ///
+    /// world.despawn(npc);
+    ///
+    /// assert!(!world.handlers().contains(handler));
+    /// ```
+    pub fn add_owned_handler<H: IntoHandler<M>, M>(
+        &mut self,
+        owner: EntityId,
+        handler: H,
+    ) -> HandlerId {
+        let id = self.add_handler(handler);
+
+        if self.entities.contains(owner) {
+            self.handler_owners.entry(owner).or_default().push(id);
+        } else {
+            self.remove_handler(id);
+        }
+
+        id
+    }
+
+    /// Adds a handler to the world which is removed after it runs for the
+    /// first time, and returns its [`HandlerId`].
+    ///
+    /// The handler is removed with [`World::remove_handler`] after the flush
+    /// of the event queue it ran in has finished. Unlike [`World::add_handler`],
+    /// adding the same handler more than once adds separate handlers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler fails to initialize. See [`World::add_handler`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct LevelLoaded;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let handler = world.add_handler_once(|_: Receiver<LevelLoaded>| println!("welcome!"));
+    ///
+    /// world.send(LevelLoaded); // Prints "welcome!".
+    /// world.send(LevelLoaded); // Prints nothing.
+    ///
+    /// assert!(!world.handlers().contains(handler));
+    /// ```
+    pub fn add_handler_once<H: IntoHandler<M>, M>(&mut self, handler: H) -> HandlerId {
+        self.add_handler(Once::new(handler.into_handler()))
+    }
+
+    /// Removes a handler from the world, returns its [`HandlerInfo`], and sends
+    /// the [`RemoveHandler`] event. If the `handler` ID is invalid, then `None`
+    /// is returned and no event is sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// # #[derive(GlobalEvent)]
+    /// # struct MyEvent;
+    /// let handler_id = world.add_handler(|_: Receiver<MyEvent>| {});
+    ///
+    /// let info = world.remove_handler(handler_id).unwrap();
+    ///
+    /// assert_eq!(info.id(), handler_id);
+    /// assert!(!world.handlers().contains(handler_id));
+    /// ```
+    pub fn remove_handler(&mut self, handler: HandlerId) -> Option<HandlerInfo> {
+        if !self.handlers.contains(handler) {
+            return None;
+        }
+
+        self.send(RemoveHandler(handler));
+
+        let info = self.handlers.remove(handler).unwrap();
+
+        self.archetypes.remove_handler(&info);
+
+        self.handler_owners.retain(|_, handlers| {
+            handlers.retain(|&h| h != handler);
+            !handlers.is_empty()
+        });
+
+        Some(info)
+    }
+
+    /// Replaces the handler `id` with `handler`, and returns the [`HandlerInfo`]
+    /// of the replaced handler. The new handler keeps the `HandlerId`, the
+    /// place in the handler order, the priority, phase, labels and ordering
+    /// constraints of the replaced one. This is intended for reloading
+    /// handlers while the application is running.
+    ///
+    /// Labels and ordering constraints set on `handler` are added to the
+    /// preserved ones, and a priority or phase set on `handler` replaces the
+    /// preserved one. No [`AddHandler`] or [`RemoveHandler`] events are sent.
+    ///
+    /// Returns `None` and leaves the world unchanged if `id` is invalid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration of `handler` is invalid, or if another
+    /// handler with the same [`Handler::type_id`] exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_handler((|_: Receiver<Tick>| println!("old")).label("game"));
+    ///
+    /// world.replace_handler(id, |_: Receiver<Tick>| println!("new"));
+    ///
+    /// assert_eq!(world.handlers()[id].labels(), ["game"]);
+    ///
+    /// world.send(Tick); // Prints "new".
+    /// ```
+    ///
+    /// [`Handler::type_id`]: crate::handler::Handler::type_id
+    #[track_caller]
+    pub fn replace_handler<H: IntoHandler<M>, M>(
+        &mut self,
+        id: HandlerId,
+        handler: H,
+    ) -> Option<HandlerInfo> {
+        let old = self.handlers.get(id)?;
+
+        let config = HandlerConfig {
+            priority: old.priority(),
+            phase: old.phase(),
+            labels: old.labels().to_vec(),
+            runs_before: old.runs_before().to_vec(),
+            runs_after: old.runs_after().to_vec(),
+            ..Default::default()
+        };
+
+        let handler = handler.into_handler();
+
+        if let Some(type_id) = handler.type_id() {
+            if let Some(other) = self.handlers.get_by_type_id(type_id) {
+                assert_eq!(
+                    other.id(),
+                    id,
+                    "handler {} already exists in the world",
+                    other.name()
+                );
+            }
+        }
+
+        let info = match self.init_handler(handler, config) {
+            Ok(info) => info,
+            Err(e) => panic!("{e}"),
+        };
+
+        self.archetypes.remove_handler(&self.handlers[id]);
+
+        let old = self.handlers.replace(id, info);
+        let info = self.handlers.get_mut(id).unwrap();
+
+        #[cfg(feature = "std")]
+        {
+            info.watchdog_mut().budget = old.watchdog().budget;
+        }
+
+        self.archetypes.register_handler(info);
+
+        Some(old)
+    }
+
+    /// Shuffles the order in which handlers of the same priority run,
+    /// pseudo-randomly from `seed`. Passing `None` restores the default order,
+    /// which is the order the handlers were added in.
+    ///
+    /// The shuffled order also applies to handlers added afterwards. This is a
+    /// debugging aid for finding handlers which depend on the order of other
+    /// handlers without declaring it with a priority. See
+    /// [`EventLog::find_order_dependence`].
+    pub fn shuffle_handler_order(&mut self, seed: Option<u64>) {
+        self.handlers.shuffle(seed);
+        self.archetypes.sort_handler_lists();
+    }
+
+    /// Enables or disables the group of handlers with the given label, as
+    /// added by [`IntoHandler::label`]. Disabled handlers are skipped when
+    /// events are broadcast, but keep their [`HandlerId`]s and their place in
+    /// the handler order.
+    ///
+    /// A handler with several labels only runs if all of its groups are
+    /// enabled. Handlers added to a disabled group later are disabled as
+    /// well. All groups are enabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler((|_: Receiver<Tick>| println!("thinking")).label("ai"));
+    ///
+    /// world.set_group_enabled("ai", false);
+    /// world.send(Tick); // Prints nothing.
+    ///
+    /// world.set_group_enabled("ai", true);
+    /// world.send(Tick); // Prints "thinking".
+    /// ```
+    pub fn set_group_enabled<L>(&mut self, label: L, enabled: bool)
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        let label = label.into();
+
+        if enabled {
+            self.disabled_groups.retain(|l| *l != label);
+        } else if !self.disabled_groups.contains(&label) {
+            self.disabled_groups.push(label);
+        }
+
+        for info in self.handlers.iter_mut() {
+            let enabled = !info
+                .labels()
+                .iter()
+                .any(|l| self.disabled_groups.contains(l));
+
+            info.set_enabled(enabled);
+        }
+    }
+
+    /// Returns `true` unless the group of handlers with the given label was
+    /// disabled with [`World::set_group_enabled`].
+    pub fn is_group_enabled(&self, label: &str) -> bool {
+        !self.disabled_groups.iter().any(|l| l == label)
+    }
+
+    /// Sets the time budget of a handler, or removes it if `budget` is
+    /// `None`. The handler's own budget takes precedence over the budgets of
+    /// its groups set with [`World::set_group_budget`].
+    ///
+    /// Whenever a run of the handler takes longer than its budget, a
+    /// [`HandlerOverBudget`] event with the measured time is sent. If the
+    /// budget was created with [`HandlerBudget::skip_next_tick`], the handler
+    /// is also skipped until the world is next advanced with [`World::advance`]
+    /// or [`World::advance_time`]. The budget is soft: a running handler is
+    /// never interrupted.
+    ///
+    /// Has no effect if the handler doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use evenio::handler::{HandlerBudget, HandlerOverBudget};
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let slow = world.add_handler(|_: Receiver<Tick>| {
+    ///     std::thread::sleep(Duration::from_millis(2));
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<HandlerOverBudget>| {
+    ///     println!("{:?} took {:?}", r.event.handler, r.event.duration);
+    /// });
+    ///
+    /// world.set_handler_budget(slow, Some(HandlerBudget::new(Duration::from_millis(1))));
+    /// world.send(Tick); // Prints "... took 2...ms".
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn set_handler_budget(&mut self, id: HandlerId, budget: Option<HandlerBudget>) {
+        if let Some(info) = self.handlers.get_mut(id) {
+            info.watchdog_mut().budget = budget;
+        }
+    }
+
+    /// Sets the time budget of every handler with the given label, including
+    /// handlers added later, or removes it if `budget` is `None`. Handlers
+    /// with several budgeted labels use the smallest budget. See
+    /// [`World::set_handler_budget`].
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn set_group_budget<L>(&mut self, label: L, budget: Option<HandlerBudget>)
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        let label = label.into();
+
+        self.group_budgets.retain(|(l, _)| *l != label);
+
+        if let Some(budget) = budget {
+            self.group_budgets.push((label, budget));
+        }
+
+        for info in self.handlers.iter_mut() {
+            let group_budget = group_budget(&self.group_budgets, info.labels());
+            info.watchdog_mut().group_budget = group_budget;
+        }
+    }
+
+    /// Computes the [`ComponentAccess`] of the query `Q`.
+    ///
+    /// This describes which components `Q` reads and writes, and which
+    /// archetypes it matches. Any components referenced by `Q` are added to the
+    /// world if they don't exist yet. To get the combined access of a
+    /// registered handler, use [`HandlerInfo::component_access`].
+    ///
+    /// [`HandlerInfo::component_access`]: crate::handler::HandlerInfo::component_access
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InitError`] if the query fails to initialize.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::access::Access;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct A;
+    ///
+    /// #[derive(Component)]
+    /// struct B;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let ca = world.query_access::<(&mut A, With<&B>)>().unwrap();
+    ///
+    /// let a = world.add_component::<A>().index();
+    /// let b = world.add_component::<B>().index();
+    ///
+    /// assert_eq!(ca.access(a), Access::ReadWrite);
+    /// assert_eq!(ca.access(b), Access::None);
+    /// assert_eq!(ca.accessed_components(), [(a, Access::ReadWrite)]);
+    /// ```
+    pub fn query_access<Q: Query>(&mut self) -> Result<ComponentAccess, InitError> {
+        let (ca, _) = Q::init(self, &mut HandlerConfig::new())?;
+        Ok(ca)
+    }
+
+    /// Adds the component `C` to the world, returns its [`ComponentId`], and
+    /// sends the [`AddComponent`] event to signal its creation.
+    ///
+    /// If the component already exists, then the [`ComponentId`] of the
+    /// existing component is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct MyComponent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_component::<MyComponent>();
+    ///
+    /// assert_eq!(id, world.add_component::<MyComponent>());
+    /// ```
+    pub fn add_component<C: Component>(&mut self) -> ComponentId {
+        let desc = ComponentDescriptor {
+            name: any::type_name::<C>().into(),
+            type_id: Some(TypeId::of::<C>()),
+            layout: Layout::new::<C>(),
+            drop: drop_fn_of::<C>(),
+            mutability: Mutability::of::<C::Mutability>(),
+        };
+
+        unsafe { self.add_component_with_descriptor(desc) }
+    }
+
+    /// Adds a component described by a given [`ComponentDescriptor`].
+    ///
+    /// Like [`add_component`], an [`AddComponent`] event is sent if the
+    /// component is newly added. If the [`TypeId`] of the component matches an
+    /// existing component, then the existing component's [`ComponentId`] is
+    /// returned and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the component is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID.
+    /// - Drop function must be safe to call with a pointer to the component as
+    ///   described by [`DropFn`]'s documentation.
+    ///
+    /// [`add_component`]: World::add_component
+    pub unsafe fn add_component_with_descriptor(
+        &mut self,
+        desc: ComponentDescriptor,
+    ) -> ComponentId {
+        let (id, is_new) = self.components.add(desc);
+
+        if is_new {
+            self.send(AddComponent(id));
+        }
+
+        id
+    }
+
+    /// Removes a component from the world and returns its [`ComponentInfo`]. If
+    /// the `component` ID is invalid, then `None` is returned and the function
+    /// has no effect.
+    ///
+    /// Removing a component has the following effects in the order listed:
+    /// 1. The [`RemoveComponent`] event is sent.
+    /// 2. All entities with the component are despawned.
+    /// 3. All handlers that reference the component are removed.
+    /// 4. The corresponding [`Insert`] events for the component are removed.
+    /// 5. The corresponding [`Remove`] events for the component are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # let mut world = World::new();
+    /// # #[derive(Component)] struct C;
+    /// # #[derive(GlobalEvent)] struct E;
+    /// #
+    /// let component = world.add_component::<C>();
+    /// let handler = world.add_handler(|_: Receiver<E>, _: Fetcher<&C>| {});
+    ///
+    /// assert!(world.components().contains(component));
+    /// assert!(world.handlers().contains(handler));
+    ///
+    /// world.remove_component(component);
+    ///
+    /// assert!(!world.components().contains(component));
+    /// // Handler was also removed because it references `C` in its `Fetcher`.
+    /// assert!(!world.handlers().contains(handler));
+    /// ```
+    pub fn remove_component(&mut self, component: ComponentId) -> Option<ComponentInfo> {
+        if !self.components.contains(component) {
+            return None;
+        }
+
+        // Send event first.
+        self.send(RemoveComponent(component));
+
+        let despawn_idx = self.add_targeted_event::<Despawn>().index();
+
+        // Attempt to despawn all entities that still have this component.
+        for arch in self.archetypes.iter() {
+            if arch.column_of(component.index()).is_some() {
+                for &entity_id in arch.entity_ids() {
+                    self.event_queue.push(EventQueueItem {
+                        meta: EventMeta::Targeted {
+                            idx: despawn_idx,
+                            target: entity_id,
+                        },
+                        event: NonNull::from(
+                            self.bump
+                                .alloc(Despawn::because(DespawnReason::COMPONENT_REMOVED)),
+                        )
+                        .cast(),
+                        depth: 0,
+                        transaction: 0,
+                    });
+                }
+            }
+        }
+
+        self.flush_event_queue();
+
+        // Remove all handlers that reference this component.
+        let mut handlers_to_remove = vec![];
+
+        for handler in self.handlers.iter() {
+            if handler.references_component(component.index()) {
+                handlers_to_remove.push(handler.id());
+            }
+        }
+
+        for handler_id in handlers_to_remove {
+            self.remove_handler(handler_id);
+        }
+
+        let info = &self.components[component];
+
+        // Remove all the `Insert` and `Remove` events for this component.
+        let events_to_remove = info
+            .insert_events()
+            .iter()
+            .copied()
+            .chain(info.remove_events().iter().copied())
+            .collect::<Vec<_>>();
+
+        for event in events_to_remove {
+            self.remove_targeted_event(event);
+        }
+
+        // Remove all the `SpawnWith` events of bundles with this component.
+        let events_to_remove = self
+            .bundles
+            .iter()
+            .filter(|(_, bundle)| bundle.component_indices.contains(&component.index()))
+            .filter_map(|(&type_id, _)| self.global_events.get_by_type_id(type_id))
+            .map(|info| info.id())
+            .collect::<Vec<_>>();
+
+        for event in events_to_remove {
+            self.remove_global_event(event);
+        }
+
+        let mut info = self
+            .components
+            .remove(component)
+            .expect("component should still exist");
+
+        // Remove all archetypes with this component. If there are still entities with
+        // the component by this point, then they will be silently removed.
+        self.archetypes
+            .remove_component(&mut info, &mut self.components, |id| {
+                self.entities.remove(id);
+            });
+
+        Some(info)
+    }
+
+    /// Adds the global event `E` to the world, returns its [`GlobalEventId`],
+    /// and sends the [`AddGlobalEvent`] event to signal its creation.
+    ///
+    /// If the event already exists, then the [`GlobalEventId`] of the existing
+    /// event is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_global_event::<MyEvent>();
+    ///
+    /// assert_eq!(id, world.add_global_event::<MyEvent>());
+    /// ```
+    pub fn add_global_event<E: GlobalEvent>(&mut self) -> GlobalEventId {
+        let desc = EventDescriptor::new::<E>(self);
+        unsafe { self.add_global_event_with_descriptor(desc) }
+    }
+
+    /// Adds the targeted event `E` to the world, returns its
+    /// [`TargetedEventId`], and sends the [`AddTargetedEvent`] event to
+    /// signal its creation.
+    ///
+    /// If the event already exists, then the [`TargetedEventId`] of the
+    /// existing event is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(TargetedEvent)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_targeted_event::<MyEvent>();
+    ///
+    /// assert_eq!(id, world.add_targeted_event::<MyEvent>());
+    /// ```
+    pub fn add_targeted_event<E: TargetedEvent>(&mut self) -> TargetedEventId {
+        let desc = EventDescriptor::new::<E>(self);
+        unsafe { self.add_targeted_event_with_descriptor(desc) }
+    }
+
+    /// Adds a global event described by a given [`EventDescriptor`].
+    ///
+    /// Like [`add_global_event`], an [`AddGlobalEvent`] event is sent if the
+    /// event is newly added. If the [`TypeId`] of the event matches an
+    /// existing global event, then the existing event's [`GlobalEventId`] is
+    /// returned and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID.
+    /// - Drop function must be safe to call with a pointer to the event as
+    ///   described by [`DropFn`]'s documentation.
+    /// - The event's kind must be correct for the descriptor. See [`EventKind`]
+    ///   for more information.
+    ///
+    /// [`add_global_event`]: World::add_global_event
+    pub unsafe fn add_global_event_with_descriptor(
+        &mut self,
+        desc: EventDescriptor,
+    ) -> GlobalEventId {
+        let (id, is_new) = self.global_events.add(desc);
+
+        if is_new {
+            self.handlers.register_event(id.index());
+            self.send(AddGlobalEvent(id));
+        }
+
+        id
+    }
+
+    /// Adds a targeted event described by a given [`EventDescriptor`].
+    ///
+    /// Like [`add_targeted_event`], an [`AddTargetedEvent`] event is sent if
+    /// the event is newly added. If the [`TypeId`] of the event matches an
+    /// existing targeted event, then the existing event's [`GlobalEventId`] is
+    /// returned and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID.
+    /// - Drop function must be safe to call with a pointer to the event as
+    ///   described by [`DropFn`]'s documentation.
+    /// - The event's kind must be correct for the descriptor. See [`EventKind`]
+    ///   for more information.
+    ///
+    /// [`add_targeted_event`]: World::add_targeted_event
+    pub unsafe fn add_targeted_event_with_descriptor(
+        &mut self,
+        desc: EventDescriptor,
+    ) -> TargetedEventId {
+        let kind = desc.kind;
+        let type_id = desc.type_id;
+
+        let (id, is_new) = self.targeted_events.add(desc);
+
+        if is_new {
+            match kind {
+                EventKind::Normal => {}
+                EventKind::Insert { component_idx } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.insert_events.insert(id);
+                    }
+                }
+                EventKind::Remove { component_idx } | EventKind::Take { component_idx, .. } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.remove_events.insert(id);
+                    }
+                }
+                EventKind::Spawn => {}
+                EventKind::SpawnWith => {}
+                EventKind::Despawn => {}
+                EventKind::InsertBundle => {
+                    let bundle = type_id.and_then(|type_id| self.bundles.get(&type_id));
+
+                    for &component_idx in bundle.map_or(&[][..], |b| &b.component_indices) {
+                        if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                            info.insert_events.insert(id);
+                        }
+                    }
+                }
+            }
+
+            if kind.is_lifecycle() {
+                self.update_lifecycle_handlers(|events| events.push(id.into()));
+            }
+
+            self.send(AddTargetedEvent(id))
+        }
+
+        id
+    }
+
+    /// Removes a global event from the world and returns its
+    /// [`GlobalEventInfo`]. If the event ID is invalid, then `None` is
+    /// returned and the function has no effect.
+    ///
+    /// Removing an event has the following additional effects in the order
+    /// listed:
+    /// 1. The [`RemoveTargetedEvent`] event is sent.
+    /// 2. All handlers that send or receive the event are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(GlobalEvent)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_global_event::<MyEvent>();
+    /// world.remove_global_event(id);
+    ///
+    /// assert!(!world.global_events().contains(id));
+    /// ```
+    pub fn remove_global_event(&mut self, event: GlobalEventId) -> Option<GlobalEventInfo> {
+        assert!(self.event_queue.is_empty());
+
+        if !self.global_events.contains(event) {
+            return None;
+        }
+
+        // Send event before removing anything.
+        self.send(RemoveGlobalEvent(event));
+
+        // Remove all handlers that send or receive this event.
+        let mut to_remove = vec![];
+
+        for handler in self.handlers.iter() {
+            if handler.received_events().contains(&event.into())
+                || handler.sent_global_events_bitset().contains(event.index())
+            {
+                to_remove.push(handler.id());
+            }
+        }
+
+        for id in to_remove {
+            self.remove_handler(id);
+        }
+
+        let info = self.global_events.remove(event).unwrap();
+
+        self.rate_limits.remove(event.into());
+
+        if info.kind() == EventKind::SpawnWith {
+            // The bundle is added again with fresh component indices if the
+            // event is added again.
+            if let Some(type_id) = info.type_id() {
+                self.bundles.remove(&type_id);
+            }
+        }
+
+        Some(info)
+    }
+
+    /// Removes a targeted event from the world and returns its
+    /// [`TargetedEventInfo`]. If the event ID is invalid, then `None` is
+    /// returned and the function has no effect.
+    ///
+    /// Removing an event has the following additional effects in the order
+    /// listed:
+    /// 1. The [`RemoveTargetedEvent`] event is sent.
+    /// 2. All handlers that send or receive the event are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(TargetedEvent)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_targeted_event::<MyEvent>();
+    /// world.remove_targeted_event(id);
+    ///
+    /// assert!(!world.targeted_events().contains(id));
+    /// ```
+    pub fn remove_targeted_event(&mut self, event: TargetedEventId) -> Option<TargetedEventInfo> {
+        assert!(self.event_queue.is_empty());
+
+        if !self.targeted_events.contains(event) {
+            return None;
+        }
+
+        // Send event before doing anything else.
+        self.send(RemoveTargetedEvent(event));
+
+        // Remove all handlers that send or receive this event, except for
+        // handlers receiving the lifecycle events of all components, which
+        // just stop receiving it.
+        let mut to_remove = vec![];
+
+        for handler in self.handlers.iter() {
+            if handler
+                .sent_targeted_events_bitset()
+                .contains(event.index())
+                || (handler.received_events().contains(&event.into())
+                    && !handler.receives_lifecycle_events())
+            {
+                to_remove.push(handler.id());
+            }
+        }
+
+        for id in to_remove {
+            self.remove_handler(id);
+        }
+
+        self.update_lifecycle_handlers(|events| events.retain(|&e| e != event.into()));
+
+        let info = self.targeted_events.remove(event).unwrap();
+
+        self.rate_limits.remove(event.into());
+
+        // `Take` events can't send their component without the removed event.
+        let takes: Vec<_> = self
+            .targeted_events
+            .iter()
+            .filter(|info| {
+                matches!(info.kind(), EventKind::Take { taken_idx, .. } if taken_idx == event.index())
+            })
+            .map(|info| info.id())
+            .collect();
+
+        for id in takes {
+            self.remove_targeted_event(id);
+        }
+
+        match info.kind() {
+            EventKind::Normal => {}
+            EventKind::Insert { component_idx } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    info.insert_events.remove(&event);
+                }
+            }
+            EventKind::Remove { component_idx } | EventKind::Take { component_idx, .. } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    info.remove_events.remove(&event);
+                }
+            }
+            EventKind::Spawn => {}
+            EventKind::SpawnWith => {}
+            EventKind::Despawn => {}
+            EventKind::InsertBundle => {
+                // The bundle is added again with fresh component indices if
+                // the event is added again.
+                let bundle = info
+                    .type_id()
+                    .and_then(|type_id| self.bundles.remove(&type_id));
+
+                for &component_idx in bundle.as_ref().map_or(&[][..], |b| &b.component_indices) {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.insert_events.remove(&event);
+                    }
+                }
+            }
+        }
+
+        Some(info)
+    }
+
+    /// Returns the [`Entities`] for this world.
+    pub fn entities(&self) -> &Entities {
         &self.entities
     }
 
-    /// Returns the [`Components`] for this world.  
-    pub fn components(&self) -> &Components {
-        &self.components
+    /// Returns the [`Components`] for this world.  
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Returns the [`Handlers`] for this world.
+    pub fn handlers(&self) -> &Handlers {
+        &self.handlers
+    }
+
+    /// Returns the [`Archetypes`] for this world.
+    pub fn archetypes(&self) -> &Archetypes {
+        &self.archetypes
+    }
+
+    /// Returns the [`GlobalEvents`] for this world.
+    pub fn global_events(&self) -> &GlobalEvents {
+        &self.global_events
+    }
+
+    /// Returns the [`TargetedEvents`] for this world.
+    pub fn targeted_events(&self) -> &TargetedEvents {
+        &self.targeted_events
+    }
+
+    /// Updates the received events of all handlers which receive the
+    /// lifecycle events of all components with `f`.
+    fn update_lifecycle_handlers(&mut self, mut f: impl FnMut(&mut Vec<EventId>)) {
+        let ids: Vec<_> = self
+            .handlers
+            .iter()
+            .filter(|info| info.receives_lifecycle_events())
+            .map(|info| info.id())
+            .collect();
+
+        for id in ids {
+            let info = self.handlers.get_mut(id).unwrap();
+
+            self.archetypes.remove_handler(info);
+
+            let mut events = info.received_events().to_vec();
+            f(&mut events);
+            info.set_received_events(events.into());
+
+            self.archetypes.register_handler(info);
+        }
+    }
+
+    /// Adds the [`Bundle`] `B` contained in the event `E` to the world if it
+    /// wasn't already added.
+    #[track_caller]
+    pub(crate) fn add_bundle<E: 'static, B: Bundle>(&mut self, component_ptrs: ComponentPtrsFn) {
+        let type_id = TypeId::of::<E>();
+
+        if !self.bundles.contains_key(&type_id) {
+            let info = BundleInfo::new::<B>(self, component_ptrs);
+            self.bundles.insert(type_id, info);
+        }
+    }
+
+    /// Returns the unique identifier of this world.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the cache of fetcher states shared between
+    /// [`CachedFetcher`]s.
+    ///
+    /// [`CachedFetcher`]: crate::fetch::CachedFetcher
+    pub(crate) fn fetcher_cache_mut(&mut self) -> &mut TypeIdMap<Weak<dyn any::Any>> {
+        &mut self.fetcher_cache
+    }
+
+    /// Adds the queued events to the recording in the order they'll be
+    /// broadcast. Only called before flushing, when all queued events were
+    /// sent from outside of handlers.
+    fn record_queued_events(&mut self) {
+        let tick = self.schedule.tick;
+
+        for item in self
+            .event_queue
+            .iter()
+            .rev()
+            .chain(self.deferred_queue.iter())
+        {
+            let (id, type_id, target) = match item.meta {
+                EventMeta::Global { idx } => {
+                    let info = self.global_events.get_by_index(idx).unwrap();
+                    (EventId::Global(info.id()), info.type_id(), None)
+                }
+                EventMeta::Targeted { idx, target } => {
+                    let info = self.targeted_events.get_by_index(idx).unwrap();
+                    (EventId::Targeted(info.id()), info.type_id(), Some(target))
+                }
+            };
+
+            // SAFETY: The event pointer is valid and of the type in the info.
+            unsafe { self.recorder.record(id, type_id, item.event, target, tick) };
+        }
+    }
+
+    /// Send all queued events to handlers. The event queue will be empty after
+    /// this call.
+    fn flush_event_queue(&mut self) {
+        if self.recorder.is_recording() {
+            self.record_queued_events();
+        }
+
+        let mut end_pending = !self.event_queue.is_empty() || !self.deferred_queue.is_empty();
+
+        // Once all other events have been handled, move on to the deferred events,
+        // and finally to `BroadcastEnded`.
+        'next_event: while let Some(item) = self
+            .event_queue
+            .pop()
+            .or_else(|| self.deferred_queue.pop())
+            .or_else(|| self.release_rate_limited())
+            .or_else(|| self.broadcast_ended(&mut end_pending))
+        {
+            struct EventDropper<'a> {
+                event: NonNull<u8>,
+                drop: DropFn,
+                ownership_flag: Ownership,
+                world: &'a mut World,
+            }
+
+            impl<'a> EventDropper<'a> {
+                fn new(event: NonNull<u8>, drop: DropFn, world: &'a mut World) -> Self {
+                    Self {
+                        event,
+                        drop,
+                        ownership_flag: Ownership::Borrowed,
+                        world,
+                    }
+                }
+
+                /// Extracts the event pointer and drop fn without running
+                /// the destructor.
+                #[inline]
+                fn unpack(self) -> (NonNull<u8>, DropFn) {
+                    let event = self.event;
+                    let drop = self.drop;
+                    mem::forget(self);
+
+                    (event, drop)
+                }
+
+                /// Drops the held event.
+                #[inline]
+                unsafe fn drop_event(self) {
+                    if let (event, Some(drop)) = self.unpack() {
+                        drop(event);
+                    }
+                }
+            }
+
+            impl Drop for EventDropper<'_> {
+                #[cold]
+                fn drop(&mut self) {
+                    // In case `Handler::run` unwinds, we need to drop the event we're holding on
+                    // the stack as well as the events still the in the event queue.
+
+                    #[cfg(feature = "std")]
+                    debug_assert!(std::thread::panicking());
+
+                    // Drop the held event.
+                    if self.ownership_flag == Ownership::Borrowed {
+                        if let Some(drop) = self.drop {
+                            unsafe { drop(self.event) };
+                        }
+                    }
+
+                    // Drop all events remaining in the event queues.
+                    // This must be done here instead of the World's destructor because events
+                    // could contain borrowed data.
+                    for item in self
+                        .world
+                        .event_queue
+                        .iter()
+                        .chain(self.world.deferred_queue.iter())
+                    {
+                        let drop = match item.meta {
+                            EventMeta::Global { idx } => unsafe {
+                                self.world
+                                    .global_events
+                                    .get_by_index(idx)
+                                    .unwrap_unchecked()
+                                    .drop()
+                            },
+                            EventMeta::Targeted { idx, .. } => unsafe {
+                                self.world
+                                    .targeted_events
+                                    .get_by_index(idx)
+                                    .unwrap_unchecked()
+                                    .drop()
+                            },
+                        };
+
+                        if let Some(drop) = drop {
+                            unsafe { drop(item.event) };
+                        }
+                    }
+
+                    self.world.event_queue.clear();
+                    self.world.deferred_queue.clear();
+                    self.world.dedup.clear();
+                    self.world.rate_limits.end_flush();
+                    self.world.transaction_deferred = 0;
+                }
+            }
+
+            if item.transaction == 0 {
+                // Not inside of a transaction anymore.
+                self.transaction_deferred = 0;
+            }
+
+            if !self.rate_limits.is_empty() {
+                let id = self.event_id(item.meta);
+                let target = match item.meta {
+                    EventMeta::Global { .. } => None,
+                    EventMeta::Targeted { target, .. } => Some(target),
+                };
+                let now = self.clock.time.elapsed();
+
+                // SAFETY: The queue owns the event, which is the event with the
+                // ID.
+                if !unsafe { self.rate_limits.admit(id, item.event, target, now) } {
+                    continue;
+                }
+            }
+
+            let (mut ctx, event_kind, handlers, target_location) = match item.meta {
+                EventMeta::Global { idx } => {
+                    let info = unsafe { self.global_events.get_by_index(idx).unwrap_unchecked() };
+                    let kind = info.kind();
+                    let handlers: *const HandlerList =
+                        unsafe { self.handlers.get_global_list(idx).unwrap_unchecked() };
+                    let ctx = EventDropper::new(item.event, info.drop(), self);
+
+                    let location = EntityLocation::NULL;
+
+                    (ctx, kind, handlers, location)
+                }
+                EventMeta::Targeted { idx, target } => {
+                    let info = unsafe { self.targeted_events.get_by_index(idx).unwrap_unchecked() };
+                    let kind = info.kind();
+                    let policy = info.dead_target_policy();
+                    let id = info.id();
+                    let ctx = EventDropper::new(item.event, info.drop(), self);
+
+                    let Some(location) = ctx.world.entities.get(target) else {
+                        // The event and the rest of the queue are dropped by
+                        // `ctx` while unwinding.
+                        assert!(
+                            policy != DeadTargetPolicy::Panic,
+                            "targeted event `{}` sent to {target:?}, which doesn't exist",
+                            unsafe { ctx.world.targeted_events.get(id).unwrap_unchecked() }.name()
+                        );
+
+                        // Entity doesn't exist. Skip the event.
+                        unsafe { ctx.drop_event() };
+
+                        if policy == DeadTargetPolicy::DeadLetter {
+                            self.queue_dead_letter(item, id);
+                        }
+
+                        continue;
+                    };
+
+                    let arch = unsafe {
+                        ctx.world
+                            .archetypes
+                            .get(location.archetype)
+                            .unwrap_unchecked()
+                    };
+
+                    let handlers: *const HandlerList =
+                        arch.handler_list_for(idx).unwrap_or(&EMPTY_HANDLER_LIST);
+
+                    (ctx, kind, handlers, location)
+                }
+            };
+
+            if ctx
+                .world
+                .max_event_depth
+                .is_some_and(|max| item.depth > max)
+            {
+                unsafe { ctx.drop_event() };
+                self.queue_depth_limit_exceeded(item);
+                continue;
+            }
+
+            if !ctx.world.dedup.is_empty() {
+                let type_id = ctx.world.event_type_id(item.meta);
+                let target = match item.meta {
+                    EventMeta::Global { .. } => None,
+                    EventMeta::Targeted { target, .. } => Some(target),
+                };
+
+                if unsafe { ctx.world.dedup.is_duplicate(type_id, item.event, target) } {
+                    unsafe { ctx.drop_event() };
+                    continue;
+                }
+            }
+
+            ctx.world.event_depth = item.depth;
+
+            let suppressed = ctx.world.lifecycle_events_suppressed
+                && matches!(
+                    event_kind,
+                    EventKind::Spawn
+                        | EventKind::Insert { .. }
+                        | EventKind::Remove { .. }
+                        | EventKind::Take { .. }
+                        | EventKind::InsertBundle
+                        | EventKind::SpawnWith
+                );
+
+            let handlers: *const HandlerList = if suppressed {
+                &EMPTY_HANDLER_LIST
+            } else {
+                handlers
+            };
+
+            let intercepting = !suppressed && !ctx.world.interceptors.list.is_empty();
+
+            if intercepting {
+                let world = &mut *ctx.world;
+                let mut event =
+                    InterceptedEvent::new(&item, &world.global_events, &world.targeted_events);
+
+                if world.interceptors.before(&mut event) == Verdict::Veto {
+                    unsafe { ctx.drop_event() };
+                    continue;
+                }
+            }
+
+            let events_before = ctx.world.event_queue.len();
+
+            #[cfg(feature = "metrics")]
+            let start = std::time::Instant::now();
+
+            let catch_panics = ctx.world.catch_panics;
+            let entries: *const [_] = unsafe { (*handlers).slice() };
+            let mut i = 0;
+
+            while let Some(mut info_ptr) = unsafe { (&*entries).get(i).copied() } {
+                #[cfg(feature = "rayon")]
+                {
+                    let end = unsafe { (*handlers).parallel_end(i) };
+
+                    if end - i > 1 {
+                        let panics = unsafe {
+                            run_parallel(
+                                &(&*entries)[i..end],
+                                EventPtr::new(
+                                    ctx.event,
+                                    NonNull::from(&mut ctx.ownership_flag),
+                                    item.meta,
+                                ),
+                                target_location,
+                                ctx.world.unsafe_cell_mut(),
+                                catch_panics,
+                            )
+                        };
+
+                        for (handler, payload) in panics {
+                            #[cfg(feature = "std")]
+                            ctx.world.queue_handler_panicked(item, handler, payload);
+
+                            #[cfg(not(feature = "std"))]
+                            let _ = (handler, payload);
+                        }
+
+                        #[cfg(feature = "std")]
+                        for &(mut info_ptr) in unsafe { &(&*entries)[i..end] } {
+                            let info = unsafe { info_ptr.as_info_mut() };
+
+                            if let Some(duration) = info.watchdog_mut().overrun.take() {
+                                ctx.world.queue_over_budget(item, info, duration);
+                            }
+                        }
+
+                        i = end;
+                        continue;
+                    }
+                }
+
+                i += 1;
+
+                let info = unsafe { info_ptr.as_info_mut() };
+
+                if !info.is_enabled() {
+                    continue;
+                }
+
+                #[cfg(feature = "std")]
+                if info.watchdog().skipped_frame == Some(ctx.world.frame) {
+                    continue;
+                }
+
+                let handler: *mut dyn Handler = info.handler_mut();
+
+                let event_ptr =
+                    EventPtr::new(ctx.event, NonNull::from(&mut ctx.ownership_flag), item.meta);
+
+                let world_cell = ctx.world.unsafe_cell_mut();
+
+                #[cfg(feature = "std")]
+                let handler_start = info.is_timed().then(std::time::Instant::now);
+
+                let res = unsafe {
+                    run_handler(
+                        handler,
+                        info,
+                        event_ptr,
+                        target_location,
+                        world_cell,
+                        catch_panics,
+                    )
+                };
+
+                #[cfg(feature = "std")]
+                if let Some(start) = handler_start {
+                    info.record_run_time(start.elapsed(), ctx.world.frame);
+
+                    if let Some(duration) = info.watchdog_mut().overrun.take() {
+                        ctx.world.queue_over_budget(item, info, duration);
+                    }
+                }
+
+                if let Err(payload) = res {
+                    #[cfg(feature = "std")]
+                    ctx.world.queue_handler_panicked(item, info.id(), payload);
+
+                    #[cfg(not(feature = "std"))]
+                    let _ = payload;
+                }
+
+                // Did the handler take ownership of the event?
+                if ctx.ownership_flag != Ownership::Borrowed {
+                    let notify = ctx.ownership_flag == Ownership::TakenAndNotify;
+
+                    // Don't drop event since we don't own it anymore.
+                    ctx.unpack();
+
+                    if intercepting {
+                        let mut event = InterceptedEvent::new(
+                            &item,
+                            &self.global_events,
+                            &self.targeted_events,
+                        );
+                        event.consume();
+                        self.interceptors.after(&event);
+                    }
+
+                    #[cfg(feature = "metrics")]
+                    self.record_metrics(item.meta, start.elapsed());
+
+                    unsafe { self.order_pushed_events(&item, events_before) };
+
+                    if notify {
+                        self.queue_taken(item, info.id());
+                    }
+
+                    continue 'next_event;
+                }
+            }
+
+            unsafe { ctx.world.order_pushed_events(&item, events_before) };
+
+            #[cfg(feature = "metrics")]
+            if !suppressed {
+                ctx.world.record_metrics(item.meta, start.elapsed());
+            }
+
+            if intercepting {
+                let world = &mut *ctx.world;
+                let event =
+                    InterceptedEvent::new(&item, &world.global_events, &world.targeted_events);
+                world.interceptors.after(&event);
+            }
+
+            match event_kind {
+                EventKind::Normal => {
+                    // Ordinary event. Run drop fn.
+                    unsafe { ctx.drop_event() };
+                }
+                EventKind::Insert { component_idx } => {
+                    debug_assert_ne!(target_location, EntityLocation::NULL);
+
+                    let dst = unsafe {
+                        ctx.world.archetypes.traverse_insert(
+                            target_location.archetype,
+                            component_idx,
+                            &mut ctx.world.components,
+                            &mut ctx.world.handlers,
+                        )
+                    };
+
+                    // `Insert<C>` is `repr(transparent)`.
+                    let component_ptr = ctx.event.as_ptr().cast_const();
+
+                    if ctx.world.next_coalescable(item.meta, dst).is_some() {
+                        unsafe {
+                            ctx.world.move_entity_coalesced(
+                                item.meta,
+                                target_location,
+                                dst,
+                                vec![(component_idx, component_ptr)],
+                            )
+                        };
+                    } else {
+                        unsafe {
+                            ctx.world.archetypes.move_entity(
+                                target_location,
+                                dst,
+                                [(component_idx, component_ptr)],
+                                &mut ctx.world.entities,
+                            )
+                        };
+                    }
+
+                    // Inserted component is owned by the archetype now. We wait to unpack
+                    // in case one of the above functions panics.
+                    ctx.unpack();
+                }
+                EventKind::Remove { component_idx } => {
+                    // `Remove` doesn't need drop.
+                    let _ = ctx.unpack();
+
+                    let dst = unsafe {
+                        self.archetypes.traverse_remove(
+                            target_location.archetype,
+                            component_idx,
+                            &mut self.components,
+                            &mut self.handlers,
+                        )
+                    };
+
+                    if self.next_coalescable(item.meta, dst).is_some() {
+                        unsafe {
+                            self.move_entity_coalesced(item.meta, target_location, dst, vec![])
+                        };
+                    } else {
+                        unsafe {
+                            self.archetypes.move_entity(
+                                target_location,
+                                dst,
+                                [],
+                                &mut self.entities,
+                            )
+                        };
+                    }
+                }
+                EventKind::Take {
+                    component_idx,
+                    taken_idx,
+                } => {
+                    // `Take` doesn't need drop.
+                    let _ = ctx.unpack();
+
+                    let EventMeta::Targeted { target, .. } = item.meta else {
+                        unreachable!("`Take` is a targeted event")
+                    };
+
+                    let arch = unsafe {
+                        self.archetypes
+                            .get(target_location.archetype)
+                            .unwrap_unchecked()
+                    };
+
+                    // Nothing to take.
+                    if arch.column_of(component_idx).is_none() {
+                        continue;
+                    }
+
+                    let layout = unsafe {
+                        self.components
+                            .get_by_index(component_idx)
+                            .unwrap_unchecked()
+                    }
+                    .layout();
+
+                    // `TakenComponent<C>` is `repr(transparent)`.
+                    let taken = self.bump.alloc_layout(layout);
+
+                    let dst = unsafe {
+                        self.archetypes.traverse_remove(
+                            target_location.archetype,
+                            component_idx,
+                            &mut self.components,
+                            &mut self.handlers,
+                        )
+                    };
+
+                    unsafe {
+                        self.archetypes.move_entity_taking(
+                            target_location,
+                            dst,
+                            [],
+                            Some((component_idx, taken)),
+                            &mut self.entities,
+                        )
+                    };
+
+                    // Handled before the events sent by the handlers of `Take`.
+                    self.event_queue.push(EventQueueItem {
+                        meta: EventMeta::Targeted {
+                            idx: taken_idx,
+                            target,
+                        },
+                        event: taken,
+                        depth: item.depth + 1,
+                        transaction: 0,
+                    });
+                }
+                EventKind::Spawn => {
+                    // `Spawn` doesn't need drop.
+                    let _ = ctx.unpack();
+
+                    // Spawn all entities from the reserved entity queue.
+                    self.reserved_entities
+                        .spawn_all(&mut self.entities, |id| self.archetypes.spawn(id));
+                }
+                EventKind::SpawnWith => {
+                    let EventMeta::Global { idx } = item.meta else {
+                        unreachable!("`SpawnWith` is a global event")
+                    };
+
+                    // `SpawnWith<B>` is `repr(C)` with the entity ID as the
+                    // first field.
+                    let entity = unsafe { *ctx.event.cast::<EntityId>().as_ref() };
+
+                    if !ctx.world.pending_spawns.remove(&entity) {
+                        // The event was not sent by `spawn_with`, and may name
+                        // an existing entity.
+                        unsafe { ctx.drop_event() };
+                        continue;
+                    }
+
+                    // Spawn all entities from the reserved entity queue.
+                    ctx.world
+                        .reserved_entities
+                        .spawn_all(&mut ctx.world.entities, |id| ctx.world.archetypes.spawn(id));
+
+                    let Some(location) = ctx.world.entities.get(entity) else {
+                        // The entity was spawned early by another event and
+                        // despawned in the meantime.
+                        unsafe { ctx.drop_event() };
+                        continue;
+                    };
+
+                    let bundle = unsafe {
+                        let type_id = ctx
+                            .world
+                            .global_events
+                            .get_by_index(idx)
+                            .unwrap_unchecked()
+                            .type_id()
+                            .unwrap_unchecked();
+
+                        // Bundle was added during initialization of the event.
+                        ctx.world.bundles.get(&type_id).unwrap_unchecked()
+                    };
+
+                    let mut component_ptrs = Vec::with_capacity(bundle.component_indices.len());
+                    unsafe { (bundle.component_ptrs)(ctx.event, &mut component_ptrs) };
+
+                    let new_components = bundle
+                        .component_indices
+                        .iter()
+                        .copied()
+                        .zip(component_ptrs)
+                        .collect();
+
+                    unsafe {
+                        ctx.world.archetypes.insert_components(
+                            location,
+                            new_components,
+                            &mut ctx.world.components,
+                            &mut ctx.world.handlers,
+                            &mut ctx.world.entities,
+                        )
+                    };
+
+                    // Inserted components are owned by the archetype now. The
+                    // entity ID doesn't need drop.
+                    ctx.unpack();
+                }
+                EventKind::InsertBundle => {
+                    debug_assert_ne!(target_location, EntityLocation::NULL);
+
+                    let EventMeta::Targeted { idx, .. } = item.meta else {
+                        unreachable!("`InsertBundle` is a targeted event")
+                    };
+
+                    let bundle = unsafe {
+                        let type_id = ctx
+                            .world
+                            .targeted_events
+                            .get_by_index(idx)
+                            .unwrap_unchecked()
+                            .type_id()
+                            .unwrap_unchecked();
+
+                        // Bundle was added during initialization of the event.
+                        ctx.world.bundles.get(&type_id).unwrap_unchecked()
+                    };
+
+                    let mut component_ptrs = Vec::with_capacity(bundle.component_indices.len());
+                    unsafe { (bundle.component_ptrs)(ctx.event, &mut component_ptrs) };
+
+                    let new_components = bundle
+                        .component_indices
+                        .iter()
+                        .copied()
+                        .zip(component_ptrs)
+                        .collect();
+
+                    unsafe {
+                        ctx.world.archetypes.insert_components(
+                            target_location,
+                            new_components,
+                            &mut ctx.world.components,
+                            &mut ctx.world.handlers,
+                            &mut ctx.world.entities,
+                        )
+                    };
+
+                    // Inserted components are owned by the archetype now.
+                    ctx.unpack();
+                }
+                EventKind::Despawn => {
+                    // `Despawn` doesn't need drop.
+                    let _ = ctx.unpack();
+
+                    if let EventMeta::Targeted { target, .. } = item.meta {
+                        if let Some(handlers) = self.handler_owners.remove(&target) {
+                            // Owned handlers stop running right away, but can only be
+                            // removed once the flush has finished.
+                            for &handler in &handlers {
+                                if let Some(info) = self.handlers.get_mut(handler) {
+                                    info.set_enabled(false);
+                                }
+                            }
+
+                            self.expired_handlers.extend(handlers);
+                        }
+                    }
+
+                    unsafe {
+                        self.archetypes
+                            .remove_entity(target_location, &mut self.entities)
+                    };
+
+                    // Reset next key iter.
+                    self.reserved_entities.refresh(&self.entities);
+                }
+            }
+        }
+
+        self.dedup.clear();
+        self.rate_limits.end_flush();
+        self.bump.reset();
+        self.deferred_queue.finish();
+        debug_assert!(self.event_queue.is_empty());
+
+        if !self.pending_spawns.is_empty() {
+            // The `SpawnWith` events of these entities were dropped or consumed
+            // before they spawned them, so release the reserved IDs. No handler
+            // has observed the entities.
+            self.reserved_entities
+                .spawn_all(&mut self.entities, |id| self.archetypes.spawn(id));
+
+            for entity in mem::take(&mut self.pending_spawns) {
+                if let Some(location) = self.entities.get(entity) {
+                    unsafe { self.archetypes.remove_entity(location, &mut self.entities) };
+                }
+            }
+
+            self.reserved_entities.refresh(&self.entities);
+        }
+
+        // Removing a handler sends `RemoveHandler`, so this can't be done while
+        // the queue is being flushed.
+        for handler in mem::take(&mut self.expired_handlers) {
+            self.remove_handler(handler);
+        }
+
+        // Flushes may send events, which flush the queue again.
+        for flush in self.deferred_flushes.take() {
+            flush(self);
+        }
+
+        if !self.tasks.is_empty() {
+            self.poll_tasks();
+        }
+    }
+
+    /// Puts the events pushed by the handlers of `item` in the order they are
+    /// handled in. `events_before` is the length of the queue before the
+    /// handlers ran.
+    ///
+    /// # Safety
+    ///
+    /// `events_before` must not be greater than the length of the queue.
+    unsafe fn order_pushed_events(&mut self, item: &EventQueueItem, events_before: usize) {
+        let pushed = self.event_queue.len() - events_before;
+
+        // Reverse pushed events so they're handled in FIFO order.
+        self.event_queue
+            .get_unchecked_mut(events_before..)
+            .reverse();
+
+        if item.transaction == 0 {
+            return;
+        }
+
+        // The rest of the transaction is at the top of the queue, preceded by
+        // the events pushed by the handlers of the transaction's earlier
+        // events. Move the pushed events below those, so they are handled
+        // after the whole transaction, in the order they were sent.
+        let remaining = item.transaction as usize - 1;
+        let start = events_before - remaining - self.transaction_deferred;
+
+        self.event_queue
+            .get_unchecked_mut(start..)
+            .rotate_right(pushed);
+
+        self.transaction_deferred = if remaining == 0 {
+            0
+        } else {
+            self.transaction_deferred + pushed
+        };
+    }
+
+    /// Returns the kind of the event at the top of the queue if it is an
+    /// [`Insert`] or [`Remove`] event which can be applied together with the
+    /// structural event described by `meta`.
+    ///
+    /// This is the case if the event has the same target and nothing can
+    /// observe it being broadcast: no handlers listen for it while the target
+    /// is in the archetype `arch`, and there are no interceptors,
+    /// deduplicators or rate limits.
+    fn next_coalescable(&self, meta: EventMeta, arch: ArchetypeIdx) -> Option<EventKind> {
+        let EventMeta::Targeted { target, .. } = meta else {
+            return None;
+        };
+
+        let next = self.event_queue.last()?;
+
+        let EventMeta::Targeted {
+            idx,
+            target: next_target,
+        } = next.meta
+        else {
+            return None;
+        };
+
+        if next_target != target
+            || !self.interceptors.list.is_empty()
+            || !self.dedup.is_empty()
+            || !self.rate_limits.is_empty()
+            || self.max_event_depth.is_some_and(|max| next.depth > max)
+        {
+            return None;
+        }
+
+        let kind = unsafe { self.targeted_events.get_by_index(idx).unwrap_unchecked() }.kind();
+
+        if !matches!(kind, EventKind::Insert { .. } | EventKind::Remove { .. }) {
+            return None;
+        }
+
+        let arch = unsafe { self.archetypes.get(arch).unwrap_unchecked() };
+
+        let unobserved = self.lifecycle_events_suppressed
+            || arch
+                .handler_list_for(idx)
+                .map_or(true, |list| list.slice().is_empty());
+
+        unobserved.then_some(kind)
+    }
+
+    /// Moves the target of the structural event described by `meta` to the
+    /// archetype `dst`, together with the [`Insert`] and [`Remove`] events
+    /// returned by [`next_coalescable`](Self::next_coalescable). The entity is
+    /// moved only once, instead of through every intermediate archetype.
+    ///
+    /// # Safety
+    ///
+    /// - `src` must be the location of the target.
+    /// - `new_components` must be valid for
+    ///   [`Archetypes::assign_and_move_entity`] with `dst`.
+    unsafe fn move_entity_coalesced(
+        &mut self,
+        meta: EventMeta,
+        src: EntityLocation,
+        mut dst: ArchetypeIdx,
+        mut new_components: Vec<(ComponentIdx, *const u8)>,
+    ) {
+        while let Some(kind) = self.next_coalescable(meta, dst) {
+            let item = self.event_queue.pop().unwrap_unchecked();
+
+            #[cfg(feature = "metrics")]
+            self.record_metrics(item.meta, core::time::Duration::ZERO);
+
+            let (EventKind::Insert { component_idx } | EventKind::Remove { component_idx }) = kind
+            else {
+                unreachable!("only `Insert` and `Remove` events are coalesced")
+            };
+
+            // A component inserted by an earlier event is replaced or removed,
+            // so it must be dropped.
+            if let Some(i) = new_components
+                .iter()
+                .position(|&(idx, _)| idx == component_idx)
+            {
+                let (_, ptr) = new_components.swap_remove(i);
+
+                let info = self
+                    .components
+                    .get_by_index(component_idx)
+                    .unwrap_unchecked();
+
+                if let Some(drop) = info.drop() {
+                    drop(NonNull::new_unchecked(ptr.cast_mut()));
+                }
+            }
+
+            if let EventKind::Insert { .. } = kind {
+                // `Insert<C>` is `repr(transparent)`.
+                new_components.push((component_idx, item.event.as_ptr().cast_const()));
+
+                dst = self.archetypes.traverse_insert(
+                    dst,
+                    component_idx,
+                    &mut self.components,
+                    &mut self.handlers,
+                );
+            } else {
+                dst = self.archetypes.traverse_remove(
+                    dst,
+                    component_idx,
+                    &mut self.components,
+                    &mut self.handlers,
+                );
+            }
+        }
+
+        self.archetypes
+            .assign_and_move_entity(src, dst, new_components, &mut self.entities);
+    }
+
+    /// Returns the [`BroadcastEnded`] event to broadcast if `pending` is set,
+    /// and clears `pending`.
+    fn broadcast_ended(&mut self, pending: &mut bool) -> Option<EventQueueItem> {
+        if !mem::take(pending) {
+            return None;
+        }
+
+        // If the event was never added, there are no handlers listening for it.
+        let info = self
+            .global_events
+            .get_by_type_id(TypeId::of::<BroadcastEnded>())?;
+
+        Some(EventQueueItem {
+            meta: EventMeta::Global {
+                idx: info.id().index(),
+            },
+            event: NonNull::from(self.bump.alloc(BroadcastEnded)).cast(),
+            depth: 0,
+            transaction: 0,
+        })
+    }
+
+    /// Returns an event held back by a rate limit which may now be broadcast.
+    fn release_rate_limited(&mut self) -> Option<EventQueueItem> {
+        if self.rate_limits.is_empty() {
+            return None;
+        }
+
+        let now = self.clock.time.elapsed();
+
+        let (id, event, target) = self.rate_limits.release(now, &self.bump)?;
+
+        // Limits are removed along with their event, so the event still
+        // exists.
+        let meta = match (id, target) {
+            (EventId::Global(id), _) => EventMeta::Global { idx: id.index() },
+            (EventId::Targeted(id), target) => EventMeta::Targeted {
+                idx: id.index(),
+                target: target.unwrap(),
+            },
+        };
+
+        Some(EventQueueItem {
+            meta,
+            event,
+            depth: 0,
+            transaction: 0,
+        })
+    }
+
+    /// Returns the ID of a queued event.
+    fn event_id(&self, meta: EventMeta) -> EventId {
+        match meta {
+            EventMeta::Global { idx } => unsafe {
+                self.global_events
+                    .get_by_index(idx)
+                    .unwrap_unchecked()
+                    .id()
+                    .into()
+            },
+            EventMeta::Targeted { idx, .. } => unsafe {
+                self.targeted_events
+                    .get_by_index(idx)
+                    .unwrap_unchecked()
+                    .id()
+                    .into()
+            },
+        }
+    }
+
+    /// Returns the ID of the event `E`, adding the event if it doesn't exist.
+    fn event_id_of<E: Event>(&mut self) -> EventId {
+        let idx = <E as EventSet>::new_indices(self);
+
+        if TypeId::of::<E::EventIdx>() == TypeId::of::<TargetedEventIdx>() {
+            EventId::Targeted(
+                self.targeted_events
+                    .get_by_index(TargetedEventIdx(idx))
+                    .unwrap()
+                    .id(),
+            )
+        } else {
+            EventId::Global(
+                self.global_events
+                    .get_by_index(GlobalEventIdx(idx))
+                    .unwrap()
+                    .id(),
+            )
+        }
+    }
+
+    /// Returns the type ID of a queued event.
+    fn event_type_id(&self, meta: EventMeta) -> Option<TypeId> {
+        match meta {
+            EventMeta::Global { idx } => unsafe {
+                self.global_events
+                    .get_by_index(idx)
+                    .unwrap_unchecked()
+                    .type_id()
+            },
+            EventMeta::Targeted { idx, .. } => unsafe {
+                self.targeted_events
+                    .get_by_index(idx)
+                    .unwrap_unchecked()
+                    .type_id()
+            },
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_metrics(&mut self, meta: EventMeta, time: core::time::Duration) {
+        let metrics = match meta {
+            EventMeta::Global { idx } => unsafe {
+                self.global_events
+                    .get_by_index_mut(idx)
+                    .unwrap_unchecked()
+                    .metrics_mut()
+            },
+            EventMeta::Targeted { idx, .. } => unsafe {
+                self.targeted_events
+                    .get_by_index_mut(idx)
+                    .unwrap_unchecked()
+                    .metrics_mut()
+            },
+        };
+
+        metrics.record(time);
+    }
+
+    /// Queues a [`DepthLimitExceeded`] event in place of the given event.
+    fn queue_depth_limit_exceeded(&mut self, item: EventQueueItem) {
+        // The event can't be added while flushing the queue. If it was never
+        // added, there are no handlers listening for it.
+        let Some(info) = self
+            .global_events
+            .get_by_type_id(TypeId::of::<DepthLimitExceeded>())
+        else {
+            return;
+        };
+
+        let (event, target) = match item.meta {
+            EventMeta::Global { idx } => {
+                let id = unsafe { self.global_events.get_by_index(idx).unwrap_unchecked() }.id();
+                (EventId::Global(id), None)
+            }
+            EventMeta::Targeted { idx, target } => {
+                let id = unsafe { self.targeted_events.get_by_index(idx).unwrap_unchecked() }.id();
+                (EventId::Targeted(id), Some(target))
+            }
+        };
+
+        let event = DepthLimitExceeded {
+            event,
+            target,
+            depth: item.depth,
+        };
+
+        self.event_queue.push(EventQueueItem {
+            meta: EventMeta::Global {
+                idx: info.id().index(),
+            },
+            event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: 0,
+            transaction: 0,
+        });
+    }
+
+    /// Queues a [`DeadLetter`] event in place of the given targeted event.
+    fn queue_dead_letter(&mut self, item: EventQueueItem, event: TargetedEventId) {
+        let EventMeta::Targeted { target, .. } = item.meta else {
+            return;
+        };
+
+        // Added by `set_dead_target_policy`, but might have been removed since.
+        let Some(info) = self
+            .global_events
+            .get_by_type_id(TypeId::of::<DeadLetter>())
+        else {
+            return;
+        };
+
+        self.event_queue.push(EventQueueItem {
+            meta: EventMeta::Global {
+                idx: info.id().index(),
+            },
+            event: NonNull::from(self.bump.alloc(DeadLetter { event, target })).cast(),
+            depth: item.depth,
+            transaction: 0,
+        });
+    }
+
+    /// Queues a [`HandlerPanicked`] event for a panic of `handler` caught while
+    /// it handled the given event. Resumes the panic if the event is itself a
+    /// `HandlerPanicked`.
+    #[cfg(feature = "std")]
+    fn queue_handler_panicked(
+        &mut self,
+        item: EventQueueItem,
+        handler: HandlerId,
+        payload: PanicPayload,
+    ) {
+        if self.event_type_id(item.meta) == Some(TypeId::of::<HandlerPanicked>()) {
+            std::panic::resume_unwind(payload);
+        }
+
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            (*message).into()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".into()
+        };
+
+        let event = HandlerPanicked { handler, message };
+        let idx = self.add_global_event::<HandlerPanicked>().index();
+
+        self.event_queue.push(EventQueueItem {
+            meta: EventMeta::Global { idx },
+            event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: item.depth + 1,
+            transaction: 0,
+        });
+    }
+
+    /// Queues a [`HandlerOverBudget`] event for a run of `info` which took
+    /// `duration` while handling the given event. Overruns of the handlers of
+    /// `HandlerOverBudget` itself aren't reported.
+    #[cfg(feature = "std")]
+    fn queue_over_budget(&mut self, item: EventQueueItem, info: &HandlerInfo, duration: Duration) {
+        if self.event_type_id(item.meta) == Some(TypeId::of::<HandlerOverBudget>()) {
+            return;
+        }
+
+        let Some(budget) = info.budget() else {
+            return;
+        };
+
+        let event = HandlerOverBudget {
+            handler: info.id(),
+            duration,
+            budget,
+        };
+        let idx = self.add_global_event::<HandlerOverBudget>().index();
+
+        self.event_queue.push(EventQueueItem {
+            meta: EventMeta::Global { idx },
+            event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: item.depth + 1,
+            transaction: 0,
+        });
+    }
+
+    /// Queues a [`Taken`] event for an event taken by `handler`.
+    fn queue_taken(&mut self, item: EventQueueItem, handler: HandlerId) {
+        // The event can't be added while flushing the queue. If it was never
+        // added, there are no handlers listening for it.
+        let Some(info) = self.global_events.get_by_type_id(TypeId::of::<Taken>()) else {
+            return;
+        };
+
+        let (event, target) = match item.meta {
+            EventMeta::Global { idx } => {
+                let id = unsafe { self.global_events.get_by_index(idx).unwrap_unchecked() }.id();
+                (EventId::Global(id), None)
+            }
+            EventMeta::Targeted { idx, target } => {
+                let id = unsafe { self.targeted_events.get_by_index(idx).unwrap_unchecked() }.id();
+                (EventId::Targeted(id), Some(target))
+            }
+        };
+
+        let event = Taken {
+            event,
+            target,
+            handler,
+        };
+
+        // Handled before the events sent by the taking handler.
+        self.event_queue.push(EventQueueItem {
+            meta: EventMeta::Global {
+                idx: info.id().index(),
+            },
+            event: NonNull::from(self.bump.alloc(event)).cast(),
+            depth: item.depth + 1,
+            transaction: 0,
+        });
+    }
+
+    /// Returns a new [`UnsafeWorldCell`] with permission to _read_ all data in
+    /// this world.
+    pub fn unsafe_cell(&self) -> UnsafeWorldCell {
+        self.publish_address();
+
+        UnsafeWorldCell {
+            world: NonNull::from(self),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a new [`UnsafeWorldCell`] with permission to _read and write_
+    /// all data in this world.
+    pub fn unsafe_cell_mut(&mut self) -> UnsafeWorldCell {
+        self.publish_address();
+
+        UnsafeWorldCell {
+            world: NonNull::from(self),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a pointer to the current address of this world. See
+    /// [`publish_address`](Self::publish_address).
+    pub(crate) fn address(&self) -> NonNull<AtomicPtr<World>> {
+        NonNull::from(&*self.address)
+    }
+
+    /// Stores the current address of this world, which stays valid until the
+    /// world is moved.
+    pub(crate) fn publish_address(&self) {
+        self.address
+            .store(self as *const World as *mut World, Ordering::Relaxed);
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the params of a handler, given their component accesses, whose
+/// access conflicts on the component `idx`: a param conflicting with itself, or else the first pair
+/// of params which conflict with each other.
+fn conflicting_params(
+    accesses: &[ComponentAccess],
+    params: &[Option<&'static str>],
+    idx: ComponentIdx,
+) -> [Option<&'static str>; 2] {
+    for (i, ca) in accesses.iter().enumerate() {
+        if ca.collect_conflicts().contains(&idx) {
+            return [params[i], params[i]];
+        }
+    }
+
+    for (i, a) in accesses.iter().enumerate() {
+        for (j, b) in accesses.iter().enumerate().skip(i + 1) {
+            if a.and(b).collect_conflicts().contains(&idx) {
+                return [params[i], params[j]];
+            }
+        }
+    }
+
+    [None, None]
+}
+
+/// Returns the smallest of the budgets of `labels` in `group_budgets`.
+#[cfg(feature = "std")]
+fn group_budget(
+    group_budgets: &[(Cow<'static, str>, HandlerBudget)],
+    labels: &[Cow<'static, str>],
+) -> Option<HandlerBudget> {
+    group_budgets
+        .iter()
+        .filter(|(label, _)| labels.contains(label))
+        .map(|&(_, budget)| budget)
+        .min_by_key(|budget| budget.time)
+}
+
+/// Checks that an event can be safely sent as plain bytes.
+#[track_caller]
+fn assert_plain_event(
+    type_id: Option<TypeId>,
+    kind: EventKind,
+    drop: DropFn,
+    layout: Layout,
+    bytes: &[u8],
+) {
+    assert!(
+        type_id.is_none() && kind == EventKind::Normal && drop.is_none(),
+        "event must have no type ID, no drop function and the normal event kind to be sent as bytes"
+    );
+
+    assert_eq!(
+        bytes.len(),
+        layout.size(),
+        "number of bytes must match the size of the event"
+    );
+}
+
+/// Deferred events, ordered by priority and then by the order they were sent
+/// in.
+#[derive(Debug)]
+struct DeferredQueue {
+    items: BTreeMap<(u64, Reverse<Priority>, u64), EventQueueItem>,
+    /// The insertion order of the next deferred event.
+    next_order: u64,
+    /// The most recent epoch. Each barrier starts a new epoch, and all events
+    /// of an epoch are broadcast before the events of later epochs.
+    last_epoch: u64,
+    /// The epoch of the deferred event currently being broadcast, if any.
+    /// Events deferred while it broadcasts belong to the same epoch.
+    current_epoch: Option<u64>,
+}
+
+impl DeferredQueue {
+    fn new() -> Self {
+        Self {
+            items: BTreeMap::new(),
+            next_order: 0,
+            last_epoch: 0,
+            current_epoch: None,
+        }
+    }
+
+    fn push(&mut self, priority: Priority, item: EventQueueItem) {
+        let epoch = self.current_epoch.unwrap_or(self.last_epoch);
+
+        self.items
+            .insert((epoch, Reverse(priority), self.next_order), item);
+        self.next_order += 1;
+    }
+
+    /// Makes all events deferred after this call broadcast after the events
+    /// deferred before it.
+    fn barrier(&mut self) {
+        self.last_epoch += 1;
+
+        if let Some(epoch) = &mut self.current_epoch {
+            *epoch = self.last_epoch;
+        }
+    }
+
+    /// Removes the deferred event to broadcast next.
+    fn pop(&mut self) -> Option<EventQueueItem> {
+        let ((epoch, _, _), item) = self.items.pop_first()?;
+        self.current_epoch = Some(epoch);
+        Some(item)
+    }
+
+    /// Returns the deferred events in the order they'll be broadcast.
+    fn iter(&self) -> impl Iterator<Item = &EventQueueItem> {
+        self.items.values()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Resets the epochs once all events have been broadcast.
+    fn finish(&mut self) {
+        debug_assert!(self.is_empty());
+
+        self.last_epoch = 0;
+        self.current_epoch = None;
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+        self.finish();
+    }
+}
+
+/// A scheduled event, which is sent by calling the function.
+pub(crate) type ScheduledEvent = Box<dyn FnOnce(&mut World)>;
+
+/// Events scheduled to be sent at a later tick.
+struct Schedule {
+    /// The current tick.
+    tick: u64,
+    /// Scheduled events, keyed by the tick they are due and the order they
+    /// were scheduled in.
+    events: BTreeMap<(u64, u64), ScheduledEvent>,
+    /// The insertion order of the next scheduled event.
+    next_order: u64,
+}
+
+impl Schedule {
+    fn new() -> Self {
+        Self {
+            tick: 0,
+            events: BTreeMap::new(),
+            next_order: 0,
+        }
+    }
+
+    /// Schedules an event `delay` ticks after the current tick.
+    fn insert(&mut self, delay: u64, event: ScheduledEvent) {
+        let due = self.tick.saturating_add(delay);
+
+        self.events.insert((due, self.next_order), event);
+        self.next_order += 1;
+    }
+
+    /// Removes and returns the next event which is due by the current tick.
+    fn pop_due(&mut self) -> Option<ScheduledEvent> {
+        let entry = self.events.first_entry()?;
+
+        (entry.key().0 <= self.tick).then(|| entry.remove())
+    }
+}
+
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Schedule")
+            .field("tick", &self.tick)
+            .field("events", &self.events.keys())
+            .field("next_order", &self.next_order)
+            .finish()
+    }
+}
+
+/// The identifier of the next world to be created.
+static NEXT_WORLD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Returns a pointer to the component at `row` in a column with the given
+/// data pointer.
+pub(crate) fn column_ptr(
+    data: NonNull<u8>,
+    info: &ComponentInfo,
+    row: ArchetypeRow,
+) -> NonNull<u8> {
+    let offset = info.layout().size() * row.0 as usize;
+    // SAFETY: Columns are allocations of `entity_count * size` bytes.
+    unsafe { NonNull::new_unchecked(data.as_ptr().add(offset)) }
+}
+
+/// The handlers of events which have none, or whose handlers don't run.
+static EMPTY_HANDLER_LIST: HandlerList = HandlerList::new();
+
+/// The payload of a panic caught by [`run_handler`].
+#[cfg(feature = "std")]
+type PanicPayload = Box<dyn any::Any + Send>;
+
+/// Panics can't be caught without `std`.
+#[cfg(not(feature = "std"))]
+type PanicPayload = core::convert::Infallible;
+
+/// Runs a handler. If `catch_panics` is set, a panic of the handler is caught
+/// and its payload returned.
+///
+/// # Safety
+///
+/// The arguments must be valid for running the handler, as described by
+/// [`Handler::run`].
+#[inline]
+unsafe fn run_handler(
+    handler: *mut dyn Handler,
+    info: &HandlerInfo,
+    event_ptr: EventPtr,
+    target_location: EntityLocation,
+    world: UnsafeWorldCell,
+    catch_panics: bool,
+) -> Result<(), PanicPayload> {
+    #[cfg(feature = "std")]
+    if catch_panics {
+        return std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            (*handler).run(info, event_ptr, target_location, world)
+        }));
+    }
+
+    #[cfg(not(feature = "std"))]
+    let _ = catch_panics;
+
+    (*handler).run(info, event_ptr, target_location, world);
+    Ok(())
+}
+
+/// Runs a group of parallel handlers on the rayon thread pool, and returns
+/// once all of them have finished. Returns the caught panics of the handlers
+/// if `catch_panics` is set.
+///
+/// # Safety
+///
+/// The handlers must be able to run in parallel, as determined by
+/// [`HandlerList::parallel_end`], and the event and world must be valid for
+/// running them.
+#[cfg(feature = "rayon")]
+unsafe fn run_parallel(
+    group: &[HandlerInfoPtr],
+    event_ptr: EventPtr,
+    target_location: EntityLocation,
+    world: UnsafeWorldCell,
+    catch_panics: bool,
+) -> Vec<(HandlerId, PanicPayload)> {
+    /// Sends the pointers needed to run a handler to another thread.
+    struct AssertSend<T>(T);
+
+    // SAFETY: The handlers in a group don't alias each other's access, and
+    // their handler types and accessed data are `Send` and `Sync` as required
+    // by `World::add_parallel_handler`.
+    unsafe impl<T> Send for AssertSend<T> {}
+
+    impl<T> AssertSend<T> {
+        // A method rather than field access, so that closures capture the
+        // whole wrapper.
+        fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    let mut panics: Vec<Option<PanicPayload>> = group.iter().map(|_| None).collect();
+
+    let args = AssertSend((group, event_ptr, world, &mut panics));
+
+    rayon::scope(move |scope| {
+        let (group, event_ptr, world, panics) = args.into_inner();
+
+        for (&info_ptr, panic) in group.iter().zip(panics) {
+            let args = AssertSend((info_ptr, event_ptr, world, panic));
+
+            scope.spawn(move |_| {
+                let (mut info_ptr, event_ptr, world, panic) = args.into_inner();
+                let info = unsafe { info_ptr.as_info_mut() };
+
+                if !info.is_enabled() {
+                    return;
+                }
+
+                #[cfg(feature = "std")]
+                if info.watchdog().skipped_frame == Some(world.frame()) {
+                    return;
+                }
+
+                let handler: *mut dyn Handler = info.handler_mut();
+
+                #[cfg(feature = "std")]
+                let handler_start = info.is_timed().then(std::time::Instant::now);
+
+                let res = unsafe {
+                    run_handler(
+                        handler,
+                        info,
+                        event_ptr,
+                        target_location,
+                        world,
+                        catch_panics,
+                    )
+                };
+
+                // Overruns are reported by the caller.
+                #[cfg(feature = "std")]
+                if let Some(start) = handler_start {
+                    info.record_run_time(start.elapsed(), world.frame());
+                }
+
+                *panic = res.err();
+            });
+        }
+    });
+
+    group
+        .iter()
+        .zip(panics)
+        .filter_map(|(info_ptr, panic)| Some((unsafe { info_ptr.as_info() }.id(), panic?)))
+        .collect()
+}
+
+/// Reference to a [`World`] where all methods take `self` and aliasing rules
+/// are not checked. It is the caller's responsibility to ensure that Rust's
+/// aliasing rules are not violated.
+#[derive(Clone, Copy, Debug)]
+pub struct UnsafeWorldCell<'a> {
+    world: NonNull<World>,
+    _marker: PhantomData<&'a World>,
+}
+
+impl<'a> UnsafeWorldCell<'a> {
+    /// Creates a new `UnsafeWorldCell` from a pointer to a world.
+    ///
+    /// # Safety
+    ///
+    /// The world must be valid for `'a`, and the caller must uphold the
+    /// aliasing rules for the accessed data.
+    pub(crate) unsafe fn from_address(world: NonNull<World>) -> Self {
+        Self {
+            world,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Allocate data in the world's bump allocator.
+    ///
+    /// This operation is not thread safe.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    #[inline]
+    pub unsafe fn alloc_layout(self, layout: Layout) -> NonNull<u8> {
+        let bump = unsafe { &(*self.world.as_ptr()).bump };
+        bump.alloc_layout(layout)
+    }
+
+    /// Add a global event to the event queue. Ownership of the event is
+    /// transferred.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Event must outlive call to top level [`World::send`] or
+    ///   [`World::send_to`].
+    /// - Event index must be correct for the given event.
+    #[inline]
+    pub unsafe fn queue_global(self, event: NonNull<u8>, idx: GlobalEventIdx) {
+        let event_queue = &mut (*self.world.as_ptr()).event_queue;
+
+        event_queue.push(EventQueueItem {
+            meta: EventMeta::Global { idx },
+            event,
+            depth: (*self.world.as_ptr()).event_depth + 1,
+            transaction: 0,
+        });
+    }
+
+    /// Add a targeted event to the event queue. Ownership of the event is
+    /// transferred.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue
+    #[inline]
+    pub unsafe fn queue_targeted(
+        self,
+        target: EntityId,
+        event: NonNull<u8>,
+        idx: TargetedEventIdx,
+    ) {
+        let event_queue = &mut (*self.world.as_ptr()).event_queue;
+
+        event_queue.push(EventQueueItem {
+            meta: EventMeta::Targeted { idx, target },
+            event,
+            depth: (*self.world.as_ptr()).event_depth + 1,
+            transaction: 0,
+        });
+    }
+
+    /// Makes the events queued since the queue had length `start` a
+    /// transaction, which is broadcast without other events in between. See
+    /// [`Sender::send_all`].
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue.
+    /// - `start` must not be greater than the length of the queue.
+    pub(crate) unsafe fn mark_transaction(self, start: usize) {
+        let event_queue = &mut (*self.world.as_ptr()).event_queue;
+
+        let items = event_queue.get_unchecked_mut(start..);
+        let len = items.len() as u32;
+
+        for (i, item) in items.iter_mut().enumerate() {
+            item.transaction = len - i as u32;
+        }
+    }
+
+    /// Returns the length of the event queue.
+    ///
+    /// # Safety
+    ///
+    /// - Must have permission to access the event queue.
+    pub(crate) unsafe fn queue_len(self) -> usize {
+        (*self.world.as_ptr()).event_queue.len()
+    }
+
+    /// Add an event to the deferred event queue, which is flushed once the
+    /// regular event queue is empty. Ownership of the event is transferred.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue.
+    /// - Event must outlive call to top level [`World::send`] or
+    ///   [`World::send_to`].
+    /// - Event index must be correct for the given event.
+    #[inline]
+    pub(crate) unsafe fn queue_deferred(
+        self,
+        priority: Priority,
+        meta: EventMeta,
+        event: NonNull<u8>,
+    ) {
+        let deferred_queue = &mut (*self.world.as_ptr()).deferred_queue;
+
+        // Deferred events start a new chain.
+        deferred_queue.push(
+            priority,
+            EventQueueItem {
+                meta,
+                event,
+                depth: 0,
+                transaction: 0,
+            },
+        );
+    }
+
+    /// Adds a barrier to the queue of deferred events. See
+    /// [`Sender::defer_barrier`].
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue.
+    #[inline]
+    pub(crate) unsafe fn queue_barrier(self) {
+        (*self.world.as_ptr()).deferred_queue.barrier();
+    }
+
+    /// Spawns a task, which is polled at the end of the flush.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    #[inline]
+    pub(crate) unsafe fn spawn_task<F>(self, future: F)
+    where
+        F: Future<Output = Completion> + 'static,
+    {
+        (*self.world.as_ptr()).tasks.spawn(future);
+    }
+
+    /// Removes the handler once the event queue has been flushed.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    #[inline]
+    pub(crate) unsafe fn remove_handler_after_flush(self, handler: HandlerId) {
+        (*self.world.as_ptr()).expired_handlers.push(handler);
+    }
+
+    /// Runs `flush` once the event queue has been flushed.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    #[inline]
+    pub(crate) unsafe fn queue_flush(self, flush: Flush) {
+        (*self.world.as_ptr()).deferred_flushes.0.push(flush);
+    }
+
+    /// Schedules an event to be sent `delay` ticks after the current tick.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue.
+    #[inline]
+    pub(crate) unsafe fn schedule(self, delay: u64, event: ScheduledEvent) {
+        (*self.world.as_ptr()).schedule.insert(delay, event);
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    pub unsafe fn queue_spawn(self) -> EntityId {
+        let entity_id = (*self.world.as_ptr())
+            .reserved_entities
+            .reserve(self.entities());
+
+        entity_id
+    }
+
+    /// Reserves an entity to be spawned by a [`SpawnWith`] event.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    pub(crate) unsafe fn queue_spawn_with(self) -> EntityId {
+        let entity_id = self.queue_spawn();
+        (*self.world.as_ptr()).pending_spawns.insert(entity_id);
+        entity_id
+    }
+
+    /// Returns the frame of the world, used for handler budgets.
+    #[cfg(all(feature = "std", feature = "rayon"))]
+    pub(crate) fn frame(self) -> u64 {
+        unsafe { (*self.world.as_ptr()).frame }
+    }
+
+    /// Returns the [`Entities`] for this world.
+    pub fn entities(self) -> &'a Entities {
+        unsafe { &(*self.world.as_ptr()).entities }
+    }
+
+    /// Returns the [`Components`] for this world.
+    pub fn components(self) -> &'a Components {
+        unsafe { &(*self.world.as_ptr()).components }
+    }
+
+    /// Returns the [`Handlers`] for this world.
+    pub fn handlers(self) -> &'a Handlers {
+        unsafe { &(*self.world.as_ptr()).handlers }
+    }
+
+    /// Returns the [`Archetypes`] for this world.
+    pub fn archetypes(self) -> &'a Archetypes {
+        unsafe { &(*self.world.as_ptr()).archetypes }
+    }
+
+    /// Returns the [`GlobalEvents`] for this world.
+    pub fn global_events(self) -> &'a GlobalEvents {
+        unsafe { &(*self.world.as_ptr()).global_events }
+    }
+
+    /// Returns the [`TargetedEvents`] for this world.
+    pub fn targeted_events(self) -> &'a TargetedEvents {
+        unsafe { &(*self.world.as_ptr()).targeted_events }
+    }
+
+    /// Returns an immutable reference to the underlying world.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission to access the entire world immutably.
+    pub fn world(self) -> &'a World {
+        unsafe { &*self.world.as_ptr() }
+    }
+
+    /// Returns a mutable reference to the underlying world.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission the access the entire world mutably.
+    pub unsafe fn world_mut(self) -> &'a mut World {
+        &mut *self.world.as_ptr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::alloc::Layout;
+    use core::any;
+    use core::cell::{Cell, RefCell};
+    use core::mem;
+    use core::panic::AssertUnwindSafe;
+    use std::panic;
+
+    use core::any::TypeId;
+
+    use crate::event::{EventDescriptor, EventKind};
+    #[cfg(feature = "std")]
+    use crate::handler::HandlerPanicked;
+    use crate::mutability::Mutability;
+    use crate::prelude::*;
+
+    #[test]
+    fn world_drops_events() {
+        #[derive(GlobalEvent)]
+        struct A(Rc<()>);
+
+        #[derive(GlobalEvent)]
+        struct B(Rc<()>);
+
+        #[derive(GlobalEvent)]
+        struct C(Rc<()>);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, s: Sender<B>| {
+            s.send(B(r.event.0.clone()));
+            s.send(B(r.event.0.clone()));
+        });
+
+        world.add_handler(|r: Receiver<B>, s: Sender<C>| {
+            s.send(C(r.event.0.clone()));
+            s.send(C(r.event.0.clone()));
+        });
+
+        world.add_handler(|r: Receiver<C>| println!("got C {:?}", Rc::as_ptr(&r.event.0)));
+
+        let rc = Rc::new(());
+
+        world.send(A(rc.clone()));
+
+        drop(world);
+
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn world_drops_events_on_panic() {
+        #[derive(GlobalEvent)]
+        struct A(Rc<()>);
+
+        impl Drop for A {
+            fn drop(&mut self) {
+                eprintln!("calling A destructor");
+            }
+        }
+
+        #[derive(GlobalEvent)]
+        struct B(Rc<()>);
+
+        #[allow(dead_code)]
+        #[derive(GlobalEvent)]
+        struct C(Rc<()>);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, s: Sender<B>| {
+            s.send(B(r.event.0.clone()));
+            s.send(B(r.event.0.clone()));
+        });
+
+        world.add_handler(|r: Receiver<B>, s: Sender<C>| {
+            s.send(C(r.event.0.clone()));
+            s.send(C(r.event.0.clone()));
+        });
+
+        world.add_handler(|_: Receiver<C>| panic!("oops!"));
+
+        let arc = Rc::new(());
+        let arc_cloned = arc.clone();
+
+        let mut world = AssertUnwindSafe(world);
+
+        let res = panic::catch_unwind(move || world.send(A(arc_cloned)));
+
+        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "oops!");
+
+        assert_eq!(Rc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn event_metrics() {
+        use crate::event::EventMetrics;
+
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(TargetedEvent)]
+        struct B;
+
+        let mut world = World::new();
+
+        world.add_handler(|_: Receiver<A>, s: Sender<B>| s.send_to(EntityId::NULL, B));
+        world.add_handler(|r: ReceiverMut<B, ()>| {
+            EventMut::take(r.event);
+        });
+
+        let a = world.add_global_event::<A>();
+        let b = world.add_targeted_event::<B>();
+
+        world.send(A);
+        world.send(A);
+
+        let e = world.spawn();
+        world.send_to(e, B);
+
+        let a_metrics = world.event_metrics(a.into()).unwrap();
+        assert_eq!(a_metrics.count(), 2);
+        assert!(a_metrics.mean_time().is_some());
+
+        // Events sent to a nonexistent entity are not counted.
+        assert_eq!(world.targeted_events()[b].metrics().count(), 1);
+
+        world.reset_event_metrics();
+
+        assert_eq!(world.global_events()[a].metrics(), EventMetrics::default());
+        assert_eq!(world.event_metrics(b.into()).unwrap().mean_time(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn handler_metrics() {
+        use crate::handler::HandlerMetrics;
+
+        #[derive(GlobalEvent)]
+        struct A;
+
+        let mut world = World::new();
+
+        let a = world.add_handler(|_: Receiver<A>| {});
+        let b = world.add_handler(|r: ReceiverMut<A>| {
+            EventMut::take(r.event);
+        });
+        // Doesn't run since `b` takes the event.
+        let c = world.add_handler(|_: Receiver<A>, _: Sender<A>| {});
+
+        world.send(A);
+        world.send(A);
+
+        let metrics = world.handlers()[b].metrics();
+        assert_eq!(metrics.count(), 2);
+        assert!(metrics.max_time() <= metrics.total_time());
+        assert!(metrics.mean_time().is_some());
+        assert_eq!(world.handlers()[a].metrics().count(), 2);
+        assert_eq!(world.handlers()[c].metrics(), HandlerMetrics::default());
+
+        assert_eq!(world.handlers().by_total_time().len(), 3);
+
+        world.reset_handler_metrics();
+        assert_eq!(world.handlers()[a].metrics().count(), 0);
+    }
+
+    #[test]
+    fn interceptors() {
+        use core::cell::RefCell;
+
+        use crate::event::{InterceptedEvent, Interceptor, Verdict};
+
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(GlobalEvent)]
+        struct B;
+
+        type Log = Rc<RefCell<Vec<(&'static str, u32, bool)>>>;
+
+        struct Logger(Log);
+
+        impl Interceptor for Logger {
+            fn before(&mut self, event: &mut InterceptedEvent) -> Verdict {
+                if let Some(a) = event.downcast_mut::<A>() {
+                    a.0 *= 10;
+
+                    if a.0 > 100 {
+                        return Verdict::Veto;
+                    }
+                }
+
+                Verdict::Continue
+            }
+
+            fn after(&mut self, event: &InterceptedEvent) {
+                let name = if event.downcast_ref::<A>().is_some() {
+                    "A"
+                } else {
+                    "B"
+                };
+
+                self.0
+                    .borrow_mut()
+                    .push((name, event.depth(), event.is_consumed()));
+            }
+        }
+
+        let mut world = World::new();
+
+        let received = Rc::new(RefCell::new(vec![]));
+        let received_2 = received.clone();
+
+        world.add_handler(move |r: Receiver<A>, s: Sender<B>| {
+            received_2.borrow_mut().push(r.event.0);
+            s.send(B);
+        });
+
+        world.add_handler(|r: ReceiverMut<B>| {
+            EventMut::take(r.event);
+        });
+
+        let log = Log::default();
+        world.add_interceptor(Logger(log.clone()));
+
+        world.send(A(5));
+        world.send(A(50));
+
+        assert_eq!(*received.borrow(), [50]);
+        assert_eq!(*log.borrow(), [("A", 0, false), ("B", 1, true)]);
+    }
+
+    #[test]
+    fn despawn_cascade() {
+        #[derive(Component)]
+        struct Children(Vec<EntityId>);
+
+        #[derive(Component)]
+        struct Attachment(EntityId);
+
+        let mut world = World::new();
+
+        world.add_despawn_cascade(|c: &Children| c.0.clone());
+        let handler = world.add_despawn_cascade(|a: &Attachment| [a.0]);
+
+        let grandchild = world.spawn();
+        let child = world.spawn();
+        let attachment = world.spawn();
+        let root = world.spawn();
+        let other = world.spawn();
+
+        world.insert(child, Children(vec![grandchild]));
+        world.insert(root, Children(vec![child, root]));
+        world.insert(grandchild, Attachment(attachment));
+        // Cycle back to the root.
+        world.insert(attachment, Children(vec![root]));
+
+        world.despawn(root);
+
+        assert_eq!(world.entities().len(), 1);
+        assert!(world.entities().contains(other));
+
+        // Removing the handler stops the cascade.
+        world.remove_handler(handler);
+
+        let attachment = world.spawn();
+        world.insert(other, Attachment(attachment));
+        world.despawn(other);
+
+        assert!(world.entities().contains(attachment));
+    }
+
+    #[test]
+    fn dedup_events() {
+        use core::cell::RefCell;
+
+        use crate::event::Priority;
+
+        #[derive(GlobalEvent, Clone, PartialEq, Eq, Hash, Debug)]
+        struct A(u32);
+
+        #[derive(TargetedEvent)]
+        struct B(u32, &'static str);
+
+        #[derive(GlobalEvent)]
+        struct Start(EntityId, EntityId);
+
+        let mut world = World::new();
+
+        world.dedup::<A>();
+        world.dedup_by_key(|b: &B| b.0);
+
+        world.add_handler(|r: Receiver<Start>, s: Sender<(A, B)>| {
+            s.send(A(1));
+            s.send(A(2));
+            s.send(A(1));
+            s.send_prioritized(Priority::LOW, A(2));
+
+            s.send_to(r.event.0, B(1, "first"));
+            s.send_to(r.event.0, B(1, "second"));
+            s.send_to(r.event.1, B(1, "other target"));
+        });
+
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let log_2 = log.clone();
+        world.add_handler(move |r: Receiver<A>| log_2.borrow_mut().push(format!("{:?}", r.event)));
+
+        let log_3 = log.clone();
+        world.add_handler(move |r: Receiver<B, ()>| log_3.borrow_mut().push(r.event.1.into()));
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+
+        world.send(Start(e1, e2));
+
+        assert_eq!(*log.borrow(), ["A(1)", "A(2)", "first", "other target"]);
+
+        // Duplicates are only removed within a flush.
+        log.borrow_mut().clear();
+        world.send(A(1));
+        world.send(A(1));
+
+        assert_eq!(*log.borrow(), ["A(1)", "A(1)"]);
+    }
+
+    #[test]
+    fn remove_component_with_linked_archetypes() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        // Create archetype `(B)` before `(A, B)`, and link them by removing
+        // `A` rather than inserting it.
+        let e1 = world.spawn();
+        world.insert(e1, B);
+        let e2 = world.spawn();
+        world.insert(e2, A);
+        world.insert(e2, B);
+        world.remove::<A>(e2);
+
+        let b = world.add_component::<B>();
+        world.remove_component(b);
+
+        assert!(!world.entities().contains(e1));
+        assert!(!world.entities().contains(e2));
+    }
+
+    #[test]
+    fn bump_allocator_is_reset() {
+        let mut world = World::new();
+
+        #[derive(GlobalEvent)]
+        struct Data(#[allow(dead_code)] u64);
+
+        world.send(Data(123));
+
+        let ptr1 = world.bump.alloc(1_u8) as *const u8;
+        world.bump.reset();
+        let ptr2 = world.bump.alloc(1_u8) as *const u8;
+
+        assert_eq!(ptr1, ptr2);
+    }
+
+    #[test]
+    fn bump_allocator_is_reused() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(GlobalEvent)]
+        struct B(#[allow(dead_code)] [u64; 8]);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, s: Sender<(A, B)>| {
+            s.send(B([0; 8]));
+            if r.event.0 > 0 {
+                s.send(A(r.event.0 - 1));
+            }
+        });
+
+        // The arena grows to fit a whole broadcast, ...
+        world.send(A(100));
+        world.send(A(100));
+        let allocated = world.bump.allocated_bytes();
+
+        for _ in 0..100 {
+            world.send(A(100));
+        }
+
+        // ... and later broadcasts reuse that memory.
+        assert_eq!(world.bump.allocated_bytes(), allocated);
+    }
+
+    #[test]
+    fn send_to_all() {
+        #[derive(TargetedEvent, Clone)]
+        struct E(#[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<E, (EntityId, &mut A)>, s: Sender<Insert<B>>| {
+            let (id, a) = r.query;
+            a.0 += 1;
+            // Moving the target to a different archetype doesn't affect the
+            // remaining targets.
+            s.insert(id, B);
+        });
+
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, A(0));
+
+            if i % 3 == 0 {
+                world.insert(e, B);
+            }
+        }
+
+        let rc = Rc::new(());
+
+        world.send_to_all::<(With<&A>, Not<&B>), _>(E(rc.clone()));
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let mut counts: Vec<_> = world.iter::<&A>().map(|a| a.0).collect();
+        counts.sort_unstable();
+        assert_eq!(counts, [0, 0, 0, 0, 1, 1, 1, 1, 1, 1]);
+
+        world.send_to_all::<With<&A>, _>(E(rc.clone()));
+        world.send_to_all::<Not<&A>, _>(E(rc.clone()));
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        assert_eq!(world.iter::<&A>().map(|a| a.0).sum::<u32>(), 16);
+    }
+
+    #[test]
+    fn delayed_events() {
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(TargetedEvent)]
+        struct B(u32);
+
+        #[derive(GlobalEvent)]
+        struct Dropped(#[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct Log(Vec<u32>);
+
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log(vec![]));
+
+        world.add_handler(move |r: Receiver<A>, s: Sender<B>| {
+            s.send_to(log, B(r.event.0));
+
+            // Repeat every 2 ticks.
+            if r.event.0 < 3 {
+                s.send_to_delayed(2, log, B(r.event.0 + 100));
+            }
+        });
+
+        world.add_handler(|r: Receiver<B, &mut Log>, s: Sender<A>| {
+            r.query.0.push(r.event.0);
+
+            if r.event.0 >= 100 {
+                s.send_delayed(0, A(r.event.0 - 99));
+            }
+        });
+
+        world.send_delayed(1, A(10));
+        world.send_delayed(0, A(0));
+        world.send_to_delayed(1, log, B(20));
+        world.send_delayed(5, A(30));
+
+        let check = |world: &mut World, ticks, expected: &[u32]| {
+            world.advance(ticks);
+            let log = &mut world.get_mut::<Log>(log).unwrap().0;
+            assert_eq!(log, expected);
+            log.clear();
+        };
+
+        check(&mut world, 0, &[0]);
+        check(&mut world, 1, &[10, 20]);
+        check(&mut world, 1, &[100, 1]);
+        check(&mut world, 2, &[101, 2]);
+        check(&mut world, 1, &[30]);
+        check(&mut world, 10, &[102, 3]);
+        check(&mut world, 10, &[]);
+
+        assert_eq!(world.tick(), 25);
+
+        // Pending events are dropped with the world.
+        let rc = Rc::new(());
+        world.send_delayed(1, Dropped(rc.clone()));
+        drop(world);
+
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn record_and_replay() {
+        #[derive(GlobalEvent, Clone)]
+        struct A(u32);
+
+        #[derive(TargetedEvent, Clone)]
+        struct B(u32);
+
+        #[derive(GlobalEvent, Clone)]
+        struct NotRecorded;
+
+        #[derive(Component)]
+        struct Log(Vec<u32>);
+
+        fn setup() -> World {
+            let mut world = World::new();
+
+            world.add_handler(|r: Receiver<A>, s: Sender<(B, Spawn)>| {
+                let e = s.spawn();
+                s.send_to(e, B(r.event.0 + 1));
+            });
+
+            world.add_handler(|r: Receiver<Spawn>, s: Sender<Insert<Log>>| {
+                s.insert(r.event.0, Log(vec![]));
+            });
+
+            world.add_handler(|r: Receiver<B, &mut Log>| r.query.0.push(r.event.0));
+
+            world.record::<A>();
+            world.record::<B>();
+            world.record::<Spawn>();
+
+            world
+        }
+
+        fn logs(world: &mut World) -> Vec<Vec<u32>> {
+            world.query_iter::<&Log>().map(|l| l.0.clone()).collect()
+        }
+
+        let mut world = setup();
+
+        assert!(world.recorded_events().is_none());
+
+        world.send(A(0));
+
+        world.start_recording();
+
+        let e = world.spawn();
+        world.send_to(e, B(10));
+        world.send(NotRecorded);
+        world.send(A(20));
+        world.send_delayed(1, A(30));
+        world.advance(1);
+
+        let log = world.stop_recording().unwrap();
+
+        let names: Vec<_> = log.iter().map(|e| e.name()).collect();
+        assert_eq!(
+            names,
+            [
+                any::type_name::<Spawn>(),
+                any::type_name::<B>(),
+                any::type_name::<A>(),
+                any::type_name::<A>()
+            ]
+        );
+        assert_eq!(
+            log.iter().map(|e| e.ordinal()).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        // The delayed event is recorded when it is broadcast.
+        assert_eq!(log.iter().nth(3).unwrap().tick(), 1);
+        assert_eq!(log.iter().nth(1).unwrap().target(), Some(e));
+        assert_eq!(
+            log.iter().nth(2).unwrap().downcast_ref::<A>().unwrap().0,
+            20
+        );
+
+        let mut replayed = setup();
+        replayed.send(A(0));
+        log.replay(&mut replayed);
+
+        assert_eq!(logs(&mut replayed), logs(&mut world));
+    }
+
+    #[test]
+    fn send_untyped() {
+        #[derive(GlobalEvent)]
+        struct A(Rc<u32>);
+
+        #[derive(TargetedEvent)]
+        struct B(Rc<u32>);
+
+        #[derive(Component)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C(0));
+
+        world.add_handler(|r: Receiver<A>, mut c: Single<&mut C>| c.0 += *r.event.0);
+        world.add_handler(|r: Receiver<B, &mut C>| r.query.0 += *r.event.0 * 10);
+
+        let a = world.add_global_event::<A>();
+        let b = world.add_targeted_event::<B>();
+
+        let rc = Rc::new(1);
+
+        let mut event = mem::ManuallyDrop::new(A(rc.clone()));
+        unsafe { world.send_untyped(a, (&mut *event as *mut A).cast()) };
+
+        let mut event = mem::ManuallyDrop::new(B(rc.clone()));
+        unsafe { world.send_to_untyped(b, e, (&mut *event as *mut B).cast()) };
+
+        assert_eq!(world.get::<C>(e).unwrap().0, 11);
+        // The events were dropped by the world.
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn send_bytes_typed_event() {
+        #[derive(GlobalEvent)]
+        struct A(#[allow(dead_code)] u32);
+
+        let mut world = World::new();
+        let id = world.add_global_event::<A>();
+
+        world.send_bytes(id, &[0; 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn send_bytes_wrong_size() {
+        let mut world = World::new();
+
+        let id = unsafe {
+            world.add_global_event_with_descriptor(EventDescriptor {
+                name: "Bytes".into(),
+                type_id: None,
+                kind: EventKind::Normal,
+                layout: Layout::new::<[u8; 4]>(),
+                drop: None,
+                mutability: Mutability::Immutable,
+                clone: None,
+                serialize: None,
+                deserialize: None,
+            })
+        };
+
+        world.send_bytes(id, &[0; 3]);
+    }
+
+    #[test]
+    fn send_request() {
+        use crate::event::{Request, RequestError};
+
+        #[derive(Component)]
+        struct Health(u32);
+
+        struct TotalHealth;
+
+        let mut world = World::new();
+
+        for i in 1..=3 {
+            let e = world.spawn();
+            world.insert(e, Health(i));
+        }
+
+        assert_eq!(
+            world.send_request::<TotalHealth, u32>(TotalHealth),
+            Err(RequestError::NoResponse)
+        );
+
+        world.add_handler(
+            |r: Receiver<Request<TotalHealth, u32>>, f: Fetcher<&Health>| {
+                r.event.respond(f.iter().map(|h| h.0).sum());
+            },
+        );
+
+        assert_eq!(world.send_request::<TotalHealth, u32>(TotalHealth), Ok(6));
+
+        world.add_handler(|r: Receiver<Request<TotalHealth, u8>>| {
+            assert!(!r.event.is_answered());
+            r.event.respond(1);
+        });
+
+        world.add_handler(|r: Receiver<Request<TotalHealth, u8>>| {
+            assert!(r.event.is_answered());
+            r.event.respond(2);
+        });
+
+        assert_eq!(
+            world.send_request::<TotalHealth, u8>(TotalHealth),
+            Err(RequestError::MultipleResponses(2))
+        );
+    }
+
+    #[test]
+    fn category_handler() {
+        use core::cell::RefCell;
+
+        struct Cat;
+
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(TargetedEvent)]
+        struct B;
+
+        #[derive(GlobalEvent)]
+        struct Borrowed<'a>(&'a u32);
+
+        #[derive(GlobalEvent)]
+        struct NotInCategory;
+
+        let mut world = World::new();
+
+        let log = Rc::new(RefCell::new(vec![]));
+
+        world.add_to_category::<Cat, A>();
+        world.add_to_category::<Cat, A>();
+        world.add_to_category::<Cat, Borrowed>();
+
+        let log_clone = log.clone();
+        world.add_category_handler::<Cat, _>(move |e| {
+            let value = e
+                .downcast_ref::<A>()
+                .map(|a| a.0)
+                .or_else(|| e.downcast_ref::<Borrowed>().map(|b| *b.0));
+            log_clone.borrow_mut().push((e.target(), value));
+        });
+
+        // Added after the handler.
+        world.add_to_category::<Cat, B>();
+
+        let e = world.spawn();
+
+        world.send(A(1));
+        world.send_to(e, B);
+        world.send(NotInCategory);
+        world.send(Borrowed(&2));
+
+        assert_eq!(
+            *log.borrow(),
+            [(None, Some(1)), (Some(e), None), (None, Some(2))]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn drain_queue() {
+        use std::thread;
+
+        use crate::event::EventQueue;
+
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(TargetedEvent)]
+        struct B(u32);
+
+        #[derive(Component)]
+        struct Log(Vec<u32>);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        let queue = EventQueue::new();
+
+        let q = queue.clone();
+        world.add_handler(move |r: Receiver<A>, mut log: Single<&mut Log>| {
+            log.0.push(r.event.0);
+            // Sent during the next drain.
+            q.push_to(e, B(r.event.0 + 1));
+        });
+        world.add_handler(|r: Receiver<B, &mut Log>| r.query.0.push(r.event.0));
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let queue = queue.clone();
+                thread::spawn(move || queue.push(A(i * 10)))
+            })
+            .collect();
+
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        assert_eq!(queue.len(), 4);
+
+        world.drain_queue(&queue);
+
+        assert_eq!(queue.len(), 4);
+
+        let mut log = mem::take(&mut world.get_mut::<Log>(e).unwrap().0);
+        log.sort_unstable();
+        assert_eq!(log, [0, 10, 20, 30]);
+
+        world.drain_queue(&queue);
+
+        assert!(queue.is_empty());
+
+        let mut log = mem::take(&mut world.get_mut::<Log>(e).unwrap().0);
+        log.sort_unstable();
+        assert_eq!(log, [1, 11, 21, 31]);
+    }
+
+    #[test]
+    fn insert_bundle() {
+        use crate::event::InsertBundle;
+
+        #[derive(Component, PartialEq, Debug)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B(#[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct C(String);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            |r: Receiver<InsertBundle<(A, B, C)>, EntityId>, f: Fetcher<&A>| {
+                // Runs before the components are inserted.
+                assert!(f.get(r.query).is_err());
+            },
+        );
+
+        world.add_handler(|_: Receiver<Insert<A>, ()>| panic!("per-component handler ran"));
+
+        let rc = Rc::new(());
+
+        let e = world.spawn();
+        world.insert_bundle(e, (A(1), B(rc.clone()), C("c".into())));
+
+        assert_eq!(world.get::<A>(e), Some(&A(1)));
+        assert!(world.get::<B>(e).is_some());
+        assert!(world.get::<C>(e).is_some());
+        assert_eq!(Rc::strong_count(&rc), 2);
+
+        // Existing components are replaced and dropped.
+        world.insert_bundle(e, (B(Rc::new(())), A(2)));
+
+        assert_eq!(world.get::<A>(e), Some(&A(2)));
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // Entity with some of the components is moved and keeps the others.
+        let e2 = world.spawn();
+        world.send_to(e2, Insert(C("x".into())));
+        world.send_to(e2, Insert(B(rc.clone())));
+        world.insert_bundle(e2, (A(3), B(Rc::new(()))));
+
+        assert_eq!(world.get::<A>(e2), Some(&A(3)));
+        assert_eq!(world.get::<C>(e2).unwrap().0, "x");
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // Removing one of the components removes the event.
+        let event = world
+            .targeted_events()
+            .get_by_type_id(TypeId::of::<InsertBundle<(A, B, C)>>())
+            .unwrap()
+            .id();
+        let component = world.add_component::<B>();
+        world.remove_component(component);
+
+        assert!(!world.targeted_events().contains(event));
+
+        // The bundle can be used again with the new component.
+        let e3 = world.spawn();
+        world.insert_bundle(e3, (A(4), B(rc.clone()), C("y".into())));
+
+        assert_eq!(world.get::<A>(e3), Some(&A(4)));
+        assert!(world.get::<B>(e3).is_some());
+        assert_eq!(world.get::<C>(e3).unwrap().0, "y");
+    }
+
+    #[test]
+    fn spawn_with() {
+        use crate::event::{InterceptedEvent, Interceptor, SpawnWith, Verdict};
+
+        #[derive(Component, PartialEq, Debug)]
+        struct A(u32);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct B(EntityId);
+
+        #[derive(GlobalEvent)]
+        struct E;
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<SpawnWith<(A,)>>, f: Fetcher<()>| {
+            // Runs before the entity is spawned.
+            assert!(f.get(r.event.0).is_err());
+        });
+
+        world.add_handler(|_: Receiver<Spawn>| panic!("`Spawn` handler ran"));
+
+        type ParentAndChild = (SpawnWith<(A,)>, SpawnWith<(A, B)>);
+
+        world.add_handler(|_: Receiver<E>, s: Sender<ParentAndChild>| {
+            let parent = s.spawn_with((A(1),));
+            s.spawn_with((A(2), B(parent)));
+        });
+
+        world.add_handler(|r: Receiver<SpawnWith<(A, B)>>, f: Fetcher<&A>| {
+            // The parent was spawned with its components.
+            assert_eq!(f.get(r.event.1 .1 .0), Ok(&A(1)));
+        });
+
+        let e = world.spawn_with((A(0),));
+        assert_eq!(world.get::<A>(e), Some(&A(0)));
+
+        world.send(E);
+        assert_eq!(world.entities().len(), 3);
+
+        // Events naming an entity which wasn't reserved are ignored.
+        world.send(SpawnWith(e, (B(e),)));
+        assert_eq!(world.get::<B>(e), None);
+        assert_eq!(world.entities().len(), 3);
+
+        // The entity of a dropped event is released.
+        struct VetoSpawn;
+
+        impl Interceptor for VetoSpawn {
+            fn before(&mut self, event: &mut InterceptedEvent) -> Verdict {
+                match event.downcast_ref::<SpawnWith<(A,)>>() {
+                    Some(_) => Verdict::Veto,
+                    None => Verdict::Continue,
+                }
+            }
+        }
+
+        world.add_interceptor(VetoSpawn);
+
+        let vetoed = world.spawn_with((A(3),));
+        assert!(!world.entities().contains(vetoed));
+
+        let e2 = world.spawn_with((B(e),));
+        assert!(!world.entities().contains(vetoed));
+        assert!(world.entities().contains(e2));
+        assert_eq!(world.entities().len(), 4);
+
+        // Removing one of the components removes the event.
+        let event = world
+            .global_events()
+            .get_by_type_id(TypeId::of::<SpawnWith<(A, B)>>())
+            .unwrap()
+            .id();
+        let component = world.add_component::<B>();
+        world.remove_component(component);
+
+        assert!(!world.global_events().contains(event));
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_bundle_duplicate_components() {
+        #[derive(Component)]
+        struct A;
+
+        let mut world = World::new();
+        let e = world.spawn();
+
+        world.insert_bundle(e, (A, A));
+    }
+
+    #[test]
+    fn add_handler_for_events() {
+        use crate::handler::HandleEvent;
+
+        #[derive(GlobalEvent)]
+        struct A(u32);
+
+        #[derive(GlobalEvent)]
+        struct B(u32);
+
+        #[derive(TargetedEvent)]
+        struct C(u32);
+
+        trait Value {
+            fn value(&self) -> u32;
+        }
+
+        impl Value for A {
+            fn value(&self) -> u32 {
+                self.0
+            }
+        }
+
+        impl Value for B {
+            fn value(&self) -> u32 {
+                self.0 * 10
+            }
+        }
+
+        impl Value for C {
+            fn value(&self) -> u32 {
+                self.0 * 100
+            }
+        }
+
+        struct Sum(Rc<Cell<u32>>);
+
+        impl<E: Value> HandleEvent<E> for Sum {
+            fn handle(&mut self, event: &E) {
+                self.0.set(self.0.get() + event.value());
+            }
+        }
+
+        let mut world = World::new();
+
+        let sum = Rc::new(Cell::new(0));
+
+        let ids = world.add_handler_for_events::<(A, B, C), _>(Sum(sum.clone()));
+        assert_eq!(ids.len(), 3);
+
+        world.send(A(1));
+        world.send(B(2));
+
+        // The target has no components.
+        let e = world.spawn();
+        world.send_to(e, C(3));
+
+        assert_eq!(sum.get(), 321);
+
+        world.remove_handler(ids[1]);
+        world.send(B(2));
+
+        assert_eq!(sum.get(), 321);
+    }
+
+    #[test]
+    fn without_lifecycle_events() {
+        #[derive(Component)]
+        struct C(u32);
+
+        #[derive(GlobalEvent)]
+        struct E;
+
+        let mut world = World::new();
+
+        let count = Rc::new(Cell::new(0));
+
+        let c = count.clone();
+        world.add_handler(move |_: Receiver<Spawn>| c.set(c.get() + 1));
+        let c = count.clone();
+        world.add_handler(move |_: Receiver<Insert<C>, ()>| c.set(c.get() + 1));
+        let c = count.clone();
+        world.add_handler(move |_: Receiver<Remove<C>, ()>| c.set(c.get() + 1));
+        let c = count.clone();
+        world.add_handler(move |_: Receiver<E>| c.set(c.get() + 100));
+
+        let (e1, e2) = world.without_lifecycle_events(|world| {
+            let e1 = world.spawn();
+            world.insert(e1, C(1));
+            let e2 = world.spawn();
+            world.insert(e2, C(2));
+            world.remove::<C>(e2);
+            // Other events are still broadcast.
+            world.send(E);
+            (e1, e2)
+        });
+
+        assert_eq!(count.get(), 100);
+        assert_eq!(world.get::<C>(e1).map(|c| c.0), Some(1));
+        assert!(world.get::<C>(e2).is_none());
+
+        // Lifecycle events are broadcast again after the scope ends, even if it
+        // panics.
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            world.without_lifecycle_events(|_| panic!());
+        }));
+        assert!(res.is_err());
+
+        world.insert(e2, C(3));
+        assert_eq!(count.get(), 101);
+    }
+
+    #[test]
+    fn despawn_reason() {
+        use alloc::vec::Vec;
+        use core::cell::RefCell;
+
+        use crate::event::DespawnReason;
+
+        const KILLED: DespawnReason = DespawnReason("killed");
+
+        #[derive(Component)]
+        struct Children(Vec<EntityId>);
+
+        let mut world = World::new();
+
+        let reasons = Rc::new(RefCell::new(vec![]));
+
+        let r = reasons.clone();
+        world.add_handler(move |d: Receiver<Despawn, EntityId>| {
+            r.borrow_mut().push((d.query, d.event.reason));
+        });
+
+        world.add_despawn_cascade(|c: &Children| c.0.clone());
+
+        let child = world.spawn();
+        let parent = world.spawn();
+        world.insert(parent, Children(vec![child]));
+        let other = world.spawn();
+        let c = world.add_component::<Children>();
+        world.insert(other, Children(vec![]));
+        let plain = world.spawn();
+
+        world.despawn_because(parent, KILLED);
+        world.remove_component(c);
+        world.despawn(plain);
+
+        assert_eq!(
+            *reasons.borrow(),
+            [
+                (parent, Some(KILLED)),
+                (child, Some(KILLED)),
+                (other, Some(DespawnReason::COMPONENT_REMOVED)),
+                (plain, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_target_policy() {
+        use crate::event::{DeadLetter, DeadTargetPolicy};
+
+        #[derive(TargetedEvent)]
+        struct A;
+
+        #[derive(TargetedEvent)]
+        struct B;
+
+        #[derive(TargetedEvent)]
+        struct C;
+
+        let mut world = World::new();
+
+        world.set_dead_target_policy::<B>(DeadTargetPolicy::DeadLetter);
+        world.set_dead_target_policy::<C>(DeadTargetPolicy::Panic);
+
+        let letters = Rc::new(Cell::new(0));
+        let l = letters.clone();
+        world.add_handler(move |_: Receiver<DeadLetter>| l.set(l.get() + 1));
+
+        let e = world.spawn();
+        world.despawn(e);
+
+        world.send_to(e, A);
+        assert_eq!(letters.get(), 0);
+
+        world.send_to(e, B);
+        assert_eq!(letters.get(), 1);
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| world.send_to(e, C)));
+        assert!(res.is_err());
+
+        // The world is still usable after the panic.
+        world.send_to(e, B);
+        assert_eq!(letters.get(), 2);
     }
 
-    /// Returns the [`Handlers`] for this world.
-    pub fn handlers(&self) -> &Handlers {
-        &self.handlers
+    #[test]
+    fn dead_target_panic() {
+        use crate::event::DeadTargetPolicy;
+
+        #[derive(TargetedEvent)]
+        struct A;
+
+        #[derive(GlobalEvent)]
+        struct B(#[allow(dead_code)] Rc<()>);
+
+        #[derive(GlobalEvent)]
+        struct Start(EntityId);
+
+        let mut world = World::new();
+
+        world.set_dead_target_policy::<A>(DeadTargetPolicy::Panic);
+
+        let rc = Rc::new(());
+        let rc_2 = rc.clone();
+        world.add_handler(move |r: Receiver<Start>, s: Sender<(A, B)>| {
+            s.send_to(r.event.0, A);
+            s.send(B(rc_2.clone()));
+        });
+
+        let e = world.spawn();
+        world.despawn(e);
+
+        // Panics regardless of debug assertions.
+        let res = panic::catch_unwind(AssertUnwindSafe(|| world.send(Start(e))));
+        assert!(res.is_err());
+
+        // The queued `B` was dropped.
+        assert_eq!(Rc::strong_count(&rc), 2);
     }
 
-    /// Returns the [`Archetypes`] for this world.
-    pub fn archetypes(&self) -> &Archetypes {
-        &self.archetypes
+    #[test]
+    fn coalesce_structural_events() {
+        #[derive(GlobalEvent)]
+        struct E;
+
+        #[derive(Component)]
+        struct A(#[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct B(#[allow(dead_code)] Rc<()>);
+
+        #[derive(Component)]
+        struct C(#[allow(dead_code)] Rc<()>);
+
+        type Events = (Spawn, Insert<A>, Insert<B>, Insert<C>, Remove<B>);
+
+        let mut world = World::new();
+
+        let rc = Rc::new(());
+
+        let r = rc.clone();
+        world.add_handler(move |_: Receiver<E>, s: Sender<Events>| {
+            let e = s.spawn();
+            s.insert(e, A(r.clone()));
+            s.insert(e, B(r.clone()));
+            s.insert(e, B(r.clone()));
+            s.insert(e, C(r.clone()));
+            s.remove::<B>(e);
+            s.insert(e, A(r.clone()));
+        });
+
+        // Observes the entity between the inserts, which stops them from being
+        // coalesced.
+        let saw_a = Rc::new(Cell::new(false));
+        let s = saw_a.clone();
+        world.add_handler(move |_: Receiver<Insert<C>, Has<&A>>| s.set(true));
+
+        world.send(E);
+
+        assert!(saw_a.get());
+
+        let mut entities = world.iter::<(EntityId, &A, Option<&B>, &C)>();
+        let (_, _, b, _) = entities.next().unwrap();
+        assert!(b.is_none());
+        assert!(entities.next().is_none());
+        drop(entities);
+
+        // Only the handler and the remaining `A` and `C` hold a reference.
+        assert_eq!(Rc::strong_count(&rc), 4);
     }
 
-    /// Returns the [`GlobalEvents`] for this world.
-    pub fn global_events(&self) -> &GlobalEvents {
-        &self.global_events
+    #[test]
+    fn despawn_veto() {
+        #[derive(Component)]
+        struct Shield(u32);
+
+        #[derive(Component)]
+        struct Children(Vec<EntityId>);
+
+        let mut world = World::new();
+
+        let despawned = Rc::new(Cell::new(0));
+        let d = despawned.clone();
+        world.add_handler(move |_: Receiver<Despawn, ()>| d.set(d.get() + 1));
+
+        world.add_despawn_cascade(|c: &Children| c.0.clone());
+
+        let veto = world.add_despawn_veto(|s: &mut Shield, _: &Despawn| {
+            s.0 = s.0.saturating_sub(1);
+            s.0 > 0
+        });
+
+        let child = world.spawn();
+        let e = world.spawn();
+        world.insert(e, Shield(2));
+        world.insert(e, Children(vec![child]));
+
+        // Neither the cascade nor other handlers observe the vetoed despawn.
+        world.despawn(e);
+        assert!(world.entities().contains(e));
+        assert!(world.entities().contains(child));
+        assert_eq!(despawned.get(), 0);
+
+        world.despawn(e);
+        assert!(!world.entities().contains(e));
+        assert!(!world.entities().contains(child));
+        assert_eq!(despawned.get(), 2);
+
+        world.remove_handler(veto);
+
+        let e = world.spawn();
+        world.insert(e, Shield(10));
+        world.despawn(e);
+        assert!(!world.entities().contains(e));
     }
 
-    /// Returns the [`TargetedEvents`] for this world.
-    pub fn targeted_events(&self) -> &TargetedEvents {
-        &self.targeted_events
+    #[test]
+    fn send_boxed() {
+        use crate::event::BoxedEvent;
+
+        #[derive(GlobalEvent)]
+        struct A(Rc<()>);
+
+        #[derive(GlobalEvent)]
+        struct B;
+
+        #[derive(TargetedEvent)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        let a = world.add_global_event::<A>();
+        let b = world.add_global_event::<B>();
+        let c = world.add_targeted_event::<C>();
+        let bytes = unsafe {
+            world.add_global_event_with_descriptor(EventDescriptor {
+                name: "Bytes".into(),
+                type_id: None,
+                kind: EventKind::Normal,
+                layout: Layout::new::<u32>(),
+                drop: None,
+                mutability: Mutability::Immutable,
+                clone: None,
+                serialize: None,
+                deserialize: None,
+            })
+        };
+
+        let sum = Rc::new(Cell::new(0));
+
+        let s = sum.clone();
+        world.add_handler(move |r: Receiver<A>| s.set(s.get() + Rc::strong_count(&r.event.0)));
+        let s = sum.clone();
+        world.add_handler(move |_: Receiver<B>| s.set(s.get() + 10));
+        let s = sum.clone();
+        world.add_handler(move |r: Receiver<C, ()>| s.set(s.get() + r.event.0 as usize));
+
+        let rc = Rc::new(());
+        let e = world.spawn();
+
+        world.send_boxed(BoxedEvent::new(a, A(rc.clone())));
+        world.send_boxed(BoxedEvent::new(b, B));
+        world.send_boxed(BoxedEvent::new_targeted(c, e, C(100)));
+        world.send_boxed(BoxedEvent::from_bytes(bytes, 7_u32.to_ne_bytes()));
+
+        assert_eq!(sum.get(), 112);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // The payload must have the type of the event.
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            world.send_boxed(BoxedEvent::new(a, B));
+        }));
+        assert!(res.is_err());
+        assert_eq!(sum.get(), 112);
     }
 
-    /// Send all queued events to handlers. The event queue will be empty after
-    /// this call.
-    fn flush_event_queue(&mut self) {
-        'next_event: while let Some(item) = self.event_queue.pop() {
-            struct EventDropper<'a> {
-                event: NonNull<u8>,
-                drop: DropFn,
-                ownership_flag: bool,
-                world: &'a mut World,
-            }
+    #[test]
+    fn rate_limit() {
+        use core::cell::RefCell;
+        use core::time::Duration;
 
-            impl<'a> EventDropper<'a> {
-                fn new(event: NonNull<u8>, drop: DropFn, world: &'a mut World) -> Self {
-                    Self {
-                        event,
-                        drop,
-                        ownership_flag: false,
-                        world,
-                    }
-                }
+        use crate::event::{Overflow, RateLimit};
 
-                /// Extracts the event pointer and drop fn without running
-                /// the destructor.
-                #[inline]
-                fn unpack(self) -> (NonNull<u8>, DropFn) {
-                    let event = self.event;
-                    let drop = self.drop;
-                    mem::forget(self);
+        #[derive(GlobalEvent)]
+        struct A(u32);
 
-                    (event, drop)
-                }
+        #[derive(GlobalEvent)]
+        struct B(u32);
 
-                /// Drops the held event.
-                #[inline]
-                unsafe fn drop_event(self) {
-                    if let (event, Some(drop)) = self.unpack() {
-                        drop(event);
-                    }
-                }
+        #[derive(GlobalEvent)]
+        struct Start;
+
+        let mut world = World::new();
+
+        world.rate_limit::<A>(RateLimit::per_flush(2));
+        world.rate_limit::<B>(
+            RateLimit::min_interval(Duration::from_secs(1)).overflow(Overflow::Coalesce),
+        );
+
+        world.add_handler(|_: Receiver<Start>, s: Sender<(A, B)>| {
+            for i in 0..5 {
+                s.send(A(i));
+                s.send(B(i));
             }
+        });
 
-            impl Drop for EventDropper<'_> {
-                #[cold]
-                fn drop(&mut self) {
-                    // In case `Handler::run` unwinds, we need to drop the event we're holding on
-                    // the stack as well as the events still the in the event queue.
+        let log = Rc::new(RefCell::new(vec![]));
 
-                    #[cfg(feature = "std")]
-                    debug_assert!(std::thread::panicking());
+        let log_2 = log.clone();
+        world.add_handler(move |r: Receiver<A>| log_2.borrow_mut().push(format!("A{}", r.event.0)));
 
-                    // Drop the held event.
-                    if !self.ownership_flag {
-                        if let Some(drop) = self.drop {
-                            unsafe { drop(self.event) };
-                        }
-                    }
+        let log_3 = log.clone();
+        world.add_handler(move |r: Receiver<B>| log_3.borrow_mut().push(format!("B{}", r.event.0)));
 
-                    // Drop all events remaining in the event queue.
-                    // This must be done here instead of the World's destructor because events
-                    // could contain borrowed data.
-                    for item in &self.world.event_queue {
-                        let drop = match item.meta {
-                            EventMeta::Global { idx } => unsafe {
-                                self.world
-                                    .global_events
-                                    .get_by_index(idx)
-                                    .unwrap_unchecked()
-                                    .drop()
-                            },
-                            EventMeta::Targeted { idx, .. } => unsafe {
-                                self.world
-                                    .targeted_events
-                                    .get_by_index(idx)
-                                    .unwrap_unchecked()
-                                    .drop()
-                            },
-                        };
+        world.send(Start);
 
-                        if let Some(drop) = drop {
-                            unsafe { drop(item.event) };
-                        }
-                    }
+        assert_eq!(*log.borrow(), ["A0", "B0", "A1"]);
+        log.borrow_mut().clear();
 
-                    self.world.event_queue.clear();
-                }
-            }
+        // The held `B` is released once the interval has passed.
+        world.advance_time(Duration::from_millis(500));
+        assert!(log.borrow().is_empty());
 
-            let (mut ctx, event_kind, handlers, target_location) = match item.meta {
-                EventMeta::Global { idx } => {
-                    let info = unsafe { self.global_events.get_by_index(idx).unwrap_unchecked() };
-                    let kind = info.kind();
-                    let handlers: *const [_] =
-                        unsafe { self.handlers.get_global_list(idx).unwrap_unchecked() }.slice();
-                    let ctx = EventDropper::new(item.event, info.drop(), self);
+        world.advance_time(Duration::from_millis(500));
+        assert_eq!(*log.borrow(), ["B4"]);
+        log.borrow_mut().clear();
 
-                    let location = EntityLocation::NULL;
+        // Counts are reset for every flush.
+        world.send(A(10));
+        world.send(A(11));
+        assert_eq!(*log.borrow(), ["A10", "A11"]);
+    }
 
-                    (ctx, kind, handlers, location)
-                }
-                EventMeta::Targeted { idx, target } => {
-                    let info = unsafe { self.targeted_events.get_by_index(idx).unwrap_unchecked() };
-                    let kind = info.kind();
-                    let ctx = EventDropper::new(item.event, info.drop(), self);
+    #[test]
+    fn rate_limit_event() {
+        use core::ptr::NonNull;
+        use core::time::Duration;
 
-                    let Some(location) = ctx.world.entities.get(target) else {
-                        // Entity doesn't exist. Skip the event.
-                        unsafe { ctx.drop_event() };
-                        continue;
-                    };
+        use crate::access::Access;
+        use crate::event::{Overflow, RateLimit};
+        use crate::handler::{RawHandler, RawHandlerDescriptor};
 
-                    let arch = unsafe {
-                        ctx.world
-                            .archetypes
-                            .get(location.archetype)
-                            .unwrap_unchecked()
-                    };
+        #[derive(GlobalEvent)]
+        struct Borrowed<'a>(&'a Cell<u32>);
 
-                    static EMPTY: HandlerList = HandlerList::new();
+        let mut world = World::new();
 
-                    let handlers: *const [_] = arch.handler_list_for(idx).unwrap_or(&EMPTY).slice();
+        let limit = RateLimit::min_interval(Duration::from_secs(1)).overflow(Overflow::Coalesce);
+
+        // An event without a Rust type is held back until the interval has
+        // passed.
+        let script = unsafe {
+            world.add_global_event_with_descriptor(EventDescriptor {
+                name: "Script".into(),
+                type_id: None,
+                kind: EventKind::Normal,
+                layout: Layout::new::<[u8; 4]>(),
+                drop: None,
+                mutability: Mutability::Immutable,
+                clone: None,
+                serialize: None,
+                deserialize: None,
+            })
+        };
 
-                    (ctx, kind, handlers, location)
-                }
-            };
+        world.rate_limit_event(script.into(), limit);
+
+        let count = Rc::new(Cell::new(0));
+        let count_2 = count.clone();
+        world.add_handler(unsafe {
+            RawHandler::new(RawHandlerDescriptor {
+                name: "script".into(),
+                received_events: vec![script.into()],
+                received_event_access: Access::Read,
+                target_access: vec![],
+                component_access: vec![],
+                sent_events: vec![],
+                run: |data, _, event, _, _| {
+                    let bytes = event.as_ptr().cast::<[u8; 4]>().read();
+                    data.cast::<Cell<u32>>()
+                        .as_ref()
+                        .set(u32::from_le_bytes(bytes));
+                },
+                data: NonNull::from(&*count_2).cast(),
+                drop: None,
+            })
+        });
 
-            let events_before = ctx.world.event_queue.len();
+        world.send_bytes(script, &1_u32.to_le_bytes());
+        world.send_bytes(script, &2_u32.to_le_bytes());
+        assert_eq!(count.get(), 1);
 
-            for mut info_ptr in unsafe { (*handlers).iter().copied() } {
-                let info = unsafe { info_ptr.as_info_mut() };
+        world.advance_time(Duration::from_secs(1));
+        assert_eq!(count.get(), 2);
 
-                let handler: *mut dyn Handler = info.handler_mut();
+        // An event which may borrow is dropped at the end of the flush.
+        let borrowed = world.add_global_event::<Borrowed>();
+        world.rate_limit_event(borrowed.into(), limit);
 
-                let event_ptr = EventPtr::new(ctx.event, NonNull::from(&mut ctx.ownership_flag));
+        let seen = Rc::new(Cell::new(0));
+        let seen_2 = seen.clone();
+        world.add_handler(move |r: Receiver<Borrowed>| seen_2.set(r.event.0.get()));
 
-                let world_cell = ctx.world.unsafe_cell_mut();
+        {
+            let a = Cell::new(1);
+            let b = Cell::new(2);
+            world.send(Borrowed(&a));
+            world.send(Borrowed(&b));
+        }
 
-                unsafe { (*handler).run(info, event_ptr, target_location, world_cell) };
+        world.advance_time(Duration::from_secs(1));
+        assert_eq!(seen.get(), 1);
+    }
 
-                // Did the handler take ownership of the event?
-                if ctx.ownership_flag {
-                    // Don't drop event since we don't own it anymore.
-                    ctx.unpack();
+    #[test]
+    fn owned_handlers() {
+        #[derive(GlobalEvent)]
+        struct A;
 
-                    // Reverse pushed events so they're handled in FIFO order.
-                    unsafe {
-                        self.event_queue
-                            .get_unchecked_mut(events_before..)
-                            .reverse()
-                    };
+        let mut world = World::new();
 
-                    continue 'next_event;
-                }
-            }
+        let owner = world.spawn();
+        let other = world.spawn();
 
-            // Reverse pushed events so they're handled in FIFO order.
-            unsafe {
-                ctx.world
-                    .event_queue
-                    .get_unchecked_mut(events_before..)
-                    .reverse()
-            };
+        let count = Rc::new(Cell::new(0));
 
-            match event_kind {
-                EventKind::Normal => {
-                    // Ordinary event. Run drop fn.
-                    unsafe { ctx.drop_event() };
-                }
-                EventKind::Insert { component_idx } => {
-                    debug_assert_ne!(target_location, EntityLocation::NULL);
+        let count_2 = count.clone();
+        let h1 =
+            world.add_owned_handler(owner, move |_: Receiver<A>| count_2.set(count_2.get() + 1));
 
-                    let dst = unsafe {
-                        ctx.world.archetypes.traverse_insert(
-                            target_location.archetype,
-                            component_idx,
-                            &mut ctx.world.components,
-                            &mut ctx.world.handlers,
-                        )
-                    };
+        // Despawning the owner from a handler removes the handler after the flush.
+        let h2 = world.add_owned_handler(owner, move |_: Receiver<A>, s: Sender<Despawn>| {
+            s.despawn(owner)
+        });
 
-                    // `Insert<C>` is `repr(transparent)`.
-                    let component_ptr = ctx.event.as_ptr().cast_const();
+        let h3 = world.add_owned_handler(other, |_: Receiver<A>| {});
 
-                    unsafe {
-                        ctx.world.archetypes.move_entity(
-                            target_location,
-                            dst,
-                            [(component_idx, component_ptr)],
-                            &mut ctx.world.entities,
-                        )
-                    };
+        world.send(A);
 
-                    // Inserted component is owned by the archetype now. We wait to unpack
-                    // in case one of the above functions panics.
-                    ctx.unpack();
-                }
-                EventKind::Remove { component_idx } => {
-                    // `Remove` doesn't need drop.
-                    let _ = ctx.unpack();
+        assert_eq!(count.get(), 1);
+        assert!(!world.handlers().contains(h1));
+        assert!(!world.handlers().contains(h2));
+        assert!(world.handlers().contains(h3));
 
-                    let dst = unsafe {
-                        self.archetypes.traverse_remove(
-                            target_location.archetype,
-                            component_idx,
-                            &mut self.components,
-                            &mut self.handlers,
-                        )
-                    };
+        world.send(A);
+        assert_eq!(count.get(), 1);
 
-                    unsafe {
-                        self.archetypes
-                            .move_entity(target_location, dst, [], &mut self.entities)
-                    };
-                }
-                EventKind::Spawn => {
-                    // `Spawn` doesn't need drop.
-                    let _ = ctx.unpack();
+        // The owner doesn't exist.
+        let h4 = world.add_owned_handler(owner, |_: Receiver<A>| {});
+        assert!(!world.handlers().contains(h4));
 
-                    // Spawn all entities from the reserved entity queue.
-                    self.reserved_entities
-                        .spawn_all(&mut self.entities, |id| self.archetypes.spawn(id));
-                }
-                EventKind::Despawn => {
-                    // `Despawn` doesn't need drop.
-                    let _ = ctx.unpack();
+        // Handlers don't run after their owner is despawned in the same flush.
+        #[derive(GlobalEvent)]
+        struct B;
+
+        let owner = world.spawn();
+
+        world.add_handler(move |_: Receiver<B>, s: Sender<(Despawn, A)>| {
+            s.despawn(owner);
+            s.send(A);
+        });
+
+        let count_2 = count.clone();
+        let h5 =
+            world.add_owned_handler(owner, move |_: Receiver<A>| count_2.set(count_2.get() + 1));
+
+        world.send(B);
+        assert_eq!(count.get(), 1);
+        assert!(!world.handlers().contains(h5));
+
+        // Removing an owned handler forgets its owner.
+        let h6 = world.add_owned_handler(other, |_: Receiver<A>| {});
+        world.remove_handler(h3);
+        world.remove_handler(h6);
+        assert!(world.handler_owners.is_empty());
+    }
+
+    #[test]
+    fn shuffle_handler_order() {
+        use core::cell::RefCell;
+
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(TargetedEvent)]
+        struct B;
 
-                    unsafe {
-                        self.archetypes
-                            .remove_entity(target_location, &mut self.entities)
-                    };
+        #[derive(Component)]
+        struct C;
 
-                    // Reset next key iter.
-                    self.reserved_entities.refresh(&self.entities);
-                }
-            }
-        }
+        #[derive(Component)]
+        struct D;
 
-        self.bump.reset();
-        debug_assert!(self.event_queue.is_empty());
-    }
+        let mut world = World::new();
 
-    /// Returns a new [`UnsafeWorldCell`] with permission to _read_ all data in
-    /// this world.
-    pub fn unsafe_cell(&self) -> UnsafeWorldCell {
-        UnsafeWorldCell {
-            world: NonNull::from(self),
-            _marker: PhantomData,
-        }
-    }
+        let log = Rc::new(RefCell::new(vec![]));
 
-    /// Returns a new [`UnsafeWorldCell`] with permission to _read and write_
-    /// all data in this world.
-    pub fn unsafe_cell_mut(&mut self) -> UnsafeWorldCell {
-        UnsafeWorldCell {
-            world: NonNull::from(self),
-            _marker: PhantomData,
+        // Every closure needs a distinct type.
+        macro_rules! add_handlers {
+            ($($i:literal)*) => {$(
+                let l = log.clone();
+                world.add_handler((move |_: Receiver<A>| l.borrow_mut().push($i)).low());
+                let l = log.clone();
+                world.add_handler(move |_: Receiver<B, ()>| l.borrow_mut().push($i));
+            )*};
         }
-    }
-}
 
-impl Default for World {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        add_handlers!(0 1 2 3 4 5 6 7);
 
-/// Reference to a [`World`] where all methods take `self` and aliasing rules
-/// are not checked. It is the caller's responsibility to ensure that Rust's
-/// aliasing rules are not violated.
-#[derive(Clone, Copy, Debug)]
-pub struct UnsafeWorldCell<'a> {
-    world: NonNull<World>,
-    _marker: PhantomData<&'a World>,
-}
+        let l = log.clone();
+        world.add_handler((move |_: Receiver<A>| l.borrow_mut().push(100)).high());
 
-impl<'a> UnsafeWorldCell<'a> {
-    /// Allocate data in the world's bump allocator.
-    ///
-    /// This operation is not thread safe.
-    ///
-    /// # Safety
-    ///
-    /// - Must be called from within a handler.
-    #[inline]
-    pub unsafe fn alloc_layout(self, layout: Layout) -> NonNull<u8> {
-        let bump = unsafe { &(*self.world.as_ptr()).bump };
-        bump.alloc_layout(layout)
-    }
+        let run = |world: &mut World| {
+            // Spawn into a new archetype to check that its handler lists are
+            // ordered the same way.
+            let e = world.spawn();
+            world.insert(e, C);
 
-    /// Add a global event to the event queue. Ownership of the event is
-    /// transferred.
-    ///
-    /// # Safety
-    ///
-    /// - Must be called from within a handler.
-    /// - Event must outlive call to top level [`World::send`] or
-    ///   [`World::send_to`].
-    /// - Event index must be correct for the given event.
-    #[inline]
-    pub unsafe fn queue_global(self, event: NonNull<u8>, idx: GlobalEventIdx) {
-        let event_queue = &mut (*self.world.as_ptr()).event_queue;
+            world.send(A);
+            world.send_to(e, B);
+            world.despawn(e);
 
-        event_queue.push(EventQueueItem {
-            meta: EventMeta::Global { idx },
-            event,
-        });
+            mem::take(&mut *log.borrow_mut())
+        };
+
+        let default = run(&mut world);
+        assert_eq!(
+            default,
+            [100, 0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 7]
+        );
+
+        world.shuffle_handler_order(Some(42));
+        let shuffled = run(&mut world);
+
+        assert_ne!(shuffled, default);
+        // High priority handlers still run first.
+        assert_eq!(shuffled[0], 100);
+        assert_eq!(run(&mut world), shuffled);
+
+        // Archetypes created after shuffling use the same order.
+        let e = world.spawn();
+        world.insert(e, C);
+        world.insert(e, D);
+        world.send_to(e, B);
+        assert_eq!(mem::take(&mut *log.borrow_mut()), shuffled[9..]);
+
+        world.shuffle_handler_order(None);
+        assert_eq!(run(&mut world), default);
     }
 
-    /// Add a targeted event to the event queue. Ownership of the event is
-    /// transferred.
-    ///
-    /// # Safety
-    ///
-    /// - Must be called from within a handler.
-    /// - Must have permission to access the event queue
-    #[inline]
-    pub unsafe fn queue_targeted(
-        self,
-        target: EntityId,
-        event: NonNull<u8>,
-        idx: TargetedEventIdx,
-    ) {
-        let event_queue = &mut (*self.world.as_ptr()).event_queue;
+    #[test]
+    fn handler_labels() {
+        use core::cell::RefCell;
 
-        event_queue.push(EventQueueItem {
-            meta: EventMeta::Targeted { idx, target },
-            event,
-        });
-    }
+        #[derive(GlobalEvent)]
+        struct A;
 
-    /// # Safety
-    ///
-    /// - Must be called from within a handler.
-    pub unsafe fn queue_spawn(self) -> EntityId {
-        let entity_id = (*self.world.as_ptr())
-            .reserved_entities
-            .reserve(self.entities());
+        #[derive(TargetedEvent)]
+        struct B;
 
-        entity_id
-    }
+        #[derive(Component)]
+        struct C;
 
-    /// Returns the [`Entities`] for this world.
-    pub fn entities(self) -> &'a Entities {
-        unsafe { &(*self.world.as_ptr()).entities }
-    }
+        let mut world = World::new();
+        let log = Rc::new(RefCell::new(vec![]));
 
-    /// Returns the [`Components`] for this world.
-    pub fn components(self) -> &'a Components {
-        unsafe { &(*self.world.as_ptr()).components }
-    }
+        macro_rules! push {
+            ($n:literal) => {{
+                let l = log.clone();
+                move |_: Receiver<A>| l.borrow_mut().push($n)
+            }};
+        }
 
-    /// Returns the [`Handlers`] for this world.
-    pub fn handlers(self) -> &'a Handlers {
-        unsafe { &(*self.world.as_ptr()).handlers }
+        world.add_handler(push!(0).after("physics").label("render"));
+        world.add_handler(push!(1).label("physics"));
+        world.add_handler(push!(2).before("physics").label("input"));
+        world.add_handler(push!(3).before("input").low());
+        world.add_handler(push!(4));
+
+        world.send(A);
+        assert_eq!(mem::take(&mut *log.borrow_mut()), [2, 1, 0, 4, 3]);
+
+        // Label constraints apply to targeted handlers as well.
+        let l = log.clone();
+        world.add_handler((move |_: Receiver<B, &C>| l.borrow_mut().push(5)).after("input"));
+        let l = log.clone();
+        world.add_handler((move |_: Receiver<B, &C>| l.borrow_mut().push(6)).label("input"));
+
+        let e = world.spawn();
+        world.insert(e, C);
+        world.send_to(e, B);
+        assert_eq!(mem::take(&mut *log.borrow_mut()), [6, 5]);
+
+        // Shuffling keeps the constraints.
+        world.shuffle_handler_order(Some(7));
+        world.send(A);
+        let order = mem::take(&mut *log.borrow_mut());
+        let pos = |n| order.iter().position(|&x| x == n).unwrap();
+        assert!(pos(2) < pos(1) && pos(1) < pos(0));
+        assert_eq!(order[4], 3);
     }
 
-    /// Returns the [`Archetypes`] for this world.
-    pub fn archetypes(self) -> &'a Archetypes {
-        unsafe { &(*self.world.as_ptr()).archetypes }
-    }
+    #[test]
+    fn handler_groups() {
+        use core::cell::RefCell;
 
-    /// Returns the [`GlobalEvents`] for this world.
-    pub fn global_events(self) -> &'a GlobalEvents {
-        unsafe { &(*self.world.as_ptr()).global_events }
-    }
+        #[derive(GlobalEvent)]
+        struct A;
 
-    /// Returns the [`TargetedEvents`] for this world.
-    pub fn targeted_events(self) -> &'a TargetedEvents {
-        unsafe { &(*self.world.as_ptr()).targeted_events }
-    }
+        let mut world = World::new();
+        let log = Rc::new(RefCell::new(vec![]));
 
-    /// Returns an immutable reference to the underlying world.
-    ///
-    /// # Safety
-    ///
-    /// Must have permission to access the entire world immutably.
-    pub fn world(self) -> &'a World {
-        unsafe { &*self.world.as_ptr() }
-    }
+        macro_rules! push {
+            ($n:literal) => {{
+                let l = log.clone();
+                move |_: Receiver<A>| l.borrow_mut().push($n)
+            }};
+        }
 
-    /// Returns a mutable reference to the underlying world.
-    ///
-    /// # Safety
-    ///
-    /// Must have permission the access the entire world mutably.
-    pub unsafe fn world_mut(self) -> &'a mut World {
-        &mut *self.world.as_ptr()
-    }
-}
+        world.add_handler(push!(0).label("ai"));
+        let id = world.add_handler(push!(1).label("debug").label("ai"));
+        world.add_handler(push!(2));
 
-#[cfg(test)]
-mod tests {
-    use alloc::rc::Rc;
-    use core::panic::AssertUnwindSafe;
-    use std::panic;
+        world.set_group_enabled("ai", false);
+        assert!(!world.is_group_enabled("ai"));
+        assert!(!world.handlers().get(id).unwrap().is_enabled());
 
-    use crate::prelude::*;
+        world.add_handler(push!(3).label("ai"));
+        world.send(A);
+        assert_eq!(mem::take(&mut *log.borrow_mut()), [2]);
+
+        world.set_group_enabled("debug", false);
+        world.set_group_enabled("ai", true);
+        world.send(A);
+        assert_eq!(mem::take(&mut *log.borrow_mut()), [0, 2, 3]);
+
+        world.set_group_enabled("debug", true);
+        world.send(A);
+        assert_eq!(mem::take(&mut *log.borrow_mut()), [0, 1, 2, 3]);
+    }
 
     #[test]
-    fn world_drops_events() {
+    fn add_handler_once() {
         #[derive(GlobalEvent)]
-        struct A(Rc<()>);
+        struct A;
 
         #[derive(GlobalEvent)]
-        struct B(Rc<()>);
+        struct B;
 
-        #[derive(GlobalEvent)]
-        struct C(Rc<()>);
+        #[derive(Component)]
+        struct Count(u32);
 
         let mut world = World::new();
 
-        world.add_handler(|r: Receiver<A>, s: Sender<B>| {
-            s.send(B(r.event.0.clone()));
-            s.send(B(r.event.0.clone()));
-        });
-
-        world.add_handler(|r: Receiver<B>, s: Sender<C>| {
-            s.send(C(r.event.0.clone()));
-            s.send(C(r.event.0.clone()));
-        });
+        let e = world.spawn();
+        world.insert(e, Count(0));
 
-        world.add_handler(|r: Receiver<C>| println!("got C {:?}", Rc::as_ptr(&r.event.0)));
+        fn handler(_: Receiver<A>, mut c: Single<&mut Count>) {
+            c.0 += 1;
+        }
 
-        let rc = Rc::new(());
+        let id_1 = world.add_handler_once(handler);
+        let id_2 = world.add_handler_once(handler);
+        assert_ne!(id_1, id_2);
 
-        world.send(A(rc.clone()));
+        // Both run, but only once, even when the event is sent again within
+        // the same flush.
+        world.add_handler(|_: Receiver<B>, s: Sender<(A, A)>| {
+            s.send(A);
+            s.send(A);
+        });
 
-        drop(world);
+        world.send(B);
+        assert_eq!(world.get::<Count>(e).unwrap().0, 2);
+        assert!(!world.handlers().contains(id_1));
+        assert!(!world.handlers().contains(id_2));
 
-        assert_eq!(Rc::strong_count(&rc), 1);
+        world.send(A);
+        assert_eq!(world.get::<Count>(e).unwrap().0, 2);
     }
 
+    #[cfg(feature = "rayon")]
     #[test]
-    fn world_drops_events_on_panic() {
+    fn parallel_handlers() {
+        use std::sync::Mutex;
+
         #[derive(GlobalEvent)]
-        struct A(Rc<()>);
+        struct E;
 
-        impl Drop for A {
-            fn drop(&mut self) {
-                eprintln!("calling A destructor");
-            }
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B(u32);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, A(0));
+        world.insert(e, B(0));
+
+        static SEEN: Mutex<Vec<u32>> = Mutex::new(vec![]);
+
+        unsafe {
+            world.add_parallel_handler(|_: Receiver<E>, mut a: Single<&mut A>| a.0 += 1);
+            world.add_parallel_handler(|_: Receiver<E>, mut b: Single<&mut B>| b.0 += 1);
+            world.add_parallel_handler(|_: Receiver<E>, a: Single<&A>| {
+                SEEN.lock().unwrap().push(a.0)
+            });
         }
 
-        #[derive(GlobalEvent)]
-        struct B(Rc<()>);
+        world.add_handler(|_: Receiver<E>, b: Single<&B>| SEEN.lock().unwrap().push(b.0));
 
-        #[allow(dead_code)]
+        // Handlers which can't run in parallel are rejected.
+        assert!(world
+            .try_add_handler(crate::handler::Parallel(
+                (|_: Receiver<E>, _: Sender<E>| {}).into_handler()
+            ))
+            .is_err());
+
+        let idx = world
+            .global_events()
+            .get_by_type_id(TypeId::of::<E>())
+            .unwrap()
+            .id()
+            .index();
+        let list = world.handlers.get_global_list(idx).unwrap();
+
+        // The third handler reads `A`, which the first writes, so it starts a
+        // new group. The last handler isn't parallel.
+        assert_eq!(list.parallel_end(0), 2);
+        assert_eq!(list.parallel_end(2), 3);
+        assert_eq!(list.parallel_end(3), 4);
+
+        world.send(E);
+
+        assert_eq!(world.get::<A>(e).unwrap().0, 1);
+        assert_eq!(world.get::<B>(e).unwrap().0, 1);
+        assert_eq!(*SEEN.lock().unwrap(), [1, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn catch_panics() {
         #[derive(GlobalEvent)]
-        struct C(Rc<()>);
+        struct E;
+
+        #[derive(Component)]
+        struct Log(Vec<String>);
 
         let mut world = World::new();
+        world.set_catch_panics(true);
 
-        world.add_handler(|r: Receiver<A>, s: Sender<B>| {
-            s.send(B(r.event.0.clone()));
-            s.send(B(r.event.0.clone()));
-        });
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
 
-        world.add_handler(|r: Receiver<B>, s: Sender<C>| {
-            s.send(C(r.event.0.clone()));
-            s.send(C(r.event.0.clone()));
+        let bad = world.add_handler(|_: Receiver<E>| panic!("bad handler {}", 1));
+        world.add_handler(|_: Receiver<E>, mut log: Single<&mut Log>| log.0.push("next".into()));
+        world.add_handler(|r: Receiver<HandlerPanicked>, mut log: Single<&mut Log>| {
+            log.0
+                .push(format!("{:?}: {}", r.event.handler, r.event.message))
         });
 
-        world.add_handler(|_: Receiver<C>| panic!("oops!"));
+        world.send(E);
 
-        let arc = Rc::new(());
-        let arc_cloned = arc.clone();
+        assert_eq!(
+            world.get::<Log>(e).unwrap().0,
+            ["next".to_owned(), format!("{bad:?}: bad handler 1")]
+        );
 
-        let mut world = AssertUnwindSafe(world);
+        // Panics of `HandlerPanicked` handlers aren't caught.
+        world.add_handler(|_: Receiver<HandlerPanicked>| panic!("again"));
 
-        let res = panic::catch_unwind(move || world.send(A(arc_cloned)));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| world.send(E)));
+        assert!(res.is_err());
+    }
 
-        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "oops!");
+    #[test]
+    fn access_conflicts() {
+        #[derive(GlobalEvent)]
+        struct E;
 
-        assert_eq!(Rc::strong_count(&arc), 1);
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        let err = world
+            .try_add_handler(|_: Receiver<E>, _: Fetcher<(&mut A, &B)>, _: Fetcher<&A>| {})
+            .unwrap_err();
+
+        let a = world.add_component::<A>();
+
+        assert_eq!(err.conflicts().len(), 1);
+        assert_eq!(err.conflicts()[0].component, a);
+        assert_eq!(
+            err.conflicts()[0].params,
+            [
+                Some(any::type_name::<Fetcher<(&mut A, &B)>>()),
+                Some(any::type_name::<Fetcher<&A>>())
+            ]
+        );
+
+        let err = world
+            .try_add_handler(|_: Receiver<E>, _: Fetcher<(&mut B, &B)>| {})
+            .unwrap_err();
+
+        let param = Some(any::type_name::<Fetcher<(&mut B, &B)>>());
+        assert_eq!(err.conflicts()[0].params, [param, param]);
+
+        let err = world.try_add_handler(|| {}).unwrap_err();
+        assert!(err.conflicts().is_empty());
     }
 
     #[test]
-    fn bump_allocator_is_reset() {
+    fn replace_handler() {
+        #[derive(GlobalEvent)]
+        struct A;
+
+        #[derive(TargetedEvent)]
+        struct B;
+
+        #[derive(Component)]
+        struct C;
+
         let mut world = World::new();
 
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let l = log.clone();
+        let id = world.add_handler(
+            (move |_: Receiver<A>| l.borrow_mut().push("old"))
+                .label("x")
+                .after("y"),
+        );
+
+        let l = log.clone();
+        world.add_handler((move |_: Receiver<A>| l.borrow_mut().push("y")).label("y"));
+
+        let l = log.clone();
+        let old = world
+            .replace_handler(
+                id,
+                (move |_: Receiver<A>| l.borrow_mut().push("new")).label("x"),
+            )
+            .unwrap();
+        assert_eq!(old.id(), id);
+
+        let info = &world.handlers()[id];
+        assert_eq!(info.labels(), ["x"]);
+        assert_eq!(info.runs_after(), ["y"]);
+        assert_eq!(world.handlers().iter().next().unwrap().id(), id);
+
+        world.send(A);
+        assert_eq!(*log.borrow(), ["y", "new"]);
+
+        let l = log.clone();
+        world.replace_handler(id, move |_: Receiver<B, &C>| {
+            l.borrow_mut().push("targeted")
+        });
+
+        let e = world.spawn();
+        world.insert(e, C);
+
+        world.send(A);
+        world.send_to(e, B);
+        assert_eq!(*log.borrow(), ["y", "new", "y", "targeted"]);
+
+        world.remove_handler(id);
+        assert!(world.replace_handler(id, |_: Receiver<A>| {}).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn handler_budget() {
+        use core::time::Duration;
+
+        use crate::handler::{HandlerBudget, HandlerOverBudget};
+
         #[derive(GlobalEvent)]
-        struct Data(#[allow(dead_code)] u64);
+        struct A;
 
-        world.send(Data(123));
+        let mut world = World::new();
 
-        let ptr1 = world.bump.alloc(1_u8) as *const u8;
-        world.bump.reset();
-        let ptr2 = world.bump.alloc(1_u8) as *const u8;
+        let runs = Rc::new(Cell::new(0));
+        let r = runs.clone();
+        let slow = world.add_handler(
+            (move |_: Receiver<A>| {
+                r.set(r.get() + 1);
+                std::thread::sleep(Duration::from_millis(1));
+            })
+            .label("slow"),
+        );
+
+        let overruns = Rc::new(RefCell::new(vec![]));
+        let o = overruns.clone();
+        world.add_handler(move |r: Receiver<HandlerOverBudget>| {
+            assert!(r.event.duration > r.event.budget.time);
+            o.borrow_mut().push(r.event.handler);
+        });
 
-        assert_eq!(ptr1, ptr2);
+        world.send(A);
+        assert!(overruns.borrow().is_empty());
+
+        let budget = HandlerBudget::new(Duration::ZERO).skip_next_tick();
+        world.set_group_budget("slow", Some(budget));
+        assert_eq!(world.handlers()[slow].budget(), Some(budget));
+
+        world.send(A);
+        world.send(A);
+        assert_eq!(runs.get(), 3);
+        assert_eq!(*overruns.borrow(), [slow, slow]);
+
+        // The handler is skipped during the next tick.
+        world.advance(1);
+        world.send(A);
+        assert_eq!(runs.get(), 3);
+
+        // The handler's own budget takes precedence over its group's. The
+        // pending skip still applies.
+        world.set_handler_budget(slow, Some(HandlerBudget::new(Duration::from_secs(60))));
+        world.send(A);
+        assert_eq!(runs.get(), 3);
+
+        world.advance(1);
+        world.send(A);
+        world.send(A);
+        assert_eq!(runs.get(), 5);
+        assert_eq!(overruns.borrow().len(), 2);
+
+        // Frames advanced with `advance_time` skip the handler as well.
+        world.set_handler_budget(slow, Some(budget));
+        world.send(A);
+        assert_eq!(runs.get(), 6);
+
+        world.advance_time(Duration::ZERO);
+        world.send(A);
+        assert_eq!(runs.get(), 6);
+
+        world.advance_time(Duration::ZERO);
+        world.send(A);
+        assert_eq!(runs.get(), 7);
+
+        world.set_handler_budget(slow, None);
+        world.set_group_budget("slow", None);
+        assert_eq!(world.handlers()[slow].budget(), None);
     }
 }